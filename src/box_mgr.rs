@@ -1,6 +1,14 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
 
+use crate::config::{ContainerDef, HealthConfig, HealthKind, ServiceDef};
 use crate::error::{DevError, Result};
+use crate::exec;
+use crate::hints;
 
 const BOX_BIN: &str = "a3s-box";
 
@@ -38,6 +46,96 @@ pub struct BoxImage {
     pub pulled: String,
     #[serde(rename = "Reference", default)]
     pub reference: String,
+    /// `os/arch[/variant]` the image was built for, empty when a3s-box does not say.
+    #[serde(rename = "Platform", default)]
+    pub platform: String,
+    /// The platform differs from the host, so containers run under emulation.
+    #[serde(rename = "Emulated", default)]
+    pub emulated: bool,
+}
+
+/// `a3s-box inspect --format json` of one container. Every field is optional so
+/// older and newer a3s-box versions both deserialize.
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BoxInspect {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub state: BoxState,
+    pub restart_count: u32,
+    pub mounts: Vec<BoxMount>,
+    pub config: BoxConfig,
+    pub host_config: BoxHostConfig,
+    pub network_settings: BoxNetworkSettings,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BoxState {
+    /// "running", "exited", "created", …
+    pub status: String,
+    pub running: bool,
+    pub exit_code: i32,
+    pub started_at: String,
+    pub finished_at: String,
+    /// Only present when the image defines a health check.
+    pub health: Option<BoxHealth>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BoxHealth {
+    /// "starting", "healthy" or "unhealthy".
+    pub status: String,
+    pub failing_streak: u32,
+}
+
+/// Resource limits the container runs with; 0 means unlimited.
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BoxHostConfig {
+    /// Memory limit in bytes.
+    pub memory: u64,
+    /// Relative CPU weight (1024 = one share).
+    pub cpu_shares: u64,
+    /// CPU limit in billionths of a CPU.
+    pub nano_cpus: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BoxMount {
+    #[serde(rename = "Type")]
+    pub kind: String,
+    pub source: String,
+    pub destination: String,
+    #[serde(rename = "RW")]
+    pub rw: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BoxConfig {
+    /// `KEY=value` entries.
+    pub env: Vec<String>,
+    pub cmd: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BoxNetworkSettings {
+    #[serde(rename = "IPAddress")]
+    pub ip_address: String,
+    /// Container port (e.g. "5432/tcp") to the host bindings published for it.
+    pub ports: std::collections::BTreeMap<String, Option<Vec<BoxPortBinding>>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BoxPortBinding {
+    pub host_ip: String,
+    pub host_port: String,
 }
 
 #[derive(Debug, Serialize, Default)]
@@ -58,6 +156,84 @@ pub struct BoxVolume {
     pub in_use_by: String,
 }
 
+/// One resource usage sample of a running container.
+#[derive(Debug, Clone, Serialize, Default, PartialEq)]
+pub struct BoxStats {
+    pub id: String,
+    pub name: String,
+    pub cpu_percent: f64,
+    pub memory_usage: u64,
+    pub memory_limit: u64,
+    pub memory_percent: f64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+/// A line of `a3s-box stats --format json`: sizes and percentages come as text,
+/// "1.5MiB / 1.9GiB" and "12.34%".
+#[derive(Deserialize)]
+struct RawStats {
+    #[serde(rename = "ID", default)]
+    id: String,
+    #[serde(rename = "Name", default)]
+    name: String,
+    #[serde(rename = "CPUPerc", default)]
+    cpu_perc: String,
+    #[serde(rename = "MemUsage", default)]
+    mem_usage: String,
+    #[serde(rename = "MemPerc", default)]
+    mem_perc: String,
+    #[serde(rename = "NetIO", default)]
+    net_io: String,
+    #[serde(rename = "BlockIO", default)]
+    block_io: String,
+}
+
+/// What a prune removed.
+#[derive(Debug, Serialize, Default, PartialEq)]
+pub struct PruneReport {
+    /// IDs (and untagged references) of what was deleted, in output order.
+    pub removed: Vec<String>,
+    /// 0 when a3s-box doesn't report it.
+    pub reclaimed_bytes: u64,
+}
+
+/// What a `system prune` removed, by kind.
+#[derive(Debug, Serialize, Default, PartialEq)]
+pub struct BoxPruneReport {
+    pub containers_deleted: u32,
+    pub images_deleted: u32,
+    pub volumes_deleted: u32,
+    /// 0 when a3s-box doesn't report it.
+    pub space_reclaimed_bytes: u64,
+}
+
+/// A container for [`run_container`] to create.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BoxRunSpec {
+    pub image: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `(host, container)` port pairs.
+    #[serde(default)]
+    pub ports: Vec<(u16, u16)>,
+    /// `(host path, container path)` bind mounts.
+    #[serde(default)]
+    pub volumes: Vec<(PathBuf, String)>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub network: Option<String>,
+    /// Overrides the image's command.
+    #[serde(default)]
+    pub cmd: Option<Vec<String>>,
+    /// Return once the container started rather than when it exits.
+    #[serde(default)]
+    pub detach: bool,
+}
+
 #[derive(Debug, Serialize, Default)]
 pub struct BoxInfo {
     pub version: String,
@@ -66,6 +242,51 @@ pub struct BoxInfo {
     pub boxes_total: u32,
     pub boxes_running: u32,
     pub images_cached: String,
+    /// Filled in by callers that also asked [`disk_usage`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_usage: Option<BoxDiskUsage>,
+}
+
+/// Disk space taken by a3s-box artifacts, in bytes (`system df`).
+#[derive(Debug, Serialize, Default, PartialEq)]
+pub struct BoxDiskUsage {
+    pub images_size: u64,
+    pub containers_size: u64,
+    pub volumes_size: u64,
+    pub build_cache_size: u64,
+    /// What a prune could free, over all kinds.
+    pub reclaimable: u64,
+}
+
+impl BoxDiskUsage {
+    /// Count one `system df` row: `size` like "16.43GB", `reclaimable` like
+    /// "11.63GB (70%)".
+    fn add(&mut self, kind: &str, size: &str, reclaimable: &str) {
+        let size = parse_human_size(size).unwrap_or(0);
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "images" => self.images_size += size,
+            "containers" => self.containers_size += size,
+            "local volumes" | "volumes" => self.volumes_size += size,
+            "build cache" => self.build_cache_size += size,
+            _ => return,
+        }
+        self.reclaimable += reclaimable
+            .split_whitespace()
+            .next()
+            .and_then(parse_human_size)
+            .unwrap_or(0);
+    }
+}
+
+/// A line of `a3s-box system df --format json`.
+#[derive(Deserialize)]
+struct RawDiskUsage {
+    #[serde(rename = "Type", default)]
+    kind: String,
+    #[serde(rename = "Size", default)]
+    size: String,
+    #[serde(rename = "Reclaimable", default)]
+    reclaimable: String,
 }
 
 // ── Queries ───────────────────────────────────────────────────────────────────
@@ -104,7 +325,8 @@ pub async fn list_images() -> Result<Vec<BoxImage>> {
         if line.is_empty() {
             continue;
         }
-        if let Ok(img) = serde_json::from_str::<BoxImage>(line) {
+        if let Ok(mut img) = serde_json::from_str::<BoxImage>(line) {
+            img.emulated = is_emulated_on(&img.platform, host_platform());
             result.push(img);
         }
     }
@@ -174,17 +396,347 @@ pub async fn get_info() -> Result<BoxInfo> {
     Ok(info)
 }
 
+/// Disk space used by images, containers, volumes and the build cache.
+pub async fn disk_usage() -> Result<BoxDiskUsage> {
+    let json = run(&["system", "df", "--format", "json"]).await;
+    if let Some(usage) = json.ok().and_then(|out| parse_disk_usage_json(&out)) {
+        return Ok(usage);
+    }
+    // Versions without `--format json` only print the table.
+    Ok(parse_disk_usage_table(&run(&["system", "df"]).await?))
+}
+
+/// One JSON object per line (or a single array of them); `None` if it isn't JSON.
+fn parse_disk_usage_json(out: &str) -> Option<BoxDiskUsage> {
+    let rows: Vec<RawDiskUsage> = match serde_json::from_str(out.trim()) {
+        Ok(rows) => rows,
+        Err(_) => out
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<std::result::Result<_, _>>()
+            .ok()?,
+    };
+    if rows.is_empty() {
+        return None;
+    }
+    let mut usage = BoxDiskUsage::default();
+    for row in rows {
+        usage.add(&row.kind, &row.size, &row.reclaimable);
+    }
+    Some(usage)
+}
+
+/// `TYPE  TOTAL  ACTIVE  SIZE  RECLAIMABLE` table.
+fn parse_disk_usage_table(out: &str) -> BoxDiskUsage {
+    let mut usage = BoxDiskUsage::default();
+    for cols in parse_table(out) {
+        if let [kind, _, _, size, reclaimable, ..] = &cols[..] {
+            usage.add(kind, size, reclaimable);
+        }
+    }
+    usage
+}
+
+pub async fn inspect(id: &str) -> Result<BoxInspect> {
+    let out = run(&["inspect", id, "--format", "json"]).await?;
+    parse_inspect(id, &out)
+}
+
+/// Parse `a3s-box inspect` output, which is a single object or a one-element array.
+fn parse_inspect(id: &str, out: &str) -> Result<BoxInspect> {
+    let value: serde_json::Value = serde_json::from_str(out.trim())
+        .map_err(|e| DevError::Config(format!("unexpected a3s-box inspect output: {e}")))?;
+    let value = match value {
+        serde_json::Value::Array(items) => items.into_iter().next(),
+        serde_json::Value::Null => None,
+        other => Some(other),
+    }
+    .ok_or_else(|| DevError::Config(format!("no such container: {id}")))?;
+    serde_json::from_value(value)
+        .map_err(|e| DevError::Config(format!("unexpected a3s-box inspect output: {e}")))
+}
+
 pub async fn container_logs(id: &str, tail: usize) -> Result<String> {
     let tail_s = tail.to_string();
     let out = run(&["logs", id, "--tail", &tail_s]).await?;
     Ok(out)
 }
 
+/// A single resource usage sample of container `id`.
+pub async fn container_stats(id: &str) -> Result<BoxStats> {
+    let out = run(&["stats", "--no-stream", "--format", "json", id]).await?;
+    out.lines()
+        .find(|l| !l.trim().is_empty())
+        .ok_or_else(|| DevError::Config(format!("a3s-box stats printed nothing for {id}")))
+        .and_then(parse_stats)
+}
+
+/// Send a sample of container `id` to `sender` every time a3s-box reports one, until
+/// the container stops or the receiver is dropped.
+pub async fn container_stats_stream(id: &str, sender: mpsc::Sender<BoxStats>) -> Result<()> {
+    stats_stream_with(BOX_BIN, id, sender).await
+}
+
+async fn stats_stream_with(
+    bin: impl AsRef<std::ffi::OsStr>,
+    id: &str,
+    sender: mpsc::Sender<BoxStats>,
+) -> Result<()> {
+    let mut child = exec::spawn(
+        tokio::process::Command::new(bin)
+            .args(["stats", "--format", "json", id])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true),
+    )
+    .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    if let Some(pipe) = child.stdout.take() {
+        let mut lines = BufReader::new(pipe).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if sender.send(parse_stats(&line)?).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+    let out = child
+        .wait_with_output()
+        .await
+        .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    if !out.status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box stats {id} failed: {}",
+            hints::explain(BOX_BIN, &String::from_utf8_lossy(&out.stderr))
+        )));
+    }
+    Ok(())
+}
+
+/// One line of `a3s-box stats --format json`. Streaming output redraws the screen
+/// between samples, so anything before the JSON object is skipped.
+fn parse_stats(line: &str) -> Result<BoxStats> {
+    let json = line.find('{').map_or(line, |i| &line[i..]);
+    let raw: RawStats = serde_json::from_str(json)
+        .map_err(|e| DevError::Config(format!("unexpected a3s-box stats output: {e}")))?;
+    let pair = |s: &str| {
+        let (a, b) = s.split_once('/').unwrap_or((s, ""));
+        (
+            parse_human_size(a).unwrap_or(0),
+            parse_human_size(b).unwrap_or(0),
+        )
+    };
+    let percent = |s: &str| s.trim().trim_end_matches('%').parse().unwrap_or(0.0);
+    let (memory_usage, memory_limit) = pair(&raw.mem_usage);
+    let (rx_bytes, tx_bytes) = pair(&raw.net_io);
+    let (read_bytes, write_bytes) = pair(&raw.block_io);
+    Ok(BoxStats {
+        id: raw.id,
+        name: raw.name,
+        cpu_percent: percent(&raw.cpu_perc),
+        memory_usage,
+        memory_limit,
+        memory_percent: percent(&raw.mem_perc),
+        rx_bytes,
+        tx_bytes,
+        read_bytes,
+        write_bytes,
+    })
+}
+
 pub async fn stop_container(id: &str) -> Result<()> {
     run(&["stop", id]).await?;
     Ok(())
 }
 
+/// `a3s-box restart` container `id`, giving it `timeout_secs` to stop before it is
+/// killed, or the daemon's default when `None`.
+pub async fn restart_container(id: &str, timeout_secs: Option<u32>) -> Result<()> {
+    restart_with(BOX_BIN, id, timeout_secs).await
+}
+
+async fn restart_with(
+    bin: impl AsRef<std::ffi::OsStr>,
+    id: &str,
+    timeout_secs: Option<u32>,
+) -> Result<()> {
+    let out = exec::output(tokio::process::Command::new(bin).args(restart_args(id, timeout_secs)))
+        .await
+        .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    if !out.status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box restart {id} failed ({}): {}",
+            out.status,
+            hints::explain(BOX_BIN, &String::from_utf8_lossy(&out.stderr))
+        )));
+    }
+    Ok(())
+}
+
+/// `restart [-t <timeout>] <id>`.
+fn restart_args(id: &str, timeout_secs: Option<u32>) -> Vec<String> {
+    let mut args = vec!["restart".to_string()];
+    if let Some(t) = timeout_secs {
+        args.extend(["-t".into(), t.to_string()]);
+    }
+    args.push(id.into());
+    args
+}
+
+/// Create and start a container from `spec` and return it as `ps` lists it. A
+/// detached run is found by the ID it prints; a foreground one, which prints the
+/// container's own output, by its name, so it needs one.
+pub async fn run_container(spec: &BoxRunSpec) -> Result<BoxContainer> {
+    if spec.image.trim().is_empty() {
+        return Err(DevError::Config("a3s-box run: no image given".into()));
+    }
+    if !spec.detach && spec.name.is_none() {
+        return Err(DevError::Config(
+            "a3s-box run: a container that isn't detached needs a name".into(),
+        ));
+    }
+    let out = exec::output(tokio::process::Command::new(BOX_BIN).args(run_container_args(spec)))
+        .await
+        .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    if !out.status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box run {} failed ({}): {}",
+            spec.image,
+            out.status,
+            hints::explain(BOX_BIN, &String::from_utf8_lossy(&out.stderr))
+        )));
+    }
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let wanted = match (spec.detach, &spec.name) {
+        (true, _) => stdout
+            .lines()
+            .map(str::trim)
+            .rfind(|l| !l.is_empty())
+            .unwrap_or_default()
+            .to_string(),
+        (false, name) => name.clone().unwrap_or_default(),
+    };
+    list_containers(true)
+        .await?
+        .into_iter()
+        .find(|c| is_container(c, &wanted))
+        .ok_or_else(|| {
+            DevError::Config(format!(
+                "a3s-box run {} started '{wanted}' but it isn't listed",
+                spec.image
+            ))
+        })
+}
+
+/// Whether `c` is the container `wanted` names: its name, or its ID in the full or
+/// the short form `ps` prints.
+fn is_container(c: &BoxContainer, wanted: &str) -> bool {
+    !wanted.is_empty()
+        && (c.name == wanted
+            || (!c.id.is_empty() && (wanted.starts_with(&c.id) || c.id.starts_with(wanted))))
+}
+
+/// `run [-d] [--name n] [--network n] [-p h:c]... [-v h:c]... [-e K=V]... image
+/// [-- cmd...]`, with env sorted so the arguments are stable.
+fn run_container_args(spec: &BoxRunSpec) -> Vec<String> {
+    let mut args = vec!["run".to_string()];
+    if spec.detach {
+        args.push("-d".into());
+    }
+    if let Some(name) = &spec.name {
+        args.extend(["--name".into(), name.clone()]);
+    }
+    if let Some(network) = &spec.network {
+        args.extend(["--network".into(), network.clone()]);
+    }
+    for (host, container) in &spec.ports {
+        args.extend(["-p".into(), format!("{host}:{container}")]);
+    }
+    for (host, container) in &spec.volumes {
+        args.extend(["-v".into(), format!("{}:{container}", host.display())]);
+    }
+    let mut env: Vec<_> = spec.env.iter().collect();
+    env.sort();
+    for (k, v) in env {
+        args.extend(["-e".into(), format!("{k}={v}")]);
+    }
+    args.push(spec.image.clone());
+    if let Some(cmd) = spec.cmd.as_ref().filter(|c| !c.is_empty()) {
+        args.push("--".into());
+        args.extend(cmd.iter().cloned());
+    }
+    args
+}
+
+/// Run `command` in container `id` and return what it wrote to stdout and stderr.
+pub async fn exec_container(id: &str, command: &[&str]) -> Result<String> {
+    exec_with(BOX_BIN, id, command).await
+}
+
+async fn exec_with(bin: impl AsRef<std::ffi::OsStr>, id: &str, command: &[&str]) -> Result<String> {
+    let out = exec::output(
+        tokio::process::Command::new(bin)
+            .args(exec_args(id, command, false, false))
+            .stdin(std::process::Stdio::null()),
+    )
+    .await
+    .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    if !out.status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box exec {id} -- {} failed ({}): {}",
+            command.join(" "),
+            out.status,
+            hints::explain(BOX_BIN, &stderr)
+        )));
+    }
+    let mut output = String::from_utf8_lossy(&out.stdout).into_owned();
+    output.push_str(&stderr);
+    Ok(output)
+}
+
+/// [`exec_container`] attached to this terminal, with a TTY when stdin is one.
+pub async fn exec_container_interactive(id: &str, command: &[&str]) -> Result<()> {
+    use std::io::IsTerminal;
+    use std::process::Stdio;
+    let tty = std::io::stdin().is_terminal();
+    let status = exec::spawn(
+        tokio::process::Command::new(BOX_BIN)
+            .args(exec_args(id, command, true, tty))
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit()),
+    )
+    .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?
+    .wait()
+    .await
+    .map_err(|e| DevError::Config(format!("a3s-box exec: {e}")))?;
+    if !status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box exec {id} -- {} failed ({status})",
+            command.join(" ")
+        )));
+    }
+    Ok(())
+}
+
+/// `exec [-i] [-t] <id> -- <command>`. The `--` is always there so flags in
+/// `command` reach the command instead of a3s-box.
+fn exec_args<'a>(id: &'a str, command: &[&'a str], stdin: bool, tty: bool) -> Vec<&'a str> {
+    let mut args = vec!["exec"];
+    if stdin {
+        args.push("-i");
+    }
+    if tty {
+        args.push("-t");
+    }
+    args.extend([id, "--"]);
+    args.extend(command);
+    args
+}
+
 pub async fn remove_container(id: &str) -> Result<()> {
     run(&["rm", "-f", id]).await?;
     Ok(())
@@ -205,23 +757,836 @@ pub async fn remove_volume(name: &str) -> Result<()> {
     Ok(())
 }
 
-pub async fn pull_image(reference: &str) -> Result<()> {
-    run(&["pull", reference]).await?;
+/// A network or volume `create_*` made, or found already there.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BoxCreated {
+    /// What `create` printed (a network id, a volume name); the name when it existed.
+    pub id: String,
+    pub name: String,
+    pub existed: bool,
+}
+
+/// Create volume `name` unless it already exists.
+pub async fn create_volume(name: &str, driver: Option<&str>) -> Result<BoxCreated> {
+    if list_volumes().await?.iter().any(|v| v.name == name) {
+        return created(name, Err(already_exists()));
+    }
+    created(name, run(&create_volume_args(name, driver)).await)
+}
+
+/// Create network `name` unless it already exists.
+pub async fn create_network(
+    name: &str,
+    driver: Option<&str>,
+    subnet: Option<&str>,
+) -> Result<BoxCreated> {
+    if list_networks().await?.iter().any(|n| n.name == name) {
+        return created(name, Err(already_exists()));
+    }
+    created(name, run(&create_network_args(name, driver, subnet)).await)
+}
+
+/// A volume or network as `ls` lists it, with whether `create` found it already
+/// there — what `POST /api/box/volumes` and `/api/box/networks` answer with.
+#[derive(Debug, Serialize)]
+pub struct BoxListed<T> {
+    #[serde(flatten)]
+    pub item: T,
+    pub existed: bool,
+}
+
+/// [`create_volume`], answered with the volume as `volume ls` lists it.
+pub async fn create_volume_listed(
+    name: &str,
+    driver: Option<&str>,
+) -> Result<BoxListed<BoxVolume>> {
+    let created = create_volume(name, driver).await?;
+    listed("volume", created, list_volumes().await?, |v| &v.name)
+}
+
+/// [`create_network`], answered with the network as `network ls` lists it.
+pub async fn create_network_listed(
+    name: &str,
+    driver: Option<&str>,
+    subnet: Option<&str>,
+) -> Result<BoxListed<BoxNetwork>> {
+    let created = create_network(name, driver, subnet).await?;
+    listed("network", created, list_networks().await?, |n| &n.name)
+}
+
+/// The entry of `items` named like `created`.
+fn listed<T>(
+    kind: &str,
+    created: BoxCreated,
+    items: Vec<T>,
+    name_of: impl Fn(&T) -> &String,
+) -> Result<BoxListed<T>> {
+    let name = created.name;
+    items
+        .into_iter()
+        .find(|item| *name_of(item) == name)
+        .map(|item| BoxListed {
+            item,
+            existed: created.existed,
+        })
+        .ok_or_else(|| {
+            DevError::Config(format!(
+                "a3s-box {kind} create {name} succeeded but it isn't listed"
+            ))
+        })
+}
+
+fn already_exists() -> DevError {
+    DevError::Config("already exists".into())
+}
+
+/// The outcome of `create name`; an "already exists" failure (the resource was
+/// listed, or appeared between listing and creating) counts as success.
+fn created(name: &str, out: Result<String>) -> Result<BoxCreated> {
+    match out {
+        Ok(out) => Ok(BoxCreated {
+            id: parse_created_id(&out).unwrap_or_else(|| name.to_string()),
+            name: name.to_string(),
+            existed: false,
+        }),
+        Err(e) if e.to_string().contains("already exists") => Ok(BoxCreated {
+            id: name.to_string(),
+            name: name.to_string(),
+            existed: true,
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// The id `create` prints: the last word of its last non-empty line, so warnings
+/// printed before it are skipped.
+fn parse_created_id(out: &str) -> Option<String> {
+    out.lines()
+        .rev()
+        .find_map(|l| l.split_whitespace().last())
+        .map(str::to_string)
+}
+
+/// `volume create [--driver d] name`; an empty driver means the default.
+fn create_volume_args<'a>(name: &'a str, driver: Option<&'a str>) -> Vec<&'a str> {
+    let mut args = vec!["volume", "create"];
+    if let Some(driver) = driver.filter(|d| !d.trim().is_empty()) {
+        args.extend(["--driver", driver]);
+    }
+    args.push(name);
+    args
+}
+
+/// `network create [--driver d] [--subnet s] name`; empty values are left out.
+fn create_network_args<'a>(
+    name: &'a str,
+    driver: Option<&'a str>,
+    subnet: Option<&'a str>,
+) -> Vec<&'a str> {
+    let mut args = vec!["network", "create"];
+    if let Some(driver) = driver.filter(|d| !d.trim().is_empty()) {
+        args.extend(["--driver", driver]);
+    }
+    if let Some(subnet) = subnet.filter(|s| !s.trim().is_empty()) {
+        args.extend(["--subnet", subnet]);
+    }
+    args.push(name);
+    args
+}
+
+/// Remove dangling images, or every image no container uses when `dangling_only`
+/// is false.
+pub async fn prune_images(dangling_only: bool) -> Result<PruneReport> {
+    let mut args = vec!["image", "prune", "--force"];
+    if !dangling_only {
+        args.push("--all");
+    }
+    Ok(parse_prune(&run(&args).await?))
+}
+
+/// Remove all stopped boxes.
+pub async fn prune_containers() -> Result<PruneReport> {
+    Ok(parse_prune(&run(&["container", "prune", "--force"]).await?))
+}
+
+/// Remove stopped boxes, dangling images and unused networks, and unused volumes
+/// too when `prune_volumes` is set.
+pub async fn prune(prune_volumes: bool) -> Result<BoxPruneReport> {
+    let mut args = vec!["system", "prune", "-f"];
+    if prune_volumes {
+        args.push("--volumes");
+    }
+    Ok(parse_system_prune(&run(&args).await?))
+}
+
+/// Parse `a3s-box system prune` output: a `Deleted Containers:`, `Deleted Images:`,
+/// `Deleted Volumes:` (or `Deleted Networks:`) section per kind, then the
+/// `Total reclaimed space` trailer. Images count their `deleted:` lines, not the
+/// `untagged:` ones.
+pub fn parse_system_prune(out: &str) -> BoxPruneReport {
+    let mut report = BoxPruneReport::default();
+    let mut section = String::new();
+    for line in out.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let lower = line.to_ascii_lowercase();
+        if let Some(size) = lower.strip_prefix("total reclaimed space:") {
+            report.space_reclaimed_bytes = parse_human_size(size).unwrap_or(0);
+            continue;
+        }
+        if lower.starts_with("deleted ") && line.ends_with(':') {
+            section = lower;
+            continue;
+        }
+        let removed = match line.split_once(": ") {
+            Some((kind, _)) => kind.eq_ignore_ascii_case("deleted"),
+            None => !line.contains(' '),
+        };
+        if !removed {
+            continue;
+        }
+        if section.contains("container") {
+            report.containers_deleted += 1;
+        } else if section.contains("image") {
+            report.images_deleted += 1;
+        } else if section.contains("volume") {
+            report.volumes_deleted += 1;
+        }
+    }
+    report
+}
+
+/// Parse `a3s-box … prune` output: a `Deleted …:` header, one `deleted: <id>`,
+/// `untagged: <ref>` or bare ID per line, and a `Total reclaimed space: 1.2GB`
+/// trailer that may be missing.
+pub fn parse_prune(out: &str) -> PruneReport {
+    let mut report = PruneReport::default();
+    for line in out.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let lower = line.to_ascii_lowercase();
+        if let Some(size) = lower.strip_prefix("total reclaimed space:") {
+            report.reclaimed_bytes = parse_human_size(size).unwrap_or(0);
+        } else if lower.starts_with("deleted ") && line.ends_with(':') {
+            continue;
+        } else if let Some((kind, item)) = line.split_once(": ") {
+            if matches!(kind.to_ascii_lowercase().as_str(), "deleted" | "untagged") {
+                report.removed.push(item.trim().to_string());
+            }
+        } else if !line.contains(' ') {
+            report.removed.push(line.to_string());
+        }
+    }
+    report
+}
+
+/// "1.2GB", "512kB", "3.5MiB", "0B" → bytes. SI units are decimal, `…iB` binary.
+fn parse_human_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let value: f64 = num.parse().ok()?;
+    let multiplier: f64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        "tib" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((value * multiplier).round() as u64)
+}
+
+/// Pull `reference`, for `platform` if given (otherwise a3s-box picks the host's),
+/// and return the platform of the image that actually landed — empty if it can't be
+/// inspected, since the pull itself succeeded. A mismatch with the host is logged
+/// loudly: everything in that image will run under emulation.
+pub async fn pull_image(reference: &str, platform: Option<&str>) -> Result<String> {
+    let mut args = vec!["pull"];
+    if let Some(p) = platform {
+        args.extend(["--platform", p]);
+    }
+    args.push(reference);
+    run(&args).await?;
+
+    let pulled = match run(&["image", "inspect", reference]).await {
+        Ok(out) => parse_inspect_platform(&out).unwrap_or_default(),
+        Err(e) => {
+            tracing::debug!("pulled {reference} but could not inspect it: {e}");
+            String::new()
+        }
+    };
+    if is_emulated_on(&pulled, host_platform()) {
+        let hint = run(&["manifest", "inspect", reference])
+            .await
+            .ok()
+            .and_then(|out| {
+                let platforms = parse_manifest_platforms(&out);
+                native_platform(&platforms, host_platform()).map(|p| format!(" (pull {p} instead)"))
+            })
+            .unwrap_or_default();
+        tracing::warn!(
+            "{reference} is {pulled} but this host is {} — containers will run emulated and slowly{hint}",
+            host_platform()
+        );
+    }
+    Ok(pulled)
+}
+
+/// Build `context` into `tag` with `a3s-box build`, passing each of `build_args` as a
+/// `--build-arg`. Every line of build output, stdout and stderr alike, is sent to
+/// `output`; the build goes on if the receiver is dropped. On failure the error
+/// carries the build's stderr.
+pub async fn build_image(
+    context: &Path,
+    tag: &str,
+    file: Option<&Path>,
+    build_args: &HashMap<String, String>,
+    output: mpsc::Sender<String>,
+) -> Result<()> {
+    build_with(BOX_BIN, context, tag, file, build_args, output).await
+}
+
+async fn build_with(
+    bin: impl AsRef<std::ffi::OsStr>,
+    context: &Path,
+    tag: &str,
+    file: Option<&Path>,
+    build_args: &HashMap<String, String>,
+    output: mpsc::Sender<String>,
+) -> Result<()> {
+    if !context.is_dir() {
+        return Err(DevError::Config(format!(
+            "build context {} is not a directory",
+            context.display()
+        )));
+    }
+    match file {
+        Some(f) if !f.is_file() => {
+            return Err(DevError::Config(format!(
+                "Containerfile {} not found",
+                f.display()
+            )));
+        }
+        None if !BUILD_FILES.iter().any(|f| context.join(f).is_file()) => {
+            return Err(DevError::Config(format!(
+                "no Dockerfile or Containerfile in build context {}",
+                context.display()
+            )));
+        }
+        _ => {}
+    }
+
+    let (status, stderr) = stream_lines(
+        tokio::process::Command::new(bin).args(build_cmd_args(context, tag, file, build_args)),
+        output,
+    )
+    .await?;
+    if !status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box build failed: {}",
+            hints::explain(BOX_BIN, &stderr)
+        )));
+    }
     Ok(())
 }
 
+/// Run `cmd`, sending every line it prints, stdout and stderr alike, to `output`
+/// (carrying on if the receiver is dropped). Returns its exit status and stderr.
+async fn stream_lines(
+    cmd: &mut tokio::process::Command,
+    output: mpsc::Sender<String>,
+) -> Result<(std::process::ExitStatus, String)> {
+    let mut child = exec::spawn(
+        cmd.stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped()),
+    )
+    .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+
+    let stderr_task = child.stderr.take().map(|pipe| {
+        let output = output.clone();
+        tokio::spawn(async move {
+            let mut stderr = String::new();
+            let mut lines = BufReader::new(pipe).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                stderr.push_str(&line);
+                stderr.push('\n');
+                let _ = output.send(line).await;
+            }
+            stderr
+        })
+    });
+    if let Some(pipe) = child.stdout.take() {
+        let mut lines = BufReader::new(pipe).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let _ = output.send(line).await;
+        }
+    }
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    let stderr = match stderr_task {
+        Some(t) => t.await.unwrap_or_default(),
+        None => String::new(),
+    };
+    Ok((status, stderr))
+}
+
+/// Give local image `source` the additional name `target`.
+pub async fn tag_image(source: &str, target: &str) -> Result<()> {
+    run(&["tag", source, target]).await?;
+    Ok(())
+}
+
+/// Push `reference` to its registry, sending each line of progress to `sender`; the
+/// push goes on if the receiver is dropped.
+pub async fn push_image(reference: &str, sender: mpsc::Sender<String>) -> Result<()> {
+    push_with(BOX_BIN, reference, sender).await
+}
+
+async fn push_with(
+    bin: impl AsRef<std::ffi::OsStr>,
+    reference: &str,
+    sender: mpsc::Sender<String>,
+) -> Result<()> {
+    let (status, stderr) = stream_lines(
+        tokio::process::Command::new(bin).args(["push", reference]),
+        sender,
+    )
+    .await?;
+    if !status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box push {reference} failed: {}",
+            hints::explain(BOX_BIN, &stderr)
+        )));
+    }
+    Ok(())
+}
+
+/// Log in to registry `server`. The password is written to `--password-stdin` so it
+/// never shows up in the process list or shell history.
+pub async fn login_registry(server: &str, username: &str, password: &str) -> Result<()> {
+    login_with(BOX_BIN, server, username, password).await
+}
+
+async fn login_with(
+    bin: impl AsRef<std::ffi::OsStr>,
+    server: &str,
+    username: &str,
+    password: &str,
+) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut child = exec::spawn(
+        tokio::process::Command::new(bin)
+            .args(["login", "--username", username, "--password-stdin", server])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped()),
+    )
+    .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Dropping stdin closes it, so the tool sees the end of the password.
+        stdin
+            .write_all(password.as_bytes())
+            .await
+            .map_err(|e| DevError::Config(format!("a3s-box login: {e}")))?;
+    }
+    let out = child
+        .wait_with_output()
+        .await
+        .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    if !out.status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box login {server} failed: {}",
+            hints::explain(BOX_BIN, &String::from_utf8_lossy(&out.stderr))
+        )));
+    }
+    Ok(())
+}
+
+pub async fn logout_registry(server: &str) -> Result<()> {
+    run(&["logout", server]).await?;
+    Ok(())
+}
+
+/// Write image `reference` to the tarball `dest`, whose directory must exist.
+pub async fn save_image(reference: &str, dest: &Path) -> Result<()> {
+    save_with(BOX_BIN, reference, dest).await
+}
+
+async fn save_with(bin: impl AsRef<std::ffi::OsStr>, reference: &str, dest: &Path) -> Result<()> {
+    let dir = match dest.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    if !dir.is_dir() {
+        return Err(DevError::Config(format!(
+            "cannot save {reference} to {}: no such directory {}",
+            dest.display(),
+            dir.display()
+        )));
+    }
+    let out = exec::output(tokio::process::Command::new(bin).args(save_args(reference, dest)))
+        .await
+        .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    if !out.status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box save {reference} failed: {}",
+            hints::explain(BOX_BIN, &String::from_utf8_lossy(&out.stderr))
+        )));
+    }
+    Ok(())
+}
+
+/// `save -o <dest> <reference>`
+fn save_args<'a>(reference: &'a str, dest: &'a Path) -> Vec<&'a std::ffi::OsStr> {
+    vec![
+        "save".as_ref(),
+        "-o".as_ref(),
+        dest.as_os_str(),
+        reference.as_ref(),
+    ]
+}
+
+/// Load the images in tarball `src`, returning their names (or IDs, for images
+/// saved without one) in the order they were loaded.
+pub async fn load_image(src: &Path) -> Result<Vec<String>> {
+    load_with(BOX_BIN, src).await
+}
+
+async fn load_with(bin: impl AsRef<std::ffi::OsStr>, src: &Path) -> Result<Vec<String>> {
+    std::fs::File::open(src)
+        .map_err(|e| DevError::Config(format!("cannot read {}: {e}", src.display())))?;
+    let out = exec::output(tokio::process::Command::new(bin).args([
+        "load".as_ref(),
+        "-i".as_ref(),
+        src.as_os_str(),
+    ]))
+    .await
+    .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
+    if !out.status.success() {
+        return Err(DevError::Config(format!(
+            "a3s-box load {} failed: {}",
+            src.display(),
+            hints::explain(BOX_BIN, &String::from_utf8_lossy(&out.stderr))
+        )));
+    }
+    Ok(parse_loaded_images(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// Image names from `load` output: one `Loaded image: <name>` (or `Loaded image
+/// ID: <id>`) line per image in the tarball.
+fn parse_loaded_images(out: &str) -> Vec<String> {
+    out.lines()
+        .filter_map(|l| {
+            let l = l.trim();
+            l.strip_prefix("Loaded image ID:")
+                .or_else(|| l.strip_prefix("Loaded image:"))
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Files `a3s-box build` looks for in the context when no `-f` is given.
+const BUILD_FILES: [&str; 2] = ["Dockerfile", "Containerfile"];
+
+/// `build -t <tag> [-f <file>] [--build-arg K=V ...] <context>`, build args sorted by
+/// name so the command line is stable.
+fn build_cmd_args(
+    context: &Path,
+    tag: &str,
+    file: Option<&Path>,
+    build_args: &HashMap<String, String>,
+) -> Vec<std::ffi::OsString> {
+    let mut args: Vec<std::ffi::OsString> = vec!["build".into(), "-t".into(), tag.into()];
+    if let Some(f) = file {
+        args.extend(["-f".into(), f.into()]);
+    }
+    let mut build_args: Vec<_> = build_args.iter().collect();
+    build_args.sort();
+    for (k, v) in build_args {
+        args.extend(["--build-arg".into(), format!("{k}={v}").into()]);
+    }
+    args.push(context.into());
+    args
+}
+
+/// Tag to build `context` as when none is given: its directory name, lowercased
+/// and with anything an image reference cannot hold replaced by `-`.
+pub fn default_build_tag(context: &Path) -> String {
+    let dir = std::fs::canonicalize(context).unwrap_or_else(|_| context.to_path_buf());
+    let name: String = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let name = name.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    if name.is_empty() {
+        "image".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+// ── Container services ────────────────────────────────────────────────────────
+
+/// Name of the a3s-box container backing the `container "<service>"` block.
+pub fn container_name(service: &str) -> String {
+    format!("a3s-{service}")
+}
+
+/// `a3s-box run` in the foreground, so the supervisor owns the container's lifetime
+/// and its output lands in the service log.
+pub fn run_args(service: &str, c: &ContainerDef) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        BOX_BIN.into(),
+        "run".into(),
+        "--rm".into(),
+        "--name".into(),
+        container_name(service),
+    ];
+    if let Some(p) = &c.platform {
+        args.extend(["--platform".into(), p.clone()]);
+    }
+    for p in &c.ports {
+        args.extend(["-p".into(), p.clone()]);
+    }
+    let mut env: Vec<_> = c.env.iter().collect();
+    env.sort();
+    for (k, v) in env {
+        args.extend(["-e".into(), format!("{k}={v}")]);
+    }
+    for v in &c.volumes {
+        args.extend(["-v".into(), v.clone()]);
+    }
+    args.push(c.image.clone());
+    if !c.command.is_empty() {
+        args.push("--".into());
+        args.extend(c.command.iter().cloned());
+    }
+    args
+}
+
+/// Pull the image only when it is not cached yet, so `a3s up` stays fast offline.
+pub fn pull_if_missing_cmd(c: &ContainerDef) -> String {
+    let image = shell_quote(&c.image);
+    let platform = c
+        .platform
+        .as_deref()
+        .map(|p| format!("--platform {} ", shell_quote(p)))
+        .unwrap_or_default();
+    let script = format!(
+        "{BOX_BIN} image inspect {image} >/dev/null 2>&1 || {BOX_BIN} pull {platform}{image}"
+    );
+    format!("sh -c {}", shell_quote(&script))
+}
+
+/// Host port a `ports` entry publishes on: "5432:5432", "127.0.0.1:5432:5432",
+/// "8080" or any of those with a "/tcp" suffix.
+pub fn host_port(spec: &str) -> std::result::Result<u16, String> {
+    let spec = spec.split('/').next().unwrap_or(spec);
+    let parts: Vec<&str> = spec.split(':').collect();
+    let host = match parts.as_slice() {
+        [p] | [p, _] | [_, p, _] => *p,
+        _ => return Err(format!("invalid port mapping '{spec}'")),
+    };
+    if let Some(container) = parts.last() {
+        container
+            .parse::<u16>()
+            .map_err(|_| format!("invalid container port in '{spec}'"))?;
+    }
+    match host.parse::<u16>() {
+        Ok(0) | Err(_) => Err(format!("invalid host port in '{spec}'")),
+        Ok(port) => Ok(port),
+    }
+}
+
+/// The service that runs container `name`: `a3s-box run` as its command, a
+/// pull-if-missing `pre_start`, a `post_stop` that removes the container even if
+/// it did not shut down cleanly, and a TCP health check unless one is given.
+pub fn container_service(name: &str, c: &ContainerDef) -> std::result::Result<ServiceDef, String> {
+    if c.image.trim().is_empty() {
+        return Err("image must not be empty".into());
+    }
+    let ports = c
+        .ports
+        .iter()
+        .map(|p| host_port(p))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let port = ports.first().copied().unwrap_or(0);
+    let health = c.health.clone().or_else(|| {
+        (port != 0).then(|| HealthConfig {
+            kind: HealthKind::Tcp,
+            path: None,
+            interval: std::time::Duration::from_secs(2),
+            timeout: std::time::Duration::from_secs(1),
+            retries: 3,
+            start_period: Some(std::time::Duration::from_secs(60)),
+            startup_retries: None,
+        })
+    });
+    let cmd = run_args(name, c)
+        .iter()
+        .map(|a| shell_quote(a))
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(ServiceDef {
+        cmd,
+        dir: None,
+        port,
+        subdomain: c.subdomain.clone(),
+        env: Default::default(),
+        env_file: None,
+        env_reload: false,
+        shell: None,
+        log_file: None,
+        log_rotate_mb: 0,
+        log_max_size: None,
+        log_keep: 1,
+        pre_start: Some(pull_if_missing_cmd(c)),
+        post_stop: Some(format!("{BOX_BIN} rm -f {}", container_name(name))),
+        hook_timeout: std::time::Duration::from_secs(600),
+        depends_on: c.depends_on.clone(),
+        depends_on_timeout: None,
+        watch: None,
+        health,
+        restart: Default::default(),
+        stop_timeout: c.stop_timeout,
+        disabled: c.disabled,
+        autostart: true,
+        labels: c.labels.clone(),
+        limits: None,
+        standby: false,
+        k8s: None,
+        lint_ignore: vec![],
+        env_secret: Default::default(),
+    })
+}
+
+/// Quote `s` for `split_cmd` when it has anything but plain word characters.
+pub(crate) fn shell_quote(s: &str) -> String {
+    let plain = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c));
+    if plain {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+// ── Platforms ─────────────────────────────────────────────────────────────────
+
+/// `linux/<arch>` of this machine, in OCI naming.
+pub fn host_platform() -> &'static str {
+    match std::env::consts::ARCH {
+        "aarch64" => "linux/arm64",
+        "x86_64" => "linux/amd64",
+        "arm" => "linux/arm",
+        "riscv64" => "linux/riscv64",
+        "s390x" => "linux/s390x",
+        "powerpc64" => "linux/ppc64le",
+        _ => "linux/unknown",
+    }
+}
+
+/// Architecture part of an `os/arch[/variant]` platform, with the kernel spellings
+/// (`x86_64`, `aarch64`) folded into OCI ones.
+fn arch_of(platform: &str) -> &str {
+    let arch = platform.split('/').nth(1).unwrap_or(platform);
+    match arch {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => other,
+    }
+}
+
+/// Whether an image for `platform` needs emulation on `host`. An unknown
+/// (empty) platform is given the benefit of the doubt.
+pub fn is_emulated_on(platform: &str, host: &str) -> bool {
+    !platform.is_empty() && arch_of(platform) != arch_of(host)
+}
+
+/// Platform recorded in `a3s-box image inspect` output — a single object or a
+/// one-element array with `Os`/`Architecture`/`Variant`.
+pub fn parse_inspect_platform(json: &str) -> Option<String> {
+    let v: serde_json::Value = serde_json::from_str(json.trim()).ok()?;
+    let obj = match &v {
+        serde_json::Value::Array(items) => items.first()?,
+        other => other,
+    };
+    let field = |k: &str| obj.get(k).and_then(|v| v.as_str()).unwrap_or_default();
+    let (os, arch) = (field("Os"), field("Architecture"));
+    if arch.is_empty() {
+        return None;
+    }
+    let os = if os.is_empty() { "linux" } else { os };
+    Some(match field("Variant") {
+        "" => format!("{os}/{arch}"),
+        variant => format!("{os}/{arch}/{variant}"),
+    })
+}
+
+/// Platforms offered by a multi-arch manifest list (OCI index), in listing order.
+/// Attestation entries (`unknown/unknown`) are skipped.
+pub fn parse_manifest_platforms(json: &str) -> Vec<String> {
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(json.trim()) else {
+        return vec![];
+    };
+    let Some(manifests) = v.get("manifests").and_then(|m| m.as_array()) else {
+        return vec![];
+    };
+    manifests
+        .iter()
+        .filter_map(|m| {
+            let p = m.get("platform")?;
+            let os = p.get("os")?.as_str()?;
+            let arch = p.get("architecture")?.as_str()?;
+            if os == "unknown" {
+                return None;
+            }
+            Some(match p.get("variant").and_then(|v| v.as_str()) {
+                Some(variant) => format!("{os}/{arch}/{variant}"),
+                None => format!("{os}/{arch}"),
+            })
+        })
+        .collect()
+}
+
+/// The entry of a manifest list that runs natively on `host`, if there is one.
+pub fn native_platform<'a>(platforms: &'a [String], host: &str) -> Option<&'a str> {
+    platforms
+        .iter()
+        .find(|p| !is_emulated_on(p, host))
+        .map(String::as_str)
+}
+
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
 async fn run(args: &[&str]) -> Result<String> {
-    let out = tokio::process::Command::new(BOX_BIN)
-        .args(args)
-        .output()
+    let out = exec::output(tokio::process::Command::new(BOX_BIN).args(args))
         .await
         .map_err(|e| DevError::Config(format!("failed to run a3s-box: {e}")))?;
     let stdout = String::from_utf8_lossy(&out.stdout).into_owned();
     let stderr = String::from_utf8_lossy(&out.stderr).into_owned();
     if !out.status.success() && stdout.trim().is_empty() {
-        return Err(DevError::Config(format!("a3s-box error: {stderr}")));
+        return Err(DevError::Config(format!(
+            "a3s-box error: {}",
+            hints::explain(BOX_BIN, &stderr)
+        )));
     }
     Ok(stdout)
 }
@@ -242,3 +1607,837 @@ fn parse_table(text: &str) -> Vec<Vec<String>> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTI_ARCH_INDEX: &str = r#"{
+      "schemaVersion": 2,
+      "mediaType": "application/vnd.oci.image.index.v1+json",
+      "manifests": [
+        {"digest": "sha256:aa", "platform": {"architecture": "amd64", "os": "linux"}},
+        {"digest": "sha256:bb", "platform": {"architecture": "arm64", "os": "linux", "variant": "v8"}},
+        {"digest": "sha256:cc", "platform": {"architecture": "unknown", "os": "unknown"}}
+      ]
+    }"#;
+
+    #[test]
+    fn test_parse_image_prune_output() {
+        let out = "Deleted Images:
+untagged: api:dev
+deleted: sha256:3f1c2a
+deleted: sha256:9b7e44
+
+Total reclaimed space: 1.25GB
+";
+        assert_eq!(
+            parse_prune(out),
+            PruneReport {
+                removed: vec![
+                    "api:dev".into(),
+                    "sha256:3f1c2a".into(),
+                    "sha256:9b7e44".into()
+                ],
+                reclaimed_bytes: 1_250_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_container_prune_output() {
+        let out = "Deleted Containers:\n4c1d9e0a\nb82f7713\n\nTotal reclaimed space: 12.5MiB\n";
+        let report = parse_prune(out);
+        assert_eq!(report.removed, vec!["4c1d9e0a", "b82f7713"]);
+        assert_eq!(report.reclaimed_bytes, 13_107_200);
+    }
+
+    #[test]
+    fn test_parse_prune_without_reclaimed_space() {
+        let report = parse_prune("Deleted Containers:\n4c1d9e0a\n");
+        assert_eq!(report.removed, vec!["4c1d9e0a"]);
+        assert_eq!(report.reclaimed_bytes, 0);
+        assert_eq!(
+            parse_prune("Total reclaimed space: ???"),
+            PruneReport::default()
+        );
+        assert_eq!(parse_prune(""), PruneReport::default());
+        assert_eq!(parse_human_size("0B"), Some(0));
+        assert_eq!(parse_human_size("512kB"), Some(512_000));
+    }
+
+    #[test]
+    fn test_parse_system_prune_counts_each_kind() {
+        let out = "Deleted Containers:\n4c1d9e0a\n9f2b7c11\n\nDeleted Networks:\na3s-old\n\n\
+                   Deleted Volumes:\npgdata-old\n\nDeleted Images:\nuntagged: alpine:3.18\n\
+                   deleted: sha256:aa11\ndeleted: sha256:bb22\n\n\
+                   Total reclaimed space: 1.5 GB\n";
+        assert_eq!(
+            parse_system_prune(out),
+            BoxPruneReport {
+                containers_deleted: 2,
+                images_deleted: 2,
+                volumes_deleted: 1,
+                space_reclaimed_bytes: 1_500_000_000,
+            }
+        );
+        assert_eq!(
+            parse_system_prune("Total reclaimed space: 0B\n"),
+            BoxPruneReport::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_platforms_skips_attestations() {
+        assert_eq!(
+            parse_manifest_platforms(MULTI_ARCH_INDEX),
+            vec!["linux/amd64", "linux/arm64/v8"]
+        );
+        assert!(parse_manifest_platforms(r#"{"schemaVersion": 2}"#).is_empty());
+        assert!(parse_manifest_platforms("not json").is_empty());
+    }
+
+    #[test]
+    fn test_native_platform_picks_host_arch() {
+        let platforms = parse_manifest_platforms(MULTI_ARCH_INDEX);
+        assert_eq!(
+            native_platform(&platforms, "linux/arm64"),
+            Some("linux/arm64/v8")
+        );
+        assert_eq!(
+            native_platform(&platforms, "linux/amd64"),
+            Some("linux/amd64")
+        );
+        assert_eq!(native_platform(&platforms, "linux/s390x"), None);
+    }
+
+    #[test]
+    fn test_parse_inspect_platform() {
+        let single = r#"{"Id": "sha256:aa", "Os": "linux", "Architecture": "amd64"}"#;
+        assert_eq!(
+            parse_inspect_platform(single).as_deref(),
+            Some("linux/amd64")
+        );
+        let array = r#"[{"Architecture": "arm64", "Variant": "v8"}]"#;
+        assert_eq!(
+            parse_inspect_platform(array).as_deref(),
+            Some("linux/arm64/v8")
+        );
+        assert_eq!(parse_inspect_platform(r#"{"Id": "x"}"#), None);
+    }
+
+    #[test]
+    fn test_is_emulated_on() {
+        assert!(is_emulated_on("linux/amd64", "linux/arm64"));
+        assert!(!is_emulated_on("linux/arm64/v8", "linux/arm64"));
+        assert!(!is_emulated_on("linux/x86_64", "linux/amd64"));
+        assert!(!is_emulated_on("", "linux/arm64"));
+    }
+
+    #[test]
+    fn test_default_build_tag_from_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = dir.path().join("My App");
+        std::fs::create_dir(&ctx).unwrap();
+        assert_eq!(default_build_tag(&ctx), "my-app");
+        assert_eq!(default_build_tag(Path::new("/")), "image");
+    }
+
+    #[tokio::test]
+    async fn test_build_rejects_missing_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, _rx) = mpsc::channel(8);
+        let no_args = HashMap::new();
+        let err = build_image(&dir.path().join("nope"), "x", None, &no_args, tx.clone())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("is not a directory"), "{err}");
+
+        let file = dir.path().join("Containerfile");
+        let err = build_image(dir.path(), "x", Some(&file), &no_args, tx.clone())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Containerfile"), "{err}");
+
+        let err = build_image(dir.path(), "x", None, &no_args, tx)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no Dockerfile or Containerfile"), "{err}");
+    }
+
+    #[test]
+    fn test_build_cmd_args() {
+        let args = |file: Option<&Path>, build_args: &[(&str, &str)]| {
+            let build_args: HashMap<String, String> = build_args
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            build_cmd_args(Path::new("./app"), "app:dev", file, &build_args)
+                .into_iter()
+                .map(|a| a.into_string().unwrap())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        assert_eq!(args(None, &[]), "build -t app:dev ./app");
+        assert_eq!(
+            args(
+                Some(Path::new("app/Dockerfile.dev")),
+                &[("VERSION", "1.2"), ("BASE", "alpine:3.20")]
+            ),
+            "build -t app:dev -f app/Dockerfile.dev --build-arg BASE=alpine:3.20 --build-arg VERSION=1.2 ./app"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_streams_stdout_and_stderr() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Dockerfile"), "FROM scratch\n").unwrap();
+        let bin = fake_box(
+            dir.path(),
+            "echo \"step 1: $*\"\necho 'warning: no cache' >&2\necho 'step 2'",
+        );
+        let (tx, mut rx) = mpsc::channel(8);
+        build_with(&bin, dir.path(), "x", None, &HashMap::new(), tx)
+            .await
+            .unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = rx.recv().await {
+            lines.push(line);
+        }
+        lines.sort();
+        assert_eq!(
+            lines,
+            [
+                format!("step 1: build -t x {}", dir.path().display()),
+                "step 2".to_string(),
+                "warning: no cache".to_string(),
+            ]
+        );
+
+        let bin = fake_box(
+            dir.path(),
+            "echo 'error: FROM scratch: not found' >&2\nexit 1",
+        );
+        let (tx, _rx) = mpsc::channel(8);
+        let err = build_with(&bin, dir.path(), "x", None, &HashMap::new(), tx)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("FROM scratch: not found"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_push_streams_progress_and_names_the_image_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = fake_box(dir.path(), "echo \"$*\"\necho 'layer 1/1 pushed' >&2");
+        let (tx, mut rx) = mpsc::channel(8);
+        push_with(&bin, "ghcr.io/a3s/api:dev", tx).await.unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = rx.recv().await {
+            lines.push(line);
+        }
+        lines.sort();
+        assert_eq!(lines, ["layer 1/1 pushed", "push ghcr.io/a3s/api:dev"]);
+
+        let bin = fake_box(dir.path(), "echo 'denied: requested access' >&2\nexit 1");
+        let (tx, _rx) = mpsc::channel(8);
+        let err = push_with(&bin, "ghcr.io/a3s/api:dev", tx)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("a3s-box push ghcr.io/a3s/api:dev failed"),
+            "{err}"
+        );
+        assert!(err.contains("denied: requested access"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_login_pipes_password_through_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let args = dir.path().join("args");
+        let stdin = dir.path().join("stdin");
+        let bin = fake_box(
+            dir.path(),
+            &format!(
+                "echo \"$*\" > '{}'\ncat > '{}'",
+                args.display(),
+                stdin.display()
+            ),
+        );
+        login_with(&bin, "ghcr.io", "dev", "s3cret pass")
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&args).unwrap().trim(),
+            "login --username dev --password-stdin ghcr.io"
+        );
+        assert_eq!(std::fs::read_to_string(&stdin).unwrap(), "s3cret pass");
+
+        let bin = fake_box(
+            dir.path(),
+            "cat >/dev/null\necho 'unauthorized' >&2\nexit 1",
+        );
+        let err = login_with(&bin, "ghcr.io", "dev", "wrong")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("a3s-box login ghcr.io failed"), "{err}");
+        assert!(!err.contains("wrong"), "{err}");
+    }
+
+    fn postgres() -> ContainerDef {
+        ContainerDef {
+            image: "postgres:16".into(),
+            platform: None,
+            ports: vec!["5432:5432".into()],
+            env: [("POSTGRES_PASSWORD".to_string(), "dev pass".to_string())].into(),
+            volumes: vec!["pgdata:/var/lib/postgresql/data".into()],
+            command: vec![],
+            depends_on: vec![],
+            health: None,
+            subdomain: None,
+            labels: vec![],
+            disabled: false,
+            stop_timeout: std::time::Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_run_args() {
+        let mut c = postgres();
+        c.platform = Some("linux/amd64".into());
+        c.command = vec!["postgres".into(), "-c".into(), "fsync=off".into()];
+        assert_eq!(
+            run_args("db", &c).join(" "),
+            "a3s-box run --rm --name a3s-db --platform linux/amd64 -p 5432:5432 \
+             -e POSTGRES_PASSWORD=dev pass -v pgdata:/var/lib/postgresql/data postgres:16 \
+             -- postgres -c fsync=off"
+        );
+    }
+
+    #[test]
+    fn test_run_container_args() {
+        let spec = BoxRunSpec {
+            image: "redis:7".into(),
+            name: Some("cache".into()),
+            ports: vec![(6380, 6379), (8001, 8001)],
+            volumes: vec![(PathBuf::from("/tmp/redis data"), "/data".into())],
+            env: [
+                ("REDIS_ARGS".to_string(), "--save 60 1".to_string()),
+                ("A".to_string(), "1".to_string()),
+            ]
+            .into(),
+            network: Some("a3s".into()),
+            cmd: Some(vec![
+                "redis-server".into(),
+                "--appendonly".into(),
+                "yes".into(),
+            ]),
+            detach: true,
+        };
+        assert_eq!(
+            run_container_args(&spec),
+            [
+                "run",
+                "-d",
+                "--name",
+                "cache",
+                "--network",
+                "a3s",
+                "-p",
+                "6380:6379",
+                "-p",
+                "8001:8001",
+                "-v",
+                "/tmp/redis data:/data",
+                "-e",
+                "A=1",
+                "-e",
+                "REDIS_ARGS=--save 60 1",
+                "redis:7",
+                "--",
+                "redis-server",
+                "--appendonly",
+                "yes",
+            ]
+        );
+        let bare = BoxRunSpec {
+            image: "alpine".into(),
+            cmd: Some(vec![]),
+            ..Default::default()
+        };
+        assert_eq!(run_container_args(&bare), ["run", "alpine"]);
+    }
+
+    #[test]
+    fn test_run_spec_from_json_and_container_match() {
+        let spec: BoxRunSpec = serde_json::from_str(
+            r#"{"image": "nginx", "ports": [[8080, 80]], "volumes": [["./site", "/usr/share/nginx/html"]], "detach": true}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            run_container_args(&spec).join(" "),
+            "run -d -p 8080:80 -v ./site:/usr/share/nginx/html nginx"
+        );
+
+        let c = BoxContainer {
+            id: "4c1d9e0a7b2f".into(),
+            name: "web".into(),
+            ..Default::default()
+        };
+        assert!(is_container(&c, "4c1d9e0a7b2f0123456789abcdef"));
+        assert!(is_container(&c, "web"));
+        assert!(!is_container(&c, "4c1d0000"));
+        assert!(!is_container(&c, ""));
+    }
+
+    #[test]
+    fn test_container_service_round_trips_through_split_cmd() {
+        let c = postgres();
+        let svc = container_service("db", &c).unwrap();
+        assert_eq!(crate::supervisor::split_cmd(&svc.cmd), run_args("db", &c));
+        assert_eq!(svc.port, 5432);
+        assert_eq!(svc.post_stop.as_deref(), Some("a3s-box rm -f a3s-db"));
+
+        let hook = crate::supervisor::split_cmd(svc.pre_start.as_deref().unwrap());
+        assert_eq!(
+            hook,
+            [
+                "sh",
+                "-c",
+                "a3s-box image inspect postgres:16 >/dev/null 2>&1 || a3s-box pull postgres:16"
+            ]
+        );
+        let health = svc.health.unwrap();
+        assert_eq!(health.kind, HealthKind::Tcp);
+        assert_eq!(health.retries, 3);
+        assert_eq!(
+            health.start_period,
+            Some(std::time::Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn test_container_without_ports_has_no_health_check() {
+        let mut c = postgres();
+        c.ports.clear();
+        let svc = container_service("worker", &c).unwrap();
+        assert_eq!(svc.port, 0);
+        assert!(svc.health.is_none());
+    }
+
+    #[test]
+    fn test_host_port() {
+        assert_eq!(host_port("5432:5432"), Ok(5432));
+        assert_eq!(host_port("127.0.0.1:6380:6379"), Ok(6380));
+        assert_eq!(host_port("8080"), Ok(8080));
+        assert_eq!(host_port("53:53/udp"), Ok(53));
+        assert!(host_port("db:5432").is_err());
+        assert!(host_port("5432:pg").is_err());
+        assert!(host_port("0:5432").is_err());
+        assert!(container_service(
+            "db",
+            &ContainerDef {
+                image: " ".into(),
+                ..postgres()
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_inspect() {
+        let out = r#"[{
+          "Id": "4f2a9c",
+          "Name": "a3s-db",
+          "Image": "postgres:16",
+          "State": {"Status": "exited", "Running": false, "ExitCode": 137,
+                    "StartedAt": "2024-05-01T10:00:00Z", "FinishedAt": "2024-05-01T10:05:00Z",
+                    "Health": {"Status": "unhealthy", "FailingStreak": 3}},
+          "RestartCount": 2,
+          "Mounts": [{"Type": "volume", "Source": "pgdata", "Destination": "/var/lib/postgresql/data", "RW": true}],
+          "Config": {"Env": ["POSTGRES_PASSWORD=dev"], "Cmd": ["postgres"]},
+          "HostConfig": {"Memory": 536870912, "CpuShares": 512, "NanoCpus": 1500000000},
+          "NetworkSettings": {"IPAddress": "10.88.0.4",
+                              "Ports": {"5432/tcp": [{"HostIp": "0.0.0.0", "HostPort": "5432"}], "9187/tcp": null}},
+          "Platform": "linux"
+        }]"#;
+        let c = parse_inspect("a3s-db", out).unwrap();
+        assert_eq!(c.name, "a3s-db");
+        assert_eq!(c.state.exit_code, 137);
+        assert!(!c.state.running);
+        assert_eq!(c.restart_count, 2);
+        assert_eq!(c.mounts[0].destination, "/var/lib/postgresql/data");
+        assert_eq!(c.config.env, ["POSTGRES_PASSWORD=dev"]);
+        assert_eq!(c.host_config.memory, 512 * 1024 * 1024);
+        assert_eq!(c.host_config.cpu_shares, 512);
+        assert_eq!(c.host_config.nano_cpus, 1_500_000_000);
+        let health = c.state.health.as_ref().unwrap();
+        assert_eq!(
+            (health.status.as_str(), health.failing_streak),
+            ("unhealthy", 3)
+        );
+        assert_eq!(c.network_settings.ip_address, "10.88.0.4");
+        assert_eq!(
+            c.network_settings.ports["5432/tcp"].as_ref().unwrap()[0].host_port,
+            "5432"
+        );
+        assert_eq!(c.network_settings.ports["9187/tcp"], None);
+    }
+
+    #[test]
+    fn test_parse_inspect_tolerates_missing_keys() {
+        let c = parse_inspect("x", r#"{"Id": "x", "State": {"Running": true}}"#).unwrap();
+        assert!(c.state.running);
+        assert!(c.mounts.is_empty());
+        assert_eq!(c.state.status, "");
+        assert_eq!(c.state.health, None);
+        assert_eq!(c.host_config, BoxHostConfig::default());
+    }
+
+    const STATS_LINE: &str = r#"{"BlockIO":"4.1MB / 12.3kB","CPUPerc":"12.34%","Container":"a3s-db","ID":"4c1d9e0a","MemPerc":"1.25%","MemUsage":"25.5MiB / 2GiB","Name":"a3s-db","NetIO":"1.2kB / 648B","PIDs":"7"}"#;
+
+    #[test]
+    fn test_parse_stats() {
+        let s = parse_stats(STATS_LINE).unwrap();
+        assert_eq!(s.id, "4c1d9e0a");
+        assert_eq!(s.name, "a3s-db");
+        assert_eq!(s.cpu_percent, 12.34);
+        assert_eq!(s.memory_usage, 26_738_688);
+        assert_eq!(s.memory_limit, 2 * 1024 * 1024 * 1024);
+        assert_eq!(s.memory_percent, 1.25);
+        assert_eq!((s.rx_bytes, s.tx_bytes), (1_200, 648));
+        assert_eq!((s.read_bytes, s.write_bytes), (4_100_000, 12_300));
+    }
+
+    #[test]
+    fn test_parse_stats_of_stopped_container_and_screen_redraw() {
+        let stopped = r#"{"ID":"4c1d","Name":"a3s-db","CPUPerc":"--","MemUsage":"-- / --","MemPerc":"--","NetIO":"--","BlockIO":"--"}"#;
+        let s = parse_stats(stopped).unwrap();
+        assert_eq!(s.cpu_percent, 0.0);
+        assert_eq!((s.memory_usage, s.memory_limit), (0, 0));
+
+        let redrawn = format!("\u{1b}[2J\u{1b}[H{STATS_LINE}");
+        assert_eq!(parse_stats(&redrawn).unwrap().name, "a3s-db");
+        assert!(parse_stats("not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stats_stream_sends_every_sample() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = dir.path().join("stats.jsonl");
+        std::fs::write(
+            &fixture,
+            format!("{STATS_LINE}\n\n{}\n", STATS_LINE.replace("12.34%", "50%")),
+        )
+        .unwrap();
+        let bin = fake_box(
+            dir.path(),
+            &format!(
+                "[ \"$*\" = \"stats --format json a3s-db\" ] || exit 2; cat {}",
+                fixture.display()
+            ),
+        );
+        let (tx, mut rx) = mpsc::channel(4);
+        stats_stream_with(&bin, "a3s-db", tx).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().cpu_percent, 12.34);
+        assert_eq!(rx.recv().await.unwrap().cpu_percent, 50.0);
+        assert!(rx.recv().await.is_none());
+
+        let bin = fake_box(dir.path(), "echo 'no such container' >&2; exit 1");
+        let (tx, _rx) = mpsc::channel(1);
+        let err = stats_stream_with(&bin, "nope", tx).await.unwrap_err();
+        assert!(err.to_string().contains("no such container"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_inspect_unknown_id() {
+        let err = parse_inspect("nope", "[]").unwrap_err().to_string();
+        assert!(err.contains("no such container: nope"), "{err}");
+        assert!(parse_inspect("nope", "garbage").is_err());
+    }
+
+    #[test]
+    fn test_images_line_reads_platform() {
+        let line = r#"{"Repository":"postgres","Tag":"16","Platform":"linux/amd64"}"#;
+        let img: BoxImage = serde_json::from_str(line).unwrap();
+        assert_eq!(img.platform, "linux/amd64");
+        assert!(!img.emulated);
+        let old: BoxImage = serde_json::from_str(r#"{"Repository":"redis"}"#).unwrap();
+        assert_eq!(old.platform, "");
+    }
+
+    #[test]
+    fn test_exec_args_always_separate_the_command() {
+        assert_eq!(
+            exec_args("a3s-db", &["psql", "-U", "app"], false, false),
+            ["exec", "a3s-db", "--", "psql", "-U", "app"]
+        );
+        assert_eq!(
+            exec_args("a3s-db", &["sh"], true, true),
+            ["exec", "-i", "-t", "a3s-db", "--", "sh"]
+        );
+        assert_eq!(
+            exec_args("a3s-db", &[], false, false),
+            ["exec", "a3s-db", "--"]
+        );
+    }
+
+    #[test]
+    fn test_listed_answers_with_the_ls_entry_and_existed() {
+        let created = |existed| BoxCreated {
+            id: "v1".into(),
+            name: "pgdata".into(),
+            existed,
+        };
+        let volumes = || {
+            vec![
+                BoxVolume {
+                    name: "other".into(),
+                    ..Default::default()
+                },
+                BoxVolume {
+                    driver: "local".into(),
+                    name: "pgdata".into(),
+                    mount_point: "/var/lib/a3s/pgdata".into(),
+                    in_use_by: String::new(),
+                },
+            ]
+        };
+        let made = listed("volume", created(true), volumes(), |v| &v.name).unwrap();
+        assert_eq!(
+            serde_json::to_value(&made).unwrap(),
+            serde_json::json!({
+                "driver": "local",
+                "name": "pgdata",
+                "mount_point": "/var/lib/a3s/pgdata",
+                "in_use_by": "",
+                "existed": true
+            })
+        );
+        let err = listed("volume", created(false), vec![], |v: &BoxVolume| &v.name)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("isn't listed"), "{err}");
+    }
+
+    #[test]
+    fn test_created_parses_id_and_tolerates_existing() {
+        let id = "3f2c9a1b7e4d";
+        let made = created("a3s", Ok(format!("{id}\n"))).unwrap();
+        assert_eq!((made.id.as_str(), made.existed), (id, false));
+        let warned = created("a3s", Ok(format!("WARNING: subnet overlaps\n\n{id}\n"))).unwrap();
+        assert_eq!(warned.id, id);
+        assert_eq!(created("pgdata", Ok(String::new())).unwrap().id, "pgdata");
+
+        let existing = created(
+            "pgdata",
+            Err(DevError::Config(
+                "a3s-box error: volume pgdata already exists".into(),
+            )),
+        )
+        .unwrap();
+        assert_eq!(
+            existing,
+            BoxCreated {
+                id: "pgdata".into(),
+                name: "pgdata".into(),
+                existed: true
+            }
+        );
+        assert!(created(
+            "pgdata",
+            Err(DevError::Config("a3s-box error: denied".into()))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_create_args_omit_empty_driver_and_subnet() {
+        assert_eq!(
+            create_volume_args("pgdata", None),
+            ["volume", "create", "pgdata"]
+        );
+        assert_eq!(
+            create_volume_args("pgdata", Some("")),
+            ["volume", "create", "pgdata"]
+        );
+        assert_eq!(
+            create_volume_args("pgdata", Some("local")),
+            ["volume", "create", "--driver", "local", "pgdata"]
+        );
+        assert_eq!(
+            create_network_args("a3s", Some(" "), Some("")),
+            ["network", "create", "a3s"]
+        );
+        assert_eq!(
+            create_network_args("a3s", Some("bridge"), Some("10.88.0.0/16")),
+            [
+                "network",
+                "create",
+                "--driver",
+                "bridge",
+                "--subnet",
+                "10.88.0.0/16",
+                "a3s"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_restart_args_timeout_only_when_given() {
+        assert_eq!(restart_args("a3s-db", None), ["restart", "a3s-db"]);
+        assert_eq!(
+            restart_args("a3s-db", Some(30)),
+            ["restart", "-t", "30", "a3s-db"]
+        );
+        assert_eq!(restart_args("a3s-db", Some(0)).last().unwrap(), "a3s-db");
+    }
+
+    #[test]
+    fn test_save_args_put_the_output_before_the_reference() {
+        assert_eq!(
+            save_args("nginx:1.27", Path::new("/tmp/cache/nginx.tar")),
+            ["save", "-o", "/tmp/cache/nginx.tar", "nginx:1.27"]
+        );
+    }
+
+    #[test]
+    fn test_parse_loaded_images_lists_every_image_in_the_tarball() {
+        let out = "Loading layer  72.8MB/72.8MB\n\
+                   Loaded image: nginx:1.27\n\
+                   Loaded image: redis:7-alpine\n\
+                   Loaded image ID: sha256:9c7a54a9a43c\n";
+        assert_eq!(
+            parse_loaded_images(out),
+            ["nginx:1.27", "redis:7-alpine", "sha256:9c7a54a9a43c"]
+        );
+        assert!(parse_loaded_images("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_disk_usage_json() {
+        let out = r#"{"Active":"2","Reclaimable":"11.63GB (70%)","Size":"16.43GB","TotalCount":"5","Type":"Images"}
+{"Active":"0","Reclaimable":"134.5kB (100%)","Size":"134.5kB","TotalCount":"2","Type":"Containers"}
+{"Active":"1","Reclaimable":"800MB (66%)","Size":"1.2GB","TotalCount":"3","Type":"Local Volumes"}
+{"Active":"0","Reclaimable":"0B","Size":"0B","TotalCount":"0","Type":"Build Cache"}
+"#;
+        let expected = BoxDiskUsage {
+            images_size: 16_430_000_000,
+            containers_size: 134_500,
+            volumes_size: 1_200_000_000,
+            build_cache_size: 0,
+            reclaimable: 11_630_000_000 + 134_500 + 800_000_000,
+        };
+        assert_eq!(parse_disk_usage_json(out), Some(expected));
+        let array = format!("[{}]", out.trim().lines().collect::<Vec<_>>().join(","));
+        assert_eq!(
+            parse_disk_usage_json(&array).unwrap().images_size,
+            16_430_000_000
+        );
+        assert_eq!(parse_disk_usage_json("TYPE  TOTAL"), None);
+        assert_eq!(parse_disk_usage_json(""), None);
+    }
+
+    #[test]
+    fn test_parse_disk_usage_table() {
+        let out = "\
+TYPE            TOTAL     ACTIVE    SIZE      RECLAIMABLE
+Images          5         2         16.43GB   11.63GB (70%)
+Containers      2         0         134.5kB   134.5kB (100%)
+Local Volumes   3         1         1.2GB     800MB (66%)
+Build Cache     4         0         2.5GB     2.5GB
+";
+        assert_eq!(
+            parse_disk_usage_table(out),
+            BoxDiskUsage {
+                images_size: 16_430_000_000,
+                containers_size: 134_500,
+                volumes_size: 1_200_000_000,
+                build_cache_size: 2_500_000_000,
+                reclaimable: 11_630_000_000 + 134_500 + 800_000_000 + 2_500_000_000,
+            }
+        );
+    }
+
+    /// Write an executable fake a3s-box that runs `body` as a shell script.
+    fn fake_box(dir: &Path, body: &str) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("a3s-box");
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_exec_passes_arguments_in_order_and_captures_both_streams() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = fake_box(dir.path(), r#"echo "$*"; echo warn >&2"#);
+        let out = exec_with(&bin, "a3s-db", &["ls", "-la", "--", "/tmp"])
+            .await
+            .unwrap();
+        assert_eq!(out, "exec a3s-db -- ls -la -- /tmp\nwarn\n");
+    }
+
+    #[tokio::test]
+    async fn test_exec_non_zero_exit_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = fake_box(dir.path(), "echo 'no such container: nope' >&2; exit 1");
+        let err = exec_with(&bin, "nope", &["true"]).await.unwrap_err();
+        assert!(matches!(err, DevError::Config(_)));
+        let err = err.to_string();
+        assert!(err.contains("a3s-box exec nope -- true failed"), "{err}");
+        assert!(err.contains("no such container: nope"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_restart_failure_names_the_container() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("args");
+        let bin = fake_box(dir.path(), &format!("echo \"$*\" > {}", log.display()));
+        restart_with(&bin, "a3s-db", Some(5)).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "restart -t 5 a3s-db\n"
+        );
+
+        let bin = fake_box(dir.path(), "echo 'no such container: nope' >&2; exit 1");
+        let err = restart_with(&bin, "nope", None).await.unwrap_err();
+        assert!(matches!(err, DevError::Config(_)));
+        let err = err.to_string();
+        assert!(err.contains("a3s-box restart nope failed"), "{err}");
+        assert!(err.contains("no such container: nope"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_pass_paths_and_reject_inaccessible_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("args");
+        let bin = fake_box(
+            dir.path(),
+            &format!(
+                "echo \"$*\" >> {}; echo 'Loaded image: web:dev'; echo 'Loaded image: db:dev'",
+                log.display()
+            ),
+        );
+        let tar = dir.path().join("images.tar");
+        save_with(&bin, "web:dev", &tar).await.unwrap();
+        std::fs::write(&tar, "").unwrap();
+        let loaded = load_with(&bin, &tar).await.unwrap();
+        assert_eq!(loaded, ["web:dev", "db:dev"]);
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            format!("save -o {0} web:dev\nload -i {0}\n", tar.display())
+        );
+
+        let missing = dir.path().join("nope/images.tar");
+        let err = save_with(&bin, "web:dev", &missing).await.unwrap_err();
+        assert!(matches!(err, DevError::Config(_)), "{err}");
+        let err = load_with(&bin, &missing).await.unwrap_err();
+        assert!(matches!(err, DevError::Config(_)), "{err}");
+        assert!(err.to_string().contains("cannot read"), "{err}");
+    }
+}
@@ -3,23 +3,46 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 use indexmap::IndexMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::error::{DevError, Result};
+use crate::parse_error;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct DevConfig {
     #[serde(default)]
     pub dev: GlobalSettings,
     #[serde(default)]
     pub service: IndexMap<String, ServiceDef>,
+    /// Containers run through a3s-box. Each one is also added to `service` at load
+    /// time, so it starts, stops and reports status like any other service.
+    #[serde(default)]
+    pub container: IndexMap<String, ContainerDef>,
     /// Named environment overrides. `a3s up --env <name>` merges the matching
     /// block's per-service env on top of the base service env.
     #[serde(default)]
     pub env_override: IndexMap<String, EnvOverride>,
+    /// Named variants of the base config. `A3S_ENV=<name>` or `a3s up --env <name>`
+    /// deep-merges the matching block's services over the base ones before validation.
+    #[serde(default)]
+    pub environment: IndexMap<String, Environment>,
+    /// Other HCL files (glob patterns relative to this file) whose `service`,
+    /// `env_override` and `environment` blocks are merged in. Their `dev {}` blocks
+    /// are ignored.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Files each service comes from: the file that declares it, then the local
+    /// overlay if that changed it. Filled in at load time for `a3s config show`.
+    #[serde(skip)]
+    #[cfg_attr(feature = "schema", schemars(skip))]
+    pub sources: IndexMap<String, Vec<PathBuf>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct GlobalSettings {
     #[serde(default = "default_proxy_port")]
     pub proxy_port: u16,
@@ -31,6 +54,10 @@ pub struct GlobalSettings {
     /// Kubernetes context (only used when runtime = "k8s")
     #[serde(default)]
     pub k8s_context: Option<String>,
+    /// Kubeconfig file for kubectl, relative to A3sfile.hcl (default: kubectl's own,
+    /// `$KUBECONFIG` or `~/.kube/config`)
+    #[serde(default)]
+    pub k8s_kubeconfig: Option<PathBuf>,
     /// Kubernetes namespace (only used when runtime = "k8s")
     #[serde(default = "default_k8s_namespace")]
     pub k8s_namespace: String,
@@ -38,9 +65,71 @@ pub struct GlobalSettings {
     /// When set, images are tagged and pushed before deploying.
     #[serde(default)]
     pub registry: Option<String>,
-    /// Enable HTTPS for the reverse proxy (generates self-signed certificate)
+    /// Enable HTTPS for the reverse proxy. Same as `tls { enabled = true }`.
     #[serde(default)]
     pub https: bool,
+    /// HTTPS for the reverse proxy, with certificates from a local CA.
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Reverse-tunnel settings for `a3s share`.
+    #[serde(default)]
+    pub share: Option<ShareConfig>,
+    /// Startup progress style. Unset = fancy on a terminal, plain otherwise.
+    #[serde(default)]
+    pub progress: Option<ProgressStyle>,
+    /// Health-check uptime percentage over the last 24h below which a service is
+    /// badged "flaky" in `a3s status` and `a3s health report`.
+    #[serde(default = "default_flaky_threshold")]
+    pub flaky_threshold: f64,
+    /// What a foreground `a3s up` does with its services when its terminal closes.
+    #[serde(default)]
+    pub orphan_policy: OrphanPolicy,
+    /// How long a service waits for each of its `depends_on` to come up before it is
+    /// marked blocked. 0 (default) = wait as long as it takes.
+    #[serde(default, with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
+    pub depends_on_timeout: Duration,
+    /// How long shutdown stops services gracefully, dependents before their
+    /// dependencies; whatever is still running then is killed.
+    #[serde(default = "default_shutdown_timeout", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
+    pub shutdown_timeout: Duration,
+    /// IP address the web UI listens on. Beyond loopback every API call needs the token
+    /// from `~/.a3s/token`.
+    #[serde(default = "default_ui_bind")]
+    pub ui_bind: String,
+    /// Directory, relative to A3sfile.hcl, where every service's log lines are kept
+    /// so `a3s logs` history outlives the supervisor (default: ".a3s/logs").
+    #[serde(default = "default_log_dir")]
+    pub log_dir: PathBuf,
+    /// Disk space the files in `log_dir` may take in total, e.g. "100mb"; the oldest
+    /// rotated files are deleted first (default: 100mb).
+    #[serde(
+        default = "default_log_retention",
+        deserialize_with = "size_serde::deserialize",
+        serialize_with = "size_serde::serialize"
+    )]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "size_serde::schema_required")
+    )]
+    pub log_retention: u64,
+    /// Also serve IPC on this TCP port, on 127.0.0.1 only, for clients that can't
+    /// reach the Unix socket (e.g. from a container). Unlike the socket it is open to
+    /// every local user, so clients must present the token in `.a3s/ipc-token`.
+    #[serde(default)]
+    pub ipc_tcp_port: Option<u16>,
+    /// How long the daemon works on one IPC request before answering "request timed
+    /// out" and closing the connection, and how long it waits for a silent client
+    /// (default: 30s). Start, stop, restart and reload requests are not limited.
+    #[serde(default = "default_ipc_request_timeout", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
+    pub ipc_request_timeout: Duration,
+    /// The same for streams meant to stay open, `logs --follow` and `events`.
+    /// 0 (default) = until the client disconnects.
+    #[serde(default, with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
+    pub ipc_stream_timeout: Duration,
 }
 
 impl Default for GlobalSettings {
@@ -50,9 +139,23 @@ impl Default for GlobalSettings {
             log_level: default_log_level(),
             runtime: default_runtime(),
             k8s_context: None,
+            k8s_kubeconfig: None,
             k8s_namespace: default_k8s_namespace(),
             registry: None,
             https: false,
+            tls: TlsConfig::default(),
+            share: None,
+            progress: None,
+            flaky_threshold: default_flaky_threshold(),
+            orphan_policy: OrphanPolicy::default(),
+            depends_on_timeout: Duration::ZERO,
+            shutdown_timeout: default_shutdown_timeout(),
+            ui_bind: default_ui_bind(),
+            log_dir: default_log_dir(),
+            log_retention: default_log_retention(),
+            ipc_tcp_port: None,
+            ipc_request_timeout: default_ipc_request_timeout(),
+            ipc_stream_timeout: Duration::ZERO,
         }
     }
 }
@@ -69,23 +172,132 @@ fn default_runtime() -> String {
 fn default_k8s_namespace() -> String {
     "default".into()
 }
+fn default_flaky_threshold() -> f64 {
+    95.0
+}
+fn default_ui_bind() -> String {
+    "127.0.0.1".into()
+}
+fn default_log_dir() -> PathBuf {
+    PathBuf::from(".a3s/logs")
+}
+fn default_log_retention() -> u64 {
+    100 << 20
+}
+
+impl GlobalSettings {
+    /// Whether the proxy serves HTTPS (`https = true` or `tls { enabled = true }`).
+    pub fn tls_enabled(&self) -> bool {
+        self.https || self.tls.enabled
+    }
+
+    /// `--kubeconfig` and `--context` for kubectl, for those that are set.
+    pub fn kubectl_args(&self) -> Vec<std::ffi::OsString> {
+        let mut args = Vec::new();
+        if let Some(kubeconfig) = &self.k8s_kubeconfig {
+            args.extend(["--kubeconfig".into(), kubeconfig.into()]);
+        }
+        if let Some(ctx) = &self.k8s_context {
+            args.extend(["--context".into(), ctx.into()]);
+        }
+        args
+    }
+}
+
+/// `dev { tls { ... } }` — HTTPS for the subdomain proxy.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// CA certificate (PEM) that signs the per-subdomain certificates. Relative to
+    /// the A3sfile.hcl directory; default `~/.a3s/ca/ca.pem`. Generated if missing.
+    #[serde(default)]
+    pub cert: Option<PathBuf>,
+    /// Private key of `cert`; default `~/.a3s/ca/ca-key.pem`.
+    #[serde(default)]
+    pub key: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    /// CA certificate and key paths, resolved against `base_dir`.
+    pub fn ca_paths(&self, base_dir: &std::path::Path) -> (PathBuf, PathBuf) {
+        let ca_dir = crate::cert::default_ca_dir();
+        (
+            base_dir.join(self.cert.clone().unwrap_or_else(|| ca_dir.join("ca.pem"))),
+            base_dir.join(
+                self.key
+                    .clone()
+                    .unwrap_or_else(|| ca_dir.join("ca-key.pem")),
+            ),
+        )
+    }
+}
+
+/// `dev { share { ... } }` — where `a3s share` opens its reverse tunnels.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ShareConfig {
+    /// Tunnel backend. Only "ssh" (an `ssh -R` reverse tunnel) is supported for now.
+    #[serde(default = "default_share_provider")]
+    pub provider: String,
+    /// Jump host that exposes the shared port (e.g. "tunnel.example.com").
+    pub host: String,
+    /// SSH user on the jump host (default: whatever ssh config resolves).
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Inclusive range of remote ports handed out to shares (default: [20000, 20999]).
+    #[serde(default = "default_share_port_range")]
+    pub port_range: [u16; 2],
+}
 
-#[derive(Debug, Deserialize, Clone)]
+fn default_share_provider() -> String {
+    "ssh".into()
+}
+fn default_share_port_range() -> [u16; 2] {
+    [20000, 20999]
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct EnvOverride {
-    /// Per-service env overrides. Only `env` is supported; other fields are ignored.
+    /// Per-service env overrides. Only `env` is supported; use an `environment` block
+    /// to override other fields.
     #[serde(default)]
     pub service: IndexMap<String, ServiceEnvOverride>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct Environment {
+    /// Partial `service` blocks, merged field by field like A3sfile.local.hcl: maps
+    /// such as `env` are unioned, lists and plain values are replaced. A service only
+    /// the environment declares is added.
+    #[serde(default)]
+    pub service: IndexMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct ServiceEnvOverride {
     #[serde(default)]
     pub env: HashMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct ServiceDef {
     pub cmd: String,
+    /// Shell that runs `cmd` and the hooks, as `<shell> -c <cmd>` (default: `sh`).
+    /// `"none"` runs `cmd` directly, split into words like a shell would.
+    #[serde(default)]
+    pub shell: Option<String>,
     #[serde(default)]
     pub dir: Option<PathBuf>,
     /// Port to bind. 0 = auto-assign a free port (portless-style).
@@ -99,12 +311,32 @@ pub struct ServiceDef {
     /// Variables in `env` take precedence over env_file.
     #[serde(default)]
     pub env_file: Option<PathBuf>,
+    /// Restart the service when `env_file` changes, re-reading it (`env` still wins).
+    #[serde(default)]
+    pub env_reload: bool,
+    /// Variables whose values are read at start: a shell command whose trimmed
+    /// stdout is the value, or `keychain:<item>`. Never written to disk, masked in
+    /// logs, and taking precedence over `env`.
+    #[serde(default)]
+    pub env_secret: HashMap<String, String>,
     /// Write stdout/stderr to this file (append mode). Relative to the A3sfile.hcl directory.
     #[serde(default)]
     pub log_file: Option<PathBuf>,
     /// Rotate `log_file` when it reaches this size (in MB). 0 = no rotation (default).
     #[serde(default)]
     pub log_rotate_mb: u64,
+    /// Rotate `log_file` when it reaches this size, e.g. "10mb". Overrides `log_rotate_mb`.
+    #[serde(
+        default,
+        deserialize_with = "size_serde::deserialize_opt",
+        serialize_with = "size_serde::serialize_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "size_serde::schema"))]
+    pub log_max_size: Option<u64>,
+    /// Rotated copies of `log_file` to keep (`api.log.1` is the newest).
+    #[serde(default = "default_log_keep")]
+    pub log_keep: u32,
     /// Shell command to run (in the service's working directory) before starting the service.
     /// A non-zero exit code aborts startup.
     #[serde(default)]
@@ -112,8 +344,25 @@ pub struct ServiceDef {
     /// Shell command to run (in the service's working directory) after the service stops.
     #[serde(default)]
     pub post_stop: Option<String>,
+    /// How long `pre_start` or `post_stop` may run before it is killed (default: 5m).
+    /// A timed-out `pre_start` aborts startup like a non-zero exit.
+    #[serde(default = "default_hook_timeout", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
+    pub hook_timeout: Duration,
     #[serde(default)]
     pub depends_on: Vec<String>,
+    /// Overrides `dev.depends_on_timeout` for this service's dependencies.
+    #[serde(
+        default,
+        deserialize_with = "duration_serde::deserialize_opt",
+        serialize_with = "duration_serde::serialize_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "duration_serde::schema_opt")
+    )]
+    pub depends_on_timeout: Option<Duration>,
     #[serde(default)]
     pub watch: Option<WatchConfig>,
     #[serde(default)]
@@ -122,20 +371,96 @@ pub struct ServiceDef {
     pub restart: RestartConfig,
     /// How long to wait for SIGTERM before sending SIGKILL (default: 5s).
     #[serde(default = "default_stop_timeout", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
     pub stop_timeout: Duration,
     /// If true, this service is skipped entirely (not started, not validated for deps).
     #[serde(default)]
     pub disabled: bool,
+    /// If false, `a3s up` registers the service but leaves it stopped until
+    /// `a3s start`, a restart, or a starting dependent brings it up.
+    #[serde(default = "default_true")]
+    pub autostart: bool,
     /// Labels for grouping and filtering services (e.g., ["backend", "critical"]).
     #[serde(default)]
     pub labels: Vec<String>,
+    /// CPU and memory limits (cgroups v2 on Linux, best-effort rlimit elsewhere).
+    #[serde(default)]
+    pub limits: Option<LimitsConfig>,
+    /// Keep a pre-warmed spare instance so restarts swap instantly.
+    /// Requires `port = 0` and a `health` check.
+    #[serde(default)]
+    pub standby: bool,
     /// Kubernetes-specific configuration (only used when runtime = "k8s").
     #[serde(default)]
     pub k8s: Option<K8sConfig>,
+    /// Lint codes not to report for this service (see `a3s config lint`).
+    #[serde(default)]
+    pub lint_ignore: Vec<String>,
+}
+
+/// A container managed through a3s-box: pulled if missing on `a3s up`, run in the
+/// foreground while the service is up, and removed when it stops.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ContainerDef {
+    pub image: String,
+    /// Image platform to pull and run (e.g. "linux/amd64"). Default: the host's.
+    #[serde(default)]
+    pub platform: Option<String>,
+    /// Published ports: "5432:5432", "127.0.0.1:6380:6379" or "8080". The first host
+    /// port is the service port, used for the proxy and the default health check.
+    #[serde(default)]
+    pub ports: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// "name:/path" for a named volume, "./dir:/path[:ro]" for a bind mount relative
+    /// to the file that declares the container.
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    /// Command to run instead of the image's default.
+    #[serde(default)]
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Default: a TCP check on the first host port, allowing a minute for the image
+    /// to boot.
+    #[serde(default)]
+    pub health: Option<HealthConfig>,
+    #[serde(default)]
+    pub subdomain: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default = "default_stop_timeout", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
+    pub stop_timeout: Duration,
+}
+
+/// Resource limits for a locally supervised service.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Maximum memory, e.g. "512mb" or "2gb". Parsed to bytes at load time.
+    #[serde(
+        default,
+        deserialize_with = "size_serde::deserialize_opt",
+        serialize_with = "size_serde::serialize_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "size_serde::schema"))]
+    pub memory: Option<u64>,
+    /// Maximum CPU in cores, e.g. 1.5 = one and a half cores.
+    #[serde(default)]
+    pub cpu: Option<f64>,
 }
 
 /// Kubernetes-specific configuration for a service.
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct K8sConfig {
     /// Container image (e.g., "node:20-alpine", "myapp:latest").
     pub image: String,
@@ -175,7 +500,9 @@ pub struct K8sConfig {
 }
 
 /// Kubernetes volume configuration.
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct K8sVolume {
     /// Volume name (must be unique within the service).
     pub name: String,
@@ -198,7 +525,9 @@ pub struct K8sVolume {
     pub read_only: bool,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct K8sResources {
     #[serde(default)]
     pub cpu_request: Option<String>,
@@ -215,20 +544,28 @@ fn default_replicas() -> u32 {
 }
 
 /// Crash-recovery restart policy for a service.
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct RestartConfig {
     /// Maximum number of restarts before giving up (default: 10).
     #[serde(default = "default_max_restarts")]
     pub max_restarts: u32,
     /// Initial backoff delay between restarts (default: 1s).
     #[serde(default = "default_backoff", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
     pub backoff: Duration,
     /// Maximum backoff delay (default: 30s).
     #[serde(default = "default_max_backoff", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
     pub max_backoff: Duration,
     /// What to do when the service fails: "restart" (default) or "stop".
     #[serde(default)]
     pub on_failure: OnFailure,
+    /// Consecutive exits within a second of starting before the service is marked
+    /// `crash-looped` and no longer restarted (default: 5).
+    #[serde(default = "default_crash_loop_after")]
+    pub crash_loop_after: u32,
 }
 
 impl Default for RestartConfig {
@@ -238,11 +575,13 @@ impl Default for RestartConfig {
             backoff: default_backoff(),
             max_backoff: default_max_backoff(),
             on_failure: OnFailure::default(),
+            crash_loop_after: default_crash_loop_after(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum OnFailure {
     /// Restart the service with exponential backoff (default).
@@ -252,34 +591,107 @@ pub enum OnFailure {
     Stop,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+/// `dev { orphan_policy = "..." }` — services of a foreground `a3s up` whose terminal
+/// is closed (SIGHUP).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum OrphanPolicy {
+    /// Stop them, as Ctrl-C would (default).
+    #[default]
+    Stop,
+    /// Keep them and the supervisor running; SIGHUP reloads the config as it does
+    /// for `a3s up -d`.
+    Keep,
+}
+
+/// How startup progress is written to the terminal (`dev { progress = "..." }`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressStyle {
+    /// Coloured symbols (default on a terminal).
+    Fancy,
+    /// Append-only timestamped lines without colour — for screen readers and logs.
+    Plain,
+    /// Only failures and the final summary.
+    Quiet,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct WatchConfig {
     pub paths: Vec<PathBuf>,
     #[serde(default)]
     pub ignore: Vec<String>,
     #[serde(default = "default_true")]
     pub restart: bool,
+    /// Allow watch paths that resolve outside the project directory.
+    #[serde(default)]
+    pub allow_outside_project: bool,
+    /// Refuse to start if the paths cover more than this many directories.
+    #[serde(default = "default_max_watch_dirs")]
+    pub max_dirs: usize,
+    /// Also skip what the repository's `.gitignore` files and `.git/info/exclude`
+    /// ignore (default: true). `ignore` entries take precedence.
+    #[serde(default = "default_true")]
+    pub use_gitignore: bool,
+    /// Quiet period after the last change before restarting; each change resets it.
+    #[serde(default = "default_debounce", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
+    pub debounce: Duration,
 }
 
 fn default_true() -> bool {
     true
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+fn default_max_watch_dirs() -> usize {
+    10_000
+}
+
+fn default_debounce() -> Duration {
+    Duration::from_millis(300)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
 pub struct HealthConfig {
     #[serde(rename = "type")]
     pub kind: HealthKind,
     #[serde(default)]
     pub path: Option<String>,
     #[serde(default = "default_interval", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
     pub interval: Duration,
     #[serde(default = "default_timeout", with = "duration_serde")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "duration_serde::schema"))]
     pub timeout: Duration,
+    /// Failing checks in a row that mark a running service unhealthy and restart it.
     #[serde(default = "default_retries")]
     pub retries: u32,
+    /// Grace period after start: failing checks don't count until it has passed,
+    /// and the first passing check ends startup early.
+    #[serde(
+        default,
+        deserialize_with = "duration_serde::deserialize_opt",
+        serialize_with = "duration_serde::serialize_opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "duration_serde::schema_opt")
+    )]
+    pub start_period: Option<Duration>,
+    /// Failing checks after `start_period` before startup gives up (default: `retries`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_retries: Option<u32>,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum HealthKind {
     Http,
@@ -304,38 +716,214 @@ fn default_backoff() -> Duration {
 fn default_max_backoff() -> Duration {
     Duration::from_secs(30)
 }
+fn default_crash_loop_after() -> u32 {
+    5
+}
+fn default_hook_timeout() -> Duration {
+    Duration::from_secs(300)
+}
 fn default_stop_timeout() -> Duration {
     Duration::from_secs(5)
 }
+fn default_shutdown_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+fn default_ipc_request_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+fn default_log_keep() -> u32 {
+    1
+}
+
+pub(crate) use duration_serde::parse_duration;
+
+/// Subdomains a service may not claim: `a3s` and `ui` are kept free for the tool's
+/// own endpoints, `localhost` would route `localhost.localhost`.
+const RESERVED_SUBDOMAINS: &[&str] = &["a3s", "ui", "localhost"];
+
+/// Check that `sub` can be used as `<sub>.localhost`: dot-separated DNS labels of
+/// lowercase letters, digits and inner hyphens, at most 63 characters each.
+/// Uppercase is rejected because browsers lowercase the Host header before routing.
+fn check_subdomain(sub: &str) -> std::result::Result<(), String> {
+    if RESERVED_SUBDOMAINS.contains(&sub) {
+        return Err(format!("subdomain '{sub}' is reserved by a3s"));
+    }
+    for label in sub.split('.') {
+        let valid = !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid {
+            return Err(format!(
+                "subdomain '{sub}' is not a valid hostname (use lowercase letters, digits and '-', e.g. \"api\" or \"api-v2\")"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A string schema constrained by `pattern` — for values parsed from strings at load time.
+#[cfg(feature = "schema")]
+fn string_schema(pattern: &str, description: &str) -> schemars::Schema {
+    schemars::json_schema!({
+        "type": "string",
+        "pattern": pattern,
+        "description": description,
+    })
+}
+
+/// JSON Schema for A3sfile.hcl, for editor completion and validation (`a3s schema`).
+#[cfg(feature = "schema")]
+pub fn json_schema() -> schemars::Schema {
+    schemars::schema_for!(DevConfig)
+}
 
 mod duration_serde {
     use std::time::Duration;
 
-    use serde::{Deserialize, Deserializer};
+    use serde::{Deserialize, Deserializer, Serializer};
 
     pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
         let s = String::deserialize(d)?;
         parse_duration(&s).map_err(serde::de::Error::custom)
     }
 
-    fn parse_duration(s: &str) -> Result<Duration, String> {
-        if let Some(v) = s.strip_suffix("ms") {
+    pub fn deserialize_opt<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Duration>, D::Error> {
+        deserialize(d).map(Some)
+    }
+
+    pub fn serialize_opt<S: Serializer>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+        match d {
+            Some(d) => serialize(d, s),
+            None => s.serialize_none(),
+        }
+    }
+
+    /// JSON Schema for an optional duration.
+    #[cfg(feature = "schema")]
+    pub fn schema_opt(gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let mut schema = schema(gen);
+        schema.insert("type".into(), serde_json::json!(["string", "null"]));
+        schema
+    }
+
+    /// Write a duration back in the same format, e.g. "5s" or "500ms".
+    pub fn serialize<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        if d.subsec_millis() == 0 {
+            s.serialize_str(&format!("{}s", d.as_secs()))
+        } else {
+            s.serialize_str(&format!("{}ms", d.as_millis()))
+        }
+    }
+
+    /// JSON Schema for the accepted duration strings.
+    #[cfg(feature = "schema")]
+    pub fn schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        super::string_schema(
+            r"^\s*\d+\s*(ms|s|m|h)\s*$",
+            "Duration, e.g. \"500ms\", \"2s\", \"10m\" or \"1h\"",
+        )
+    }
+
+    /// Parse "500ms", "2s", "10m" or "1h" into a `Duration`.
+    pub fn parse_duration(s: &str) -> Result<Duration, String> {
+        let s = s.trim();
+        let (v, unit_secs) = if let Some(v) = s.strip_suffix("ms") {
             return v
                 .trim()
                 .parse::<u64>()
                 .map(Duration::from_millis)
                 .map_err(|e| e.to_string());
+        } else if let Some(v) = s.strip_suffix('s') {
+            (v, 1)
+        } else if let Some(v) = s.strip_suffix('m') {
+            (v, 60)
+        } else if let Some(v) = s.strip_suffix('h') {
+            (v, 3600)
+        } else {
+            return Err(format!(
+                "unknown duration format: '{s}' (use '500ms', '2s', '10m' or '1h')"
+            ));
+        };
+        v.trim()
+            .parse::<u64>()
+            .map(|n| Duration::from_secs(n * unit_secs))
+            .map_err(|e| e.to_string())
+    }
+}
+
+mod size_serde {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn deserialize_opt<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+        deserialize(d).map(Some)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<u64, D::Error> {
+        let s = String::deserialize(d)?;
+        parse_size(&s).map_err(serde::de::Error::custom)
+    }
+
+    pub fn serialize<S: Serializer>(v: &u64, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&v.to_string())
+    }
+
+    /// Write a size back as a plain byte count string, which `parse_size` accepts.
+    pub fn serialize_opt<S: Serializer>(v: &Option<u64>, s: S) -> Result<S::Ok, S::Error> {
+        match v {
+            Some(bytes) => s.serialize_str(&bytes.to_string()),
+            None => s.serialize_none(),
         }
-        if let Some(v) = s.strip_suffix('s') {
-            return v
-                .trim()
-                .parse::<u64>()
-                .map(Duration::from_secs)
-                .map_err(|e| e.to_string());
+    }
+
+    /// JSON Schema for the accepted size strings (the field is optional).
+    #[cfg(feature = "schema")]
+    pub fn schema(gen: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let mut schema = schema_required(gen);
+        schema.insert("type".into(), serde_json::json!(["string", "null"]));
+        schema
+    }
+
+    /// JSON Schema for the accepted size strings.
+    #[cfg(feature = "schema")]
+    pub fn schema_required(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        super::string_schema(
+            r"^\s*\d+(\.\d+)?\s*([kKmMgGtT]?[bB]?)\s*$",
+            "Size, e.g. \"512mb\" or \"2gb\" (binary units)",
+        )
+    }
+
+    /// Parse a size such as "512", "64kb", "512mb", "2gb" or "1.5g" into bytes.
+    /// Units are binary (1kb = 1024 bytes) and case-insensitive.
+    pub fn parse_size(s: &str) -> Result<u64, String> {
+        let lower = s.trim().to_ascii_lowercase();
+        let digits_end = lower
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(lower.len());
+        let (num, unit) = lower.split_at(digits_end);
+        let multiplier: u64 = match unit.trim() {
+            "" | "b" => 1,
+            "k" | "kb" => 1 << 10,
+            "m" | "mb" => 1 << 20,
+            "g" | "gb" => 1 << 30,
+            "t" | "tb" => 1 << 40,
+            other => {
+                return Err(format!(
+                    "unknown size unit '{other}' in '{s}' (use b, kb, mb, gb or tb)"
+                ))
+            }
+        };
+        let value: f64 = num
+            .parse()
+            .map_err(|_| format!("invalid size '{s}' (e.g. '512mb', '2gb')"))?;
+        let bytes = value * multiplier as f64;
+        if bytes < 1.0 {
+            return Err(format!("size '{s}' must be at least 1 byte"));
         }
-        Err(format!(
-            "unknown duration format: '{s}' (use '2s' or '500ms')"
-        ))
+        Ok(bytes as u64)
     }
 }
 
@@ -424,6 +1012,240 @@ pub fn expand_env_func(src: &str) -> String {
     out
 }
 
+/// Check that `path` can be appended to, or created along with its missing parent
+/// directories, without touching anything.
+fn check_log_file(path: &std::path::Path) -> std::result::Result<(), String> {
+    if path.is_dir() {
+        return Err(format!("{} is a directory", path.display()));
+    }
+    if path.exists() {
+        return std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map(drop)
+            .map_err(|e| format!("{} is not writable: {e}", path.display()));
+    }
+    let mut dir = path.parent().unwrap_or(std::path::Path::new("."));
+    while !dir.as_os_str().is_empty() && !dir.exists() {
+        dir = dir.parent().unwrap_or(std::path::Path::new(""));
+    }
+    if dir.as_os_str().is_empty() {
+        dir = std::path::Path::new(".");
+    }
+    if !dir.is_dir() {
+        return Err(format!(
+            "{}: {} is not a directory",
+            path.display(),
+            dir.display()
+        ));
+    }
+    let c_dir = std::ffi::CString::new(dir.as_os_str().as_encoded_bytes())
+        .map_err(|_| format!("{}: invalid path", path.display()))?;
+    // SAFETY: `c_dir` is a valid NUL-terminated string for the duration of the call.
+    if unsafe { libc::access(c_dir.as_ptr(), libc::W_OK) } != 0 {
+        return Err(format!(
+            "{}: directory {} is not writable",
+            path.display(),
+            dir.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Canonical project directory (the directory holding A3sfile.hcl).
+fn project_root(base_dir: &std::path::Path) -> Result<PathBuf> {
+    let base_dir = if base_dir.as_os_str().is_empty() {
+        std::path::Path::new(".")
+    } else {
+        base_dir
+    };
+    base_dir
+        .canonicalize()
+        .map_err(|e| DevError::Config(format!("cannot resolve {}: {e}", base_dir.display())))
+}
+
+/// Watch-path resolution and safety checks for a single service; see
+/// [`DevConfig::resolve_watch_paths`].
+fn resolve_service_watch(root: &std::path::Path, name: &str, svc: &mut ServiceDef) -> Result<()> {
+    let Some(watch) = svc.watch.as_mut() else {
+        return Ok(());
+    };
+    let svc_dir = match &svc.dir {
+        Some(d) => root.join(d),
+        None => root.to_path_buf(),
+    };
+    for path in &mut watch.paths {
+        let resolved = normalize_path(&svc_dir.join(&*path));
+        if !watch.allow_outside_project && !resolved.starts_with(root) {
+            return Err(DevError::Config(format!(
+                "service '{name}': watch path '{}' resolves to {}, outside the project \
+                 directory {} — set allow_outside_project = true to allow it",
+                path.display(),
+                resolved.display(),
+                root.display()
+            )));
+        }
+        *path = resolved;
+    }
+    if svc.disabled {
+        return Ok(());
+    }
+    let ceiling = watch.max_dirs.saturating_mul(10);
+    let count = count_watch_dirs(&watch.paths, &watch.ignore, watch.use_gitignore, ceiling)
+        .map_err(|e| DevError::Config(format!("service '{name}': {e}")))?;
+    if count > watch.max_dirs {
+        let plus = if count > ceiling { "+" } else { "" };
+        return Err(DevError::Config(format!(
+            "service '{name}': watch paths cover {count}{plus} directories (limit {}) — \
+             narrow `paths`, add `ignore` rules, or raise `max_dirs`",
+            watch.max_dirs
+        )));
+    }
+    Ok(())
+}
+
+/// Lexically resolve `.` and `..` components without touching the filesystem,
+/// so paths that don't exist yet can still be checked against the project root.
+fn normalize_path(path: &std::path::Path) -> PathBuf {
+    use std::path::Component;
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Count the directories a recursive watch on `paths` would register, skipping
+/// anything matched by `ignore` (same gitignore rules as the watcher). Stops once the
+/// count exceeds `stop_after`. Errors on a symlink that loops back to an ancestor.
+fn count_watch_dirs(
+    paths: &[PathBuf],
+    ignore: &[String],
+    use_gitignore: bool,
+    stop_after: usize,
+) -> std::result::Result<usize, String> {
+    fn walk(
+        dir: &std::path::Path,
+        ignore: &crate::watcher::IgnoreRules,
+        ancestors: &mut Vec<PathBuf>,
+        count: &mut usize,
+        stop_after: usize,
+    ) -> std::result::Result<(), String> {
+        let Ok(canonical) = dir.canonicalize() else {
+            return Ok(());
+        };
+        if ancestors.contains(&canonical) {
+            return Err(format!(
+                "symlink loop in watch paths at {} (points back to {})",
+                dir.display(),
+                canonical.display()
+            ));
+        }
+        *count += 1;
+        if *count > stop_after {
+            return Ok(());
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Ok(());
+        };
+        ancestors.push(canonical);
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() || ignore.is_ignored(&path) {
+                continue;
+            }
+            walk(&path, ignore, ancestors, count, stop_after)?;
+            if *count > stop_after {
+                break;
+            }
+        }
+        ancestors.pop();
+        Ok(())
+    }
+
+    let ignore = crate::watcher::IgnoreRules::new(paths, ignore, use_gitignore);
+    let mut count = 0;
+    for path in paths {
+        if path.is_dir() {
+            walk(path, &ignore, &mut Vec::new(), &mut count, stop_after)?;
+        }
+    }
+    Ok(count)
+}
+
+/// The personal overlay merged on top of `path`: `A3sfile.hcl` → `A3sfile.local.hcl`.
+pub fn local_overlay_path(path: &std::path::Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.local.hcl"))
+}
+
+/// `base` with `patch` deep-merged on top, via their JSON form.
+fn merged<T: Serialize + serde::de::DeserializeOwned>(
+    base: &T,
+    patch: serde_json::Value,
+) -> std::result::Result<T, serde_json::Error> {
+    let mut value = serde_json::to_value(base)?;
+    merge_json(&mut value, patch);
+    serde_json::from_value(value)
+}
+
+/// Objects merge key by key; anything else in `patch` replaces what is in `base`.
+fn merge_json(base: &mut serde_json::Value, patch: serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (k, v) in patch {
+                merge_json(base.entry(k).or_insert(serde_json::Value::Null), v);
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+/// Make `./x:/path` and `../x:/path` volume sources absolute, relative to `dir`.
+/// Anything else is a named volume or already absolute and is left alone.
+fn resolve_bind_mounts(dir: &std::path::Path, c: &mut ContainerDef) {
+    for v in &mut c.volumes {
+        if v.starts_with("./") || v.starts_with("../") {
+            if let Some((src, rest)) = v.split_once(':') {
+                *v = format!("{}:{rest}", dir.join(src).display());
+            }
+        }
+    }
+}
+
+/// Expand an `include` glob relative to `dir` into a sorted list of files.
+/// A pattern without wildcards must name an existing file.
+fn resolve_include(dir: &std::path::Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let full = if std::path::Path::new(pattern).is_absolute() {
+        pattern.to_string()
+    } else {
+        format!(
+            "{}/{pattern}",
+            glob::Pattern::escape(&dir.to_string_lossy())
+        )
+    };
+    let entries = glob::glob(&full)
+        .map_err(|e| DevError::Config(format!("invalid include pattern '{pattern}': {e}")))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort();
+    if paths.is_empty() && glob::Pattern::escape(pattern) == pattern {
+        return Err(DevError::Config(format!(
+            "include '{pattern}' not found in {}",
+            dir.display()
+        )));
+    }
+    Ok(paths)
+}
+
 /// Replace `${VAR}` placeholders in `s` with OS environment variable values.
 /// Unknown variables are left as-is (the `${VAR}` literal is preserved).
 pub fn interpolate_env_vars(s: &str) -> String {
@@ -445,10 +1267,17 @@ pub fn interpolate_env_vars(s: &str) -> String {
     result
 }
 
-/// Replace `${name.port}` placeholders in `s` with the runtime-assigned port for that service.
+/// Replace `${name.port}` and `{{service.name.port}}` placeholders in `s` with the
+/// runtime-assigned port for that service.
 /// Any placeholder that doesn't match a known service name is left unchanged.
 /// This is called at service-start time, after OS-env interpolation has already run.
 pub fn interpolate_service_ports(s: &str, ports: &HashMap<String, u16>) -> String {
+    let s = replace_service_refs(s, |name, attr| {
+        (attr == "port")
+            .then(|| ports.get(name))
+            .flatten()
+            .map(u16::to_string)
+    });
     let mut result = String::with_capacity(s.len());
     let mut chars = s.chars().peekable();
     while let Some(ch) = chars.next() {
@@ -470,7 +1299,82 @@ pub fn interpolate_service_ports(s: &str, ports: &HashMap<String, u16>) -> Strin
     result
 }
 
-/// Apply `${name.port}` interpolation to `cmd`, `env`, and hook fields of a `ServiceDef`.
+/// The `(service, attribute)` of every `{{service.<name>.<attribute>}}` in `s`.
+fn service_refs(s: &str) -> Vec<(&str, &str)> {
+    let mut out = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        if let Some(r) = parse_service_ref(&rest[start + 2..start + len]) {
+            out.push(r);
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out
+}
+
+fn parse_service_ref(inner: &str) -> Option<(&str, &str)> {
+    inner
+        .trim()
+        .strip_prefix("service.")?
+        .rsplit_once('.')
+        .filter(|(name, attr)| !name.is_empty() && !attr.is_empty())
+}
+
+/// `s` with each `{{service.<name>.<attribute>}}` that `value` knows replaced; the
+/// rest are left as written.
+fn replace_service_refs(s: &str, value: impl Fn(&str, &str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let whole = &rest[start..start + len + 2];
+        out.push_str(&rest[..start]);
+        match parse_service_ref(&whole[2..whole.len() - 2]).and_then(|(n, a)| value(n, a)) {
+            Some(v) => out.push_str(&v),
+            None => out.push_str(whole),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Fields of `svc` that may hold `{{service.…}}` references, with their names.
+fn templated_fields(svc: &ServiceDef) -> Vec<(String, &str)> {
+    let mut out = vec![("cmd".to_string(), svc.cmd.as_str())];
+    out.extend(
+        svc.env
+            .iter()
+            .map(|(k, v)| (format!("env.{k}"), v.as_str())),
+    );
+    out.extend(svc.pre_start.as_deref().map(|h| ("pre_start".into(), h)));
+    out.extend(svc.post_stop.as_deref().map(|h| ("post_stop".into(), h)));
+    out
+}
+
+/// Environment variable through which a service's port is exported to every other
+/// service: `db` → `A3S_SERVICE_DB_PORT`, `auth-api` → `A3S_SERVICE_AUTH_API_PORT`.
+pub fn service_port_var(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("A3S_SERVICE_{name}_PORT")
+}
+
+/// Apply `${name.port}` interpolation to `cmd`, `env`, and hook fields of a `ServiceDef`,
+/// and export each known port as `A3S_SERVICE_<NAME>_PORT` (explicit `env` wins).
 /// `ports` maps service names to their runtime-assigned ports.
 pub fn resolve_service_ports(mut svc: ServiceDef, ports: &HashMap<String, u16>) -> ServiceDef {
     svc.cmd = interpolate_service_ports(&svc.cmd, ports);
@@ -483,6 +1387,11 @@ pub fn resolve_service_ports(mut svc: ServiceDef, ports: &HashMap<String, u16>)
     if let Some(h) = svc.post_stop.take() {
         svc.post_stop = Some(interpolate_service_ports(&h, ports));
     }
+    for (name, port) in ports {
+        svc.env
+            .entry(service_port_var(name))
+            .or_insert_with(|| port.to_string());
+    }
     svc
 }
 
@@ -504,57 +1413,313 @@ fn parse_env_file(contents: &str) -> HashMap<String, String> {
     map
 }
 
+/// The environment selected by `A3S_ENV`, if any.
+pub fn selected_env() -> Option<String> {
+    std::env::var("A3S_ENV").ok().filter(|e| !e.is_empty())
+}
+
 impl DevConfig {
+    /// Load config with the environment `A3S_ENV` selects, if any.
     pub fn from_file(path: &std::path::Path) -> Result<Self> {
-        Self::from_file_with_env(path, None)
+        Self::from_file_with_env(path, selected_env().as_deref())
     }
 
-    /// Load config, optionally applying a named env_override block on top.
+    /// Load config, optionally applying the named `environment` and `env_override`
+    /// blocks on top. `None` is the base config alone.
     pub fn from_file_with_env(path: &std::path::Path, env_name: Option<&str>) -> Result<Self> {
-        let raw = std::fs::read_to_string(path)
-            .map_err(|e| DevError::Config(format!("cannot read {}: {e}", path.display())))?;
-        // Expand env("VAR") calls before HCL parsing.
-        let src = expand_env_func(&raw);
-        let mut cfg: DevConfig = hcl::from_str(&src)
-            .map_err(|e| DevError::Config(format!("parse error in {}: {e}", path.display())))?;
+        let mut cfg = Self::load_with_includes(path, &mut Vec::new())?;
+        cfg.apply_local_overlay(path)?;
         let base_dir = path.parent().unwrap_or(std::path::Path::new("."));
+        if let Some(name) = env_name {
+            cfg.apply_environment(name, base_dir)?;
+        }
+        if let Some(k) = cfg.dev.k8s_kubeconfig.as_mut().filter(|k| k.is_relative()) {
+            *k = base_dir.join(&*k);
+        }
+        cfg.add_container_services()?;
         cfg.resolve_env_files(base_dir)?;
+        cfg.resolve_watch_paths(base_dir)?;
+        cfg.check_log_files(base_dir)?;
         cfg.apply_global_dotenv(base_dir);
         cfg.apply_interpolation();
         if let Some(name) = env_name {
-            cfg.apply_env_override(name)?;
+            cfg.apply_env_override(name);
         }
+        cfg.link_service_refs();
+        cfg.resolve_subdomain_refs();
         cfg.validate()?;
         Ok(cfg)
     }
 
-    /// Merge env variables from a named `env_override` block into matching services.
-    /// Override values take precedence over the base service env.
-    fn apply_env_override(&mut self, name: &str) -> Result<()> {
-        let overrides = self.env_override.get(name).cloned().ok_or_else(|| {
-            DevError::Config(format!(
-                "env_override '{name}' not found in A3sfile.hcl — available: {}",
-                self.env_override
-                    .keys()
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            ))
-        })?;
-        for (svc_name, svc_override) in &overrides.service {
-            if let Some(svc) = self.service.get_mut(svc_name) {
-                for (k, v) in &svc_override.env {
-                    svc.env.insert(k.clone(), v.clone());
-                }
+    /// Parse a single file and recursively merge everything it `include`s.
+    /// Included files are merged in order (later ones win on key collision) and the
+    /// including file's own blocks are applied last, so it can override any of them.
+    /// `stack` holds the canonical paths currently being loaded, for cycle detection.
+    fn load_with_includes(path: &std::path::Path, stack: &mut Vec<PathBuf>) -> Result<Self> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| DevError::Config(format!("cannot read {}: {e}", path.display())))?;
+        if let Some(pos) = stack.iter().position(|p| *p == canonical) {
+            let chain = stack[pos..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(DevError::Config(format!("include cycle: {chain}")));
+        }
+
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| DevError::Config(format!("cannot read {}: {e}", path.display())))?;
+        // Expand env("VAR") calls before HCL parsing.
+        let src = expand_env_func(&raw);
+        let mut cfg: DevConfig =
+            hcl::from_str(&src).map_err(|e| parse_error::hcl_error(path, &src, &e))?;
+        let dir = path.parent().unwrap_or(std::path::Path::new("."));
+        // env_file paths are relative to the file that declares them.
+        for svc in cfg.service.values_mut() {
+            if let Some(f) = svc.env_file.as_mut().filter(|f| f.is_relative()) {
+                *f = dir.join(&*f);
             }
         }
-        Ok(())
+        for c in cfg.container.values_mut() {
+            resolve_bind_mounts(dir, c);
+        }
+        cfg.sources = cfg
+            .service
+            .keys()
+            .chain(cfg.container.keys())
+            .map(|name| (name.clone(), vec![path.to_path_buf()]))
+            .collect();
+        if cfg.include.is_empty() {
+            return Ok(cfg);
+        }
+
+        stack.push(canonical);
+        let mut service = IndexMap::new();
+        let mut container = IndexMap::new();
+        let mut env_override = IndexMap::new();
+        let mut environment = IndexMap::new();
+        let mut sources = IndexMap::new();
+        for pattern in std::mem::take(&mut cfg.include) {
+            for inc_path in resolve_include(dir, &pattern)? {
+                let inc = Self::load_with_includes(&inc_path, stack)?;
+                service.extend(inc.service);
+                container.extend(inc.container);
+                env_override.extend(inc.env_override);
+                environment.extend(inc.environment);
+                sources.extend(inc.sources);
+            }
+        }
+        stack.pop();
+
+        service.extend(std::mem::take(&mut cfg.service));
+        container.extend(std::mem::take(&mut cfg.container));
+        env_override.extend(std::mem::take(&mut cfg.env_override));
+        environment.extend(std::mem::take(&mut cfg.environment));
+        sources.extend(std::mem::take(&mut cfg.sources));
+        cfg.service = service;
+        cfg.container = container;
+        cfg.env_override = env_override;
+        cfg.environment = environment;
+        cfg.sources = sources;
+        Ok(cfg)
     }
 
-    /// For each service with an `env_file`, parse the file and merge its variables.
-    /// Variables already present in `env` take precedence (env_file provides defaults).
-    fn resolve_env_files(&mut self, base_dir: &std::path::Path) -> Result<()> {
-        for (name, svc) in &mut self.service {
+    /// Deep-merge the personal overlay next to `path` (`A3sfile.local.hcl` for
+    /// `A3sfile.hcl`), if there is one. Blocks merge field by field, maps such as
+    /// `env` are unioned with the overlay winning, and lists and plain values are
+    /// replaced. Services only the overlay declares are added.
+    fn apply_local_overlay(&mut self, path: &std::path::Path) -> Result<()> {
+        let overlay_path = local_overlay_path(path);
+        let Ok(raw) = std::fs::read_to_string(&overlay_path) else {
+            return Ok(());
+        };
+        let src = expand_env_func(&raw);
+        let at = |e: &dyn std::fmt::Display| {
+            parse_error::serde_error(&overlay_path, &src, &e.to_string())
+        };
+        let overlay: serde_json::Value =
+            hcl::from_str(&src).map_err(|e| parse_error::hcl_error(&overlay_path, &src, &e))?;
+        let serde_json::Value::Object(mut overlay) = overlay else {
+            return Ok(());
+        };
+        if overlay.contains_key("include") {
+            return Err(at(&"`include` is not supported in a local overlay"));
+        }
+
+        if let Some(dev) = overlay.remove("dev") {
+            self.dev = merged(&self.dev, dev).map_err(|e| at(&format_args!("dev: {e}")))?;
+        }
+        if let Some(serde_json::Value::Object(services)) = overlay.remove("service") {
+            let dir = overlay_path.parent().unwrap_or(std::path::Path::new("."));
+            for (name, patch) in services {
+                let declares_env_file = patch.get("env_file").is_some();
+                let mut svc = match self.service.get(&name) {
+                    Some(base) => merged(base, patch),
+                    None => serde_json::from_value::<ServiceDef>(patch),
+                }
+                .map_err(|e| at(&format_args!("service '{name}': {e}")))?;
+                if declares_env_file {
+                    if let Some(f) = svc.env_file.as_mut().filter(|f| f.is_relative()) {
+                        *f = dir.join(&*f);
+                    }
+                }
+                self.sources
+                    .entry(name.clone())
+                    .or_default()
+                    .push(overlay_path.clone());
+                self.service.insert(name, svc);
+            }
+        }
+        if let Some(serde_json::Value::Object(containers)) = overlay.remove("container") {
+            let dir = overlay_path.parent().unwrap_or(std::path::Path::new("."));
+            for (name, patch) in containers {
+                let mut c = match self.container.get(&name) {
+                    Some(base) => merged(base, patch),
+                    None => serde_json::from_value::<ContainerDef>(patch),
+                }
+                .map_err(|e| at(&format_args!("container '{name}': {e}")))?;
+                resolve_bind_mounts(dir, &mut c);
+                self.sources
+                    .entry(name.clone())
+                    .or_default()
+                    .push(overlay_path.clone());
+                self.container.insert(name, c);
+            }
+        }
+        if let Some(serde_json::Value::Object(overrides)) = overlay.remove("env_override") {
+            for (name, patch) in overrides {
+                let ov = match self.env_override.get(&name) {
+                    Some(base) => merged(base, patch),
+                    None => serde_json::from_value::<EnvOverride>(patch),
+                }
+                .map_err(|e| at(&format_args!("env_override '{name}': {e}")))?;
+                self.env_override.insert(name, ov);
+            }
+        }
+        if let Some(serde_json::Value::Object(environments)) = overlay.remove("environment") {
+            for (name, patch) in environments {
+                let env = match self.environment.get(&name) {
+                    Some(base) => merged(base, patch),
+                    None => serde_json::from_value::<Environment>(patch),
+                }
+                .map_err(|e| at(&format_args!("environment '{name}': {e}")))?;
+                self.environment.insert(name, env);
+            }
+        }
+        if let Some(key) = overlay.keys().next() {
+            return Err(at(&format_args!(
+                "unknown field `{key}`, expected one of `dev`, `service`, `container`, \
+                 `env_override`, `environment`"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Deep-merge the `environment` block called `name` over the services. A name
+    /// only an `env_override` block defines is fine; one neither defines is an error.
+    /// Relative `env_file` paths in the block are relative to `base_dir`.
+    fn apply_environment(&mut self, name: &str, base_dir: &std::path::Path) -> Result<()> {
+        if !self.environment.contains_key(name) && !self.env_override.contains_key(name) {
+            let mut defined: Vec<&str> = self
+                .environment
+                .keys()
+                .chain(self.env_override.keys())
+                .map(String::as_str)
+                .collect();
+            defined.sort_unstable();
+            defined.dedup();
+            return Err(DevError::Config(format!(
+                "unknown environment '{name}' — defined: {}",
+                if defined.is_empty() {
+                    "none".to_string()
+                } else {
+                    defined.join(", ")
+                }
+            )));
+        }
+        let Some(env) = self.environment.get(name).cloned() else {
+            return Ok(());
+        };
+        for (svc_name, patch) in env.service {
+            let declares_env_file = patch.get("env_file").is_some();
+            let mut svc = match self.service.get(&svc_name) {
+                Some(base) => merged(base, patch),
+                None => serde_json::from_value::<ServiceDef>(patch),
+            }
+            .map_err(|e| {
+                DevError::Config(format!("environment '{name}': service '{svc_name}': {e}"))
+            })?;
+            if declares_env_file {
+                if let Some(f) = svc.env_file.as_mut().filter(|f| f.is_relative()) {
+                    *f = base_dir.join(&*f);
+                }
+            }
+            self.service.insert(svc_name, svc);
+        }
+        Ok(())
+    }
+
+    /// Add a service for every `container` block, so containers are started, stopped,
+    /// health-checked and ordered by `depends_on` like processes are.
+    fn add_container_services(&mut self) -> Result<()> {
+        for (name, c) in &self.container {
+            if self.service.contains_key(name) {
+                return Err(DevError::Config(format!(
+                    "'{name}' is declared both as a service and as a container"
+                )));
+            }
+            let svc = crate::box_mgr::container_service(name, c)
+                .map_err(|e| DevError::Config(format!("container '{name}': {e}")))?;
+            self.service.insert(name.clone(), svc);
+        }
+        Ok(())
+    }
+
+    /// Make every `watch.paths` entry absolute (relative to the service `dir`, which is
+    /// itself relative to the project directory) and refuse unsafe watch sets: paths
+    /// escaping the project without `allow_outside_project`, trees larger than
+    /// `max_dirs`, and symlink loops.
+    fn resolve_watch_paths(&mut self, base_dir: &std::path::Path) -> Result<()> {
+        let root = project_root(base_dir)?;
+        for (name, svc) in &mut self.service {
+            resolve_service_watch(&root, name, svc)?;
+        }
+        Ok(())
+    }
+
+    /// Fail early if any enabled service's `log_file` could not be written.
+    fn check_log_files(&self, base_dir: &std::path::Path) -> Result<()> {
+        for (name, svc) in self.service.iter().filter(|(_, s)| !s.disabled) {
+            if let Some(f) = &svc.log_file {
+                check_log_file(&base_dir.join(f))
+                    .map_err(|e| DevError::Config(format!("service '{name}': log_file {e}")))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge env variables from a named `env_override` block into matching services.
+    /// Override values take precedence over the base service env.
+    /// Unknown names are caught by [`Self::apply_environment`].
+    fn apply_env_override(&mut self, name: &str) {
+        let Some(overrides) = self.env_override.get(name).cloned() else {
+            return;
+        };
+        for (svc_name, svc_override) in &overrides.service {
+            if let Some(svc) = self.service.get_mut(svc_name) {
+                for (k, v) in &svc_override.env {
+                    svc.env.insert(k.clone(), v.clone());
+                }
+            }
+        }
+    }
+
+    /// For each service with an `env_file`, parse the file and merge its variables.
+    /// Variables already present in `env` take precedence (env_file provides defaults).
+    fn resolve_env_files(&mut self, base_dir: &std::path::Path) -> Result<()> {
+        for (name, svc) in &mut self.service {
             let Some(ref env_file) = svc.env_file else {
                 continue;
             };
@@ -608,19 +1773,268 @@ impl DevConfig {
         }
     }
 
+    /// Make every service start after the services its `{{service.…}}` references
+    /// name, by adding them to `depends_on`. Unknown and disabled services are left
+    /// for validation to report.
+    fn link_service_refs(&mut self) {
+        let enabled: Vec<String> = self
+            .service
+            .iter()
+            .filter(|(_, s)| !s.disabled)
+            .map(|(n, _)| n.clone())
+            .collect();
+        for (name, svc) in self.service.iter_mut() {
+            let refs: Vec<String> = templated_fields(svc)
+                .iter()
+                .flat_map(|(_, v)| service_refs(v))
+                .map(|(target, _)| target.to_string())
+                .filter(|t| t != name && enabled.contains(t))
+                .collect();
+            for target in refs {
+                if !svc.depends_on.contains(&target) {
+                    svc.depends_on.push(target);
+                }
+            }
+        }
+    }
+
+    /// Replace `{{service.<name>.subdomain_url}}` references, which are known before
+    /// anything starts. Ports are resolved at start time by [`resolve_service_ports`].
+    fn resolve_subdomain_refs(&mut self) {
+        let urls: HashMap<String, String> = self
+            .service
+            .keys()
+            .filter_map(|n| Some((n.clone(), self.subdomain_url(n)?)))
+            .collect();
+        let resolve = |s: &str| {
+            replace_service_refs(s, |name, attr| {
+                (attr == "subdomain_url")
+                    .then(|| urls.get(name).cloned())
+                    .flatten()
+            })
+        };
+        for svc in self.service.values_mut() {
+            svc.cmd = resolve(&svc.cmd);
+            for v in svc.env.values_mut() {
+                *v = resolve(v);
+            }
+            if let Some(h) = svc.pre_start.take() {
+                svc.pre_start = Some(resolve(&h));
+            }
+            if let Some(h) = svc.post_stop.take() {
+                svc.post_stop = Some(resolve(&h));
+            }
+        }
+    }
+
+    /// The proxy URL of service `name`, e.g. `http://api.localhost:7080`, if it has a
+    /// subdomain.
+    pub fn subdomain_url(&self, name: &str) -> Option<String> {
+        let sub = self.service.get(name)?.subdomain.as_deref()?;
+        let (scheme, default_port) = if self.dev.tls_enabled() {
+            ("https", 443)
+        } else {
+            ("http", 80)
+        };
+        Some(if self.dev.proxy_port == default_port {
+            format!("{scheme}://{sub}.localhost")
+        } else {
+            format!("{scheme}://{sub}.localhost:{}", self.dev.proxy_port)
+        })
+    }
+
+    /// Fail on the first of `names` that isn't a service, listing the ones there are.
+    pub fn check_service_names(&self, names: &[String]) -> Result<()> {
+        let unknown: Vec<&str> = names
+            .iter()
+            .filter(|n| !self.service.contains_key(*n))
+            .map(String::as_str)
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        let valid: Vec<&str> = self.service.keys().map(String::as_str).collect();
+        Err(DevError::Config(format!(
+            "unknown service{} {} (services: {})",
+            if unknown.len() == 1 { "" } else { "s" },
+            unknown
+                .iter()
+                .map(|n| format!("'{n}'"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            valid.join(", ")
+        )))
+    }
+
     pub fn validate(&self) -> Result<()> {
+        match self.problems().into_iter().next() {
+            Some((_, e)) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Every validation problem, in the order `validate()` checks them, paired with
+    /// the service it concerns.
+    fn problems(&self) -> Vec<(Option<String>, DevError)> {
+        let mut out = Vec::new();
         // Port conflict check — skip port 0 (auto-assigned at runtime) and disabled services
         let mut seen: HashMap<u16, &str> = HashMap::new();
         for (name, svc) in &self.service {
             if svc.disabled || svc.port == 0 {
                 continue;
             }
-            if let Some(other) = seen.insert(svc.port, name.as_str()) {
-                return Err(DevError::PortConflict {
-                    a: other.to_string(),
-                    b: name.clone(),
-                    port: svc.port,
-                });
+            if let Some(other) = seen.get(&svc.port) {
+                out.push((
+                    Some(name.clone()),
+                    DevError::PortConflict {
+                        a: other.to_string(),
+                        b: name.clone(),
+                        port: svc.port,
+                    },
+                ));
+            } else {
+                seen.insert(svc.port, name.as_str());
+            }
+        }
+        // Shell check — without a shell, `cmd` itself must name a program
+        for (name, svc) in &self.service {
+            let msg = match svc.shell.as_deref() {
+                Some("none") if crate::supervisor::split_cmd(&svc.cmd).is_empty() => {
+                    "shell = \"none\" needs a cmd with a program to run"
+                }
+                Some(shell) if shell.trim().is_empty() => {
+                    "shell must name a program, or be \"none\""
+                }
+                _ => continue,
+            };
+            out.push((
+                Some(name.clone()),
+                DevError::Config(format!("service '{name}': {msg}")),
+            ));
+        }
+        // Subdomain check — each must be a valid hostname and routed to one service only
+        let mut seen: HashMap<&str, &str> = HashMap::new();
+        for (name, svc) in &self.service {
+            let Some(sub) = svc.subdomain.as_deref() else {
+                continue;
+            };
+            if let Err(msg) = check_subdomain(sub) {
+                out.push((
+                    Some(name.clone()),
+                    DevError::Config(format!("service '{name}': {msg}")),
+                ));
+                continue;
+            }
+            if svc.disabled {
+                continue;
+            }
+            if let Some(other) = seen.get(sub) {
+                out.push((
+                    Some(name.clone()),
+                    DevError::SubdomainConflict {
+                        a: other.to_string(),
+                        b: name.clone(),
+                        subdomain: sub.to_string(),
+                    },
+                ));
+            } else {
+                seen.insert(sub, name.as_str());
+            }
+        }
+        for (name, svc) in &self.service {
+            if svc.log_file.is_some() && svc.log_keep == 0 {
+                out.push((
+                    Some(name.clone()),
+                    DevError::Config(format!("service '{name}': log_keep must be at least 1")),
+                ));
+            }
+        }
+        // Resource limits must be positive
+        for (name, svc) in &self.service {
+            if let Some(cpu) = svc.limits.as_ref().and_then(|l| l.cpu) {
+                if cpu.is_nan() || cpu <= 0.0 {
+                    out.push((
+                        Some(name.clone()),
+                        DevError::Config(format!(
+                            "service '{name}': limits.cpu must be greater than 0 (got {cpu})"
+                        )),
+                    ));
+                }
+            }
+        }
+        if self.dev.ui_bind.parse::<std::net::IpAddr>().is_err() {
+            out.push((
+                None,
+                DevError::Config(format!(
+                    "dev.ui_bind must be an IP address, e.g. \"127.0.0.1\" or \"0.0.0.0\" (got '{}')",
+                    self.dev.ui_bind
+                )),
+            ));
+        }
+        if self.dev.tls.cert.is_some() != self.dev.tls.key.is_some() {
+            out.push((
+                None,
+                DevError::Config("dev.tls: set both cert and key, or neither".into()),
+            ));
+        }
+        let threshold = self.dev.flaky_threshold;
+        if !(0.0..=100.0).contains(&threshold) {
+            out.push((
+                None,
+                DevError::Config(format!(
+                    "dev.flaky_threshold must be between 0 and 100 (got {threshold})"
+                )),
+            ));
+        }
+        // Warm standby needs a second port and a readiness signal
+        for (name, svc) in &self.service {
+            if !svc.standby {
+                continue;
+            }
+            if svc.port != 0 {
+                out.push((
+                    Some(name.clone()),
+                    DevError::Config(format!(
+                        "service '{name}': standby requires port = 0 so the spare can get its own port"
+                    )),
+                ));
+            }
+            if svc.health.is_none() {
+                out.push((
+                    Some(name.clone()),
+                    DevError::Config(format!(
+                        "service '{name}': standby requires a health check to know when the spare is ready"
+                    )),
+                ));
+            }
+        }
+        // `{{service.<name>.<attribute>}}` references must name an enabled service and
+        // something it has
+        for (name, svc) in self.service.iter().filter(|(_, s)| !s.disabled) {
+            for (field, value) in templated_fields(svc) {
+                for (target, attr) in service_refs(value) {
+                    let reference = format!("{{{{service.{target}.{attr}}}}}");
+                    let problem = match self.service.get(target) {
+                        None => Some(format!("references unknown service '{target}'")),
+                        Some(t) if t.disabled => {
+                            Some(format!("references disabled service '{target}'"))
+                        }
+                        Some(_) if attr == "port" => None,
+                        Some(t) if attr == "subdomain_url" && t.subdomain.is_none() => {
+                            Some(format!("references {reference}, but '{target}' has no subdomain"))
+                        }
+                        Some(_) if attr == "subdomain_url" => None,
+                        Some(_) => Some(format!(
+                            "references unknown attribute in {reference} — use port or subdomain_url"
+                        )),
+                    };
+                    if let Some(problem) = problem {
+                        out.push((
+                            Some(name.clone()),
+                            DevError::Config(format!("service '{name}': {field} {problem}")),
+                        ));
+                    }
+                }
             }
         }
         // Unknown depends_on references — skip disabled services
@@ -631,19 +2045,283 @@ impl DevConfig {
             for dep in &svc.depends_on {
                 let dep_svc = self.service.get(dep);
                 if dep_svc.is_none() || dep_svc.is_some_and(|d| d.disabled) {
-                    return Err(DevError::Config(format!(
-                        "service '{name}' depends_on unknown or disabled service '{dep}'"
-                    )));
+                    out.push((
+                        Some(name.clone()),
+                        DevError::Config(format!(
+                            "service '{name}' depends_on unknown or disabled service '{dep}'"
+                        )),
+                    ));
                 }
             }
         }
-        Ok(())
+        out
+    }
+
+    /// Check `path` and report every problem found, instead of stopping at the first
+    /// like [`DevConfig::from_file`] does. Nothing is started. Used by `a3s config check`.
+    pub fn diagnostics(path: &std::path::Path) -> Vec<Diagnostic> {
+        let mut cfg = match Self::load_with_includes(path, &mut Vec::new())
+            .and_then(|mut cfg| cfg.apply_local_overlay(path).map(|()| cfg))
+            .and_then(|mut cfg| cfg.add_container_services().map(|()| cfg))
+        {
+            Ok(cfg) => cfg,
+            Err(e) => return vec![Diagnostic::error(None, e)],
+        };
+        let mut out = Vec::new();
+
+        for (name, svc) in &cfg.service {
+            if let Some(f) = svc.env_file.as_ref().filter(|f| !f.exists()) {
+                out.push(Diagnostic::error(
+                    Some(name),
+                    DevError::Config(format!("env_file {} not found", f.display())),
+                ));
+            }
+        }
+
+        let base_dir = path.parent().unwrap_or(std::path::Path::new("."));
+        for (name, svc) in cfg.service.iter().filter(|(_, s)| !s.disabled) {
+            if let Some(Err(e)) = svc
+                .log_file
+                .as_ref()
+                .map(|f| check_log_file(&base_dir.join(f)))
+            {
+                out.push(Diagnostic::error(
+                    Some(name),
+                    DevError::Config(format!("log_file {e}")),
+                ));
+            }
+        }
+        match project_root(base_dir) {
+            Ok(root) => {
+                for (name, svc) in &mut cfg.service {
+                    if let Err(e) = resolve_service_watch(&root, name, svc) {
+                        out.push(Diagnostic::error(Some(name), e));
+                    }
+                }
+            }
+            Err(e) => out.push(Diagnostic::error(None, e)),
+        }
+        out.extend(cfg.lint().into_iter().map(Diagnostic::from));
+
+        out.extend(
+            cfg.problems()
+                .into_iter()
+                .map(|(svc, e)| Diagnostic::error(svc.as_deref(), e)),
+        );
+
+        // Unknown dependencies look like cycles to the graph builder, so only look for
+        // real cycles once every depends_on target exists.
+        let deps_known = cfg
+            .service
+            .values()
+            .flat_map(|s| &s.depends_on)
+            .all(|d| cfg.service.contains_key(d));
+        if deps_known {
+            if let Err(e @ DevError::Cycle(_)) = crate::graph::DependencyGraph::from_config(&cfg) {
+                out.push(Diagnostic::error(None, e));
+            }
+        }
+        out
+    }
+}
+
+impl DevConfig {
+    /// "Probably wrong" findings in the config at `path`, which never block startup.
+    /// Load errors are left to [`DevConfig::from_file`] and `a3s config check`.
+    pub fn lints(path: &std::path::Path) -> Vec<Lint> {
+        let Ok(mut cfg) = Self::load_with_includes(path, &mut Vec::new())
+            .and_then(|mut cfg| cfg.apply_local_overlay(path).map(|()| cfg))
+            .and_then(|mut cfg| cfg.add_container_services().map(|()| cfg))
+        else {
+            return vec![];
+        };
+        let base_dir = path.parent().unwrap_or(std::path::Path::new("."));
+        if let Ok(root) = project_root(base_dir) {
+            for (name, svc) in &mut cfg.service {
+                let _ = resolve_service_watch(&root, name, svc);
+            }
+        }
+        cfg.lint()
+    }
+
+    /// Lints of enabled services, minus the codes each lists in `lint_ignore`.
+    /// Expects watch paths resolved and `env_file` not yet merged into `env`.
+    fn lint(&self) -> Vec<Lint> {
+        let mut out = Vec::new();
+        for (name, svc) in self.service.iter().filter(|(_, s)| !s.disabled) {
+            let mut push = |code: &'static str, field: String, message: String| {
+                if !svc.lint_ignore.iter().any(|c| c == code) {
+                    out.push(Lint {
+                        code,
+                        service: name.clone(),
+                        field,
+                        message,
+                    });
+                }
+            };
+
+            for p in svc.watch.iter().flat_map(|w| &w.paths) {
+                if !p.exists() {
+                    push(
+                        "missing-watch-path",
+                        "watch.paths".into(),
+                        format!("watch path {} does not exist", p.display()),
+                    );
+                }
+            }
+
+            if let Some(f) = &svc.env_file {
+                let from_file = std::fs::read_to_string(f)
+                    .map(|c| parse_env_file(&c))
+                    .unwrap_or_default();
+                let mut shadowed: Vec<&String> = from_file
+                    .keys()
+                    .filter(|k| svc.env.contains_key(*k))
+                    .collect();
+                shadowed.sort();
+                for key in shadowed {
+                    push(
+                        "env-shadowed",
+                        format!("env.{key}"),
+                        format!("inline env overrides {key} from {}", f.display()),
+                    );
+                }
+            }
+
+            if let Some(h) = svc.health.as_ref().filter(|h| h.kind == HealthKind::Tcp) {
+                if h.path.is_some() {
+                    push(
+                        "tcp-health-path",
+                        "health.path".into(),
+                        "`path` has no effect on a tcp health check".into(),
+                    );
+                }
+            }
+
+            if svc.subdomain.is_some() && svc.port == 0 && !listens_on_port_env(&svc.cmd) {
+                push(
+                    "auto-port-subdomain",
+                    "subdomain".into(),
+                    "routed to an auto-assigned port passed as $PORT, but cmd neither \
+                     mentions PORT nor is a known framework — set a fixed `port` \
+                     if the service listens elsewhere"
+                        .into(),
+                );
+            }
+
+            let mut secrets: Vec<&String> = svc
+                .env
+                .iter()
+                .filter(|(k, v)| looks_like_secret(k) && self.written_inline(name, v))
+                .map(|(k, _)| k)
+                .collect();
+            secrets.sort();
+            for key in secrets {
+                push(
+                    "inline-secret",
+                    format!("env.{key}"),
+                    format!("{key} looks like a secret committed inline — use env(\"{key}\"), env_secret or an env_file"),
+                );
+            }
+        }
+        out
+    }
+
+    /// True if `value` appears as a literal string in a file `service` comes from,
+    /// rather than arriving through `env()` or `${VAR}`.
+    fn written_inline(&self, service: &str, value: &str) -> bool {
+        if value.is_empty() || value.contains("${") {
+            return false;
+        }
+        let quoted = format!("\"{value}\"");
+        self.sources
+            .get(service)
+            .into_iter()
+            .flatten()
+            .any(|f| std::fs::read_to_string(f).is_ok_and(|src| src.contains(&quoted)))
+    }
+}
+
+/// Env var names that usually hold credentials.
+fn looks_like_secret(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    [
+        "SECRET",
+        "TOKEN",
+        "PASSWORD",
+        "PASSWD",
+        "API_KEY",
+        "PRIVATE_KEY",
+        "ACCESS_KEY",
+    ]
+    .iter()
+    .any(|s| key.contains(s))
+}
+
+/// Whether `cmd` can pick up the port a3s assigns: it references PORT itself, or it
+/// runs a framework a3s passes `--port` to.
+fn listens_on_port_env(cmd: &str) -> bool {
+    let parts = crate::supervisor::split_cmd(cmd);
+    cmd.contains("PORT") || !crate::supervisor::framework_port_args(&parts, "1").is_empty()
+}
+
+// ── Diagnostics ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single problem reported by [`DevConfig::diagnostics`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The service the problem concerns; `None` for file-level problems.
+    pub service: Option<String>,
+    pub message: String,
+}
+
+/// A "probably wrong" finding from [`DevConfig::lints`]. Never blocks startup;
+/// silence one with `lint_ignore = ["<code>"]` in the service block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Lint {
+    pub code: &'static str,
+    pub service: String,
+    /// The offending field, e.g. `health.path` or `env.API_TOKEN`.
+    pub field: String,
+    pub message: String,
+}
+
+impl From<Lint> for Diagnostic {
+    fn from(l: Lint) -> Self {
+        Self {
+            severity: Severity::Warning,
+            service: Some(l.service),
+            message: format!("{}: {} [{}]", l.field, l.message, l.code),
+        }
+    }
+}
+
+impl Diagnostic {
+    fn error(service: Option<&str>, e: DevError) -> Self {
+        let message = match e {
+            DevError::Config(msg) => msg,
+            other => other.to_string(),
+        };
+        Self {
+            severity: Severity::Error,
+            service: service.map(str::to_string),
+            message,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use size_serde::parse_size;
 
     fn make_svc(port: u16, depends_on: Vec<&str>) -> ServiceDef {
         ServiceDef {
@@ -653,18 +2331,29 @@ mod tests {
             subdomain: None,
             env: Default::default(),
             env_file: None,
+            env_reload: false,
+            shell: None,
             log_file: None,
             log_rotate_mb: 0,
+            log_max_size: None,
+            log_keep: 1,
             pre_start: None,
             post_stop: None,
+            hook_timeout: std::time::Duration::from_secs(300),
             depends_on: depends_on.into_iter().map(|s| s.to_string()).collect(),
+            depends_on_timeout: None,
             watch: None,
             health: None,
             restart: Default::default(),
             stop_timeout: std::time::Duration::from_secs(5),
             disabled: false,
+            autostart: true,
             labels: vec![],
+            standby: false,
+            limits: None,
             k8s: None,
+            lint_ignore: vec![],
+            env_secret: Default::default(),
         }
     }
 
@@ -676,7 +2365,11 @@ mod tests {
         DevConfig {
             dev: GlobalSettings::default(),
             service: map,
+            container: Default::default(),
             env_override: Default::default(),
+            environment: Default::default(),
+            include: vec![],
+            sources: Default::default(),
         }
     }
 
@@ -698,6 +2391,23 @@ mod tests {
         assert!(matches!(cfg.validate(), Err(DevError::PortConflict { .. })));
     }
 
+    #[test]
+    fn test_validate_shell() {
+        let with_shell = |shell: &str, cmd: &str| {
+            let mut svc = make_svc(0, vec![]);
+            svc.shell = Some(shell.into());
+            svc.cmd = cmd.into();
+            make_config(vec![("a", svc)]).validate()
+        };
+        assert!(with_shell("bash", "set -o pipefail; make | tee log").is_ok());
+        assert!(with_shell("none", "node server.js").is_ok());
+        assert!(matches!(with_shell("none", "  "), Err(DevError::Config(m)) if m.contains("none")));
+        assert!(with_shell("", "echo").is_err());
+        let cfg: DevConfig =
+            hcl::from_str("service \"a\" {\n  cmd = \"echo\"\n  shell = \"bash\"\n}\n").unwrap();
+        assert_eq!(cfg.service["a"].shell.as_deref(), Some("bash"));
+    }
+
     #[test]
     fn test_validate_port_zero_no_conflict() {
         // Two services with port=0 should not conflict
@@ -705,6 +2415,65 @@ mod tests {
         assert!(cfg.validate().is_ok());
     }
 
+    fn with_subdomain(port: u16, sub: &str) -> ServiceDef {
+        let mut svc = make_svc(port, vec![]);
+        svc.subdomain = Some(sub.into());
+        svc
+    }
+
+    #[test]
+    fn test_validate_subdomain_conflict() {
+        let cfg = make_config(vec![
+            ("a", with_subdomain(3000, "api")),
+            ("b", with_subdomain(3001, "api")),
+        ]);
+        match cfg.validate() {
+            Err(DevError::SubdomainConflict { a, b, subdomain }) => {
+                assert_eq!(
+                    (a.as_str(), b.as_str(), subdomain.as_str()),
+                    ("a", "b", "api")
+                );
+            }
+            other => panic!("expected SubdomainConflict, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_validate_distinct_subdomains_ok() {
+        let cfg = make_config(vec![
+            ("a", with_subdomain(3000, "api")),
+            ("b", with_subdomain(3001, "api-v2.internal")),
+        ]);
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn test_disabled_skips_subdomain_conflict() {
+        let mut svc_b = with_subdomain(3001, "api");
+        svc_b.disabled = true;
+        let cfg = make_config(vec![("a", with_subdomain(3000, "api")), ("b", svc_b)]);
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_invalid_subdomain() {
+        for bad in [
+            "my api", "api/v2", "my_api", "Api", "-api", "api-", "a..b", "",
+        ] {
+            let cfg = make_config(vec![("a", with_subdomain(3000, bad))]);
+            assert!(
+                matches!(cfg.validate(), Err(DevError::Config(ref m)) if m.contains("not a valid hostname")),
+                "{bad:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_reserved_subdomain() {
+        let cfg = make_config(vec![("a", with_subdomain(3000, "ui"))]);
+        assert!(matches!(cfg.validate(), Err(DevError::Config(ref m)) if m.contains("reserved")));
+    }
+
     #[test]
     fn test_validate_unknown_depends_on() {
         let cfg = make_config(vec![("a", make_svc(3000, vec!["nonexistent"]))]);
@@ -816,6 +2585,22 @@ service "api" {
         );
     }
 
+    #[test]
+    fn test_shutdown_timeout_default_and_custom() {
+        let cfg: DevConfig = hcl::from_str(r#"service "api" { cmd = "echo" }"#).unwrap();
+        assert_eq!(cfg.dev.shutdown_timeout, std::time::Duration::from_secs(30));
+        let cfg: DevConfig = hcl::from_str(
+            r#"
+dev { shutdown_timeout = "2m" }
+service "api" { cmd = "echo" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.dev.shutdown_timeout,
+            std::time::Duration::from_secs(120)
+        );
+    }
+
     #[test]
     fn test_restart_config_defaults() {
         let src = r#"service "api" { cmd = "echo" }"#;
@@ -825,6 +2610,7 @@ service "api" {
         assert_eq!(r.backoff, std::time::Duration::from_secs(1));
         assert_eq!(r.max_backoff, std::time::Duration::from_secs(30));
         assert_eq!(r.on_failure, OnFailure::Restart);
+        assert_eq!(r.crash_loop_after, 5);
     }
 
     #[test]
@@ -837,6 +2623,7 @@ service "api" {
     backoff      = "2s"
     max_backoff  = "60s"
     on_failure   = "stop"
+    crash_loop_after = 2
   }
 }"#;
         let cfg: DevConfig = hcl::from_str(src).unwrap();
@@ -845,19 +2632,46 @@ service "api" {
         assert_eq!(r.backoff, std::time::Duration::from_secs(2));
         assert_eq!(r.max_backoff, std::time::Duration::from_secs(60));
         assert_eq!(r.on_failure, OnFailure::Stop);
+        assert_eq!(r.crash_loop_after, 2);
     }
 
     #[test]
-    fn test_service_def_partial_eq_same() {
-        let a = make_svc(3000, vec![]);
-        let b = make_svc(3000, vec![]);
-        assert_eq!(a, b);
-    }
-
-    #[test]
-    fn test_service_def_partial_eq_different_port() {
-        let a = make_svc(3000, vec![]);
-        let b = make_svc(3001, vec![]);
+    fn test_health_start_period() {
+        let src = r#"
+service "api" {
+  cmd = "echo"
+  health {
+    type            = "tcp"
+    start_period    = "90s"
+    startup_retries = 10
+  }
+}"#;
+        let cfg: DevConfig = hcl::from_str(src).unwrap();
+        let h = cfg.service["api"].health.as_ref().unwrap();
+        assert_eq!(h.start_period, Some(std::time::Duration::from_secs(90)));
+        assert_eq!(h.startup_retries, Some(10));
+        assert_eq!(h.retries, 3);
+
+        let src = r#"service "api" {
+  cmd = "echo"
+  health { type = "tcp" }
+}"#;
+        let cfg: DevConfig = hcl::from_str(src).unwrap();
+        let h = cfg.service["api"].health.as_ref().unwrap();
+        assert_eq!((h.start_period, h.startup_retries), (None, None));
+    }
+
+    #[test]
+    fn test_service_def_partial_eq_same() {
+        let a = make_svc(3000, vec![]);
+        let b = make_svc(3000, vec![]);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_service_def_partial_eq_different_port() {
+        let a = make_svc(3000, vec![]);
+        let b = make_svc(3001, vec![]);
         assert_ne!(a, b);
     }
 
@@ -997,6 +2811,20 @@ service "api" {
         assert!(cfg.service["api"].post_stop.is_none());
     }
 
+    #[test]
+    fn test_hook_timeout_default_and_override() {
+        let src = r#"
+service "api" { cmd = "echo" }
+service "db" {
+  cmd          = "echo"
+  hook_timeout = "30s"
+}
+"#;
+        let cfg: DevConfig = hcl::from_str(src).unwrap();
+        assert_eq!(cfg.service["api"].hook_timeout, Duration::from_secs(300));
+        assert_eq!(cfg.service["db"].hook_timeout, Duration::from_secs(30));
+    }
+
     // ── Inter-service port interpolation ─────────────────────────────────────
 
     #[test]
@@ -1036,6 +2864,125 @@ service "api" {
         assert_eq!(resolved.env["DB_URL"], "postgres://localhost:5432/app");
     }
 
+    #[test]
+    fn test_resolve_service_ports_exports_port_vars() {
+        let mut ports = HashMap::new();
+        ports.insert("db".to_string(), 5432u16);
+        ports.insert("auth-api".to_string(), 4100u16);
+        let mut svc = make_svc(3000, vec![]);
+        svc.env
+            .insert("A3S_SERVICE_DB_PORT".into(), "override".into());
+        let resolved = resolve_service_ports(svc, &ports);
+        assert_eq!(resolved.env["A3S_SERVICE_AUTH_API_PORT"], "4100");
+        assert_eq!(resolved.env["A3S_SERVICE_DB_PORT"], "override");
+    }
+
+    #[test]
+    fn test_service_ref_templates() {
+        assert_eq!(
+            service_refs("http://localhost:{{service.api.port}}/{{ service.web.subdomain_url }}"),
+            [("api", "port"), ("web", "subdomain_url")]
+        );
+        assert!(service_refs("{{ .Values.x }} {{service.}} {{service.api.port").is_empty());
+
+        let mut ports = HashMap::new();
+        ports.insert("api".to_string(), 4100u16);
+        assert_eq!(
+            interpolate_service_ports(
+                "http://localhost:{{service.api.port}} {{service.db.port}} {{service.api.host}}",
+                &ports
+            ),
+            "http://localhost:4100 {{service.db.port}} {{service.api.host}}"
+        );
+    }
+
+    #[test]
+    fn test_service_refs_resolve_urls_and_add_ordering() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            r#"
+dev { proxy_port = 7080 }
+service "api" {
+  cmd       = "api"
+  port      = 0
+  subdomain = "api"
+}
+service "web" {
+  cmd = "web"
+  env = {
+    API_URL    = "http://localhost:{{service.api.port}}"
+    PUBLIC_API = "{{service.api.subdomain_url}}/v1"
+  }
+}
+"#,
+        )
+        .unwrap();
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        let web = &cfg.service["web"];
+        assert_eq!(web.depends_on, ["api"]);
+        assert_eq!(web.env["PUBLIC_API"], "http://api.localhost:7080/v1");
+        assert_eq!(web.env["API_URL"], "http://localhost:{{service.api.port}}");
+
+        let mut ports = HashMap::new();
+        ports.insert("api".to_string(), 41234u16);
+        let resolved = resolve_service_ports(web.clone(), &ports);
+        assert_eq!(resolved.env["API_URL"], "http://localhost:41234");
+    }
+
+    #[test]
+    fn test_service_refs_must_name_a_known_service_and_attribute() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        let check = |env: &str| {
+            std::fs::write(
+                &hcl_path,
+                format!(
+                    "service \"api\" {{\n  cmd = \"api\"\n}}\nservice \"web\" {{\n  cmd = \"web\"\n  env = {{ URL = \"{env}\" }}\n}}\n"
+                ),
+            )
+            .unwrap();
+            DevConfig::from_file(&hcl_path).unwrap_err().to_string()
+        };
+        let err = check("{{service.apii.port}}");
+        assert!(
+            err.contains("service 'web': env.URL references unknown service 'apii'"),
+            "{err}"
+        );
+        let err = check("{{service.api.subdomain_url}}");
+        assert!(err.contains("'api' has no subdomain"), "{err}");
+        let err = check("{{service.api.host}}");
+        assert!(err.contains("use port or subdomain_url"), "{err}");
+    }
+
+    #[test]
+    fn test_k8s_kubeconfig_is_relative_to_the_a3sfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            "dev {\n  k8s_context    = \"staging\"\n  k8s_kubeconfig = \"kube/staging.yaml\"\n}\n",
+        )
+        .unwrap();
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        let kubeconfig = dir.path().join("kube/staging.yaml");
+        assert_eq!(
+            cfg.dev.k8s_kubeconfig.as_deref(),
+            Some(kubeconfig.as_path())
+        );
+        assert_eq!(
+            cfg.dev.kubectl_args(),
+            [
+                "--kubeconfig".into(),
+                kubeconfig.into_os_string(),
+                "--context".into(),
+                "staging".into()
+            ]
+        );
+        assert!(GlobalSettings::default().kubectl_args().is_empty());
+    }
+
     #[test]
     fn test_log_rotate_mb_default_zero() {
         let src = r#"service "api" { cmd = "echo" }"#;
@@ -1059,6 +3006,56 @@ service "api" {
         let _ = src;
     }
 
+    #[test]
+    fn test_log_max_size_and_keep() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            "service \"api\" {\n  cmd = \"echo\"\n  log_file = \"logs/new/api.log\"\n  log_max_size = \"10mb\"\n  log_keep = 3\n}\n",
+        )
+        .unwrap();
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        assert_eq!(cfg.service["api"].log_max_size, Some(10 << 20));
+        assert_eq!(cfg.service["api"].log_keep, 3);
+        // Checking the path must not create it.
+        assert!(!dir.path().join("logs").exists());
+
+        std::fs::write(
+            &hcl_path,
+            "service \"api\" {\n  cmd = \"echo\"\n  log_file = \"api.log\"\n  log_keep = 0\n}\n",
+        )
+        .unwrap();
+        let err = DevConfig::from_file(&hcl_path).unwrap_err();
+        assert!(err.to_string().contains("log_keep"), "{err}");
+    }
+
+    #[test]
+    fn test_unwritable_log_file_fails_at_load() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("logs"), "a file, not a directory").unwrap();
+        std::fs::create_dir(dir.path().join("api.log")).unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        for (log_file, expected) in [
+            ("logs/api.log", "is not a directory"),
+            ("api.log", "is a directory"),
+        ] {
+            std::fs::write(
+                &hcl_path,
+                format!("service \"api\" {{\n  cmd = \"echo\"\n  log_file = \"{log_file}\"\n}}\n"),
+            )
+            .unwrap();
+            let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+            assert!(err.contains("service 'api': log_file"), "{err}");
+            assert!(err.contains(expected), "{err}");
+            let diags = DevConfig::diagnostics(&hcl_path);
+            assert!(
+                diags.iter().any(|d| d.message.contains(expected)),
+                "{log_file}"
+            );
+        }
+    }
+
     // ── Labels ─────────────────────────────────────────────────────────────
 
     #[test]
@@ -1080,6 +3077,24 @@ service "api" {
         assert!(cfg.service["api"].labels.is_empty());
     }
 
+    #[test]
+    fn test_depends_on_autostart_false_service_is_allowed() {
+        let src = r#"
+service "db" {
+  cmd       = "echo"
+  autostart = false
+}
+service "api" {
+  cmd        = "echo"
+  depends_on = ["db"]
+}
+"#;
+        let cfg: DevConfig = hcl::from_str(src).unwrap();
+        assert!(!cfg.service["db"].autostart);
+        assert!(cfg.service["api"].autostart);
+        cfg.validate().unwrap();
+    }
+
     // ── env() function expansion ───────────────────────────────────────────
 
     #[test]
@@ -1134,6 +3149,153 @@ service "api" {
         std::env::remove_var("_A3S_EF_QUOTE");
     }
 
+    // ── Resource limits ───────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("512b"), Ok(512));
+        assert_eq!(parse_size("64kb"), Ok(64 * 1024));
+        assert_eq!(parse_size("512mb"), Ok(512 * 1024 * 1024));
+        assert_eq!(parse_size("2gb"), Ok(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("2g"), Ok(2 * 1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_parse_size_case_and_whitespace() {
+        assert_eq!(parse_size(" 2GB "), Ok(2 << 30));
+        assert_eq!(parse_size("16 MB"), Ok(16 << 20));
+    }
+
+    #[test]
+    fn test_parse_size_fractional() {
+        assert_eq!(parse_size("1.5gb"), Ok(3 << 29));
+        assert_eq!(parse_size("0.5kb"), Ok(512));
+    }
+
+    #[test]
+    fn test_parse_size_invalid() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("gb").is_err());
+        assert!(parse_size("12xb").is_err());
+        assert!(parse_size("1.2.3mb").is_err());
+        assert!(parse_size("0").is_err());
+    }
+
+    #[test]
+    fn test_limits_block_parsed() {
+        let src = r#"
+service "webpack" {
+  cmd = "webpack --watch"
+  limits {
+    memory = "2gb"
+    cpu    = 1.5
+  }
+}
+"#;
+        let cfg: DevConfig = hcl::from_str(src).unwrap();
+        let limits = cfg.service["webpack"].limits.clone().unwrap();
+        assert_eq!(limits.memory, Some(2 << 30));
+        assert_eq!(limits.cpu, Some(1.5));
+    }
+
+    #[test]
+    fn test_limits_bad_memory_is_parse_error() {
+        let src = r#"
+service "api" {
+  cmd    = "echo"
+  limits { memory = "lots" }
+}
+"#;
+        assert!(hcl::from_str::<DevConfig>(src).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_non_positive_cpu() {
+        let mut svc = make_svc(3000, vec![]);
+        svc.limits = Some(LimitsConfig {
+            memory: None,
+            cpu: Some(0.0),
+        });
+        let cfg = make_config(vec![("a", svc)]);
+        assert!(matches!(cfg.validate(), Err(DevError::Config(_))));
+    }
+
+    // ── Durations ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_duration_units() {
+        use std::time::Duration;
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("3s"), Ok(Duration::from_secs(3)));
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_duration("1d").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    // ── share ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_share_block_parsed() {
+        let src = r#"
+dev {
+  share {
+    host       = "tunnel.example.com"
+    user       = "dev"
+    port_range = [21000, 21010]
+  }
+}
+"#;
+        let cfg: DevConfig = hcl::from_str(src).unwrap();
+        let share = cfg.dev.share.unwrap();
+        assert_eq!(share.provider, "ssh");
+        assert_eq!(share.host, "tunnel.example.com");
+        assert_eq!(share.user.as_deref(), Some("dev"));
+        assert_eq!(share.port_range, [21000, 21010]);
+    }
+
+    #[test]
+    fn test_share_defaults_to_none() {
+        let cfg: DevConfig = hcl::from_str("").unwrap();
+        assert!(cfg.dev.share.is_none());
+    }
+
+    // ── schema ────────────────────────────────────────────────────────────
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn test_json_schema_shape() {
+        let schema = serde_json::to_value(json_schema()).unwrap();
+        let defs = &schema["$defs"];
+        assert_eq!(defs["ServiceDef"]["required"], serde_json::json!(["cmd"]));
+        assert_eq!(
+            defs["HealthKind"]["enum"],
+            serde_json::json!(["http", "tcp"])
+        );
+        assert_eq!(
+            defs["HealthConfig"]["required"],
+            serde_json::json!(["type"])
+        );
+        let stop = &defs["ServiceDef"]["properties"]["stop_timeout"];
+        assert_eq!(stop["type"], "string");
+        assert_eq!(stop["default"], "5s");
+        assert!(stop["pattern"].is_string());
+        let memory = &defs["LimitsConfig"]["properties"]["memory"];
+        assert_eq!(memory["type"], serde_json::json!(["string", "null"]));
+    }
+
+    // ── progress ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_progress_style_parsed() {
+        let cfg: DevConfig = hcl::from_str("dev {\n  progress = \"plain\"\n}\n").unwrap();
+        assert_eq!(cfg.dev.progress, Some(ProgressStyle::Plain));
+        let cfg: DevConfig = hcl::from_str("").unwrap();
+        assert_eq!(cfg.dev.progress, None);
+        assert!(hcl::from_str::<DevConfig>("dev {\n  progress = \"loud\"\n}\n").is_err());
+    }
+
     // ── env_override ──────────────────────────────────────────────────────
 
     #[test]
@@ -1186,4 +3348,863 @@ env_override "staging" {
             Some("localhost")
         );
     }
+
+    // ── environment ───────────────────────────────────────────────────────
+
+    const ENVIRONMENTS: &str = r#"
+service "web" {
+  cmd  = "npm run dev"
+  port = 3000
+  env  = { MODE = "dev", LOG = "debug" }
+}
+
+service "worker" {
+  cmd = "npm run worker"
+}
+
+environment "ci" {
+  service "web" {
+    cmd  = "npm start"
+    port = 0
+    env  = { MODE = "ci" }
+  }
+  service "worker" {
+    disabled = true
+  }
+}
+
+env_override "staging" {
+  service "web" {
+    env = { MODE = "staging" }
+  }
+}
+"#;
+
+    #[test]
+    fn test_environment_deep_merges_over_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(&hcl_path, ENVIRONMENTS).unwrap();
+        let cfg = DevConfig::from_file_with_env(&hcl_path, Some("ci")).unwrap();
+        let web = &cfg.service["web"];
+        assert_eq!(web.cmd, "npm start");
+        assert_eq!(web.port, 0);
+        assert_eq!(web.env["MODE"], "ci");
+        assert_eq!(web.env["LOG"], "debug");
+        assert!(cfg.service["worker"].disabled);
+
+        let base = DevConfig::from_file_with_env(&hcl_path, None).unwrap();
+        assert_eq!(base.service["web"].port, 3000);
+        assert!(!base.service["worker"].disabled);
+    }
+
+    #[test]
+    fn test_environment_unknown_name_lists_defined_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(&hcl_path, ENVIRONMENTS).unwrap();
+        let err = DevConfig::from_file_with_env(&hcl_path, Some("prod"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("unknown environment 'prod' — defined: ci, staging"),
+            "{err}"
+        );
+        // env_override names are environments too.
+        let cfg = DevConfig::from_file_with_env(&hcl_path, Some("staging")).unwrap();
+        assert_eq!(cfg.service["web"].env["MODE"], "staging");
+    }
+
+    #[test]
+    fn test_environment_is_validated_after_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            r#"
+service "a" {
+  cmd  = "a"
+  port = 4000
+}
+service "b" {
+  cmd = "b"
+}
+environment "clash" {
+  service "b" {
+    port = 4000
+  }
+}
+environment "extra" {
+  service "c" {
+    port = 4001
+  }
+}
+"#,
+        )
+        .unwrap();
+        assert!(DevConfig::from_file_with_env(&hcl_path, Some("clash")).is_err());
+        let err = DevConfig::from_file_with_env(&hcl_path, Some("extra"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("environment 'extra': service 'c'"), "{err}");
+    }
+
+    // ── include ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_include_merges_services_from_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("services")).unwrap();
+        std::fs::write(
+            dir.path().join("services/api.hcl"),
+            "service \"api\" {\n  cmd = \"node api.js\"\n  depends_on = [\"db\"]\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("services/db.hcl"),
+            "service \"db\" {\n  cmd = \"postgres\"\n}\n",
+        )
+        .unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            "include = [\"services/*.hcl\"]\n\nservice \"web\" {\n  cmd = \"vite\"\n}\n",
+        )
+        .unwrap();
+
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        let names: Vec<&str> = cfg.service.keys().map(|s| s.as_str()).collect();
+        assert_eq!(names, vec!["api", "db", "web"]);
+        assert_eq!(cfg.service["api"].depends_on, vec!["db"]);
+    }
+
+    #[test]
+    fn test_include_later_file_wins_on_collision() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("a.hcl"),
+            "service \"api\" {\n  cmd = \"from-a\"\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("b.hcl"),
+            "service \"api\" {\n  cmd = \"from-b\"\n}\n",
+        )
+        .unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(&hcl_path, "include = [\"a.hcl\", \"b.hcl\"]\n").unwrap();
+
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        assert_eq!(cfg.service.len(), 1);
+        assert_eq!(cfg.service["api"].cmd, "from-b");
+    }
+
+    #[test]
+    fn test_include_validates_merged_result() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("api.hcl"),
+            "service \"api\" {\n  cmd = \"x\"\n  depends_on = [\"missing\"]\n}\n",
+        )
+        .unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(&hcl_path, "include = [\"api.hcl\"]\n").unwrap();
+        assert!(DevConfig::from_file(&hcl_path).is_err());
+    }
+
+    #[test]
+    fn test_include_cycle_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.hcl"), "include = [\"b.hcl\"]\n").unwrap();
+        std::fs::write(dir.path().join("b.hcl"), "include = [\"a.hcl\"]\n").unwrap();
+        let err = DevConfig::from_file(&dir.path().join("a.hcl"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("include cycle"), "{err}");
+        assert!(err.contains("a.hcl") && err.contains("b.hcl"), "{err}");
+    }
+
+    #[test]
+    fn test_include_missing_literal_file_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(&hcl_path, "include = [\"nope.hcl\"]\n").unwrap();
+        let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+        assert!(err.contains("nope.hcl"), "{err}");
+        // A glob that matches nothing is fine.
+        std::fs::write(&hcl_path, "include = [\"extra/*.hcl\"]\n").unwrap();
+        assert!(DevConfig::from_file(&hcl_path).is_ok());
+    }
+
+    // ── watch path safety ─────────────────────────────────────────────────
+
+    fn write_watch_config(dir: &std::path::Path, watch: &str) -> PathBuf {
+        let hcl_path = dir.join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            format!("service \"web\" {{\n  cmd = \"x\"\n  watch {{\n{watch}\n  }}\n}}\n"),
+        )
+        .unwrap();
+        hcl_path
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(std::path::Path::new("/a/b/../c/./d")),
+            PathBuf::from("/a/c/d")
+        );
+        assert_eq!(
+            normalize_path(std::path::Path::new("/a/../../..")),
+            PathBuf::from("/")
+        );
+    }
+
+    #[test]
+    fn test_watch_paths_resolved_relative_to_service_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("api/src")).unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            "service \"api\" {\n  cmd = \"x\"\n  dir = \"api\"\n  watch {\n    paths = [\"./src\"]\n  }\n}\n",
+        )
+        .unwrap();
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        assert_eq!(
+            cfg.service["api"].watch.as_ref().unwrap().paths,
+            vec![root.join("api/src")]
+        );
+    }
+
+    #[test]
+    fn test_watch_debounce_default_and_override() {
+        let parse = |watch: &str| -> Duration {
+            let src = format!("service \"api\" {{\n  cmd = \"x\"\n  watch {{\n{watch}\n  }}\n}}\n");
+            let cfg: DevConfig = hcl::from_str(&src).unwrap();
+            cfg.service["api"].watch.as_ref().unwrap().debounce
+        };
+        assert_eq!(parse("paths = [\"src\"]"), Duration::from_millis(300));
+        assert_eq!(
+            parse("paths = [\"src\"]\n    debounce = \"1s\""),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn test_watch_path_outside_project_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        for paths in ["[\"/\"]", "[\"../..\"]", "[\"src/../../elsewhere\"]"] {
+            let hcl_path = write_watch_config(dir.path(), &format!("    paths = {paths}"));
+            let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+            assert!(err.contains("outside the project"), "{paths}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_watch_path_outside_project_allowed_with_flag() {
+        let project = tempfile::tempdir().unwrap();
+        let shared = tempfile::tempdir().unwrap();
+        let hcl_path = write_watch_config(
+            project.path(),
+            &format!(
+                "    paths = [\"{}\"]\n    allow_outside_project = true",
+                shared.path().display()
+            ),
+        );
+        assert!(DevConfig::from_file(&hcl_path).is_ok());
+    }
+
+    #[test]
+    fn test_count_watch_dirs_respects_ignore_and_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        for d in ["src/a", "src/b", "node_modules/x/y", "node_modules/z"] {
+            std::fs::create_dir_all(dir.path().join(d)).unwrap();
+        }
+        let paths = vec![dir.path().to_path_buf()];
+        // root, src, src/a, src/b, node_modules, node_modules/x, node_modules/x/y, node_modules/z
+        assert_eq!(count_watch_dirs(&paths, &[], false, 100), Ok(8));
+        assert_eq!(
+            count_watch_dirs(&paths, &["node_modules".into()], false, 100),
+            Ok(4)
+        );
+        // Counting stops just past the ceiling.
+        assert_eq!(count_watch_dirs(&paths, &[], false, 3), Ok(4));
+    }
+
+    #[test]
+    fn test_watch_dir_cap_refused_with_count() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..5 {
+            std::fs::create_dir_all(dir.path().join(format!("src/d{i}"))).unwrap();
+        }
+        let hcl_path = write_watch_config(dir.path(), "    paths = [\"src\"]\n    max_dirs = 3");
+        let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+        assert!(err.contains("cover 6 directories (limit 3)"), "{err}");
+
+        let hcl_path = write_watch_config(dir.path(), "    paths = [\"src\"]\n    max_dirs = 6");
+        assert!(DevConfig::from_file(&hcl_path).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_watch_symlink_loop_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("src"), dir.path().join("src/nested/loop"))
+            .unwrap();
+        let err = count_watch_dirs(&[dir.path().join("src")], &[], false, 100).unwrap_err();
+        assert!(err.contains("symlink loop"), "{err}");
+    }
+
+    // ── diagnostics ───────────────────────────────────────────────────────
+
+    #[test]
+    fn test_diagnostics_reports_every_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            r#"
+service "a" {
+  cmd  = "x"
+  port = 3000
+}
+service "b" {
+  cmd  = "x"
+  port = 3000
+}
+service "c" {
+  cmd  = "x"
+  port = 3000
+  depends_on = ["ghost"]
+  env_file   = "missing.env"
+}
+service "d" {
+  cmd = "x"
+  depends_on = ["phantom"]
+  watch {
+    paths = ["./nope"]
+  }
+}
+"#,
+        )
+        .unwrap();
+
+        let diags = DevConfig::diagnostics(&hcl_path);
+        let errors: Vec<_> = diags
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect();
+        // two port conflicts, two unknown deps, one missing env_file
+        assert_eq!(errors.len(), 5, "{diags:#?}");
+        assert_eq!(
+            errors
+                .iter()
+                .filter(|d| d.message.contains("port 3000"))
+                .count(),
+            2
+        );
+        assert!(errors
+            .iter()
+            .any(|d| d.service.as_deref() == Some("c") && d.message.contains("missing.env")));
+        assert!(errors.iter().any(|d| d.message.contains("'phantom'")));
+
+        let warnings: Vec<_> = diags
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].service.as_deref(), Some("d"));
+        assert!(warnings[0].message.contains("nope"));
+    }
+
+    #[test]
+    fn test_diagnostics_reports_cycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            "service \"a\" {\n  cmd = \"x\"\n  depends_on = [\"b\"]\n}\nservice \"b\" {\n  cmd = \"x\"\n  depends_on = [\"a\"]\n}\n",
+        )
+        .unwrap();
+        let diags = DevConfig::diagnostics(&hcl_path);
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.contains("cycle"), "{diags:?}");
+        assert_eq!(diags[0].service, None);
+    }
+
+    #[test]
+    fn test_diagnostics_clean_config_and_parse_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(&hcl_path, "service \"a\" {\n  cmd = \"x\"\n}\n").unwrap();
+        assert!(DevConfig::diagnostics(&hcl_path).is_empty());
+
+        std::fs::write(&hcl_path, "service \"a\" {\n").unwrap();
+        let diags = DevConfig::diagnostics(&hcl_path);
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.contains("parse error"));
+    }
+
+    #[test]
+    fn test_diagnostic_json_shape() {
+        let d = Diagnostic {
+            severity: Severity::Warning,
+            service: Some("api".into()),
+            message: "m".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(&d).unwrap(),
+            serde_json::json!({"severity": "warning", "service": "api", "message": "m"})
+        );
+    }
+
+    // ── lints ─────────────────────────────────────────────────────────────
+
+    fn lint_project(hcl: &str) -> Vec<Lint> {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("api.env"),
+            "DB_URL=postgres://x\nLOG=info\n",
+        )
+        .unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(&hcl_path, hcl).unwrap();
+        DevConfig::lints(&hcl_path)
+    }
+
+    const LINTY: &str = r#"
+service "api" {
+  cmd       = "node api.js"
+  subdomain = "api"
+  env_file  = "api.env"
+  env = {
+    LOG          = "debug"
+    STRIPE_TOKEN = "sk_live_abc123"
+  }
+  watch {
+    paths = ["./nope"]
+  }
+}
+service "db" {
+  cmd  = "postgres"
+  port = 5432
+  health {
+    type = "tcp"
+    path = "/health"
+  }
+}
+"#;
+
+    #[test]
+    fn test_lints_report_each_code() {
+        let lints = lint_project(LINTY);
+        let found: Vec<(&str, &str, &str)> = lints
+            .iter()
+            .map(|l| (l.service.as_str(), l.field.as_str(), l.code))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("api", "watch.paths", "missing-watch-path"),
+                ("api", "env.LOG", "env-shadowed"),
+                ("api", "subdomain", "auto-port-subdomain"),
+                ("api", "env.STRIPE_TOKEN", "inline-secret"),
+                ("db", "health.path", "tcp-health-path"),
+            ],
+            "{lints:#?}"
+        );
+    }
+
+    #[test]
+    fn test_lints_suppressed_per_service() {
+        let hcl = LINTY.replace(
+            "  subdomain = \"api\"\n",
+            "  subdomain = \"api\"\n  lint_ignore = [\"inline-secret\", \"auto-port-subdomain\"]\n",
+        );
+        let codes: Vec<&str> = lint_project(&hcl).iter().map(|l| l.code).collect();
+        assert_eq!(
+            codes,
+            vec!["missing-watch-path", "env-shadowed", "tcp-health-path"]
+        );
+    }
+
+    #[test]
+    fn test_lints_skip_safe_patterns() {
+        std::env::set_var("A3S_LINT_TEST_TOKEN", "from-the-environment");
+        let lints = lint_project(
+            r#"
+service "web" {
+  cmd       = "vite"
+  subdomain = "web"
+  env = {
+    API_TOKEN   = env("A3S_LINT_TEST_TOKEN")
+    DB_PASSWORD = "${DB_PASSWORD}"
+  }
+}
+service "off" {
+  cmd      = "x"
+  disabled = true
+  env = {
+    SECRET = "hunter2"
+  }
+}
+"#,
+        );
+        assert!(lints.is_empty(), "{lints:#?}");
+    }
+
+    #[test]
+    fn test_lints_do_not_fail_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl_path,
+            "service \"db\" {\n  cmd = \"x\"\n  health {\n    type = \"tcp\"\n    path = \"/\"\n  }\n}\n",
+        )
+        .unwrap();
+        assert!(DevConfig::from_file(&hcl_path).is_ok());
+        let diags = DevConfig::diagnostics(&hcl_path);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, Severity::Warning);
+        assert!(diags[0].message.contains("[tcp-health-path]"), "{diags:?}");
+    }
+
+    // ── standby ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_standby_requires_auto_port_and_health() {
+        let mut svc = make_svc(3000, vec![]);
+        svc.standby = true;
+        let cfg = make_config(vec![("ml", svc)]);
+        let errs: Vec<String> = cfg
+            .problems()
+            .into_iter()
+            .map(|(_, e)| e.to_string())
+            .collect();
+        assert_eq!(errs.len(), 2, "{errs:?}");
+        assert!(errs[0].contains("port = 0"));
+        assert!(errs[1].contains("health check"));
+    }
+
+    #[test]
+    fn test_check_service_names_lists_valid_ones() {
+        let cfg = make_config(vec![
+            ("api", make_svc(3000, vec![])),
+            ("db", make_svc(5432, vec![])),
+        ]);
+        assert!(cfg
+            .check_service_names(&["api".into(), "db".into()])
+            .is_ok());
+        let msg = cfg
+            .check_service_names(&["api".into(), "apii".into()])
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("unknown service 'apii' (services: "), "{msg}");
+        assert!(msg.contains("api") && msg.contains("db"), "{msg}");
+        let msg = cfg
+            .check_service_names(&["x".into(), "y".into()])
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("unknown services 'x', 'y'"), "{msg}");
+    }
+
+    #[test]
+    fn test_standby_parsed_and_valid() {
+        let hcl = r#"
+service "ml" {
+  cmd     = "python serve.py"
+  standby = true
+  health {
+    type = "tcp"
+  }
+}
+"#;
+        let cfg: DevConfig = hcl::from_str(hcl).unwrap();
+        assert!(cfg.service["ml"].standby);
+        assert!(cfg.validate().is_ok());
+    }
+
+    // ── local overlay ─────────────────────────────────────────────────────
+
+    fn write_project(base: &str, overlay: Option<&str>) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl_path = dir.path().join("A3sfile.hcl");
+        std::fs::write(&hcl_path, base).unwrap();
+        if let Some(overlay) = overlay {
+            std::fs::write(dir.path().join("A3sfile.local.hcl"), overlay).unwrap();
+        }
+        (dir, hcl_path)
+    }
+
+    const OVERLAY_BASE: &str = r#"
+service "db" {
+  cmd  = "postgres"
+  port = 5432
+}
+service "api" {
+  cmd        = "node api.js"
+  port       = 3000
+  depends_on = ["db", "search"]
+  env = {
+    LOG_LEVEL = "info"
+    REGION    = "eu"
+  }
+  health {
+    type     = "http"
+    path     = "/health"
+    interval = "5s"
+  }
+}
+service "search" {
+  cmd = "elasticsearch"
+}
+"#;
+
+    #[test]
+    fn test_local_overlay_deep_merges_services() {
+        let overlay = r#"
+service "api" {
+  port       = 4000
+  depends_on = ["db"]
+  env = {
+    LOG_LEVEL = "debug"
+  }
+  health {
+    interval = "1s"
+  }
+}
+service "search" {
+  disabled = true
+}
+service "scratch" {
+  cmd = "./try.sh"
+}
+"#;
+        let (_dir, hcl_path) = write_project(OVERLAY_BASE, Some(overlay));
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+
+        let api = &cfg.service["api"];
+        assert_eq!(api.cmd, "node api.js");
+        assert_eq!(api.port, 4000);
+        assert_eq!(api.depends_on, vec!["db"], "lists are replaced");
+        assert_eq!(api.env["LOG_LEVEL"], "debug");
+        assert_eq!(api.env["REGION"], "eu", "maps are unioned");
+        let health = api.health.as_ref().unwrap();
+        assert_eq!(health.kind, HealthKind::Http);
+        assert_eq!(health.path.as_deref(), Some("/health"));
+        assert_eq!(health.interval, Duration::from_secs(1));
+
+        assert!(cfg.service["search"].disabled);
+        assert_eq!(cfg.service["db"].port, 5432);
+        let names: Vec<&str> = cfg.service.keys().map(|s| s.as_str()).collect();
+        assert_eq!(names, vec!["db", "api", "search", "scratch"]);
+    }
+
+    #[test]
+    fn test_local_overlay_records_sources() {
+        let overlay =
+            "service \"api\" {\n  port = 4000\n}\nservice \"scratch\" {\n  cmd = \"x\"\n}\n";
+        let (dir, hcl_path) = write_project(OVERLAY_BASE, Some(overlay));
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        let local = dir.path().join("A3sfile.local.hcl");
+        assert_eq!(cfg.sources["db"], vec![hcl_path.clone()]);
+        assert_eq!(cfg.sources["api"], vec![hcl_path.clone(), local.clone()]);
+        assert_eq!(cfg.sources["scratch"], vec![local]);
+    }
+
+    #[test]
+    fn test_local_overlay_validated_after_merge() {
+        // The overlay moves api onto db's port.
+        let (_dir, hcl_path) =
+            write_project(OVERLAY_BASE, Some("service \"api\" {\n  port = 5432\n}\n"));
+        let err = DevConfig::from_file(&hcl_path).unwrap_err();
+        assert!(
+            matches!(err, DevError::PortConflict { port: 5432, .. }),
+            "{err}"
+        );
+
+        // Disabling a service others depend on is caught too.
+        let (_dir, hcl_path) = write_project(
+            OVERLAY_BASE,
+            Some("service \"db\" {\n  disabled = true\n}\n"),
+        );
+        assert!(DevConfig::from_file(&hcl_path).is_err());
+    }
+
+    #[test]
+    fn test_local_overlay_new_service_needs_cmd() {
+        let (_dir, hcl_path) = write_project(
+            OVERLAY_BASE,
+            Some("service \"extra\" {\n  port = 9000\n}\n"),
+        );
+        let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+        assert!(
+            err.contains("A3sfile.local.hcl") && err.contains("extra"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_are_rejected_with_position_and_suggestion() {
+        let (_dir, hcl_path) = write_project(
+            "service \"api\" {\n  cmd = \"node\"\n  watch {\n    path = [\"src\"]\n  }\n}\n",
+            None,
+        );
+        let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+        assert!(
+            err.contains("A3sfile.hcl:4:5: unknown field `path`"),
+            "{err}"
+        );
+        assert!(err.contains("did you mean `paths`?"), "{err}");
+
+        let (_dir, hcl_path) =
+            write_project(OVERLAY_BASE, Some("service \"api\" {\n  prot = 4000\n}\n"));
+        let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+        assert!(err.contains("A3sfile.local.hcl:2:3"), "{err}");
+        assert!(err.contains("did you mean `port`?"), "{err}");
+
+        let (_dir, hcl_path) =
+            write_project(OVERLAY_BASE, Some("servce \"api\" {\n  port = 4000\n}\n"));
+        let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+        assert!(err.contains("A3sfile.local.hcl:1:1"), "{err}");
+        assert!(err.contains("did you mean `service`?"), "{err}");
+    }
+
+    #[test]
+    fn test_local_overlay_dev_block_and_absent_overlay() {
+        let (_dir, hcl_path) = write_project(OVERLAY_BASE, Some("dev {\n  proxy_port = 9080\n}\n"));
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        assert_eq!(cfg.dev.proxy_port, 9080);
+        assert_eq!(cfg.dev.log_level, "info");
+
+        let (_dir, hcl_path) = write_project(OVERLAY_BASE, None);
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        assert_eq!(cfg.dev.proxy_port, 7080);
+        assert_eq!(cfg.service["api"].port, 3000);
+    }
+
+    #[test]
+    fn test_local_overlay_path_follows_config_name() {
+        assert_eq!(
+            local_overlay_path(std::path::Path::new("/p/A3sfile.hcl")),
+            PathBuf::from("/p/A3sfile.local.hcl")
+        );
+        assert_eq!(
+            local_overlay_path(std::path::Path::new("/p/staging.hcl")),
+            PathBuf::from("/p/staging.local.hcl")
+        );
+    }
+
+    // ── flaky threshold ───────────────────────────────────────────────────
+
+    #[test]
+    fn test_flaky_threshold_default_and_range() {
+        let cfg: DevConfig = hcl::from_str("").unwrap();
+        assert_eq!(cfg.dev.flaky_threshold, 95.0);
+
+        let cfg: DevConfig = hcl::from_str("dev {\n  flaky_threshold = 99.5\n}").unwrap();
+        assert_eq!(cfg.dev.flaky_threshold, 99.5);
+        assert!(cfg.validate().is_ok());
+
+        let cfg: DevConfig = hcl::from_str("dev {\n  flaky_threshold = 150\n}").unwrap();
+        let err = cfg.validate().unwrap_err().to_string();
+        assert!(err.contains("flaky_threshold"), "{err}");
+    }
+
+    #[test]
+    fn test_ui_bind_default_and_validation() {
+        let cfg: DevConfig = hcl::from_str("").unwrap();
+        assert_eq!(cfg.dev.ui_bind, "127.0.0.1");
+
+        let cfg: DevConfig = hcl::from_str("dev {\n  ui_bind = \"::\"\n}").unwrap();
+        assert!(cfg.validate().is_ok());
+
+        let cfg: DevConfig = hcl::from_str("dev {\n  ui_bind = \"localhost\"\n}").unwrap();
+        let err = cfg.validate().unwrap_err().to_string();
+        assert!(err.contains("dev.ui_bind"), "{err}");
+    }
+
+    const CONTAINER_PROJECT: &str = r#"
+container "db" {
+  image   = "postgres:16"
+  ports   = ["5432:5432"]
+  volumes = ["./initdb:/docker-entrypoint-initdb.d:ro", "pgdata:/var/lib/postgresql/data"]
+  env = {
+    POSTGRES_PASSWORD = "dev"
+  }
+}
+service "api" {
+  cmd        = "node api.js"
+  port       = 3000
+  depends_on = ["db"]
+}
+"#;
+
+    #[test]
+    fn test_container_blocks_become_services() {
+        let (dir, hcl_path) = write_project(CONTAINER_PROJECT, None);
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        assert_eq!(
+            cfg.service.keys().collect::<Vec<_>>(),
+            ["api", "db"],
+            "containers are added after services"
+        );
+        let db = &cfg.service["db"];
+        assert!(
+            db.cmd.starts_with("a3s-box run --rm --name a3s-db"),
+            "{}",
+            db.cmd
+        );
+        assert_eq!(db.port, 5432);
+        assert_eq!(db.health.as_ref().unwrap().kind, HealthKind::Tcp);
+        assert_eq!(cfg.sources["db"], vec![hcl_path.clone()]);
+
+        let bind = format!(
+            "{}:/docker-entrypoint-initdb.d:ro",
+            dir.path().join("./initdb").display()
+        );
+        assert!(cfg.container["db"].volumes.contains(&bind));
+        assert!(cfg.container["db"]
+            .volumes
+            .contains(&"pgdata:/var/lib/postgresql/data".to_string()));
+
+        let graph = crate::graph::DependencyGraph::from_config(&cfg).unwrap();
+        assert_eq!(graph.start_order(), ["db", "api"]);
+    }
+
+    #[test]
+    fn test_container_and_service_names_must_differ() {
+        let base = format!("{CONTAINER_PROJECT}\nservice \"db\" {{\n  cmd = \"postgres\"\n}}\n");
+        let (_dir, hcl_path) = write_project(&base, None);
+        let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+        assert!(
+            err.contains("both as a service and as a container"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_container_port_conflicts_with_service() {
+        let base = CONTAINER_PROJECT.replace("port       = 3000", "port       = 5432");
+        let (_dir, hcl_path) = write_project(&base, None);
+        let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+        assert!(err.contains("5432"), "{err}");
+    }
+
+    #[test]
+    fn test_container_overlay_and_bad_ports() {
+        let overlay = "container \"db\" {\n  ports = [\"5433:5432\"]\n}\n";
+        let (_dir, hcl_path) = write_project(CONTAINER_PROJECT, Some(overlay));
+        let cfg = DevConfig::from_file(&hcl_path).unwrap();
+        assert_eq!(cfg.service["db"].port, 5433);
+        assert_eq!(cfg.container["db"].image, "postgres:16");
+
+        let (_dir, hcl_path) =
+            write_project(&CONTAINER_PROJECT.replace("5432:5432", "pg:5432"), None);
+        let err = DevConfig::from_file(&hcl_path).unwrap_err().to_string();
+        assert!(err.contains("container 'db'"), "{err}");
+    }
 }
@@ -0,0 +1,306 @@
+//! The background supervisor `a3s up --detach` starts. It records its pid in
+//! `.a3s/daemon.pid` next to A3sfile.hcl and writes its output to `.a3s/daemon.log`,
+//! so `a3s down` can find it and a second `up` can tell it is there. File watchers
+//! never look inside `.a3s/`, so the daemon's own log lines don't restart services.
+//!
+//! The daemon holds an exclusive `flock` on the pid file for as long as it runs, and
+//! the kernel drops that lock when it exits however it exits. A pid file nobody
+//! holds the lock on is stale — whatever process now has that pid is not the daemon —
+//! and is removed.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// `.a3s/` in the directory of `config_path`.
+fn state_dir(config_path: &Path) -> PathBuf {
+    match config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join(".a3s"),
+        _ => PathBuf::from(".a3s"),
+    }
+}
+
+pub fn pid_path(config_path: &Path) -> PathBuf {
+    state_dir(config_path).join("daemon.pid")
+}
+
+/// The token TCP clients of `ipc_tcp_port` present in their hello.
+pub fn ipc_token_path(config_path: &Path) -> PathBuf {
+    state_dir(config_path).join("ipc-token")
+}
+
+/// Where the daemon's stdout and stderr go.
+pub fn log_path(config_path: &Path) -> PathBuf {
+    state_dir(config_path).join("daemon.log")
+}
+
+/// The daemon's log, opened for appending (and `.a3s/` created) for the child's
+/// stdout and stderr.
+pub fn open_log(config_path: &Path) -> std::io::Result<std::fs::File> {
+    let path = log_path(config_path);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+}
+
+/// The pid of the daemon running for this project: the one a live process holds
+/// the pid file's lock for. A pid file nobody holds is removed.
+pub fn running_pid(config_path: &Path) -> Option<u32> {
+    let path = pid_path(config_path);
+    let file = File::open(&path).ok()?;
+    match try_lock(&file) {
+        Ok(false) => std::fs::read_to_string(&path).ok()?.trim().parse().ok(),
+        Ok(true) => {
+            tracing::debug!("removing stale {}", path.display());
+            let _ = std::fs::remove_file(&path);
+            None
+        }
+        Err(e) => {
+            tracing::debug!("cannot lock {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// This process's entry in the pid file, locked until dropped and then removed.
+pub struct PidFile {
+    path: PathBuf,
+    _lock: File,
+}
+
+impl PidFile {
+    /// Record this process as the project's daemon. Fails while another daemon
+    /// holds the pid file.
+    pub fn create(config_path: &Path) -> crate::error::Result<PidFile> {
+        use std::io::Write;
+
+        let path = pid_path(config_path);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        loop {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .read(true)
+                .write(true)
+                .open(&path)?;
+            if !try_lock(&file)? {
+                let holder = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|s| s.trim().parse::<u32>().ok())
+                    .map_or(String::new(), |pid| format!(" (pid {pid})"));
+                return Err(crate::error::DevError::Config(format!(
+                    "a3s is already running for this project{holder}"
+                )));
+            }
+            // Whoever held the lock before may have removed the file in between, and
+            // a lock on an unlinked file guards nothing: open the new one and retry.
+            if !same_file(&file, &path) {
+                continue;
+            }
+            file.set_len(0)?;
+            writeln!(file, "{}", std::process::id())?;
+            return Ok(PidFile { path, _lock: file });
+        }
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Removed while still locked, so nobody takes the file for stale and
+        // removes a successor's.
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Take an exclusive lock on `file` without blocking. `Ok(false)` when another open
+/// file holds it.
+fn try_lock(file: &File) -> std::io::Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::fd::AsRawFd;
+        // SAFETY: flock only reads the descriptor, which `file` keeps open.
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+            return Ok(true);
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() == std::io::ErrorKind::WouldBlock {
+            Ok(false)
+        } else {
+            Err(err)
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = file;
+        Ok(true)
+    }
+}
+
+/// Whether `path` still names the file `file` has open.
+fn same_file(file: &File, path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (file.metadata(), std::fs::metadata(path)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (file, path);
+        true
+    }
+}
+
+/// SIGTERM the daemon — it shuts its services down in order — and wait up to
+/// `timeout` for it to exit. Returns whether it did. `pid` is what
+/// [`running_pid`] returned; exit is seen by the daemon releasing the pid file, not
+/// by the pid going away, since the pid may be reused.
+pub async fn terminate(config_path: &Path, pid: u32, timeout: Duration) -> bool {
+    let gone = || running_pid(config_path) != Some(pid);
+    if gone() {
+        return true;
+    }
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{kill, Signal};
+        use nix::unistd::Pid;
+        if kill(Pid::from_raw(pid as i32), Signal::SIGTERM).is_err() {
+            return gone();
+        }
+    }
+    let deadline = tokio::time::Instant::now() + timeout;
+    while !gone() {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_pid_file_is_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        let pid_file = pid_path(&hcl);
+        assert_eq!(pid_file, dir.path().join(".a3s").join("daemon.pid"));
+        assert_eq!(running_pid(&hcl), None);
+
+        // A crashed daemon's pid may belong to some other live process by now; the
+        // file isn't locked, so that process isn't taken for the daemon.
+        let mut other = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        std::fs::create_dir_all(pid_file.parent().unwrap()).unwrap();
+        std::fs::write(&pid_file, format!("{}\n", other.id())).unwrap();
+        assert_eq!(running_pid(&hcl), None);
+        assert!(!pid_file.exists());
+        other.kill().unwrap();
+        other.wait().unwrap();
+
+        std::fs::write(&pid_file, "garbage").unwrap();
+        assert_eq!(running_pid(&hcl), None);
+        assert!(!pid_file.exists());
+    }
+
+    #[test]
+    fn test_daemon_files_are_invisible_to_watchers() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        let ignore = crate::watcher::IgnoreRules::new(&[dir.path().to_path_buf()], &[], false);
+        assert!(ignore.is_ignored(&log_path(&hcl)));
+        assert!(ignore.is_ignored(&pid_path(&hcl)));
+    }
+
+    #[test]
+    fn test_pid_file_refuses_a_second_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        {
+            let _pid = PidFile::create(&hcl).unwrap();
+            assert_eq!(running_pid(&hcl), Some(std::process::id()));
+            assert!(pid_path(&hcl).exists(), "a held pid file isn't stale");
+
+            let err = PidFile::create(&hcl).err().unwrap();
+            assert!(
+                err.to_string()
+                    .contains(&format!("pid {}", std::process::id())),
+                "{err}"
+            );
+        }
+        assert!(!pid_path(&hcl).exists(), "removed on drop");
+        assert_eq!(running_pid(&hcl), None);
+
+        // A leftover file nobody holds doesn't stop the next daemon.
+        std::fs::write(pid_path(&hcl), "1\n").unwrap();
+        let _pid = PidFile::create(&hcl).unwrap();
+        assert_eq!(running_pid(&hcl), Some(std::process::id()));
+    }
+
+    #[tokio::test]
+    async fn test_terminate_leaves_processes_that_are_not_the_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        let mut child = tokio::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id().unwrap();
+        std::fs::create_dir_all(state_dir(&hcl)).unwrap();
+        std::fs::write(pid_path(&hcl), format!("{pid}\n")).unwrap();
+
+        // The file names the child but the child holds no lock: nothing to stop.
+        assert!(terminate(&hcl, pid, Duration::from_secs(5)).await);
+        assert!(child.try_wait().unwrap().is_none(), "signalled a stranger");
+        child.kill().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_terminate_waits_for_the_daemon_to_release_the_pid_file() {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::process::CommandExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        std::fs::create_dir_all(state_dir(&hcl)).unwrap();
+        let lock = File::create(pid_path(&hcl)).unwrap();
+        assert!(try_lock(&lock).unwrap());
+        // Hand the locked descriptor to a child standing in for the daemon.
+        let fd = lock.as_raw_fd();
+        let mut cmd = std::process::Command::new("sleep");
+        cmd.arg("30");
+        // SAFETY: fcntl is async-signal-safe; clearing FD_CLOEXEC keeps the
+        // descriptor open across exec.
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::fcntl(fd, libc::F_SETFD, 0) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let mut child = cmd.spawn().unwrap();
+        let pid = child.id();
+        std::fs::write(pid_path(&hcl), format!("{pid}\n")).unwrap();
+        drop(lock);
+        let reaper = tokio::task::spawn_blocking(move || child.wait());
+
+        assert_eq!(running_pid(&hcl), Some(pid));
+        assert!(terminate(&hcl, pid, Duration::from_secs(5)).await);
+        assert!(!reaper.await.unwrap().unwrap().success());
+        assert_eq!(running_pid(&hcl), None);
+    }
+}
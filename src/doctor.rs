@@ -0,0 +1,503 @@
+//! `a3s doctor`: is everything a3s shells out to installed, and can it create its
+//! IPC socket? Each failed check comes with the command that fixes it.
+//!
+//! Which tools are hard requirements depends on the A3sfile: `kubectl` for
+//! `runtime = "k8s"`, `a3s-box` for `container` blocks, each service's shell and
+//! program, and so on. The rest are listed as optional.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+
+use crate::config::DevConfig;
+
+/// One line of the checklist.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Check {
+    /// What was checked: a program name, or "IPC socket".
+    pub name: String,
+    pub ok: bool,
+    /// A failed required check makes `a3s doctor` exit non-zero.
+    pub required: bool,
+    /// The version or path found, or what is wrong.
+    pub detail: String,
+    /// What in the A3sfile needs it; empty for optional tools.
+    pub needed_by: String,
+    /// How to fix a failed check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+/// The whole checklist, in the order it is printed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// No required check failed.
+    pub fn ok(&self) -> bool {
+        self.checks.iter().all(|c| c.ok || !c.required)
+    }
+}
+
+/// A program a3s or a typical service runs, and how to get it.
+struct Tool {
+    binary: &'static str,
+    /// Arguments that print the version; empty when there's no portable way.
+    version_args: &'static [&'static str],
+    /// Install command on macOS, and elsewhere.
+    brew: &'static str,
+    other: &'static str,
+    /// Checked (as optional) even when the A3sfile doesn't need it.
+    always: bool,
+}
+
+const TOOLS: &[Tool] = &[
+    Tool {
+        binary: "sh",
+        version_args: &[],
+        brew: "install a POSIX shell as /bin/sh",
+        other: "install a POSIX shell as /bin/sh",
+        always: false,
+    },
+    Tool {
+        binary: "a3s-box",
+        version_args: &["--version"],
+        brew: "a3s update box",
+        other: "a3s update box",
+        always: true,
+    },
+    Tool {
+        binary: "kubectl",
+        version_args: &["version", "--client"],
+        brew: "brew install kubectl",
+        other: "see https://kubernetes.io/docs/tasks/tools/",
+        always: true,
+    },
+    Tool {
+        binary: "limactl",
+        version_args: &["--version"],
+        brew: "brew install lima",
+        other: "see https://lima-vm.io/docs/installation/",
+        always: true,
+    },
+    Tool {
+        binary: "helm",
+        version_args: &["version", "--short"],
+        brew: "brew install helm",
+        other: "see https://helm.sh/docs/intro/install/",
+        always: false,
+    },
+    Tool {
+        binary: "docker",
+        version_args: &["--version"],
+        brew: "brew install --cask docker",
+        other: "see https://docs.docker.com/engine/install/",
+        always: false,
+    },
+    Tool {
+        binary: "ssh",
+        version_args: &["-V"],
+        brew: "brew install openssh",
+        other: "install the OpenSSH client, e.g. apt install openssh-client",
+        always: false,
+    },
+    Tool {
+        binary: "node",
+        version_args: &["--version"],
+        brew: "brew install node",
+        other: "see https://nodejs.org/en/download",
+        always: false,
+    },
+    Tool {
+        binary: "npm",
+        version_args: &["--version"],
+        brew: "brew install node",
+        other: "see https://nodejs.org/en/download",
+        always: false,
+    },
+    Tool {
+        binary: "npx",
+        version_args: &["--version"],
+        brew: "brew install node",
+        other: "see https://nodejs.org/en/download",
+        always: false,
+    },
+    Tool {
+        binary: "pnpm",
+        version_args: &["--version"],
+        brew: "npm install -g pnpm",
+        other: "npm install -g pnpm",
+        always: false,
+    },
+    Tool {
+        binary: "yarn",
+        version_args: &["--version"],
+        brew: "npm install -g yarn",
+        other: "npm install -g yarn",
+        always: false,
+    },
+    Tool {
+        binary: "cargo",
+        version_args: &["--version"],
+        brew: "see https://rustup.rs",
+        other: "see https://rustup.rs",
+        always: false,
+    },
+    Tool {
+        binary: "go",
+        version_args: &["version"],
+        brew: "brew install go",
+        other: "see https://go.dev/dl/",
+        always: false,
+    },
+    Tool {
+        binary: "python3",
+        version_args: &["--version"],
+        brew: "brew install python",
+        other: "see https://www.python.org/downloads/",
+        always: false,
+    },
+];
+
+/// Shell words that start a `cmd` without being a program on PATH.
+const SHELL_BUILTINS: &[&str] = &["cd", "exec", "export", "source", ".", "set", "ulimit"];
+
+/// A program to look for, and what needs it.
+#[derive(Debug, Clone, PartialEq)]
+struct Need {
+    binary: String,
+    required: bool,
+    needed_by: Vec<String>,
+}
+
+/// Check the programs `cfg` needs (or just the optional ones without an A3sfile)
+/// and the IPC socket of the project at `config_path`.
+pub fn run(cfg: Option<&DevConfig>, config_path: &Path) -> Report {
+    let mut checks: Vec<Check> = needs(cfg).into_iter().map(probe).collect();
+    checks.push(socket_check(&crate::ipc::socket_path(config_path)));
+    Report { checks }
+}
+
+/// Every program to check, required ones first, each once.
+fn needs(cfg: Option<&DevConfig>) -> Vec<Need> {
+    let mut needs: Vec<Need> = Vec::new();
+    let mut need = |binary: &str, why: String| match needs.iter_mut().find(|n| n.binary == binary) {
+        Some(n) => n.needed_by.push(why),
+        None => needs.push(Need {
+            binary: binary.to_string(),
+            required: true,
+            needed_by: vec![why],
+        }),
+    };
+    if let Some(cfg) = cfg {
+        if cfg.dev.runtime == "k8s" {
+            need("kubectl", "runtime = \"k8s\"".into());
+            for (name, svc) in cfg.service.iter().filter(|(_, s)| !s.disabled) {
+                let Some(k8s) = &svc.k8s else { continue };
+                if k8s.dockerfile.is_some() {
+                    need("docker", format!("service \"{name}\" (dockerfile)"));
+                }
+                if k8s.helm_chart.is_some() {
+                    need("helm", format!("service \"{name}\" (helm_chart)"));
+                }
+            }
+        } else {
+            for (name, svc) in cfg.service.iter().filter(|(_, s)| !s.disabled) {
+                let why = format!("service \"{name}\"");
+                let shell = svc.shell.as_deref();
+                if shell != Some("none") {
+                    need(shell.unwrap_or("sh"), why.clone());
+                }
+                if let Some(program) = cmd_program(&svc.cmd, shell) {
+                    need(program, why);
+                }
+            }
+        }
+        if cfg.container.values().any(|c| !c.disabled) {
+            need("a3s-box", "container blocks".into());
+        }
+        if cfg.dev.share.as_ref().is_some_and(|s| s.provider == "ssh") {
+            need("ssh", "share (provider = \"ssh\")".into());
+        }
+    }
+    for tool in TOOLS.iter().filter(|t| t.always) {
+        if !needs.iter().any(|n| n.binary == tool.binary) {
+            needs.push(Need {
+                binary: tool.binary.to_string(),
+                required: false,
+                needed_by: Vec::new(),
+            });
+        }
+    }
+    needs
+}
+
+/// The program a service's `cmd` runs, when it can be told from the first word:
+/// not a path, an assignment or a shell builtin.
+fn cmd_program<'a>(cmd: &'a str, shell: Option<&str>) -> Option<&'a str> {
+    let first = cmd.split_whitespace().next()?;
+    let builtin = shell != Some("none") && SHELL_BUILTINS.contains(&first);
+    (!builtin && !first.contains(['/', '=', '$'])).then_some(first)
+}
+
+fn probe(need: Need) -> Check {
+    let tool = TOOLS.iter().find(|t| t.binary == need.binary);
+    let (ok, detail, fix) = match which(&need.binary) {
+        None => {
+            let fix = match tool {
+                Some(t) if cfg!(target_os = "macos") => t.brew.to_string(),
+                Some(t) => t.other.to_string(),
+                None => format!("install {} or change the cmd that runs it", need.binary),
+            };
+            (false, "not found on PATH".to_string(), Some(fix))
+        }
+        Some(path) => match tool.filter(|t| !t.version_args.is_empty()) {
+            // Only known tools are run: an unknown service program might not take
+            // --version and start up instead.
+            None => (true, path.display().to_string(), None),
+            Some(t) => match version(&path, t.version_args) {
+                Some(v) => (true, v, None),
+                None => (
+                    false,
+                    format!("{} doesn't run", path.display()),
+                    Some(format!(
+                        "reinstall {}: `{} {}` failed",
+                        t.binary,
+                        t.binary,
+                        t.version_args.join(" ")
+                    )),
+                ),
+            },
+        },
+    };
+    Check {
+        name: need.binary,
+        ok,
+        required: need.required,
+        detail,
+        needed_by: need.needed_by.join(", "),
+        fix,
+    }
+}
+
+/// The first line `program args` prints, on stdout or (like `ssh -V`) stderr.
+fn version(program: &Path, args: &[&str]) -> Option<String> {
+    let out = Command::new(program).args(args).output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let first_line = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .map(str::to_string)
+    };
+    first_line(&out.stdout).or_else(|| first_line(&out.stderr))
+}
+
+/// Where `name` is on PATH.
+pub fn which(name: &str) -> Option<PathBuf> {
+    which_in(name, &std::env::var_os("PATH")?)
+}
+
+/// The first executable file called `name` in the directories of `path`.
+fn which_in(name: &str, path: &std::ffi::OsStr) -> Option<PathBuf> {
+    if name.is_empty() || name.contains('/') {
+        return None;
+    }
+    std::env::split_paths(path)
+        .map(|dir| dir.join(name))
+        .find(|p| is_executable(p))
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// Whether a daemon listens on `socket`, or else whether one could be created there.
+fn socket_check(socket: &Path) -> Check {
+    let dir = socket.parent().unwrap_or(Path::new("."));
+    let (ok, detail) = if std::os::unix::net::UnixStream::connect(socket).is_ok() {
+        (true, format!("daemon listening on {}", socket.display()))
+    } else {
+        let probe = dir.join(format!(".a3s-doctor-{}", std::process::id()));
+        match std::fs::write(&probe, b"") {
+            Ok(()) => {
+                let _ = std::fs::remove_file(&probe);
+                (true, format!("can create {}", socket.display()))
+            }
+            Err(e) => (false, format!("can't write to {}: {e}", dir.display())),
+        }
+    };
+    Check {
+        name: "IPC socket".into(),
+        ok,
+        required: true,
+        detail,
+        needed_by: String::new(),
+        fix: (!ok).then(|| {
+            format!(
+                "make {} writable, or point TMPDIR at a writable directory",
+                dir.display()
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_which_searches_path_for_executables() {
+        use std::os::unix::fs::PermissionsExt;
+        let (a, b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let write = |dir: &Path, mode: u32| {
+            let file = dir.join("tool");
+            std::fs::write(&file, "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(mode)).unwrap();
+            file
+        };
+        // Not executable in the first directory, so the second one's wins.
+        write(a.path(), 0o644);
+        let tool = write(b.path(), 0o755);
+        std::fs::create_dir(b.path().join("dir")).unwrap();
+        let path = std::env::join_paths([a.path(), b.path()]).unwrap();
+        assert_eq!(which_in("tool", &path), Some(tool));
+        assert_eq!(which_in("missing", &path), None);
+        assert_eq!(which_in("dir", &path), None);
+        assert_eq!(which_in("", &path), None);
+    }
+
+    fn required(cfg: Option<&DevConfig>) -> Vec<String> {
+        needs(cfg)
+            .into_iter()
+            .filter(|n| n.required)
+            .map(|n| n.binary)
+            .collect()
+    }
+
+    #[test]
+    fn test_needs_follow_the_config() {
+        let local: DevConfig = hcl::from_str(
+            r#"
+            dev {
+              share {
+                host = "tunnel.example.com"
+              }
+            }
+            service "web" {
+              cmd = "npm run dev"
+            }
+            service "api" {
+              cmd   = "cd api && cargo run"
+              shell = "bash"
+            }
+            service "bin" {
+              cmd   = "./target/debug/bin"
+              shell = "none"
+            }
+            service "old" {
+              cmd      = "python3 -m http.server"
+              disabled = true
+            }
+            container "db" {
+              image = "postgres:16"
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            required(Some(&local)),
+            ["sh", "npm", "bash", "a3s-box", "ssh"]
+        );
+        let all = needs(Some(&local));
+        let sh = all.iter().find(|n| n.binary == "sh").unwrap();
+        assert_eq!(sh.needed_by, ["service \"web\""]);
+        let optional: Vec<&str> = all
+            .iter()
+            .filter(|n| !n.required)
+            .map(|n| n.binary.as_str())
+            .collect();
+        assert_eq!(optional, ["kubectl", "limactl"]);
+
+        let k8s: DevConfig = hcl::from_str(
+            r#"
+            dev {
+              runtime = "k8s"
+            }
+            service "web" {
+              cmd = "npm run dev"
+              k8s {
+                image      = "web:dev"
+                dockerfile = "Dockerfile"
+              }
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(required(Some(&k8s)), ["kubectl", "docker"]);
+        assert!(required(None).is_empty());
+    }
+
+    #[test]
+    fn test_cmd_program_skips_paths_and_builtins() {
+        assert_eq!(cmd_program("npm run dev", None), Some("npm"));
+        assert_eq!(cmd_program("cd web && npm start", None), None);
+        assert_eq!(cmd_program("./run.sh", None), None);
+        assert_eq!(cmd_program("PORT=1 node x.js", None), None);
+        assert_eq!(cmd_program("cd", Some("none")), Some("cd"));
+        assert_eq!(cmd_program("   ", None), None);
+    }
+
+    #[test]
+    fn test_probe_reports_missing_programs_with_a_fix() {
+        let missing = probe(Need {
+            binary: "a3s-doctor-no-such-program".into(),
+            required: true,
+            needed_by: vec!["service \"vm\"".into()],
+        });
+        assert!(!missing.ok);
+        assert_eq!(missing.detail, "not found on PATH");
+        assert_eq!(missing.needed_by, "service \"vm\"");
+        assert!(missing.fix.as_deref().unwrap().contains("change the cmd"));
+
+        let sh = probe(Need {
+            binary: "sh".into(),
+            required: true,
+            needed_by: Vec::new(),
+        });
+        assert!(sh.ok, "{sh:?}");
+        assert_eq!(sh.fix, None);
+
+        let optional = Check {
+            required: false,
+            ..missing.clone()
+        };
+        assert!(Report {
+            checks: vec![optional, sh]
+        }
+        .ok());
+        assert!(!Report {
+            checks: vec![missing]
+        }
+        .ok());
+    }
+
+    #[test]
+    fn test_socket_check_needs_a_writable_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let ok = socket_check(&dir.path().join("a3s-test.sock"));
+        assert!(ok.ok && ok.required, "{ok:?}");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let bad = socket_check(&dir.path().join("missing").join("a3s-test.sock"));
+        assert!(!bad.ok);
+        assert!(bad.fix.unwrap().contains("TMPDIR"));
+    }
+}
@@ -19,6 +19,13 @@ pub enum DevError {
 
     #[error("port conflict: services '{a}' and '{b}' both use port {port}")]
     PortConflict { a: String, b: String, port: u16 },
+
+    #[error("subdomain conflict: services '{a}' and '{b}' both use subdomain '{subdomain}'")]
+    SubdomainConflict {
+        a: String,
+        b: String,
+        subdomain: String,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, DevError>;
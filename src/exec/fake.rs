@@ -0,0 +1,318 @@
+//! Scripted responses for external tools, for hermetic tests.
+//!
+//! With `A3S_FAKE_EXEC=<dir>`, a `test-util` build answers [`super::output`] from the
+//! `*.json` files in `<dir>` instead of running anything. Each file holds one
+//! [`FakeResponse`] or an array of them; files are read in name order and the first
+//! response whose `program` and `args` match wins:
+//!
+//! ```json
+//! {"program": "a3s-box", "args": ["ps", "**"], "stdout": "…", "exit": 0, "delay_ms": 0}
+//! ```
+//!
+//! Tools that are spawned rather than run to completion (see [`super::spawn`]) are
+//! played by a stand-in `sh` that prints the same response, so callers reading its
+//! output line by line see it as they would the real tool's.
+//!
+//! In `args`, `*` matches any one argument and a trailing `**` any number of them.
+//! `program` is compared with the file name of what would have run, so
+//! `/usr/local/bin/kubectl` matches `kubectl`. An invocation nothing matches fails
+//! like a missing tool would. Every invocation is appended to `<dir>/calls.jsonl` as
+//! a JSON array of the program and its arguments.
+
+use std::ffi::OsStr;
+use std::io::{self, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Names the directory of scripted responses.
+pub const FAKE_EXEC_VAR: &str = "A3S_FAKE_EXEC";
+
+/// What one invocation of an external tool prints and exits with.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FakeResponse {
+    pub program: String,
+    /// Argument patterns: literal, `*` for any one, or a trailing `**` for the rest.
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    #[serde(default)]
+    pub exit: i32,
+    /// How long the tool "runs" before answering.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+impl FakeResponse {
+    fn matches(&self, program: &str, args: &[String]) -> bool {
+        if self.program != program {
+            return false;
+        }
+        let mut args = args.iter();
+        for (i, pattern) in self.args.iter().enumerate() {
+            if pattern == "**" && i + 1 == self.args.len() {
+                return true;
+            }
+            match args.next() {
+                Some(arg) if pattern == "*" || pattern == arg => {}
+                _ => return false,
+            }
+        }
+        args.next().is_none()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(FakeResponse),
+    Many(Vec<FakeResponse>),
+}
+
+/// The scripted responses of one test, in match order.
+#[derive(Debug, Default)]
+pub struct FakeExecRegistry {
+    responses: Vec<FakeResponse>,
+    /// Where invocations are recorded; `None` for a registry built in memory.
+    calls: Option<PathBuf>,
+}
+
+impl FakeExecRegistry {
+    #[cfg(test)]
+    pub fn new(responses: Vec<FakeResponse>) -> Self {
+        Self {
+            responses,
+            calls: None,
+        }
+    }
+
+    /// Read every `*.json` file in `dir`, in name order.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension() == Some(OsStr::new("json")))
+            .collect();
+        files.sort();
+        let mut responses = Vec::new();
+        for file in files {
+            let text = std::fs::read_to_string(&file)?;
+            let parsed = serde_json::from_str(&text).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {e}", file.display()),
+                )
+            })?;
+            match parsed {
+                OneOrMany::One(r) => responses.push(r),
+                OneOrMany::Many(rs) => responses.extend(rs),
+            }
+        }
+        Ok(Self {
+            responses,
+            calls: Some(dir.join("calls.jsonl")),
+        })
+    }
+
+    /// The registry `A3S_FAKE_EXEC` points at. It is read again on every call, so a
+    /// test can change the responses while the daemon runs.
+    #[cfg(feature = "test-util")]
+    pub fn from_env() -> io::Result<Option<Self>> {
+        std::env::var_os(FAKE_EXEC_VAR)
+            .map(|dir| Self::load(Path::new(&dir)))
+            .transpose()
+    }
+
+    /// The first response matching `program` and `args`.
+    pub fn find(&self, program: &str, args: &[String]) -> Option<&FakeResponse> {
+        self.responses.iter().find(|r| r.matches(program, args))
+    }
+
+    /// What running `cmd` would have produced.
+    pub async fn output(&self, cmd: &std::process::Command) -> io::Result<Output> {
+        let r = self.respond(cmd)?;
+        if r.delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(r.delay_ms)).await;
+        }
+        Ok(Output {
+            status: ExitStatus::from_raw(r.exit << 8),
+            stdout: r.stdout.clone().into_bytes(),
+            stderr: r.stderr.clone().into_bytes(),
+        })
+    }
+
+    /// A process standing in for `cmd`: it waits `delay_ms`, prints the response and
+    /// exits with its code.
+    pub fn spawn(&self, cmd: &std::process::Command) -> io::Result<tokio::process::Child> {
+        let r = self.respond(cmd)?;
+        let delay = format!("{}.{:03}", r.delay_ms / 1000, r.delay_ms % 1000);
+        tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(r#"sleep "$4"; printf %s "$2" >&2; printf %s "$1"; exit "$3""#)
+            .arg("a3s-fake-exec")
+            .args([&r.stdout, &r.stderr, &r.exit.to_string(), &delay])
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+    }
+
+    /// Record `cmd` and find its response.
+    fn respond(&self, cmd: &std::process::Command) -> io::Result<&FakeResponse> {
+        let program = Path::new(cmd.get_program())
+            .file_name()
+            .unwrap_or(cmd.get_program())
+            .to_string_lossy()
+            .into_owned();
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        if let Some(calls) = &self.calls {
+            let argv: Vec<&String> = std::iter::once(&program).chain(&args).collect();
+            let mut line = serde_json::to_string(&argv)?;
+            line.push('\n');
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(calls)?
+                .write_all(line.as_bytes())?;
+        }
+        self.find(&program, &args).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{FAKE_EXEC_VAR} has no response for `{program} {}`",
+                    args.join(" ")
+                ),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(program: &str, args: &[&str]) -> FakeResponse {
+        FakeResponse {
+            program: program.into(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_argument_patterns() {
+        let r = response("kubectl", &["--namespace", "*", "get", "pods"]);
+        assert!(r.matches("kubectl", &argv(&["--namespace", "dev", "get", "pods"])));
+        assert!(!r.matches("kubectl", &argv(&["--namespace", "dev", "get"])));
+        assert!(!r.matches(
+            "kubectl",
+            &argv(&["--namespace", "dev", "get", "pods", "-w"])
+        ));
+        assert!(!r.matches("helm", &argv(&["--namespace", "dev", "get", "pods"])));
+
+        let rest = response("a3s-box", &["ps", "**"]);
+        assert!(rest.matches("a3s-box", &argv(&["ps"])));
+        assert!(rest.matches("a3s-box", &argv(&["ps", "--format", "json", "-a"])));
+        assert!(!rest.matches("a3s-box", &argv(&["images"])));
+    }
+
+    #[test]
+    fn test_load_reads_files_in_name_order() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("20-any.json"),
+            r#"[{"program": "a3s-box", "args": ["**"], "stdout": "any"}]"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("10-ps.json"),
+            r#"{"program": "a3s-box", "args": ["ps", "**"], "stdout": "ps"}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        let reg = FakeExecRegistry::load(dir.path()).unwrap();
+        assert_eq!(reg.find("a3s-box", &argv(&["ps"])).unwrap().stdout, "ps");
+        assert_eq!(
+            reg.find("a3s-box", &argv(&["images"])).unwrap().stdout,
+            "any"
+        );
+
+        std::fs::write(dir.path().join("30-bad.json"), "{").unwrap();
+        let err = FakeExecRegistry::load(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("30-bad.json"), "{err}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_output_replays_response_and_records_call() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("box.json"),
+            r#"{"program": "a3s-box", "args": ["stop", "*"], "stderr": "busy", "exit": 3, "delay_ms": 5000}"#,
+        )
+        .unwrap();
+        let reg = FakeExecRegistry::load(dir.path()).unwrap();
+        let mut cmd = std::process::Command::new("/opt/bin/a3s-box");
+        cmd.args(["stop", "a3s-db"]);
+
+        let started = tokio::time::Instant::now();
+        let out = reg.output(&cmd).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(5));
+        assert_eq!(out.status.code(), Some(3));
+        assert_eq!(out.stderr, b"busy");
+        assert!(out.stdout.is_empty());
+
+        let calls = std::fs::read_to_string(dir.path().join("calls.jsonl")).unwrap();
+        assert_eq!(calls, "[\"a3s-box\",\"stop\",\"a3s-db\"]\n");
+    }
+
+    #[tokio::test]
+    async fn test_spawn_plays_response_through_a_stand_in() {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let mut r = response("a3s-box", &["stats", "**"]);
+        r.stdout = "{\"ID\":\"c1\"}\n{\"ID\":\"c2\"}\n".into();
+        r.stderr = "gone".into();
+        r.exit = 1;
+        r.delay_ms = 50;
+        let reg = FakeExecRegistry::new(vec![r]);
+        let mut cmd = std::process::Command::new("a3s-box");
+        cmd.args(["stats", "--format", "json", "c1"]);
+
+        let mut child = reg.spawn(&cmd).unwrap();
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "{\"ID\":\"c1\"}");
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "{\"ID\":\"c2\"}");
+        assert_eq!(lines.next_line().await.unwrap(), None);
+        let out = child.wait_with_output().await.unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        assert_eq!(out.stderr, b"gone");
+
+        let mut other = std::process::Command::new("a3s-box");
+        other.arg("push");
+        let err = reg.spawn(&other).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_invocation_is_not_found() {
+        let reg = FakeExecRegistry::new(vec![response("kubectl", &["version"])]);
+        let mut cmd = std::process::Command::new("kubectl");
+        cmd.args(["get", "pods"]);
+        let err = reg.output(&cmd).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("`kubectl get pods`"), "{err}");
+    }
+}
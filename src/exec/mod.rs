@@ -0,0 +1,37 @@
+//! Running external tools (kubectl, a3s-box, …) for their output.
+//!
+//! Call sites go through [`output`] instead of `Command::output`, and through
+//! [`spawn`] instead of `Command::spawn` when they stream a tool's output or feed its
+//! stdin, so that a build with the `test-util` feature can answer them from the
+//! scripted responses in the directory `A3S_FAKE_EXEC` points at (see [`fake`]),
+//! without the real tools.
+
+use std::io;
+use std::process::Output;
+
+use tokio::process::{Child, Command};
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod fake;
+
+/// `cmd.output()`, or the scripted response for it when `A3S_FAKE_EXEC` is set in a
+/// `test-util` build.
+pub async fn output(cmd: &mut Command) -> io::Result<Output> {
+    #[cfg(feature = "test-util")]
+    if let Some(fake) = fake::FakeExecRegistry::from_env()? {
+        return fake.output(cmd.as_std()).await;
+    }
+    cmd.output().await
+}
+
+/// `cmd.spawn()`, or — when `A3S_FAKE_EXEC` is set in a `test-util` build — a
+/// stand-in process that prints the scripted response on piped stdout and stderr
+/// and exits with its code. The stand-in's stdin is closed, and an invocation with
+/// no scripted response fails to spawn rather than running the real tool.
+pub fn spawn(cmd: &mut Command) -> io::Result<Child> {
+    #[cfg(feature = "test-util")]
+    if let Some(fake) = fake::FakeExecRegistry::from_env()? {
+        return fake.spawn(cmd.as_std());
+    }
+    cmd.spawn()
+}
@@ -162,25 +162,40 @@ mod tests {
                     subdomain: None,
                     env: Default::default(),
                     env_file: None,
+                    env_reload: false,
+                    shell: None,
                     log_file: None,
                     log_rotate_mb: 0,
+                    log_max_size: None,
+                    log_keep: 1,
                     pre_start: None,
                     post_stop: None,
+                    hook_timeout: std::time::Duration::from_secs(300),
                     depends_on: deps.iter().map(|s| s.to_string()).collect(),
+                    depends_on_timeout: None,
                     watch: None,
                     health: None,
                     restart: Default::default(),
                     stop_timeout: std::time::Duration::from_secs(5),
                     disabled: false,
+                    autostart: true,
                     labels: vec![],
+                    standby: false,
+                    limits: None,
                     k8s: None,
+                    lint_ignore: vec![],
+                    env_secret: Default::default(),
                 },
             );
         }
         DevConfig {
             dev: Default::default(),
             service: map,
+            container: Default::default(),
             env_override: Default::default(),
+            environment: Default::default(),
+            include: vec![],
+            sources: Default::default(),
         }
     }
 
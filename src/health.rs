@@ -9,7 +9,8 @@ use crate::config::{HealthConfig, HealthKind, ServiceDef};
 
 #[async_trait]
 pub trait HealthProbe: Send + Sync {
-    async fn check(&self, port: u16, svc: &ServiceDef) -> bool;
+    /// Ok when the service answers; otherwise why it didn't, for status rows.
+    async fn check(&self, port: u16, svc: &ServiceDef) -> Result<(), String>;
 }
 
 pub struct HttpProbe {
@@ -30,18 +31,20 @@ impl HttpProbe {
 
 #[async_trait]
 impl HealthProbe for HttpProbe {
-    async fn check(&self, port: u16, svc: &ServiceDef) -> bool {
+    async fn check(&self, port: u16, svc: &ServiceDef) -> Result<(), String> {
         let path = svc
             .health
             .as_ref()
             .and_then(|h| h.path.as_deref())
             .unwrap_or("/health");
         let url = format!("http://127.0.0.1:{port}{path}");
-        self.client
-            .get(&url)
-            .send()
-            .await
-            .is_ok_and(|r| r.status().is_success())
+        match self.client.get(&url).send().await {
+            Ok(r) if r.status().is_success() => Ok(()),
+            Ok(r) => Err(format!("GET {path} returned {}", r.status())),
+            Err(e) if e.is_timeout() => Err(format!("GET {path} timed out")),
+            Err(e) if e.is_connect() => Err(format!("connection to :{port} refused")),
+            Err(e) => Err(format!("GET {path} failed: {e}")),
+        }
     }
 }
 
@@ -57,13 +60,15 @@ impl TcpProbe {
 
 #[async_trait]
 impl HealthProbe for TcpProbe {
-    async fn check(&self, port: u16, _svc: &ServiceDef) -> bool {
+    async fn check(&self, port: u16, _svc: &ServiceDef) -> Result<(), String> {
         let Ok(addr) = format!("127.0.0.1:{port}").parse::<SocketAddr>() else {
-            return false;
+            return Err(format!("bad port {port}"));
         };
-        timeout(self.timeout, TcpStream::connect(addr))
-            .await
-            .is_ok_and(|r| r.is_ok())
+        match timeout(self.timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(format!("connecting to :{port} failed: {e}")),
+            Err(_) => Err(format!("connecting to :{port} timed out")),
+        }
     }
 }
 
@@ -82,20 +87,40 @@ impl HealthChecker {
         Some(Self { probe, config })
     }
 
-    /// Poll until healthy or retries exhausted. Returns true if healthy.
+    /// Poll until healthy or startup retries exhausted. Returns true if healthy.
+    /// Failures within `start_period` of the call don't count against the retries.
     /// `port` is the actual bound port (may differ from svc.port for port=0 services).
     pub async fn wait_healthy(&self, svc: &ServiceDef, port: u16) -> bool {
-        for _ in 0..self.config.retries {
+        self.wait_startup(svc, port).await.is_ok()
+    }
+
+    /// [`wait_healthy`](Self::wait_healthy), failing with why the last check failed.
+    pub async fn wait_startup(&self, svc: &ServiceDef, port: u16) -> Result<(), String> {
+        let grace = self.config.start_period.unwrap_or_default();
+        let retries = self.startup_retries();
+        let started = tokio::time::Instant::now();
+        let mut failures = 0;
+        let mut reason = String::from("no health check ran");
+        while failures < retries {
             tokio::time::sleep(self.config.interval).await;
-            if self.probe.check(port, svc).await {
-                return true;
+            match self.probe.check(port, svc).await {
+                Ok(()) => return Ok(()),
+                Err(e) => reason = e,
+            }
+            if started.elapsed() >= grace {
+                failures += 1;
             }
         }
-        false
+        Err(reason)
+    }
+
+    /// Failed checks after `start_period` that end startup unhealthy.
+    pub fn startup_retries(&self) -> u32 {
+        self.config.startup_retries.unwrap_or(self.config.retries)
     }
 
     /// Run a single health check. Used by the ongoing health monitor.
-    pub async fn check_once(&self, port: u16, svc: &ServiceDef) -> bool {
+    pub async fn check_once(&self, port: u16, svc: &ServiceDef) -> Result<(), String> {
         self.probe.check(port, svc).await
     }
 }
@@ -113,18 +138,29 @@ mod tests {
             subdomain: None,
             env: Default::default(),
             env_file: None,
+            env_reload: false,
+            shell: None,
             log_file: None,
             log_rotate_mb: 0,
+            log_max_size: None,
+            log_keep: 1,
             pre_start: None,
             post_stop: None,
+            hook_timeout: std::time::Duration::from_secs(300),
             depends_on: vec![],
+            depends_on_timeout: None,
             watch: None,
             health: None,
             restart: Default::default(),
             stop_timeout: std::time::Duration::from_secs(5),
             disabled: false,
+            autostart: true,
             labels: vec![],
+            standby: false,
+            limits: None,
             k8s: None,
+            lint_ignore: vec![],
+            env_secret: Default::default(),
         }
     }
 
@@ -148,7 +184,7 @@ mod tests {
 
         let probe = TcpProbe::new(Duration::from_secs(1));
         let svc = dummy_svc();
-        assert!(probe.check(port, &svc).await);
+        assert!(probe.check(port, &svc).await.is_ok());
     }
 
     #[tokio::test]
@@ -156,7 +192,41 @@ mod tests {
         let port = free_port().await; // bind then drop — port is closed
         let probe = TcpProbe::new(Duration::from_millis(100));
         let svc = dummy_svc();
-        assert!(!probe.check(port, &svc).await);
+        let err = probe.check(port, &svc).await.unwrap_err();
+        assert!(err.contains(&format!(":{port}")), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_http_probe_reports_the_status_of_a_failing_check() {
+        use tokio::io::AsyncWriteExt;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = listener.accept().await {
+                let _ = conn
+                    .write_all(b"HTTP/1.1 500 Internal Server Error\r\ncontent-length: 0\r\n\r\n")
+                    .await;
+            }
+        });
+
+        let probe = HttpProbe::new(Duration::from_secs(1));
+        let err = probe.check(port, &dummy_svc()).await.unwrap_err();
+        assert_eq!(err, "GET /health returned 500 Internal Server Error");
+
+        let checker = HealthChecker {
+            probe: Box::new(probe),
+            config: crate::config::HealthConfig {
+                kind: crate::config::HealthKind::Http,
+                path: None,
+                interval: Duration::from_millis(1),
+                timeout: Duration::from_secs(1),
+                retries: 2,
+                start_period: None,
+                startup_retries: None,
+            },
+        };
+        let err = checker.wait_startup(&dummy_svc(), port).await.unwrap_err();
+        assert!(err.contains("500"), "{err}");
     }
 
     #[tokio::test]
@@ -176,6 +246,8 @@ mod tests {
             interval: Duration::from_millis(10),
             timeout: Duration::from_millis(200),
             retries: 3,
+            start_period: None,
+            startup_retries: None,
         };
         let checker = HealthChecker {
             probe: Box::new(TcpProbe::new(config.timeout)),
@@ -196,6 +268,8 @@ mod tests {
             interval: Duration::from_millis(1),
             timeout: Duration::from_millis(50),
             retries: 2,
+            start_period: None,
+            startup_retries: None,
         };
         let checker = HealthChecker {
             probe: Box::new(TcpProbe::new(config.timeout)),
@@ -204,4 +278,63 @@ mod tests {
         let svc = dummy_svc();
         assert!(!checker.wait_healthy(&svc, port).await);
     }
+
+    /// Accept connections on `port` once `delay` has passed, like a slow-booting service.
+    fn listen_after(port: u16, delay: Duration) {
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+            loop {
+                let _ = listener.accept().await;
+            }
+        });
+    }
+
+    fn tcp_checker(start_period: Option<Duration>, startup_retries: Option<u32>) -> HealthChecker {
+        use crate::config::{HealthConfig, HealthKind};
+        let config = HealthConfig {
+            kind: HealthKind::Tcp,
+            path: None,
+            interval: Duration::from_millis(20),
+            timeout: Duration::from_millis(50),
+            retries: 2,
+            start_period,
+            startup_retries,
+        };
+        HealthChecker {
+            probe: Box::new(TcpProbe::new(config.timeout)),
+            config,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_period_waits_out_a_slow_start() {
+        // Two retries 20ms apart give up long before the service listens...
+        let port = free_port().await;
+        listen_after(port, Duration::from_millis(300));
+        assert!(
+            !tcp_checker(None, None)
+                .wait_healthy(&dummy_svc(), port)
+                .await
+        );
+
+        // ...but failures inside the start period don't count.
+        let port = free_port().await;
+        listen_after(port, Duration::from_millis(300));
+        let started = std::time::Instant::now();
+        let checker = tcp_checker(Some(Duration::from_secs(5)), None);
+        assert!(checker.wait_healthy(&dummy_svc(), port).await);
+        // The first passing check ends startup without waiting out the period.
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_startup_retries_apply_after_start_period() {
+        let port = free_port().await;
+        let started = std::time::Instant::now();
+        let checker = tcp_checker(Some(Duration::from_millis(100)), Some(3));
+        assert!(!checker.wait_healthy(&dummy_svc(), port).await);
+        // Checks at ~100, 120 and 140ms count; the ones before are within the period.
+        assert!(started.elapsed() >= Duration::from_millis(100 + 2 * 20));
+    }
 }
@@ -0,0 +1,308 @@
+//! Health-check history for the flakiness report.
+//!
+//! Outcomes are folded into per-service counters, one bucket per 5-minute window,
+//! and buckets older than 24h are dropped — memory stays bounded no matter how often
+//! checks run. The history is saved to `.a3s/health-stats.json` next to A3sfile.hcl
+//! so it survives daemon restarts.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ipc::HealthReportRow;
+
+/// Width of one aggregation bucket.
+pub const BUCKET_SECS: u64 = 5 * 60;
+/// How far back the report looks.
+pub const WINDOW_SECS: u64 = 24 * 60 * 60;
+/// How often the daemon writes the history to disk.
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Bucket {
+    /// Unix time the window starts at, a multiple of `BUCKET_SECS`.
+    start: u64,
+    ok: u32,
+    fail: u32,
+    flaps: u32,
+    /// Longest outage that ended inside this window.
+    longest_outage: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ServiceHistory {
+    buckets: VecDeque<Bucket>,
+    /// Outcome of the previous check, to count passing → failing flaps.
+    #[serde(skip)]
+    last_ok: Option<bool>,
+    /// Time of the first failure of the outage in progress.
+    #[serde(skip)]
+    down_since: Option<u64>,
+}
+
+/// Bucketed health-check outcomes of every service.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HealthStats {
+    services: BTreeMap<String, ServiceHistory>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+pub type SharedHealthStats = Arc<Mutex<HealthStats>>;
+
+impl HealthStats {
+    /// Where the history of the project in `config_dir` is kept.
+    pub fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join(".a3s").join("health-stats.json")
+    }
+
+    /// Read a saved history. A missing or unreadable file starts a fresh one.
+    /// Outages in progress are not carried over — the daemon was not watching.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(self)?)?;
+        std::fs::rename(tmp, path)
+    }
+
+    /// Record one check outcome of `service` at unix time `now`.
+    pub fn record(&mut self, service: &str, ok: bool, now: u64) {
+        let hist = self.services.entry(service.to_string()).or_default();
+        let start = now - now % BUCKET_SECS;
+        if hist.buckets.back().is_none_or(|b| b.start < start) {
+            hist.buckets.push_back(Bucket {
+                start,
+                ..Default::default()
+            });
+        }
+        while hist
+            .buckets
+            .front()
+            .is_some_and(|b| b.start + WINDOW_SECS <= now)
+        {
+            hist.buckets.pop_front();
+        }
+        let bucket = hist.buckets.back_mut().expect("bucket pushed above");
+
+        if ok {
+            bucket.ok += 1;
+            if let Some(since) = hist.down_since.take() {
+                bucket.longest_outage = bucket.longest_outage.max(now.saturating_sub(since));
+            }
+        } else {
+            bucket.fail += 1;
+            if hist.last_ok == Some(true) {
+                bucket.flaps += 1;
+            }
+            hist.down_since.get_or_insert(now);
+        }
+        hist.last_ok = Some(ok);
+        self.dirty = true;
+    }
+
+    /// Statistics of `service` over the 24h before `now`.
+    pub fn report(&self, service: &str, now: u64, flaky_threshold: f64) -> HealthReportRow {
+        let mut row = HealthReportRow {
+            name: service.to_string(),
+            checks: 0,
+            uptime_pct: None,
+            flaps: 0,
+            longest_outage_secs: 0,
+            flaky: false,
+        };
+        let Some(hist) = self.services.get(service) else {
+            return row;
+        };
+        let mut ok = 0;
+        for b in hist.buckets.iter().filter(|b| b.start + WINDOW_SECS > now) {
+            ok += b.ok;
+            row.checks += b.ok + b.fail;
+            row.flaps += b.flaps;
+            row.longest_outage_secs = row.longest_outage_secs.max(b.longest_outage);
+        }
+        if let Some(since) = hist.down_since {
+            row.longest_outage_secs = row.longest_outage_secs.max(now.saturating_sub(since));
+        }
+        if row.checks > 0 {
+            let pct = f64::from(ok) * 100.0 / f64::from(row.checks);
+            row.uptime_pct = Some(pct);
+            row.flaky = pct < flaky_threshold;
+        }
+        row
+    }
+
+    /// Reports of every service with history, by name.
+    pub fn report_all(&self, now: u64, flaky_threshold: f64) -> Vec<HealthReportRow> {
+        self.services
+            .keys()
+            .map(|name| self.report(name, now, flaky_threshold))
+            .filter(|row| row.checks > 0)
+            .collect()
+    }
+}
+
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Write `stats` to `path` every minute while it has unsaved outcomes.
+pub async fn persist_loop(stats: SharedHealthStats, path: PathBuf) {
+    let mut tick = tokio::time::interval(SAVE_INTERVAL);
+    tick.tick().await;
+    loop {
+        tick.tick().await;
+        save_if_dirty(&stats, &path);
+    }
+}
+
+pub fn save_if_dirty(stats: &SharedHealthStats, path: &Path) {
+    let mut stats = stats.lock().unwrap();
+    if !stats.dirty {
+        return;
+    }
+    match stats.save(path) {
+        Ok(()) => stats.dirty = false,
+        Err(e) => tracing::warn!("cannot save {}: {e}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A day-aligned start so bucket boundaries are easy to reason about.
+    const T0: u64 = 1_700_006_400;
+
+    /// Record `outcomes` for `svc`, one check every `interval` seconds from `start`.
+    fn feed(stats: &mut HealthStats, svc: &str, start: u64, interval: u64, outcomes: &str) -> u64 {
+        let mut now = start;
+        for c in outcomes.chars() {
+            stats.record(svc, c == '+', now);
+            now += interval;
+        }
+        now - interval
+    }
+
+    #[test]
+    fn test_uptime_flaps_and_longest_outage() {
+        let mut stats = HealthStats::default();
+        // 10 checks, 10s apart: two outages of 20s and 30s.
+        let last = feed(&mut stats, "api", T0, 10, "++--+---++");
+        let row = stats.report("api", last, 95.0);
+        assert_eq!(row.checks, 10);
+        assert_eq!(row.uptime_pct, Some(50.0));
+        assert_eq!(row.flaps, 2);
+        assert_eq!(row.longest_outage_secs, 30);
+        assert!(row.flaky);
+    }
+
+    #[test]
+    fn test_ongoing_outage_counts_until_now() {
+        let mut stats = HealthStats::default();
+        feed(&mut stats, "db", T0, 10, "+---");
+        assert_eq!(stats.report("db", T0 + 100, 95.0).longest_outage_secs, 90);
+    }
+
+    #[test]
+    fn test_failing_from_the_start_is_not_a_flap() {
+        let mut stats = HealthStats::default();
+        let last = feed(&mut stats, "db", T0, 10, "--+");
+        let row = stats.report("db", last, 95.0);
+        assert_eq!(row.flaps, 0);
+        assert_eq!(row.longest_outage_secs, 20);
+    }
+
+    #[test]
+    fn test_flaky_badge_transitions() {
+        let mut stats = HealthStats::default();
+        let mut now = feed(&mut stats, "api", T0, 10, &"+".repeat(19));
+        assert!(!stats.report("api", now, 95.0).flaky);
+
+        // 19/20 = 95% is still fine, 19/21 is not.
+        now += 10;
+        stats.record("api", false, now);
+        assert!(!stats.report("api", now, 95.0).flaky);
+        now += 10;
+        stats.record("api", false, now);
+        assert!(stats.report("api", now, 95.0).flaky);
+
+        // A day later the bad checks have aged out.
+        now = feed(&mut stats, "api", now + WINDOW_SECS, 10, "+");
+        let row = stats.report("api", now, 95.0);
+        assert_eq!(row.checks, 1);
+        assert!(!row.flaky);
+    }
+
+    #[test]
+    fn test_buckets_are_bounded_to_the_window() {
+        let mut stats = HealthStats::default();
+        // Two days of checks every minute.
+        feed(&mut stats, "api", T0, 60, &"+".repeat(2 * 24 * 60));
+        let buckets = stats.services["api"].buckets.len() as u64;
+        assert_eq!(buckets, WINDOW_SECS / BUCKET_SECS);
+    }
+
+    #[test]
+    fn test_report_ignores_expired_buckets_without_new_checks() {
+        let mut stats = HealthStats::default();
+        feed(&mut stats, "api", T0, 10, "+-+");
+        assert_eq!(
+            stats
+                .report("api", T0 + WINDOW_SECS + BUCKET_SECS, 95.0)
+                .checks,
+            0
+        );
+        assert!(stats
+            .report_all(T0 + WINDOW_SECS + BUCKET_SECS, 95.0)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_unknown_service_has_no_uptime() {
+        let row = HealthStats::default().report("nope", T0, 95.0);
+        assert_eq!(row.uptime_pct, None);
+        assert!(!row.flaky);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = HealthStats::path(dir.path());
+        let shared: SharedHealthStats = Default::default();
+        let last = feed(&mut shared.lock().unwrap(), "api", T0, 10, "++-+");
+        save_if_dirty(&shared, &path);
+        assert!(!shared.lock().unwrap().dirty);
+
+        let loaded = HealthStats::load(&path);
+        assert_eq!(
+            loaded.report("api", last, 95.0),
+            shared.lock().unwrap().report("api", last, 95.0)
+        );
+        assert_eq!(loaded.report_all(last, 95.0).len(), 1);
+    }
+
+    #[test]
+    fn test_load_missing_or_corrupt_starts_fresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = HealthStats::path(dir.path());
+        assert!(HealthStats::load(&path).services.is_empty());
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "not json").unwrap();
+        assert!(HealthStats::load(&path).services.is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+//! Plain-language hints for well-known failures of the tools `a3s` shells out to.
+//!
+//! When kubectl, limactl, brew or a3s-box fails, its stderr is matched against
+//! [`HINTS`]. On a match the error shows the last line of the raw output, what it
+//! usually means and the command to run next; `-v` keeps the full output. Each hint
+//! has an ID so it can be silenced with `A3S_NO_HINTS=<id>[,<id>…]` (`*` for all).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// One known failure of one tool.
+#[derive(Debug)]
+pub struct Hint {
+    pub id: &'static str,
+    pub tool: &'static str,
+    /// Regex over the tool's stderr. Capture groups can be used as `$1` in `next`.
+    pub pattern: &'static str,
+    /// What went wrong, in a sentence.
+    pub explain: &'static str,
+    /// What to run next.
+    pub next: &'static str,
+}
+
+/// Checked in order, so put specific patterns before general ones of the same tool.
+/// limactl logs in logfmt, so its patterns allow `\"` for quotes.
+pub const HINTS: &[Hint] = &[
+    // ── kubectl ──────────────────────────────────────────────────────────────
+    Hint {
+        id: "kubectl-no-kubeconfig",
+        tool: "kubectl",
+        pattern: r"connection to the server localhost:8080 was refused",
+        explain: "kubectl found no kubeconfig and fell back to localhost:8080",
+        next: "export KUBECONFIG=~/.kube/config",
+    },
+    Hint {
+        id: "kubectl-certificate",
+        tool: "kubectl",
+        pattern: r"x509: certificate (?:has expired|signed by unknown authority)",
+        explain: "the cluster's certificate doesn't match your kubeconfig — the cluster was probably recreated",
+        next: "kubectl config view --minify",
+    },
+    Hint {
+        id: "kubectl-unreachable",
+        tool: "kubectl",
+        pattern: r"Unable to connect to the server|connect: connection refused|i/o timeout",
+        explain: "the Kubernetes API server isn't answering — the cluster (or the VM it runs in) is not running",
+        next: "kubectl cluster-info",
+    },
+    Hint {
+        id: "kubectl-unknown-context",
+        tool: "kubectl",
+        pattern: r#"context "([^"]+)" (?:does not exist|not found)"#,
+        explain: "the k8s_context in A3sfile.hcl is not in your kubeconfig",
+        next: "kubectl config get-contexts",
+    },
+    Hint {
+        id: "kubectl-missing-namespace",
+        tool: "kubectl",
+        pattern: r#"namespaces "([^"]+)" not found"#,
+        explain: "the k8s_namespace does not exist in this cluster yet",
+        next: "kubectl create namespace $1",
+    },
+    Hint {
+        id: "kubectl-forbidden",
+        tool: "kubectl",
+        pattern: r#"is forbidden: User "([^"]*)" cannot"#,
+        explain: "your kubeconfig user lacks the RBAC permission for this",
+        next: "kubectl auth can-i --list",
+    },
+    // ── limactl ──────────────────────────────────────────────────────────────
+    Hint {
+        id: "lima-no-instance",
+        tool: "limactl",
+        pattern: r#"instance \\?"([^"\\]+)\\?" does not exist"#,
+        explain: "there is no Lima VM with that name",
+        next: "limactl list",
+    },
+    Hint {
+        id: "lima-stopped",
+        tool: "limactl",
+        pattern: r#"expected status \\?"Running\\?", got \\?"Stopped"#,
+        explain: "the Lima VM is stopped",
+        next: "limactl list",
+    },
+    // ── brew ─────────────────────────────────────────────────────────────────
+    Hint {
+        id: "brew-no-formula",
+        tool: "brew",
+        pattern: r#"No available formula with the name "([^"]+)""#,
+        explain: "Homebrew has no formula by that name",
+        next: "brew search $1",
+    },
+    Hint {
+        id: "brew-locked",
+        tool: "brew",
+        pattern: r"has already locked|Another active Homebrew .* process",
+        explain: "another brew command is still running",
+        next: "pgrep -fl brew",
+    },
+    // ── a3s-box ──────────────────────────────────────────────────────────────
+    Hint {
+        id: "box-no-virtualization",
+        tool: "a3s-box",
+        pattern: r"(?i)/dev/kvm|virtualization (?:is )?not (?:supported|available)|hypervisor",
+        explain: "a3s-box needs hardware virtualization (KVM on Linux, Hypervisor.framework on macOS)",
+        next: "a3s box info",
+    },
+    Hint {
+        id: "box-image-not-found",
+        tool: "a3s-box",
+        pattern: r"(?i)manifest unknown|pull access denied|repository does not exist",
+        explain: "the image doesn't exist, or the registry needs you to log in",
+        next: "a3s box images",
+    },
+    Hint {
+        id: "box-disk-full",
+        tool: "a3s-box",
+        pattern: r"(?i)no space left on device",
+        explain: "the disk holding a3s-box images is full",
+        next: "a3s box images",
+    },
+];
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Keep the full tool output in errors (`-v`).
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+fn compiled() -> &'static [Regex] {
+    static RE: OnceLock<Vec<Regex>> = OnceLock::new();
+    RE.get_or_init(|| {
+        HINTS
+            .iter()
+            .map(|h| Regex::new(h.pattern).expect("hint patterns are valid"))
+            .collect()
+    })
+}
+
+/// The first hint of `tool` matching `stderr` that is not suppressed, with `next`
+/// expanded from the match.
+pub fn lookup(tool: &str, stderr: &str, suppressed: &[&str]) -> Option<(&'static Hint, String)> {
+    if suppressed.contains(&"*") {
+        return None;
+    }
+    HINTS
+        .iter()
+        .zip(compiled())
+        .filter(|(h, _)| h.tool == tool && !suppressed.contains(&h.id))
+        .find_map(|(h, re)| {
+            let caps = re.captures(stderr)?;
+            let mut next = String::new();
+            caps.expand(h.next, &mut next);
+            Some((h, next))
+        })
+}
+
+/// `stderr` of a failed `tool` run as it should appear in an error message.
+pub fn render(tool: &str, stderr: &str, verbose: bool, suppressed: &[&str]) -> String {
+    let raw = stderr.trim();
+    let Some((hint, next)) = lookup(tool, raw, suppressed) else {
+        return raw.to_string();
+    };
+    let shown = if verbose {
+        raw
+    } else {
+        raw.lines().rfind(|l| !l.trim().is_empty()).unwrap_or(raw)
+    };
+    let mut out = format!(
+        "{shown}\n  hint: {}\n  try:  {next}\n  (hint {}; silence with A3S_NO_HINTS={})",
+        hint.explain, hint.id, hint.id
+    );
+    if !verbose && shown.len() < raw.len() {
+        out.push_str("\n  run with -v for the full output");
+    }
+    out
+}
+
+/// [`render`] with the `-v` flag and `A3S_NO_HINTS` of this run.
+pub fn explain(tool: &str, stderr: &str) -> String {
+    let suppressed = std::env::var("A3S_NO_HINTS").unwrap_or_default();
+    let suppressed: Vec<&str> = suppressed.split(',').map(str::trim).collect();
+    render(tool, stderr, VERBOSE.load(Ordering::Relaxed), &suppressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(tool: &str, stderr: &str) -> Option<&'static str> {
+        lookup(tool, stderr, &[]).map(|(h, _)| h.id)
+    }
+
+    #[test]
+    fn test_all_patterns_compile_and_ids_are_unique() {
+        assert_eq!(compiled().len(), HINTS.len());
+        let mut ids: Vec<_> = HINTS.iter().map(|h| h.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), HINTS.len());
+    }
+
+    #[test]
+    fn test_kubectl_samples() {
+        assert_eq!(
+            id(
+                "kubectl",
+                "Unable to connect to the server: dial tcp 127.0.0.1:6443: connect: connection refused"
+            ),
+            Some("kubectl-unreachable")
+        );
+        assert_eq!(
+            id(
+                "kubectl",
+                "The connection to the server localhost:8080 was refused - did you specify the right host or port?"
+            ),
+            Some("kubectl-no-kubeconfig")
+        );
+        assert_eq!(
+            id("kubectl", "error: context \"k3d-dev\" does not exist"),
+            Some("kubectl-unknown-context")
+        );
+        assert_eq!(
+            id(
+                "kubectl",
+                "Error from server (Forbidden): pods is forbidden: User \"dev\" cannot list resource \"pods\" in API group \"\" in the namespace \"a3s\""
+            ),
+            Some("kubectl-forbidden")
+        );
+        assert_eq!(
+            id(
+                "kubectl",
+                "Unable to connect to the server: x509: certificate signed by unknown authority"
+            ),
+            Some("kubectl-certificate")
+        );
+    }
+
+    #[test]
+    fn test_capture_expands_into_next_command() {
+        let (hint, next) = lookup(
+            "kubectl",
+            "Error from server (NotFound): error when creating \"STDIN\": namespaces \"a3s-dev\" not found",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(hint.id, "kubectl-missing-namespace");
+        assert_eq!(next, "kubectl create namespace a3s-dev");
+
+        let (_, next) = lookup(
+            "brew",
+            "Warning: No available formula with the name \"limactl\". Did you mean lima?",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(next, "brew search limactl");
+    }
+
+    #[test]
+    fn test_limactl_brew_and_box_samples() {
+        assert_eq!(
+            id(
+                "limactl",
+                "time=\"2024-05-01T10:00:00Z\" level=fatal msg=\"instance \\\"k3s\\\" does not exist, run `limactl create k3s` to create a new instance\""
+            ),
+            Some("lima-no-instance")
+        );
+        assert_eq!(
+            id("limactl", "FATA[0000] instance \"k3s\" does not exist"),
+            Some("lima-no-instance")
+        );
+        assert_eq!(
+            id(
+                "limactl",
+                "FATA[0000] expected status \"Running\", got \"Stopped\""
+            ),
+            Some("lima-stopped")
+        );
+        assert_eq!(
+            id(
+                "brew",
+                "Error: A `brew install` process has already locked /opt/homebrew/Cellar/lima"
+            ),
+            Some("brew-locked")
+        );
+        assert_eq!(
+            id(
+                "a3s-box",
+                "Error: failed to open /dev/kvm: Permission denied"
+            ),
+            Some("box-no-virtualization")
+        );
+        assert_eq!(
+            id(
+                "a3s-box",
+                "Error: pull failed: docker.io/library/postgress:16: manifest unknown"
+            ),
+            Some("box-image-not-found")
+        );
+    }
+
+    #[test]
+    fn test_hints_are_scoped_to_their_tool() {
+        assert_eq!(id("a3s-box", "connect: connection refused"), None);
+        assert_eq!(id("kubectl", "no space left on device"), None);
+    }
+
+    #[test]
+    fn test_render_shortens_unless_verbose() {
+        let stderr = "E0501 10:00:00 memcache.go:265] couldn't get current server API group list\nUnable to connect to the server: dial tcp 127.0.0.1:6443: connect: connection refused\n";
+        let short = render("kubectl", stderr, false, &[]);
+        assert!(
+            short.starts_with("Unable to connect to the server"),
+            "{short}"
+        );
+        assert!(short.contains("try:  kubectl cluster-info"));
+        assert!(short.contains("run with -v"));
+
+        let full = render("kubectl", stderr, true, &[]);
+        assert!(full.starts_with("E0501"), "{full}");
+        assert!(!full.contains("run with -v"));
+    }
+
+    #[test]
+    fn test_suppression_and_unknown_errors() {
+        let stderr = "Unable to connect to the server: EOF";
+        assert_eq!(
+            render("kubectl", stderr, false, &["kubectl-unreachable"]),
+            stderr
+        );
+        assert_eq!(render("kubectl", stderr, false, &["*"]), stderr);
+        assert_eq!(
+            render("kubectl", "error: something new\n", false, &[]),
+            "error: something new"
+        );
+    }
+}
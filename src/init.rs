@@ -0,0 +1,395 @@
+//! `a3s init` — scaffold a starter A3sfile.hcl by inspecting the project directory.
+//!
+//! A Procfile is taken as the authoritative process list when present. Otherwise each
+//! recognised project type (Node, Rust, Go) contributes one service. Anything that is
+//! an assumption rather than something read from a file is recorded as a guess so the
+//! summary can point it out.
+
+use std::path::Path;
+
+/// A service inferred from the project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedService {
+    pub name: String,
+    pub cmd: String,
+    /// 0 = unknown, left to auto-assignment.
+    pub port: u16,
+    pub subdomain: Option<String>,
+    /// Where the service came from, e.g. "package.json scripts.dev".
+    pub source: String,
+    /// Assumptions made while building the service.
+    pub guesses: Vec<String>,
+}
+
+/// Everything `a3s init` found in a directory.
+#[derive(Debug, Default)]
+pub struct Detection {
+    pub services: Vec<DetectedService>,
+    /// Files that were recognised but not converted.
+    pub notes: Vec<String>,
+}
+
+/// Inspect `dir` and infer services.
+pub fn detect(dir: &Path) -> Detection {
+    let mut out = Detection::default();
+    if let Some(procfile) = read(dir, "Procfile") {
+        out.services = detect_procfile(&procfile);
+    } else {
+        out.services
+            .extend(read(dir, "package.json").and_then(|s| detect_node(dir, &s)));
+        out.services
+            .extend(read(dir, "Cargo.toml").and_then(|s| detect_rust(&s)));
+        out.services
+            .extend(read(dir, "go.mod").and_then(|s| detect_go(&s)));
+        dedupe_names(&mut out.services);
+    }
+    for compose in ["docker-compose.yml", "docker-compose.yaml", "compose.yaml"] {
+        if dir.join(compose).exists() {
+            out.notes.push(format!(
+                "{compose} found — container services are not converted; add them by hand"
+            ));
+        }
+    }
+    out
+}
+
+/// Render detected services as A3sfile.hcl source.
+pub fn render(services: &[DetectedService]) -> String {
+    let mut out = String::from("# Generated by `a3s init` — review before running `a3s up`.\n");
+    if services.is_empty() {
+        out.push_str(
+            "\n# Nothing recognisable was found. Example:\n\
+             #\n\
+             # service \"web\" {\n\
+             #   cmd  = \"npm run dev\"\n\
+             #   port = 3000\n\
+             # }\n",
+        );
+        return out;
+    }
+    for svc in services {
+        out.push_str(&format!("\nservice {} {{\n", quote(&svc.name)));
+        out.push_str(&format!("  cmd = {}\n", quote(&svc.cmd)));
+        if svc.port != 0 {
+            out.push_str(&format!("  port = {}\n", svc.port));
+        }
+        if let Some(sub) = &svc.subdomain {
+            out.push_str(&format!("  subdomain = {}\n", quote(sub)));
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+fn read(dir: &Path, file: &str) -> Option<String> {
+    std::fs::read_to_string(dir.join(file)).ok()
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// ── Procfile ─────────────────────────────────────────────────────────────────
+
+fn detect_procfile(contents: &str) -> Vec<DetectedService> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_once(':'))
+        .map(|(name, cmd)| {
+            let cmd = cmd.trim().to_string();
+            let port = port_in_command(&cmd).unwrap_or(0);
+            let name = name.trim().to_string();
+            DetectedService {
+                subdomain: (name == "web").then(|| name.clone()),
+                source: "Procfile".into(),
+                guesses: if port == 0 {
+                    vec![format!("{name}: port auto-assigned (exported as $PORT)")]
+                } else {
+                    vec![]
+                },
+                name,
+                cmd,
+                port,
+            }
+        })
+        .collect()
+}
+
+// ── Node ─────────────────────────────────────────────────────────────────────
+
+fn detect_node(dir: &Path, package_json: &str) -> Option<DetectedService> {
+    let pkg: serde_json::Value = serde_json::from_str(package_json).ok()?;
+    let scripts = pkg["scripts"].as_object()?;
+    let (script, body) = ["dev", "start", "serve"]
+        .iter()
+        .find_map(|k| scripts.get(*k).and_then(|v| v.as_str()).map(|b| (*k, b)))?;
+
+    let runner = if dir.join("pnpm-lock.yaml").exists() {
+        "pnpm"
+    } else if dir.join("yarn.lock").exists() {
+        "yarn"
+    } else if dir.join("bun.lockb").exists() || dir.join("bun.lock").exists() {
+        "bun run"
+    } else {
+        "npm run"
+    };
+
+    let mut guesses = Vec::new();
+    let port = match port_in_command(body) {
+        Some(p) => p,
+        None => match framework_default_port(body) {
+            Some((framework, p)) => {
+                guesses.push(format!("web: port {p} is the {framework} default"));
+                p
+            }
+            None => {
+                guesses.push("web: port auto-assigned (exported as $PORT)".into());
+                0
+            }
+        },
+    };
+    Some(DetectedService {
+        name: "web".into(),
+        cmd: format!("{runner} {script}"),
+        port,
+        subdomain: Some("web".into()),
+        source: format!("package.json scripts.{script}"),
+        guesses,
+    })
+}
+
+/// Default dev-server ports of common frameworks, keyed by the binary in the script.
+fn framework_default_port(script: &str) -> Option<(&'static str, u16)> {
+    const DEFAULTS: &[(&str, u16)] = &[
+        ("vite", 5173),
+        ("next", 3000),
+        ("nuxt", 3000),
+        ("astro", 4321),
+        ("remix", 3000),
+        ("react-scripts", 3000),
+        ("ng", 4200),
+        ("svelte-kit", 5173),
+    ];
+    let first = script.split_whitespace().next()?;
+    DEFAULTS.iter().find(|(bin, _)| *bin == first).copied()
+}
+
+/// A port given explicitly in a command: `--port 3000`, `--port=3000`, `-p 3000`,
+/// or `PORT=3000`.
+fn port_in_command(cmd: &str) -> Option<u16> {
+    let words: Vec<&str> = cmd.split_whitespace().collect();
+    for (i, w) in words.iter().enumerate() {
+        if let Some(v) = w
+            .strip_prefix("--port=")
+            .or_else(|| w.strip_prefix("PORT="))
+        {
+            if let Ok(p) = v.parse() {
+                return Some(p);
+            }
+        }
+        if matches!(*w, "--port" | "-p") {
+            if let Some(Ok(p)) = words.get(i + 1).map(|v| v.parse()) {
+                return Some(p);
+            }
+        }
+    }
+    None
+}
+
+// ── Rust ─────────────────────────────────────────────────────────────────────
+
+fn detect_rust(cargo_toml: &str) -> Option<DetectedService> {
+    let mut in_package = false;
+    let mut name = None;
+    for line in cargo_toml.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+        if in_package {
+            if let Some(v) = line.strip_prefix("name").map(str::trim_start) {
+                if let Some(v) = v.strip_prefix('=') {
+                    name = Some(v.trim().trim_matches('"').to_string());
+                }
+            }
+        }
+    }
+    // A virtual workspace manifest has no package to run.
+    let name = name?;
+    Some(DetectedService {
+        cmd: "cargo run".into(),
+        port: 0,
+        subdomain: None,
+        source: "Cargo.toml".into(),
+        guesses: vec![format!(
+            "{name}: port auto-assigned (exported as $PORT) — read it in your server"
+        )],
+        name,
+    })
+}
+
+// ── Go ───────────────────────────────────────────────────────────────────────
+
+fn detect_go(go_mod: &str) -> Option<DetectedService> {
+    let module = go_mod
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("module "))?
+        .trim();
+    let name = module.rsplit('/').next().unwrap_or(module).to_string();
+    Some(DetectedService {
+        cmd: "go run .".into(),
+        port: 0,
+        subdomain: None,
+        source: "go.mod".into(),
+        guesses: vec![format!(
+            "{name}: port auto-assigned (exported as $PORT) — read it in your server"
+        )],
+        name,
+    })
+}
+
+/// Keep service names unique when several detectors pick the same one.
+fn dedupe_names(services: &mut [DetectedService]) {
+    let mut seen = std::collections::HashSet::new();
+    for svc in services.iter_mut() {
+        let base = svc.name.clone();
+        let mut n = 2;
+        while !seen.insert(svc.name.clone()) {
+            svc.name = format!("{base}-{n}");
+            n += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DevConfig;
+
+    fn fixture(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, contents) in files {
+            std::fs::write(dir.path().join(name), contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_detect_node_vite_with_pnpm() {
+        let dir = fixture(&[
+            (
+                "package.json",
+                r#"{"name": "app", "scripts": {"dev": "vite", "build": "vite build"}}"#,
+            ),
+            ("pnpm-lock.yaml", ""),
+        ]);
+        let found = detect(dir.path());
+        assert_eq!(found.services.len(), 1);
+        let web = &found.services[0];
+        assert_eq!(web.name, "web");
+        assert_eq!(web.cmd, "pnpm dev");
+        assert_eq!(web.port, 5173);
+        assert_eq!(web.source, "package.json scripts.dev");
+        assert!(web.guesses[0].contains("vite default"));
+    }
+
+    #[test]
+    fn test_detect_node_explicit_port_and_start_script() {
+        let dir = fixture(&[(
+            "package.json",
+            r#"{"scripts": {"start": "node server.js --port 8080"}}"#,
+        )]);
+        let web = &detect(dir.path()).services[0];
+        assert_eq!(web.cmd, "npm run start");
+        assert_eq!(web.port, 8080);
+        assert!(web.guesses.is_empty());
+    }
+
+    #[test]
+    fn test_detect_rust_package() {
+        let dir = fixture(&[(
+            "Cargo.toml",
+            "[package]\nname = \"api-server\"\nversion = \"0.1.0\"\n\n[dependencies]\nname = \"x\"\n",
+        )]);
+        let svc = &detect(dir.path()).services[0];
+        assert_eq!(svc.name, "api-server");
+        assert_eq!(svc.cmd, "cargo run");
+        assert_eq!(svc.port, 0);
+    }
+
+    #[test]
+    fn test_rust_virtual_workspace_is_skipped() {
+        let dir = fixture(&[("Cargo.toml", "[workspace]\nmembers = [\"a\", \"b\"]\n")]);
+        assert!(detect(dir.path()).services.is_empty());
+    }
+
+    #[test]
+    fn test_detect_go_module() {
+        let dir = fixture(&[("go.mod", "module github.com/acme/billing\n\ngo 1.22\n")]);
+        let svc = &detect(dir.path()).services[0];
+        assert_eq!(svc.name, "billing");
+        assert_eq!(svc.cmd, "go run .");
+    }
+
+    #[test]
+    fn test_procfile_wins_over_language_detection() {
+        let dir = fixture(&[
+            (
+                "Procfile",
+                "# processes\nweb: bundle exec rails s -p 3000\nworker: bundle exec sidekiq\n",
+            ),
+            ("package.json", r#"{"scripts": {"dev": "vite"}}"#),
+        ]);
+        let found = detect(dir.path());
+        let names: Vec<&str> = found.services.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["web", "worker"]);
+        assert_eq!(found.services[0].cmd, "bundle exec rails s -p 3000");
+        assert_eq!(found.services[0].port, 3000);
+        assert_eq!(found.services[0].subdomain.as_deref(), Some("web"));
+        assert_eq!(found.services[1].port, 0);
+    }
+
+    #[test]
+    fn test_compose_file_is_noted() {
+        let dir = fixture(&[("docker-compose.yml", "services: {}\n")]);
+        let found = detect(dir.path());
+        assert!(found.services.is_empty());
+        assert!(found.notes[0].contains("docker-compose.yml"));
+    }
+
+    #[test]
+    fn test_mixed_project_names_are_unique() {
+        let dir = fixture(&[
+            ("package.json", r#"{"scripts": {"dev": "next dev"}}"#),
+            ("go.mod", "module example.com/web\n"),
+        ]);
+        let names: Vec<String> = detect(dir.path())
+            .services
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["web", "web-2"]);
+    }
+
+    #[test]
+    fn test_render_round_trips_through_config_parser() {
+        let dir = fixture(&[(
+            "Procfile",
+            "web: PORT=4000 node \"server.js\"\nworker: node worker.js\n",
+        )]);
+        let hcl = render(&detect(dir.path()).services);
+        let cfg: DevConfig = hcl::from_str(&hcl).unwrap();
+        assert_eq!(cfg.service["web"].cmd, "PORT=4000 node \"server.js\"");
+        assert_eq!(cfg.service["web"].port, 4000);
+        assert_eq!(cfg.service["worker"].port, 0);
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn test_render_empty_is_valid_hcl() {
+        let cfg: DevConfig = hcl::from_str(&render(&[])).unwrap();
+        assert!(cfg.service.is_empty());
+    }
+}
@@ -1,5 +1,21 @@
+use std::net::Ipv4Addr;
 use std::path::PathBuf;
 
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpStream, UnixStream};
+
+use crate::error::{DevError, Result};
+
+/// IPC protocol version. Bump it only for changes an older peer would misread —
+/// renamed or removed requests, responses or fields. New variants and defaulted
+/// fields don't count: an older daemon answers them with `bad request`.
+/// v2 replaced the unreleased v1 hello (`client_version` / `server_version`).
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// The oldest protocol version the daemon still serves, and the oldest daemon this
+/// client talks to.
+pub const MIN_SUPPORTED_VERSION: u32 = 2;
+
 /// Return a project-specific socket path derived from the canonical directory
 /// that contains `config_path`. Two projects on the same machine get distinct
 /// sockets so their daemons never interfere with each other.
@@ -15,10 +31,75 @@ pub fn socket_path(config_path: &std::path::Path) -> PathBuf {
     std::env::temp_dir().join(format!("a3s-{:08x}.sock", h as u32))
 }
 
+/// Where a project's daemon listens: its Unix socket, and 127.0.0.1 on
+/// `ipc_tcp_port` when the A3sfile sets one.
+#[derive(Debug, Clone)]
+pub struct IpcAddr {
+    pub socket: PathBuf,
+    pub tcp_port: Option<u16>,
+    /// What the hello presents for TCP: `A3S_IPC_TOKEN`, or the daemon's
+    /// `.a3s/ipc-token`.
+    pub token: Option<String>,
+}
+
+/// A connection to the daemon over either transport.
+pub trait IpcIo: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> IpcIo for T {}
+
+impl IpcAddr {
+    pub fn for_project(config_path: &std::path::Path) -> Self {
+        let tcp_port = crate::config::DevConfig::from_file(config_path)
+            .ok()
+            .and_then(|c| c.dev.ipc_tcp_port);
+        let token = tcp_port.and_then(|_| {
+            std::env::var("A3S_IPC_TOKEN").ok().or_else(|| {
+                std::fs::read_to_string(crate::daemon::ipc_token_path(config_path))
+                    .ok()
+                    .map(|t| t.trim().to_string())
+            })
+        });
+        Self {
+            socket: socket_path(config_path),
+            tcp_port,
+            token,
+        }
+    }
+
+    /// Whether this project's own daemon listens on the Unix socket. Unlike
+    /// [`connect`](Self::connect) it never tries TCP: whatever answers on the port may
+    /// be another project's daemon.
+    pub async fn local_daemon_listens(&self) -> bool {
+        UnixStream::connect(&self.socket).await.is_ok()
+    }
+
+    /// Connect over the Unix socket, falling back to TCP when the socket isn't
+    /// reachable and a port is configured.
+    pub async fn connect(&self) -> std::io::Result<Box<dyn IpcIo>> {
+        match UnixStream::connect(&self.socket).await {
+            Ok(stream) => Ok(Box::new(stream)),
+            Err(e) => match self.tcp_port {
+                Some(port) => Ok(Box::new(
+                    TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await?,
+                )),
+                None => Err(e),
+            },
+        }
+    }
+}
+
 /// IPC request from client commands to the running daemon.
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "cmd", rename_all = "snake_case")]
 pub enum IpcRequest {
+    /// First line of every connection: the client's [`PROTOCOL_VERSION`]. The daemon
+    /// refuses any other request before it.
+    Hello {
+        version: u32,
+        /// Required over TCP, where any local user can connect: the daemon's
+        /// `.a3s/ipc-token`. The owner-only Unix socket needs none.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+    },
     Status,
     Stop {
         services: Vec<String>,
@@ -26,22 +107,113 @@ pub enum IpcRequest {
     Restart {
         service: String,
     },
+    /// Send a signal (e.g. "SIGHUP") to a running service without stopping it.
+    Kill {
+        service: String,
+        signal: String,
+    },
+    /// Start stopped services and their dependencies; running ones are left alone.
+    /// Answered with `Started`, one result per service — "already running" included.
+    Start {
+        services: Vec<String>,
+    },
+    /// Lines of these services (all when empty) as they're captured; unknown names
+    /// are refused up front.
     Logs {
+        /// Clients from before multi-service follow send a single `service`.
+        #[serde(default, alias = "service", deserialize_with = "one_or_many")]
         services: Vec<String>,
         follow: bool,
+        /// Only send lines matching this regex (not matching with `invert`).
+        #[serde(default)]
+        grep: Option<String>,
+        #[serde(default)]
+        invert: bool,
     },
     History {
         services: Vec<String>,
         lines: usize,
+        /// Only lines captured since then: how long ago (`"10m"`) or an RFC 3339 time.
+        #[serde(default)]
+        since: Option<String>,
+        /// Of those lines, only the ones matching this regex (not matching with
+        /// `invert`).
+        #[serde(default)]
+        grep: Option<String>,
+        #[serde(default)]
+        invert: bool,
     },
     /// Reload A3sfile.hcl without restarting unchanged services.
-    Reload,
+    Reload {
+        /// Leave changed services running as they are; they're reported instead.
+        #[serde(default)]
+        no_restart: bool,
+    },
+    /// Expose a running service through a temporary reverse tunnel.
+    Share {
+        service: String,
+        /// Tear the share down after this many seconds; `None` = until stopped.
+        ttl_secs: Option<u64>,
+    },
+    /// Stop sharing a service.
+    Unshare {
+        service: String,
+    },
+    /// `kubectl apply -f` a manifest file or directory (relative to A3sfile.hcl).
+    KubeDeploy {
+        path: PathBuf,
+    },
+    /// `kubectl delete -f` a manifest file or directory (relative to A3sfile.hcl).
+    KubeUndeploy {
+        path: PathBuf,
+    },
+    /// Health-check uptime, flaps and outages over the last 24h.
+    HealthReport,
+    /// CPU and memory of every running service, as last sampled.
+    Metrics,
+    /// Stream an `Event` for every state change of these services (all when empty),
+    /// until the client disconnects.
+    Events {
+        #[serde(default)]
+        services: Vec<String>,
+    },
+    /// `kubectl create namespace`.
+    KubeCreateNamespace {
+        name: String,
+    },
+    /// `kubectl delete namespace`; `force` drops the grace period.
+    KubeDeleteNamespace {
+        name: String,
+        #[serde(default)]
+        force: bool,
+    },
+    /// `kubectl exec` a one-off command in a pod; answered with `Output`.
+    KubePodExec {
+        /// Defaults to `k8s_namespace`.
+        #[serde(default)]
+        namespace: Option<String>,
+        pod: String,
+        #[serde(default)]
+        container: Option<String>,
+        command: Vec<String>,
+    },
+    /// Several one-shot requests in one round-trip — at most 64, changes run one at a
+    /// time in order and only reads concurrently; answered with one `Batch` holding a
+    /// response per request, in request order. Streaming requests and nested batches
+    /// get an `Error` in their place.
+    Batch {
+        requests: Vec<IpcRequest>,
+    },
 }
 
 /// IPC response from daemon to client.
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum IpcResponse {
+    Hello {
+        version: u32,
+        min_supported: u32,
+    },
     Status {
         rows: Vec<StatusRow>,
     },
@@ -53,15 +225,103 @@ pub enum IpcResponse {
         service: String,
         line: String,
         color_idx: usize,
+        /// Capture time in milliseconds since the Unix epoch (absent from older daemons).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ts_ms: Option<u64>,
+        /// `"stdout"` or `"stderr"` (absent from older daemons).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stream: Option<String>,
     },
     Reloaded {
         started: Vec<String>,
         stopped: Vec<String>,
         restarted: Vec<String>,
+        /// Changed but left running because of `no_restart`.
+        #[serde(default)]
+        not_restarted: Vec<String>,
     },
     Stopped {
         services: Vec<String>,
     },
+    /// Answer to `Start`: what happened to each requested service and dependency.
+    Started {
+        results: Vec<StartResult>,
+    },
+    Shared {
+        service: String,
+        url: String,
+        expires_in_secs: Option<u64>,
+    },
+    HealthReport {
+        rows: Vec<HealthReportRow>,
+    },
+    Metrics {
+        entries: Vec<ServiceMetrics>,
+    },
+    Event(ServiceEvent),
+    Output {
+        stdout: String,
+    },
+    Batch {
+        responses: Vec<IpcResponse>,
+    },
+}
+
+/// A list of names, or the single (possibly null) name older clients send.
+fn one_or_many<'de, D: serde::Deserializer<'de>>(
+    d: D,
+) -> std::result::Result<Vec<String>, D::Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Option<String>),
+        Many(Vec<String>),
+    }
+    Ok(match <OneOrMany as serde::Deserialize>::deserialize(d)? {
+        OneOrMany::One(name) => name.into_iter().collect(),
+        OneOrMany::Many(names) => names,
+    })
+}
+
+/// The daemon's answer to a [`IpcRequest::Hello`] from a `version` client: an
+/// error, after which it closes the connection, when the client is too old.
+pub fn hello_response(version: u32) -> IpcResponse {
+    if version < MIN_SUPPORTED_VERSION {
+        IpcResponse::Error {
+            msg: format!(
+                "this a3s speaks IPC protocol v{version} but the running daemon needs at \
+                 least v{MIN_SUPPORTED_VERSION} — upgrade a3s, or restart the daemon with \
+                 the a3s you have"
+            ),
+        }
+    } else {
+        IpcResponse::Hello {
+            version: PROTOCOL_VERSION,
+            min_supported: MIN_SUPPORTED_VERSION,
+        }
+    }
+}
+
+/// Check the daemon's answer to our hello.
+pub fn check_hello(resp: &IpcResponse) -> Result<()> {
+    match resp {
+        IpcResponse::Hello { version, .. } if *version >= MIN_SUPPORTED_VERSION => Ok(()),
+        IpcResponse::Hello { version, .. } => Err(DevError::Config(daemon_too_old(*version))),
+        // v1 daemons can't read our hello — those from before the handshake don't
+        // know the request at all.
+        IpcResponse::Error { msg } if msg.starts_with("bad request") => {
+            Err(DevError::Config(daemon_too_old(1)))
+        }
+        IpcResponse::Error { msg } => Err(DevError::Config(msg.clone())),
+        _ => Err(DevError::Config("unexpected IPC response to hello".into())),
+    }
+}
+
+fn daemon_too_old(version: u32) -> String {
+    format!(
+        "this a3s speaks IPC protocol v{PROTOCOL_VERSION} but the running daemon speaks \
+         v{version} — restart the daemon with `a3s down && a3s up`"
+    )
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -71,11 +331,122 @@ pub struct StatusRow {
     pub pid: Option<u32>,
     pub port: u16,
     pub subdomain: Option<String>,
-    pub uptime_secs: Option<u64>,
+    /// Seconds since the current process started (reset on restart); 0 without one.
+    #[serde(default, deserialize_with = "zero_if_null")]
+    pub uptime_secs: u64,
+    /// CPU use of the process and its descendants, in percent of one core.
+    #[serde(default)]
+    pub cpu_percent: Option<f64>,
+    /// Resident memory of the process and its descendants, in bytes.
+    #[serde(default)]
+    pub rss_bytes: Option<u64>,
     pub proxy_port: u16,
     pub restart_count: u32,
     /// None = no health check configured; Some(true/false) = last check result.
     pub healthy: Option<bool>,
+    /// `healthy`, `starting` (no check passed yet), `unhealthy (2/3 failures)`, or
+    /// `none` without a health block or a process to check.
+    #[serde(default = "no_health")]
+    pub health: String,
+    /// Unix seconds of the last passing health check.
+    #[serde(default)]
+    pub last_healthy_at: Option<u64>,
+    /// Why the last failing health check failed.
+    #[serde(default)]
+    pub last_health_error: Option<String>,
+    /// Public URL of an active `a3s share` tunnel, if any.
+    #[serde(default)]
+    pub share_url: Option<String>,
+    /// The pre-warmed spare of a `standby = true` service, if one is up.
+    #[serde(default)]
+    pub standby: Option<StandbyRow>,
+    /// Health-check uptime over the last 24h is below `dev.flaky_threshold`.
+    #[serde(default)]
+    pub flaky: bool,
+    /// Image of a `container` block; None for process services.
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Why a `blocked` service didn't start, naming the dependency.
+    #[serde(default)]
+    pub blocked: Option<String>,
+    /// Why a `failed` service couldn't be started, e.g. a secret that didn't resolve.
+    #[serde(default)]
+    pub start_error: Option<String>,
+    /// Exit code of the last process of a `failed` or `crash-looped` service.
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// The last log lines of a `crash-looped` service, oldest first.
+    #[serde(default)]
+    pub last_logs: Vec<String>,
+}
+
+fn no_health() -> String {
+    "none".into()
+}
+
+/// `null`, as older daemons send for a service without a process, reads as 0.
+fn zero_if_null<'de, D: serde::Deserializer<'de>>(d: D) -> std::result::Result<u64, D::Error> {
+    Ok(<Option<u64> as serde::Deserialize>::deserialize(d)?.unwrap_or(0))
+}
+
+/// What a `Start` request did for one service.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StartResult {
+    pub service: String,
+    /// "started", "already running", "blocked" (a dependency didn't come up) or
+    /// "failed".
+    pub outcome: String,
+    /// Why the service is blocked or failed.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StandbyRow {
+    /// "starting" while warming up, "running" once healthy and ready to promote.
+    pub state: String,
+    pub pid: Option<u32>,
+    pub port: u16,
+}
+
+/// Health-check statistics of one service over the last 24h.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HealthReportRow {
+    pub name: String,
+    /// Number of recorded health checks.
+    pub checks: u32,
+    /// Percentage of passing checks; `None` until the first check.
+    pub uptime_pct: Option<f64>,
+    /// Times the service went from passing to failing.
+    pub flaps: u32,
+    /// Longest run of failing checks, including one still ongoing.
+    pub longest_outage_secs: u64,
+    pub flaky: bool,
+}
+
+/// Resource usage of one running service's process tree.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ServiceMetrics {
+    pub service: String,
+    pub pid: u32,
+    /// Percent of one core, measured over one second.
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub uptime_secs: u64,
+}
+
+/// A service changing state, e.g. from "running" to "restarting".
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ServiceEvent {
+    pub service: String,
+    /// `None` for the first change seen since the daemon started.
+    pub old_state: Option<String>,
+    pub new_state: String,
+    /// Milliseconds since the Unix epoch.
+    pub ts_ms: u64,
+    /// e.g. "exited with code 1" or "file changed".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
 }
 
 #[cfg(test)]
@@ -96,6 +467,78 @@ mod tests {
         assert_eq!(a, b);
     }
 
+    #[tokio::test]
+    async fn test_local_daemon_probe_ignores_the_tcp_port() {
+        // Something — say another project's daemon — answers on the port.
+        let tcp = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let addr = IpcAddr {
+            socket: dir.path().join("none.sock"),
+            tcp_port: Some(tcp.local_addr().unwrap().port()),
+            token: None,
+        };
+        assert!(addr.connect().await.is_ok());
+        assert!(!addr.local_daemon_listens().await);
+
+        let _unix = tokio::net::UnixListener::bind(&addr.socket).unwrap();
+        assert!(addr.local_daemon_listens().await);
+    }
+
+    #[test]
+    fn test_hello_handshake() {
+        let json = serde_json::to_string(&IpcRequest::Hello {
+            version: PROTOCOL_VERSION,
+            token: None,
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"cmd":"hello","version":2}"#);
+
+        let resp = hello_response(PROTOCOL_VERSION);
+        assert_eq!(
+            serde_json::to_string(&resp).unwrap(),
+            r#"{"type":"hello","version":2,"min_supported":2}"#
+        );
+        check_hello(&resp).unwrap();
+        // A newer client is served; it decides whether this daemon is new enough.
+        check_hello(&hello_response(PROTOCOL_VERSION + 1)).unwrap();
+        let err = check_hello(&IpcResponse::Hello {
+            version: MIN_SUPPORTED_VERSION - 1,
+            min_supported: 1,
+        })
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("daemon speaks v1"), "{err}");
+        assert!(err.contains("restart the daemon"), "{err}");
+    }
+
+    #[test]
+    fn test_hello_refuses_old_clients() {
+        let req: IpcRequest = serde_json::from_str(r#"{"cmd":"hello","version":1}"#).unwrap();
+        let IpcRequest::Hello { version, .. } = req else {
+            panic!("wrong variant: {req:?}");
+        };
+        let IpcResponse::Error { msg } = hello_response(version) else {
+            panic!("v1 client accepted");
+        };
+        assert!(msg.contains("needs at least v2"), "{msg}");
+    }
+
+    #[test]
+    fn test_check_hello_refuses_older_daemons() {
+        for msg in [
+            // A daemon from before the handshake.
+            "bad request: unknown variant `hello`, expected one of `status`, `stop`",
+            // A v1 daemon knows hello, but not its v2 shape.
+            "bad request: missing field `client_version` at line 1 column 25",
+        ] {
+            let resp = IpcResponse::Error { msg: msg.into() };
+            let err = check_hello(&resp).unwrap_err().to_string();
+            assert!(err.contains("daemon speaks v1"), "{err}");
+        }
+    }
+
     #[test]
     fn test_request_status_roundtrip() {
         let req = IpcRequest::Status;
@@ -123,17 +566,165 @@ mod tests {
         let req = IpcRequest::Logs {
             services: vec!["web".into()],
             follow: true,
+            grep: Some("ERROR|WARN".into()),
+            invert: true,
         };
         let json = serde_json::to_string(&req).unwrap();
         let decoded: IpcRequest = serde_json::from_str(&json).unwrap();
-        if let IpcRequest::Logs { services, follow } = decoded {
+        if let IpcRequest::Logs {
+            services,
+            follow,
+            grep,
+            invert,
+        } = decoded
+        {
             assert_eq!(services, vec!["web".to_string()]);
             assert!(follow);
+            assert_eq!(grep.as_deref(), Some("ERROR|WARN"));
+            assert!(invert);
+        } else {
+            panic!("wrong variant");
+        }
+        // Clients from before server-side filtering leave both out.
+        let old = r#"{"cmd":"logs","services":[],"follow":false}"#;
+        assert!(matches!(
+            serde_json::from_str(old).unwrap(),
+            IpcRequest::Logs {
+                grep: None,
+                invert: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_request_logs_accepts_single_service_of_old_clients() {
+        let services = |json: &str| match serde_json::from_str(json).unwrap() {
+            IpcRequest::Logs { services, .. } => services,
+            other => panic!("wrong variant: {other:?}"),
+        };
+        assert_eq!(
+            services(r#"{"cmd":"logs","service":"api","follow":true}"#),
+            ["api"]
+        );
+        assert!(services(r#"{"cmd":"logs","service":null,"follow":true}"#).is_empty());
+        assert!(services(r#"{"cmd":"logs","follow":true}"#).is_empty());
+        assert_eq!(
+            services(r#"{"cmd":"logs","services":["api","worker"],"follow":true}"#),
+            ["api", "worker"]
+        );
+    }
+
+    #[test]
+    fn test_request_kube_deploy_roundtrip() {
+        let req = IpcRequest::KubeDeploy {
+            path: "k8s/".into(),
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert!(json.contains("\"cmd\":\"kube_deploy\""));
+        let decoded: IpcRequest = serde_json::from_str(&json).unwrap();
+        if let IpcRequest::KubeDeploy { path } = decoded {
+            assert_eq!(path, PathBuf::from("k8s/"));
         } else {
             panic!("wrong variant");
         }
     }
 
+    #[test]
+    fn test_request_kube_namespace_roundtrip() {
+        let req = IpcRequest::KubeCreateNamespace { name: "dev".into() };
+        let json = serde_json::to_string(&req).unwrap();
+        assert!(json.contains("\"cmd\":\"kube_create_namespace\""));
+        let decoded: IpcRequest =
+            serde_json::from_str(r#"{"cmd":"kube_delete_namespace","name":"dev"}"#).unwrap();
+        assert!(matches!(
+            decoded,
+            IpcRequest::KubeDeleteNamespace { ref name, force: false } if name == "dev"
+        ));
+    }
+
+    #[test]
+    fn test_request_kube_pod_exec_roundtrip() {
+        let decoded: IpcRequest =
+            serde_json::from_str(r#"{"cmd":"kube_pod_exec","pod":"api-0","command":["ls","/"]}"#)
+                .unwrap();
+        let IpcRequest::KubePodExec {
+            namespace,
+            pod,
+            container,
+            command,
+        } = decoded
+        else {
+            panic!("wrong variant");
+        };
+        assert_eq!((namespace, container), (None, None));
+        assert_eq!(pod, "api-0");
+        assert_eq!(command, ["ls", "/"]);
+
+        let resp = IpcResponse::Output {
+            stdout: "bin\n".into(),
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"type\":\"output\""));
+    }
+
+    #[test]
+    fn test_request_share_roundtrip() {
+        let req = IpcRequest::Share {
+            service: "web".into(),
+            ttl_secs: Some(3600),
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert!(json.contains("\"cmd\":\"share\""));
+        let decoded: IpcRequest = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            decoded,
+            IpcRequest::Share { ref service, ttl_secs: Some(3600) } if service == "web"
+        ));
+    }
+
+    #[test]
+    fn test_status_row_without_share_url_decodes() {
+        let json = r#"{"name":"web","state":"running","pid":1,"port":3000,"subdomain":null,
+            "uptime_secs":5,"proxy_port":7080,"restart_count":0,"healthy":null}"#;
+        let row: StatusRow = serde_json::from_str(json).unwrap();
+        assert!(row.share_url.is_none());
+        assert_eq!(row.uptime_secs, 5);
+
+        let json = r#"{"name":"web","state":"stopped","pid":null,"port":3000,"subdomain":null,
+            "uptime_secs":null,"proxy_port":7080,"restart_count":0,"healthy":null}"#;
+        let row: StatusRow = serde_json::from_str(json).unwrap();
+        assert_eq!((row.pid, row.uptime_secs), (None, 0));
+    }
+
+    #[test]
+    fn test_batch_nests_requests_and_responses() {
+        let req = IpcRequest::Batch {
+            requests: vec![IpcRequest::Status, IpcRequest::HealthReport],
+        };
+        let json = serde_json::to_string(&req).unwrap();
+        assert_eq!(
+            json,
+            r#"{"cmd":"batch","requests":[{"cmd":"status"},{"cmd":"health_report"}]}"#
+        );
+        let IpcRequest::Batch { requests } = serde_json::from_str(&json).unwrap() else {
+            panic!("wrong variant");
+        };
+        assert_eq!(requests.len(), 2);
+
+        let resp = IpcResponse::Batch {
+            responses: vec![IpcResponse::Ok, IpcResponse::Error { msg: "no".into() }],
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        let IpcResponse::Batch { responses } = serde_json::from_str(&json).unwrap() else {
+            panic!("wrong variant");
+        };
+        assert!(matches!(
+            responses[..],
+            [IpcResponse::Ok, IpcResponse::Error { .. }]
+        ));
+    }
+
     #[test]
     fn test_response_ok_roundtrip() {
         let resp = IpcResponse::Ok;
@@ -142,12 +733,70 @@ mod tests {
         let _: IpcResponse = serde_json::from_str(&json).unwrap();
     }
 
+    #[test]
+    fn test_response_started_roundtrip() {
+        let resp = IpcResponse::Started {
+            results: vec![StartResult {
+                service: "api".into(),
+                outcome: "already running".into(),
+                error: None,
+            }],
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"type\":\"started\""), "{json}");
+        let IpcResponse::Started { results } = serde_json::from_str(&json).unwrap() else {
+            panic!("wrong variant");
+        };
+        assert_eq!(results[0].outcome, "already running");
+    }
+
+    #[test]
+    fn test_response_event_is_flat() {
+        let event = ServiceEvent {
+            service: "api".into(),
+            old_state: Some("running".into()),
+            new_state: "restarting".into(),
+            ts_ms: 1_714_557_600_000,
+            reason: Some("exited with code 1".into()),
+        };
+        let json = serde_json::to_string(&IpcResponse::Event(event.clone())).unwrap();
+        assert!(
+            json.starts_with(r#"{"type":"event","service":"api","#),
+            "{json}"
+        );
+        let IpcResponse::Event(decoded) = serde_json::from_str(&json).unwrap() else {
+            panic!("wrong variant");
+        };
+        assert_eq!(decoded, event);
+    }
+
+    #[test]
+    fn test_response_metrics_roundtrip() {
+        let entry = ServiceMetrics {
+            service: "api".into(),
+            pid: 4242,
+            cpu_percent: 12.5,
+            rss_bytes: 64 << 20,
+            uptime_secs: 90,
+        };
+        let json = serde_json::to_string(&IpcResponse::Metrics {
+            entries: vec![entry.clone()],
+        })
+        .unwrap();
+        let IpcResponse::Metrics { entries } = serde_json::from_str(&json).unwrap() else {
+            panic!("wrong variant");
+        };
+        assert_eq!(entries, [entry]);
+    }
+
     #[test]
     fn test_response_log_line_roundtrip() {
         let resp = IpcResponse::LogLine {
             service: "api".into(),
             line: "started".into(),
             color_idx: 3,
+            ts_ms: Some(1_714_557_600_123),
+            stream: Some("stderr".into()),
         };
         let json = serde_json::to_string(&resp).unwrap();
         let decoded: IpcResponse = serde_json::from_str(&json).unwrap();
@@ -155,13 +804,27 @@ mod tests {
             service,
             line,
             color_idx,
+            ts_ms,
+            stream,
         } = decoded
         {
             assert_eq!(service, "api");
             assert_eq!(line, "started");
             assert_eq!(color_idx, 3);
+            assert_eq!(ts_ms, Some(1_714_557_600_123));
+            assert_eq!(stream.as_deref(), Some("stderr"));
         } else {
             panic!("wrong variant");
         }
     }
+
+    #[test]
+    fn test_log_line_without_ts_or_stream_still_decodes() {
+        // What daemons from before timestamps were carried send.
+        let json = r#"{"type":"log_line","service":"api","line":"hi","color_idx":0}"#;
+        let Ok(IpcResponse::LogLine { ts_ms, stream, .. }) = serde_json::from_str(json) else {
+            panic!("should decode");
+        };
+        assert_eq!((ts_ms, stream), (None, None));
+    }
 }
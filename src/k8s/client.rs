@@ -1,6 +1,10 @@
+use crate::config::GlobalSettings;
 use crate::error::{DevError, Result};
+use crate::exec;
+use crate::hints;
 use crate::log::LogAggregator;
-use std::path::Path;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
@@ -10,36 +14,83 @@ use tokio::process::Command;
 pub struct K8sClient {
     pub context: Option<String>,
     pub namespace: String,
+    /// Kubeconfig file to use instead of kubectl's default (`$KUBECONFIG` or
+    /// `~/.kube/config`).
+    pub kubeconfig: Option<PathBuf>,
+    /// kubectl binary; overridden in tests with a fake script.
+    kubectl: PathBuf,
 }
 
 impl K8sClient {
     pub fn new(context: Option<String>, namespace: String) -> Self {
-        Self { context, namespace }
+        Self {
+            context,
+            namespace,
+            kubeconfig: None,
+            kubectl: PathBuf::from("kubectl"),
+        }
     }
 
-    /// Check if kubectl is available on PATH.
-    pub async fn check_available() -> Result<bool> {
-        let output = Command::new("kubectl")
-            .arg("version")
-            .arg("--client")
-            .arg("--output=json")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .await;
+    /// A client for the `dev` block's `k8s_context`, `k8s_kubeconfig` and
+    /// `k8s_namespace`.
+    pub fn from_settings(dev: &GlobalSettings) -> Self {
+        Self::new(dev.k8s_context.clone(), dev.k8s_namespace.clone())
+            .with_kubeconfig(dev.k8s_kubeconfig.clone())
+    }
 
-        Ok(output.map(|s| s.success()).unwrap_or(false))
+    /// The same cluster, in `namespace`.
+    pub fn in_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
     }
 
-    /// Apply a YAML manifest to the cluster.
-    pub async fn apply_manifest(&self, yaml: &str) -> Result<()> {
-        let mut cmd = Command::new("kubectl");
-        cmd.arg("apply").arg("-f").arg("-");
+    pub fn with_kubeconfig(mut self, kubeconfig: Option<PathBuf>) -> Self {
+        self.kubeconfig = kubeconfig;
+        self
+    }
+
+    #[cfg(test)]
+    fn with_kubectl(mut self, kubectl: impl Into<PathBuf>) -> Self {
+        self.kubectl = kubectl.into();
+        self
+    }
 
+    /// A kubectl command with the client's kubeconfig, context and namespace applied.
+    fn kubectl(&self) -> Command {
+        let mut cmd = self.kubectl_cluster();
+        cmd.arg("--namespace").arg(&self.namespace);
+        cmd
+    }
+
+    /// A kubectl command with the client's kubeconfig and context, but no namespace.
+    fn kubectl_cluster(&self) -> Command {
+        let mut cmd = Command::new(&self.kubectl);
+        if let Some(ref kubeconfig) = self.kubeconfig {
+            cmd.arg("--kubeconfig").arg(kubeconfig);
+        }
         if let Some(ref ctx) = self.context {
             cmd.arg("--context").arg(ctx);
         }
-        cmd.arg("--namespace").arg(&self.namespace);
+        cmd
+    }
+
+    /// Check if kubectl is available on PATH.
+    pub async fn check_available() -> Result<bool> {
+        let output = exec::output(
+            Command::new("kubectl")
+                .arg("version")
+                .arg("--client")
+                .arg("--output=json"),
+        )
+        .await;
+
+        Ok(output.map(|o| o.status.success()).unwrap_or(false))
+    }
+
+    /// Apply a YAML manifest to the cluster.
+    pub async fn apply_manifest(&self, yaml: &str) -> Result<()> {
+        let mut cmd = self.kubectl();
+        cmd.arg("apply").arg("-f").arg("-");
 
         cmd.stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -66,7 +117,7 @@ impl K8sClient {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(DevError::Config(format!(
                 "kubectl apply failed: {}",
-                stderr
+                hints::explain("kubectl", &stderr)
             )));
         }
 
@@ -75,13 +126,8 @@ impl K8sClient {
 
     /// Delete a Kubernetes resource.
     pub async fn delete_resource(&self, kind: &str, name: &str) -> Result<()> {
-        let mut cmd = Command::new("kubectl");
+        let mut cmd = self.kubectl();
         cmd.arg("delete").arg(kind).arg(name);
-
-        if let Some(ref ctx) = self.context {
-            cmd.arg("--context").arg(ctx);
-        }
-        cmd.arg("--namespace").arg(&self.namespace);
         cmd.arg("--ignore-not-found=true");
 
         let output = cmd
@@ -91,29 +137,247 @@ impl K8sClient {
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            tracing::warn!("kubectl delete {} {} failed: {}", kind, name, stderr);
+            tracing::warn!(
+                "kubectl delete {} {} failed: {}",
+                kind,
+                name,
+                hints::explain("kubectl", &stderr)
+            );
+        }
+
+        Ok(())
+    }
+
+    /// `kubectl apply -f <path>` for a manifest file or directory (recursively).
+    /// Returns kubectl's output, e.g. `deployment.apps/web configured`.
+    pub async fn deploy(&self, manifest_path: &Path) -> Result<String> {
+        self.run_on_manifests("apply", manifest_path).await
+    }
+
+    /// `kubectl delete -f <path>` — the inverse of [`deploy`](Self::deploy).
+    pub async fn undeploy(&self, manifest_path: &Path) -> Result<String> {
+        self.run_on_manifests("delete", manifest_path).await
+    }
+
+    /// `kubectl create namespace <name>`.
+    pub async fn create_namespace(&self, name: &str) -> Result<()> {
+        validate_namespace(name)?;
+        self.run_cluster(&["create", "namespace", name]).await
+    }
+
+    /// `kubectl delete namespace <name>`; `force` skips the grace period, for
+    /// namespaces stuck in Terminating.
+    pub async fn delete_namespace(&self, name: &str, force: bool) -> Result<()> {
+        validate_namespace(name)?;
+        self.run_cluster(&delete_namespace_args(name, force)).await
+    }
+
+    /// `kubectl scale deployment/<name> --replicas=<replicas>` in the client namespace.
+    /// `replicas` is checked against [`MAX_REPLICAS`] first, and a deployment that
+    /// doesn't exist is reported by name.
+    pub async fn scale_deployment(&self, name: &str, replicas: u32) -> Result<()> {
+        if replicas > MAX_REPLICAS {
+            return Err(DevError::Config(format!(
+                "cannot scale deployment '{name}' to {replicas} replicas: at most {MAX_REPLICAS}"
+            )));
+        }
+        let mut cmd = self.kubectl();
+        cmd.arg("scale")
+            .arg(format!("deployment/{name}"))
+            .arg(format!("--replicas={replicas}"));
+        let output = exec::output(&mut cmd)
+            .await
+            .map_err(|e| DevError::Config(format!("failed to spawn kubectl: {e}")))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("NotFound") {
+                return Err(DevError::Config(format!(
+                    "deployment '{name}' not found in namespace '{}'",
+                    self.namespace
+                )));
+            }
+            return Err(DevError::Config(format!(
+                "kubectl scale deployment/{name} failed: {}",
+                hints::explain("kubectl", &stderr)
+            )));
+        }
+        Ok(())
+    }
+
+    /// CPU and memory of the cluster's nodes and the client namespace's pods, from
+    /// `kubectl top`. Without metrics-server both lists are empty and
+    /// `metrics_available` is false, with a message saying what to do.
+    pub async fn top(&self) -> Result<KubeTop> {
+        let nodes = self.top_nodes().await?;
+        let pods = self.top_pods().await?;
+        let message = nodes.message.or(pods.message);
+        Ok(KubeTop {
+            nodes: nodes.items,
+            pods: pods.items,
+            metrics_available: nodes.metrics_available && pods.metrics_available,
+            message,
+        })
+    }
+
+    /// `kubectl top nodes`.
+    pub async fn top_nodes(&self) -> Result<Usage<NodeUsage>> {
+        let mut cmd = self.kubectl_cluster();
+        cmd.args(["top", "nodes", "--no-headers"]);
+        let stdout = match self.run_top(cmd, "nodes").await? {
+            Ok(stdout) => stdout,
+            Err(usage) => return Ok(usage),
+        };
+        Ok(Usage::available(parse_top_nodes(&stdout)))
+    }
+
+    /// `kubectl top pods` in the client namespace.
+    pub async fn top_pods(&self) -> Result<Usage<PodUsage>> {
+        let mut cmd = self.kubectl();
+        cmd.args(["top", "pods", "--no-headers"]);
+        let stdout = match self.run_top(cmd, "pods").await? {
+            Ok(stdout) => stdout,
+            Err(usage) => return Ok(usage),
+        };
+        Ok(Usage::available(parse_top_pods(&stdout, &self.namespace)))
+    }
+
+    /// Run a `kubectl top` command: its stdout, or the empty [`Usage`] to return
+    /// when the metrics API isn't there.
+    async fn run_top<T>(
+        &self,
+        mut cmd: Command,
+        what: &str,
+    ) -> Result<std::result::Result<String, Usage<T>>> {
+        let output = exec::output(&mut cmd)
+            .await
+            .map_err(|e| DevError::Config(format!("failed to spawn kubectl: {e}")))?;
+        if output.status.success() {
+            return Ok(Ok(String::from_utf8_lossy(&output.stdout).into_owned()));
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Some(message) = metrics_unavailable(&stderr) {
+            return Ok(Err(Usage::unavailable(message)));
+        }
+        Err(DevError::Config(format!(
+            "kubectl top {what} failed: {}",
+            hints::explain("kubectl", &stderr)
+        )))
+    }
+
+    /// `kubectl exec` a command in a running pod of the client's namespace and return
+    /// its stdout. A non-zero exit is an error carrying the command's stderr.
+    pub async fn pod_exec(
+        &self,
+        pod: &str,
+        container: Option<&str>,
+        command: &[&str],
+    ) -> Result<String> {
+        let output = self
+            .kubectl()
+            .args(pod_exec_args(pod, container, command, false, false))
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|e| DevError::Config(format!("failed to spawn kubectl: {e}")))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DevError::Config(format!(
+                "kubectl exec {pod} -- {} failed ({}): {}",
+                command.join(" "),
+                output.status,
+                hints::explain("kubectl", &stderr)
+            )));
         }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
 
+    /// [`pod_exec`](Self::pod_exec) attached to this terminal: stdin, stdout and
+    /// stderr are passed through, with a TTY when stdin is one.
+    pub async fn pod_exec_interactive(
+        &self,
+        pod: &str,
+        container: Option<&str>,
+        command: &[&str],
+    ) -> Result<()> {
+        use std::io::IsTerminal;
+        let tty = std::io::stdin().is_terminal();
+        let status = self
+            .kubectl()
+            .args(pod_exec_args(pod, container, command, true, tty))
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| DevError::Config(format!("failed to spawn kubectl: {e}")))?
+            .wait()
+            .await
+            .map_err(|e| DevError::Config(format!("kubectl exec: {e}")))?;
+        if !status.success() {
+            return Err(DevError::Config(format!(
+                "kubectl exec {pod} -- {} failed ({status})",
+                command.join(" ")
+            )));
+        }
         Ok(())
     }
 
+    /// Run a cluster-scoped kubectl command: the client's context, but no namespace.
+    async fn run_cluster(&self, args: &[&str]) -> Result<()> {
+        let output = self
+            .kubectl_cluster()
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|e| DevError::Config(format!("failed to spawn kubectl: {e}")))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DevError::Config(format!(
+                "kubectl {} failed: {}",
+                args.join(" "),
+                hints::explain("kubectl", &stderr)
+            )));
+        }
+        Ok(())
+    }
+
+    async fn run_on_manifests(&self, verb: &str, manifest_path: &Path) -> Result<String> {
+        let meta = std::fs::metadata(manifest_path).map_err(|e| {
+            DevError::Config(format!(
+                "cannot read manifests at {}: {e}",
+                manifest_path.display()
+            ))
+        })?;
+        let mut cmd = self.kubectl();
+        cmd.arg(verb).arg("-f").arg(manifest_path);
+        if meta.is_dir() {
+            cmd.arg("--recursive");
+        }
+        let output = exec::output(cmd.stdin(Stdio::null()))
+            .await
+            .map_err(|e| DevError::Config(format!("failed to spawn kubectl: {e}")))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DevError::Config(format!(
+                "kubectl {verb} -f {} failed: {}",
+                manifest_path.display(),
+                hints::explain("kubectl", &stderr)
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
     /// Get pod status by label selector.
     #[allow(dead_code)]
     pub async fn get_pod_status(&self, label: &str) -> Result<PodStatus> {
-        let mut cmd = Command::new("kubectl");
+        let mut cmd = self.kubectl();
         cmd.arg("get")
             .arg("pods")
             .arg("-l")
             .arg(label)
             .arg("--output=jsonpath={.items[0].status.phase}");
 
-        if let Some(ref ctx) = self.context {
-            cmd.arg("--context").arg(ctx);
-        }
-        cmd.arg("--namespace").arg(&self.namespace);
-
-        let output = cmd
-            .output()
+        let output = exec::output(&mut cmd)
             .await
             .map_err(|e| DevError::Config(format!("kubectl get pods failed: {}", e)))?;
 
@@ -131,9 +395,57 @@ impl K8sClient {
         })
     }
 
+    /// Deployments and Services in the client namespace, for the cluster overview.
+    /// `selector` is a kubectl label selector (`app=api,tier!=cache`) that both
+    /// lists are filtered by.
+    pub async fn query_resources(&self, selector: Option<&str>) -> Result<KubeResources> {
+        Ok(KubeResources {
+            deployments: self.get_deployments(selector).await?,
+            services: self.get_services(selector).await?,
+            selector: selector.map(str::to_string),
+        })
+    }
+
+    /// List Deployments via `kubectl get deployments -o json`.
+    pub async fn get_deployments(&self, selector: Option<&str>) -> Result<Vec<KubeDeployment>> {
+        let json = self.get_json("deployments", selector).await?;
+        parse_deployments(&json, unix_now())
+    }
+
+    /// List Services via `kubectl get services -o json`.
+    pub async fn get_services(&self, selector: Option<&str>) -> Result<Vec<KubeService>> {
+        let json = self.get_json("services", selector).await?;
+        parse_services(&json)
+    }
+
+    /// Run `kubectl get <kind> -o json` in the client namespace, narrowed to
+    /// `selector` when given.
+    async fn get_json(&self, kind: &str, selector: Option<&str>) -> Result<String> {
+        let mut cmd = self.kubectl();
+        cmd.arg("get").arg(kind).arg("--output=json");
+        if let Some(selector) = selector {
+            cmd.arg("-l").arg(selector);
+        }
+
+        let output = exec::output(&mut cmd)
+            .await
+            .map_err(|e| DevError::Config(format!("kubectl get {} failed: {}", kind, e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(DevError::Config(format!(
+                "kubectl get {} failed: {}",
+                kind,
+                hints::explain("kubectl", &stderr)
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
     /// Wait for pod to be ready (with timeout).
     pub async fn wait_for_ready(&self, label: &str, timeout_secs: u64) -> Result<()> {
-        let mut cmd = Command::new("kubectl");
+        let mut cmd = self.kubectl();
         cmd.arg("wait")
             .arg("pods")
             .arg("-l")
@@ -141,11 +453,6 @@ impl K8sClient {
             .arg("--for=condition=Ready")
             .arg(format!("--timeout={}s", timeout_secs));
 
-        if let Some(ref ctx) = self.context {
-            cmd.arg("--context").arg(ctx);
-        }
-        cmd.arg("--namespace").arg(&self.namespace);
-
         let output = cmd
             .output()
             .await
@@ -153,7 +460,10 @@ impl K8sClient {
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(DevError::Config(format!("pod not ready: {}", stderr)));
+            return Err(DevError::Config(format!(
+                "pod not ready: {}",
+                hints::explain("kubectl", &stderr)
+            )));
         }
 
         Ok(())
@@ -162,17 +472,12 @@ impl K8sClient {
     /// Stream logs from a pod (returns the first pod matching the label).
     #[allow(dead_code)]
     pub async fn get_logs(&self, label: &str, tail: usize) -> Result<String> {
-        let mut cmd = Command::new("kubectl");
+        let mut cmd = self.kubectl();
         cmd.arg("logs")
             .arg("-l")
             .arg(label)
             .arg(format!("--tail={}", tail));
 
-        if let Some(ref ctx) = self.context {
-            cmd.arg("--context").arg(ctx);
-        }
-        cmd.arg("--namespace").arg(&self.namespace);
-
         let output = cmd
             .output()
             .await
@@ -181,19 +486,211 @@ impl K8sClient {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
+    /// Send a pod's log lines, each starting with its RFC 3339 timestamp, to `sender`.
+    /// With `follow`, keep following across container restarts: kubectl is started
+    /// again from the last timestamp seen for as long as the pod is pending or running,
+    /// and lines already sent are skipped. Returns when the logs end, the pod is gone
+    /// or the receiver is dropped; kubectl is killed in the last case. The bounded
+    /// channel applies backpressure to kubectl's output.
+    pub async fn stream_pod_logs(
+        &self,
+        pod: &str,
+        follow: bool,
+        sender: tokio::sync::mpsc::Sender<String>,
+    ) -> Result<()> {
+        let mut cursor = LogCursor::default();
+        loop {
+            let res = self.pod_logs_once(pod, follow, &mut cursor, &sender).await;
+            if !follow || sender.is_closed() {
+                return res;
+            }
+            // A restarting container makes kubectl exit, or fail while it is waiting.
+            match self.pod_phase(pod).await.as_deref() {
+                Some("Running" | "Pending") => {}
+                _ => return res,
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(LOG_RECONNECT_DELAY) => {}
+                _ = sender.closed() => return Ok(()),
+            }
+        }
+    }
+
+    /// One `kubectl logs` run, from `cursor` on.
+    async fn pod_logs_once(
+        &self,
+        pod: &str,
+        follow: bool,
+        cursor: &mut LogCursor,
+        sender: &tokio::sync::mpsc::Sender<String>,
+    ) -> Result<()> {
+        let mut cmd = self.kubectl();
+        cmd.args(pod_logs_args(pod, follow, cursor.since()))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| DevError::Config(format!("failed to spawn kubectl: {}", e)))?;
+
+        let stderr_task = child.stderr.take().map(|stderr| {
+            tokio::spawn(async move {
+                let mut buf = String::new();
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    buf.push_str(&line);
+                    buf.push('\n');
+                }
+                buf
+            })
+        });
+
+        if let Some(stdout) = child.stdout.take() {
+            let mut lines = BufReader::new(stdout).lines();
+            loop {
+                let line = tokio::select! {
+                    line = lines.next_line() => line,
+                    _ = sender.closed() => break,
+                };
+                match line {
+                    Ok(Some(line)) => {
+                        if !cursor.admit(&line) {
+                            continue;
+                        }
+                        if sender.send(line).await.is_err() {
+                            break;
+                        }
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        if sender.is_closed() {
+            let _ = child.kill().await;
+            return Ok(());
+        }
+
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| DevError::Config(format!("kubectl logs failed: {}", e)))?;
+        if !status.success() {
+            let stderr = match stderr_task {
+                Some(t) => t.await.unwrap_or_default(),
+                None => String::new(),
+            };
+            return Err(DevError::Config(format!(
+                "kubectl logs {} failed: {}",
+                pod,
+                hints::explain("kubectl", &stderr)
+            )));
+        }
+        Ok(())
+    }
+
+    /// `status.phase` of `pod`, or `None` when kubectl can't get it (e.g. the pod is
+    /// gone).
+    async fn pod_phase(&self, pod: &str) -> Option<String> {
+        let mut cmd = self.kubectl();
+        cmd.args(["get", "pod", pod, "--output=jsonpath={.status.phase}"]);
+        let output = exec::output(&mut cmd).await.ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Start `kubectl port-forward <resource> <local>:<remote>` in the background and
+    /// wait until the tunnel is up (kubectl prints `Forwarding from`), giving up after
+    /// [`PORT_FORWARD_TIMEOUT`]. Abort the returned task — or pass it to
+    /// [`stop_port_forward`] — to tear the tunnel down.
+    pub async fn port_forward(
+        &self,
+        resource: &str,
+        local_port: u16,
+        remote_port: u16,
+    ) -> Result<tokio::task::JoinHandle<()>> {
+        self.port_forward_with_timeout(resource, local_port, remote_port, PORT_FORWARD_TIMEOUT)
+            .await
+    }
+
+    /// [`port_forward`](Self::port_forward) with an explicit readiness timeout.
+    pub async fn port_forward_with_timeout(
+        &self,
+        resource: &str,
+        local_port: u16,
+        remote_port: u16,
+        timeout: std::time::Duration,
+    ) -> Result<tokio::task::JoinHandle<()>> {
+        let mut child = self
+            .kubectl()
+            .arg("port-forward")
+            .arg(resource)
+            .arg(format!("{local_port}:{remote_port}"))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Aborting the task drops the child, which stops the tunnel.
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| DevError::Config(format!("failed to start kubectl port-forward: {e}")))?;
+
+        let mut stdout = child.stdout.take().map(|s| BufReader::new(s).lines());
+        let ready = tokio::time::timeout(timeout, async {
+            let Some(lines) = stdout.as_mut() else {
+                return false;
+            };
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.starts_with("Forwarding from") {
+                    return true;
+                }
+            }
+            false
+        })
+        .await;
+
+        match ready {
+            Ok(true) => {}
+            Ok(false) => {
+                let mut stderr = String::new();
+                if let Some(mut err) = child.stderr.take() {
+                    use tokio::io::AsyncReadExt;
+                    let _ = err.read_to_string(&mut stderr).await;
+                }
+                return Err(DevError::Config(format!(
+                    "kubectl port-forward {resource} exited before forwarding: {}",
+                    hints::explain("kubectl", &stderr)
+                )));
+            }
+            Err(_) => {
+                return Err(DevError::Config(format!(
+                    "kubectl port-forward {resource} not ready after {}s",
+                    timeout.as_secs()
+                )));
+            }
+        }
+
+        let resource = resource.to_string();
+        Ok(tokio::spawn(async move {
+            // Keep draining stdout so kubectl never blocks on a full pipe.
+            if let Some(mut lines) = stdout {
+                while let Ok(Some(_)) = lines.next_line().await {}
+            }
+            let status = child.wait().await;
+            tracing::warn!("port-forward {resource} ended: {status:?}");
+        }))
+    }
+
     /// Rollout restart a deployment.
     pub async fn rollout_restart(&self, deployment: &str) -> Result<()> {
-        let mut cmd = Command::new("kubectl");
+        let mut cmd = self.kubectl();
         cmd.arg("rollout")
             .arg("restart")
             .arg("deployment")
             .arg(deployment);
 
-        if let Some(ref ctx) = self.context {
-            cmd.arg("--context").arg(ctx);
-        }
-        cmd.arg("--namespace").arg(&self.namespace);
-
         let output = cmd
             .output()
             .await
@@ -203,7 +700,7 @@ impl K8sClient {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(DevError::Config(format!(
                 "rollout restart failed: {}",
-                stderr
+                hints::explain("kubectl", &stderr)
             )));
         }
 
@@ -443,6 +940,141 @@ impl K8sClient {
     }
 }
 
+/// How long [`K8sClient::port_forward`] waits for the tunnel by default.
+pub const PORT_FORWARD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Upper bound for [`K8sClient::scale_deployment`]; anything above is more likely a
+/// typo than a dev cluster's intent.
+pub const MAX_REPLICAS: u32 = 100;
+
+/// Pause before following a pod's logs again after `kubectl logs -f` exits, e.g. on a
+/// container restart.
+const LOG_RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Tear down a tunnel started by [`K8sClient::port_forward`] and wait until kubectl
+/// has been killed.
+pub async fn stop_port_forward(handle: tokio::task::JoinHandle<()>) {
+    handle.abort();
+    let _ = handle.await;
+}
+
+/// Check `name` is a valid namespace: an RFC 1123 label of at most 63 lowercase
+/// alphanumerics and `-`, starting and ending with an alphanumeric.
+pub fn validate_namespace(name: &str) -> Result<()> {
+    let alnum = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    let valid = !name.is_empty()
+        && name.len() <= 63
+        && name.chars().all(|c| alnum(c) || c == '-')
+        && name.starts_with(alnum)
+        && name.ends_with(alnum);
+    if valid {
+        Ok(())
+    } else {
+        Err(DevError::Config(format!(
+            "invalid namespace '{name}': use at most 63 lowercase letters, digits and '-', starting and ending with a letter or digit"
+        )))
+    }
+}
+
+fn pod_exec_args<'a>(
+    pod: &'a str,
+    container: Option<&'a str>,
+    command: &[&'a str],
+    stdin: bool,
+    tty: bool,
+) -> Vec<&'a str> {
+    let mut args = vec!["exec"];
+    if stdin {
+        args.push("-i");
+    }
+    if tty {
+        args.push("-t");
+    }
+    if let Some(container) = container {
+        args.extend(["-c", container]);
+    }
+    args.extend([pod, "--"]);
+    args.extend(command);
+    args
+}
+
+fn pod_logs_args(pod: &str, follow: bool, since: Option<&str>) -> Vec<String> {
+    let mut args = vec!["logs".to_string(), "--timestamps".to_string()];
+    if follow {
+        args.push("--follow".into());
+    }
+    if let Some(since) = since {
+        args.push(format!("--since-time={since}"));
+    }
+    args.push(pod.into());
+    args
+}
+
+/// Where a log follower got to, so a reconnect neither repeats nor drops lines.
+/// `--since-time` only has second precision, so a reconnect replays the lines of the
+/// last second; those older than the last timestamp, or at it and already sent, are
+/// skipped.
+#[derive(Debug, Default)]
+struct LogCursor {
+    /// The last timestamp sent, as kubectl printed it and as (seconds, nanoseconds).
+    last: Option<(String, (u64, u32))>,
+    /// The lines sent with the `last` timestamp.
+    sent_at_last: Vec<String>,
+}
+
+impl LogCursor {
+    fn since(&self) -> Option<&str> {
+        self.last.as_ref().map(|(raw, _)| raw.as_str())
+    }
+
+    /// Whether `line` is new; if so, it becomes the position. Lines without a
+    /// timestamp are always new.
+    fn admit(&mut self, line: &str) -> bool {
+        let Some(raw) = line.split(' ').next() else {
+            return true;
+        };
+        let Some(at) = parse_log_timestamp(raw) else {
+            return true;
+        };
+        match &self.last {
+            Some((_, last)) if at < *last => return false,
+            Some((_, last)) if at == *last => {
+                if self.sent_at_last.iter().any(|l| l == line) {
+                    return false;
+                }
+            }
+            _ => {
+                self.last = Some((raw.to_string(), at));
+                self.sent_at_last.clear();
+            }
+        }
+        self.sent_at_last.push(line.to_string());
+        true
+    }
+}
+
+/// A `kubectl logs --timestamps` timestamp, e.g. `2024-05-01T10:00:00.123456789Z`, as
+/// seconds since the epoch and nanoseconds.
+fn parse_log_timestamp(s: &str) -> Option<(u64, u32)> {
+    let secs = parse_rfc3339(s)?;
+    let nanos = match s.strip_suffix('Z')?.split_once('.') {
+        Some((_, frac)) if !frac.is_empty() && frac.len() <= 9 => {
+            frac.parse::<u32>().ok()? * 10u32.pow(9 - frac.len() as u32)
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+    Some((secs, nanos))
+}
+
+fn delete_namespace_args(name: &str, force: bool) -> Vec<&str> {
+    let mut args = vec!["delete", "namespace", name];
+    if force {
+        args.extend(["--grace-period=0", "--force"]);
+    }
+    args
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[allow(dead_code)]
 pub enum PodStatus {
@@ -453,3 +1085,953 @@ pub enum PodStatus {
     Unknown,
     NotFound,
 }
+
+/// Cluster overview returned by [`K8sClient::query_resources`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KubeResources {
+    pub deployments: Vec<KubeDeployment>,
+    pub services: Vec<KubeService>,
+    /// Label selector the lists were filtered by, if any.
+    pub selector: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KubeDeployment {
+    pub name: String,
+    pub namespace: String,
+    pub desired_replicas: u32,
+    pub ready_replicas: u32,
+    pub available_replicas: u32,
+    /// Images of the pod template's containers, comma-separated.
+    pub image: String,
+    /// Age in kubectl's short form, e.g. "5m", "3h", "12d".
+    pub age: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KubeService {
+    pub name: String,
+    pub namespace: String,
+    /// ClusterIP, NodePort, LoadBalancer or ExternalName.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub cluster_ip: String,
+    /// Ports in kubectl's form, e.g. "80/TCP" or "80:30080/TCP".
+    pub ports: Vec<String>,
+}
+
+/// CPU and memory of one node, from `kubectl top nodes`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeUsage {
+    pub name: String,
+    pub cpu_millicores: u64,
+    /// Share of the node's allocatable CPU; `None` when kubectl doesn't know it.
+    pub cpu_percent: Option<u32>,
+    pub memory_mib: f64,
+    pub memory_percent: Option<u32>,
+}
+
+/// CPU and memory of one pod, summed over its containers, from `kubectl top pods`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PodUsage {
+    pub name: String,
+    pub namespace: String,
+    pub cpu_millicores: u64,
+    pub memory_mib: f64,
+}
+
+/// Rows of one `kubectl top` query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Usage<T> {
+    pub items: Vec<T>,
+    /// False when the cluster has no metrics API; `items` is empty then.
+    pub metrics_available: bool,
+    /// Why there are no metrics and how to get them.
+    pub message: Option<String>,
+}
+
+impl<T> Usage<T> {
+    fn available(items: Vec<T>) -> Self {
+        Self {
+            items,
+            metrics_available: true,
+            message: None,
+        }
+    }
+
+    fn unavailable(message: &str) -> Self {
+        Self {
+            items: Vec::new(),
+            metrics_available: false,
+            message: Some(message.to_string()),
+        }
+    }
+}
+
+/// Resource usage returned by [`K8sClient::top`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KubeTop {
+    pub nodes: Vec<NodeUsage>,
+    pub pods: Vec<PodUsage>,
+    pub metrics_available: bool,
+    pub message: Option<String>,
+}
+
+fn parse_items(json: &str) -> Result<Vec<serde_json::Value>> {
+    let v: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| DevError::Config(format!("invalid kubectl output: {}", e)))?;
+    Ok(v["items"].as_array().cloned().unwrap_or_default())
+}
+
+fn str_field(v: &serde_json::Value) -> String {
+    v.as_str().unwrap_or_default().to_string()
+}
+
+fn u32_field(v: &serde_json::Value) -> u32 {
+    v.as_u64().unwrap_or(0) as u32
+}
+
+fn parse_deployments(json: &str, now: u64) -> Result<Vec<KubeDeployment>> {
+    Ok(parse_items(json)?
+        .iter()
+        .map(|item| {
+            let meta = &item["metadata"];
+            KubeDeployment {
+                name: str_field(&meta["name"]),
+                namespace: str_field(&meta["namespace"]),
+                // Kubernetes defaults an unset replica count to 1.
+                desired_replicas: item["spec"]["replicas"].as_u64().unwrap_or(1) as u32,
+                ready_replicas: u32_field(&item["status"]["readyReplicas"]),
+                available_replicas: u32_field(&item["status"]["availableReplicas"]),
+                image: item["spec"]["template"]["spec"]["containers"]
+                    .as_array()
+                    .map(|cs| {
+                        cs.iter()
+                            .map(|c| c["image"].as_str().unwrap_or_default())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .unwrap_or_default(),
+                age: meta["creationTimestamp"]
+                    .as_str()
+                    .and_then(parse_rfc3339)
+                    .map(|created| format_age(now.saturating_sub(created)))
+                    .unwrap_or_else(|| "<unknown>".into()),
+            }
+        })
+        .collect())
+}
+
+fn parse_services(json: &str) -> Result<Vec<KubeService>> {
+    Ok(parse_items(json)?
+        .iter()
+        .map(|item| {
+            let spec = &item["spec"];
+            let ports = spec["ports"]
+                .as_array()
+                .map(|ports| {
+                    ports
+                        .iter()
+                        .map(|p| {
+                            let proto = p["protocol"].as_str().unwrap_or("TCP");
+                            match p["nodePort"].as_u64() {
+                                Some(node) => format!("{}:{}/{}", p["port"], node, proto),
+                                None => format!("{}/{}", p["port"], proto),
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            KubeService {
+                name: str_field(&item["metadata"]["name"]),
+                namespace: str_field(&item["metadata"]["namespace"]),
+                kind: spec["type"].as_str().unwrap_or("ClusterIP").to_string(),
+                cluster_ip: spec["clusterIP"].as_str().unwrap_or("None").to_string(),
+                ports,
+            }
+        })
+        .collect())
+}
+
+/// Why `kubectl top` failed for lack of metrics, if it did.
+fn metrics_unavailable(stderr: &str) -> Option<&'static str> {
+    if stderr.contains("metrics not available yet") {
+        Some("metrics-server has not collected metrics yet; try again in a minute")
+    } else if stderr.contains("Metrics API not available")
+        || stderr.contains("metrics.k8s.io")
+        || stderr.contains("heapster")
+    {
+        Some(
+            "metrics-server is not installed; install it with `kubectl apply -f \
+             https://github.com/kubernetes-sigs/metrics-server/releases/latest/download/components.yaml`",
+        )
+    } else {
+        None
+    }
+}
+
+/// `kubectl top nodes --no-headers`: NAME CPU CPU% MEMORY MEMORY%. Lines that don't
+/// parse are skipped.
+fn parse_top_nodes(stdout: &str) -> Vec<NodeUsage> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            let [name, cpu, cpu_percent, memory, memory_percent, ..] = cols[..] else {
+                return None;
+            };
+            Some(NodeUsage {
+                name: name.to_string(),
+                cpu_millicores: parse_cpu(cpu)?,
+                cpu_percent: parse_percent(cpu_percent),
+                memory_mib: parse_memory(memory)?,
+                memory_percent: parse_percent(memory_percent),
+            })
+        })
+        .collect()
+}
+
+/// `kubectl top pods --no-headers`: NAME CPU MEMORY.
+fn parse_top_pods(stdout: &str, namespace: &str) -> Vec<PodUsage> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            let [name, cpu, memory, ..] = cols[..] else {
+                return None;
+            };
+            Some(PodUsage {
+                name: name.to_string(),
+                namespace: namespace.to_string(),
+                cpu_millicores: parse_cpu(cpu)?,
+                memory_mib: parse_memory(memory)?,
+            })
+        })
+        .collect()
+}
+
+/// A Kubernetes CPU quantity in millicores: "250m", "1", "0.5", or the metrics
+/// API's "1500000n" / "1500u".
+fn parse_cpu(s: &str) -> Option<u64> {
+    let (num, per_core) = if let Some(n) = s.strip_suffix('m') {
+        (n, 1e3)
+    } else if let Some(n) = s.strip_suffix('u') {
+        (n, 1e6)
+    } else if let Some(n) = s.strip_suffix('n') {
+        (n, 1e9)
+    } else {
+        (s, 1.0)
+    };
+    let cores = num.parse::<f64>().ok().filter(|v| *v >= 0.0)? / per_core;
+    Some((cores * 1000.0).round() as u64)
+}
+
+/// A Kubernetes memory quantity in MiB: "128Mi", "1Gi", "512Ki", "1G", or bytes.
+fn parse_memory(s: &str) -> Option<f64> {
+    const UNITS: [(&str, f64); 10] = [
+        ("Ki", 1024.0),
+        ("Mi", 1024.0 * 1024.0),
+        ("Gi", 1024.0 * 1024.0 * 1024.0),
+        ("Ti", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ("k", 1e3),
+        ("K", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+        ("", 1.0),
+    ];
+    let (num, bytes) = UNITS
+        .iter()
+        .find_map(|(suffix, bytes)| s.strip_suffix(suffix).map(|n| (n, *bytes)))?;
+    let value = num.parse::<f64>().ok().filter(|v| *v >= 0.0)?;
+    Some(value * bytes / (1024.0 * 1024.0))
+}
+
+/// "12%", or `None` for kubectl's "<unknown>".
+fn parse_percent(s: &str) -> Option<u32> {
+    s.strip_suffix('%')?.parse().ok()
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Parse a UTC RFC 3339 timestamp as used by Kubernetes ("2024-03-01T12:00:00Z")
+/// into Unix seconds. Fractional seconds are ignored.
+fn parse_rfc3339(s: &str) -> Option<u64> {
+    let s = s.strip_suffix('Z')?;
+    let (date, time) = s.split_once('T')?;
+    let mut d = date.splitn(3, '-').map(|p| p.parse::<i64>());
+    let (y, m, day) = (d.next()?.ok()?, d.next()?.ok()?, d.next()?.ok()?);
+    let time = time.split('.').next()?;
+    let mut t = time.splitn(3, ':').map(|p| p.parse::<i64>());
+    let (hh, mm, ss) = (t.next()?.ok()?, t.next()?.ok()?, t.next()?.ok()?);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Days from civil (Howard Hinnant's algorithm).
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days * 86_400 + hh * 3600 + mm * 60 + ss).ok()
+}
+
+/// kubectl-style age: the largest whole unit, e.g. 45s, 5m, 3h, 12d.
+fn format_age(secs: u64) -> String {
+    match secs {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86_400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEPLOYMENTS_JSON: &str = r#"{
+        "apiVersion": "v1",
+        "kind": "List",
+        "items": [
+            {
+                "metadata": {
+                    "name": "api",
+                    "namespace": "default",
+                    "creationTimestamp": "2024-03-01T12:00:00Z"
+                },
+                "spec": {
+                    "replicas": 3,
+                    "template": { "spec": { "containers": [
+                        { "name": "api", "image": "ghcr.io/acme/api:1.4" },
+                        { "name": "proxy", "image": "envoyproxy/envoy:v1.29" }
+                    ] } }
+                },
+                "status": { "readyReplicas": 2, "availableReplicas": 2 }
+            },
+            {
+                "metadata": {
+                    "name": "worker",
+                    "namespace": "default",
+                    "creationTimestamp": "2024-03-01T11:59:30Z"
+                },
+                "spec": {},
+                "status": {}
+            }
+        ]
+    }"#;
+
+    const SERVICES_JSON: &str = r#"{
+        "apiVersion": "v1",
+        "kind": "List",
+        "items": [
+            {
+                "metadata": { "name": "api", "namespace": "default" },
+                "spec": {
+                    "type": "NodePort",
+                    "clusterIP": "10.43.0.12",
+                    "ports": [
+                        { "port": 80, "nodePort": 30080, "protocol": "TCP" },
+                        { "port": 53, "protocol": "UDP" }
+                    ]
+                }
+            },
+            {
+                "metadata": { "name": "db", "namespace": "data" },
+                "spec": {
+                    "type": "ClusterIP",
+                    "clusterIP": "None",
+                    "ports": [{ "port": 5432 }]
+                }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_deployments() {
+        let now = parse_rfc3339("2024-03-01T12:05:00Z").unwrap();
+        let deps = parse_deployments(DEPLOYMENTS_JSON, now).unwrap();
+        assert_eq!(deps.len(), 2);
+        assert_eq!(
+            deps[0],
+            KubeDeployment {
+                name: "api".into(),
+                namespace: "default".into(),
+                desired_replicas: 3,
+                ready_replicas: 2,
+                available_replicas: 2,
+                image: "ghcr.io/acme/api:1.4, envoyproxy/envoy:v1.29".into(),
+                age: "5m".into(),
+            }
+        );
+        // Missing replica counts fall back to Kubernetes defaults.
+        assert_eq!(deps[1].desired_replicas, 1);
+        assert_eq!(deps[1].ready_replicas, 0);
+        assert_eq!(deps[1].age, "5m");
+        assert_eq!(deps[1].image, "");
+    }
+
+    #[tokio::test]
+    async fn test_scale_deployment() {
+        let dir = tempfile::tempdir().unwrap();
+        let (kubectl, log) = listing_kubectl(dir.path());
+        let client = K8sClient::new(None, "dev".into()).with_kubectl(kubectl);
+        client.scale_deployment("api", 3).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "--namespace dev scale deployment/api --replicas=3\n"
+        );
+
+        let err = client.scale_deployment("api", 101).await.unwrap_err();
+        assert!(err.to_string().contains("at most 100"), "{err}");
+        assert_eq!(std::fs::read_to_string(&log).unwrap().lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scale_missing_deployment_names_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(
+            dir.path(),
+            "echo 'Error from server (NotFound): deployments.apps \"nope\" not found' >&2\nexit 1",
+        );
+        let client = K8sClient::new(None, "dev".into()).with_kubectl(kubectl);
+        let err = client.scale_deployment("nope", 2).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "config error: deployment 'nope' not found in namespace 'dev'"
+        );
+    }
+
+    #[test]
+    fn test_parse_services() {
+        let svcs = parse_services(SERVICES_JSON).unwrap();
+        assert_eq!(svcs.len(), 2);
+        assert_eq!(svcs[0].kind, "NodePort");
+        assert_eq!(svcs[0].cluster_ip, "10.43.0.12");
+        assert_eq!(svcs[0].ports, vec!["80:30080/TCP", "53/UDP"]);
+        assert_eq!(svcs[1].namespace, "data");
+        assert_eq!(svcs[1].cluster_ip, "None");
+        assert_eq!(svcs[1].ports, vec!["5432/TCP"]);
+    }
+
+    #[test]
+    fn test_parse_empty_and_invalid_lists() {
+        assert!(parse_services(r#"{"items": []}"#).unwrap().is_empty());
+        assert!(parse_deployments("not json", 0).is_err());
+    }
+
+    #[test]
+    fn test_kube_service_serializes_type() {
+        let svcs = parse_services(SERVICES_JSON).unwrap();
+        let v = serde_json::to_value(&svcs[0]).unwrap();
+        assert_eq!(v["type"], "NodePort");
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_rfc3339("2024-03-01T12:00:00Z"), Some(1_709_294_400));
+        assert_eq!(
+            parse_rfc3339("2024-03-01T12:00:00.123Z"),
+            Some(1_709_294_400)
+        );
+        assert_eq!(parse_rfc3339("2024-13-01T12:00:00Z"), None);
+        assert_eq!(parse_rfc3339("yesterday"), None);
+    }
+
+    /// Write an executable fake kubectl that runs `body` as a shell script.
+    fn fake_kubectl(dir: &Path, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("kubectl");
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// A fake kubectl that records its arguments, one per line, in `args.txt`.
+    fn recording_kubectl(dir: &Path) -> (PathBuf, PathBuf) {
+        let log = dir.join("args.txt");
+        let kubectl = fake_kubectl(
+            dir,
+            &format!("for a in \"$@\"; do echo \"$a\"; done > {}", log.display()),
+        );
+        (kubectl, log)
+    }
+
+    #[tokio::test]
+    async fn test_deploy_file_applies_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let (kubectl, log) = recording_kubectl(dir.path());
+        let manifest = dir.path().join("app.yaml");
+        std::fs::write(&manifest, "kind: ConfigMap\n").unwrap();
+        let client = K8sClient::new(Some("dev".into()), "apps".into()).with_kubectl(kubectl);
+        client.deploy(&manifest).await.unwrap();
+        let args = std::fs::read_to_string(log).unwrap();
+        assert_eq!(
+            args.lines().collect::<Vec<_>>(),
+            vec![
+                "--context",
+                "dev",
+                "--namespace",
+                "apps",
+                "apply",
+                "-f",
+                manifest.to_str().unwrap()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_undeploy_directory_is_recursive() {
+        let dir = tempfile::tempdir().unwrap();
+        let (kubectl, log) = recording_kubectl(dir.path());
+        let manifests = dir.path().join("k8s");
+        std::fs::create_dir(&manifests).unwrap();
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        client.undeploy(&manifests).await.unwrap();
+        let args = std::fs::read_to_string(log).unwrap();
+        let args: Vec<&str> = args.lines().collect();
+        assert_eq!(args[2..4], ["delete", "-f"]);
+        assert_eq!(args.last(), Some(&"--recursive"));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_reports_missing_path_and_kubectl_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(
+            dir.path(),
+            "echo 'error: no objects passed to apply' >&2; exit 1",
+        );
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        let err = client.deploy(&dir.path().join("missing.yaml")).await;
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("cannot read manifests"));
+        let err = client.deploy(dir.path()).await.unwrap_err().to_string();
+        assert!(err.contains("kubectl apply -f"));
+        assert!(err.contains("no objects passed to apply"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_deploy_returns_kubectl_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(dir.path(), "echo 'deployment.apps/web configured'");
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        let out = client.deploy(dir.path()).await.unwrap();
+        assert_eq!(out, "deployment.apps/web configured\n");
+    }
+
+    #[tokio::test]
+    async fn test_port_forward_waits_for_ready_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(
+            dir.path(),
+            "echo starting; sleep 0.2; echo 'Forwarding from 127.0.0.1:8080 -> 80'; sleep 30",
+        );
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        let started = std::time::Instant::now();
+        let handle = client
+            .port_forward("deployment/api", 8080, 80)
+            .await
+            .unwrap();
+        assert!(started.elapsed() >= std::time::Duration::from_millis(200));
+        assert!(!handle.is_finished());
+        stop_port_forward(handle).await;
+    }
+
+    #[tokio::test]
+    async fn test_port_forward_times_out_without_ready_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(dir.path(), "sleep 30");
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        let err = client
+            .port_forward_with_timeout(
+                "deployment/api",
+                8080,
+                80,
+                std::time::Duration::from_millis(200),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not ready"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_port_forward_reports_kubectl_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(
+            dir.path(),
+            "echo 'error: deployments.apps \"api\" not found' >&2; exit 1",
+        );
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        let err = client
+            .port_forward("deployment/api", 8080, 80)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_stream_pod_logs_delivers_lines_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(
+            dir.path(),
+            "for i in 1 2 3 4 5 6 7 8 9 10; do echo \"line $i\"; done",
+        );
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        // Small capacity so the reader has to wait on the consumer.
+        let (tx, mut rx) = tokio::sync::mpsc::channel(2);
+        let stream = tokio::spawn(async move { client.stream_pod_logs("api-0", false, tx).await });
+
+        let mut got = Vec::new();
+        while let Some(line) = rx.recv().await {
+            got.push(line);
+        }
+        stream.await.unwrap().unwrap();
+        let want: Vec<String> = (1..=10).map(|i| format!("line {i}")).collect();
+        assert_eq!(got, want);
+    }
+
+    #[tokio::test]
+    async fn test_stream_pod_logs_returns_when_receiver_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(dir.path(), "echo first\nexec sleep 30");
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let stream = tokio::spawn(async move { client.stream_pod_logs("api-0", true, tx).await });
+
+        assert_eq!(rx.recv().await.as_deref(), Some("first"));
+        drop(rx);
+        let res = tokio::time::timeout(std::time::Duration::from_secs(5), stream)
+            .await
+            .expect("stream_pod_logs should return once the receiver is gone");
+        assert!(res.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stream_pod_logs_reports_kubectl_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(
+            dir.path(),
+            "echo 'Error from server (NotFound): pods \"api-0\" not found' >&2\nexit 1",
+        );
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        let (tx, _rx) = tokio::sync::mpsc::channel(4);
+        let err = client.stream_pod_logs("api-0", true, tx).await.unwrap_err();
+        assert!(err.to_string().contains("NotFound"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_stream_pod_logs_follows_across_restart_without_repeats() {
+        let dir = tempfile::tempdir().unwrap();
+        let d = dir.path().display();
+        // The first run ends as the container restarts; the second replays the last
+        // second, as `--since-time` would, then goes on. The pod is gone afterwards.
+        let kubectl = fake_kubectl(
+            dir.path(),
+            &format!(
+                r#"case "$3" in
+logs)
+  echo "$*" >> {d}/logs-args.txt
+  if [ ! -e {d}/restarted ]; then
+    touch {d}/restarted
+    echo "2024-05-01T10:00:00.100Z one"
+    echo "2024-05-01T10:00:00.200Z two"
+  else
+    echo "2024-05-01T10:00:00.100Z one"
+    echo "2024-05-01T10:00:00.200Z two"
+    echo "2024-05-01T10:00:00.200Z two again"
+    echo "2024-05-01T10:00:01Z three"
+  fi ;;
+get)
+  if [ "$(wc -l < {d}/logs-args.txt)" -ge 2 ]; then exit 1; fi
+  echo Running ;;
+esac"#
+            ),
+        );
+        let client = K8sClient::new(None, "default".into()).with_kubectl(kubectl);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let stream = tokio::spawn(async move { client.stream_pod_logs("api-0", true, tx).await });
+
+        let mut got = Vec::new();
+        while let Some(line) = rx.recv().await {
+            got.push(line);
+        }
+        stream.await.unwrap().unwrap();
+        assert_eq!(
+            got,
+            [
+                "2024-05-01T10:00:00.100Z one",
+                "2024-05-01T10:00:00.200Z two",
+                "2024-05-01T10:00:00.200Z two again",
+                "2024-05-01T10:00:01Z three",
+            ]
+        );
+        let args = std::fs::read_to_string(dir.path().join("logs-args.txt")).unwrap();
+        assert_eq!(
+            args.lines().collect::<Vec<_>>(),
+            [
+                "--namespace default logs --timestamps --follow api-0",
+                "--namespace default logs --timestamps --follow --since-time=2024-05-01T10:00:00.200Z api-0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_cursor_skips_lines_already_sent() {
+        assert_eq!(
+            parse_log_timestamp("2024-05-01T10:00:00.5Z"),
+            Some((1_714_557_600, 500_000_000))
+        );
+        assert_eq!(
+            parse_log_timestamp("2024-05-01T10:00:00Z"),
+            Some((1_714_557_600, 0))
+        );
+        assert_eq!(parse_log_timestamp("2024-05-01T10:00:00.Z"), None);
+
+        let mut cursor = LogCursor::default();
+        assert_eq!(cursor.since(), None);
+        assert!(cursor.admit("2024-05-01T10:00:00.5Z a"));
+        assert!(cursor.admit("2024-05-01T10:00:00.5Z b"));
+        assert!(!cursor.admit("2024-05-01T10:00:00.5Z a"));
+        assert!(!cursor.admit("2024-05-01T10:00:00.25Z older"));
+        assert!(cursor.admit("no timestamp"));
+        assert!(cursor.admit("2024-05-01T10:00:01Z c"));
+        assert!(!cursor.admit("2024-05-01T10:00:00.5Z a"));
+        assert_eq!(cursor.since(), Some("2024-05-01T10:00:01Z"));
+    }
+
+    /// A fake kubectl that appends each invocation's arguments as one line to
+    /// `args.txt` and answers with an empty list.
+    fn listing_kubectl(dir: &Path) -> (PathBuf, PathBuf) {
+        let log = dir.join("args.txt");
+        let kubectl = fake_kubectl(
+            dir,
+            &format!("echo \"$*\" >> {}\necho '{{\"items\": []}}'", log.display()),
+        );
+        (kubectl, log)
+    }
+
+    #[tokio::test]
+    async fn test_query_resources_passes_label_selector() {
+        let dir = tempfile::tempdir().unwrap();
+        let (kubectl, log) = listing_kubectl(dir.path());
+        let client = K8sClient::new(None, "apps".into()).with_kubectl(kubectl);
+        let res = client
+            .query_resources(Some("app=api,tier!=cache"))
+            .await
+            .unwrap();
+        assert_eq!(res.selector.as_deref(), Some("app=api,tier!=cache"));
+        assert!(res.deployments.is_empty() && res.services.is_empty());
+        let args = std::fs::read_to_string(log).unwrap();
+        assert_eq!(
+            args.lines().collect::<Vec<_>>(),
+            vec![
+                "--namespace apps get deployments --output=json -l app=api,tier!=cache",
+                "--namespace apps get services --output=json -l app=api,tier!=cache",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_resources_without_selector_lists_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let (kubectl, log) = listing_kubectl(dir.path());
+        let client = K8sClient::new(None, "apps".into()).with_kubectl(kubectl);
+        let res = client.query_resources(None).await.unwrap();
+        assert_eq!(res.selector, None);
+        let args = std::fs::read_to_string(log).unwrap();
+        assert!(!args.contains("-l"), "{args}");
+        assert_eq!(args.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_validate_namespace() {
+        for ok in ["a3s", "dev-1", "0", &"a".repeat(63)] {
+            assert!(validate_namespace(ok).is_ok(), "{ok}");
+        }
+        for bad in ["", "Dev", "-dev", "dev-", "a_b", "a.b", &"a".repeat(64)] {
+            let err = validate_namespace(bad).unwrap_err();
+            assert!(err.to_string().contains("invalid namespace"), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_delete_namespace_args() {
+        assert_eq!(
+            delete_namespace_args("dev", false),
+            ["delete", "namespace", "dev"]
+        );
+        assert_eq!(
+            delete_namespace_args("dev", true),
+            ["delete", "namespace", "dev", "--grace-period=0", "--force"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namespace_commands_are_cluster_scoped() {
+        let dir = tempfile::tempdir().unwrap();
+        let (kubectl, log) = listing_kubectl(dir.path());
+        let client = K8sClient::new(Some("dev".into()), "apps".into()).with_kubectl(kubectl);
+        client.create_namespace("preview-1").await.unwrap();
+        client.delete_namespace("preview-1", true).await.unwrap();
+        assert!(client.create_namespace("Preview_1").await.is_err());
+        let args = std::fs::read_to_string(log).unwrap();
+        assert_eq!(
+            args.lines().collect::<Vec<_>>(),
+            vec![
+                "--context dev create namespace preview-1",
+                "--context dev delete namespace preview-1 --grace-period=0 --force",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_settings_kubeconfig_and_context_reach_every_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let (kubectl, log) = listing_kubectl(dir.path());
+        let dev = GlobalSettings {
+            k8s_context: Some("staging".into()),
+            k8s_kubeconfig: Some("/team/kubeconfig".into()),
+            k8s_namespace: "apps".into(),
+            ..Default::default()
+        };
+        let client = K8sClient::from_settings(&dev).with_kubectl(kubectl);
+        client.query_resources(None).await.unwrap();
+        client.create_namespace("preview-1").await.unwrap();
+        client
+            .in_namespace("other")
+            .scale_deployment("api", 1)
+            .await
+            .unwrap();
+        let args = std::fs::read_to_string(log).unwrap();
+        assert_eq!(
+            args.lines().collect::<Vec<_>>(),
+            vec![
+                "--kubeconfig /team/kubeconfig --context staging --namespace apps get deployments --output=json",
+                "--kubeconfig /team/kubeconfig --context staging --namespace apps get services --output=json",
+                "--kubeconfig /team/kubeconfig --context staging create namespace preview-1",
+                "--kubeconfig /team/kubeconfig --context staging --namespace other scale deployment/api --replicas=1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pod_exec_args() {
+        assert_eq!(
+            pod_exec_args("api-0", None, &["ls", "-la"], false, false),
+            ["exec", "api-0", "--", "ls", "-la"]
+        );
+        assert_eq!(
+            pod_exec_args("api-0", Some("sidecar"), &["sh"], true, true),
+            ["exec", "-i", "-t", "-c", "sidecar", "api-0", "--", "sh"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pod_exec_returns_stdout_and_fails_on_nonzero_exit() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(
+            dir.path(),
+            "shift 4; [ \"$1\" = exec ] || exit 2; shift 3\n\
+             if [ \"$1\" = false ]; then echo 'command terminated with exit code 1' >&2; exit 1; fi\n\
+             echo \"$@\"",
+        );
+        let client = K8sClient::new(Some("dev".into()), "apps".into()).with_kubectl(kubectl);
+        let out = client
+            .pod_exec("api-0", None, &["echo", "hi"])
+            .await
+            .unwrap();
+        assert_eq!(out, "echo hi\n");
+        let err = client
+            .pod_exec("api-0", None, &["false"])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("exit code 1"), "{err}");
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(45), "45s");
+        assert_eq!(format_age(300), "5m");
+        assert_eq!(format_age(3 * 3600 + 59), "3h");
+        assert_eq!(format_age(12 * 86_400), "12d");
+    }
+
+    #[test]
+    fn test_parse_cpu_units() {
+        assert_eq!(parse_cpu("100m"), Some(100));
+        assert_eq!(parse_cpu("1"), Some(1000));
+        assert_eq!(parse_cpu("0.5"), Some(500));
+        assert_eq!(parse_cpu("2000000n"), Some(2));
+        assert_eq!(parse_cpu("250000u"), Some(250));
+        assert_eq!(parse_cpu("abc"), None);
+        assert_eq!(parse_cpu("-1"), None);
+    }
+
+    #[test]
+    fn test_parse_memory_units() {
+        assert_eq!(parse_memory("128Mi"), Some(128.0));
+        assert_eq!(parse_memory("1Gi"), Some(1024.0));
+        assert_eq!(parse_memory("512Ki"), Some(0.5));
+        assert_eq!(parse_memory("1048576"), Some(1.0));
+        assert_eq!(parse_memory("1G"), Some(1e9 / 1_048_576.0));
+        assert_eq!(parse_memory("12Xi"), None);
+    }
+
+    #[test]
+    fn test_parse_top_output() {
+        let nodes = parse_top_nodes(
+            "node-a   250m   12%   1843Mi   47%\nnode-b   1      <unknown>   2Gi   <unknown>\n",
+        );
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].cpu_millicores, 250);
+        assert_eq!(nodes[0].cpu_percent, Some(12));
+        assert_eq!(nodes[0].memory_mib, 1843.0);
+        assert_eq!(nodes[1].cpu_millicores, 1000);
+        assert_eq!(nodes[1].memory_mib, 2048.0);
+        assert_eq!(nodes[1].memory_percent, None);
+
+        let pods = parse_top_pods("api-7d9f   3m   24Mi\nbroken\n", "dev");
+        assert_eq!(
+            pods,
+            vec![PodUsage {
+                name: "api-7d9f".into(),
+                namespace: "dev".into(),
+                cpu_millicores: 3,
+                memory_mib: 24.0,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_top_without_metrics_server_is_empty_not_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let kubectl = fake_kubectl(
+            dir.path(),
+            "echo 'error: Metrics API not available' >&2\nexit 1",
+        );
+        let client = K8sClient::new(None, "dev".into()).with_kubectl(kubectl);
+        let top = client.top().await.unwrap();
+        assert!(!top.metrics_available);
+        assert!(top.nodes.is_empty() && top.pods.is_empty());
+        assert!(top
+            .message
+            .unwrap()
+            .contains("metrics-server is not installed"));
+
+        let kubectl = fake_kubectl(dir.path(), "echo 'error: Unauthorized' >&2\nexit 1");
+        let client = K8sClient::new(None, "dev".into()).with_kubectl(kubectl);
+        let err = client.top_pods().await.unwrap_err();
+        assert!(err.to_string().contains("kubectl top pods failed"), "{err}");
+    }
+}
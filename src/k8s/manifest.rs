@@ -515,17 +515,26 @@ mod tests {
                 map
             },
             env_file: None,
+            env_reload: false,
+            shell: None,
             log_file: None,
             log_rotate_mb: 0,
+            log_max_size: None,
+            log_keep: 1,
             pre_start: None,
             post_stop: None,
+            hook_timeout: std::time::Duration::from_secs(300),
             depends_on: vec![],
+            depends_on_timeout: None,
             watch: None,
             health: None,
             restart: Default::default(),
             stop_timeout: std::time::Duration::from_secs(5),
             disabled: false,
+            autostart: true,
             labels: vec![],
+            standby: false,
+            limits: None,
             k8s: Some(K8sConfig {
                 image: "node:20".to_string(),
                 dockerfile: None,
@@ -539,6 +548,8 @@ mod tests {
                 secrets: HashMap::new(),
                 volumes: vec![],
             }),
+            lint_ignore: vec![],
+            env_secret: Default::default(),
         }
     }
 
@@ -697,6 +708,8 @@ mod tests {
             interval: std::time::Duration::from_secs(5),
             timeout: std::time::Duration::from_secs(2),
             retries: 3,
+            start_period: None,
+            startup_retries: None,
         });
 
         let config_dir = std::path::Path::new("/tmp");
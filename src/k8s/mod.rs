@@ -2,5 +2,5 @@ pub mod client;
 pub mod manifest;
 pub mod runtime;
 
-pub use client::K8sClient;
+pub use client::{stop_port_forward, K8sClient, KubeResources, KubeTop};
 pub use runtime::K8sRuntime;
@@ -1,5 +1,7 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
 use colored::Colorize;
 use serde::Serialize;
@@ -14,17 +16,98 @@ pub struct LogLine {
     pub line: String,
     #[serde(skip)]
     pub color_idx: usize,
+    #[serde(skip)]
+    pub stream: LogStream,
+    /// When the line was captured.
+    #[serde(skip)]
+    pub at: SystemTime,
+}
+
+/// A `grep` over log lines: keeps the lines matching a regex, or with `invert` the
+/// ones that don't. Without a pattern every line is kept.
+#[derive(Debug, Clone, Default)]
+pub struct LineFilter {
+    re: Option<regex::Regex>,
+    invert: bool,
+}
+
+impl LineFilter {
+    pub fn new(pattern: Option<&str>, invert: bool) -> Result<Self, regex::Error> {
+        Ok(Self {
+            re: pattern.map(regex::Regex::new).transpose()?,
+            invert,
+        })
+    }
+
+    pub fn keeps(&self, line: &str) -> bool {
+        self.re
+            .as_ref()
+            .is_none_or(|re| re.is_match(line) != self.invert)
+    }
+
+    pub fn regex(&self) -> Option<&regex::Regex> {
+        self.re.as_ref()
+    }
+
+    pub fn invert(&self) -> bool {
+        self.invert
+    }
+}
+
+/// Which pipe of the service a line came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LogStream {
+    #[default]
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Where and how one service's log file is written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFileSpec {
+    pub path: PathBuf,
+    /// Rotate once the file reaches this many bytes; 0 = never.
+    pub max_bytes: u64,
+    /// Rotated files to keep: `<path>.1` (newest) … `<path>.<keep>`.
+    pub keep: u32,
+}
+
+/// Where every service's lines are kept on disk, as `<dir>/<service>.log`, so history
+/// outlives the supervisor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryDir {
+    pub dir: PathBuf,
+    /// Bytes all files in `dir` may take together; the oldest rotated ones go first.
+    pub retention: u64,
 }
 
 /// Aggregates log lines from all services into a single broadcast channel.
-/// Also maintains a ring buffer of recent lines for history replay.
+/// Also maintains a ring buffer of recent lines for history replay, backed by
+/// [`HistoryDir`] files once [`LogAggregator::persist_history`] is called.
 pub struct LogAggregator {
     tx: broadcast::Sender<LogLine>,
     history: Mutex<VecDeque<LogLine>>,
+    history_dir: Mutex<Option<HistoryDir>>,
+    /// Running log file writer of each service, so a restart doesn't add a second one.
+    file_writers: Mutex<HashMap<String, (LogFileSpec, tokio::task::AbortHandle)>>,
+    /// Secret values of each service, masked in its lines before they go anywhere.
+    masks: Arc<Mutex<HashMap<String, Vec<String>>>>,
 }
 
 const HISTORY_CAP: usize = 1000;
 
+/// Rotated copies of each history file kept before retention trims them.
+const HISTORY_KEEP: u32 = 3;
+
 // Fixed palette — one color per service slot (cycles if > 8 services)
 const COLORS: &[&str] = &[
     "cyan",
@@ -44,6 +127,9 @@ impl LogAggregator {
             Self {
                 tx,
                 history: Mutex::new(VecDeque::with_capacity(HISTORY_CAP)),
+                history_dir: Mutex::new(None),
+                file_writers: Mutex::new(HashMap::new()),
+                masks: Arc::default(),
             },
             rx,
         )
@@ -52,13 +138,16 @@ impl LogAggregator {
     /// Spawn a task that reads lines from `stdout` and broadcasts them.
     pub fn attach(&self, service: String, color_idx: usize, stdout: ChildStdout) {
         let tx = self.tx.clone();
+        let masks = self.masks.clone();
         tokio::spawn(async move {
             let mut reader = BufReader::new(stdout).lines();
             while let Ok(Some(line)) = reader.next_line().await {
                 let _ = tx.send(LogLine {
+                    line: masked(&masks, &service, line),
                     service: service.clone(),
-                    line,
                     color_idx,
+                    stream: LogStream::Stdout,
+                    at: SystemTime::now(),
                 });
             }
         });
@@ -67,13 +156,16 @@ impl LogAggregator {
     /// Spawn a task that reads lines from `stderr` and broadcasts them.
     pub fn attach_stderr(&self, service: String, color_idx: usize, stderr: ChildStderr) {
         let tx = self.tx.clone();
+        let masks = self.masks.clone();
         tokio::spawn(async move {
             let mut reader = BufReader::new(stderr).lines();
             while let Ok(Some(line)) = reader.next_line().await {
                 let _ = tx.send(LogLine {
+                    line: masked(&masks, &service, line),
                     service: service.clone(),
-                    line,
                     color_idx,
+                    stream: LogStream::Stderr,
+                    at: SystemTime::now(),
                 });
             }
         });
@@ -85,6 +177,8 @@ impl LogAggregator {
             service: service.to_string(),
             line: line.to_string(),
             color_idx,
+            stream: LogStream::Stdout,
+            at: SystemTime::now(),
         };
         let mut history = self.history.lock().unwrap();
         if history.len() >= HISTORY_CAP {
@@ -95,83 +189,49 @@ impl LogAggregator {
         let _ = self.tx.send(entry);
     }
 
+    /// Mask `values` in `service`'s lines from now on, replacing those of its previous
+    /// start.
+    pub fn set_masks(&self, service: &str, values: Vec<String>) {
+        let mut masks = self.masks.lock().unwrap();
+        if values.is_empty() {
+            masks.remove(service);
+        } else {
+            masks.insert(service.to_string(), values);
+        }
+    }
+
     pub fn subscribe(&self) -> broadcast::Receiver<LogLine> {
         self.tx.subscribe()
     }
 
-    /// Spawn a task that writes log lines for `service` to `path` (append mode).
-    /// If `rotate_bytes` is non-zero, the file is rotated (renamed to `<path>.1`)
-    /// when it reaches that size.
-    pub fn register_log_file(&self, service: String, path: std::path::PathBuf, rotate_bytes: u64) {
-        let mut rx = self.tx.subscribe();
-        tokio::spawn(async move {
-            use tokio::io::AsyncWriteExt;
-
-            let open_file = |p: std::path::PathBuf| async move {
-                tokio::fs::OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(p)
-                    .await
-            };
-
-            let file = match open_file(path.clone()).await {
-                Ok(f) => f,
-                Err(e) => {
-                    tracing::warn!("cannot open log file {}: {e}", path.display());
-                    return;
-                }
-            };
-            let mut writer = tokio::io::BufWriter::new(file);
-            let mut bytes_written: u64 = 0;
-
-            loop {
-                match rx.recv().await {
-                    Ok(entry) if entry.service == service => {
-                        let line = format!("{}\n", entry.line);
-                        if writer.write_all(line.as_bytes()).await.is_err() {
-                            break;
-                        }
-                        let _ = writer.flush().await;
-                        bytes_written += line.len() as u64;
-
-                        // Rotate when the size limit is reached.
-                        if rotate_bytes > 0 && bytes_written >= rotate_bytes {
-                            let rotated = path.with_extension(
-                                path.extension()
-                                    .and_then(|e| e.to_str())
-                                    .map(|e| format!("{e}.1"))
-                                    .unwrap_or_else(|| "log.1".into()),
-                            );
-                            // Flush and drop the current writer before renaming.
-                            drop(writer);
-                            let _ = tokio::fs::rename(&path, &rotated).await;
-                            tracing::info!("[{service}] rotated log → {}", rotated.display());
-                            match open_file(path.clone()).await {
-                                Ok(f) => {
-                                    writer = tokio::io::BufWriter::new(f);
-                                    bytes_written = 0;
-                                }
-                                Err(e) => {
-                                    tracing::warn!(
-                                        "cannot reopen log file after rotation {}: {e}",
-                                        path.display()
-                                    );
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    Ok(_) => {}
-                    Err(broadcast::error::RecvError::Closed) => break,
-                    Err(broadcast::error::RecvError::Lagged(_)) => {}
-                }
+    /// Write `service`'s lines to `spec.path` (append mode, parent dirs created), each
+    /// prefixed with a UTC timestamp and the stream. Registering the same spec again —
+    /// as every restart does — keeps the running writer; a changed spec replaces it.
+    pub fn register_log_file(&self, service: String, spec: LogFileSpec) {
+        let mut writers = self.file_writers.lock().unwrap();
+        if let Some((running, handle)) = writers.get(&service) {
+            if *running == spec && !handle.is_finished() {
+                return;
             }
-        });
+            handle.abort();
+        }
+        let rx = self.tx.subscribe();
+        let task = tokio::spawn(write_log_file(service.clone(), spec.clone(), rx));
+        writers.insert(service, (spec, task.abort_handle()));
     }
 
     /// Return up to `n` recent log lines, optionally filtered by service.
     pub fn recent(&self, services: &[String], n: usize) -> Vec<LogLine> {
+        self.recent_since(services, n, None)
+    }
+
+    /// [`Self::recent`] without lines captured before `cutoff`.
+    fn recent_since(
+        &self,
+        services: &[String],
+        n: usize,
+        cutoff: Option<SystemTime>,
+    ) -> Vec<LogLine> {
         let history = match self.history.lock() {
             Ok(h) => h,
             Err(e) => {
@@ -182,6 +242,7 @@ impl LogAggregator {
         history
             .iter()
             .filter(|l| services.is_empty() || services.contains(&l.service))
+            .filter(|l| cutoff.is_none_or(|c| l.at >= c))
             .rev()
             .take(n)
             .cloned()
@@ -191,6 +252,37 @@ impl LogAggregator {
             .collect()
     }
 
+    /// Up to `n` recent lines like [`Self::recent`], read from the history files
+    /// instead when memory doesn't go back that far (e.g. after a restart). With
+    /// `since`, only lines captured at or after it.
+    pub fn history(
+        &self,
+        services: &[String],
+        n: usize,
+        since: Option<SystemTime>,
+    ) -> Vec<LogLine> {
+        let recent = self.recent_since(services, n, since);
+        if recent.len() >= n {
+            return recent;
+        }
+        let Some(spec) = self.history_dir.lock().unwrap().clone() else {
+            return recent;
+        };
+        let disk = read_history(&spec.dir, services, n, since);
+        if disk.len() > recent.len() {
+            disk
+        } else {
+            recent
+        }
+    }
+
+    /// Append every line to its service's file under `spec.dir` from now on, rotating
+    /// each file at a quarter of `spec.retention` and trimming the directory to it.
+    pub fn persist_history(log: &Arc<Self>, spec: HistoryDir) {
+        *log.history_dir.lock().unwrap() = Some(spec.clone());
+        tokio::spawn(write_history(spec, log.tx.subscribe()));
+    }
+
     /// Print log lines to stdout with colored service prefix.
     pub async fn print_loop(mut rx: broadcast::Receiver<LogLine>) {
         loop {
@@ -247,6 +339,403 @@ impl LogAggregator {
     }
 }
 
+/// `line` of `service` with its secrets masked.
+fn masked(masks: &Mutex<HashMap<String, Vec<String>>>, service: &str, line: String) -> String {
+    match masks.lock().unwrap().get(service) {
+        Some(values) => crate::secrets::mask(line, values),
+        None => line,
+    }
+}
+
+async fn write_log_file(service: String, spec: LogFileSpec, mut rx: broadcast::Receiver<LogLine>) {
+    use tokio::io::AsyncWriteExt;
+
+    let path = spec.path;
+    let open_file = |p: PathBuf| async move {
+        if let Some(dir) = p.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(p)
+            .await
+    };
+
+    let file = match open_file(path.clone()).await {
+        Ok(f) => f,
+        Err(e) => {
+            tracing::warn!("cannot open log file {}: {e}", path.display());
+            return;
+        }
+    };
+    // Appending across restarts: count what is already there towards rotation.
+    let mut bytes_written = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+    let mut writer = tokio::io::BufWriter::new(file);
+
+    loop {
+        match rx.recv().await {
+            Ok(entry) if entry.service == service => {
+                let line = format_file_line(&utc_timestamp(entry.at), &entry);
+                if writer.write_all(line.as_bytes()).await.is_err() {
+                    break;
+                }
+                let _ = writer.flush().await;
+                bytes_written += line.len() as u64;
+
+                // Rotate when the size limit is reached.
+                if spec.max_bytes > 0 && bytes_written >= spec.max_bytes {
+                    // Flush and drop the current writer before renaming.
+                    drop(writer);
+                    if let Err(e) = rotate_files(&path, spec.keep) {
+                        tracing::warn!("[{service}] cannot rotate {}: {e}", path.display());
+                    } else {
+                        tracing::info!("[{service}] rotated log {}", path.display());
+                    }
+                    match open_file(path.clone()).await {
+                        Ok(f) => {
+                            writer = tokio::io::BufWriter::new(f);
+                            bytes_written = 0;
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                "cannot reopen log file after rotation {}: {e}",
+                                path.display()
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Closed) => break,
+            Err(broadcast::error::RecvError::Lagged(_)) => {}
+        }
+    }
+}
+
+async fn write_history(spec: HistoryDir, mut rx: broadcast::Receiver<LogLine>) {
+    use tokio::io::AsyncWriteExt;
+
+    if let Err(e) = tokio::fs::create_dir_all(&spec.dir).await {
+        tracing::warn!("cannot create log dir {}: {e}", spec.dir.display());
+        return;
+    }
+    let rotate_at = (spec.retention / 4).max(1);
+    // Open file of each service that has logged, with its size so far.
+    let mut files: HashMap<String, (tokio::io::BufWriter<tokio::fs::File>, u64)> = HashMap::new();
+
+    loop {
+        let entry = match rx.recv().await {
+            Ok(entry) => entry,
+            Err(broadcast::error::RecvError::Closed) => break,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+        };
+        let path = history_path(&spec.dir, &entry.service);
+        if !files.contains_key(&entry.service) {
+            let file = match tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await
+            {
+                Ok(f) => f,
+                Err(e) => {
+                    tracing::warn!("cannot open log file {}: {e}", path.display());
+                    continue;
+                }
+            };
+            let len = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+            files.insert(
+                entry.service.clone(),
+                (tokio::io::BufWriter::new(file), len),
+            );
+        }
+        let Some((writer, len)) = files.get_mut(&entry.service) else {
+            continue;
+        };
+        let line = format_file_line(&utc_timestamp(entry.at), &entry);
+        if writer.write_all(line.as_bytes()).await.is_err() {
+            files.remove(&entry.service);
+            continue;
+        }
+        let _ = writer.flush().await;
+        *len += line.len() as u64;
+
+        if *len >= rotate_at {
+            // Closed here, reopened fresh by the service's next line.
+            files.remove(&entry.service);
+            if let Err(e) = rotate_files(&path, HISTORY_KEEP) {
+                tracing::warn!("[{}] cannot rotate {}: {e}", entry.service, path.display());
+            }
+            if let Err(e) = trim_history(&spec.dir, spec.retention) {
+                tracing::warn!("cannot trim log dir {}: {e}", spec.dir.display());
+            }
+        }
+    }
+}
+
+/// `<dir>/<service>.log`.
+fn history_path(dir: &Path, service: &str) -> PathBuf {
+    dir.join(format!("{}.log", service.replace(['/', '\\'], "_")))
+}
+
+/// Delete rotated files in `dir`, oldest first, until everything in it fits in
+/// `retention` bytes. The files being written to are left alone.
+fn trim_history(dir: &Path, retention: u64) -> std::io::Result<()> {
+    let mut total = 0;
+    let mut rotated = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        if !meta.is_file() {
+            continue;
+        }
+        total += meta.len();
+        if entry.path().extension().is_some_and(|e| e != "log") {
+            let modified = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
+            rotated.push((modified, meta.len(), entry.path()));
+        }
+    }
+    rotated.sort();
+    for (_, len, path) in rotated {
+        if total <= retention {
+            break;
+        }
+        std::fs::remove_file(&path)?;
+        total -= len;
+    }
+    Ok(())
+}
+
+/// The last `n` lines of `services` (every file in `dir` when empty) across their
+/// history files, oldest first, reading each file backwards from its end. Lines
+/// written before `cutoff` are left out.
+fn read_history(
+    dir: &Path,
+    services: &[String],
+    n: usize,
+    cutoff: Option<SystemTime>,
+) -> Vec<LogLine> {
+    // Timestamps sort as text, so the cutoff is compared in the same format.
+    let cutoff = cutoff.map(utc_timestamp);
+    let before_cutoff = |line: &str| {
+        cutoff
+            .as_deref()
+            .is_some_and(|c| line.split(' ').next().unwrap_or("") < c)
+    };
+    let services: Vec<String> = if services.is_empty() {
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "log"))
+            .filter_map(|p| Some(p.file_stem()?.to_string_lossy().into_owned()))
+            .collect()
+    } else {
+        services.to_vec()
+    };
+    let mut lines: Vec<(String, LogLine)> = Vec::new();
+    for service in services {
+        let path = history_path(dir, &service);
+        let mut newest_first: Vec<String> = Vec::new();
+        for gen in 0..=HISTORY_KEEP {
+            if newest_first.len() >= n {
+                break;
+            }
+            let file = if gen == 0 {
+                path.clone()
+            } else {
+                rotated_path(&path, gen)
+            };
+            match tail_lines(&file, n - newest_first.len()) {
+                Ok(tail) => newest_first.extend(tail.into_iter().rev()),
+                Err(_) => break,
+            }
+            // Older rotated files can only hold older lines.
+            if newest_first.last().is_some_and(|l| before_cutoff(l)) {
+                break;
+            }
+        }
+        lines.extend(
+            newest_first
+                .into_iter()
+                .rev()
+                .filter(|l| !before_cutoff(l))
+                .filter_map(|l| parse_file_line(&service, &l)),
+        );
+    }
+    // Timestamps sort as text; a stable sort keeps each service's own order.
+    lines.sort_by(|a, b| a.0.cmp(&b.0));
+    let skip = lines.len().saturating_sub(n);
+    lines.into_iter().skip(skip).map(|(_, l)| l).collect()
+}
+
+/// Up to the last `n` lines of `path`, oldest first, reading only as much of the end
+/// of the file as it takes.
+fn tail_lines(path: &Path, n: usize) -> std::io::Result<Vec<String>> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    const CHUNK: u64 = 8192;
+    let mut file = std::fs::File::open(path)?;
+    let mut pos = file.metadata()?.len();
+    let mut buf: Vec<u8> = Vec::new();
+    // One more newline than lines wanted, unless the whole file has been read.
+    while pos > 0 && buf.iter().filter(|&&b| b == b'\n').count() <= n {
+        let step = CHUNK.min(pos);
+        pos -= step;
+        file.seek(SeekFrom::Start(pos))?;
+        let mut chunk = vec![0; step as usize];
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+    }
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<&str> = text.lines().collect();
+    if pos > 0 && !lines.is_empty() {
+        // Starts mid-line.
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(n);
+    Ok(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+/// Timestamp and line back from a [`format_file_line`] line of `service`.
+fn parse_file_line(service: &str, line: &str) -> Option<(String, LogLine)> {
+    let (ts, rest) = line.split_once(' ')?;
+    let (stream, text) = rest.split_once(' ').unwrap_or((rest, ""));
+    let stream = match stream {
+        "stdout" => LogStream::Stdout,
+        "stderr" => LogStream::Stderr,
+        _ => return None,
+    };
+    Some((
+        ts.to_string(),
+        LogLine {
+            service: service.to_string(),
+            line: text.to_string(),
+            color_idx: 0,
+            stream,
+            at: parse_timestamp(ts).unwrap_or(std::time::UNIX_EPOCH),
+        },
+    ))
+}
+
+/// One log file line: `2024-05-01T10:00:00.123Z stderr <line>`.
+fn format_file_line(ts: &str, entry: &LogLine) -> String {
+    format!("{ts} {} {}\n", entry.stream.as_str(), entry.line)
+}
+
+/// `<path>.<n>`, the n-th newest rotated copy of `path`.
+fn rotated_path(path: &Path, n: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+/// Shift `<path>.1` … `<path>.<keep-1>` up by one, dropping the oldest, and move
+/// `path` to `<path>.1`.
+fn rotate_files(path: &Path, keep: u32) -> std::io::Result<()> {
+    let keep = keep.max(1);
+    for n in (1..keep).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            std::fs::rename(&from, rotated_path(path, n + 1))?;
+        }
+    }
+    std::fs::rename(path, rotated_path(path, 1))
+}
+
+/// RFC 3339 UTC with milliseconds, e.g. `2024-05-01T10:00:00.123Z`.
+fn utc_timestamp(t: SystemTime) -> String {
+    let d = t.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    let secs = d.as_secs() as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Civil from days (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60,
+        d.subsec_millis()
+    )
+}
+
+/// An RFC 3339 time such as `2024-05-01T10:00:00Z` or `2024-05-01T12:00:00.5+02:00`,
+/// [`utc_timestamp`] strings included.
+pub fn parse_timestamp(ts: &str) -> Option<SystemTime> {
+    let num = |r: std::ops::Range<usize>| ts.get(r)?.parse::<i64>().ok();
+    let sep = |i: usize, seps: &[u8]| ts.as_bytes().get(i).is_some_and(|b| seps.contains(b));
+    if !(sep(4, b"-") && sep(7, b"-") && sep(10, b"Tt ") && sep(13, b":") && sep(16, b":")) {
+        return None;
+    }
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, min, sec) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    let (nanos, zone) = match ts.get(19..)?.strip_prefix('.') {
+        Some(frac) => {
+            let digits = frac
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(frac.len());
+            if digits == 0 {
+                return None;
+            }
+            let nanos = format!("{:0<9}", &frac[..digits.min(9)])
+                .parse::<u32>()
+                .ok()?;
+            (nanos, &frac[digits..])
+        }
+        None => (0, &ts[19..]),
+    };
+    let offset = match zone {
+        "Z" | "z" => 0,
+        // ASCII only, so the byte offsets below are char boundaries.
+        _ if zone.len() == 6 && zone.is_ascii() && zone.as_bytes()[3] == b':' => {
+            let sign = match zone.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            sign * (zone[1..3].parse::<i64>().ok()? * 3600 + zone[4..6].parse::<i64>().ok()? * 60)
+        }
+        _ => return None,
+    };
+    // Days from civil (Howard Hinnant's algorithm), the inverse of the above.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    let secs = days * 86_400 + hour * 3600 + min * 60 + sec - offset;
+    std::time::UNIX_EPOCH.checked_add(Duration::new(u64::try_from(secs).ok()?, nanos))
+}
+
+/// The cutoff for a `since` given as how long ago (`"10m"`) or an RFC 3339 time.
+pub fn parse_since(since: &str) -> Result<SystemTime, String> {
+    if let Some(t) = parse_timestamp(since.trim()) {
+        return Ok(t);
+    }
+    crate::config::parse_duration(since)
+        .map(|d| {
+            SystemTime::now()
+                .checked_sub(d)
+                .unwrap_or(std::time::UNIX_EPOCH)
+        })
+        .map_err(|_| {
+            format!("invalid since '{since}': use a duration like '10m' or an RFC 3339 time")
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -256,6 +745,8 @@ mod tests {
             service: service.to_string(),
             line: line.to_string(),
             color_idx: 0,
+            stream: LogStream::Stdout,
+            at: SystemTime::now(),
         }
     }
 
@@ -274,6 +765,19 @@ mod tests {
         assert_eq!(all[2].line, "line3");
     }
 
+    #[test]
+    fn test_line_filter() {
+        let errors = LineFilter::new(Some("ERROR|WARN"), false).unwrap();
+        assert!(errors.keeps("WARN disk almost full"));
+        assert!(!errors.keeps("INFO ready"));
+        let others = LineFilter::new(Some("ERROR|WARN"), true).unwrap();
+        assert!(!others.keeps("ERROR boom"));
+        assert!(others.keeps("INFO ready"));
+        // Nothing to invert without a pattern.
+        assert!(LineFilter::new(None, true).unwrap().keeps("anything"));
+        assert!(LineFilter::new(Some("(unclosed"), false).is_err());
+    }
+
     #[test]
     fn test_recent_filters_by_service() {
         let (agg, _rx) = LogAggregator::new();
@@ -326,4 +830,277 @@ mod tests {
         assert_eq!(agg.recent(&[], 10).len(), 0);
         assert_eq!(agg.recent(&["svc".to_string()], 10).len(), 0);
     }
+
+    #[test]
+    fn test_utc_timestamp() {
+        let t = std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_714_557_600_123);
+        assert_eq!(utc_timestamp(t), "2024-05-01T10:00:00.123Z");
+        assert_eq!(
+            utc_timestamp(std::time::UNIX_EPOCH),
+            "1970-01-01T00:00:00.000Z"
+        );
+        let leap = std::time::UNIX_EPOCH + std::time::Duration::from_secs(951_782_400);
+        assert_eq!(utc_timestamp(leap), "2000-02-29T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_file_line_has_timestamp_and_stream() {
+        let mut entry = make_line("api", "listening on :3000");
+        assert_eq!(
+            format_file_line("2024-05-01T10:00:00.123Z", &entry),
+            "2024-05-01T10:00:00.123Z stdout listening on :3000\n"
+        );
+        entry.stream = LogStream::Stderr;
+        assert!(format_file_line("t", &entry).starts_with("t stderr "));
+    }
+
+    #[test]
+    fn test_rotate_keeps_n_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api.log");
+        for gen in ["first", "second", "third", "fourth"] {
+            std::fs::write(&path, gen).unwrap();
+            rotate_files(&path, 2).unwrap();
+        }
+        let read = |p: PathBuf| std::fs::read_to_string(p).unwrap();
+        assert!(!path.exists());
+        assert_eq!(read(rotated_path(&path, 1)), "fourth");
+        assert_eq!(read(rotated_path(&path, 2)), "third");
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    /// Wait for the writer task to write `needle` to `path`.
+    async fn wait_for(path: &Path, needle: &str) -> String {
+        for _ in 0..200 {
+            let text = std::fs::read_to_string(path).unwrap_or_default();
+            if text.contains(needle) {
+                return text;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        std::fs::read_to_string(path).unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_log_file_created_with_parents_and_not_duplicated_on_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/nested/api.log");
+        let (agg, _rx) = LogAggregator::new();
+        let spec = LogFileSpec {
+            path: path.clone(),
+            max_bytes: 0,
+            keep: 1,
+        };
+        agg.register_log_file("api".into(), spec.clone());
+        // A restart registers the same file again.
+        agg.register_log_file("api".into(), spec);
+        tokio::task::yield_now().await;
+        agg.push("api", "hello", 0);
+        agg.push("web", "not mine", 0);
+        agg.push("api", "bye", 0);
+        let text = wait_for(&path, "bye").await;
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2, "{text}");
+        assert!(lines[0].ends_with(" stdout hello"), "{text}");
+        assert!(lines[1].ends_with(" stdout bye"), "{text}");
+    }
+
+    #[tokio::test]
+    async fn test_log_file_rotates_at_max_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api.log");
+        // Already-written bytes count towards the limit.
+        std::fs::write(&path, "x".repeat(90)).unwrap();
+        let (agg, _rx) = LogAggregator::new();
+        agg.register_log_file(
+            "api".into(),
+            LogFileSpec {
+                path: path.clone(),
+                max_bytes: 100,
+                keep: 1,
+            },
+        );
+        tokio::task::yield_now().await;
+        agg.push("api", "over the limit", 0);
+        agg.push("api", "fresh file", 0);
+        let text = wait_for(&path, "fresh file").await;
+        assert!(text.ends_with(" stdout fresh file\n"), "{text}");
+        let rotated = std::fs::read_to_string(rotated_path(&path, 1)).unwrap();
+        assert!(rotated.ends_with(" stdout over the limit\n"), "{rotated}");
+    }
+
+    #[test]
+    fn test_tail_lines_reads_only_the_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api.log");
+        // Longer than one read chunk, so the tail starts mid-file.
+        let text: String = (0..2000).map(|i| format!("line {i}\n")).collect();
+        std::fs::write(&path, text).unwrap();
+        assert_eq!(
+            tail_lines(&path, 3).unwrap(),
+            ["line 1997", "line 1998", "line 1999"]
+        );
+        assert_eq!(tail_lines(&path, 5000).unwrap().len(), 2000);
+        assert!(tail_lines(&dir.path().join("missing.log"), 3).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_history_falls_back_to_files_across_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let api = dir.path().join("api.log");
+        std::fs::write(
+            rotated_path(&api, 1),
+            "2024-05-01T10:00:00.000Z stdout booting\n2024-05-01T10:00:01.000Z stderr panic: oops\n",
+        )
+        .unwrap();
+        std::fs::write(&api, "2024-05-01T10:00:03.000Z stdout booting again\n").unwrap();
+        std::fs::write(
+            dir.path().join("web.log"),
+            "2024-05-01T10:00:02.000Z stdout ready\n",
+        )
+        .unwrap();
+
+        let agg = Arc::new(LogAggregator::new().0);
+        LogAggregator::persist_history(
+            &agg,
+            HistoryDir {
+                dir: dir.path().to_path_buf(),
+                retention: 1 << 20,
+            },
+        );
+        let text = |lines: Vec<LogLine>| lines.into_iter().map(|l| l.line).collect::<Vec<_>>();
+        assert_eq!(
+            text(agg.history(&["api".to_string()], 2, None)),
+            ["panic: oops", "booting again"]
+        );
+        assert_eq!(
+            text(agg.history(&[], 10, None)),
+            ["booting", "panic: oops", "ready", "booting again"]
+        );
+        assert_eq!(agg.history(&[], 10, None)[1].stream, LogStream::Stderr);
+    }
+
+    #[test]
+    fn test_history_since_filters_by_capture_time() {
+        let (agg, _rx) = LogAggregator::new();
+        let now = SystemTime::now();
+        {
+            let mut h = agg.history.lock().unwrap();
+            for (line, ago) in [("old", 3600), ("recent", 300), ("new", 5)] {
+                let mut l = make_line("api", line);
+                l.at = now - Duration::from_secs(ago);
+                h.push_back(l);
+            }
+        }
+        let text = |lines: Vec<LogLine>| lines.into_iter().map(|l| l.line).collect::<Vec<_>>();
+        let ten_min = Some(now - Duration::from_secs(600));
+        assert_eq!(text(agg.history(&[], 100, ten_min)), ["recent", "new"]);
+        // Both limits apply.
+        assert_eq!(text(agg.history(&[], 1, ten_min)), ["new"]);
+        assert_eq!(text(agg.history(&[], 100, None)), ["old", "recent", "new"]);
+    }
+
+    #[test]
+    fn test_read_history_since_skips_older_lines_and_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let api = dir.path().join("api.log");
+        std::fs::write(
+            rotated_path(&api, 1),
+            "2024-05-01T09:00:00.000Z stdout yesterday-ish
+",
+        )
+        .unwrap();
+        std::fs::write(
+            &api,
+            "2024-05-01T09:59:00.000Z stdout before
+2024-05-01T10:00:30.000Z stderr after
+",
+        )
+        .unwrap();
+        let cutoff = parse_timestamp("2024-05-01T10:00:00.000Z");
+        let lines = read_history(dir.path(), &[], 10, cutoff);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].line, "after");
+        assert_eq!(read_history(dir.path(), &[], 10, None).len(), 3);
+    }
+
+    #[test]
+    fn test_parse_timestamp_inverts_utc_timestamp() {
+        for millis in [0, 951_782_400_123, 1_714_557_600_500, 4_102_444_799_999] {
+            let t = std::time::UNIX_EPOCH + Duration::from_millis(millis);
+            assert_eq!(parse_timestamp(&utc_timestamp(t)), Some(t));
+        }
+        assert_eq!(parse_timestamp("not a time"), None);
+    }
+
+    #[test]
+    fn test_parse_timestamp_accepts_rfc3339_offsets_and_fractions() {
+        let t = std::time::UNIX_EPOCH + Duration::from_secs(1_714_557_600);
+        assert_eq!(parse_timestamp("2024-05-01T10:00:00Z"), Some(t));
+        assert_eq!(parse_timestamp("2024-05-01T12:00:00+02:00"), Some(t));
+        assert_eq!(parse_timestamp("2024-05-01t06:30:00-03:30"), Some(t));
+        assert_eq!(
+            parse_timestamp("2024-05-01T10:00:00.25Z"),
+            Some(t + Duration::from_millis(250))
+        );
+        for bad in [
+            "2024-05-01T10:00:00",
+            "2024-05-01T10:00:00.Z",
+            "2024-05-01 10:00",
+            "2024-05-01T10:00:00é0:00",
+        ] {
+            assert_eq!(parse_timestamp(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_parse_since_takes_a_duration_or_a_time() {
+        let cutoff = parse_since("10m").unwrap();
+        let ago = SystemTime::now().duration_since(cutoff).unwrap();
+        assert!(ago >= Duration::from_secs(600));
+        assert_eq!(
+            parse_since("2024-05-01T10:00:00Z"),
+            Ok(std::time::UNIX_EPOCH + Duration::from_secs(1_714_557_600))
+        );
+        assert!(parse_since("yesterday").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_persisted_history_is_written_per_service() {
+        let dir = tempfile::tempdir().unwrap();
+        let agg = Arc::new(LogAggregator::new().0);
+        LogAggregator::persist_history(
+            &agg,
+            HistoryDir {
+                dir: dir.path().join("logs"),
+                retention: 1 << 20,
+            },
+        );
+        tokio::task::yield_now().await;
+        agg.push("api", "hello", 0);
+        agg.push("web", "hi", 0);
+        let text = wait_for(&dir.path().join("logs/api.log"), "hello").await;
+        assert!(text.ends_with(" stdout hello\n"), "{text}");
+        let text = wait_for(&dir.path().join("logs/web.log"), "hi").await;
+        assert!(!text.contains("hello"), "{text}");
+    }
+
+    #[test]
+    fn test_trim_history_drops_oldest_rotated_files_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let api = dir.path().join("api.log");
+        std::fs::write(rotated_path(&api, 2), "x".repeat(40)).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(rotated_path(&api, 1), "x".repeat(40)).unwrap();
+        std::fs::write(&api, "x".repeat(40)).unwrap();
+
+        trim_history(dir.path(), 100).unwrap();
+        assert!(!rotated_path(&api, 2).exists());
+        assert!(rotated_path(&api, 1).exists());
+
+        // The file being written to stays even over the limit.
+        trim_history(dir.path(), 10).unwrap();
+        assert!(!rotated_path(&api, 1).exists());
+        assert!(api.exists());
+    }
 }
@@ -4,18 +4,27 @@ use std::sync::Arc;
 use clap::{Parser, Subcommand};
 use colored::Colorize;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::UnixStream;
 
 mod box_mgr;
 mod cert;
 mod config;
+mod daemon;
+mod doctor;
 mod error;
+mod exec;
 mod graph;
 mod health;
+mod health_stats;
+mod hints;
+mod init;
 mod ipc;
 mod k8s;
 mod log;
+mod parse_error;
+mod progress;
 mod proxy;
+mod secrets;
+mod share;
 mod state;
 mod supervisor;
 mod ui;
@@ -38,10 +47,34 @@ struct Cli {
     #[arg(short, long, default_value = "A3sfile.hcl")]
     file: PathBuf,
 
+    /// Show the full output of failing external tools instead of a hint
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Print JSON instead of tables (same as `--format json`)
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Output format of status, config, health, doctor and `box` listings
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+impl Cli {
+    fn json(&self) -> bool {
+        self.json || self.format == OutputFormat::Json
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Start all (or named) services in dependency order
@@ -51,7 +84,8 @@ enum Commands {
         /// Filter services by label (can be repeated, e.g., --label backend --label critical)
         #[arg(short, long)]
         label: Vec<String>,
-        /// Apply a named env_override block from A3sfile.hcl (e.g., --env staging)
+        /// Apply a named environment / env_override block from A3sfile.hcl (e.g., --env ci;
+        /// default: $A3S_ENV)
         #[arg(short, long)]
         env: Option<String>,
         /// Run as background daemon (detach from terminal)
@@ -69,6 +103,14 @@ enum Commands {
         /// Timeout in seconds for --wait (default: 60)
         #[arg(long, default_value_t = 60)]
         wait_timeout: u64,
+        /// Print what would be started — commands, ports, env, dependency waits and
+        /// health checks — without starting anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Set on the background process `--detach` starts: SIGHUP reloads the config
+        /// rather than meaning the terminal went away.
+        #[arg(long, hide = true)]
+        daemonized: bool,
     },
     /// Stop all (or named) services
     Down {
@@ -80,12 +122,21 @@ enum Commands {
     },
     /// Restart a service
     Restart { service: String },
+    /// Send a signal to a running service without stopping it (e.g. SIGHUP to reload)
+    Kill {
+        service: String,
+        /// Signal name, e.g. SIGHUP, USR1 or term
+        #[arg(short, long)]
+        signal: String,
+    },
+    /// Start stopped services (e.g. `autostart = false` ones) and their dependencies
+    Start {
+        #[arg(required = true)]
+        services: Vec<String>,
+    },
     /// Show service status (alias: ps)
     #[command(alias = "ps")]
     Status {
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
         /// Continuously refresh (like top)
         #[arg(short, long)]
         watch: bool,
@@ -95,18 +146,30 @@ enum Commands {
     },
     /// Tail logs (all services or one)
     Logs {
-        /// Filter to specific services (can be repeated)
+        /// Services to show, e.g. `a3s logs api worker` (default: all)
+        services: Vec<String>,
+        /// Also show this service (can be repeated)
         #[arg(short, long)]
         service: Vec<String>,
         /// Keep streaming
         #[arg(short, long, default_value_t = true)]
         follow: bool,
-        /// Filter log lines by keyword (case-insensitive)
+        /// Print the history and exit instead of streaming
+        #[arg(long)]
+        no_follow: bool,
+        /// Only show lines matching this regex, e.g. 'ERROR|WARN' (case-insensitive)
         #[arg(short, long)]
         grep: Option<String>,
+        /// With --grep, show the lines that don't match instead
+        #[arg(long, requires = "grep")]
+        invert: bool,
         /// Number of historical lines to show (default: 200)
         #[arg(short = 'n', long, default_value_t = 200)]
         last: usize,
+        /// Only show lines from this long ago on, e.g. "10m" or "2h", or from an RFC 3339
+        /// time such as 2024-05-01T10:00:00Z
+        #[arg(long, value_parser = since_arg)]
+        since: Option<String>,
         /// Prefix each line with a timestamp
         #[arg(short = 't', long)]
         timestamps: bool,
@@ -117,6 +180,25 @@ enum Commands {
         #[arg(long)]
         strict: bool,
     },
+    /// Scaffold an A3sfile.hcl by inspecting the current project
+    Init {
+        /// Overwrite an existing A3sfile.hcl
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the JSON Schema for A3sfile.hcl (for editor completion and validation)
+    #[cfg(feature = "schema")]
+    Schema,
+    /// Inspect A3sfile.hcl without starting anything
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Health-check statistics of the last 24h
+    Health {
+        #[command(subcommand)]
+        command: HealthCommand,
+    },
     /// Show live CPU and memory usage per service (requires a running daemon)
     Top {
         /// Refresh interval in seconds
@@ -130,6 +212,23 @@ enum Commands {
         /// Port mapping: <local-port>:<remote-port>
         ports: String,
     },
+    /// Run a command in a running pod, attached to this terminal
+    PodExec {
+        /// Pod name
+        pod: String,
+        /// Container in the pod (default: the pod's default container)
+        #[arg(short, long)]
+        container: Option<String>,
+        /// Namespace (default: k8s_namespace from A3sfile.hcl)
+        #[arg(short, long)]
+        namespace: Option<String>,
+        /// Command and arguments
+        #[arg(trailing_var_arg = true, required = true)]
+        cmd: Vec<String>,
+    },
+    /// Check that the tools A3sfile.hcl needs are installed, with a fix for each
+    /// missing one; exits non-zero if a required one is missing
+    Doctor,
     /// Upgrade a3s to the latest version
     Upgrade,
     /// List all installed a3s ecosystem tools
@@ -139,7 +238,8 @@ enum Commands {
         /// Tool name(s) to update: box, gateway, power (default: all)
         tools: Vec<String>,
     },
-    /// Run a command in a service's environment and directory
+    /// Run a command in a service's environment and directory (inside the container for
+    /// container services)
     Exec {
         /// Service name to take env and dir from
         service: String,
@@ -157,12 +257,109 @@ enum Commands {
         cmd: Vec<String>,
     },
     /// Reload A3sfile.hcl without restarting unchanged services
-    Reload,
+    Reload {
+        /// Don't restart services whose definition changed; just list them
+        #[arg(long)]
+        no_restart: bool,
+    },
+    /// Share a running service with a teammate through a temporary reverse tunnel
+    Share {
+        /// Service to share
+        service: String,
+        /// Tear the tunnel down automatically after this long (e.g. 30m, 1h)
+        #[arg(long)]
+        ttl: Option<String>,
+        /// Stop sharing the service
+        #[arg(long)]
+        stop: bool,
+    },
     /// Proxy to an a3s ecosystem tool (e.g. `a3s box`, `a3s gateway`)
     #[command(external_subcommand)]
     Tool(Vec<String>),
 }
 
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Report every problem in A3sfile.hcl at once; exits non-zero on errors (for CI)
+    Check,
+    /// Report "probably wrong" settings that don't block startup
+    Lint,
+    /// List services with the file(s) each comes from, after includes and A3sfile.local.hcl
+    Show {
+        /// Show the config with this environment applied (default: $A3S_ENV)
+        #[arg(short, long)]
+        env: Option<String>,
+    },
+}
+
+/// `a3s box build` — handled here rather than proxied, so the tag can default.
+#[derive(Parser)]
+#[command(name = "a3s box build")]
+struct BoxBuildArgs {
+    /// Build context directory
+    #[arg(default_value = ".")]
+    context: PathBuf,
+    /// Image tag (default: the context directory name)
+    #[arg(short, long)]
+    tag: Option<String>,
+    /// Containerfile to use (default: the one in the context)
+    #[arg(short, long)]
+    file: Option<PathBuf>,
+    /// Build-time variable, as KEY=VALUE (repeatable)
+    #[arg(long = "build-arg", value_name = "KEY=VALUE", value_parser = parse_build_arg)]
+    build_args: Vec<(String, String)>,
+}
+
+fn parse_build_arg(s: &str) -> std::result::Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((k, v)) if !k.is_empty() => Ok((k.to_string(), v.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{s}'")),
+    }
+}
+
+/// `a3s box image prune` — handled here so removing in-use-able images needs a yes.
+#[derive(Parser)]
+#[command(name = "a3s box image prune")]
+struct BoxImagePruneArgs {
+    /// Remove every image no container uses, not just dangling ones
+    #[arg(short, long)]
+    all: bool,
+    /// Don't ask for confirmation
+    #[arg(short, long)]
+    force: bool,
+}
+
+/// `a3s box network create` — succeeds when the network already exists.
+#[derive(Parser)]
+#[command(name = "a3s box network create")]
+struct BoxNetworkCreateArgs {
+    /// Network name
+    name: String,
+    /// Network driver (default: a3s-box's default)
+    #[arg(short, long)]
+    driver: Option<String>,
+    /// Subnet in CIDR form, e.g. 10.88.0.0/16
+    #[arg(long)]
+    subnet: Option<String>,
+}
+
+/// `a3s box volume create` — succeeds when the volume already exists.
+#[derive(Parser)]
+#[command(name = "a3s box volume create")]
+struct BoxVolumeCreateArgs {
+    /// Volume name
+    name: String,
+    /// Volume driver (default: local)
+    #[arg(short, long)]
+    driver: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum HealthCommand {
+    /// Uptime, flaps and longest outage per service; works without a running daemon
+    Report,
+}
+
 #[tokio::main]
 async fn main() {
     // Parse CLI first so we can read log_level from A3sfile.hcl for `up`
@@ -186,15 +383,26 @@ async fn main() {
         .without_time()
         .init();
 
+    hints::set_verbose(cli.verbose);
+    let json = cli.json();
+    if json {
+        colored::control::set_override(false);
+    }
     if let Err(e) = run(cli).await {
-        eprintln!("{} {e}", "[a3s]".red().bold());
+        if json {
+            println!("{}", json_envelope::<()>(None, Some(&e.to_string())));
+        } else {
+            eprintln!("{} {e}", "[a3s]".red().bold());
+        }
         std::process::exit(1);
     }
 }
 
 async fn run(cli: Cli) -> Result<()> {
-    // Project-specific socket path — computed once and used by all IPC client commands.
-    let sock = ipc::socket_path(&cli.file);
+    // Project-specific socket (and TCP port) — computed once and used by all IPC
+    // client commands.
+    let sock = ipc::IpcAddr::for_project(&cli.file);
+    let json = cli.json();
 
     match &cli.command {
         Commands::Up {
@@ -206,13 +414,91 @@ async fn run(cli: Cli) -> Result<()> {
             ui_port,
             wait,
             wait_timeout,
+            dry_run,
+            daemonized,
         } => {
+            let env = &env.clone().or_else(config::selected_env);
+            let progress = progress::Progress::new(
+                DevConfig::from_file(&cli.file)
+                    .ok()
+                    .and_then(|c| c.dev.progress),
+            );
+            for l in DevConfig::lints(&cli.file) {
+                progress.warn(format!(
+                    "{}: {}: {} [{}]",
+                    l.service, l.field, l.message, l.code
+                ));
+            }
+
+            if *dry_run {
+                let cfg = DevConfig::from_file_with_env(&cli.file, env.as_deref())?;
+                let mut names = services.clone();
+                if !label.is_empty() {
+                    names.extend(filter_by_labels(&cfg, label));
+                }
+                print_plan(&supervisor::plan::plan(&cfg, &names)?);
+                return Ok(());
+            }
+
+            // A daemon already runs this project: add the named services to it rather
+            // than starting a second supervisor that takes over its socket.
+            let daemon_pid = if *daemonized {
+                None
+            } else {
+                daemon::running_pid(&cli.file)
+            };
+            if !*daemonized && sock.local_daemon_listens().await {
+                let cfg = DevConfig::from_file_with_env(&cli.file, env.as_deref())?;
+                let mut names = services.clone();
+                if !label.is_empty() {
+                    names.extend(filter_by_labels(&cfg, label));
+                }
+                if names.is_empty() {
+                    let instance = daemon_pid.map_or(String::new(), |pid| {
+                        format!(
+                            " (pid {pid}, log {})",
+                            daemon::log_path(&cli.file).display()
+                        )
+                    });
+                    return Err(DevError::Config(format!(
+                        "a3s is already running for this project{instance} — name the services \
+                         to add (`a3s up <service>`) or run `a3s down` first"
+                    )));
+                }
+                cfg.check_service_names(&names)?;
+                let resp = ipc_send(
+                    IpcRequest::Start {
+                        services: names.clone(),
+                    },
+                    &sock,
+                )
+                .await?;
+                return print_start_results(resp, &names, "(added to the running a3s)");
+            }
+            if let Some(pid) = daemon_pid {
+                return Err(DevError::Config(format!(
+                    "an a3s daemon (pid {pid}) runs for this project but doesn't answer on {} \
+                     — it may still be starting; see {} or run `a3s down`",
+                    sock.socket.display(),
+                    daemon::log_path(&cli.file).display()
+                )));
+            }
+            // Unknown names fail here, before a daemon is spawned or anything starts.
+            if !services.is_empty() {
+                DevConfig::from_file_with_env(&cli.file, env.as_deref())?
+                    .check_service_names(services)?;
+            }
+
             if *detach {
                 // Re-launch self as background daemon, dropping --detach flag
                 let exe = std::env::current_exe()
                     .map_err(|e| DevError::Config(format!("cannot find self: {e}")))?;
-                let mut args: Vec<String> =
-                    vec!["--file".into(), cli.file.display().to_string(), "up".into()];
+                let mut args: Vec<String> = vec![
+                    "--file".into(),
+                    cli.file.display().to_string(),
+                    "up".into(),
+                    "--daemonized".into(),
+                ];
                 if *no_ui {
                     args.push("--no-ui".into());
                 }
@@ -230,32 +516,53 @@ async fn run(cli: Cli) -> Result<()> {
                 }
                 args.extend(services.iter().cloned());
 
-                std::process::Command::new(&exe)
-                    .args(&args)
+                let log = daemon::open_log(&cli.file)?;
+                let mut cmd = std::process::Command::new(&exe);
+                cmd.args(&args)
                     .stdin(std::process::Stdio::null())
-                    .stdout(std::process::Stdio::null())
-                    .stderr(std::process::Stdio::null())
+                    .stdout(log.try_clone()?)
+                    .stderr(log);
+                // Its own process group, so the terminal's Ctrl-C and job control
+                // don't reach it.
+                #[cfg(unix)]
+                std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+                let child = cmd
                     .spawn()
                     .map_err(|e| DevError::Config(format!("failed to daemonize: {e}")))?;
 
-                println!("{} a3s daemon started in background", "✓".green());
+                progress.done(format!(
+                    "a3s daemon started in background (pid {})",
+                    child.id()
+                ));
+                println!("  socket {}", sock.socket.display());
+                println!(
+                    "  log    {} — {} for service output",
+                    daemon::log_path(&cli.file).display(),
+                    "a3s logs".cyan()
+                );
                 if *wait {
-                    println!("{} waiting for services to become healthy...", "→".cyan());
-                    wait_for_healthy(&sock, *wait_timeout).await?;
-                    println!("{} all services healthy", "✓".green());
+                    progress.step("waiting for services to become healthy...");
+                    wait_for_healthy(&sock, *wait_timeout, &progress).await?;
+                    progress.summary("all services healthy");
                 } else {
                     println!("  run {} to check status", "a3s status".cyan());
                     println!("  run {} to stop", "a3s down".cyan());
                 }
                 return Ok(());
             }
+            // Held until shutdown; a second daemon for this project stops here.
+            let _pid_file = if *daemonized {
+                Some(daemon::PidFile::create(&cli.file)?)
+            } else {
+                None
+            };
 
             let cfg = Arc::new(DevConfig::from_file_with_env(&cli.file, env.as_deref())?);
 
             // Check runtime mode
             if cfg.dev.runtime == "k8s" {
                 // Kubernetes mode
-                println!("{} runtime: kubernetes", "→".cyan());
+                progress.step("runtime: kubernetes");
 
                 // Check if kubectl is available
                 if !k8s::K8sClient::check_available().await? {
@@ -264,17 +571,16 @@ async fn run(cli: Cli) -> Result<()> {
                     ));
                 }
 
-                let k8s_client =
-                    k8s::K8sClient::new(cfg.dev.k8s_context.clone(), cfg.dev.k8s_namespace.clone());
+                let k8s_client = k8s::K8sClient::from_settings(&cfg.dev);
                 let (log, log_rx) = crate::log::LogAggregator::new();
                 let log = std::sync::Arc::new(log);
                 tokio::spawn(crate::log::LogAggregator::print_loop(log_rx));
                 crate::log::LogAggregator::spawn_history_recorder(log.clone());
                 let k8s_runtime = k8s::K8sRuntime::new(k8s_client, log, cfg.dev.registry.clone());
 
-                println!("{} namespace: {}", "→".cyan(), cfg.dev.k8s_namespace);
+                progress.step(format!("namespace: {}", cfg.dev.k8s_namespace));
                 if let Some(ref ctx) = cfg.dev.k8s_context {
-                    println!("{} context: {}", "→".cyan(), ctx);
+                    progress.step(format!("context: {ctx}"));
                 }
 
                 let config_dir = cli
@@ -290,35 +596,37 @@ async fn run(cli: Cli) -> Result<()> {
                     config_dir,
                     services.clone(),
                     label.clone(),
+                    progress,
                 )
                 .await;
             }
 
             // Local process mode (default)
-            println!("{} runtime: local", "→".cyan());
+            progress.step("runtime: local");
 
             // Start proxy
-            let proxy = if cfg.dev.https {
+            let proxy = if cfg.dev.tls_enabled() {
                 let config_dir = cli.file.parent().unwrap_or(std::path::Path::new("."));
-                let (cert, key) = cert::get_or_create_cert(config_dir).await?;
+                let (cert_path, key_path) = cfg.dev.tls.ca_paths(config_dir);
+                let (ca, created) = cert::LocalCa::load_or_create(&cert_path, &key_path)?;
+                if created {
+                    progress.warn(cert::trust_instructions(&cert_path));
+                }
                 Arc::new(
-                    proxy::ProxyRouter::new(cfg.dev.proxy_port)
-                        .with_https(cert, key)
-                        .map_err(|e| DevError::Config(format!("failed to setup HTTPS: {}", e)))?,
+                    proxy::ProxyRouter::new(cfg.dev.proxy_port).with_tls(cert::server_config(ca)?),
                 )
             } else {
                 Arc::new(proxy::ProxyRouter::new(cfg.dev.proxy_port))
             };
             let proxy_port = cfg.dev.proxy_port;
-            let protocol = if cfg.dev.https { "https" } else { "http" };
+            let protocol = if cfg.dev.tls_enabled() {
+                "https"
+            } else {
+                "http"
+            };
             let proxy_run = proxy.clone();
             tokio::spawn(async move { proxy_run.run().await });
-            println!(
-                "{} proxy  {}://*.localhost:{}",
-                "→".cyan(),
-                protocol,
-                proxy_port
-            );
+            progress.step(format!("proxy  {protocol}://*.localhost:{proxy_port}"));
 
             let (sup, _) = Supervisor::new(cfg.clone(), proxy, cli.file.clone(), env.clone());
             let sup: Arc<Supervisor> = Arc::new(sup);
@@ -327,16 +635,30 @@ async fn run(cli: Cli) -> Result<()> {
 
             // Start web UI
             if !no_ui {
-                let ui_port = *ui_port;
+                let ip: std::net::IpAddr = cfg.dev.ui_bind.parse().map_err(|_| {
+                    DevError::Config(format!("dev.ui_bind: invalid IP '{}'", cfg.dev.ui_bind))
+                })?;
+                let addr = std::net::SocketAddr::new(ip, *ui_port);
+                let auth = Arc::new(ui::Auth::generate(&addr)?);
+                let token_path = ui::default_token_path();
+                if let Err(e) = auth.save(&token_path) {
+                    progress.warn(format!(
+                        "could not write the UI token to {}: {e}",
+                        token_path.display()
+                    ));
+                }
+                let url = if ip.is_loopback() || ip.is_unspecified() {
+                    format!("http://localhost:{ui_port}/?token={}", auth.token())
+                } else {
+                    format!("http://{addr}/?token={}", auth.token())
+                };
                 let sup_ui = sup.clone();
-                tokio::spawn(async move { ui::serve(sup_ui, ui_port).await });
-                println!("{} ui     http://localhost:{}", "→".cyan(), ui_port);
+                tokio::spawn(async move { ui::serve(sup_ui, addr, auth).await });
+                progress.step(format!("ui     {url}"));
                 // Open browser after a short delay
                 tokio::spawn(async move {
                     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                    let _ = std::process::Command::new("open")
-                        .arg(format!("http://localhost:{ui_port}"))
-                        .spawn();
+                    let _ = std::process::Command::new("open").arg(url).spawn();
                 });
             }
 
@@ -355,39 +677,77 @@ async fn run(cli: Cli) -> Result<()> {
                 }
             }
 
-            if target_services.is_empty() {
-                sup.clone().start_all().await?;
+            // Take over the signals before any service exists: their default action
+            // would kill us mid-startup and orphan whatever was already spawned.
+            #[cfg(unix)]
+            let (mut sigterm, mut sighup) = {
+                use tokio::signal::unix::{signal, SignalKind};
+                (
+                    signal(SignalKind::terminate())?,
+                    signal(SignalKind::hangup())?,
+                )
+            };
+
+            let started = if target_services.is_empty() {
+                sup.clone().start_all().await
             } else {
-                sup.clone().start_named(&target_services).await?;
+                sup.clone().start_requested(&target_services).await
+            };
+            let mut rows = sup.status_rows().await;
+            if let Err(e) = started {
+                // Keep supervising whatever did start; the failed services show as
+                // failed, with why, in `a3s status`.
+                if !rows.iter().any(|r| r.pid.is_some()) {
+                    return Err(e);
+                }
+                progress.warn(format!("not every service started: {e}"));
             }
+            rows.retain(|r| r.state != "not requested");
+            let running = rows.iter().filter(|r| r.state == "running").count();
+            progress.summary(format!("{running}/{} services running", rows.len()));
 
-            // Wait for Ctrl+C, SIGTERM (shutdown) or SIGHUP (config reload).
+            // Wait for Ctrl+C, SIGTERM (shutdown) or SIGHUP: a config reload for the
+            // daemon, the terminal closing for a foreground run.
             #[cfg(unix)]
             {
-                use tokio::signal::unix::{signal, SignalKind};
-                let mut sigterm = signal(SignalKind::terminate())
-                    .unwrap_or_else(|_| signal(SignalKind::terminate()).unwrap());
-                let mut sighup = signal(SignalKind::hangup())
-                    .unwrap_or_else(|_| signal(SignalKind::hangup()).unwrap());
+                let stop_on_hangup =
+                    !*daemonized && cfg.dev.orphan_policy == config::OrphanPolicy::Stop;
                 loop {
                     tokio::select! {
                         _ = tokio::signal::ctrl_c() => break,
                         _ = sigterm.recv() => break,
                         _ = sighup.recv() => {
+                            if stop_on_hangup {
+                                tracing::info!("terminal closed — stopping services");
+                                break;
+                            }
                             tracing::info!("SIGHUP received — reloading config");
-                            if let Err(e) = sup.reload_from_disk().await {
+                            if let Err(e) = sup.reload_from_disk(true).await {
                                 tracing::error!("config reload failed: {e}");
                             }
                         }
                     }
                 }
+
+                // The terminal may be gone, so don't let a failed print abort shutdown.
+                use std::io::Write;
+                let _ = writeln!(std::io::stdout(), "\n{} shutting down...", "→".yellow());
+                let second_signal = async {
+                    tokio::select! {
+                        _ = tokio::signal::ctrl_c() => {}
+                        _ = sigterm.recv() => {}
+                        _ = sighup.recv() => {}
+                    }
+                };
+                sup.shutdown(second_signal).await;
             }
             #[cfg(not(unix))]
-            tokio::signal::ctrl_c().await.ok();
-
-            println!("\n{} shutting down...", "→".yellow());
-            sup.clone().stop_all().await;
-            let _ = std::fs::remove_file(&sock);
+            {
+                tokio::signal::ctrl_c().await.ok();
+                println!("\n{} shutting down...", "→".yellow());
+                sup.clone().stop_all().await;
+            }
+            let _ = std::fs::remove_file(&sock.socket);
         }
 
         Commands::Validate { strict } => {
@@ -550,15 +910,10 @@ async fn run(cli: Cli) -> Result<()> {
                     }
 
                     // Check cluster is reachable
-                    let client = k8s::K8sClient::new(
-                        cfg.dev.k8s_context.clone(),
-                        cfg.dev.k8s_namespace.clone(),
-                    );
+                    let client = k8s::K8sClient::from_settings(&cfg.dev);
                     let mut cmd = tokio::process::Command::new("kubectl");
                     cmd.arg("cluster-info");
-                    if let Some(ref ctx) = cfg.dev.k8s_context {
-                        cmd.arg("--context").arg(ctx);
-                    }
+                    cmd.args(cfg.dev.kubectl_args());
                     cmd.stdout(std::process::Stdio::null())
                         .stderr(std::process::Stdio::null());
                     let _ = client; // suppress unused warning
@@ -578,7 +933,16 @@ async fn run(cli: Cli) -> Result<()> {
                         if svc.disabled {
                             continue;
                         }
-                        // Check that the binary in `cmd` exists on PATH.
+                        // Check that the binary in `cmd` (and its shell) exists on PATH.
+                        if let Some(shell) = svc.shell.as_deref().filter(|s| *s != "none") {
+                            if !which_binary(shell) {
+                                println!(
+                                    "  {} [{name}] shell '{shell}' not found on PATH",
+                                    "✗".red()
+                                );
+                                all_ok = false;
+                            }
+                        }
                         let binary = svc.cmd.split_whitespace().next().unwrap_or("");
                         if which_binary(binary) {
                             println!("  {} [{name}] binary '{binary}' found", "✓".green());
@@ -626,11 +990,6 @@ async fn run(cli: Cli) -> Result<()> {
                 }
             }
 
-            // pid -> (total_cpu_ticks, sample_time) — used for delta CPU% on Linux.
-            // On macOS we fall back to ps lifetime average.
-            let mut prev_ticks: std::collections::HashMap<u32, (u64, std::time::Instant)> =
-                std::collections::HashMap::new();
-
             loop {
                 let resp = ipc_send(IpcRequest::Status, &sock).await;
                 match resp {
@@ -650,14 +1009,11 @@ async fn run(cli: Cli) -> Result<()> {
                             let state_colored = match row.state.as_str() {
                                 "running" => row.state.green().to_string(),
                                 "starting" | "restarting" => row.state.yellow().to_string(),
-                                "unhealthy" | "failed" => row.state.red().to_string(),
+                                "unhealthy" | "failed" | "oom-killed" | "crash-looped"
+                                | "blocked" => row.state.red().to_string(),
                                 _ => row.state.dimmed().to_string(),
                             };
-                            let (cpu_str, mem_str) = row
-                                .pid
-                                .and_then(|pid| query_process_stats_delta(pid, &mut prev_ticks))
-                                .map(|(cpu, mem)| (format!("{cpu:.1}%"), format_bytes(mem)))
-                                .unwrap_or_else(|| ("-".into(), "-".into()));
+                            let (cpu_str, mem_str) = usage_cells(row);
                             println!(
                                 "{:<16} {:<20} {:<8} {:<10} {}",
                                 row.name,
@@ -686,19 +1042,15 @@ async fn run(cli: Cli) -> Result<()> {
             }
         }
 
-        Commands::Status {
-            json,
-            watch,
-            interval,
-        } => {
+        Commands::Status { watch, interval } => {
             // k8s mode: show pod status via kubectl
             if let Ok(cfg) = DevConfig::from_file(&cli.file) {
                 if cfg.dev.runtime == "k8s" {
-                    return k8s_status(&cfg, *json).await;
+                    return k8s_status(&cfg, json).await;
                 }
             }
 
-            if *json && *watch {
+            if json && *watch {
                 return Err(DevError::Config(
                     "--json and --watch are mutually exclusive".into(),
                 ));
@@ -711,47 +1063,49 @@ async fn run(cli: Cli) -> Result<()> {
                         Ok(IpcResponse::Status { rows }) => {
                             print!("\x1b[2J\x1b[H");
                             println!(
-                                "{:<16} {:<12} {:<8} {:<6} {:<8} {:<6} {:<24} {}",
+                                "{:<16} {:<12} {:<8} {:<7} {:<9} {:<6} {:<8} {:<15} {:<24} {}",
                                 "SERVICE".bold(),
                                 "STATE".bold(),
                                 "PID".bold(),
+                                "CPU".bold(),
+                                "MEM".bold(),
                                 "PORT".bold(),
                                 "RESTARTS".bold(),
                                 "HEALTH".bold(),
                                 "URL".bold(),
                                 "UPTIME".bold(),
                             );
-                            println!("{}", "─".repeat(86).dimmed());
+                            println!("{}", "─".repeat(113).dimmed());
+                            let shares = active_shares(&rows);
+                            let standbys = standby_rows(&rows);
+                            let blocked = blocked_rows(&rows);
+                            let start_errors = start_error_rows(&rows);
+                            let crash_loops = crash_loop_rows(&rows);
+                            let failing = failing_health_rows(&rows);
                             for row in rows {
                                 let state_colored = match row.state.as_str() {
                                     "running" => row.state.green().to_string(),
                                     "starting" | "restarting" => row.state.yellow().to_string(),
-                                    "unhealthy" | "failed" => row.state.red().to_string(),
+                                    "unhealthy" | "failed" | "oom-killed" | "crash-looped"
+                                    | "blocked" => row.state.red().to_string(),
                                     _ => row.state.dimmed().to_string(),
                                 };
-                                let url = row
-                                    .subdomain
-                                    .map(|s| format!("http://{s}.localhost"))
-                                    .unwrap_or_default();
-                                let uptime = row
-                                    .uptime_secs
-                                    .map(format_uptime)
-                                    .unwrap_or_else(|| "-".into());
+                                let health = health_cell(&row);
+                                let url = url_cell(&row);
+                                let uptime = uptime_cell(&row);
+                                let (cpu, mem) = usage_cells(&row);
                                 let restarts = if row.restart_count == 0 {
                                     "-".dimmed().to_string()
                                 } else {
                                     row.restart_count.to_string().yellow().to_string()
                                 };
-                                let health = match row.healthy {
-                                    Some(true) => "✓".green().to_string(),
-                                    Some(false) => "✗".red().to_string(),
-                                    None => "-".dimmed().to_string(),
-                                };
                                 println!(
-                                    "{:<16} {:<20} {:<8} {:<6} {:<16} {:<14} {:<24} {}",
+                                    "{:<16} {:<20} {:<8} {:<7} {:<9} {:<6} {:<16} {:<23} {:<24} {}",
                                     row.name,
                                     state_colored,
                                     row.pid.map(|p| p.to_string()).unwrap_or_else(|| "-".into()),
+                                    cpu,
+                                    mem,
                                     if row.port == 0 {
                                         "auto".into()
                                     } else {
@@ -763,6 +1117,12 @@ async fn run(cli: Cli) -> Result<()> {
                                     uptime.dimmed(),
                                 );
                             }
+                            print_shares(&shares);
+                            print_standbys(&standbys);
+                            print_blocked(&blocked);
+                            print_start_errors(&start_errors);
+                            print_crash_loops(&crash_loops);
+                            print_failing_health(&failing);
                             println!(
                                 "\n{} refresh every {}s — Ctrl+C to exit",
                                 "·".dimmed(),
@@ -785,55 +1145,54 @@ async fn run(cli: Cli) -> Result<()> {
 
             let resp = ipc_send(IpcRequest::Status, &sock).await?;
             if let IpcResponse::Status { rows } = resp {
-                if *json {
-                    println!(
-                        "{}",
-                        serde_json::to_string_pretty(&rows)
-                            .map_err(|e| DevError::Config(format!("json: {e}")))?
-                    );
+                if json {
+                    print_json(&rows);
                 } else {
                     println!(
-                        "{:<16} {:<12} {:<8} {:<6} {:<8} {:<6} {:<24} {}",
+                        "{:<16} {:<12} {:<8} {:<7} {:<9} {:<6} {:<8} {:<15} {:<24} {}",
                         "SERVICE".bold(),
                         "STATE".bold(),
                         "PID".bold(),
+                        "CPU".bold(),
+                        "MEM".bold(),
                         "PORT".bold(),
                         "RESTARTS".bold(),
                         "HEALTH".bold(),
                         "URL".bold(),
                         "UPTIME".bold(),
                     );
-                    println!("{}", "─".repeat(86).dimmed());
+                    println!("{}", "─".repeat(113).dimmed());
+                    let shares = active_shares(&rows);
+                    let standbys = standby_rows(&rows);
+                    let blocked = blocked_rows(&rows);
+                    let start_errors = start_error_rows(&rows);
+                    let crash_loops = crash_loop_rows(&rows);
+                    let failing = failing_health_rows(&rows);
                     for row in rows {
                         let state_colored = match row.state.as_str() {
                             "running" => row.state.green().to_string(),
                             "starting" | "restarting" => row.state.yellow().to_string(),
-                            "unhealthy" | "failed" => row.state.red().to_string(),
+                            "unhealthy" | "failed" | "oom-killed" | "crash-looped" | "blocked" => {
+                                row.state.red().to_string()
+                            }
                             _ => row.state.dimmed().to_string(),
                         };
-                        let url = row
-                            .subdomain
-                            .map(|s| format!("http://{s}.localhost"))
-                            .unwrap_or_default();
-                        let uptime = row
-                            .uptime_secs
-                            .map(format_uptime)
-                            .unwrap_or_else(|| "-".into());
+                        let health = health_cell(&row);
+                        let url = url_cell(&row);
+                        let uptime = uptime_cell(&row);
+                        let (cpu, mem) = usage_cells(&row);
                         let restarts = if row.restart_count == 0 {
                             "-".dimmed().to_string()
                         } else {
                             row.restart_count.to_string().yellow().to_string()
                         };
-                        let health = match row.healthy {
-                            Some(true) => "✓".green().to_string(),
-                            Some(false) => "✗".red().to_string(),
-                            None => "-".dimmed().to_string(),
-                        };
                         println!(
-                            "{:<16} {:<20} {:<8} {:<6} {:<16} {:<14} {:<24} {}",
+                            "{:<16} {:<20} {:<8} {:<7} {:<9} {:<6} {:<16} {:<23} {:<24} {}",
                             row.name,
                             state_colored,
                             row.pid.map(|p| p.to_string()).unwrap_or_else(|| "-".into()),
+                            cpu,
+                            mem,
                             if row.port == 0 {
                                 "auto".into()
                             } else {
@@ -845,6 +1204,12 @@ async fn run(cli: Cli) -> Result<()> {
                             uptime.dimmed(),
                         );
                     }
+                    print_shares(&shares);
+                    print_standbys(&standbys);
+                    print_blocked(&blocked);
+                    print_start_errors(&start_errors);
+                    print_crash_loops(&crash_loops);
+                    print_failing_health(&failing);
                 }
             }
         }
@@ -867,15 +1232,17 @@ async fn run(cli: Cli) -> Result<()> {
                 }
             }
 
-            match ipc_send(
-                IpcRequest::Stop {
-                    services: target_services,
-                },
-                &sock,
-            )
-            .await?
-            {
-                IpcResponse::Stopped { services: stopped } => {
+            // Everything, with a detached daemon running: stop the daemon too.
+            let daemon_pid = if target_services.is_empty() {
+                daemon::running_pid(&cli.file)
+            } else {
+                None
+            };
+            let stop = IpcRequest::Stop {
+                services: target_services,
+            };
+            match ipc_send(stop, &sock).await {
+                Ok(IpcResponse::Stopped { services: stopped }) => {
                     if stopped.is_empty() {
                         println!("{} nothing was running", "·".dimmed());
                     } else {
@@ -884,7 +1251,25 @@ async fn run(cli: Cli) -> Result<()> {
                         }
                     }
                 }
-                _ => println!("{} stopped", "✓".green()),
+                Ok(_) => println!("{} stopped", "✓".green()),
+                Err(e) if daemon_pid.is_none() => return Err(e),
+                Err(_) => println!("{} the daemon isn't answering", "·".dimmed()),
+            }
+            if let Some(pid) = daemon_pid {
+                let grace = DevConfig::from_file(&cli.file)
+                    .map_or(std::time::Duration::from_secs(30), |c| {
+                        c.dev.shutdown_timeout
+                    });
+                if daemon::terminate(&cli.file, pid, grace + std::time::Duration::from_secs(5))
+                    .await
+                {
+                    println!("{} stopped a3s daemon (pid {pid})", "✓".green());
+                } else {
+                    return Err(DevError::Config(format!(
+                        "a3s daemon (pid {pid}) is still running — see {}",
+                        daemon::log_path(&cli.file).display()
+                    )));
+                }
             }
         }
 
@@ -892,10 +1277,7 @@ async fn run(cli: Cli) -> Result<()> {
             // k8s mode: rollout restart
             if let Ok(cfg) = DevConfig::from_file(&cli.file) {
                 if cfg.dev.runtime == "k8s" {
-                    let client = k8s::K8sClient::new(
-                        cfg.dev.k8s_context.clone(),
-                        cfg.dev.k8s_namespace.clone(),
-                    );
+                    let client = k8s::K8sClient::from_settings(&cfg.dev);
                     client.rollout_restart(service).await?;
                     println!("{} restarted {}", "✓".green(), service.cyan());
                     return Ok(());
@@ -911,11 +1293,242 @@ async fn run(cli: Cli) -> Result<()> {
             println!("{} restarted {}", "✓".green(), service.cyan());
         }
 
-        Commands::Reload => match ipc_send(IpcRequest::Reload, &sock).await? {
+        Commands::Kill { service, signal } => {
+            let resp = ipc_send(
+                IpcRequest::Kill {
+                    service: service.clone(),
+                    signal: signal.clone(),
+                },
+                &sock,
+            )
+            .await?;
+            if let IpcResponse::Error { msg } = resp {
+                return Err(DevError::Config(msg));
+            }
+            println!("{} sent {} to {}", "✓".green(), signal, service.cyan());
+        }
+
+        Commands::Start { services } => {
+            DevConfig::from_file(&cli.file)?.check_service_names(services)?;
+            let resp = ipc_send(
+                IpcRequest::Start {
+                    services: services.clone(),
+                },
+                &sock,
+            )
+            .await?;
+            print_start_results(resp, services, "")?;
+        }
+
+        Commands::Init { force } => {
+            if cli.file.exists() && !*force {
+                return Err(DevError::Config(format!(
+                    "{} already exists — pass --force to overwrite",
+                    cli.file.display()
+                )));
+            }
+            let dir = match cli.file.parent() {
+                Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+                _ => std::path::PathBuf::from("."),
+            };
+            let found = init::detect(&dir);
+            std::fs::write(&cli.file, init::render(&found.services))?;
+            println!("{} wrote {}", "✓".green(), cli.file.display());
+            if found.services.is_empty() {
+                println!(
+                    "  {} no project type recognised — edit the example to get started",
+                    "!".yellow()
+                );
+            }
+            for svc in &found.services {
+                let port = if svc.port == 0 {
+                    "auto".to_string()
+                } else {
+                    format!(":{}", svc.port)
+                };
+                println!(
+                    "  {} {:<12} {:<20} {} {}",
+                    "→".cyan(),
+                    svc.name.cyan(),
+                    svc.cmd,
+                    port,
+                    format!("(from {})", svc.source).dimmed()
+                );
+            }
+            for guess in found.services.iter().flat_map(|s| &s.guesses) {
+                println!("  {} guessed {guess}", "·".dimmed());
+            }
+            for note in &found.notes {
+                println!("  {} {note}", "!".yellow());
+            }
+        }
+
+        #[cfg(feature = "schema")]
+        Commands::Schema => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&config::json_schema()).unwrap_or_default()
+            );
+        }
+
+        Commands::Config {
+            command: ConfigCommand::Check,
+        } => {
+            let diags = DevConfig::diagnostics(&cli.file);
+            let errors = diags
+                .iter()
+                .filter(|d| d.severity == config::Severity::Error)
+                .count();
+            let failure =
+                (errors > 0).then(|| format!("{errors} error(s) in {}", cli.file.display()));
+            if json {
+                print_json_or_exit(&diags, failure);
+                return Ok(());
+            }
+            if diags.is_empty() {
+                println!("{} {} has no problems", "✓".green(), cli.file.display());
+            } else {
+                for d in &diags {
+                    let symbol = match d.severity {
+                        config::Severity::Error => "✗".red(),
+                        config::Severity::Warning => "!".yellow(),
+                    };
+                    match &d.service {
+                        Some(svc) => println!("  {symbol} {}: {}", svc.cyan(), d.message),
+                        None => println!("  {symbol} {}", d.message),
+                    }
+                }
+            }
+            if let Some(failure) = failure {
+                return Err(DevError::Config(failure));
+            }
+        }
+
+        Commands::Config {
+            command: ConfigCommand::Lint,
+        } => {
+            let lints = DevConfig::lints(&cli.file);
+            if json {
+                print_json(&lints);
+            } else if lints.is_empty() {
+                println!("{} {} looks fine", "✓".green(), cli.file.display());
+            } else {
+                for l in &lints {
+                    println!(
+                        "  {} {} {}: {} {}",
+                        "!".yellow(),
+                        l.service.cyan(),
+                        l.field,
+                        l.message,
+                        format!("[{}]", l.code).dimmed()
+                    );
+                }
+            }
+        }
+
+        Commands::Config {
+            command: ConfigCommand::Show { env },
+        } => {
+            let env = env.clone().or_else(config::selected_env);
+            let cfg = DevConfig::from_file_with_env(&cli.file, env.as_deref())?;
+            let dir = cli.file.parent().unwrap_or(std::path::Path::new("."));
+            let rows: Vec<(&String, &config::ServiceDef, Vec<String>)> = cfg
+                .service
+                .iter()
+                .map(|(name, svc)| {
+                    let sources = cfg
+                        .sources
+                        .get(name)
+                        .into_iter()
+                        .flatten()
+                        .map(|p| p.strip_prefix(dir).unwrap_or(p).display().to_string())
+                        .collect();
+                    (name, svc, sources)
+                })
+                .collect();
+            if json {
+                let out: Vec<serde_json::Value> = rows
+                    .iter()
+                    .map(|(name, svc, sources)| {
+                        serde_json::json!({
+                            "name": name,
+                            "port": svc.port,
+                            "disabled": svc.disabled,
+                            "sources": sources,
+                        })
+                    })
+                    .collect();
+                print_json(&out);
+            } else {
+                if let Some(env) = &env {
+                    println!("{} environment: {}", "·".dimmed(), env.cyan());
+                }
+                println!(
+                    "{:<16} {:<6} {}",
+                    "SERVICE".bold(),
+                    "PORT".bold(),
+                    "SOURCE".bold()
+                );
+                for (name, svc, sources) in rows {
+                    let port = if svc.port == 0 {
+                        "auto".to_string()
+                    } else {
+                        svc.port.to_string()
+                    };
+                    let name = if svc.disabled {
+                        format!("{name:<16}").dimmed().to_string()
+                    } else {
+                        format!("{name:<16}")
+                    };
+                    let disabled = if svc.disabled {
+                        format!(" {}", "(disabled)".dimmed())
+                    } else {
+                        String::new()
+                    };
+                    println!("{name} {port:<6} {}{disabled}", sources.join(" + "));
+                }
+            }
+        }
+
+        Commands::Health {
+            command: HealthCommand::Report,
+        } => {
+            let rows = match ipc_send(IpcRequest::HealthReport, &sock).await {
+                Ok(IpcResponse::HealthReport { rows }) => rows,
+                Ok(IpcResponse::Error { msg }) => return Err(DevError::Config(msg)),
+                Ok(_) => return Err(DevError::Config("unexpected IPC response".into())),
+                // No daemon — report from the history it saved.
+                Err(_) => {
+                    let threshold = DevConfig::from_file(&cli.file)
+                        .map(|c| c.dev.flaky_threshold)
+                        .unwrap_or(config::GlobalSettings::default().flaky_threshold);
+                    let dir = cli.file.parent().unwrap_or(std::path::Path::new("."));
+                    health_stats::HealthStats::load(&health_stats::HealthStats::path(dir))
+                        .report_all(health_stats::unix_now(), threshold)
+                }
+            };
+            if json {
+                print_json(&rows);
+            } else if rows.is_empty() {
+                println!("{} no health checks recorded yet", "·".dimmed());
+            } else {
+                print_health_report(&rows);
+            }
+        }
+
+        Commands::Reload { no_restart } => match ipc_send(
+            IpcRequest::Reload {
+                no_restart: *no_restart,
+            },
+            &sock,
+        )
+        .await?
+        {
             IpcResponse::Reloaded {
                 started,
                 stopped,
                 restarted,
+                not_restarted,
             } => {
                 println!("{} config reloaded", "✓".green());
                 for s in &stopped {
@@ -927,7 +1540,19 @@ async fn run(cli: Cli) -> Result<()> {
                 for s in &started {
                     println!("  {} started  {}", "+".green(), s.cyan());
                 }
-                if stopped.is_empty() && restarted.is_empty() && started.is_empty() {
+                for s in &not_restarted {
+                    println!(
+                        "  {} changed  {} {}",
+                        "~".yellow(),
+                        s.cyan(),
+                        "(not restarted)".dimmed()
+                    );
+                }
+                if stopped.is_empty()
+                    && restarted.is_empty()
+                    && started.is_empty()
+                    && not_restarted.is_empty()
+                {
                     println!("  no changes");
                 }
             }
@@ -939,26 +1564,101 @@ async fn run(cli: Cli) -> Result<()> {
             }
         },
 
+        Commands::Share { service, ttl, stop } => {
+            if *stop {
+                match ipc_send(
+                    IpcRequest::Unshare {
+                        service: service.clone(),
+                    },
+                    &sock,
+                )
+                .await?
+                {
+                    IpcResponse::Error { msg } => return Err(DevError::Config(msg)),
+                    _ => println!("{} stopped sharing {}", "✓".green(), service.cyan()),
+                }
+                return Ok(());
+            }
+            let ttl_secs = ttl
+                .as_deref()
+                .map(config::parse_duration)
+                .transpose()
+                .map_err(|e| DevError::Config(format!("invalid --ttl: {e}")))?
+                .map(|d| d.as_secs());
+            match ipc_send(
+                IpcRequest::Share {
+                    service: service.clone(),
+                    ttl_secs,
+                },
+                &sock,
+            )
+            .await?
+            {
+                IpcResponse::Shared {
+                    service,
+                    url,
+                    expires_in_secs,
+                } => {
+                    println!(
+                        "{} sharing {} at {}",
+                        "✓".green(),
+                        service.cyan(),
+                        url.bold()
+                    );
+                    match expires_in_secs {
+                        Some(secs) => {
+                            println!("  {} expires in {}", "·".dimmed(), format_uptime(secs))
+                        }
+                        None => println!(
+                            "  run {} to stop",
+                            format!("a3s share {service} --stop").cyan()
+                        ),
+                    }
+                }
+                IpcResponse::Error { msg } => return Err(DevError::Config(msg)),
+                _ => {}
+            }
+        }
+
         Commands::Logs {
+            services,
             service,
             follow,
+            no_follow,
             grep,
+            invert,
             last,
+            since,
             timestamps,
         } => {
+            let follow = *follow && !*no_follow;
+            let service: Vec<String> = services.iter().chain(service).cloned().collect();
+            let pattern = grep.as_ref().map(|g| format!("(?i){g}"));
+            let filter = log::LineFilter::new(pattern.as_deref(), *invert)
+                .map_err(|e| DevError::Config(format!("invalid --grep pattern: {e}")))?;
             // k8s mode: stream pod logs via kubectl
             if let Ok(cfg) = DevConfig::from_file(&cli.file) {
                 if cfg.dev.runtime == "k8s" {
-                    return k8s_logs(&cfg, service, *follow, grep.as_deref(), *last).await;
+                    return k8s_logs(&cfg, &service, follow, &filter, *last, since.as_deref())
+                        .await;
                 }
             }
 
             let services = if service.is_empty() {
                 None
             } else {
-                Some(service.clone())
+                Some(service)
             };
-            stream_logs(services, *follow, grep.clone(), *last, *timestamps, &sock).await?;
+            stream_logs(
+                services,
+                follow,
+                &filter,
+                *last,
+                since.clone(),
+                *timestamps,
+                &sock,
+            )
+            .await?;
         }
 
         Commands::PortForward { service, ports } => {
@@ -994,6 +1694,10 @@ async fn run(cli: Cli) -> Result<()> {
                 )));
             }
 
+            let client = k8s::K8sClient::from_settings(&cfg.dev);
+            let mut tunnel = client
+                .port_forward(&format!("deployment/{service}"), local_port, remote_port)
+                .await?;
             println!(
                 "{} forwarding localhost:{} -> {}:{}",
                 "→".cyan(),
@@ -1003,39 +1707,59 @@ async fn run(cli: Cli) -> Result<()> {
             );
             println!("{} press Ctrl+C to stop", "·".dimmed());
 
-            // Run kubectl port-forward
-            let mut cmd = tokio::process::Command::new("kubectl");
-            cmd.arg("port-forward")
-                .arg(format!("deployment/{}", service))
-                .arg(format!("{}:{}", local_port, remote_port))
-                .arg("-n")
-                .arg(&cfg.dev.k8s_namespace);
-
-            if let Some(ref ctx) = cfg.dev.k8s_context {
-                cmd.arg("--context").arg(ctx);
-            }
-
-            let mut child = cmd.spawn().map_err(|e| {
-                DevError::Config(format!("failed to start kubectl port-forward: {}", e))
-            })?;
-
-            // Wait for Ctrl+C
             tokio::select! {
                 _ = tokio::signal::ctrl_c() => {
                     println!("\n{} stopping port-forward...", "→".yellow());
-                    let _ = child.kill().await;
+                    k8s::stop_port_forward(tunnel).await;
                 }
-                status = child.wait() => {
-                    match status {
-                        Ok(s) if !s.success() => {
-                            return Err(DevError::Config("kubectl port-forward failed".into()));
-                        }
-                        Err(e) => {
-                            return Err(DevError::Config(format!("kubectl port-forward error: {}", e)));
-                        }
-                        _ => {}
+                _ = &mut tunnel => {
+                    return Err(DevError::Config("kubectl port-forward exited".into()));
+                }
+            }
+        }
+
+        Commands::PodExec {
+            pod,
+            container,
+            namespace,
+            cmd,
+        } => {
+            let cfg = DevConfig::from_file(&cli.file)?;
+            let namespace = namespace
+                .clone()
+                .unwrap_or_else(|| cfg.dev.k8s_namespace.clone());
+            let client = k8s::K8sClient::from_settings(&cfg.dev).in_namespace(namespace);
+            let cmd: Vec<&str> = cmd.iter().map(String::as_str).collect();
+            client
+                .pod_exec_interactive(pod, container.as_deref(), &cmd)
+                .await?;
+        }
+
+        Commands::Doctor => {
+            let cfg = match DevConfig::from_file(&cli.file) {
+                Ok(cfg) => Some(cfg),
+                Err(e) => {
+                    if cli.file.exists() && !json {
+                        println!(
+                            "{} {} can't be read ({e}) — checking the optional tools only\n",
+                            "·".dimmed(),
+                            cli.file.display()
+                        );
                     }
+                    None
                 }
+            };
+            let report = doctor::run(cfg.as_ref(), &cli.file);
+            if json {
+                let failure = (!report.ok()).then(|| "required tools are missing".to_string());
+                print_json_or_exit(&report, failure);
+                return Ok(());
+            }
+            print_doctor(&report);
+            if !report.ok() {
+                return Err(DevError::Config(
+                    "required tools are missing — see the fixes above".into(),
+                ));
             }
         }
 
@@ -1133,7 +1857,103 @@ async fn run(cli: Cli) -> Result<()> {
         Commands::Tool(args) => {
             let tool = &args[0];
             let rest = &args[1..];
-            proxy_tool(tool, rest).await?;
+            // `a3s --json box …` always answers in JSON (or refuses); a `--json` after
+            // `box` is only ours for the commands we answer, a3s-box's otherwise.
+            if tool == "box" && (json || (box_has_json(rest) && rest.iter().any(|a| a == "--json")))
+            {
+                box_json(rest).await?;
+            } else if tool == "box" && rest.first().is_some_and(|a| a == "build") {
+                let build = BoxBuildArgs::parse_from(rest);
+                let tag = build
+                    .tag
+                    .unwrap_or_else(|| box_mgr::default_build_tag(&build.context));
+                ensure_tool("a3s-box", "A3S-Lab", "Box").await?;
+                println!("{} building {}", "→".cyan(), tag.cyan());
+                let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(64);
+                let printer = tokio::spawn(async move {
+                    while let Some(line) = rx.recv().await {
+                        println!("{line}");
+                    }
+                });
+                let build_args = build.build_args.into_iter().collect();
+                let res = box_mgr::build_image(
+                    &build.context,
+                    &tag,
+                    build.file.as_deref(),
+                    &build_args,
+                    tx,
+                )
+                .await;
+                let _ = printer.await;
+                res?;
+                println!("{} built {}", "✓".green(), tag.cyan());
+            } else if tool == "box" && rest.len() >= 2 && rest[1] == "create" {
+                let created = match rest[0].as_str() {
+                    "network" => {
+                        let create = BoxNetworkCreateArgs::parse_from(&rest[1..]);
+                        ensure_tool("a3s-box", "A3S-Lab", "Box").await?;
+                        box_mgr::create_network(
+                            &create.name,
+                            create.driver.as_deref(),
+                            create.subnet.as_deref(),
+                        )
+                        .await?
+                    }
+                    "volume" => {
+                        let create = BoxVolumeCreateArgs::parse_from(&rest[1..]);
+                        ensure_tool("a3s-box", "A3S-Lab", "Box").await?;
+                        box_mgr::create_volume(&create.name, create.driver.as_deref()).await?
+                    }
+                    _ => return proxy_tool(tool, rest).await,
+                };
+                if created.existed {
+                    println!(
+                        "{} {} {} already exists",
+                        "·".dimmed(),
+                        rest[0],
+                        created.name.cyan()
+                    );
+                } else if created.id == created.name {
+                    println!(
+                        "{} created {} {}",
+                        "✓".green(),
+                        rest[0],
+                        created.name.cyan()
+                    );
+                } else {
+                    println!(
+                        "{} created {} {} {}",
+                        "✓".green(),
+                        rest[0],
+                        created.name.cyan(),
+                        created.id.dimmed()
+                    );
+                }
+            } else if tool == "box" && rest.len() >= 2 && rest[1] == "prune" {
+                match rest[0].as_str() {
+                    "image" => {
+                        let prune = BoxImagePruneArgs::parse_from(&rest[1..]);
+                        if prune.all
+                            && !prune.force
+                            && !confirm("Remove all images not used by a container?")?
+                        {
+                            println!("{} nothing removed", "·".dimmed());
+                            return Ok(());
+                        }
+                        ensure_tool("a3s-box", "A3S-Lab", "Box").await?;
+                        let report = box_mgr::prune_images(!prune.all).await?;
+                        print_prune_report(&report, ("image", "images"));
+                    }
+                    "container" => {
+                        ensure_tool("a3s-box", "A3S-Lab", "Box").await?;
+                        let report = box_mgr::prune_containers().await?;
+                        print_prune_report(&report, ("stopped box", "stopped boxes"));
+                    }
+                    _ => proxy_tool(tool, rest).await?,
+                }
+            } else {
+                proxy_tool(tool, rest).await?;
+            }
         }
 
         Commands::Run { service, cmd } => {
@@ -1178,6 +1998,14 @@ async fn run(cli: Cli) -> Result<()> {
 
         Commands::Exec { service, cmd } => {
             let cfg = DevConfig::from_file(&cli.file)?;
+            if cfg.container.contains_key(service.as_str()) {
+                let cmd: Vec<&str> = cmd.iter().map(String::as_str).collect();
+                return box_mgr::exec_container_interactive(
+                    &box_mgr::container_name(service),
+                    &cmd,
+                )
+                .await;
+            }
             let svc = cfg
                 .service
                 .get(service.as_str())
@@ -1212,6 +2040,146 @@ async fn run(cli: Cli) -> Result<()> {
     Ok(())
 }
 
+/// `a3s up --dry-run`: each service in start order, grouped by wave.
+fn print_plan(steps: &[supervisor::plan::PlanStep]) {
+    println!(
+        "{} {} service(s) would start {}",
+        "[a3s]".cyan().bold(),
+        steps.len(),
+        "(dry run, nothing started)".dimmed()
+    );
+    let mut wave = None;
+    for step in steps {
+        if wave != Some(step.wave) {
+            wave = Some(step.wave);
+            println!("\n{}", format!("wave {}", step.wave + 1).bold());
+        }
+        println!("  {}", step.service.cyan());
+        for w in &step.waits {
+            let until = if w.healthy { "healthy" } else { "started" };
+            let timeout = if step.wait_timeout.is_zero() {
+                "no timeout".to_string()
+            } else {
+                format!("timeout {:?}", step.wait_timeout)
+            };
+            println!("    {:<8}{} {until} ({timeout})", "waits", w.service);
+        }
+        let dir = step
+            .dir
+            .as_ref()
+            .map_or("(current directory)".to_string(), |d| {
+                d.display().to_string()
+            });
+        println!("    {:<8}{dir}", "dir");
+        if let Some(hook) = &step.pre_start {
+            println!("    {:<8}{hook}", "pre");
+        }
+        let argv: Vec<String> = step.argv.iter().map(|a| box_mgr::shell_quote(a)).collect();
+        println!("    {:<8}{}", "cmd", argv.join(" "));
+        match step.port {
+            Some(port) => println!("    {:<8}{port}", "port"),
+            None => println!("    {:<8}{}", "port", "auto-assigned at start".dimmed()),
+        }
+        for (i, (k, v)) in step.env.iter().enumerate() {
+            println!("    {:<8}{k}={v}", if i == 0 { "env" } else { "" });
+        }
+        if !step.secrets.is_empty() {
+            println!(
+                "    {:<8}{} {}",
+                "secrets",
+                step.secrets.join(", "),
+                "(read at start)".dimmed()
+            );
+        }
+        match &step.health {
+            Some(h) => {
+                let kind = match h.kind {
+                    config::HealthKind::Http => "http",
+                    config::HealthKind::Tcp => "tcp",
+                };
+                let path = h
+                    .path
+                    .as_deref()
+                    .map(|p| format!(" {p}"))
+                    .unwrap_or_default();
+                let mut detail = format!(
+                    "every {:?}, timeout {:?}, {} retries",
+                    h.interval, h.timeout, h.retries
+                );
+                if let Some(grace) = h.start_period {
+                    detail.push_str(&format!(", start period {grace:?}"));
+                }
+                println!("    {:<8}{kind}{path} {}", "health", detail.dimmed());
+            }
+            None => println!("    {:<8}{}", "health", "none".dimmed()),
+        }
+    }
+}
+
+/// Print the answer to a `Start` request, one line per service. Errors if any
+/// service didn't come up. `note` is appended to every "started" line.
+fn print_start_results(resp: IpcResponse, names: &[String], note: &str) -> Result<()> {
+    let results = match resp {
+        IpcResponse::Error { msg } => return Err(DevError::Config(msg)),
+        IpcResponse::Started { results } => results,
+        // A daemon from before per-service results answers a bare Ok.
+        _ => names
+            .iter()
+            .map(|s| ipc::StartResult {
+                service: s.clone(),
+                outcome: "started".into(),
+                error: None,
+            })
+            .collect(),
+    };
+    let mut failed = 0;
+    for r in &results {
+        match r.outcome.as_str() {
+            "started" => println!(
+                "{} started {} {}",
+                "✓".green(),
+                r.service.cyan(),
+                note.dimmed()
+            ),
+            "already running" => println!(
+                "{} {} {}",
+                "·".dimmed(),
+                r.service.cyan(),
+                "already running".dimmed()
+            ),
+            outcome => {
+                failed += 1;
+                let error = r.error.as_deref().unwrap_or("see `a3s logs`");
+                println!("{} {} {outcome}: {error}", "✗".red(), r.service.cyan());
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(DevError::Config(format!(
+            "{failed} of {} services didn't start",
+            results.len()
+        )));
+    }
+    Ok(())
+}
+
+/// CPU and MEM columns: usage of the service's process tree; "-" without a process.
+fn usage_cells(row: &ipc::StatusRow) -> (String, String) {
+    match (row.cpu_percent, row.rss_bytes) {
+        (Some(cpu), Some(rss)) => (format!("{cpu:.1}%"), format_bytes(rss)),
+        _ => ("-".into(), "-".into()),
+    }
+}
+
+/// How long the service's current process has run; "-" without one.
+fn uptime_cell(row: &ipc::StatusRow) -> String {
+    if row.pid.is_some() {
+        format_uptime(row.uptime_secs)
+    } else {
+        "-".into()
+    }
+}
+
 fn format_uptime(secs: u64) -> String {
     if secs < 60 {
         format!("{secs}s")
@@ -1221,12 +2189,209 @@ fn format_uptime(secs: u64) -> String {
         format!("{}h{}m", secs / 3600, (secs % 3600) / 60)
     }
 }
+
+fn print_health_report(rows: &[ipc::HealthReportRow]) {
+    println!(
+        "{:<16} {:<12} {:<8} {:<6} {}",
+        "SERVICE".bold(),
+        "UPTIME".bold(),
+        "CHECKS".bold(),
+        "FLAPS".bold(),
+        "LONGEST OUTAGE".bold(),
+    );
+    println!("{}", "─".repeat(58).dimmed());
+    for row in rows {
+        let uptime = row
+            .uptime_pct
+            .map(|p| format!("{p:.2}%"))
+            .unwrap_or_else(|| "-".into());
+        let uptime = if row.flaky {
+            format!("{uptime} flaky").yellow().to_string()
+        } else {
+            uptime.green().to_string()
+        };
+        let outage = if row.longest_outage_secs == 0 {
+            "-".into()
+        } else {
+            format_uptime(row.longest_outage_secs)
+        };
+        println!(
+            "{:<16} {:<21} {:<8} {:<6} {}",
+            row.name, uptime, row.checks, row.flaps, outage
+        );
+    }
+}
+
+/// HEALTH column: `healthy`, `starting` or `unhealthy 2/3` (failures of retries),
+/// or a "flaky" badge when the service passes now but its 24h uptime is below
+/// `dev.flaky_threshold`. Daemons without the `health` field fall back to ✓/✗.
+fn health_cell(row: &ipc::StatusRow) -> String {
+    if let Some(failures) = row
+        .health
+        .strip_prefix("unhealthy (")
+        .and_then(|h| h.strip_suffix(" failures)"))
+    {
+        return format!("unhealthy {failures}").red().to_string();
+    }
+    match (row.health.as_str(), row.healthy) {
+        (_, Some(false)) => "✗".red().to_string(),
+        ("starting", _) => "starting".yellow().to_string(),
+        _ if row.flaky => "flaky".yellow().to_string(),
+        ("healthy", _) => "healthy".green().to_string(),
+        (_, Some(true)) => "✓".green().to_string(),
+        _ => "-".dimmed().to_string(),
+    }
+}
+
+/// Services whose health checks are failing, with why and when they last passed.
+fn failing_health_rows(rows: &[ipc::StatusRow]) -> Vec<(String, String, Option<u64>)> {
+    rows.iter()
+        .filter(|r| r.health.starts_with("unhealthy"))
+        .map(|r| {
+            let reason = r.last_health_error.clone().unwrap_or_default();
+            (r.name.clone(), reason, r.last_healthy_at)
+        })
+        .collect()
+}
+
+fn print_failing_health(failing: &[(String, String, Option<u64>)]) {
+    if failing.is_empty() {
+        return;
+    }
+    println!("\n{}", "FAILING HEALTH CHECKS".bold());
+    for (name, reason, last_ok) in failing {
+        let last_ok = match last_ok {
+            Some(at) => format!("last healthy {} UTC", clock_time(*at)),
+            None => "never healthy".into(),
+        };
+        println!(
+            "  {:<16} {} {reason} {}",
+            name.cyan(),
+            "→".red(),
+            format!("({last_ok})").dimmed()
+        );
+    }
+}
+
+/// URL column: the proxy URL, or the image a container service runs.
+fn url_cell(row: &ipc::StatusRow) -> String {
+    match (&row.subdomain, &row.image) {
+        (Some(s), _) => format!("http://{s}.localhost"),
+        (None, Some(image)) => image.clone(),
+        (None, None) => String::new(),
+    }
+}
+
+/// (service, url) pairs for rows with an active `a3s share` tunnel.
+fn active_shares(rows: &[ipc::StatusRow]) -> Vec<(String, String)> {
+    rows.iter()
+        .filter_map(|r| r.share_url.clone().map(|u| (r.name.clone(), u)))
+        .collect()
+}
+
+fn print_shares(shares: &[(String, String)]) {
+    if shares.is_empty() {
+        return;
+    }
+    println!("\n{}", "SHARED".bold());
+    for (name, url) in shares {
+        println!("  {:<16} {}", name.cyan(), url);
+    }
+}
+
+/// (service, spare) pairs for `standby = true` services with a spare instance up.
+fn standby_rows(rows: &[ipc::StatusRow]) -> Vec<(String, ipc::StandbyRow)> {
+    rows.iter()
+        .filter_map(|r| r.standby.clone().map(|s| (r.name.clone(), s)))
+        .collect()
+}
+
+fn blocked_rows(rows: &[ipc::StatusRow]) -> Vec<(String, String)> {
+    rows.iter()
+        .filter_map(|r| r.blocked.clone().map(|b| (r.name.clone(), b)))
+        .collect()
+}
+
+/// Which dependency edge kept each blocked service down.
+fn print_blocked(blocked: &[(String, String)]) {
+    if blocked.is_empty() {
+        return;
+    }
+    println!("\n{}", "BLOCKED".bold());
+    for (name, reason) in blocked {
+        println!("  {:<16} {} {}", name.cyan(), "→".red(), reason);
+    }
+}
+
+/// Services that failed to start, with the error.
+fn start_error_rows(rows: &[ipc::StatusRow]) -> Vec<(String, String)> {
+    rows.iter()
+        .filter_map(|r| r.start_error.clone().map(|e| (r.name.clone(), e)))
+        .collect()
+}
+
+fn print_start_errors(failed: &[(String, String)]) {
+    if failed.is_empty() {
+        return;
+    }
+    println!("\n{}", "FAILED TO START".bold());
+    for (name, error) in failed {
+        println!("  {:<16} {} {}", name.cyan(), "→".red(), error);
+    }
+}
+
+/// Crash-looped services with their last exit code and log lines.
+fn crash_loop_rows(rows: &[ipc::StatusRow]) -> Vec<(String, Option<i32>, Vec<String>)> {
+    rows.iter()
+        .filter(|r| r.state == "crash-looped")
+        .map(|r| (r.name.clone(), r.exit_code, r.last_logs.clone()))
+        .collect()
+}
+
+fn print_crash_loops(crash_loops: &[(String, Option<i32>, Vec<String>)]) {
+    if crash_loops.is_empty() {
+        return;
+    }
+    println!("\n{}", "CRASH-LOOPED".bold());
+    for (name, code, logs) in crash_loops {
+        let code = code.map(|c| c.to_string()).unwrap_or_else(|| "?".into());
+        println!(
+            "  {:<16} {} exit code {code} — `a3s restart {name}` to retry",
+            name.cyan(),
+            "→".red(),
+        );
+        for line in logs {
+            println!("    {}", line.dimmed());
+        }
+    }
+}
+
+fn print_standbys(standbys: &[(String, ipc::StandbyRow)]) {
+    if standbys.is_empty() {
+        return;
+    }
+    println!("\n{}", "STANDBY".bold());
+    for (name, spare) in standbys {
+        let state = match spare.state.as_str() {
+            "running" => spare.state.green().to_string(),
+            _ => spare.state.yellow().to_string(),
+        };
+        let pid = spare
+            .pid
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| "-".into());
+        println!(
+            "  {:<16} {:<20} pid {:<8} :{}",
+            name.cyan(),
+            state,
+            pid,
+            spare.port
+        );
+    }
+}
+
 fn which_binary(name: &str) -> bool {
-    std::process::Command::new("which")
-        .arg(name)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+    doctor::which(name).is_some()
 }
 
 /// Filter services by labels. Returns service names that match ANY of the given labels.
@@ -1259,7 +2424,19 @@ async fn proxy_tool(alias: &str, args: &[String]) -> Result<()> {
             "unknown tool '{alias}' — run `a3s list` to see available tools"
         ))
     })?;
+    ensure_tool(binary, owner, repo).await?;
+
+    // Replace current process with the tool
+    use std::os::unix::process::CommandExt;
+    let err = std::process::Command::new(binary).args(args).exec();
+    Err(DevError::Process {
+        service: binary.to_string(),
+        msg: err.to_string(),
+    })
+}
 
+/// Install an ecosystem tool from its GitHub releases if it is not on PATH.
+async fn ensure_tool(binary: &'static str, owner: &'static str, repo: &'static str) -> Result<()> {
     if !which_binary(binary) {
         println!(
             "{} {} not found — installing from {}/{}...",
@@ -1279,24 +2456,21 @@ async fn proxy_tool(alias: &str, args: &[String]) -> Result<()> {
             .await
             .map_err(|e| DevError::Config(format!("failed to install {binary}: {e}")))?;
     }
-
-    // Replace current process with the tool
-    use std::os::unix::process::CommandExt;
-    let err = std::process::Command::new(binary).args(args).exec();
-    Err(DevError::Process {
-        service: binary.to_string(),
-        msg: err.to_string(),
-    })
+    Ok(())
 }
 
 /// Poll the daemon via IPC until all services are healthy or the timeout expires.
 /// Used by `a3s up --detach --wait`.
-async fn wait_for_healthy(sock: &std::path::Path, timeout_secs: u64) -> Result<()> {
+async fn wait_for_healthy(
+    sock: &ipc::IpcAddr,
+    timeout_secs: u64,
+    progress: &progress::Progress,
+) -> Result<()> {
     let deadline = std::time::Instant::now() + std::time::Duration::from_secs(timeout_secs);
 
     // Wait for socket to appear (daemon may still be starting)
     loop {
-        if sock.exists() {
+        if sock.socket.exists() {
             break;
         }
         if std::time::Instant::now() >= deadline {
@@ -1307,6 +2481,8 @@ async fn wait_for_healthy(sock: &std::path::Path, timeout_secs: u64) -> Result<(
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     }
 
+    let mut last_state: std::collections::HashMap<String, String> =
+        std::collections::HashMap::new();
     loop {
         if std::time::Instant::now() >= deadline {
             return Err(DevError::Config(
@@ -1314,14 +2490,23 @@ async fn wait_for_healthy(sock: &std::path::Path, timeout_secs: u64) -> Result<(
             ));
         }
         if let Ok(IpcResponse::Status { rows }) = ipc_send(IpcRequest::Status, sock).await {
+            for r in &rows {
+                if last_state.get(&r.name) != Some(&r.state) {
+                    progress.state(&r.name, &r.state);
+                    last_state.insert(r.name.clone(), r.state.clone());
+                }
+            }
             if rows.iter().any(|r| r.state == "failed") {
                 return Err(DevError::Config(
                     "one or more services failed to start".into(),
                 ));
             }
-            let all_settled = rows
-                .iter()
-                .all(|r| matches!(r.state.as_str(), "running" | "stopped" | "failed"));
+            let all_settled = rows.iter().all(|r| {
+                matches!(
+                    r.state.as_str(),
+                    "running" | "stopped" | "failed" | "blocked" | "not requested"
+                )
+            });
             if all_settled {
                 return Ok(());
             }
@@ -1331,45 +2516,107 @@ async fn wait_for_healthy(sock: &std::path::Path, timeout_secs: u64) -> Result<(
     }
 }
 
-async fn ipc_send(req: IpcRequest, sock: &std::path::Path) -> Result<IpcResponse> {
-    let stream = UnixStream::connect(sock)
+type IpcLines = tokio::io::Lines<BufReader<tokio::io::ReadHalf<Box<dyn ipc::IpcIo>>>>;
+type IpcWriter = tokio::io::WriteHalf<Box<dyn ipc::IpcIo>>;
+
+/// Connect to the daemon, check it speaks our protocol and send `req`.
+async fn ipc_open(req: &IpcRequest, sock: &ipc::IpcAddr) -> Result<(IpcLines, IpcWriter)> {
+    let stream = sock
+        .connect()
         .await
         .map_err(|_| DevError::Config("no running a3s daemon — run `a3s up` first".into()))?;
-
     let (reader, mut writer) = tokio::io::split(stream);
-    let line = serde_json::to_string(&req)
+    let mut lines = BufReader::new(reader).lines();
+
+    let hello = IpcRequest::Hello {
+        version: ipc::PROTOCOL_VERSION,
+        token: sock.token.clone(),
+    };
+    ipc_write(&mut writer, &hello).await?;
+    ipc::check_hello(&ipc_read(&mut lines).await?)?;
+
+    ipc_write(&mut writer, req).await?;
+    Ok((lines, writer))
+}
+
+async fn ipc_write(writer: &mut IpcWriter, req: &IpcRequest) -> Result<()> {
+    let line = serde_json::to_string(req)
         .map_err(|e| DevError::Config(format!("IPC serialize error: {e}")))?;
     writer.write_all(format!("{line}\n").as_bytes()).await?;
+    Ok(())
+}
 
-    let mut lines = BufReader::new(reader).lines();
+async fn ipc_read(lines: &mut IpcLines) -> Result<IpcResponse> {
     let resp_line = lines
         .next_line()
         .await?
         .ok_or_else(|| DevError::Config("daemon closed connection".into()))?;
-
     serde_json::from_str(&resp_line).map_err(|e| DevError::Config(format!("bad IPC response: {e}")))
 }
 
+async fn ipc_send(req: IpcRequest, sock: &ipc::IpcAddr) -> Result<IpcResponse> {
+    let (mut lines, _writer) = ipc_open(&req, sock).await?;
+    ipc_read(&mut lines).await
+}
+
+/// `--since`: checked here, sent as given for the daemon to resolve.
+fn since_arg(s: &str) -> std::result::Result<String, String> {
+    log::parse_since(s).map(|_| s.to_string())
+}
+
 async fn stream_logs(
     services: Option<Vec<String>>,
     follow: bool,
-    grep: Option<String>,
+    filter: &log::LineFilter,
     last: usize,
+    since: Option<String>,
     timestamps: bool,
-    sock: &std::path::Path,
+    sock: &ipc::IpcAddr,
 ) -> Result<()> {
-    let print_line = |svc: &str, color_idx: usize, text: String| {
-        if grep
-            .as_deref()
-            .is_none_or(|g| text.to_lowercase().contains(&g.to_lowercase()))
-        {
-            let prefix = colorize_prefix(&format!("[{svc}]"), color_idx);
-            let body = grep
-                .as_deref()
-                .map(|g| highlight_grep(&text, g))
-                .unwrap_or_else(|| text.clone());
+    use std::io::IsTerminal;
+    // `colored` honors NO_COLOR itself but not a piped stdout.
+    if !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+    let service_list = services.as_deref().unwrap_or(&[]);
+
+    // Tags are padded to the longest name that may show up, growing if a longer one
+    // does (e.g. a service started after this began).
+    let width = std::cell::Cell::new(if service_list.is_empty() {
+        match ipc_send(IpcRequest::Status, sock).await {
+            Ok(IpcResponse::Status { rows }) => tag_width(
+                rows.iter()
+                    .filter(|r| r.pid.is_some())
+                    .map(|r| r.name.as_str()),
+            ),
+            _ => 0,
+        }
+    } else {
+        tag_width(service_list.iter().map(String::as_str))
+    });
+    let print_line = |resp: IpcResponse| {
+        let IpcResponse::LogLine {
+            service: svc,
+            line: text,
+            ts_ms,
+            stream,
+            ..
+        } = resp
+        else {
+            return;
+        };
+        width.set(width.get().max(tag_width([svc.as_str()])));
+        // The daemon filters too; older ones ignore the pattern and send everything.
+        if filter.keeps(&text) {
+            let prefix = service_tag(&svc, width.get());
+            let body = match filter.regex().filter(|_| !filter.invert()) {
+                Some(re) => highlight_grep(&text, re),
+                None if stream.as_deref() == Some("stderr") => text.red().to_string(),
+                None => text,
+            };
             if timestamps {
-                let ts = chrono_now();
+                // Older daemons don't send the capture time; fall back to now.
+                let ts = ts_ms.map_or_else(chrono_now, |ms| clock_time(ms / 1000));
                 println!("{} {} {}", ts.dimmed(), prefix, body);
             } else {
                 println!("{} {}", prefix, body);
@@ -1377,37 +2624,21 @@ async fn stream_logs(
         }
     };
 
-    let service_list = services.as_deref().unwrap_or(&[]);
-
     // First replay history
     {
-        let stream = UnixStream::connect(sock)
-            .await
-            .map_err(|_| DevError::Config("no running a3s daemon — run `a3s up` first".into()))?;
-        let (reader, mut writer) = tokio::io::split(stream);
         let req = IpcRequest::History {
             services: service_list.to_vec(),
             lines: last,
+            since,
+            grep: filter.regex().map(|re| re.as_str().to_string()),
+            invert: filter.invert(),
         };
-        writer
-            .write_all(
-                format!(
-                    "{}\n",
-                    serde_json::to_string(&req)
-                        .map_err(|e| DevError::Config(format!("IPC serialize error: {e}")))?
-                )
-                .as_bytes(),
-            )
-            .await?;
-        let mut lines = BufReader::new(reader).lines();
+        let (mut lines, _writer) = ipc_open(&req, sock).await?;
         while let Ok(Some(line)) = lines.next_line().await {
-            if let Ok(IpcResponse::LogLine {
-                service: svc,
-                line: text,
-                color_idx,
-            }) = serde_json::from_str::<IpcResponse>(&line)
-            {
-                print_line(&svc, color_idx, text);
+            match serde_json::from_str::<IpcResponse>(&line) {
+                Ok(IpcResponse::Error { msg }) => return Err(DevError::Config(msg)),
+                Ok(resp) => print_line(resp),
+                Err(_) => {}
             }
         }
     }
@@ -1417,38 +2648,23 @@ async fn stream_logs(
     }
 
     // Then stream live, with Ctrl+C support.
-    let stream = UnixStream::connect(sock)
-        .await
-        .map_err(|_| DevError::Config("no running a3s daemon — run `a3s up` first".into()))?;
-    let (reader, mut writer) = tokio::io::split(stream);
     let req = IpcRequest::Logs {
         services: service_list.to_vec(),
         follow: true,
+        grep: filter.regex().map(|re| re.as_str().to_string()),
+        invert: filter.invert(),
     };
-    writer
-        .write_all(
-            format!(
-                "{}\n",
-                serde_json::to_string(&req)
-                    .map_err(|e| DevError::Config(format!("IPC serialize error: {e}")))?
-            )
-            .as_bytes(),
-        )
-        .await?;
-
-    let mut lines = BufReader::new(reader).lines();
+    let (mut lines, _writer) = ipc_open(&req, sock).await?;
     loop {
         tokio::select! {
             _ = tokio::signal::ctrl_c() => break,
             result = lines.next_line() => {
                 match result {
-                    Ok(Some(line)) => {
-                        if let Ok(IpcResponse::LogLine { service: svc, line: text, color_idx }) =
-                            serde_json::from_str::<IpcResponse>(&line)
-                        {
-                            print_line(&svc, color_idx, text);
-                        }
-                    }
+                    Ok(Some(line)) => match serde_json::from_str::<IpcResponse>(&line) {
+                        Ok(IpcResponse::Error { msg }) => return Err(DevError::Config(msg)),
+                        Ok(resp) => print_line(resp),
+                        Err(_) => {}
+                    },
                     _ => break,
                 }
             }
@@ -1465,6 +2681,11 @@ fn chrono_now() -> String {
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
+    clock_time(secs)
+}
+
+/// HH:MM:SS of `secs` since the Unix epoch.
+fn clock_time(secs: u64) -> String {
     // UTC offset not available in std — display UTC time.
     let s = secs % 86400;
     format!("{:02}:{:02}:{:02}", s / 3600, (s % 3600) / 60, s % 60)
@@ -1476,7 +2697,6 @@ fn port_available(port: u16) -> bool {
 }
 
 /// Deterministically map a service name to one of the log palette colors.
-#[allow(dead_code)]
 fn service_color(name: &str) -> usize {
     name.bytes().fold(0usize, |acc, b| {
         acc.wrapping_mul(31).wrapping_add(b as usize)
@@ -1499,87 +2719,185 @@ fn colorize_prefix(s: &str, color_idx: usize) -> String {
     }
 }
 
-/// Highlight all case-insensitive occurrences of `needle` in `haystack` using bold.
-fn highlight_grep(haystack: &str, needle: &str) -> String {
+/// Width of the `[service]` tag column for `names`.
+fn tag_width<'a>(names: impl IntoIterator<Item = &'a str>) -> usize {
+    names
+        .into_iter()
+        .map(|n| n.chars().count() + 2)
+        .max()
+        .unwrap_or(0)
+}
+
+/// `[service]` padded to `width` columns, in the service's own color.
+fn service_tag(service: &str, width: usize) -> String {
+    let tag = format!("{:<width$}", format!("[{service}]"));
+    colorize_prefix(&tag, service_color(service))
+}
+
+/// Highlight every match of `re` in `haystack` using bold.
+fn highlight_grep(haystack: &str, re: &regex::Regex) -> String {
     use colored::Colorize;
-    if needle.is_empty() {
-        return haystack.to_string();
-    }
-    let lower = haystack.to_lowercase();
-    let lower_needle = needle.to_lowercase();
-    let mut result = String::with_capacity(haystack.len());
-    let mut pos = 0;
-    while let Some(idx) = lower[pos..].find(&lower_needle) {
-        let abs = pos + idx;
-        result.push_str(&haystack[pos..abs]);
-        result.push_str(&haystack[abs..abs + needle.len()].bold().to_string());
-        pos = abs + needle.len();
-    }
-    result.push_str(&haystack[pos..]);
-    result
-}
-
-/// Query CPU% and RSS memory (in bytes) for a process by PID using `ps`.
-/// Returns `None` if the process is not found or `ps` is unavailable.
-fn query_process_stats(pid: u32) -> Option<(f32, u64)> {
-    let output = std::process::Command::new("ps")
-        .args(["-o", "%cpu=,rss=", "-p", &pid.to_string()])
-        .output()
-        .ok()?;
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let line = stdout.lines().find(|l| !l.trim().is_empty())?;
-    let mut parts = line.split_whitespace();
-    let cpu: f32 = parts.next()?.parse().ok()?;
-    let rss_kb: u64 = parts.next()?.parse().ok()?;
-    Some((cpu, rss_kb * 1024))
-}
-
-/// Read raw CPU ticks for a process from /proc (Linux only).
-/// Returns total utime+stime ticks and the system clock tick rate.
-#[cfg(target_os = "linux")]
-fn read_proc_cpu_ticks(pid: u32) -> Option<(u64, u64)> {
-    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
-    // Fields are space-separated; utime is field 14 (0-indexed 13), stime is 15.
-    // The comm field (2) may contain spaces inside parens, so find the closing ')' first.
-    let after_comm = stat.rfind(')')?;
-    let fields: Vec<&str> = stat[after_comm + 2..].split_whitespace().collect();
-    let utime: u64 = fields.get(11)?.parse().ok()?;
-    let stime: u64 = fields.get(12)?.parse().ok()?;
-    // SAFETY: sysconf is a standard POSIX call with no side effects.
-    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64;
-    Some((utime + stime, ticks_per_sec))
-}
-
-/// Delta-based CPU% and RSS. On Linux uses /proc for accurate per-interval CPU.
-/// On other platforms falls back to ps lifetime average.
-fn query_process_stats_delta(
-    pid: u32,
-    prev: &mut std::collections::HashMap<u32, (u64, std::time::Instant)>,
-) -> Option<(f32, u64)> {
-    #[cfg(target_os = "linux")]
-    {
-        if let Some((ticks, ticks_per_sec)) = read_proc_cpu_ticks(pid) {
-            let now = std::time::Instant::now();
-            let cpu = if let Some((prev_ticks, prev_time)) = prev.get(&pid) {
-                let delta_ticks = ticks.saturating_sub(*prev_ticks) as f32;
-                let delta_secs = now.duration_since(*prev_time).as_secs_f32();
-                if delta_secs > 0.0 && ticks_per_sec > 0 {
-                    (delta_ticks / ticks_per_sec as f32 / delta_secs) * 100.0
-                } else {
-                    0.0
-                }
-            } else {
-                0.0 // first sample — show 0 until we have a delta
-            };
-            prev.insert(pid, (ticks, now));
-            // Get RSS from ps since /proc/status parsing is verbose
-            let rss_kb = query_process_stats(pid).map(|(_, b)| b).unwrap_or(0);
-            return Some((cpu, rss_kb));
+    re.replace_all(haystack, |c: &regex::Captures| match &c[0] {
+        "" => String::new(),
+        m => m.bold().to_string(),
+    })
+    .into_owned()
+}
+
+/// Ask a yes/no question on the terminal; anything but y/yes is no. Refuses to
+/// guess when stdin is not a terminal.
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::{IsTerminal, Write};
+    if !std::io::stdin().is_terminal() {
+        return Err(DevError::Config(format!(
+            "{question} — not a terminal, pass --force to confirm"
+        )));
+    }
+    print!("{} {question} [y/N] ", "?".yellow());
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+fn print_doctor(report: &doctor::Report) {
+    let width = report
+        .checks
+        .iter()
+        .map(|c| c.name.len())
+        .max()
+        .unwrap_or(0);
+    for check in &report.checks {
+        let mark = match (check.ok, check.required) {
+            (true, _) => "✓".green(),
+            (false, true) => "✗".red(),
+            (false, false) => "·".dimmed(),
+        };
+        let why = if !check.needed_by.is_empty() {
+            format!(" ({})", check.needed_by).dimmed().to_string()
+        } else if !check.required {
+            " (optional)".dimmed().to_string()
+        } else {
+            String::new()
+        };
+        println!("{mark} {:<width$}  {}{why}", check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            println!("  {:<width$}  {} {fix}", "", "→".cyan());
         }
     }
-    // macOS / fallback
-    let _ = prev;
-    query_process_stats(pid)
+    if report.ok() {
+        println!("\n{} everything required is installed", "✓".green());
+    }
+}
+
+/// The document every `--json` output is wrapped in: `{"ok": true, "data": ...}`,
+/// or `{"ok": false, "error": "..."}` — with the data too when there is some, like
+/// the diagnostics that fail `config check`.
+fn json_envelope<T: serde::Serialize>(data: Option<&T>, error: Option<&str>) -> String {
+    let mut doc = serde_json::json!({ "ok": error.is_none() });
+    if let Some(data) = data {
+        doc["data"] = serde_json::to_value(data).unwrap_or_default();
+    }
+    if let Some(error) = error {
+        doc["error"] = error.into();
+    }
+    serde_json::to_string_pretty(&doc).unwrap_or_default()
+}
+
+fn print_json<T: serde::Serialize>(data: &T) {
+    println!("{}", json_envelope(Some(data), None));
+}
+
+/// Print `data` and, when the command failed, exit 1 — without `main` printing a
+/// second document for the error.
+fn print_json_or_exit<T: serde::Serialize>(data: &T, failure: Option<String>) {
+    println!("{}", json_envelope(Some(data), failure.as_deref()));
+    if failure.is_some() {
+        std::process::exit(1);
+    }
+}
+
+/// True for the `a3s box` commands [`box_json`] answers itself.
+fn box_has_json(args: &[String]) -> bool {
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| *a != "--json")
+        .collect();
+    matches!(
+        args.as_slice(),
+        ["ps", ..]
+            | ["images"]
+            | ["image", "ls"]
+            | ["network", "ls"]
+            | ["volume", "ls"]
+            | ["info"]
+            | ["network", "create", ..]
+            | ["volume", "create", ..]
+    )
+}
+
+/// `a3s box` in JSON mode: the listings and creates a3s understands itself are
+/// answered with their structs instead of a3s-box's tables; anything else is refused.
+async fn box_json(args: &[String]) -> Result<()> {
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| *a != "--json")
+        .collect();
+    ensure_tool("a3s-box", "A3S-Lab", "Box").await?;
+    match args.as_slice() {
+        ["ps", flags @ ..] => {
+            let all = flags.iter().any(|f| *f == "-a" || *f == "--all");
+            print_json(&box_mgr::list_containers(all).await?);
+        }
+        ["images"] | ["image", "ls"] => print_json(&box_mgr::list_images().await?),
+        ["network", "ls"] => print_json(&box_mgr::list_networks().await?),
+        ["volume", "ls"] => print_json(&box_mgr::list_volumes().await?),
+        ["info"] => print_json(&box_mgr::get_info().await?),
+        ["network", rest @ ..] if rest.first() == Some(&"create") => {
+            let create = BoxNetworkCreateArgs::parse_from(rest);
+            let created = box_mgr::create_network(
+                &create.name,
+                create.driver.as_deref(),
+                create.subnet.as_deref(),
+            )
+            .await?;
+            print_json(&created);
+        }
+        ["volume", rest @ ..] if rest.first() == Some(&"create") => {
+            let create = BoxVolumeCreateArgs::parse_from(rest);
+            print_json(&box_mgr::create_volume(&create.name, create.driver.as_deref()).await?);
+        }
+        _ => {
+            return Err(DevError::Config(format!(
+                "`a3s box {}` has no JSON output — JSON works for ps, images, \
+                 network ls, volume ls, info and network/volume create",
+                args.join(" ")
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn print_prune_report(report: &box_mgr::PruneReport, (one, many): (&str, &str)) {
+    for id in &report.removed {
+        println!("  {} {}", "·".dimmed(), id);
+    }
+    let what = if report.removed.len() == 1 { one } else { many };
+    let reclaimed = if report.reclaimed_bytes > 0 {
+        format!(", reclaimed {}", format_bytes(report.reclaimed_bytes))
+    } else {
+        String::new()
+    };
+    println!(
+        "{} removed {} {what}{reclaimed}",
+        "✓".green(),
+        report.removed.len()
+    );
 }
 
 fn format_bytes(bytes: u64) -> String {
@@ -1592,7 +2910,7 @@ fn format_bytes(bytes: u64) -> String {
 
 /// k8s down: delete all resources for the given services (or all if empty).
 async fn k8s_down(cfg: &DevConfig, services: &[String], label: &[String]) -> Result<()> {
-    let client = k8s::K8sClient::new(cfg.dev.k8s_context.clone(), cfg.dev.k8s_namespace.clone());
+    let client = k8s::K8sClient::from_settings(&cfg.dev);
 
     let mut targets: Vec<String> = if services.is_empty() && label.is_empty() {
         cfg.service.keys().cloned().collect()
@@ -1627,8 +2945,16 @@ async fn k8s_down(cfg: &DevConfig, services: &[String], label: &[String]) -> Res
     Ok(())
 }
 
-/// k8s status: show pod status via kubectl get pods.
+/// k8s status: show pod status via kubectl get pods, or with `--json` the
+/// Deployments and Services a3s manages.
 async fn k8s_status(cfg: &DevConfig, json: bool) -> Result<()> {
+    if json {
+        let resources = k8s::K8sClient::from_settings(&cfg.dev)
+            .query_resources(Some("managed-by=a3s"))
+            .await?;
+        print_json(&resources);
+        return Ok(());
+    }
     let mut cmd = std::process::Command::new("kubectl");
     cmd.arg("get")
         .arg("pods")
@@ -1637,15 +2963,8 @@ async fn k8s_status(cfg: &DevConfig, json: bool) -> Result<()> {
         .arg("-n")
         .arg(&cfg.dev.k8s_namespace);
 
-    if let Some(ref ctx) = cfg.dev.k8s_context {
-        cmd.arg("--context").arg(ctx);
-    }
-
-    if json {
-        cmd.arg("-o").arg("json");
-    } else {
-        cmd.arg("-o").arg("wide");
-    }
+    cmd.args(cfg.dev.kubectl_args());
+    cmd.arg("-o").arg("wide");
 
     let status = cmd
         .status()
@@ -1669,9 +2988,7 @@ async fn k8s_top(cfg: &DevConfig, interval: u64) -> Result<()> {
             .arg(&cfg.dev.k8s_namespace)
             .arg("--no-headers");
 
-        if let Some(ref ctx) = cfg.dev.k8s_context {
-            cmd.arg("--context").arg(ctx);
-        }
+        cmd.args(cfg.dev.kubectl_args());
 
         let output = cmd
             .output()
@@ -1715,7 +3032,7 @@ async fn k8s_top(cfg: &DevConfig, interval: u64) -> Result<()> {
             }
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            println!("{} {}", "error:".red(), stderr.trim());
+            println!("{} {}", "error:".red(), hints::explain("kubectl", &stderr));
             println!(
                 "{}",
                 "hint: kubectl top requires metrics-server to be installed".dimmed()
@@ -1741,8 +3058,9 @@ async fn k8s_logs(
     cfg: &DevConfig,
     services: &[String],
     follow: bool,
-    grep: Option<&str>,
+    filter: &log::LineFilter,
     tail: usize,
+    since: Option<&str>,
 ) -> Result<()> {
     use tokio::io::{AsyncBufReadExt, BufReader};
 
@@ -1762,10 +3080,18 @@ async fn k8s_logs(
             .arg("-n")
             .arg(&cfg.dev.k8s_namespace)
             .arg(format!("--tail={}", tail));
-
-        if let Some(ref ctx) = cfg.dev.k8s_context {
-            cmd.arg("--context").arg(ctx);
+        match since {
+            Some(t) if log::parse_timestamp(t).is_some() => {
+                cmd.arg(format!("--since-time={t}"));
+            }
+            Some(d) => {
+                let d = config::parse_duration(d).map_err(DevError::Config)?;
+                cmd.arg(format!("--since={}s", d.as_secs().max(1)));
+            }
+            None => {}
         }
+
+        cmd.args(cfg.dev.kubectl_args());
         if follow {
             cmd.arg("-f");
         }
@@ -1773,7 +3099,7 @@ async fn k8s_logs(
         cmd.stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::null());
 
-        let grep = grep.map(|s| s.to_lowercase());
+        let filter = filter.clone();
         let handle = tokio::spawn(async move {
             let mut child = match cmd.spawn() {
                 Ok(c) => c,
@@ -1785,10 +3111,8 @@ async fn k8s_logs(
             if let Some(stdout) = child.stdout.take() {
                 let mut lines = BufReader::new(stdout).lines();
                 while let Ok(Some(line)) = lines.next_line().await {
-                    if let Some(ref g) = grep {
-                        if !line.to_lowercase().contains(g.as_str()) {
-                            continue;
-                        }
+                    if !filter.keeps(&line) {
+                        continue;
                     }
                     println!("{} {}", format!("[{}]", svc_name).cyan(), line);
                 }
@@ -1818,6 +3142,7 @@ async fn run_k8s_mode(
     config_dir: std::path::PathBuf,
     services: Vec<String>,
     label: Vec<String>,
+    progress: progress::Progress,
 ) -> Result<()> {
     // Determine which services to deploy
     let mut target_services: Vec<String> = if services.is_empty() && label.is_empty() {
@@ -1856,7 +3181,7 @@ async fn run_k8s_mode(
         graph.transitive_start_order(&target_refs)
     };
 
-    println!("{} deploying {} services", "→".cyan(), deploy_order.len());
+    progress.step(format!("deploying {} services", deploy_order.len()));
 
     // Deploy services in dependency order
     for svc_name in &deploy_order {
@@ -1865,20 +3190,18 @@ async fn run_k8s_mode(
             .get(svc_name)
             .ok_or_else(|| DevError::Config(format!("service {} not found", svc_name)))?;
 
-        println!("{} {} deploying...", "→".cyan(), svc_name);
-        k8s_runtime
-            .start_service(svc_name, svc, &config_dir)
-            .await?;
-        println!("{} {} running", "✓".green(), svc_name);
+        progress.step(format!("{svc_name} deploying..."));
+        if let Err(e) = k8s_runtime.start_service(svc_name, svc, &config_dir).await {
+            progress.state(svc_name, "failed");
+            return Err(e);
+        }
+        progress.done(format!("{svc_name} running"));
     }
 
     // Deploy ingress if any service has subdomain
     k8s_runtime.deploy_ingress(&cfg.service).await?;
 
-    println!(
-        "\n{} all services deployed successfully",
-        "✓".green().bold()
-    );
+    progress.summary("all services deployed successfully");
 
     // Start file watchers for services with watch config
     let watched: Vec<String> = deploy_order
@@ -1940,6 +3263,9 @@ async fn run_k8s_mode(
             svc_name.clone(),
             paths,
             watch_cfg.ignore.clone(),
+            watch_cfg.use_gitignore,
+            vec![config_dir.join(&cfg.dev.log_dir)],
+            watch_cfg.debounce,
             watch_tx.clone(),
         );
         _watcher_stops.push(stop);
@@ -1986,6 +3312,17 @@ mod tests {
         drop(listener);
     }
 
+    #[test]
+    fn test_box_has_json() {
+        let args = |s: &str| s.split(' ').map(String::from).collect::<Vec<_>>();
+        assert!(box_has_json(&args("ps -a --json")));
+        assert!(box_has_json(&args("--json network create net1")));
+        assert!(box_has_json(&args("image ls")));
+        assert!(!box_has_json(&args("logs web --json")));
+        assert!(!box_has_json(&args("inspect c1 --json")));
+        assert!(!box_has_json(&args("image rm nginx")));
+    }
+
     #[test]
     fn test_format_bytes_kb() {
         assert_eq!(format_bytes(512 * 1024), "512 KB");
@@ -2011,20 +3348,21 @@ mod tests {
 
     #[test]
     fn test_highlight_grep_empty_needle() {
-        assert_eq!(highlight_grep("hello world", ""), "hello world");
+        let re = regex::Regex::new("").unwrap();
+        assert_eq!(highlight_grep("hello world", &re), "hello world");
     }
 
     #[test]
     fn test_highlight_grep_no_match() {
         // No match — returned unchanged (no ANSI codes injected).
-        let result = highlight_grep("hello world", "xyz");
+        let result = highlight_grep("hello world", &regex::Regex::new("xyz").unwrap());
         assert_eq!(result, "hello world");
     }
 
     #[test]
     fn test_highlight_grep_case_insensitive() {
         colored::control::set_override(true);
-        let result = highlight_grep("Hello World", "hello");
+        let result = highlight_grep("Hello World", &regex::Regex::new("(?i)hello").unwrap());
         assert!(result.contains("Hello")); // original casing preserved
         assert!(result.len() > "Hello World".len()); // ANSI codes added
         colored::control::unset_override();
@@ -2033,11 +3371,20 @@ mod tests {
     #[test]
     fn test_highlight_grep_multiple_matches() {
         colored::control::set_override(true);
-        let result = highlight_grep("foo bar foo", "foo");
+        let result = highlight_grep("foo bar foo", &regex::Regex::new("fo+").unwrap());
+        assert_eq!(result.matches("foo").count(), 2);
         assert!(result.len() > "foo bar foo".len());
         colored::control::unset_override();
     }
 
+    #[test]
+    fn test_service_tags_are_aligned() {
+        assert_eq!(tag_width(["api", "worker"]), 8);
+        assert_eq!(tag_width([""; 0]), 0);
+        assert!(service_tag("api", 8).contains("[api]   "));
+        assert!(service_tag("worker", 2).contains("[worker]"));
+    }
+
     #[test]
     fn test_chrono_now_format() {
         let ts = chrono_now();
@@ -2051,5 +3398,82 @@ mod tests {
         assert!(h < 24);
         assert!(m < 60);
         assert!(s < 60);
+        assert_eq!(clock_time(1_714_557_600), "10:00:00");
+    }
+
+    #[test]
+    fn test_health_cell_flaky_badge() {
+        let mut row: ipc::StatusRow = serde_json::from_str(
+            r#"{"name":"api","state":"running","pid":1,"port":3000,"subdomain":null,
+                "uptime_secs":5,"proxy_port":7080,"restart_count":0,"healthy":true}"#,
+        )
+        .unwrap();
+        assert!(health_cell(&row).contains('✓'));
+        row.flaky = true;
+        assert!(health_cell(&row).contains("flaky"));
+        // A failing check outranks the badge.
+        row.healthy = Some(false);
+        assert!(health_cell(&row).contains('✗'));
+    }
+
+    #[test]
+    fn test_health_cell_shows_the_health_state() {
+        let mut row: ipc::StatusRow = serde_json::from_str(
+            r#"{"name":"api","state":"running","pid":1,"port":3000,"subdomain":null,
+                "uptime_secs":5,"proxy_port":7080,"restart_count":0,"healthy":true,
+                "health":"starting"}"#,
+        )
+        .unwrap();
+        assert!(health_cell(&row).contains("starting"));
+        row.health = "healthy".into();
+        assert!(health_cell(&row).contains("healthy"));
+        row.health = "unhealthy (2/3 failures)".into();
+        row.last_health_error = Some("GET /health returned 500".into());
+        assert!(health_cell(&row).contains("unhealthy 2/3"));
+        let failing = failing_health_rows(std::slice::from_ref(&row));
+        assert_eq!(
+            failing,
+            vec![("api".into(), "GET /health returned 500".into(), None)]
+        );
+
+        // Rows of older daemons carry no `health`.
+        let old: ipc::StatusRow = serde_json::from_str(
+            r#"{"name":"db","state":"running","pid":2,"port":5432,"subdomain":null,
+                "uptime_secs":5,"proxy_port":7080,"restart_count":0,"healthy":null}"#,
+        )
+        .unwrap();
+        assert_eq!(old.health, "none");
+        assert!(health_cell(&old).contains('-'));
+    }
+
+    #[test]
+    fn test_usage_cells() {
+        let mut row: ipc::StatusRow = serde_json::from_str(
+            r#"{"name":"api","state":"running","pid":1,"port":3000,"subdomain":null,
+                "uptime_secs":5,"cpu_percent":12.345,"rss_bytes":52428800,
+                "proxy_port":7080,"restart_count":0,"healthy":null}"#,
+        )
+        .unwrap();
+        assert_eq!(usage_cells(&row), ("12.3%".into(), "50.0 MB".into()));
+        row.cpu_percent = None;
+        row.rss_bytes = None;
+        assert_eq!(usage_cells(&row), ("-".into(), "-".into()));
+    }
+
+    #[test]
+    fn test_uptime_cell() {
+        let mut row: ipc::StatusRow = serde_json::from_str(
+            r#"{"name":"api","state":"running","pid":1,"port":3000,"subdomain":null,
+                "uptime_secs":3720,"proxy_port":7080,"restart_count":0,"healthy":null}"#,
+        )
+        .unwrap();
+        assert_eq!(uptime_cell(&row), "1h2m");
+        row.uptime_secs = 45;
+        assert_eq!(uptime_cell(&row), "45s");
+        row.uptime_secs = 125;
+        assert_eq!(uptime_cell(&row), "2m5s");
+        row.pid = None;
+        row.uptime_secs = 0;
+        assert_eq!(uptime_cell(&row), "-");
     }
 }
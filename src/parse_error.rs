@@ -0,0 +1,245 @@
+//! Parse and deserialize errors in HCL files, shown where they happened.
+//!
+//! The hcl crate reports syntax errors with a position but deserialize errors
+//! (unknown or mistyped fields) without one. Both are turned into an error naming
+//! `file:line:column`, with the surrounding source lines and a caret under the
+//! offending token. An unknown field is looked up in the file to find its position,
+//! and gets a "did you mean" from the fields serde expected.
+
+use std::ops::Range;
+use std::path::Path;
+
+use hcl::edit::parser::parse_body;
+use hcl::edit::structure::{Body, Structure};
+use hcl::edit::Span;
+
+use crate::error::DevError;
+
+/// Lines of source shown before and after the offending one.
+const CONTEXT_LINES: usize = 1;
+
+/// `err` from parsing `src`, the text of `path`.
+pub fn hcl_error(path: &Path, src: &str, err: &hcl::Error) -> DevError {
+    match err {
+        hcl::Error::Parse(e) => {
+            let offset = e.location().offset();
+            located(path, src, e.message(), Some(offset..offset + 1), None)
+        }
+        other => serde_error(path, src, &other.to_string()),
+    }
+}
+
+/// A deserialize error `msg` about the HCL in `path`, whose text is `src`.
+pub fn serde_error(path: &Path, src: &str, msg: &str) -> DevError {
+    let Some((field, expected)) = unknown_field(msg) else {
+        return located(path, src, msg, None, None);
+    };
+    let span = locate_field(src, field, &expected);
+    located(path, src, msg, span, suggest(field, &expected))
+}
+
+fn located(
+    path: &Path,
+    src: &str,
+    msg: &str,
+    span: Option<Range<usize>>,
+    suggestion: Option<&str>,
+) -> DevError {
+    let mut out = format!("parse error in {}", path.display());
+    if let Some(span) = &span {
+        let (line, column) = line_column(src, span.start);
+        out.push_str(&format!(":{line}:{column}"));
+    }
+    out.push_str(&format!(": {msg}"));
+    if let Some(span) = span {
+        out.push('\n');
+        out.push_str(&snippet(src, span));
+    }
+    if let Some(s) = suggestion {
+        out.push_str(&format!("\n  did you mean `{s}`?"));
+    }
+    DevError::Config(out)
+}
+
+/// One-based line and column (in characters) of byte `offset`.
+fn line_column(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..floor_char_boundary(src, offset)];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn floor_char_boundary(src: &str, offset: usize) -> usize {
+    let mut offset = offset.min(src.len());
+    while !src.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// The lines around `span`, numbered, with carets under the span.
+fn snippet(src: &str, span: Range<usize>) -> String {
+    let (line, column) = line_column(src, span.start);
+    let lines: Vec<&str> = src.lines().collect();
+    let first = line.saturating_sub(CONTEXT_LINES).max(1);
+    let last = (line + CONTEXT_LINES).min(lines.len()).max(line);
+    let width = last.to_string().len();
+
+    let mut out = String::new();
+    for n in first..=last {
+        let text = lines.get(n - 1).copied().unwrap_or("");
+        out.push_str(&format!("  {n:>width$} | {text}\n"));
+        if n == line {
+            let len = src
+                .get(span.clone())
+                .map_or(1, |s| s.chars().take_while(|&c| c != '\n').count())
+                .max(1);
+            out.push_str(&format!(
+                "  {:width$} | {}{}\n",
+                "",
+                " ".repeat(column - 1),
+                "^".repeat(len)
+            ));
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// The field and the expected fields of serde's "unknown field `x`, expected one of
+/// `a`, `b`" message.
+fn unknown_field(msg: &str) -> Option<(&str, Vec<&str>)> {
+    let rest = &msg[msg.find("unknown field `")? + "unknown field `".len()..];
+    let (field, rest) = rest.split_once('`')?;
+    let expected = rest.split('`').skip(1).step_by(2).collect();
+    Some((field, expected))
+}
+
+/// The key `field` that serde rejected: in the first block whose other keys are all
+/// among `expected`, else its first occurrence anywhere.
+fn locate_field(src: &str, field: &str, expected: &[&str]) -> Option<Range<usize>> {
+    let body = parse_body(src).ok()?;
+    let mut first = None;
+    find_key(&body, field, expected, &mut first).or(first)
+}
+
+fn find_key(
+    body: &Body,
+    field: &str,
+    expected: &[&str],
+    first: &mut Option<Range<usize>>,
+) -> Option<Range<usize>> {
+    let mut hit = None;
+    let mut others_expected = true;
+    for structure in body.iter() {
+        let (key, span) = match structure {
+            Structure::Attribute(a) => (a.key.as_str(), a.key.span()),
+            Structure::Block(b) => (b.ident.as_str(), b.ident.span()),
+        };
+        if key == field {
+            hit = hit.or(span);
+        } else if !expected.contains(&key) {
+            others_expected = false;
+        }
+    }
+    if let Some(span) = hit {
+        if others_expected {
+            return Some(span);
+        }
+        first.get_or_insert(span);
+    }
+    body.blocks()
+        .find_map(|b| find_key(&b.body, field, expected, first))
+}
+
+/// The expected field closest to `field`, if it is a plausible typo of it.
+fn suggest<'a>(field: &str, expected: &[&'a str]) -> Option<&'a str> {
+    expected
+        .iter()
+        .map(|e| (edit_distance(field, e), *e))
+        .filter(|(d, _)| *d <= 2 && *d < field.chars().count())
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, e)| e)
+}
+
+/// Levenshtein distance in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (diag + usize::from(ca != *cb))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            diag = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance_and_suggestion() {
+        assert_eq!(edit_distance("path", "paths"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        let fields = ["paths", "ignore", "restart", "debounce"];
+        assert_eq!(suggest("path", &fields), Some("paths"));
+        assert_eq!(suggest("ignroe", &fields), Some("ignore"));
+        assert_eq!(suggest("command", &fields), None);
+        assert_eq!(suggest("a", &["b"]), None);
+    }
+
+    #[test]
+    fn test_unknown_field_message() {
+        let (field, expected) =
+            unknown_field("unknown field `path`, expected one of `paths`, `ignore`").unwrap();
+        assert_eq!(field, "path");
+        assert_eq!(expected, ["paths", "ignore"]);
+        let (field, expected) = unknown_field("unknown field `x`, expected `cmd`").unwrap();
+        assert_eq!((field, expected), ("x", vec!["cmd"]));
+        assert!(unknown_field("missing field `cmd`").is_none());
+    }
+
+    #[test]
+    fn test_locate_field_prefers_the_block_it_is_unknown_in() {
+        let src = "service \"api\" {\n  cmd = \"x\"\n  health {\n    type = \"http\"\n    path = \"/\"\n  }\n  watch {\n    path = [\"src\"]\n  }\n}\n";
+        let span = locate_field(src, "path", &["paths", "ignore"]).unwrap();
+        assert_eq!(line_column(src, span.start), (8, 5));
+        assert_eq!(&src[span], "path");
+    }
+
+    #[test]
+    fn test_unknown_field_error_shows_position_snippet_and_suggestion() {
+        let src = "service \"api\" {\n  cmd = \"x\"\n  watch {\n    path = [\"src\"]\n  }\n}\n";
+        let err = hcl::from_str::<crate::config::DevConfig>(src).unwrap_err();
+        let msg = hcl_error(Path::new("A3sfile.hcl"), src, &err).to_string();
+        assert!(
+            msg.contains("A3sfile.hcl:4:5: unknown field `path`"),
+            "{msg}"
+        );
+        assert!(
+            msg.contains("  4 |     path = [\"src\"]\n    |     ^^^^"),
+            "{msg}"
+        );
+        assert!(msg.contains("  3 |   watch {"), "{msg}");
+        assert!(msg.ends_with("did you mean `paths`?"), "{msg}");
+    }
+
+    #[test]
+    fn test_syntax_error_points_at_the_position() {
+        let src = "service \"api\" {\n  cmd = \"x\"\n\nservice \"web\" {\n  cmd = \"y\"\n}\n";
+        let err = hcl::from_str::<crate::config::DevConfig>(src).unwrap_err();
+        let msg = hcl_error(Path::new("A3sfile.hcl"), src, &err).to_string();
+        assert!(
+            msg.starts_with("config error: parse error in A3sfile.hcl:"),
+            "{msg}"
+        );
+        assert!(msg.contains('^'), "{msg}");
+    }
+}
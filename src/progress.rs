@@ -0,0 +1,235 @@
+//! Startup progress output with a renderer chosen once per command.
+//!
+//! `fancy` keeps the coloured `→`/`✓`/`✗` lines. `plain` writes append-only
+//! `HH:MM:SS kind: message` lines with no colour or cursor control, so screen readers
+//! and log files get one self-contained line per event. `quiet` shows only failures
+//! and the final summary. A non-terminal stdout or `TERM=dumb` forces `plain` unless
+//! `quiet` was asked for.
+
+use std::io::IsTerminal;
+
+use colored::Colorize;
+
+use crate::config::ProgressStyle;
+
+/// One thing worth telling the user about while a command runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// A step is under way.
+    Step(String),
+    /// A service changed state.
+    State { service: String, state: String },
+    /// A step completed.
+    Done(String),
+    /// Worth a look, but not stopping anything.
+    Warning(String),
+    /// Something went wrong.
+    Failed(String),
+    /// Final outcome of the whole operation.
+    Summary(String),
+}
+
+/// Renders [`ProgressEvent`]s in the style resolved for this run.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    style: ProgressStyle,
+}
+
+impl Progress {
+    /// Resolve the style from config and the current terminal.
+    pub fn new(configured: Option<ProgressStyle>) -> Self {
+        let term = std::env::var("TERM").unwrap_or_default();
+        Self {
+            style: resolve(configured, std::io::stdout().is_terminal(), &term),
+        }
+    }
+
+    /// Print an event, if this style shows it.
+    pub fn emit(&self, event: ProgressEvent) {
+        if let Some(line) = render(self.style, &event, &crate::chrono_now()) {
+            println!("{line}");
+        }
+    }
+
+    pub fn step(&self, msg: impl Into<String>) {
+        self.emit(ProgressEvent::Step(msg.into()));
+    }
+
+    pub fn state(&self, service: &str, state: &str) {
+        self.emit(ProgressEvent::State {
+            service: service.to_string(),
+            state: state.to_string(),
+        });
+    }
+
+    pub fn warn(&self, msg: impl Into<String>) {
+        self.emit(ProgressEvent::Warning(msg.into()));
+    }
+
+    pub fn done(&self, msg: impl Into<String>) {
+        self.emit(ProgressEvent::Done(msg.into()));
+    }
+
+    pub fn summary(&self, msg: impl Into<String>) {
+        self.emit(ProgressEvent::Summary(msg.into()));
+    }
+}
+
+/// Pick the style: `quiet` is always honoured; otherwise a non-terminal or dumb
+/// terminal gets `plain`, and everything else what was configured (default `fancy`).
+pub fn resolve(configured: Option<ProgressStyle>, is_tty: bool, term: &str) -> ProgressStyle {
+    match configured {
+        Some(ProgressStyle::Quiet) => ProgressStyle::Quiet,
+        _ if !is_tty || term == "dumb" => ProgressStyle::Plain,
+        Some(style) => style,
+        None => ProgressStyle::Fancy,
+    }
+}
+
+/// The line `style` prints for `event` at time `ts`, or `None` if it prints nothing.
+pub fn render(style: ProgressStyle, event: &ProgressEvent, ts: &str) -> Option<String> {
+    match style {
+        ProgressStyle::Fancy => render_fancy(event),
+        ProgressStyle::Plain => Some(render_plain(event, ts)),
+        ProgressStyle::Quiet => match event {
+            ProgressEvent::Failed(_) | ProgressEvent::Summary(_) => render_fancy(event),
+            ProgressEvent::State { service, state } if is_failure(state) => {
+                render_fancy(&ProgressEvent::Failed(format!("{service} {state}")))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// State changes are already in the daemon log, so fancy only shows the milestones.
+fn render_fancy(event: &ProgressEvent) -> Option<String> {
+    match event {
+        ProgressEvent::Step(msg) => Some(format!("{} {msg}", "→".cyan())),
+        ProgressEvent::State { .. } => None,
+        ProgressEvent::Done(msg) => Some(format!("{} {msg}", "✓".green())),
+        ProgressEvent::Warning(msg) => Some(format!("{} {msg}", "!".yellow())),
+        ProgressEvent::Failed(msg) => Some(format!("{} {msg}", "✗".red())),
+        ProgressEvent::Summary(msg) => Some(format!("\n{} {msg}", "✓".green().bold())),
+    }
+}
+
+fn render_plain(event: &ProgressEvent, ts: &str) -> String {
+    match event {
+        ProgressEvent::Step(msg) => format!("{ts} step: {msg}"),
+        ProgressEvent::State { service, state } => format!("{ts} {service}: {state}"),
+        ProgressEvent::Done(msg) => format!("{ts} done: {msg}"),
+        ProgressEvent::Warning(msg) => format!("{ts} warning: {msg}"),
+        ProgressEvent::Failed(msg) => format!("{ts} failed: {msg}"),
+        ProgressEvent::Summary(msg) => format!("{ts} summary: {msg}"),
+    }
+}
+
+fn is_failure(state: &str) -> bool {
+    matches!(
+        state,
+        "failed" | "unhealthy" | "oom-killed" | "crash-looped"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script() -> Vec<ProgressEvent> {
+        vec![
+            ProgressEvent::Step("runtime: local".into()),
+            ProgressEvent::Step("wave 1/2: db".into()),
+            ProgressEvent::State {
+                service: "db".into(),
+                state: "starting".into(),
+            },
+            ProgressEvent::State {
+                service: "db".into(),
+                state: "running".into(),
+            },
+            ProgressEvent::Step("wave 2/2: api".into()),
+            ProgressEvent::State {
+                service: "api".into(),
+                state: "starting".into(),
+            },
+            ProgressEvent::State {
+                service: "api".into(),
+                state: "unhealthy".into(),
+            },
+            ProgressEvent::Failed("api: health check failed after 3 retries".into()),
+            ProgressEvent::Summary("2 services started".into()),
+        ]
+    }
+
+    /// Drop SGR colour sequences — other tests may force colour on globally.
+    fn strip_colour(s: &str) -> String {
+        let mut out = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|c| *c == 'm');
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    fn render_all(style: ProgressStyle) -> Vec<String> {
+        script()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| render(style, e, &format!("12:00:{i:02}")))
+            .collect()
+    }
+
+    #[test]
+    fn test_plain_snapshot() {
+        assert_eq!(
+            render_all(ProgressStyle::Plain).join("\n"),
+            "\
+12:00:00 step: runtime: local
+12:00:01 step: wave 1/2: db
+12:00:02 db: starting
+12:00:03 db: running
+12:00:04 step: wave 2/2: api
+12:00:05 api: starting
+12:00:06 api: unhealthy
+12:00:07 failed: api: health check failed after 3 retries
+12:00:08 summary: 2 services started"
+        );
+    }
+
+    #[test]
+    fn test_plain_has_no_escape_sequences() {
+        for line in render_all(ProgressStyle::Plain) {
+            assert!(!line.contains('\x1b') && !line.contains('\r'), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn test_quiet_shows_only_failures_and_summary() {
+        assert_eq!(
+            strip_colour(&render_all(ProgressStyle::Quiet).join("\n")),
+            "✗ api unhealthy\n✗ api: health check failed after 3 retries\n\n✓ 2 services started"
+        );
+    }
+
+    #[test]
+    fn test_fancy_skips_state_changes() {
+        let out = strip_colour(&render_all(ProgressStyle::Fancy).join("\n"));
+        assert!(out.starts_with("→ runtime: local\n→ wave 1/2: db\n→ wave 2/2: api"));
+        assert!(!out.contains("starting"));
+    }
+
+    #[test]
+    fn test_resolve_auto_detection() {
+        use ProgressStyle::*;
+        assert_eq!(resolve(None, true, "xterm-256color"), Fancy);
+        assert_eq!(resolve(None, false, "xterm-256color"), Plain);
+        assert_eq!(resolve(None, true, "dumb"), Plain);
+        assert_eq!(resolve(Some(Fancy), false, "xterm"), Plain);
+        assert_eq!(resolve(Some(Plain), true, "xterm"), Plain);
+        assert_eq!(resolve(Some(Quiet), false, "dumb"), Quiet);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -12,12 +12,15 @@ use hyper_util::rt::TokioExecutor;
 use tokio::sync::RwLock;
 
 type Routes = Arc<RwLock<HashMap<String, u16>>>;
+/// Subdomains whose service is spawned but hasn't passed a health check yet.
+type Starting = Arc<RwLock<HashSet<String>>>;
 type HttpClient = Client<hyper_util::client::legacy::connect::HttpConnector, Full<Bytes>>;
 
 /// Minimal reverse proxy: binds `proxy_port`, routes `<subdomain>.localhost` -> `127.0.0.1:<port>`.
 pub struct ProxyRouter {
     port: u16,
     routes: Routes,
+    starting: Starting,
     https: bool,
     tls_config: Option<Arc<tokio_rustls::rustls::ServerConfig>>,
 }
@@ -27,30 +30,17 @@ impl ProxyRouter {
         Self {
             port,
             routes: Arc::new(RwLock::new(HashMap::new())),
+            starting: Arc::new(RwLock::new(HashSet::new())),
             https: false,
             tls_config: None,
         }
     }
 
-    pub fn with_https(
-        mut self,
-        cert_pem: Vec<u8>,
-        key_pem: Vec<u8>,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        use tokio_rustls::rustls;
-
-        let certs = rustls_pemfile::certs(&mut &cert_pem[..]).collect::<Result<Vec<_>, _>>()?;
-        let key = rustls_pemfile::private_key(&mut &key_pem[..])?.ok_or("no private key found")?;
-
-        let mut config = rustls::ServerConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(certs, key)?;
-
-        config.alpn_protocols = vec![b"http/1.1".to_vec()];
-
+    /// Serve HTTPS with `config` (see [`crate::cert::server_config`]).
+    pub fn with_tls(mut self, config: tokio_rustls::rustls::ServerConfig) -> Self {
         self.https = true;
         self.tls_config = Some(Arc::new(config));
-        Ok(self)
+        self
     }
 
     /// Register or update a route (used when port is resolved at startup).
@@ -58,9 +48,26 @@ impl ProxyRouter {
         self.routes.write().await.insert(subdomain, port);
     }
 
+    /// Answer requests for `subdomain` with a 503 "service starting" page while
+    /// `starting`, instead of forwarding to a port that may still refuse connections.
+    pub async fn set_starting(&self, subdomain: &str, starting: bool) {
+        let mut set = self.starting.write().await;
+        if starting {
+            set.insert(subdomain.to_string());
+        } else {
+            set.remove(subdomain);
+        }
+    }
+
+    #[cfg(test)]
+    pub async fn is_starting(&self, subdomain: &str) -> bool {
+        self.starting.read().await.contains(subdomain)
+    }
+
     pub async fn run(self: Arc<Self>) {
         let addr: SocketAddr = ([127, 0, 0, 1], self.port).into();
         let routes = self.routes.clone();
+        let starting = self.starting.clone();
 
         let listener = match tokio::net::TcpListener::bind(addr).await {
             Ok(l) => l,
@@ -88,6 +95,7 @@ impl ProxyRouter {
                 };
 
                 let routes = routes.clone();
+                let starting = starting.clone();
                 let client = client.clone();
                 let tls_acceptor = tls_acceptor.clone();
 
@@ -102,7 +110,7 @@ impl ProxyRouter {
 
                     let io = hyper_util::rt::TokioIo::new(tls_stream);
                     let svc = hyper::service::service_fn(move |req| {
-                        handle(req, routes.clone(), client.clone())
+                        handle(req, routes.clone(), starting.clone(), client.clone())
                     });
                     if let Err(e) = hyper::server::conn::http1::Builder::new()
                         .serve_connection(io, svc)
@@ -123,11 +131,12 @@ impl ProxyRouter {
                 };
 
                 let routes = routes.clone();
+                let starting = starting.clone();
                 let client = client.clone();
                 tokio::spawn(async move {
                     let io = hyper_util::rt::TokioIo::new(stream);
                     let svc = hyper::service::service_fn(move |req| {
-                        handle(req, routes.clone(), client.clone())
+                        handle(req, routes.clone(), starting.clone(), client.clone())
                     });
                     if let Err(e) = hyper::server::conn::http1::Builder::new()
                         .serve_connection(io, svc)
@@ -144,6 +153,7 @@ impl ProxyRouter {
 async fn handle(
     req: Request<Incoming>,
     routes: Routes,
+    starting: Starting,
     client: HttpClient,
 ) -> Result<Response<Full<Bytes>>, Infallible> {
     // Extract subdomain from Host header (e.g. "power.localhost:7080" -> "power")
@@ -170,6 +180,10 @@ async fn handle(
             .unwrap_or_default());
     };
 
+    if starting.read().await.contains(subdomain) {
+        return Ok(starting_response(subdomain));
+    }
+
     let uri = format!(
         "http://127.0.0.1:{}{}",
         port,
@@ -225,6 +239,24 @@ async fn handle(
     }
 }
 
+/// Seconds a browser is asked to wait before retrying a starting service.
+const STARTING_RETRY_AFTER_SECS: u32 = 2;
+
+/// 503 page for a service that is up but not yet healthy; it reloads itself.
+fn starting_response(subdomain: &str) -> Response<Full<Bytes>> {
+    let body = format!(
+        "<!doctype html><meta http-equiv=\"refresh\" content=\"{STARTING_RETRY_AFTER_SECS}\">\
+         <title>{subdomain} is starting</title>\
+         <p>{subdomain}.localhost is starting — waiting for its health check to pass.</p>"
+    );
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(http::header::RETRY_AFTER, STARTING_RETRY_AFTER_SECS)
+        .header(http::header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Full::new(Bytes::from(body)))
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -266,4 +298,25 @@ mod tests {
         assert_eq!(routes.get("beta").copied(), Some(2000));
         assert_eq!(routes.get("gamma").copied(), Some(3000));
     }
+
+    #[tokio::test]
+    async fn test_set_starting_gates_until_cleared() {
+        let router = ProxyRouter::new(0);
+        router.update("api".into(), 4000).await;
+        assert!(!router.is_starting("api").await);
+        router.set_starting("api", true).await;
+        assert!(router.is_starting("api").await);
+        assert!(!router.is_starting("web").await);
+        router.set_starting("api", false).await;
+        assert!(!router.is_starting("api").await);
+    }
+
+    #[tokio::test]
+    async fn test_starting_response_is_503_with_retry_after() {
+        let resp = starting_response("api");
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers()[http::header::RETRY_AFTER], "2");
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body).contains("api.localhost is starting"));
+    }
 }
@@ -0,0 +1,186 @@
+//! `env_secret` values, resolved when a service starts.
+//!
+//! Each value is either a shell command whose trimmed stdout is the secret, or
+//! `keychain:<item>` to read the OS keychain (`security` on macOS, `secret-tool`
+//! elsewhere). Secrets only ever live in the service's environment: they are not
+//! cached or written anywhere, and log lines that contain one are masked.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use tokio::process::Command;
+
+use crate::config::ServiceDef;
+use crate::error::{DevError, Result};
+use crate::exec;
+
+/// What a secret is replaced with in log lines.
+pub const MASK: &str = "*****";
+
+/// Shorter secrets are not masked, so a one-character value doesn't blank out
+/// every occurrence of that character.
+const MIN_MASKED_LEN: usize = 4;
+
+/// Resolve every `env_secret` of `service`, running commands in its `dir` (or
+/// `config_dir`). The first secret that can't be resolved fails with its name.
+pub async fn resolve(
+    service: &str,
+    svc: &ServiceDef,
+    config_dir: &Path,
+) -> Result<HashMap<String, String>> {
+    let mut keys: Vec<&String> = svc.env_secret.keys().collect();
+    keys.sort();
+    let dir = svc.dir.as_deref().unwrap_or(config_dir);
+    let mut out = HashMap::new();
+    for key in keys {
+        let source = &svc.env_secret[key];
+        let value = resolve_one(source, dir, svc.hook_timeout)
+            .await
+            .map_err(|msg| DevError::Process {
+                service: service.to_string(),
+                msg: format!("secret {key}: {msg}"),
+            })?;
+        out.insert(key.clone(), value);
+    }
+    Ok(out)
+}
+
+async fn resolve_one(
+    source: &str,
+    dir: &Path,
+    timeout: std::time::Duration,
+) -> std::result::Result<String, String> {
+    let (mut cmd, what) = match source.strip_prefix("keychain:") {
+        Some(item) => (keychain_command(item), format!("keychain item '{item}'")),
+        None => {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(source).current_dir(dir);
+            (cmd, format!("`{source}`"))
+        }
+    };
+    cmd.stdin(std::process::Stdio::null()).kill_on_drop(true);
+    let output = match tokio::time::timeout(timeout, exec::output(&mut cmd)).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Err(format!("{what} could not run: {e}")),
+        Err(_) => return Err(format!("{what} timed out after {}s", timeout.as_secs_f32())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        return Err(format!(
+            "{what} failed ({}){}",
+            output.status,
+            if detail.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", detail.trim())
+            }
+        ));
+    }
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if value.is_empty() {
+        return Err(format!("{what} printed nothing"));
+    }
+    Ok(value)
+}
+
+/// The command that prints keychain item `item`'s password.
+fn keychain_command(item: &str) -> Command {
+    let mut cmd;
+    if cfg!(target_os = "macos") {
+        cmd = Command::new("security");
+        cmd.args(["find-generic-password", "-w", "-s", item]);
+    } else {
+        cmd = Command::new("secret-tool");
+        cmd.args(["lookup", "service", item]);
+    }
+    cmd
+}
+
+/// The secret values worth masking, longest first so a secret containing another
+/// is masked whole.
+pub fn mask_values(secrets: &HashMap<String, String>) -> Vec<String> {
+    let mut values: Vec<String> = secrets
+        .values()
+        .filter(|v| v.chars().count() >= MIN_MASKED_LEN)
+        .cloned()
+        .collect();
+    values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+    values.dedup();
+    values
+}
+
+/// `line` with every occurrence of `values` replaced by [`MASK`].
+pub fn mask(line: String, values: &[String]) -> String {
+    if !values.iter().any(|v| line.contains(v.as_str())) {
+        return line;
+    }
+    values
+        .iter()
+        .fold(line, |line, v| line.replace(v.as_str(), MASK))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn svc(secrets: &[(&str, &str)]) -> ServiceDef {
+        let mut svc: ServiceDef = hcl::from_str("cmd = \"true\"").unwrap();
+        svc.env_secret = secrets
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        svc
+    }
+
+    #[tokio::test]
+    async fn test_resolve_trims_command_output() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("key.txt"), "sk_test_123\n\n").unwrap();
+        let svc = svc(&[
+            ("STRIPE_KEY", "cat key.txt"),
+            ("TOKEN", "printf ' abc-def '"),
+        ]);
+        let got = resolve("api", &svc, dir.path()).await.unwrap();
+        assert_eq!(got["STRIPE_KEY"], "sk_test_123");
+        assert_eq!(got["TOKEN"], "abc-def");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_failure_names_service_and_secret() {
+        let dir = tempfile::tempdir().unwrap();
+        let svc = svc(&[("STRIPE_KEY", "echo 'not signed in' >&2; exit 3")]);
+        let err = resolve("api", &svc, dir.path()).await.unwrap_err();
+        let msg = err.to_string();
+        assert!(matches!(err, DevError::Process { ref service, .. } if service == "api"));
+        assert!(msg.contains("secret STRIPE_KEY"), "{msg}");
+        assert!(msg.contains("not signed in"), "{msg}");
+
+        let svc = self::svc(&[("EMPTY", "true")]);
+        let msg = resolve("api", &svc, dir.path())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            msg.contains("secret EMPTY: `true` printed nothing"),
+            "{msg}"
+        );
+    }
+
+    #[test]
+    fn test_mask_replaces_secret_values() {
+        let secrets: HashMap<String, String> = [
+            ("A".to_string(), "sk_live_abcdef".to_string()),
+            ("B".to_string(), "sk_live_abc".to_string()),
+            ("C".to_string(), "x".to_string()),
+        ]
+        .into();
+        let values = mask_values(&secrets);
+        assert_eq!(values, ["sk_live_abcdef", "sk_live_abc"]);
+        assert_eq!(
+            mask("key=sk_live_abcdef other=sk_live_abc x".into(), &values),
+            "key=***** other=***** x"
+        );
+        assert_eq!(mask("nothing here".into(), &values), "nothing here");
+    }
+}
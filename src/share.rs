@@ -0,0 +1,426 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::process::{Child, Command};
+use tokio::sync::{oneshot, RwLock};
+
+use crate::config::ShareConfig;
+use crate::error::{DevError, Result};
+use crate::log::LogAggregator;
+
+/// Delay before re-opening a tunnel whose process dropped unexpectedly.
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// A backend capable of exposing a local port on a remote, shareable address.
+/// SSH reverse tunnels are the only built-in provider; the trait leaves room for others.
+pub trait TunnelProvider: Send + Sync {
+    /// Spawn the tunnel process forwarding `remote_port` on the far side to `local_port`.
+    fn open(&self, local_port: u16, remote_port: u16) -> Result<Child>;
+    /// URL a teammate can use to reach the tunnel on `remote_port`.
+    fn public_url(&self, remote_port: u16) -> String;
+}
+
+/// `ssh -R` reverse tunnel through a configured jump host.
+pub struct SshProvider {
+    host: String,
+    user: Option<String>,
+}
+
+impl SshProvider {
+    pub fn new(host: String, user: Option<String>) -> Self {
+        Self { host, user }
+    }
+
+    fn args(&self, local_port: u16, remote_port: u16) -> Vec<String> {
+        let target = match &self.user {
+            Some(u) => format!("{u}@{}", self.host),
+            None => self.host.clone(),
+        };
+        vec![
+            "-N".into(),
+            "-o".into(),
+            "ExitOnForwardFailure=yes".into(),
+            "-o".into(),
+            "ServerAliveInterval=30".into(),
+            "-o".into(),
+            "BatchMode=yes".into(),
+            "-R".into(),
+            format!("{remote_port}:127.0.0.1:{local_port}"),
+            target,
+        ]
+    }
+}
+
+impl TunnelProvider for SshProvider {
+    fn open(&self, local_port: u16, remote_port: u16) -> Result<Child> {
+        Command::new("ssh")
+            .args(self.args(local_port, remote_port))
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| DevError::Config(format!("failed to start ssh tunnel: {e}")))
+    }
+
+    fn public_url(&self, remote_port: u16) -> String {
+        format!("http://{}:{remote_port}", self.host)
+    }
+}
+
+/// Build the provider selected by a `dev { share { ... } }` block.
+pub fn provider_for(cfg: &ShareConfig) -> Result<Arc<dyn TunnelProvider>> {
+    match cfg.provider.as_str() {
+        "ssh" => Ok(Arc::new(SshProvider::new(
+            cfg.host.clone(),
+            cfg.user.clone(),
+        ))),
+        other => Err(DevError::Config(format!(
+            "unknown share provider '{other}' (supported: ssh)"
+        ))),
+    }
+}
+
+/// Public view of an active share.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ShareInfo {
+    pub service: String,
+    pub url: String,
+    pub remote_port: u16,
+    /// Seconds until the share is torn down; `None` = no TTL.
+    pub expires_in_secs: Option<u64>,
+}
+
+struct ShareHandle {
+    /// Distinguishes successive shares of the same service so a finished
+    /// supervision task never removes a newer entry.
+    id: u64,
+    remote_port: u16,
+    url: String,
+    expires_at: Option<Instant>,
+    stop_tx: oneshot::Sender<()>,
+}
+
+impl ShareHandle {
+    fn info(&self, service: &str) -> ShareInfo {
+        ShareInfo {
+            service: service.to_string(),
+            url: self.url.clone(),
+            remote_port: self.remote_port,
+            expires_in_secs: self
+                .expires_at
+                .map(|t| t.saturating_duration_since(Instant::now()).as_secs()),
+        }
+    }
+}
+
+/// Tracks active shares and supervises their tunnel processes.
+///
+/// Each tunnel is restarted if it drops, its output goes to the log bus under
+/// `<service>/share`, and it is torn down on TTL expiry or an explicit `stop`.
+pub struct ShareManager {
+    shares: Arc<RwLock<HashMap<String, ShareHandle>>>,
+    log: Arc<LogAggregator>,
+    next_id: AtomicU64,
+}
+
+impl ShareManager {
+    pub fn new(log: Arc<LogAggregator>) -> Self {
+        Self {
+            shares: Arc::new(RwLock::new(HashMap::new())),
+            log,
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Open a tunnel for `service` listening on `local_port`.
+    /// The remote port is the first one in `port_range` not used by another share.
+    pub async fn start(
+        &self,
+        service: &str,
+        local_port: u16,
+        provider: Arc<dyn TunnelProvider>,
+        port_range: [u16; 2],
+        ttl: Option<Duration>,
+    ) -> Result<ShareInfo> {
+        let mut map = self.shares.write().await;
+        if let Some(existing) = map.get(service) {
+            return Err(DevError::Config(format!(
+                "'{service}' is already shared at {} — run `a3s share {service} --stop` first",
+                existing.url
+            )));
+        }
+        let [lo, hi] = port_range;
+        let remote_port = (lo..=hi)
+            .find(|p| !map.values().any(|h| h.remote_port == *p))
+            .ok_or_else(|| {
+                DevError::Config(format!("no free share port left in range {lo}-{hi}"))
+            })?;
+
+        let child = provider.open(local_port, remote_port)?;
+        let url = provider.public_url(remote_port);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let expires_at = ttl.map(|t| Instant::now() + t);
+        let (stop_tx, stop_rx) = oneshot::channel();
+
+        let handle = ShareHandle {
+            id,
+            remote_port,
+            url,
+            expires_at,
+            stop_tx,
+        };
+        let info = handle.info(service);
+        map.insert(service.to_string(), handle);
+        drop(map);
+
+        tracing::info!("[{service}] shared at {}", info.url);
+        self.supervise(
+            service.to_string(),
+            id,
+            child,
+            provider,
+            local_port,
+            remote_port,
+            expires_at,
+            stop_rx,
+        );
+        Ok(info)
+    }
+
+    /// Tear down the share for `service`. Returns false if it was not shared.
+    pub async fn stop(&self, service: &str) -> bool {
+        match self.shares.write().await.remove(service) {
+            Some(h) => {
+                let _ = h.stop_tx.send(());
+                tracing::info!("[{service}] share stopped");
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn list(&self) -> Vec<ShareInfo> {
+        let map = self.shares.read().await;
+        let mut list: Vec<ShareInfo> = map.iter().map(|(n, h)| h.info(n)).collect();
+        list.sort_by(|a, b| a.service.cmp(&b.service));
+        list
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn supervise(
+        &self,
+        service: String,
+        id: u64,
+        mut child: Child,
+        provider: Arc<dyn TunnelProvider>,
+        local_port: u16,
+        remote_port: u16,
+        expires_at: Option<Instant>,
+        mut stop_rx: oneshot::Receiver<()>,
+    ) {
+        let shares = self.shares.clone();
+        let log = self.log.clone();
+        let log_name = format!("{service}/share");
+
+        tokio::spawn(async move {
+            loop {
+                if let Some(stdout) = child.stdout.take() {
+                    log.attach(log_name.clone(), 0, stdout);
+                }
+                if let Some(stderr) = child.stderr.take() {
+                    log.attach_stderr(log_name.clone(), 0, stderr);
+                }
+
+                let expiry = async {
+                    match expires_at {
+                        Some(t) => tokio::time::sleep_until(t.into()).await,
+                        None => std::future::pending().await,
+                    }
+                };
+
+                tokio::select! {
+                    _ = &mut stop_rx => {
+                        let _ = child.kill().await;
+                        return;
+                    }
+                    _ = expiry => {
+                        let _ = child.kill().await;
+                        tracing::info!("[{service}] share expired");
+                        break;
+                    }
+                    status = child.wait() => {
+                        tracing::warn!(
+                            "[{service}] share tunnel exited ({}) — reopening in {}s",
+                            status.map(|s| s.to_string()).unwrap_or_else(|e| e.to_string()),
+                            RESTART_DELAY.as_secs()
+                        );
+                        tokio::select! {
+                            _ = &mut stop_rx => return,
+                            _ = tokio::time::sleep(RESTART_DELAY) => {}
+                        }
+                        match provider.open(local_port, remote_port) {
+                            Ok(c) => child = c,
+                            Err(e) => {
+                                tracing::error!("[{service}] share tunnel reopen failed: {e}");
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Expired or failed — drop the entry unless a newer share replaced it.
+            let mut map = shares.write().await;
+            if map.get(&service).is_some_and(|h| h.id == id) {
+                map.remove(&service);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Spawns `sh -c <script>` instead of a real tunnel and counts how often it was opened.
+    struct MockProvider {
+        script: &'static str,
+        opens: Arc<AtomicUsize>,
+    }
+
+    impl TunnelProvider for MockProvider {
+        fn open(&self, _local_port: u16, _remote_port: u16) -> Result<Child> {
+            self.opens.fetch_add(1, Ordering::SeqCst);
+            Command::new("sh")
+                .arg("-c")
+                .arg(self.script)
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(DevError::Io)
+        }
+
+        fn public_url(&self, remote_port: u16) -> String {
+            format!("http://share.test:{remote_port}")
+        }
+    }
+
+    fn manager() -> ShareManager {
+        let (log, _rx) = LogAggregator::new();
+        ShareManager::new(Arc::new(log))
+    }
+
+    async fn is_shared(mgr: &ShareManager, service: &str) -> bool {
+        mgr.list().await.iter().any(|s| s.service == service)
+    }
+
+    fn mock(script: &'static str) -> (Arc<dyn TunnelProvider>, Arc<AtomicUsize>) {
+        let opens = Arc::new(AtomicUsize::new(0));
+        let provider = Arc::new(MockProvider {
+            script,
+            opens: opens.clone(),
+        });
+        (provider, opens)
+    }
+
+    #[test]
+    fn test_ssh_args() {
+        let p = SshProvider::new("jump.example.com".into(), Some("dev".into()));
+        let args = p.args(3000, 20001);
+        assert!(args.contains(&"20001:127.0.0.1:3000".to_string()));
+        assert_eq!(args.last().unwrap(), "dev@jump.example.com");
+        assert_eq!(p.public_url(20001), "http://jump.example.com:20001");
+    }
+
+    #[test]
+    fn test_provider_for_unknown_errors() {
+        let cfg = ShareConfig {
+            provider: "carrier-pigeon".into(),
+            host: "h".into(),
+            user: None,
+            port_range: [20000, 20010],
+        };
+        assert!(provider_for(&cfg).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_share_lifecycle() {
+        let mgr = manager();
+        let (provider, opens) = mock("sleep 60");
+        let info = mgr
+            .start("web", 3000, provider.clone(), [20000, 20010], None)
+            .await
+            .unwrap();
+        assert_eq!(info.remote_port, 20000);
+        assert_eq!(info.url, "http://share.test:20000");
+        assert_eq!(mgr.list().await.len(), 1);
+        assert_eq!(opens.load(Ordering::SeqCst), 1);
+
+        // Sharing the same service twice is an error.
+        assert!(mgr
+            .start("web", 3000, provider.clone(), [20000, 20010], None)
+            .await
+            .is_err());
+
+        // A second service gets the next free remote port.
+        let api = mgr
+            .start("api", 4000, provider, [20000, 20010], None)
+            .await
+            .unwrap();
+        assert_eq!(api.remote_port, 20001);
+
+        assert!(mgr.stop("web").await);
+        assert!(!is_shared(&mgr, "web").await);
+        assert!(!mgr.stop("web").await);
+        assert!(mgr.stop("api").await);
+    }
+
+    #[tokio::test]
+    async fn test_share_port_range_exhausted() {
+        let mgr = manager();
+        let (provider, _) = mock("sleep 60");
+        mgr.start("a", 3000, provider.clone(), [20000, 20000], None)
+            .await
+            .unwrap();
+        assert!(mgr
+            .start("b", 3001, provider, [20000, 20000], None)
+            .await
+            .is_err());
+        mgr.stop("a").await;
+    }
+
+    #[tokio::test]
+    async fn test_share_expires_after_ttl() {
+        let mgr = manager();
+        let (provider, _) = mock("sleep 60");
+        let info = mgr
+            .start(
+                "web",
+                3000,
+                provider,
+                [20000, 20010],
+                Some(Duration::from_millis(200)),
+            )
+            .await
+            .unwrap();
+        assert!(info.expires_in_secs.is_some());
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert!(!is_shared(&mgr, "web").await, "share should have expired");
+    }
+
+    #[tokio::test]
+    async fn test_share_reopened_when_tunnel_drops() {
+        let mgr = manager();
+        let (provider, opens) = mock("exit 1");
+        mgr.start("web", 3000, provider, [20000, 20010], None)
+            .await
+            .unwrap();
+        tokio::time::sleep(RESTART_DELAY + Duration::from_millis(500)).await;
+        assert!(opens.load(Ordering::SeqCst) >= 2, "tunnel was not reopened");
+        assert!(is_shared(&mgr, "web").await);
+        mgr.stop("web").await;
+    }
+}
@@ -14,12 +14,20 @@ pub enum ServiceState {
     Unhealthy {
         pid: u32,
         failures: u32,
+        /// When the process started, carried over from `Running`.
+        since: Instant,
     },
     Stopped,
     #[allow(dead_code)]
     Failed {
         exit_code: Option<i32>,
     },
+    /// Killed by the kernel for exceeding its `limits.memory`.
+    OomKilled,
+    /// Kept exiting right after starting; no longer restarted until asked to.
+    CrashLooped {
+        exit_code: Option<i32>,
+    },
 }
 
 impl ServiceState {
@@ -30,6 +38,31 @@ impl ServiceState {
         }
     }
 
+    /// When the current process started, if there is one.
+    pub fn since(&self) -> Option<Instant> {
+        match self {
+            ServiceState::Running { since, .. } | ServiceState::Unhealthy { since, .. } => {
+                Some(*since)
+            }
+            _ => None,
+        }
+    }
+
+    /// Exit code of the last process, for services that failed.
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            ServiceState::Failed { exit_code } | ServiceState::CrashLooped { exit_code } => {
+                *exit_code
+            }
+            _ => None,
+        }
+    }
+
+    /// Whole seconds the current process has run; 0 without one.
+    pub fn uptime_secs(&self) -> u64 {
+        self.since().map_or(0, |s| s.elapsed().as_secs())
+    }
+
     pub fn label(&self) -> &'static str {
         match self {
             ServiceState::Pending => "pending",
@@ -38,6 +71,8 @@ impl ServiceState {
             ServiceState::Unhealthy { .. } => "unhealthy",
             ServiceState::Stopped => "stopped",
             ServiceState::Failed { .. } => "failed",
+            ServiceState::OomKilled => "oom-killed",
+            ServiceState::CrashLooped { .. } => "crash-looped",
         }
     }
 }
@@ -61,7 +96,8 @@ mod tests {
         assert_eq!(
             ServiceState::Unhealthy {
                 pid: 1,
-                failures: 2
+                failures: 2,
+                since: Instant::now()
             }
             .label(),
             "unhealthy"
@@ -72,6 +108,11 @@ mod tests {
             ServiceState::Failed { exit_code: Some(1) }.label(),
             "failed"
         );
+        assert_eq!(ServiceState::OomKilled.label(), "oom-killed");
+        assert_eq!(
+            ServiceState::CrashLooped { exit_code: Some(1) }.label(),
+            "crash-looped"
+        );
     }
 
     #[test]
@@ -83,11 +124,26 @@ mod tests {
         assert_eq!(s.pid(), Some(42));
     }
 
+    #[test]
+    fn test_uptime_runs_through_unhealthy_and_is_zero_without_process() {
+        let since = Instant::now() - std::time::Duration::from_secs(90);
+        assert_eq!(ServiceState::Running { pid: 1, since }.uptime_secs(), 90);
+        let unhealthy = ServiceState::Unhealthy {
+            pid: 1,
+            failures: 3,
+            since,
+        };
+        assert_eq!(unhealthy.uptime_secs(), 90);
+        assert_eq!(ServiceState::Stopped.uptime_secs(), 0);
+        assert_eq!(ServiceState::Failed { exit_code: Some(1) }.uptime_secs(), 0);
+    }
+
     #[test]
     fn test_pid_unhealthy() {
         let s = ServiceState::Unhealthy {
             pid: 99,
             failures: 3,
+            since: Instant::now(),
         };
         assert_eq!(s.pid(), Some(99));
     }
@@ -98,5 +154,6 @@ mod tests {
         assert_eq!(ServiceState::Starting.pid(), None);
         assert_eq!(ServiceState::Stopped.pid(), None);
         assert_eq!(ServiceState::Failed { exit_code: Some(1) }.pid(), None);
+        assert_eq!(ServiceState::OomKilled.pid(), None);
     }
 }
@@ -0,0 +1,78 @@
+//! Waiting on `depends_on` edges during startup.
+//!
+//! Every service being started gets a [`Started`] signal that flips once its startup
+//! is over. A dependent waits on the signals of its dependencies, each edge bounded by
+//! the dependent's `depends_on_timeout`; when an edge fails the dependent is blocked
+//! and reports the edge, while the rest of the graph keeps starting.
+
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// `None` while a service is starting, then whether it came up.
+pub type Started = watch::Sender<Option<bool>>;
+
+/// A new signal, already set for a service that is up before startup begins.
+pub fn started(up: bool) -> (Started, watch::Receiver<Option<bool>>) {
+    watch::channel(up.then_some(true))
+}
+
+/// Wait until every dependency is up. `timeout` bounds each edge from the moment we
+/// start waiting; zero waits forever. The error names the edge that failed.
+pub async fn wait_for(
+    deps: Vec<(String, watch::Receiver<Option<bool>>)>,
+    timeout: Duration,
+) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    for (dep, mut rx) in deps {
+        let up = async { rx.wait_for(Option::is_some).await.map(|s| *s == Some(true)) };
+        let up = if timeout.is_zero() {
+            up.await
+        } else {
+            match tokio::time::timeout_at(deadline, up).await {
+                Ok(up) => up,
+                Err(_) => return Err(format!("{dep} not ready after {timeout:?}")),
+            }
+        };
+        if !up.unwrap_or(false) {
+            return Err(format!("{dep} did not start"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for_names_the_edge_that_failed() {
+        let (_db, db_rx) = started(true);
+        let (_cache, cache_rx) = started(false);
+        let deps = vec![("db".to_string(), db_rx), ("cache".to_string(), cache_rx)];
+        assert_eq!(
+            wait_for(deps, Duration::from_millis(50)).await,
+            Err("cache not ready after 50ms".to_string())
+        );
+
+        let (queue, queue_rx) = started(false);
+        queue.send(Some(false)).unwrap();
+        assert_eq!(
+            wait_for(vec![("queue".into(), queue_rx)], Duration::ZERO).await,
+            Err("queue did not start".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_without_timeout_waits_for_slow_dependency() {
+        let (db, db_rx) = started(false);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            db.send(Some(true)).unwrap();
+        });
+        assert_eq!(
+            wait_for(vec![("db".into(), db_rx)], Duration::ZERO).await,
+            Ok(())
+        );
+    }
+}
@@ -1,10 +1,12 @@
+use std::net::Ipv4Addr;
 use std::sync::Arc;
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::UnixListener;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, UnixListener};
 use tokio::sync::broadcast;
 
 use crate::ipc::{socket_path, IpcRequest, IpcResponse};
+use crate::log::{LineFilter, LogLine};
 use crate::supervisor::Supervisor;
 
 /// Serialize `resp` to a newline-terminated JSON byte vec.
@@ -18,7 +20,38 @@ fn encode(resp: &IpcResponse) -> Vec<u8> {
     s.into_bytes()
 }
 
-/// Start the Unix socket IPC server. Handles status/stop/restart/logs/history requests.
+/// The `LogLine` response for a captured line, with its capture time and stream.
+fn log_line_response(entry: LogLine) -> IpcResponse {
+    IpcResponse::LogLine {
+        ts_ms: entry
+            .at
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_millis() as u64),
+        stream: Some(entry.stream.as_str().to_string()),
+        service: entry.service,
+        line: entry.line,
+        color_idx: entry.color_idx,
+    }
+}
+
+/// The filter of a `Logs`/`History` request, or the error to answer it with.
+fn line_filter(grep: Option<&str>, invert: bool) -> Result<LineFilter, IpcResponse> {
+    LineFilter::new(grep, invert).map_err(|e| IpcResponse::Error {
+        msg: format!("invalid grep pattern: {e}"),
+    })
+}
+
+/// Refuse a `Logs`/`History` request naming services the config doesn't have,
+/// listing the ones it does, rather than following nothing.
+fn known_services(sup: &Supervisor, services: &[String]) -> Result<(), IpcResponse> {
+    sup.cfg()
+        .check_service_names(services)
+        .map_err(|e| IpcResponse::Error { msg: e.to_string() })
+}
+
+/// Start the IPC server: the Unix socket, and 127.0.0.1 on `ipc_tcp_port` too when
+/// set. Both speak the same newline-delimited JSON.
 pub async fn serve(sup: Arc<Supervisor>) {
     let path = socket_path(&sup.config_path);
     let _ = std::fs::remove_file(&path);
@@ -40,6 +73,27 @@ pub async fn serve(sup: Arc<Supervisor>) {
 
     tracing::debug!("IPC socket at {}", path.display());
 
+    if let Some(port) = sup.cfg().dev.ipc_tcp_port {
+        // Loopback only, and every local user can connect: TCP clients must present
+        // the token written next to the A3sfile, readable by its owner only.
+        let token_path = crate::daemon::ipc_token_path(&sup.config_path);
+        let token = crate::ui::random_token()
+            .and_then(|t| crate::ui::save_token(&t, &token_path).map(|()| t));
+        match token {
+            Ok(token) => match TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await {
+                Ok(tcp) => {
+                    tracing::debug!("IPC on 127.0.0.1:{port}");
+                    tokio::spawn(serve_tcp(sup.clone(), tcp, token.into()));
+                }
+                Err(e) => tracing::error!("IPC TCP bind on 127.0.0.1:{port} failed: {e}"),
+            },
+            Err(e) => tracing::error!(
+                "IPC over TCP disabled: cannot write {}: {e}",
+                token_path.display()
+            ),
+        }
+    }
+
     loop {
         let (stream, _) = match listener.accept().await {
             Ok(s) => s,
@@ -48,104 +102,452 @@ pub async fn serve(sup: Arc<Supervisor>) {
                 continue;
             }
         };
+        tokio::spawn(handle_connection(sup.clone(), stream, None));
+    }
+}
 
-        let sup = sup.clone();
-        tokio::spawn(async move {
-            let (reader, mut writer) = tokio::io::split(stream);
-            let mut lines = BufReader::new(reader).lines();
-
-            while let Ok(Some(line)) = lines.next_line().await {
-                let req: IpcRequest = match serde_json::from_str(&line) {
-                    Ok(r) => r,
-                    Err(e) => {
-                        let resp = IpcResponse::Error {
-                            msg: format!("bad request: {e}"),
-                        };
-                        let _ = writer.write_all(&encode(&resp)).await;
-                        continue;
-                    }
-                };
+async fn serve_tcp(sup: Arc<Supervisor>, listener: TcpListener, token: Arc<str>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_connection(sup.clone(), stream, Some(token.clone())));
+            }
+            Err(e) => tracing::warn!("IPC accept error: {e}"),
+        }
+    }
+}
 
-                match req {
-                    IpcRequest::Status => {
-                        let rows = sup.status_rows().await;
-                        let resp = IpcResponse::Status { rows };
-                        let _ = writer.write_all(&encode(&resp)).await;
-                    }
+/// Read and answer the hello every connection opens with. False when the client
+/// sent something else, lacks the `token` the connection requires, speaks a
+/// protocol older than `MIN_SUPPORTED_VERSION` (after telling it so) or sends
+/// nothing within `idle`.
+async fn handshake<R, W>(
+    lines: &mut tokio::io::Lines<R>,
+    writer: &mut W,
+    idle: std::time::Duration,
+    token: Option<&str>,
+) -> bool
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let Some(Ok(Some(line))) = within(idle, lines.next_line()).await else {
+        return false;
+    };
+    let resp = match serde_json::from_str(&line) {
+        Ok(IpcRequest::Hello {
+            version,
+            token: presented,
+        }) => {
+            let authorized = token.is_none_or(|t| {
+                presented
+                    .as_deref()
+                    .is_some_and(|p| crate::ui::constant_time_eq(p, t))
+            });
+            if authorized {
+                crate::ipc::hello_response(version)
+            } else {
+                IpcResponse::Error {
+                    msg: "missing or invalid IPC token: TCP clients send the one in \
+                          .a3s/ipc-token next to the A3sfile (or set A3S_IPC_TOKEN)"
+                        .into(),
+                }
+            }
+        }
+        _ => IpcResponse::Error {
+            msg: r#"expected {"cmd":"hello","version":N} first"#.into(),
+        },
+    };
+    let _ = writer.write_all(&encode(&resp)).await;
+    matches!(resp, IpcResponse::Hello { .. })
+}
 
-                    IpcRequest::Stop { services } => {
-                        let stopped = if services.is_empty() {
-                            sup.stop_all().await
-                        } else {
-                            sup.stop_named(&services).await
-                        };
-                        let _ = writer
-                            .write_all(&encode(&IpcResponse::Stopped { services: stopped }))
-                            .await;
-                    }
+/// `fut`'s output, or None once `limit` has passed first. A zero `limit` waits as
+/// long as it takes.
+pub(super) async fn within<F: std::future::Future>(
+    limit: std::time::Duration,
+    fut: F,
+) -> Option<F::Output> {
+    if limit.is_zero() {
+        Some(fut.await)
+    } else {
+        tokio::time::timeout(limit, fut).await.ok()
+    }
+}
 
-                    IpcRequest::Restart { service } => {
-                        let resp = match sup.restart_service(&service).await {
-                            Ok(_) => IpcResponse::Ok,
-                            Err(e) => IpcResponse::Error { msg: e.to_string() },
-                        };
-                        let _ = writer.write_all(&encode(&resp)).await;
-                    }
+/// How long `req` may take before it is answered with "request timed out" (zero =
+/// no limit). Streams have their own `ipc_stream_timeout`. Starting, stopping,
+/// restarting and reloading services are bounded by the services' own
+/// `stop_timeout`s and health checks, which may well add up to more than
+/// `ipc_request_timeout`, so they are not cut short.
+pub(super) fn request_limit(
+    dev: &crate::config::GlobalSettings,
+    req: &IpcRequest,
+) -> std::time::Duration {
+    fn lifecycle(req: &IpcRequest) -> bool {
+        match req {
+            IpcRequest::Start { .. }
+            | IpcRequest::Stop { .. }
+            | IpcRequest::Restart { .. }
+            | IpcRequest::Reload { .. } => true,
+            IpcRequest::Batch { requests } => requests.iter().any(lifecycle),
+            _ => false,
+        }
+    }
+    match req {
+        IpcRequest::Logs { follow: true, .. } | IpcRequest::Events { .. } => dev.ipc_stream_timeout,
+        req if lifecycle(req) => std::time::Duration::ZERO,
+        _ => dev.ipc_request_timeout,
+    }
+}
 
-                    IpcRequest::Logs { services, follow } => {
-                        let mut rx = sup.subscribe_logs();
-                        loop {
-                            match rx.recv().await {
-                                Ok(entry) => {
-                                    if services.is_empty() || services.contains(&entry.service) {
-                                        let resp = IpcResponse::LogLine {
-                                            service: entry.service,
-                                            line: entry.line,
-                                            color_idx: entry.color_idx,
-                                        };
-                                        if writer.write_all(&encode(&resp)).await.is_err() {
-                                            break;
-                                        }
-                                    }
-                                }
-                                Err(broadcast::error::RecvError::Closed) => break,
-                                Err(broadcast::error::RecvError::Lagged(_)) => {}
-                            }
-                            if !follow {
-                                break;
-                            }
-                        }
-                    }
+/// Answer the requests of one client, after its hello, until it disconnects.
+/// Handles status/stop/restart/logs/history and the rest. A request that takes
+/// longer than its [`request_limit`] is answered with "request timed out" and the
+/// connection closed, as is a client that sends nothing for `ipc_request_timeout`.
+async fn handle_connection<S>(sup: Arc<Supervisor>, stream: S, token: Option<Arc<str>>)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    let idle = sup.cfg().dev.ipc_request_timeout;
+    if !handshake(&mut lines, &mut writer, idle, token.as_deref()).await {
+        return;
+    }
 
-                    IpcRequest::Reload => {
-                        let resp = match sup.reload_from_disk().await {
-                            Ok(s) => IpcResponse::Reloaded {
-                                started: s.started,
-                                stopped: s.stopped,
-                                restarted: s.restarted,
-                            },
-                            Err(e) => IpcResponse::Error { msg: e.to_string() },
-                        };
-                        let _ = writer.write_all(&encode(&resp)).await;
-                    }
+    while let Some(Ok(Some(line))) = within(idle, lines.next_line()).await {
+        let req: IpcRequest = match serde_json::from_str(&line) {
+            Ok(r) => r,
+            Err(e) => {
+                let resp = IpcResponse::Error {
+                    msg: format!("bad request: {e}"),
+                };
+                let _ = writer.write_all(&encode(&resp)).await;
+                continue;
+            }
+        };
 
-                    IpcRequest::History { services, lines } => {
-                        let recent = sup.log_history(&services, lines);
-                        for entry in recent {
-                            let resp = IpcResponse::LogLine {
-                                service: entry.service,
-                                line: entry.line,
-                                color_idx: entry.color_idx,
-                            };
+        let limit = request_limit(&sup.cfg().dev, &req);
+        let keep_open = match within(limit, answer(&sup, req, &mut writer)).await {
+            Some(keep_open) => keep_open,
+            None => {
+                let resp = IpcResponse::Error {
+                    msg: "request timed out".into(),
+                };
+                let _ = writer.write_all(&encode(&resp)).await;
+                false
+            }
+        };
+        if !keep_open {
+            break;
+        }
+    }
+}
+
+/// Answer one request on `writer`; false when the connection should close after it.
+async fn answer<W>(sup: &Arc<Supervisor>, req: IpcRequest, writer: &mut W) -> bool
+where
+    W: AsyncWrite + Unpin,
+{
+    match req {
+        IpcRequest::Batch { requests } => {
+            let resp = batch(sup, requests).await;
+            let _ = writer.write_all(&encode(&resp)).await;
+            true
+        }
+
+        IpcRequest::Logs {
+            services,
+            follow,
+            grep,
+            invert,
+        } => {
+            if let Err(resp) = known_services(sup, &services) {
+                let _ = writer.write_all(&encode(&resp)).await;
+                return false;
+            }
+            let filter = match line_filter(grep.as_deref(), invert) {
+                Ok(filter) => filter,
+                Err(resp) => {
+                    let _ = writer.write_all(&encode(&resp)).await;
+                    return false;
+                }
+            };
+            let mut rx = sup.subscribe_logs();
+            loop {
+                match rx.recv().await {
+                    Ok(entry) => {
+                        if (services.is_empty() || services.contains(&entry.service))
+                            && filter.keeps(&entry.line)
+                        {
+                            let resp = log_line_response(entry);
                             if writer.write_all(&encode(&resp)).await.is_err() {
                                 break;
                             }
                         }
-                        // Close connection after sending all history lines
-                        break;
                     }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                }
+                if !follow {
+                    break;
+                }
+            }
+            true
+        }
+
+        IpcRequest::Events { services } => {
+            let mut rx = sup.subscribe_events();
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        if !services.is_empty() && !services.contains(&event.service) {
+                            continue;
+                        }
+                        let resp = IpcResponse::Event(event);
+                        if writer.write_all(&encode(&resp)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                }
+            }
+            false
+        }
+
+        IpcRequest::History {
+            services,
+            lines,
+            since,
+            grep,
+            invert,
+        } => {
+            if let Err(resp) = known_services(sup, &services) {
+                let _ = writer.write_all(&encode(&resp)).await;
+                return false;
+            }
+            let filter = match line_filter(grep.as_deref(), invert) {
+                Ok(filter) => filter,
+                Err(resp) => {
+                    let _ = writer.write_all(&encode(&resp)).await;
+                    return false;
+                }
+            };
+            let since = match since.as_deref().map(crate::log::parse_since).transpose() {
+                Ok(since) => since,
+                Err(msg) => {
+                    let resp = IpcResponse::Error { msg };
+                    let _ = writer.write_all(&encode(&resp)).await;
+                    return false;
+                }
+            };
+            let recent = sup.log_history(&services, lines, since);
+            for entry in recent.into_iter().filter(|e| filter.keeps(&e.line)) {
+                let resp = log_line_response(entry);
+                if writer.write_all(&encode(&resp)).await.is_err() {
+                    break;
                 }
             }
-        });
+            // Close connection after sending all history lines
+            false
+        }
+
+        req => {
+            // In a task of its own, so a timeout leaves the operation to finish
+            // rather than cancelling it halfway (a restart that stopped but never
+            // started).
+            let sup = sup.clone();
+            let resp = tokio::spawn(async move { respond(&sup, req).await })
+                .await
+                .unwrap_or_else(|e| IpcResponse::Error {
+                    msg: format!("request failed: {e}"),
+                });
+            let _ = writer.write_all(&encode(&resp)).await;
+            true
+        }
+    }
+}
+
+/// Most requests one batch may hold.
+pub(super) const MAX_BATCH: usize = 64;
+
+/// Run `requests` in order and answer them in request order. Requests that change
+/// something run one at a time, each after everything before it has finished; the
+/// reads between them run concurrently.
+pub(super) async fn batch(sup: &Arc<Supervisor>, requests: Vec<IpcRequest>) -> IpcResponse {
+    if requests.len() > MAX_BATCH {
+        return IpcResponse::Error {
+            msg: format!(
+                "batch of {} requests exceeds the limit of {MAX_BATCH}",
+                requests.len()
+            ),
+        };
+    }
+    let mut responses = Vec::with_capacity(requests.len());
+    let mut reads = Vec::new();
+    for req in requests {
+        let read = read_only(&req);
+        if !read {
+            for read in reads.drain(..) {
+                responses.push(joined(read.await));
+            }
+        }
+        let sup = sup.clone();
+        let handle = tokio::spawn(async move { respond(&sup, req).await });
+        if read {
+            reads.push(handle);
+        } else {
+            responses.push(joined(handle.await));
+        }
+    }
+    for read in reads {
+        responses.push(joined(read.await));
+    }
+    IpcResponse::Batch { responses }
+}
+
+/// True for requests that only look — safe to run alongside each other in a batch.
+/// Streams and nested batches are refused in a batch, so they count too.
+fn read_only(req: &IpcRequest) -> bool {
+    matches!(
+        req,
+        IpcRequest::Hello { .. }
+            | IpcRequest::Status
+            | IpcRequest::HealthReport
+            | IpcRequest::Metrics
+            | IpcRequest::Logs { .. }
+            | IpcRequest::History { .. }
+            | IpcRequest::Events { .. }
+            | IpcRequest::Batch { .. }
+    )
+}
+
+/// The answer of a spawned request, or an error if its task panicked.
+fn joined(res: Result<IpcResponse, tokio::task::JoinError>) -> IpcResponse {
+    res.unwrap_or_else(|e| IpcResponse::Error {
+        msg: format!("batched request failed: {e}"),
+    })
+}
+
+/// The single response to a one-shot request. Streaming requests are answered on
+/// the connection itself, so they only get here from inside a batch.
+async fn respond(sup: &Arc<Supervisor>, req: IpcRequest) -> IpcResponse {
+    match req {
+        IpcRequest::Hello { version, .. } => crate::ipc::hello_response(version),
+
+        IpcRequest::Status => IpcResponse::Status {
+            rows: sup.status_rows().await,
+        },
+
+        IpcRequest::Stop { services } => {
+            let stopped = if services.is_empty() {
+                sup.stop_all().await
+            } else {
+                sup.stop_named(&services).await
+            };
+            IpcResponse::Stopped { services: stopped }
+        }
+
+        IpcRequest::Restart { service } => match sup.restart_service(&service).await {
+            Ok(_) => IpcResponse::Ok,
+            Err(e) => IpcResponse::Error { msg: e.to_string() },
+        },
+
+        IpcRequest::Kill { service, signal } => match sup.kill_service(&service, &signal).await {
+            Ok(()) => IpcResponse::Ok,
+            Err(e) => IpcResponse::Error { msg: e.to_string() },
+        },
+
+        IpcRequest::Start { services } => match sup.start_services(&services).await {
+            Ok(results) => IpcResponse::Started { results },
+            Err(e) => IpcResponse::Error { msg: e.to_string() },
+        },
+
+        IpcRequest::Reload { no_restart } => match sup.reload_from_disk(!no_restart).await {
+            Ok(s) => IpcResponse::Reloaded {
+                started: s.started,
+                stopped: s.stopped,
+                restarted: s.restarted,
+                not_restarted: s.not_restarted,
+            },
+            Err(e) => IpcResponse::Error { msg: e.to_string() },
+        },
+
+        IpcRequest::Share { service, ttl_secs } => {
+            let ttl = ttl_secs.map(std::time::Duration::from_secs);
+            match sup.share_service(&service, ttl).await {
+                Ok(info) => IpcResponse::Shared {
+                    service: info.service,
+                    url: info.url,
+                    expires_in_secs: info.expires_in_secs,
+                },
+                Err(e) => IpcResponse::Error { msg: e.to_string() },
+            }
+        }
+
+        IpcRequest::Unshare { service } => {
+            if sup.unshare_service(&service).await {
+                IpcResponse::Ok
+            } else {
+                IpcResponse::Error {
+                    msg: format!("service '{service}' is not shared"),
+                }
+            }
+        }
+
+        IpcRequest::KubeDeploy { path } => match sup.kube_deploy(&path).await {
+            Ok(stdout) => IpcResponse::Output { stdout },
+            Err(e) => IpcResponse::Error { msg: e.to_string() },
+        },
+
+        IpcRequest::KubeUndeploy { path } => match sup.kube_undeploy(&path).await {
+            Ok(stdout) => IpcResponse::Output { stdout },
+            Err(e) => IpcResponse::Error { msg: e.to_string() },
+        },
+
+        IpcRequest::KubeCreateNamespace { name } => match sup.kube_create_namespace(&name).await {
+            Ok(()) => IpcResponse::Ok,
+            Err(e) => IpcResponse::Error { msg: e.to_string() },
+        },
+
+        IpcRequest::KubeDeleteNamespace { name, force } => {
+            match sup.kube_delete_namespace(&name, force).await {
+                Ok(()) => IpcResponse::Ok,
+                Err(e) => IpcResponse::Error { msg: e.to_string() },
+            }
+        }
+
+        IpcRequest::KubePodExec {
+            namespace,
+            pod,
+            container,
+            command,
+        } => match sup
+            .kube_pod_exec(namespace.as_deref(), &pod, container.as_deref(), &command)
+            .await
+        {
+            Ok(stdout) => IpcResponse::Output { stdout },
+            Err(e) => IpcResponse::Error { msg: e.to_string() },
+        },
+
+        IpcRequest::Metrics => IpcResponse::Metrics {
+            entries: sup.metrics(),
+        },
+
+        IpcRequest::HealthReport => IpcResponse::HealthReport {
+            rows: sup.health_report(),
+        },
+
+        IpcRequest::Batch { .. } => IpcResponse::Error {
+            msg: "batch requests can't be nested".into(),
+        },
+
+        IpcRequest::Logs { .. } | IpcRequest::History { .. } | IpcRequest::Events { .. } => {
+            IpcResponse::Error {
+                msg: "logs, history and events stream their answers and can't be batched".into(),
+            }
+        }
     }
 }
@@ -0,0 +1,285 @@
+//! Per-service CPU and memory limits.
+//!
+//! On Linux each limited service gets its own cgroup v2 (`a3s-<service>`) next to the
+//! supervisor, so a runaway process is OOM-killed inside its own group without touching
+//! siblings. A warm standby spare lives in `a3s-<service>@standby` until it is promoted. When cgroups are unavailable (no delegation, macOS) the memory limit falls
+//! back to `RLIMIT_AS`; the CPU limit cannot be enforced in that mode.
+
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+
+use crate::config::LimitsConfig;
+
+/// Leaf cgroup the supervisor moves itself into so its parent may delegate controllers
+/// (cgroup v2 forbids enabling controllers on a group that still holds processes).
+#[cfg(target_os = "linux")]
+const SUPERVISOR_LEAF: &str = "a3s-supervisor";
+
+/// CFS period used for `cpu.max`, in microseconds.
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// A service cgroup plus the `oom_kill` count observed when the current process was spawned.
+#[derive(Debug, Clone)]
+pub struct Cgroup {
+    path: PathBuf,
+    oom_baseline: u64,
+}
+
+impl Cgroup {
+    /// True if the kernel OOM-killed a process in this group since it was set up.
+    pub fn oom_killed(&self) -> bool {
+        read_oom_kills(&self.path) > self.oom_baseline
+    }
+
+    /// Remove the (now empty) cgroup directory. Best-effort.
+    pub fn remove(&self) {
+        let _ = std::fs::remove_dir(&self.path);
+    }
+
+    /// Move this group's processes into `service`'s active group and remove this one —
+    /// used when a warm spare is promoted. Returns the active group.
+    pub fn promote(self, service: &str, limits: &LimitsConfig) -> std::io::Result<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            let target = Self::create(&group_name(service, false), limits)?;
+            // The spare may still be forking; a few passes drain the group.
+            for _ in 0..3 {
+                let procs = std::fs::read_to_string(self.path.join("cgroup.procs"))?;
+                if procs.trim().is_empty() {
+                    break;
+                }
+                for pid in procs.split_whitespace() {
+                    // A process may exit between the read and the write.
+                    let _ = std::fs::write(target.path.join("cgroup.procs"), pid);
+                }
+            }
+            self.remove();
+            Ok(target)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (service, limits);
+            Ok(self)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn create(group: &str, limits: &LimitsConfig) -> std::io::Result<Self> {
+        let own = std::fs::read_to_string("/proc/self/cgroup")?;
+        let rel = parse_own_cgroup(&own).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::Unsupported, "not on cgroup v2")
+        })?;
+        let mut parent = Path::new("/sys/fs/cgroup").join(rel.trim_start_matches('/'));
+        // Once we've moved into our leaf, services live next to it.
+        if parent.file_name().is_some_and(|n| n == SUPERVISOR_LEAF) {
+            parent.pop();
+        }
+        enable_controllers(&parent)?;
+
+        let path = parent.join(group);
+        std::fs::create_dir_all(&path)?;
+        let memory_max = limits
+            .memory
+            .map(|b| b.to_string())
+            .unwrap_or_else(|| "max".into());
+        std::fs::write(path.join("memory.max"), memory_max)?;
+        let cpu_max = limits
+            .cpu
+            .map(cpu_max)
+            .unwrap_or_else(|| format!("max {CPU_PERIOD_US}"));
+        std::fs::write(path.join("cpu.max"), cpu_max)?;
+
+        Ok(Self {
+            oom_baseline: read_oom_kills(&path),
+            path,
+        })
+    }
+}
+
+/// cgroup directory of `service`'s active instance, or of its warm spare.
+pub fn group_name(service: &str, standby: bool) -> String {
+    if standby {
+        format!("a3s-{service}@standby")
+    } else {
+        format!("a3s-{service}")
+    }
+}
+
+/// Configure `cmd` so the child runs under `limits` in the cgroup directory `group`.
+/// Returns the service cgroup when cgroups v2 could be used, `None` on rlimit fallback.
+#[cfg(unix)]
+pub fn apply(
+    cmd: &mut Command,
+    service: &str,
+    group: &str,
+    limits: &LimitsConfig,
+) -> Option<Cgroup> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        match Cgroup::create(group, limits) {
+            Ok(cg) => {
+                let procs = cg.path.join("cgroup.procs");
+                if let Ok(procs) = std::ffi::CString::new(procs.as_os_str().as_bytes()) {
+                    // SAFETY: join_cgroup only calls async-signal-safe libc functions.
+                    unsafe {
+                        cmd.pre_exec(move || join_cgroup(&procs));
+                    }
+                    return Some(cg);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("[{service}] cgroups v2 unavailable ({e}) — using rlimit fallback");
+            }
+        }
+    }
+
+    if limits.cpu.is_some() {
+        tracing::warn!("[{service}] cpu limit is not enforced without cgroups v2");
+    }
+    if let Some(bytes) = limits.memory {
+        // SAFETY: setrlimit is async-signal-safe.
+        unsafe {
+            cmd.pre_exec(move || set_memory_rlimit(bytes));
+        }
+    }
+    None
+}
+
+/// `cpu.max` value for `cores` CPUs, e.g. 1.5 → "150000 100000".
+fn cpu_max(cores: f64) -> String {
+    let quota = ((cores * CPU_PERIOD_US as f64).round() as u64).max(1000);
+    format!("{quota} {CPU_PERIOD_US}")
+}
+
+/// Extract the cgroup v2 path from `/proc/self/cgroup` (the `0::<path>` line).
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_own_cgroup(contents: &str) -> Option<&str> {
+    contents.lines().find_map(|l| l.strip_prefix("0::"))
+}
+
+/// Read the `oom_kill` counter from `memory.events`; 0 if unavailable.
+fn read_oom_kills(path: &Path) -> u64 {
+    std::fs::read_to_string(path.join("memory.events"))
+        .map(|s| parse_oom_kills(&s))
+        .unwrap_or(0)
+}
+
+fn parse_oom_kills(events: &str) -> u64 {
+    events
+        .lines()
+        .find_map(|l| l.strip_prefix("oom_kill "))
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(target_os = "linux")]
+fn enable_controllers(parent: &Path) -> std::io::Result<()> {
+    let control = parent.join("cgroup.subtree_control");
+    if std::fs::write(&control, "+memory +cpu").is_ok() {
+        return Ok(());
+    }
+    // Most likely EBUSY: our own group holds processes. Move the supervisor into a
+    // leaf and retry — this succeeds when a3s owns the group (e.g. under
+    // `systemd-run --user --scope -p Delegate=yes`).
+    let leaf = parent.join(SUPERVISOR_LEAF);
+    std::fs::create_dir_all(&leaf)?;
+    std::fs::write(leaf.join("cgroup.procs"), std::process::id().to_string())?;
+    std::fs::write(&control, "+memory +cpu")
+}
+
+/// Runs in the forked child before exec: writing "0" moves the calling process.
+#[cfg(target_os = "linux")]
+fn join_cgroup(procs: &std::ffi::CStr) -> std::io::Result<()> {
+    // SAFETY: plain syscalls on a valid NUL-terminated path and a static buffer.
+    unsafe {
+        let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let n = libc::write(fd, b"0".as_ptr().cast(), 1);
+        libc::close(fd);
+        if n < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_memory_rlimit(bytes: u64) -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: bytes as libc::rlim_t,
+        rlim_max: bytes as libc::rlim_t,
+    };
+    // SAFETY: setrlimit with a valid pointer to an initialised struct.
+    if unsafe { libc::setrlimit(libc::RLIMIT_AS, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_max() {
+        assert_eq!(cpu_max(1.5), "150000 100000");
+        assert_eq!(cpu_max(1.0), "100000 100000");
+        // Tiny values are clamped to the kernel minimum of 1ms per period.
+        assert_eq!(cpu_max(0.001), "1000 100000");
+    }
+
+    #[test]
+    fn test_spare_gets_its_own_group() {
+        assert_eq!(group_name("web", false), "a3s-web");
+        assert_eq!(group_name("web", true), "a3s-web@standby");
+    }
+
+    #[test]
+    fn test_parse_own_cgroup() {
+        let contents = "0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(
+            parse_own_cgroup(contents),
+            Some("/user.slice/user-1000.slice/session-2.scope")
+        );
+        // cgroup v1 hierarchies only
+        assert_eq!(parse_own_cgroup("12:memory:/foo\n"), None);
+    }
+
+    #[test]
+    fn test_parse_oom_kills() {
+        let events = "low 0\nhigh 0\nmax 12\noom 3\noom_kill 2\noom_group_kill 0\n";
+        assert_eq!(parse_oom_kills(events), 2);
+        assert_eq!(parse_oom_kills(""), 0);
+    }
+
+    #[test]
+    fn test_oom_killed_compares_against_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("memory.events"), "oom 1\noom_kill 1\n").unwrap();
+        let cg = Cgroup {
+            path: dir.path().to_path_buf(),
+            oom_baseline: 1,
+        };
+        assert!(!cg.oom_killed());
+        std::fs::write(dir.path().join("memory.events"), "oom 2\noom_kill 2\n").unwrap();
+        assert!(cg.oom_killed());
+    }
+
+    #[tokio::test]
+    async fn test_rlimit_fallback_caps_child_memory() {
+        // Apply just the rlimit path directly and check the child sees it.
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("ulimit -v");
+        unsafe {
+            cmd.pre_exec(|| set_memory_rlimit(512 * 1024 * 1024));
+        }
+        let out = cmd.output().await.unwrap();
+        let kb: u64 = String::from_utf8_lossy(&out.stdout).trim().parse().unwrap();
+        assert_eq!(kb, 512 * 1024);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -9,23 +9,84 @@ use crate::config::{DevConfig, ServiceDef};
 use crate::error::{DevError, Result};
 use crate::graph::DependencyGraph;
 use crate::health::HealthChecker;
-use crate::ipc::StatusRow;
+use crate::health_stats::{HealthStats, SharedHealthStats};
+use crate::ipc::{HealthReportRow, ServiceEvent, ServiceMetrics, StartResult, StatusRow};
 use crate::log::LogAggregator;
 use crate::proxy::ProxyRouter;
+use crate::share::{ShareInfo, ShareManager};
 use crate::state::ServiceState;
 use crate::watcher::spawn_watcher;
-use colored::Colorize;
 
-use spawn::{free_port, spawn_process, SpawnSpec};
+use spawn::{assign_port, port_free, spawn_process, SpawnSpec};
+pub(crate) use spawn::{framework_port_args, split_cmd};
 
+mod deps;
 pub mod ipc;
+mod limits;
+pub mod plan;
 mod spawn;
+mod standby;
+mod usage;
 
+/// A service changing state: starting, running, healthy, stopping, restarting...
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
-pub enum SupervisorEvent {
-    StateChanged { service: String, state: String },
-    HealthChange { service: String, healthy: bool },
+pub struct SupervisorEvent {
+    pub service: String,
+    pub state: String,
+    /// Why, when the state alone doesn't say: an exit code, failing checks, a file change.
+    pub reason: Option<String>,
+    pub at: std::time::SystemTime,
+}
+
+impl SupervisorEvent {
+    pub fn new(service: &str, state: &str) -> Self {
+        Self {
+            service: service.to_string(),
+            state: state.to_string(),
+            reason: None,
+            at: std::time::SystemTime::now(),
+        }
+    }
+
+    pub fn because(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// Forward each event as a [`ServiceEvent`] carrying the state the service was in
+/// before, until the supervisor goes away.
+async fn relay_transitions(
+    mut rx: broadcast::Receiver<SupervisorEvent>,
+    out: broadcast::Sender<ServiceEvent>,
+) {
+    let mut last: HashMap<String, String> = HashMap::new();
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let old_state = last.insert(event.service.clone(), event.state.clone());
+        let _ = out.send(ServiceEvent {
+            service: event.service,
+            old_state,
+            new_state: event.state,
+            ts_ms: event
+                .at
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            reason: event.reason,
+        });
+    }
+}
+
+/// How a process ended, for event reasons.
+fn exit_reason(code: Option<i32>) -> String {
+    match code {
+        Some(code) => format!("exited with code {code}"),
+        None => "killed by a signal".into(),
+    }
 }
 
 struct ServiceHandle {
@@ -37,27 +98,125 @@ struct ServiceHandle {
     watcher_stop: Option<std::sync::mpsc::SyncSender<()>>,
     /// Number of crash-recovery restarts since the service was first started.
     restart_count: u32,
+    /// cgroup enforcing `limits`, if any — used to tell OOM kills from plain crashes.
+    cgroup: Option<limits::Cgroup>,
+    /// What the health checks of this instance found so far.
+    health: HealthProgress,
+}
+
+/// Health-check outcomes of one instance, for the health column of status rows.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct HealthProgress {
+    /// A check has passed since the process started.
+    passed: bool,
+    /// Checks failed in a row.
+    failures: u32,
+    /// Unix seconds of the last passing check, kept across restarts.
+    last_ok: Option<u64>,
+    /// Why the last check failed, kept across restarts.
+    last_error: Option<String>,
+}
+
+impl HealthProgress {
+    fn record(&mut self, outcome: std::result::Result<(), String>) {
+        match outcome {
+            Ok(()) => {
+                self.passed = true;
+                self.failures = 0;
+                self.last_ok = Some(crate::health_stats::unix_now());
+            }
+            Err(reason) => {
+                self.failures += 1;
+                self.last_error = Some(reason);
+            }
+        }
+    }
+
+    /// Where a restarted instance starts: nothing checked yet, but the last pass and
+    /// the failure that likely caused the restart are still worth showing.
+    fn restarted(&self) -> HealthProgress {
+        HealthProgress {
+            last_ok: self.last_ok,
+            last_error: self.last_error.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// `healthy`, `starting` or `unhealthy (2/3 failures)`, against the `retries`
+    /// that mark the service unhealthy.
+    fn label(&self, retries: u32) -> String {
+        match (self.failures, self.passed) {
+            (0, true) => "healthy".into(),
+            (0, false) => "starting".into(),
+            (n, _) => format!("unhealthy ({n}/{} failures)", retries.max(1)),
+        }
+    }
+}
+
+/// A process that exits sooner than this after starting counts toward a crash loop.
+const FAST_EXIT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Delay before the first restart after a fast exit; doubles with each one after.
+const CRASH_LOOP_BACKOFF: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Log lines kept in the status row of a `crash-looped` service.
+const CRASH_LOOP_LOG_LINES: usize = 20;
+
+/// Quiet period for an `env_reload` env_file when the service has no `watch` block.
+const ENV_FILE_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// Delay before restarting after the `fast_exits`-th consecutive fast exit:
+/// 250ms, doubling each time, capped at `max`.
+fn crash_loop_backoff(fast_exits: u32, max: std::time::Duration) -> std::time::Duration {
+    let factor = 1u32
+        .checked_shl(fast_exits.saturating_sub(1))
+        .unwrap_or(u32::MAX);
+    CRASH_LOOP_BACKOFF.saturating_mul(factor).min(max)
 }
 
-/// Number of consecutive health check failures before transitioning to `Unhealthy`
-/// and triggering a restart via SIGTERM.
-const HEALTH_FAILURE_THRESHOLD: u32 = 3;
+/// Have the proxy answer a health-checked service's subdomain with a "starting" page
+/// until a check passes. Clearing applies whether or not a check is configured.
+async fn gate_proxy(proxy: &ProxyRouter, svc: &ServiceDef, starting: bool) {
+    if let Some(sub) = &svc.subdomain {
+        if svc.health.is_some() || !starting {
+            proxy.set_starting(sub, starting).await;
+        }
+    }
+}
 
 /// Spawn a background task that continuously monitors the health of a running service.
-/// On `HEALTH_FAILURE_THRESHOLD` consecutive failures the service is transitioned to
+/// On `retries` consecutive failures the service is transitioned to
 /// `Unhealthy` and SIGTERM'd — crash recovery picks it up and restarts.
 /// The task exits once the service leaves the Running/Unhealthy state (e.g. stopped).
+/// Every outcome is recorded in `stats` for the flakiness report, and the first pass
+/// opens the proxy route of a restarted instance.
+///
+/// `restarted` marks a monitor armed right after crash recovery spawned a new
+/// process, which gets the same startup grace as [`HealthChecker::wait_startup`]:
+/// until its first pass, failures within `start_period` of the spawn don't count
+/// and `startup_retries` applies instead of `retries`.
+#[allow(clippy::too_many_arguments)]
 fn run_health_monitor(
     svc_name: String,
     checker: Arc<HealthChecker>,
     svc: ServiceDef,
     handles: Arc<RwLock<HashMap<String, ServiceHandle>>>,
     events: broadcast::Sender<SupervisorEvent>,
+    stats: SharedHealthStats,
+    proxy: Arc<ProxyRouter>,
+    restarted: bool,
 ) {
     tokio::spawn(async move {
         let mut consecutive_failures: u32 = 0;
+        let grace = checker.config.start_period.unwrap_or_default();
+        let mut starting = restarted.then(tokio::time::Instant::now);
 
         loop {
+            let threshold = if starting.is_some() {
+                checker.startup_retries().max(1)
+            } else {
+                checker.config.retries.max(1)
+            };
             tokio::time::sleep(checker.config.interval).await;
 
             // Exit if service is no longer running.
@@ -72,21 +231,39 @@ fn run_health_monitor(
                 }
             };
 
-            if checker.check_once(port, &svc).await {
+            let outcome = checker.check_once(port, &svc).await;
+            let ok = outcome.is_ok();
+            if let (Err(reason), Some(since)) = (&outcome, starting) {
+                if since.elapsed() < grace {
+                    tracing::debug!("[{svc_name}] health check failed while starting: {reason}");
+                    if let Some(h) = handles.write().await.get_mut(&svc_name) {
+                        h.health.last_error = Some(reason.clone());
+                    }
+                    continue;
+                }
+            }
+            if ok {
+                starting = None;
+            }
+            stats
+                .lock()
+                .unwrap()
+                .record(&svc_name, ok, crate::health_stats::unix_now());
+            if let Some(h) = handles.write().await.get_mut(&svc_name) {
+                h.health.record(outcome);
+            }
+
+            if ok {
+                gate_proxy(&proxy, &svc, false).await;
                 if consecutive_failures > 0 {
                     tracing::info!("[{svc_name}] health check recovered");
                     // Restore Running state if currently Unhealthy.
                     let mut map = handles.write().await;
                     if let Some(h) = map.get_mut(&svc_name) {
-                        if let ServiceState::Unhealthy { pid, .. } = h.state {
-                            h.state = ServiceState::Running {
-                                pid,
-                                since: Instant::now(),
-                            };
-                            let _ = events.send(SupervisorEvent::StateChanged {
-                                service: svc_name.clone(),
-                                state: "running".into(),
-                            });
+                        // Same process, so its uptime carries on.
+                        if let ServiceState::Unhealthy { pid, since, .. } = h.state {
+                            h.state = ServiceState::Running { pid, since };
+                            let _ = events.send(SupervisorEvent::new(&svc_name, "running"));
                         }
                     }
                 }
@@ -94,20 +271,20 @@ fn run_health_monitor(
             } else {
                 consecutive_failures += 1;
                 tracing::warn!(
-                    "[{svc_name}] health check failed ({consecutive_failures}/{})",
-                    HEALTH_FAILURE_THRESHOLD
+                    "[{svc_name}] health check failed ({consecutive_failures}/{threshold})"
                 );
 
-                if consecutive_failures >= HEALTH_FAILURE_THRESHOLD {
+                if consecutive_failures >= threshold {
                     // Transition to Unhealthy, then kill — crash recovery will restart.
                     let pid = {
                         let mut map = handles.write().await;
                         if let Some(h) = map.get_mut(&svc_name) {
                             let pid = h.state.pid();
-                            if let Some(p) = pid {
+                            if let (Some(p), Some(since)) = (pid, h.state.since()) {
                                 h.state = ServiceState::Unhealthy {
                                     pid: p,
                                     failures: consecutive_failures,
+                                    since,
                                 };
                             }
                             pid
@@ -115,10 +292,10 @@ fn run_health_monitor(
                             break;
                         }
                     };
-                    let _ = events.send(SupervisorEvent::StateChanged {
-                        service: svc_name.clone(),
-                        state: "unhealthy".into(),
-                    });
+                    let _ = events.send(
+                        SupervisorEvent::new(&svc_name, "unhealthy")
+                            .because(format!("{consecutive_failures} failed health checks")),
+                    );
                     tracing::error!(
                         "[{svc_name}] unhealthy after {consecutive_failures} failures — restarting"
                     );
@@ -151,6 +328,183 @@ pub struct Supervisor {
     events: broadcast::Sender<SupervisorEvent>,
     log: Arc<LogAggregator>,
     proxy: Arc<ProxyRouter>,
+    /// Active `a3s share` tunnels, keyed by service name.
+    shares: ShareManager,
+    /// Pre-warmed spare instances of `standby = true` services.
+    spares: standby::Spares,
+    /// Health-check outcomes of the last 24h, saved under `.a3s/`.
+    health_stats: SharedHealthStats,
+    /// Services left down because a dependency didn't come up, with the reason.
+    blocked: std::sync::Mutex<HashMap<String, String>>,
+    /// Services whose last start failed before their process ran, with the error.
+    start_errors: std::sync::Mutex<HashMap<String, String>>,
+    /// Services `a3s up <names>` asked for, with their dependencies; `None` when
+    /// everything was. The rest show as "not requested" rather than stopped.
+    requested: std::sync::Mutex<Option<HashSet<String>>>,
+    /// CPU and memory of each service's process tree, cached between status requests.
+    usage: Arc<std::sync::Mutex<usage::UsageSampler>>,
+    /// Last [`sample_metrics`] of the running services, refreshed in the background.
+    metrics: Arc<std::sync::Mutex<Vec<ServiceMetrics>>>,
+    /// [`SupervisorEvent`]s paired with the state each service left, for subscribers.
+    transitions: broadcast::Sender<ServiceEvent>,
+    /// Ports given to `port = 0` services, kept after they stop so a service gets
+    /// the same one back and no other service is handed it meanwhile.
+    auto_ports: std::sync::Mutex<HashMap<String, u16>>,
+}
+
+/// How often the cached `Metrics` are refreshed.
+const METRICS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// The window CPU usage is measured over for `Metrics`.
+const METRICS_CPU_WINDOW: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// `sampler`'s usage of the trees under `pids`, taken on a blocking thread: it reads
+/// a file per process in the process table.
+async fn sample_usage(
+    sampler: Arc<std::sync::Mutex<usage::UsageSampler>>,
+    pids: Vec<u32>,
+) -> HashMap<u32, usage::Usage> {
+    tokio::task::spawn_blocking(move || sampler.lock().unwrap().sample(&pids))
+        .await
+        .unwrap_or_default()
+}
+
+/// Usage of every running service, sorted by name. Takes [`METRICS_CPU_WINDOW`]:
+/// CPU is measured between two samples that far apart.
+async fn sample_metrics(handles: &RwLock<HashMap<String, ServiceHandle>>) -> Vec<ServiceMetrics> {
+    let mut running: Vec<(String, u32, u64)> = handles
+        .read()
+        .await
+        .iter()
+        .filter_map(|(name, h)| Some((name.clone(), h.state.pid()?, h.state.uptime_secs())))
+        .collect();
+    running.sort();
+    let pids: Vec<u32> = running.iter().map(|(_, pid, _)| *pid).collect();
+    let sampler = Arc::new(std::sync::Mutex::new(usage::UsageSampler::default()));
+    sample_usage(sampler.clone(), pids.clone()).await;
+    tokio::time::sleep(METRICS_CPU_WINDOW).await;
+    let usage = sample_usage(sampler, pids).await;
+    running
+        .into_iter()
+        .filter_map(|(service, pid, uptime_secs)| {
+            let u = usage.get(&pid)?;
+            Some(ServiceMetrics {
+                service,
+                pid,
+                cpu_percent: u.cpu_percent,
+                rss_bytes: u.rss_bytes,
+                uptime_secs,
+            })
+        })
+        .collect()
+}
+
+/// Keep `out` filled with [`sample_metrics`] until the supervisor is dropped.
+async fn metrics_loop(
+    handles: std::sync::Weak<RwLock<HashMap<String, ServiceHandle>>>,
+    out: Arc<std::sync::Mutex<Vec<ServiceMetrics>>>,
+) {
+    loop {
+        let Some(handles) = handles.upgrade() else {
+            break;
+        };
+        let metrics = sample_metrics(&handles).await;
+        drop(handles);
+        *out.lock().unwrap() = metrics;
+        tokio::time::sleep(METRICS_INTERVAL - METRICS_CPU_WINDOW).await;
+    }
+}
+
+/// Ports of all services as children should see them: the assigned port of every
+/// running service, else the fixed port from config. Auto-assigned ports of services
+/// that have not started yet are unknown and left out.
+async fn runtime_ports(
+    handles: &RwLock<HashMap<String, ServiceHandle>>,
+    cfg: &DevConfig,
+) -> HashMap<String, u16> {
+    let mut ports: HashMap<String, u16> = cfg
+        .service
+        .iter()
+        .filter(|(_, s)| s.port != 0 && !s.disabled)
+        .map(|(n, s)| (n.clone(), s.port))
+        .collect();
+    ports.extend(
+        handles
+            .read()
+            .await
+            .iter()
+            .filter(|(_, h)| h.port != 0)
+            .map(|(n, h)| (n.clone(), h.port)),
+    );
+    ports
+}
+
+/// One stop sender for several watcher threads: a value sent to it stops them all.
+fn fan_out_stop(stops: Vec<std::sync::mpsc::SyncSender<()>>) -> std::sync::mpsc::SyncSender<()> {
+    if stops.len() == 1 {
+        return stops.into_iter().next().unwrap();
+    }
+    let (tx, rx) = std::sync::mpsc::sync_channel::<()>(1);
+    std::thread::spawn(move || {
+        if rx.recv().is_ok() {
+            for stop in stops {
+                let _ = stop.try_send(());
+            }
+        }
+    });
+    tx
+}
+
+/// Re-read A3sfile.hcl and swap `name`'s resolved `env` into the live config, so a
+/// restart picks up an edited env_file with the usual precedence (`env` over
+/// `env_file` over the project `.env`). Keeps the old values if the file doesn't load.
+fn refresh_env(
+    config: &ConfigCell,
+    config_path: &std::path::Path,
+    env_name: Option<&str>,
+    name: &str,
+) {
+    let mut fresh = match DevConfig::from_file_with_env(config_path, env_name) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("[{name}] env_file changed but config didn't reload: {e}");
+            return;
+        }
+    };
+    let Some(env) = fresh.service.swap_remove(name).map(|s| s.env) else {
+        return;
+    };
+    let mut cell = config.write().unwrap();
+    // Everything else keeps its running definition; A3sfile edits are reload's job.
+    let mut live = DevConfig::clone(&cell);
+    if let Some(svc) = live.service.get_mut(name) {
+        svc.env = env;
+        *cell = Arc::new(live);
+    }
+}
+
+/// A signal by name, with or without the `SIG` prefix and in any case ("hup", "SIGUSR1").
+#[cfg(unix)]
+fn parse_signal(name: &str) -> Result<nix::sys::signal::Signal> {
+    let upper = name.trim().to_ascii_uppercase();
+    let full = if upper.starts_with("SIG") {
+        upper
+    } else {
+        format!("SIG{upper}")
+    };
+    full.parse()
+        .map_err(|_| DevError::Config(format!("unknown signal '{name}'")))
+}
+
+/// SIGKILL the process groups led by `pids`.
+fn kill_process_groups(pids: &[u32]) {
+    #[cfg(unix)]
+    for &pid in pids {
+        use nix::sys::signal::{kill, Signal};
+        let _ = kill(nix::unistd::Pid::from_raw(-(pid as i32)), Signal::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = pids;
 }
 
 /// Summary of what changed during a hot-reload.
@@ -158,6 +512,9 @@ pub struct ReloadSummary {
     pub started: Vec<String>,
     pub stopped: Vec<String>,
     pub restarted: Vec<String>,
+    /// Running services whose definition changed but were left on the old one
+    /// because the reload asked not to restart.
+    pub not_restarted: Vec<String>,
 }
 
 impl Supervisor {
@@ -168,17 +525,46 @@ impl Supervisor {
         env_name: Option<String>,
     ) -> (Self, broadcast::Receiver<SupervisorEvent>) {
         let (events, rx) = broadcast::channel(4096);
+        let (transitions, _) = broadcast::channel(1024);
+        tokio::spawn(relay_transitions(events.subscribe(), transitions.clone()));
         let (log, log_rx) = LogAggregator::new();
         let log = Arc::new(log);
         tokio::spawn(LogAggregator::print_loop(log_rx));
         LogAggregator::spawn_history_recorder(log.clone());
+        let config_dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+        LogAggregator::persist_history(
+            &log,
+            crate::log::HistoryDir {
+                dir: config_dir.join(&config.dev.log_dir),
+                retention: config.dev.log_retention,
+            },
+        );
+        let stats_path = HealthStats::path(config_dir);
+        let health_stats = Arc::new(std::sync::Mutex::new(HealthStats::load(&stats_path)));
+        tokio::spawn(crate::health_stats::persist_loop(
+            health_stats.clone(),
+            stats_path,
+        ));
+        let handles = Arc::new(RwLock::new(HashMap::new()));
+        let metrics = Arc::default();
+        tokio::spawn(metrics_loop(Arc::downgrade(&handles), Arc::clone(&metrics)));
         (
             Self {
                 config: Arc::new(std::sync::RwLock::new(config)),
                 config_path,
                 env_name: Arc::new(std::sync::RwLock::new(env_name)),
-                handles: Arc::new(RwLock::new(HashMap::new())),
+                handles,
                 events,
+                shares: ShareManager::new(log.clone()),
+                spares: Default::default(),
+                health_stats,
+                blocked: Default::default(),
+                start_errors: Default::default(),
+                requested: Default::default(),
+                usage: Default::default(),
+                metrics,
+                transitions,
+                auto_ports: Default::default(),
                 log,
                 proxy,
             },
@@ -191,86 +577,310 @@ impl Supervisor {
         Arc::clone(&self.config.read().unwrap())
     }
 
+    /// Startup progress renderer for the current `dev { progress }` setting.
+    fn progress(&self) -> crate::progress::Progress {
+        crate::progress::Progress::new(self.cfg().dev.progress)
+    }
+
+    /// The `a3s doctor` checklist for the loaded config. Runs `which` and the tools'
+    /// `--version`, so call it off the async threads.
+    pub fn doctor(&self) -> crate::doctor::Report {
+        crate::doctor::run(Some(&self.cfg()), &self.config_path)
+    }
+
+    /// Directory of the A3sfile; relative paths in requests are resolved against it.
+    pub fn config_dir(&self) -> std::path::PathBuf {
+        self.config_path
+            .parent()
+            .unwrap_or(std::path::Path::new("."))
+            .to_path_buf()
+    }
+
+    /// Handle for managing the instances of a `standby = true` service.
+    fn standby_ctx(&self, name: &str, color_idx: usize) -> standby::StandbyCtx {
+        standby::StandbyCtx {
+            name: name.to_string(),
+            color_idx,
+            handles: self.handles.clone(),
+            spares: self.spares.clone(),
+            config: self.config.clone(),
+            events: self.events.clone(),
+            log: self.log.clone(),
+            proxy: self.proxy.clone(),
+            config_dir: self.config_dir(),
+        }
+    }
+
+    /// State changes of every service from now on.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ServiceEvent> {
+        self.transitions.subscribe()
+    }
+
     pub fn subscribe_logs(&self) -> broadcast::Receiver<crate::log::LogLine> {
         self.log.subscribe()
     }
 
-    pub fn log_history(&self, services: &[String], lines: usize) -> Vec<crate::log::LogLine> {
-        self.log.recent(services, lines)
+    /// Recent lines, from the log files when memory doesn't go back far enough.
+    pub fn log_history(
+        &self,
+        services: &[String],
+        lines: usize,
+        since: Option<std::time::SystemTime>,
+    ) -> Vec<crate::log::LogLine> {
+        self.log.history(services, lines, since)
     }
 
-    /// Start all non-disabled services, launching each wave concurrently.
-    /// Services within a wave have no inter-dependencies, so they can start in parallel.
+    /// Start all non-disabled services. `autostart = false` services only start when
+    /// an autostarted one depends on them.
     pub async fn start_all(self: &Arc<Self>) -> Result<()> {
         let cfg = self.cfg();
         let graph = DependencyGraph::from_config(&cfg)?;
-
-        // Global color index by position in topological order
-        let color: HashMap<String, usize> = graph
-            .start_order()
+        let autostarted: Vec<&str> = cfg
+            .service
             .iter()
-            .enumerate()
-            .map(|(i, n)| (n.clone(), i))
+            .filter(|(_, s)| s.autostart)
+            .map(|(n, _)| n.as_str())
             .collect();
-
-        let waves = graph.start_waves().to_vec();
-        let total_waves = waves.len();
-        for (wave_idx, wave) in waves.iter().enumerate() {
-            let active: Vec<&str> = wave
-                .iter()
-                .filter(|n| !cfg.service.get(*n).is_some_and(|s| s.disabled))
-                .map(|s| s.as_str())
-                .collect();
-            if !active.is_empty() {
-                println!(
-                    "{} wave {}/{}: {}",
-                    "→".cyan(),
-                    wave_idx + 1,
-                    total_waves,
-                    active.join(", ")
-                );
-            }
-            let mut set = tokio::task::JoinSet::new();
-            for name in wave {
-                if cfg.service.get(name).is_some_and(|s| s.disabled) {
-                    tracing::info!("[{name}] skipped (disabled)");
-                    continue;
-                }
-                let sup = Arc::clone(self);
-                let name = name.clone();
-                let idx = color.get(&name).copied().unwrap_or(0);
-                set.spawn(async move { sup.start_service(&name, idx).await });
-            }
-            while let Some(res) = set.join_next().await {
-                res.map_err(|e| DevError::Config(e.to_string()))??;
-            }
+        for (name, _) in cfg.service.iter().filter(|(_, s)| !s.autostart) {
+            tracing::info!("[{name}] registered (autostart = false)");
         }
-        Ok(())
+        self.start_graph(&graph, graph.transitive_start_order(&autostarted))
+            .await
     }
 
-    /// Start only the named services (and their transitive deps), in dependency order.
+    /// Start only the named services (and their transitive deps).
+    /// Services that are already running are left alone.
+    /// Unknown names fail before anything starts.
     pub async fn start_named(self: &Arc<Self>, names: &[String]) -> Result<()> {
         let cfg = self.cfg();
+        cfg.check_service_names(names)?;
         let graph = DependencyGraph::from_config(&cfg)?;
         let name_refs: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
-        let to_start = graph.transitive_start_order(&name_refs);
+        let wanted = graph.transitive_start_order(&name_refs);
+        if let Some(requested) = self.requested.lock().unwrap().as_mut() {
+            requested.extend(wanted.iter().cloned());
+        }
+        self.start_graph(&graph, wanted).await
+    }
+
+    /// `a3s start`: bring up the named services and whatever they depend on that
+    /// isn't running, reporting what happened to each of them. Services already
+    /// running are left alone and reported as such.
+    pub async fn start_services(self: &Arc<Self>, names: &[String]) -> Result<Vec<StartResult>> {
+        let cfg = self.cfg();
+        cfg.check_service_names(names)?;
+        let graph = DependencyGraph::from_config(&cfg)?;
+        let name_refs: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
+        let wanted = graph.transitive_start_order(&name_refs);
+        if let Some(requested) = self.requested.lock().unwrap().as_mut() {
+            requested.extend(wanted.iter().cloned());
+        }
+        let mut running = HashSet::new();
+        for name in &wanted {
+            if self.is_running(name).await {
+                running.insert(name.clone());
+            }
+        }
+        let mut errors: HashMap<String, String> = self
+            .start_graph_each(&graph, wanted.clone())
+            .await
+            .into_iter()
+            .map(|(name, e)| (name, e.to_string()))
+            .collect();
+        let blocked = self.blocked.lock().unwrap().clone();
+        let mut results = Vec::new();
+        for name in wanted {
+            let (outcome, error) = if running.contains(&name) {
+                ("already running", None)
+            } else if self.is_running(&name).await {
+                ("started", None)
+            } else if let Some(reason) = blocked.get(&name) {
+                ("blocked", Some(reason.clone()))
+            } else if cfg.service[&name].disabled {
+                ("failed", Some("disabled".to_string()))
+            } else {
+                ("failed", errors.remove(&name))
+            };
+            results.push(StartResult {
+                service: name,
+                outcome: outcome.into(),
+                error,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Start only the named services and their dependencies, as `a3s up <names>`:
+    /// every other service is "not requested" until something starts it.
+    pub async fn start_requested(self: &Arc<Self>, names: &[String]) -> Result<()> {
+        self.cfg().check_service_names(names)?;
+        *self.requested.lock().unwrap() = Some(HashSet::new());
+        self.start_named(names).await
+    }
+
+    /// Start `wanted` (closed under dependencies) concurrently: each service starts
+    /// as soon as its dependencies are up. A dependency that fails, or isn't up within
+    /// the `depends_on_timeout`, blocks its dependents but nothing else. Returns the
+    /// first start error once everything has settled.
+    async fn start_graph(
+        self: &Arc<Self>,
+        graph: &DependencyGraph,
+        wanted: Vec<String>,
+    ) -> Result<()> {
+        let errors = self.start_graph_each(graph, wanted).await;
+        errors.into_iter().next().map_or(Ok(()), |(_, e)| Err(e))
+    }
+
+    /// [`Self::start_graph`], returning every service that failed to start with
+    /// its error, in the order they failed.
+    async fn start_graph_each(
+        self: &Arc<Self>,
+        graph: &DependencyGraph,
+        wanted: Vec<String>,
+    ) -> Vec<(String, DevError)> {
+        let cfg = self.cfg();
+        let mut active = Vec::new();
+        for name in wanted {
+            if cfg.service.get(&name).is_some_and(|s| s.disabled) {
+                tracing::info!("[{name}] skipped (disabled)");
+            } else {
+                active.push(name);
+            }
+        }
+
+        // Waves are announced as their first service starts.
+        let waves = graph.start_waves();
+        let steps: Vec<Option<String>> = waves
+            .iter()
+            .enumerate()
+            .map(|(i, wave)| {
+                let names: Vec<&str> = wave
+                    .iter()
+                    .filter(|n| active.contains(n))
+                    .map(|s| s.as_str())
+                    .collect();
+                (!names.is_empty())
+                    .then(|| format!("wave {}/{}: {}", i + 1, waves.len(), names.join(", ")))
+            })
+            .collect();
+        let wave_of: HashMap<&str, usize> = waves
+            .iter()
+            .enumerate()
+            .flat_map(|(i, wave)| wave.iter().map(move |n| (n.as_str(), i)))
+            .collect();
+        let announced = Arc::new(std::sync::Mutex::new(0usize));
 
-        let color: HashMap<String, usize> = graph
+        // Global color index by position in topological order
+        let color: HashMap<&str, usize> = graph
             .start_order()
             .iter()
             .enumerate()
-            .map(|(i, n)| (n.clone(), i))
+            .map(|(i, n)| (n.as_str(), i))
             .collect();
 
-        for name in &to_start {
-            if cfg.service.get(name).is_some_and(|s| s.disabled) {
-                tracing::info!("[{name}] skipped (disabled)");
-                continue;
+        let mut signals = HashMap::new();
+        for name in &active {
+            signals.insert(name.clone(), deps::started(self.is_running(name).await));
+        }
+
+        let mut set = tokio::task::JoinSet::new();
+        for name in &active {
+            let (tx, _) = &signals[name];
+            if tx.borrow().is_some() {
+                continue; // already running
             }
-            let idx = color.get(name).copied().unwrap_or(0);
-            self.start_service(name, idx).await?;
+            let tx = tx.clone();
+            let svc = &cfg.service[name];
+            let deps: Vec<_> = svc
+                .depends_on
+                .iter()
+                .filter_map(|d| signals.get(d).map(|(_, rx)| (d.clone(), rx.clone())))
+                .collect();
+            let timeout = svc.depends_on_timeout.unwrap_or(cfg.dev.depends_on_timeout);
+            let wave = wave_of.get(name.as_str()).copied().unwrap_or(0);
+            let step = steps[..=wave].to_vec();
+            let announced = Arc::clone(&announced);
+            let idx = color.get(name.as_str()).copied().unwrap_or(0);
+            let sup = Arc::clone(self);
+            let name = name.clone();
+            set.spawn(async move {
+                if let Err(reason) = deps::wait_for(deps, timeout).await {
+                    sup.block(&name, reason);
+                    let _ = tx.send(Some(false));
+                    return (name, Ok(()));
+                }
+                {
+                    let mut next = announced.lock().unwrap();
+                    while *next < step.len() {
+                        if let Some(msg) = &step[*next] {
+                            sup.progress().step(msg.clone());
+                        }
+                        *next += 1;
+                    }
+                }
+                let res = sup.start_service(&name, idx).await;
+                let _ = tx.send(Some(res.is_ok()));
+                (name, res)
+            });
         }
-        Ok(())
+
+        let mut errors = Vec::new();
+        while let Some(res) = set.join_next().await {
+            match res {
+                Ok((name, Err(e))) => errors.push((name, e)),
+                Ok((_, Ok(()))) => {}
+                Err(e) => errors.push((String::new(), DevError::Config(e.to_string()))),
+            }
+        }
+        errors
+    }
+
+    /// Record that `name` failed to start — say its secrets didn't resolve — so
+    /// status shows it failed with `e`, and hand `e` back.
+    fn start_failed(&self, name: &str, e: DevError) -> DevError {
+        tracing::error!("[{name}] failed to start: {e}");
+        self.progress().state(name, "failed");
+        self.start_errors
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), e.to_string());
+        self.emit(SupervisorEvent::new(name, "failed").because(e.to_string()));
+        e
+    }
+
+    /// Record that `name` won't start because of a dependency (`reason` names it).
+    fn block(&self, name: &str, reason: String) {
+        tracing::warn!("[{name}] blocked: {reason}");
+        self.progress()
+            .emit(crate::progress::ProgressEvent::Failed(format!(
+                "{name} blocked: {reason}"
+            )));
+        self.blocked
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), reason.clone());
+        self.emit(SupervisorEvent::new(name, "blocked").because(reason));
+    }
+
+    /// The port for `port = 0` service `name`: the one it had before while that is
+    /// still free, else a new [`assign_port`]. Never a port another service was given
+    /// or declares.
+    fn auto_port(&self, name: &str, cfg: &DevConfig) -> Result<u16> {
+        let mut assigned = self.auto_ports.lock().unwrap();
+        let taken: HashSet<u16> = assigned
+            .iter()
+            .filter(|(n, _)| n.as_str() != name)
+            .map(|(_, p)| *p)
+            .chain(cfg.service.values().map(|s| s.port).filter(|p| *p != 0))
+            .collect();
+        let port = assigned
+            .get(name)
+            .copied()
+            .filter(|p| !taken.contains(p) && port_free(*p))
+            .or_else(|| assign_port(name, &taken))
+            .ok_or_else(|| DevError::Config(format!("[{name}] no free port available")))?;
+        assigned.insert(name.to_string(), port);
+        Ok(port)
     }
 
     pub async fn start_service(&self, name: &str, color_idx: usize) -> Result<()> {
@@ -281,15 +891,16 @@ impl Supervisor {
             .ok_or_else(|| DevError::UnknownService(name.to_string()))?
             .clone();
 
-        self.emit(SupervisorEvent::StateChanged {
-            service: name.to_string(),
-            state: "starting".into(),
-        });
+        self.blocked.lock().unwrap().remove(name);
+        self.start_errors.lock().unwrap().remove(name);
+        self.emit(SupervisorEvent::new(name, "starting"));
+        let progress = self.progress();
+        progress.state(name, "starting");
 
         // Resolve port: 0 = auto-assign a free port (portless-style)
         let port = if svc.port == 0 {
-            free_port()
-                .ok_or_else(|| DevError::Config(format!("[{name}] no free port available")))?
+            self.auto_port(name, &cfg)
+                .map_err(|e| self.start_failed(name, e))?
         } else {
             svc.port
         };
@@ -305,15 +916,9 @@ impl Supervisor {
         // Resolve ${other.port} references using the currently-assigned ports of all
         // running services.  This must happen after `port` is known for this service.
         let svc = {
-            let mut runtime_ports: HashMap<String, u16> = self
-                .handles
-                .read()
-                .await
-                .iter()
-                .map(|(n, h)| (n.clone(), h.port))
-                .collect();
-            runtime_ports.insert(name.to_string(), port);
-            crate::config::resolve_service_ports(svc, &runtime_ports)
+            let mut ports = runtime_ports(&self.handles, &cfg).await;
+            ports.insert(name.to_string(), port);
+            crate::config::resolve_service_ports(svc, &ports)
         };
 
         let spec = SpawnSpec {
@@ -325,10 +930,18 @@ impl Supervisor {
                 .config_path
                 .parent()
                 .unwrap_or(std::path::Path::new(".")),
+            standby: false,
         };
-        let result = spawn_process(&spec, &self.log).await?;
+        let result = spawn_process(&spec, &self.log)
+            .await
+            .map_err(|e| self.start_failed(name, e))?;
 
-        self.handles.write().await.insert(
+        let mut map = self.handles.write().await;
+        let previous_health = map
+            .get(name)
+            .map(|h| h.health.restarted())
+            .unwrap_or_default();
+        map.insert(
             name.to_string(),
             ServiceHandle {
                 child: result.child,
@@ -340,34 +953,58 @@ impl Supervisor {
                 port,
                 watcher_stop: None,
                 restart_count: 0,
+                cgroup: result.cgroup,
+                health: previous_health,
             },
         );
+        drop(map);
+        gate_proxy(&self.proxy, &svc, true).await;
 
-        self.emit(SupervisorEvent::StateChanged {
-            service: name.to_string(),
-            state: "running".into(),
-        });
+        self.emit(SupervisorEvent::new(name, "running"));
+        progress.state(name, "running");
 
         // Build health checker once so both startup wait and ongoing monitor share it.
         let health_info: Option<(Arc<HealthChecker>, ServiceDef)> =
             HealthChecker::for_service(&svc).map(|c| (Arc::new(c), svc.clone()));
 
+        let standby = svc.standby.then(|| self.standby_ctx(name, color_idx));
+
         // Crash recovery — monitor process and auto-restart on unexpected exit.
         // Pass health_info so recovery can re-arm the monitor after each restart.
-        self.spawn_crash_recovery(name.to_string(), color_idx, health_info.clone());
+        self.spawn_crash_recovery(
+            name.to_string(),
+            color_idx,
+            health_info.clone(),
+            standby.clone(),
+        );
 
         // Wait for health before unblocking dependents, then start ongoing monitor.
         if let Some((checker, svc_def)) = health_info {
-            let healthy = checker.wait_healthy(&svc_def, port).await;
-            self.emit(SupervisorEvent::HealthChange {
-                service: name.to_string(),
-                healthy,
+            let outcome = checker.wait_startup(&svc_def, port).await;
+            let healthy = outcome.is_ok();
+            if let Some(h) = self.handles.write().await.get_mut(name) {
+                match outcome {
+                    Ok(()) => h.health.record(Ok(())),
+                    Err(reason) => {
+                        h.health.failures = checker.startup_retries();
+                        h.health.last_error = Some(reason);
+                    }
+                }
+            }
+            self.emit(if healthy {
+                SupervisorEvent::new(name, "healthy")
+            } else {
+                SupervisorEvent::new(name, "unhealthy").because("startup health checks failed")
             });
-            if !healthy {
+            if healthy {
+                gate_proxy(&self.proxy, &svc_def, false).await;
+                progress.state(name, "healthy");
+            } else {
                 tracing::warn!(
                     "[{name}] health check failed after {} retries",
-                    checker.config.retries
+                    checker.startup_retries()
                 );
+                progress.state(name, "unhealthy");
             }
             // Start ongoing health monitor regardless of startup result.
             run_health_monitor(
@@ -376,53 +1013,127 @@ impl Supervisor {
                 svc_def,
                 self.handles.clone(),
                 self.events.clone(),
+                self.health_stats.clone(),
+                self.proxy.clone(),
+                false,
             );
         }
 
-        // File watcher → auto-restart on change
-        if let Some(watch) = &svc.watch {
-            if watch.restart {
-                let stop_tx = self.spawn_file_watcher(
-                    name.to_string(),
-                    watch.paths.clone(),
-                    watch.ignore.clone(),
-                );
-                if let Some(h) = self.handles.write().await.get_mut(name) {
-                    h.watcher_stop = Some(stop_tx);
-                }
+        // Warm the spare only now, so it never delays dependents.
+        if let Some(ctx) = &standby {
+            ctx.warm();
+        }
+
+        // File watcher → auto-restart on change (with `env_reload`, also of the env_file)
+        let watch = svc.watch.as_ref().filter(|w| w.restart);
+        let env_file = svc.env_file.clone().filter(|_| svc.env_reload);
+        if watch.is_some() || env_file.is_some() {
+            let stop_tx =
+                self.spawn_file_watcher(name.to_string(), watch.cloned(), env_file, standby);
+            if let Some(h) = self.handles.write().await.get_mut(name) {
+                h.watcher_stop = Some(stop_tx);
             }
         }
 
         Ok(())
     }
 
-    /// Stop all running services in reverse wave order, stopping each wave concurrently.
+    /// Stop all running services, each as soon as everything that depends on it has
+    /// stopped, waiting up to its `stop_timeout` for a graceful exit. Services still
+    /// running after `dev.shutdown_timeout` are killed at once.
     /// Returns the names of services that were actually running and got stopped.
     pub async fn stop_all(self: &Arc<Self>) -> Vec<String> {
-        let graph = match DependencyGraph::from_config(&self.cfg()) {
-            Ok(g) => g,
-            Err(_) => return vec![],
-        };
-        let running: Vec<String> = {
+        let cfg = self.cfg();
+        if DependencyGraph::from_config(&cfg).is_err() {
+            return vec![];
+        }
+        let (running, pids): (Vec<String>, Vec<u32>) = {
             let map = self.handles.read().await;
-            map.iter()
+            let running = map
+                .iter()
                 .filter(|(_, h)| !matches!(h.state, ServiceState::Stopped))
                 .map(|(n, _)| n.clone())
-                .collect()
+                .collect();
+            (
+                running,
+                map.values().filter_map(|h| h.state.pid()).collect(),
+            )
         };
-        for wave in graph.start_waves().iter().rev() {
-            let mut set = tokio::task::JoinSet::new();
-            for name in wave {
-                let sup = Arc::clone(self);
-                let name = name.clone();
-                set.spawn(async move { sup.stop_service(&name).await });
+
+        // Each service signals once it has stopped; its dependencies wait for that.
+        let mut stopped = HashMap::new();
+        let mut signals = Vec::new();
+        for name in cfg.service.keys() {
+            let (tx, rx) = tokio::sync::watch::channel(false);
+            stopped.insert(name.as_str(), rx);
+            signals.push((name.clone(), tx));
+        }
+        let mut set = tokio::task::JoinSet::new();
+        for (name, tx) in signals {
+            let dependents: Vec<_> = cfg
+                .service
+                .iter()
+                .filter(|(_, s)| s.depends_on.contains(&name))
+                .map(|(n, _)| stopped[n.as_str()].clone())
+                .collect();
+            let sup = Arc::clone(self);
+            set.spawn(async move {
+                for mut rx in dependents {
+                    let _ = rx.wait_for(|done| *done).await;
+                }
+                sup.stop_service(&name).await;
+                let _ = tx.send(true);
+            });
+        }
+
+        let deadline = tokio::time::sleep(cfg.dev.shutdown_timeout);
+        tokio::pin!(deadline);
+        let mut killed = false;
+        loop {
+            tokio::select! {
+                next = set.join_next() => if next.is_none() { break },
+                _ = &mut deadline, if !killed => {
+                    killed = true;
+                    tracing::warn!(
+                        "services still running after shutdown_timeout ({:?}) — killing them",
+                        cfg.dev.shutdown_timeout
+                    );
+                    // The stops in flight then finish at once, hooks and all.
+                    kill_process_groups(&pids);
+                }
             }
-            while set.join_next().await.is_some() {}
         }
+        crate::health_stats::save_if_dirty(
+            &self.health_stats,
+            &HealthStats::path(&self.config_dir()),
+        );
         running
     }
 
+    /// [`stop_all`](Self::stop_all), unless `abort` completes first (a second Ctrl-C or
+    /// signal): then every service and standby process group is killed at once.
+    pub async fn shutdown(self: &Arc<Self>, abort: impl std::future::Future<Output = ()>) {
+        let pids: Vec<u32> = self
+            .status_rows()
+            .await
+            .iter()
+            .flat_map(|r| [r.pid, r.standby.as_ref().and_then(|s| s.pid)])
+            .flatten()
+            .collect();
+        tokio::select! {
+            _ = self.stop_all() => {}
+            _ = abort => {
+                tracing::warn!("second signal during shutdown — killing all services");
+                kill_process_groups(&pids);
+            }
+        }
+    }
+
     pub async fn stop_service(&self, name: &str) {
+        // A share is meaningless once its service is gone.
+        self.shares.stop(name).await;
+        self.standby_ctx(name, 0).discard().await;
+
         let cfg = self.cfg();
         let svc_def = cfg.service.get(name).cloned();
         let stop_timeout = svc_def
@@ -446,20 +1157,21 @@ impl Supervisor {
                     tokio::process::Command::new("true").spawn().unwrap(),
                 );
                 h.state = ServiceState::Stopped;
-                Some((pid, child))
+                Some((pid, child, h.cgroup.take()))
             } else {
                 None
             }
         }; // write lock dropped here
 
-        self.emit(SupervisorEvent::StateChanged {
-            service: name.to_string(),
-            state: "stopped".into(),
-        });
+        if let Some(svc) = &svc_def {
+            gate_proxy(&self.proxy, svc, false).await;
+        }
 
-        let Some((pid_opt, mut child)) = extracted else {
+        let Some((pid_opt, mut child, cgroup)) = extracted else {
+            self.emit(SupervisorEvent::new(name, "stopped"));
             return;
         };
+        self.emit(SupervisorEvent::new(name, "stopping"));
 
         #[cfg(unix)]
         if let Some(pid) = pid_opt {
@@ -473,6 +1185,10 @@ impl Supervisor {
             let _ = kill(pgid, Signal::SIGKILL);
         }
         let _ = child.kill().await;
+        if let Some(cg) = cgroup {
+            cg.remove();
+        }
+        self.emit(SupervisorEvent::new(name, "stopped"));
 
         // Run post_stop hook after the process is gone.
         if let Some(svc) = svc_def {
@@ -485,6 +1201,37 @@ impl Supervisor {
         }
     }
 
+    /// Stop one service. With `dependents`, everything that depends on it is stopped
+    /// first; otherwise they keep running, and are warned about and returned.
+    pub async fn stop_one(&self, name: &str, dependents: bool) -> Vec<String> {
+        if dependents {
+            self.stop_named(&[name.to_string()]).await;
+            return vec![];
+        }
+        let still_running = self.running_dependents(name).await;
+        if !still_running.is_empty() {
+            tracing::warn!(
+                "[{name}] stopping while {} still depend on it",
+                still_running.join(", ")
+            );
+        }
+        self.stop_service(name).await;
+        still_running
+    }
+
+    /// Running services that depend on `name`, directly or transitively.
+    async fn running_dependents(&self, name: &str) -> Vec<String> {
+        let Ok(graph) = DependencyGraph::from_config(&self.cfg()) else {
+            return vec![];
+        };
+        let map = self.handles.read().await;
+        graph
+            .transitive_dependents_stop_order(&[name])
+            .into_iter()
+            .filter(|n| n != name && map.get(n).is_some_and(|h| h.state.pid().is_some()))
+            .collect()
+    }
+
     /// Stop only the named services and any services that transitively depend on them,
     /// in safe order (dependents first, then targets).
     /// Returns the names of services that were stopped.
@@ -503,23 +1250,79 @@ impl Supervisor {
         stop_order
     }
 
-    /// Reload A3sfile.hcl from disk and apply changes without restarting unchanged services.
-    pub async fn reload_from_disk(&self) -> Result<ReloadSummary> {
+    /// Reload A3sfile.hcl from disk and apply changes without restarting unchanged
+    /// services. With `restart = false`, changed services keep running as they are.
+    pub async fn reload_from_disk(&self, restart: bool) -> Result<ReloadSummary> {
         let env_name = self.env_name.read().unwrap().clone();
         let new_cfg = DevConfig::from_file_with_env(&self.config_path, env_name.as_deref())?;
-        self.reload(Arc::new(new_cfg)).await
+        self.reload(Arc::new(new_cfg), restart).await
+    }
+
+    /// `a3s kill`: send `signal` (e.g. "SIGHUP" or "USR1") to a running service's
+    /// process group. The service stays supervised; if the signal ends it, crash
+    /// recovery sees the exit like any other.
+    pub async fn kill_service(&self, name: &str, signal: &str) -> Result<()> {
+        if !self.cfg().service.contains_key(name) {
+            return Err(DevError::UnknownService(name.to_string()));
+        }
+        #[cfg(unix)]
+        {
+            use nix::sys::signal::kill;
+            use nix::unistd::Pid;
+            let sig = parse_signal(signal)?;
+            let pid = self
+                .handles
+                .read()
+                .await
+                .get(name)
+                .and_then(|h| h.state.pid())
+                .ok_or_else(|| DevError::Config(format!("[{name}] is not running")))?;
+            kill(Pid::from_raw(-(pid as i32)), sig).map_err(|e| DevError::Process {
+                service: name.to_string(),
+                msg: format!("{sig}: {e}"),
+            })
+        }
+        #[cfg(not(unix))]
+        Err(DevError::Config(format!(
+            "[{name}] cannot send {signal}: signals are only supported on unix"
+        )))
     }
 
     pub async fn restart_service(self: &Arc<Self>, name: &str) -> Result<()> {
         let cfg = self.cfg();
         let graph = DependencyGraph::from_config(&cfg)?;
 
+        // Standby services swap instances behind the proxy; dependents keep running.
+        if cfg.service.get(name).is_some_and(|s| s.standby) {
+            let color_idx = {
+                let map = self.handles.read().await;
+                map.get(name)
+                    .filter(|h| h.state.pid().is_some())
+                    .map(|h| h.color_idx)
+            };
+            if let Some(color_idx) = color_idx {
+                let ctx = self.standby_ctx(name, color_idx);
+                if !ctx.promote().await {
+                    ctx.replace_fresh().await?;
+                    ctx.warm();
+                }
+                return Ok(());
+            }
+        }
+
+        // Never started (or stopped): start it and whatever it needs on demand.
+        if !self.handles.read().await.contains_key(name) {
+            return self.start_named(&[name.to_string()]).await;
+        }
+
         // Dependents must stop before the target; stop_order = [dependents..., target].
-        let stop_order = graph.transitive_dependents_stop_order(&[name]);
+        // Dependents that weren't up (e.g. `autostart = false`) stay down.
+        let mut stop_order = graph.transitive_dependents_stop_order(&[name]);
 
         // Snapshot color indices before stopping (handles will be removed).
         let colors: HashMap<String, usize> = {
             let map = self.handles.read().await;
+            stop_order.retain(|n| n == name || map.contains_key(n));
             stop_order
                 .iter()
                 .map(|n| (n.clone(), map.get(n).map(|h| h.color_idx).unwrap_or(0)))
@@ -539,31 +1342,227 @@ impl Supervisor {
         Ok(())
     }
 
-    pub async fn status_rows(&self) -> Vec<StatusRow> {
+    /// Expose a running service through the tunnel configured in `dev { share { ... } }`.
+    pub async fn share_service(
+        &self,
+        name: &str,
+        ttl: Option<std::time::Duration>,
+    ) -> Result<ShareInfo> {
         let cfg = self.cfg();
-        let map = self.handles.read().await;
-        cfg.service
-            .iter()
-            .map(|(name, svc)| {
+        if !cfg.service.contains_key(name) {
+            return Err(DevError::UnknownService(name.to_string()));
+        }
+        let share_cfg = cfg.dev.share.as_ref().ok_or_else(|| {
+            DevError::Config(
+                "no share settings — add `dev { share { host = \"...\" } }` to A3sfile.hcl".into(),
+            )
+        })?;
+        let port = {
+            let map = self.handles.read().await;
+            match map.get(name) {
+                Some(h) if h.state.pid().is_some() => h.port,
+                _ => return Err(DevError::Config(format!("service '{name}' is not running"))),
+            }
+        };
+        let provider = crate::share::provider_for(share_cfg)?;
+        self.shares
+            .start(name, port, provider, share_cfg.port_range, ttl)
+            .await
+    }
+
+    /// Tear down the share for `name`. Returns false if it was not shared.
+    pub async fn unshare_service(&self, name: &str) -> bool {
+        self.shares.stop(name).await
+    }
+
+    /// `kubectl apply -f` manifests at `path` (relative to A3sfile.hcl) using the
+    /// configured k8s context and namespace. Returns kubectl's output.
+    pub async fn kube_deploy(&self, path: &std::path::Path) -> Result<String> {
+        self.kube_client()
+            .deploy(&self.config_dir().join(path))
+            .await
+    }
+
+    /// `kubectl delete -f` manifests at `path` (relative to A3sfile.hcl).
+    pub async fn kube_undeploy(&self, path: &std::path::Path) -> Result<String> {
+        self.kube_client()
+            .undeploy(&self.config_dir().join(path))
+            .await
+    }
+
+    /// `kubectl create namespace` in the configured context.
+    pub async fn kube_create_namespace(&self, name: &str) -> Result<()> {
+        self.kube_client().create_namespace(name).await
+    }
+
+    /// `kubectl delete namespace` in the configured context.
+    pub async fn kube_delete_namespace(&self, name: &str, force: bool) -> Result<()> {
+        self.kube_client().delete_namespace(name, force).await
+    }
+
+    /// Run a one-off command in a pod and return its stdout. `namespace` defaults to
+    /// the configured one.
+    pub async fn kube_pod_exec(
+        &self,
+        namespace: Option<&str>,
+        pod: &str,
+        container: Option<&str>,
+        command: &[String],
+    ) -> Result<String> {
+        let command: Vec<&str> = command.iter().map(String::as_str).collect();
+        self.kube_client_in(namespace)
+            .pod_exec(pod, container, &command)
+            .await
+    }
+
+    /// Send a pod's timestamped log lines to `sender`, following them across
+    /// container restarts when `follow` is set, until the receiver is dropped or the
+    /// pod is gone. `namespace` defaults to the configured one.
+    pub async fn kube_pod_logs(
+        &self,
+        namespace: Option<&str>,
+        pod: &str,
+        follow: bool,
+        sender: tokio::sync::mpsc::Sender<String>,
+    ) -> Result<()> {
+        self.kube_client_in(namespace)
+            .stream_pod_logs(pod, follow, sender)
+            .await
+    }
+
+    /// Scale a deployment to `replicas`. `namespace` defaults to the configured one.
+    pub async fn kube_scale_deployment(
+        &self,
+        namespace: Option<&str>,
+        name: &str,
+        replicas: u32,
+    ) -> Result<()> {
+        self.kube_client_in(namespace)
+            .scale_deployment(name, replicas)
+            .await
+    }
+
+    /// CPU and memory of the cluster's nodes and a namespace's pods. `namespace`
+    /// defaults to the configured one.
+    pub async fn kube_top(&self, namespace: Option<&str>) -> Result<crate::k8s::KubeTop> {
+        self.kube_client_in(namespace).top().await
+    }
+
+    /// Deployments and Services in the configured namespace, optionally filtered by
+    /// a label selector.
+    pub async fn kube_resources(
+        &self,
+        selector: Option<&str>,
+    ) -> Result<crate::k8s::KubeResources> {
+        self.kube_client().query_resources(selector).await
+    }
+
+    /// Whether `name` is among the services `a3s up` was asked to start.
+    fn is_requested(&self, name: &str) -> bool {
+        self.requested
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(|r| r.contains(name))
+    }
+
+    /// Whether `name` has a live process (running or unhealthy).
+    async fn is_running(&self, name: &str) -> bool {
+        self.handles
+            .read()
+            .await
+            .get(name)
+            .is_some_and(|h| h.state.pid().is_some())
+    }
+
+    fn kube_client(&self) -> crate::k8s::K8sClient {
+        crate::k8s::K8sClient::from_settings(&self.cfg().dev)
+    }
+
+    /// A kubectl client for `namespace`, or the configured one.
+    fn kube_client_in(&self, namespace: Option<&str>) -> crate::k8s::K8sClient {
+        match namespace {
+            Some(ns) => self.kube_client().in_namespace(ns),
+            None => self.kube_client(),
+        }
+    }
+
+    /// CPU and memory of the running services as of the last background sample,
+    /// at most [`METRICS_INTERVAL`] old.
+    pub fn metrics(&self) -> Vec<ServiceMetrics> {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    pub async fn status_rows(&self) -> Vec<StatusRow> {
+        let cfg = self.cfg();
+        let shares: HashMap<String, String> = self
+            .shares
+            .list()
+            .await
+            .into_iter()
+            .map(|s| (s.service, s.url))
+            .collect();
+        let mut spares = HashMap::new();
+        for name in cfg.service.keys() {
+            if let Some(row) = standby::spare_row(&self.spares, name).await {
+                spares.insert(name.clone(), row);
+            }
+        }
+        let flaky: Vec<String> = self
+            .health_report()
+            .into_iter()
+            .filter(|r| r.flaky)
+            .map(|r| r.name)
+            .collect();
+        let blocked = self.blocked.lock().unwrap().clone();
+        let start_errors = self.start_errors.lock().unwrap().clone();
+        let requested = self.requested.lock().unwrap().clone();
+        let auto_ports = self.auto_ports.lock().unwrap().clone();
+        // Sampled with the handles unlocked: it can take a while on a busy machine.
+        let pids: Vec<u32> = self
+            .handles
+            .read()
+            .await
+            .values()
+            .filter_map(|h| h.state.pid())
+            .collect();
+        let usage = sample_usage(self.usage.clone(), pids).await;
+        let map = self.handles.read().await;
+        cfg.service
+            .iter()
+            .map(|(name, svc)| {
                 let handle = map.get(name);
-                let state = handle
-                    .map(|h| h.state.label().to_string())
-                    .unwrap_or_else(|| "pending".into());
-                let pid = handle.and_then(|h| h.state.pid());
-                let uptime_secs = handle.and_then(|h| {
-                    if let ServiceState::Running { since, .. } = h.state {
-                        Some(since.elapsed().as_secs())
-                    } else {
-                        None
+                let blocked = blocked.get(name).filter(|_| handle.is_none()).cloned();
+                // A process left from before the failed start doesn't hide it.
+                let start_error = start_errors
+                    .get(name)
+                    .filter(|_| handle.is_none_or(|h| h.state.pid().is_none()))
+                    .cloned();
+                let state = match handle {
+                    _ if start_error.is_some() => "failed".into(),
+                    Some(h) => h.state.label().to_string(),
+                    None if blocked.is_some() => "blocked".into(),
+                    None if requested.as_ref().is_some_and(|r| !r.contains(name)) => {
+                        "not requested".into()
                     }
-                });
+                    None if !svc.autostart => "stopped".into(),
+                    None => "pending".into(),
+                };
+                let pid = handle.and_then(|h| h.state.pid());
+                let uptime_secs = handle.map_or(0, |h| h.state.uptime_secs());
+                let usage = pid.and_then(|p| usage.get(&p));
                 StatusRow {
                     name: name.clone(),
                     state,
                     pid,
-                    port: handle.map(|h| h.port).unwrap_or(svc.port),
+                    port: handle
+                        .map(|h| h.port)
+                        .or_else(|| auto_ports.get(name).copied())
+                        .unwrap_or(svc.port),
                     subdomain: svc.subdomain.clone(),
                     uptime_secs,
+                    cpu_percent: usage.map(|u| u.cpu_percent),
+                    rss_bytes: usage.map(|u| u.rss_bytes),
                     proxy_port: cfg.dev.proxy_port,
                     restart_count: handle.map(|h| h.restart_count).unwrap_or(0),
                     healthy: if svc.health.is_some() {
@@ -574,11 +1573,47 @@ impl Supervisor {
                     } else {
                         None
                     },
+                    health: match (&svc.health, handle) {
+                        (Some(check), Some(h)) if h.state.pid().is_some() => {
+                            h.health.label(check.retries)
+                        }
+                        _ => "none".into(),
+                    },
+                    last_healthy_at: handle.and_then(|h| h.health.last_ok),
+                    last_health_error: handle.and_then(|h| h.health.last_error.clone()),
+                    share_url: shares.get(name).cloned(),
+                    standby: spares.remove(name),
+                    flaky: flaky.contains(name),
+                    image: cfg.container.get(name).map(|c| c.image.clone()),
+                    blocked,
+                    start_error,
+                    exit_code: handle.and_then(|h| h.state.exit_code()),
+                    last_logs: match handle.map(|h| &h.state) {
+                        Some(ServiceState::CrashLooped { .. }) => self
+                            .log
+                            .recent(std::slice::from_ref(name), CRASH_LOOP_LOG_LINES)
+                            .into_iter()
+                            .map(|l| l.line)
+                            .collect(),
+                        _ => vec![],
+                    },
                 }
             })
             .collect()
     }
 
+    /// Health-check statistics of every service with a health check configured.
+    pub fn health_report(&self) -> Vec<HealthReportRow> {
+        let cfg = self.cfg();
+        let now = crate::health_stats::unix_now();
+        let stats = self.health_stats.lock().unwrap();
+        cfg.service
+            .iter()
+            .filter(|(_, svc)| svc.health.is_some())
+            .map(|(name, _)| stats.report(name, now, cfg.dev.flaky_threshold))
+            .collect()
+    }
+
     fn emit(&self, event: SupervisorEvent) {
         let _ = self.events.send(event);
     }
@@ -590,9 +1625,11 @@ impl Supervisor {
         svc_name: String,
         color_idx: usize,
         health_info: Option<(Arc<HealthChecker>, ServiceDef)>,
+        standby: Option<standby::StandbyCtx>,
     ) {
         let handles = self.handles.clone();
         let events = self.events.clone();
+        let health_stats = self.health_stats.clone();
         let config_cell = self.config.clone();
         let log = self.log.clone();
         let proxy = self.proxy.clone();
@@ -603,19 +1640,15 @@ impl Supervisor {
             .to_path_buf();
 
         tokio::spawn(async move {
-            // Capture assigned port once — preserves auto-assigned port across restarts
-            let assigned_port = handles
-                .read()
-                .await
-                .get(&svc_name)
-                .map(|h| h.port)
-                .unwrap_or(0);
             let mut restart_count = 0u32;
+            // Consecutive exits within `FAST_EXIT` of starting. A manual restart
+            // spawns a fresh task, which resets it.
+            let mut fast_exits = 0u32;
 
             loop {
                 // Wait for the process to exit — take the child out first so we
                 // don't hold the write lock across an async wait.
-                let child_done = {
+                let (waited_pid, started, child_done) = {
                     let mut map = handles.write().await;
                     if let Some(h) = map.get_mut(&svc_name) {
                         if !matches!(
@@ -626,10 +1659,11 @@ impl Supervisor {
                         }
                         // Replace child with a dummy so we can await outside the lock.
                         // Safety: we immediately await the real child below.
-                        Some(std::mem::replace(
+                        let child = std::mem::replace(
                             &mut h.child,
                             tokio::process::Command::new("true").spawn().unwrap(),
-                        ))
+                        );
+                        (h.state.pid(), h.state.since(), Some(child))
                     } else {
                         break;
                     }
@@ -646,11 +1680,41 @@ impl Supervisor {
                     let map = handles.read().await;
                     match map.get(&svc_name) {
                         Some(h) if matches!(h.state, ServiceState::Stopped) => break,
+                        // A standby promotion swapped in a new instance — follow it.
+                        Some(h) if h.state.pid().is_some_and(|p| Some(p) != waited_pid) => continue,
                         None => break,
                         _ => {}
                     }
                 }
 
+                // A kernel OOM kill inside the service's cgroup gets its own state so it
+                // isn't mistaken for an ordinary crash. The restart policy still applies.
+                let oom_killed = {
+                    let mut map = handles.write().await;
+                    match map.get_mut(&svc_name) {
+                        Some(h) if h.cgroup.as_ref().is_some_and(|c| c.oom_killed()) => {
+                            h.state = ServiceState::OomKilled;
+                            true
+                        }
+                        _ => false,
+                    }
+                };
+                if oom_killed {
+                    tracing::error!("[{svc_name}] killed for exceeding its memory limit");
+                    let _ = events.send(
+                        SupervisorEvent::new(&svc_name, "oom-killed")
+                            .because("exceeded its memory limit"),
+                    );
+                }
+
+                // A ready spare takes over instantly; a new spare warms in the background.
+                if let Some(ctx) = &standby {
+                    if ctx.promote().await {
+                        tracing::warn!("[{svc_name}] exited — promoted standby instance");
+                        continue;
+                    }
+                }
+
                 // Read restart policy from current service config (reflects reloads).
                 let restart_policy = config_cell
                     .read()
@@ -666,10 +1730,31 @@ impl Supervisor {
                         "[{svc_name}] exited (code={}) — on_failure=stop, not restarting",
                         code.map(|c| c.to_string()).unwrap_or_else(|| "?".into())
                     );
-                    let _ = events.send(SupervisorEvent::StateChanged {
-                        service: svc_name.clone(),
-                        state: "failed".into(),
-                    });
+                    let _ = events
+                        .send(SupervisorEvent::new(&svc_name, "failed").because(exit_reason(code)));
+                    break;
+                }
+
+                let code = exit_status.and_then(|s| s.code());
+                if started.is_some_and(|t| t.elapsed() < FAST_EXIT) {
+                    fast_exits += 1;
+                } else {
+                    fast_exits = 0;
+                }
+                if fast_exits >= restart_policy.crash_loop_after.max(1) {
+                    tracing::error!(
+                        "[{svc_name}] exited (code={}) right after starting {fast_exits} times in a row — crash-looped, not restarting",
+                        code.map(|c| c.to_string()).unwrap_or_else(|| "?".into()),
+                    );
+                    if let Some(h) = handles.write().await.get_mut(&svc_name) {
+                        h.state = ServiceState::CrashLooped { exit_code: code };
+                    }
+                    let _ = events.send(SupervisorEvent::new(&svc_name, "crash-looped").because(
+                        format!(
+                            "{} right after starting {fast_exits} times in a row",
+                            exit_reason(code)
+                        ),
+                    ));
                     break;
                 }
 
@@ -679,14 +1764,16 @@ impl Supervisor {
                         "[{svc_name}] crashed {} times — giving up",
                         restart_policy.max_restarts
                     );
-                    let _ = events.send(SupervisorEvent::StateChanged {
-                        service: svc_name.clone(),
-                        state: "failed".into(),
-                    });
+                    let _ = events.send(
+                        SupervisorEvent::new(&svc_name, "failed")
+                            .because(format!("crashed {} times", restart_policy.max_restarts)),
+                    );
                     break;
                 }
 
-                let backoff = {
+                let backoff = if fast_exits > 0 {
+                    crash_loop_backoff(fast_exits, restart_policy.max_backoff)
+                } else {
                     let base = restart_policy.backoff.as_secs().max(1);
                     let exp = base
                         .saturating_pow(restart_count)
@@ -694,17 +1781,14 @@ impl Supervisor {
                     std::time::Duration::from_secs(exp)
                 };
 
-                let code = exit_status.and_then(|s| s.code());
                 tracing::warn!(
-                    "[{svc_name}] exited (code={}) — restarting in {}s ({restart_count}/{})",
+                    "[{svc_name}] exited (code={}) — restarting in {}ms ({restart_count}/{})",
                     code.map(|c| c.to_string()).unwrap_or_else(|| "?".into()),
-                    backoff.as_secs(),
+                    backoff.as_millis(),
                     restart_policy.max_restarts,
                 );
-                let _ = events.send(SupervisorEvent::StateChanged {
-                    service: svc_name.clone(),
-                    state: "restarting".into(),
-                });
+                let _ = events
+                    .send(SupervisorEvent::new(&svc_name, "restarting").because(exit_reason(code)));
 
                 tokio::time::sleep(backoff).await;
 
@@ -712,22 +1796,22 @@ impl Supervisor {
                     Some(s) => s.clone(),
                     None => break,
                 };
-                // Use originally assigned port — avoids re-assigning a new port for port=0 services
-                let port = if assigned_port > 0 {
-                    assigned_port
-                } else {
-                    svc_def.port
-                };
+                // Reuse the currently assigned port — avoids re-assigning a new port for
+                // port=0 services (it changes only when a standby instance is promoted).
+                let port = handles
+                    .read()
+                    .await
+                    .get(&svc_name)
+                    .map(|h| h.port)
+                    .filter(|p| *p > 0)
+                    .unwrap_or(svc_def.port);
 
                 // Resolve ${other.port} references at restart time.
                 let resolved_def = {
-                    let runtime_ports: HashMap<String, u16> = handles
-                        .read()
-                        .await
-                        .iter()
-                        .map(|(n, h)| (n.clone(), h.port))
-                        .collect();
-                    crate::config::resolve_service_ports(svc_def.clone(), &runtime_ports)
+                    let cfg = Arc::clone(&config_cell.read().unwrap());
+                    let mut ports = runtime_ports(&handles, &cfg).await;
+                    ports.insert(svc_name.clone(), port);
+                    crate::config::resolve_service_ports(svc_def.clone(), &ports)
                 };
 
                 let spec = SpawnSpec {
@@ -736,15 +1820,22 @@ impl Supervisor {
                     port,
                     color_idx,
                     config_dir: &config_dir,
+                    standby: false,
                 };
                 match spawn_process(&spec, &log).await {
                     Ok(result) => {
                         if let Some(sub) = &svc_def.subdomain {
                             proxy.update(sub.clone(), port).await;
                         }
+                        // The new instance isn't served until its health check passes.
+                        gate_proxy(&proxy, &svc_def, true).await;
                         let mut map = handles.write().await;
                         let prev_restart_count =
                             map.get(&svc_name).map(|h| h.restart_count).unwrap_or(0);
+                        let prev_health = map
+                            .get(&svc_name)
+                            .map(|h| h.health.restarted())
+                            .unwrap_or_default();
                         map.insert(
                             svc_name.clone(),
                             ServiceHandle {
@@ -757,12 +1848,11 @@ impl Supervisor {
                                 port,
                                 watcher_stop: None,
                                 restart_count: prev_restart_count + 1,
+                                cgroup: result.cgroup,
+                                health: prev_health,
                             },
                         );
-                        let _ = events.send(SupervisorEvent::StateChanged {
-                            service: svc_name.clone(),
-                            state: "running".into(),
-                        });
+                        let _ = events.send(SupervisorEvent::new(&svc_name, "running"));
                         // Re-arm health monitor for the restarted process.
                         if let Some((ref checker, ref svc_def_h)) = health_info {
                             run_health_monitor(
@@ -771,6 +1861,9 @@ impl Supervisor {
                                 svc_def_h.clone(),
                                 handles.clone(),
                                 events.clone(),
+                                health_stats.clone(),
+                                proxy.clone(),
+                                true,
                             );
                         }
                         restart_count = 0;
@@ -784,31 +1877,73 @@ impl Supervisor {
         });
     }
 
-    /// Spawn a task that watches files and restarts the service on change.
+    /// Spawn a task that watches files and restarts the service on change: the
+    /// `watch` paths and, if given, `env_file`, whose variables are re-read first.
     /// Returns a sender that stops the watcher when any value is sent.
     fn spawn_file_watcher(
         &self,
         svc_name: String,
-        paths: Vec<std::path::PathBuf>,
-        ignore: Vec<String>,
+        watch: Option<crate::config::WatchConfig>,
+        env_file: Option<std::path::PathBuf>,
+        standby: Option<standby::StandbyCtx>,
     ) -> std::sync::mpsc::SyncSender<()> {
         let handles = self.handles.clone();
         let events = self.events.clone();
         let config_cell = self.config.clone();
+        let config_path = self.config_path.clone();
+        let env_name = self.env_name.clone();
         let log = self.log.clone();
         let config_dir = self
             .config_path
             .parent()
             .unwrap_or(std::path::Path::new("."))
             .to_path_buf();
+        let log_dir = config_dir.join(&self.cfg().dev.log_dir);
 
         let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(8);
-        let stop_tx = spawn_watcher(svc_name.clone(), paths, ignore, tx);
+        let mut stops = vec![];
+        if let Some(w) = &watch {
+            stops.push(spawn_watcher(
+                svc_name.clone(),
+                w.paths.clone(),
+                w.ignore.clone(),
+                w.use_gitignore,
+                vec![log_dir],
+                w.debounce,
+                tx.clone(),
+            ));
+        }
+        let reload_env = env_file.is_some();
+        if let Some(f) = env_file {
+            // Watched on its own: a .env is usually gitignored, which must not hide it.
+            let debounce = watch.as_ref().map_or(ENV_FILE_DEBOUNCE, |w| w.debounce);
+            stops.push(crate::watcher::watch_file(
+                svc_name.clone(),
+                f,
+                debounce,
+                tx,
+            ));
+        }
+        let stop_tx = fan_out_stop(stops);
         // Clone so the task can propagate watcher_stop to restarted service handles.
         let task_stop_tx = stop_tx.clone();
 
         tokio::spawn(async move {
             while let Some(changed_svc) = rx.recv().await {
+                if reload_env {
+                    let env = env_name.read().unwrap().clone();
+                    refresh_env(&config_cell, &config_path, env.as_deref(), &changed_svc);
+                }
+                // The spare runs the old code, so start a fresh instance, swap it in once
+                // healthy, then re-warm the spare.
+                if let Some(ctx) = &standby {
+                    tracing::info!("[{changed_svc}] file change — replacing instance");
+                    match ctx.replace_fresh().await {
+                        Ok(()) => ctx.warm(),
+                        Err(e) => tracing::error!("[{changed_svc}] {e}"),
+                    }
+                    continue;
+                }
                 tracing::info!("[{changed_svc}] file change — restarting");
 
                 let (color_idx, port) = {
@@ -824,14 +1959,17 @@ impl Supervisor {
                     }
                 };
 
-                let _ = events.send(SupervisorEvent::StateChanged {
-                    service: changed_svc.clone(),
-                    state: "restarting".into(),
-                });
+                let _ = events
+                    .send(SupervisorEvent::new(&changed_svc, "restarting").because("file changed"));
 
-                let svc_def = match config_cell.read().unwrap().service.get(&changed_svc) {
-                    Some(s) => s.clone(),
-                    None => continue,
+                let cfg = Arc::clone(&config_cell.read().unwrap());
+                let Some(svc_def) = cfg.service.get(&changed_svc).cloned() else {
+                    continue;
+                };
+                let svc_def = {
+                    let mut ports = runtime_ports(&handles, &cfg).await;
+                    ports.insert(changed_svc.clone(), port);
+                    crate::config::resolve_service_ports(svc_def, &ports)
                 };
 
                 let spec = SpawnSpec {
@@ -840,10 +1978,16 @@ impl Supervisor {
                     port,
                     color_idx,
                     config_dir: &config_dir,
+                    standby: false,
                 };
                 match spawn_process(&spec, &log).await {
                     Ok(result) => {
-                        handles.write().await.insert(
+                        let mut map = handles.write().await;
+                        let health = map
+                            .get(&changed_svc)
+                            .map(|h| h.health.restarted())
+                            .unwrap_or_default();
+                        map.insert(
                             changed_svc.clone(),
                             ServiceHandle {
                                 child: result.child,
@@ -857,12 +2001,12 @@ impl Supervisor {
                                 // watcher even after a file-watcher-triggered restart.
                                 watcher_stop: Some(task_stop_tx.clone()),
                                 restart_count: 0,
+                                cgroup: result.cgroup,
+                                health,
                             },
                         );
-                        let _ = events.send(SupervisorEvent::StateChanged {
-                            service: changed_svc.clone(),
-                            state: "running".into(),
-                        });
+                        drop(map);
+                        let _ = events.send(SupervisorEvent::new(&changed_svc, "running"));
                     }
                     Err(e) => {
                         tracing::error!("[{changed_svc}] restart failed: {e}");
@@ -877,15 +2021,22 @@ impl Supervisor {
     /// Hot-reload: apply a new config without a full restart.
     ///
     /// - Services removed from the new config (or newly `disabled`) are stopped.
-    /// - Services whose config changed are restarted.
+    /// - Services whose config changed are restarted, unless `restart` is false: then
+    ///   their running definition stays in the config, so status shows what actually
+    ///   runs and a later reload still sees the change.
     /// - Services newly added (and not `disabled`) are started.
     /// - Unchanged running services are left alone.
-    pub async fn reload(&self, new_config: Arc<DevConfig>) -> Result<ReloadSummary> {
+    ///
+    /// A config that doesn't validate is rejected before anything is touched.
+    pub async fn reload(&self, new_config: Arc<DevConfig>, restart: bool) -> Result<ReloadSummary> {
+        new_config.validate()?;
+        let graph = DependencyGraph::from_config(&new_config)?;
         let old_config = self.cfg();
         let mut summary = ReloadSummary {
             started: vec![],
             stopped: vec![],
             restarted: vec![],
+            not_restarted: vec![],
         };
 
         // 1. Stop removed / newly-disabled services.
@@ -899,11 +2050,22 @@ impl Supervisor {
             }
         }
 
-        // 2. Swap in the new config so start_service sees it.
-        *self.config.write().unwrap() = Arc::clone(&new_config);
+        // 2. Swap in the new config so start_service sees it, keeping the running
+        //    definition of changed services that aren't restarted.
+        let mut applied = (*new_config).clone();
+        if !restart {
+            for (name, new_svc) in &new_config.service {
+                let Some(old_svc) = old_config.service.get(name) else {
+                    continue;
+                };
+                if old_svc != new_svc && !new_svc.disabled && self.is_running(name).await {
+                    applied.service.insert(name.clone(), old_svc.clone());
+                }
+            }
+        }
+        *self.config.write().unwrap() = Arc::new(applied);
 
         // 3. Restart changed services and start new ones in dependency order.
-        let graph = DependencyGraph::from_config(&new_config)?;
         for (idx, name) in graph.start_order().iter().enumerate() {
             let Some(new_svc) = new_config.service.get(name) else {
                 continue;
@@ -911,10 +2073,19 @@ impl Supervisor {
             if new_svc.disabled {
                 continue;
             }
+            // On-demand and unrequested services are only restarted if someone
+            // started them.
+            if (!new_svc.autostart || !self.is_requested(name)) && !self.is_running(name).await {
+                continue;
+            }
             match old_config.service.get(name) {
                 Some(old_svc) if old_svc == new_svc => {
                     // Unchanged — leave running.
                 }
+                Some(_) if !restart => {
+                    tracing::info!("[{name}] config changed — not restarting");
+                    summary.not_restarted.push(name.clone());
+                }
                 Some(_) => {
                     tracing::info!("[{name}] config changed — restarting");
                     self.stop_service(name).await;
@@ -948,18 +2119,29 @@ mod tests {
             subdomain: None,
             env: Default::default(),
             env_file: None,
+            env_reload: false,
+            shell: None,
             log_file: None,
             log_rotate_mb: 0,
+            log_max_size: None,
+            log_keep: 1,
             pre_start: None,
             post_stop: None,
+            hook_timeout: std::time::Duration::from_secs(300),
             depends_on: deps.iter().map(|s| s.to_string()).collect(),
+            depends_on_timeout: None,
             watch: None,
             health: None,
             restart: Default::default(),
             stop_timeout: std::time::Duration::from_secs(1),
             disabled: false,
+            autostart: true,
             labels: vec![],
+            standby: false,
+            limits: None,
             k8s: None,
+            lint_ignore: vec![],
+            env_secret: Default::default(),
         }
     }
 
@@ -969,9 +2151,17 @@ mod tests {
             map.insert(name.to_string(), def);
         }
         Arc::new(DevConfig {
-            dev: GlobalSettings::default(),
+            // Keep test runs' log files out of the working tree.
+            dev: GlobalSettings {
+                log_dir: std::env::temp_dir().join("a3s-supervisor-test-logs"),
+                ..Default::default()
+            },
             service: map,
+            container: Default::default(),
             env_override: Default::default(),
+            environment: Default::default(),
+            include: vec![],
+            sources: Default::default(),
         })
     }
 
@@ -996,6 +2186,230 @@ mod tests {
         assert_eq!(rows[0].state, "pending");
     }
 
+    #[tokio::test]
+    async fn test_sample_metrics_of_a_running_service() {
+        let sup = make_supervisor(make_config(vec![
+            ("web", svc("sleep 60", vec![])),
+            ("idle", svc("sleep 60", vec![])),
+        ]));
+        sup.start_service("web", 0).await.unwrap();
+        let pid = sup.status_rows().await[0].pid.unwrap();
+
+        let metrics = sample_metrics(&sup.handles).await;
+        assert_eq!(metrics.len(), 1, "only running services: {metrics:?}");
+        assert_eq!((metrics[0].service.as_str(), metrics[0].pid), ("web", pid));
+        assert!(metrics[0].rss_bytes > 0);
+        assert!(metrics[0].cpu_percent >= 0.0);
+
+        sup.stop_all().await;
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parse_signal_maps_names_to_signal_numbers() {
+        for (name, num) in [
+            ("SIGHUP", libc::SIGHUP),
+            ("hup", libc::SIGHUP),
+            ("SIGUSR1", libc::SIGUSR1),
+            ("usr2", libc::SIGUSR2),
+            ("SigTerm", libc::SIGTERM),
+        ] {
+            assert_eq!(parse_signal(name).unwrap() as i32, num, "{name}");
+        }
+        assert!(matches!(parse_signal("SIGNOPE"), Err(DevError::Config(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_kill_service_signals_without_stopping() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("got");
+        let cmd = format!(
+            "trap 'echo hup >> {}' HUP; while true; do sleep 0.05; done",
+            out.display()
+        );
+        let sup = make_supervisor(make_config(vec![
+            ("web", svc(&cmd, vec![])),
+            ("idle", svc("sleep 60", vec![])),
+        ]));
+        sup.start_service("web", 0).await.unwrap();
+        let pid = sup.status_rows().await[0].pid;
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        sup.kill_service("web", "SIGHUP").await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "hup\n");
+        let rows = sup.status_rows().await;
+        assert_eq!((rows[0].state.as_str(), rows[0].pid), ("running", pid));
+
+        assert!(matches!(
+            sup.kill_service("web", "SIGNOPE").await,
+            Err(DevError::Config(_))
+        ));
+        assert!(matches!(
+            sup.kill_service("idle", "SIGHUP").await,
+            Err(DevError::Config(_))
+        ));
+        assert!(matches!(
+            sup.kill_service("nope", "SIGHUP").await,
+            Err(DevError::UnknownService(_))
+        ));
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_batch_answers_in_request_order_and_rejects_nesting() {
+        use crate::ipc::{IpcRequest, IpcResponse, PROTOCOL_VERSION};
+
+        let sup = make_supervisor(make_config(vec![("web", svc("sleep 60", vec![]))]));
+        sup.start_service("web", 0).await.unwrap();
+
+        // The reads after the restart wait for it, and every answer keeps its place.
+        let resp = ipc::batch(
+            &sup,
+            vec![
+                IpcRequest::Restart {
+                    service: "web".into(),
+                },
+                IpcRequest::Kill {
+                    service: "nope".into(),
+                    signal: "HUP".into(),
+                },
+                IpcRequest::Hello {
+                    version: PROTOCOL_VERSION,
+                    token: None,
+                },
+                IpcRequest::Batch {
+                    requests: vec![IpcRequest::Status],
+                },
+                IpcRequest::Events { services: vec![] },
+                IpcRequest::Status,
+            ],
+        )
+        .await;
+        let IpcResponse::Batch { responses } = resp else {
+            panic!("wrong variant: {resp:?}");
+        };
+        assert!(
+            matches!(
+                &responses[..],
+                [
+                    IpcResponse::Ok,
+                    IpcResponse::Error { .. },
+                    IpcResponse::Hello { .. },
+                    IpcResponse::Error { .. },
+                    IpcResponse::Error { .. },
+                    IpcResponse::Status { .. },
+                ]
+            ),
+            "{responses:?}"
+        );
+        let IpcResponse::Error { msg } = &responses[3] else {
+            unreachable!()
+        };
+        assert!(msg.contains("nested"), "{msg}");
+        assert_eq!(sup.status_rows().await[0].state, "running");
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_changes_in_order_and_is_capped() {
+        use crate::ipc::{IpcRequest, IpcResponse};
+
+        let sup = make_supervisor(make_config(vec![("web", svc("sleep 60", vec![]))]));
+        sup.start_service("web", 0).await.unwrap();
+
+        let web = || vec!["web".to_string()];
+        let resp = ipc::batch(
+            &sup,
+            vec![
+                IpcRequest::Stop { services: web() },
+                IpcRequest::Status,
+                IpcRequest::Start { services: web() },
+                IpcRequest::Status,
+            ],
+        )
+        .await;
+        let IpcResponse::Batch { responses } = resp else {
+            panic!("wrong variant: {resp:?}");
+        };
+        let state = |resp: &IpcResponse| match resp {
+            IpcResponse::Status { rows } => rows[0].state.clone(),
+            other => panic!("wrong variant: {other:?}"),
+        };
+        assert_eq!(state(&responses[1]), "stopped");
+        assert_eq!(state(&responses[3]), "running");
+
+        let resp = ipc::batch(
+            &sup,
+            (0..=ipc::MAX_BATCH).map(|_| IpcRequest::Status).collect(),
+        )
+        .await;
+        let IpcResponse::Error { msg } = resp else {
+            panic!("wrong variant: {resp:?}");
+        };
+        assert!(msg.contains("limit"), "{msg}");
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_auto_ports_are_stable_and_never_shared() {
+        let sup = make_supervisor(make_config(vec![
+            ("web", svc("sleep 60", vec![])),
+            ("api", svc("sleep 60", vec![])),
+        ]));
+        // `api` already holds the port `web` would prefer.
+        sup.auto_ports
+            .lock()
+            .unwrap()
+            .insert("api".into(), spawn::preferred_port("web"));
+        sup.start_service("web", 0).await.unwrap();
+        sup.start_service("api", 1).await.unwrap();
+        let port =
+            |rows: &[StatusRow], name: &str| rows.iter().find(|r| r.name == name).unwrap().port;
+        let rows = sup.status_rows().await;
+        let (web, api) = (port(&rows, "web"), port(&rows, "api"));
+        assert_eq!(api, spawn::preferred_port("web"));
+        assert!(web != 0 && web != api, "web {web}, api {api}");
+
+        // Stopped, the service keeps its port and gets it back.
+        sup.stop_service("web").await;
+        assert_eq!(port(&sup.status_rows().await, "web"), web);
+        sup.start_service("web", 0).await.unwrap();
+        assert_eq!(port(&sup.status_rows().await, "web"), web);
+
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_events_carry_old_and_new_state() {
+        let sup = make_supervisor(make_config(vec![("web", svc("sleep 60", vec![]))]));
+        let mut rx = sup.subscribe_events();
+        sup.start_service("web", 0).await.unwrap();
+        sup.stop_service("web").await;
+
+        let mut seen = Vec::new();
+        while seen.len() < 4 {
+            let e = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+                .await
+                .expect("event")
+                .unwrap();
+            assert_eq!(e.service, "web");
+            assert!(e.ts_ms > 0);
+            seen.push((e.old_state, e.new_state));
+        }
+        let s = |v: &str| Some(v.to_string());
+        assert_eq!(
+            seen,
+            [
+                (None, "starting".to_string()),
+                (s("starting"), "running".to_string()),
+                (s("running"), "stopping".to_string()),
+                (s("stopping"), "stopped".to_string()),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_start_and_stop_service() {
         let sup = make_supervisor(make_config(vec![("web", svc("sleep 60", vec![]))]));
@@ -1009,6 +2423,28 @@ mod tests {
         assert_eq!(rows[0].state, "stopped", "expected stopped after stop");
     }
 
+    #[tokio::test]
+    async fn test_failing_pre_start_prevents_running() {
+        let mut web = svc("sleep 60", vec![]);
+        web.pre_start = Some("false".into());
+        let sup = make_supervisor(make_config(vec![("web", web)]));
+        assert!(sup.start_service("web", 0).await.is_err());
+        assert_ne!(sup.status_rows().await[0].state, "running");
+    }
+
+    #[tokio::test]
+    async fn test_pre_start_timeout_aborts_start() {
+        let mut web = svc("sleep 60", vec![]);
+        web.pre_start = Some("sleep 30".into());
+        web.hook_timeout = std::time::Duration::from_millis(200);
+        let sup = make_supervisor(make_config(vec![("web", web)]));
+        let started = Instant::now();
+        let err = sup.start_service("web", 0).await.unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert_ne!(sup.status_rows().await[0].state, "running");
+    }
+
     #[tokio::test]
     async fn test_start_unknown_service_errors() {
         let sup = make_supervisor(make_config(vec![]));
@@ -1032,6 +2468,36 @@ mod tests {
         sup.stop_all().await;
     }
 
+    #[tokio::test]
+    async fn test_auto_ports_exported_to_service_and_dependents() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("env.txt");
+        let api_cmd = format!(
+            "sh -c 'echo \"$PORT $A3S_SERVICE_DB_PORT\" > {}; sleep 60'",
+            out.display()
+        );
+        let sup = make_supervisor(make_config(vec![
+            ("db", svc("sleep 60", vec![])),
+            ("api", svc(&api_cmd, vec!["db"])),
+        ]));
+        sup.clone().start_all().await.unwrap();
+
+        let mut seen = String::new();
+        for _ in 0..50 {
+            seen = std::fs::read_to_string(&out).unwrap_or_default();
+            if !seen.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let rows = sup.status_rows().await;
+        let port = |name: &str| rows.iter().find(|r| r.name == name).unwrap().port;
+        assert_ne!(port("db"), 0);
+        assert_eq!(seen.trim(), format!("{} {}", port("api"), port("db")));
+
+        sup.stop_all().await;
+    }
+
     #[tokio::test]
     async fn test_start_named_resolves_deps() {
         // b depends on a → start_named(["b"]) must also start a
@@ -1050,6 +2516,118 @@ mod tests {
         sup.stop_all().await;
     }
 
+    #[tokio::test]
+    async fn test_start_services_reports_each_outcome() {
+        let mut broken = svc("sleep 60", vec![]);
+        broken.pre_start = Some("false".into());
+        let sup = make_supervisor(make_config(vec![
+            ("a", svc("sleep 60", vec![])),
+            ("b", svc("sleep 60", vec!["a"])),
+            ("c", broken),
+        ]));
+        sup.clone().start_named(&["a".to_string()]).await.unwrap();
+
+        let outcomes = |results: Vec<StartResult>| {
+            results
+                .into_iter()
+                .map(|r| (r.service, r.outcome, r.error.is_some()))
+                .collect::<Vec<_>>()
+        };
+        let results = sup
+            .start_services(&["b".to_string(), "c".to_string()])
+            .await
+            .unwrap();
+        let mut results = outcomes(results);
+        results.sort();
+        assert_eq!(
+            results,
+            [
+                ("a".into(), "already running".into(), false),
+                ("b".into(), "started".into(), false),
+                ("c".into(), "failed".into(), true),
+            ]
+        );
+
+        // A stopped service comes back; its running dependency is left alone.
+        sup.stop_service("b").await;
+        let results = sup.start_services(&["b".to_string()]).await.unwrap();
+        assert_eq!(
+            outcomes(results),
+            [
+                ("a".into(), "already running".into(), false),
+                ("b".into(), "started".into(), false),
+            ]
+        );
+        assert!(sup.start_services(&["nope".to_string()]).await.is_err());
+
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_start_services_starts_dependencies_first_and_is_idempotent() {
+        let sup = make_supervisor(make_config(vec![
+            ("app", svc("sleep 60", vec!["api"])),
+            ("api", svc("sleep 60", vec!["db"])),
+            ("db", svc("sleep 60", vec![])),
+            ("other", svc("sleep 60", vec![])),
+        ]));
+        let names = |results: &[StartResult]| {
+            results
+                .iter()
+                .map(|r| (r.service.clone(), r.outcome.clone()))
+                .collect::<Vec<_>>()
+        };
+        let first = sup.start_services(&["app".to_string()]).await.unwrap();
+        assert_eq!(
+            names(&first),
+            [
+                ("db".into(), "started".into()),
+                ("api".into(), "started".into()),
+                ("app".into(), "started".into()),
+            ]
+        );
+        let rows = sup.status_rows().await;
+        let other = rows.iter().find(|r| r.name == "other").unwrap();
+        assert_eq!(other.state, "pending", "unrelated services stay down");
+        let pids: Vec<_> = rows.iter().map(|r| r.pid).collect();
+
+        // Asking again changes nothing.
+        let again = sup.start_services(&["app".to_string()]).await.unwrap();
+        assert!(again.iter().all(|r| r.outcome == "already running"));
+        let pids_after: Vec<_> = sup.status_rows().await.iter().map(|r| r.pid).collect();
+        assert_eq!(pids, pids_after);
+
+        // Over IPC the no-op is still a success, answered per service rather than
+        // with a bare `Ok`, so the CLI can say "already running"; unknown names fail.
+        use crate::ipc::{IpcRequest, IpcResponse};
+        let resp = ipc::batch(
+            &sup,
+            vec![
+                IpcRequest::Start {
+                    services: vec!["app".into()],
+                },
+                IpcRequest::Start {
+                    services: vec!["nope".into()],
+                },
+            ],
+        )
+        .await;
+        let IpcResponse::Batch { responses } = resp else {
+            panic!("wrong variant: {resp:?}");
+        };
+        let IpcResponse::Started { results } = &responses[0] else {
+            panic!("wrong variant: {:?}", responses[0]);
+        };
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.outcome == "already running"));
+        assert!(
+            matches!(responses[1], IpcResponse::Error { .. }),
+            "{responses:?}"
+        );
+
+        sup.stop_all().await;
+    }
+
     #[tokio::test]
     async fn test_start_named_skips_unrequested_services() {
         // a and c are independent; requesting only a should not start c
@@ -1069,11 +2647,369 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_restart_service() {
-        let sup = make_supervisor(make_config(vec![("web", svc("sleep 60", vec![]))]));
-        sup.start_service("web", 0).await.unwrap();
-
-        let pid_before = sup.status_rows().await[0].pid;
+    async fn test_start_requested_marks_the_rest_not_requested() {
+        let sup = make_supervisor(make_config(vec![
+            ("a", svc("sleep 60", vec![])),
+            ("b", svc("sleep 60", vec!["a"])),
+            ("c", svc("sleep 60", vec![])),
+        ]));
+        let err = sup
+            .clone()
+            .start_requested(&["b".to_string(), "nope".to_string()])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown service 'nope'"), "{err}");
+        assert!(sup.handles.read().await.is_empty());
+
+        sup.clone()
+            .start_requested(&["b".to_string()])
+            .await
+            .unwrap();
+        let state =
+            |rows: &[StatusRow], n: &str| rows.iter().find(|r| r.name == n).unwrap().state.clone();
+        let rows = sup.status_rows().await;
+        assert_eq!(state(&rows, "a"), "running");
+        assert_eq!(state(&rows, "b"), "running");
+        assert_eq!(state(&rows, "c"), "not requested");
+
+        // A later `a3s up c` adds to the running set.
+        sup.clone().start_named(&["c".to_string()]).await.unwrap();
+        assert_eq!(state(&sup.status_rows().await, "c"), "running");
+        sup.stop_service("c").await;
+        assert_eq!(state(&sup.status_rows().await, "c"), "stopped");
+
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_autostart_false_starts_on_demand() {
+        let on_demand = |deps| ServiceDef {
+            autostart: false,
+            ..svc("sleep 60", deps)
+        };
+        let sup = make_supervisor(make_config(vec![
+            ("db", on_demand(vec![])),
+            ("api", svc("sleep 60", vec!["db"])),
+            ("migrate", on_demand(vec!["db"])),
+            ("loadgen", on_demand(vec!["api"])),
+        ]));
+        sup.clone().start_all().await.unwrap();
+
+        let state = |rows: &[StatusRow], name: &str| {
+            rows.iter().find(|r| r.name == name).unwrap().state.clone()
+        };
+        let rows = sup.status_rows().await;
+        // db is pulled up by api; the other two only register.
+        assert_eq!(state(&rows, "db"), "running");
+        assert_eq!(state(&rows, "api"), "running");
+        assert_eq!(state(&rows, "migrate"), "stopped");
+        assert_eq!(state(&rows, "loadgen"), "stopped");
+
+        sup.restart_service("migrate").await.unwrap();
+        sup.restart_service("api").await.unwrap();
+        let rows = sup.status_rows().await;
+        assert_eq!(state(&rows, "migrate"), "running");
+        assert_eq!(state(&rows, "api"), "running");
+        // Restarting api must not start its on-demand dependent.
+        assert_eq!(state(&rows, "loadgen"), "stopped");
+
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_proxy_serves_starting_page_until_healthy() {
+        use crate::config::{HealthConfig, HealthKind};
+        // db never listens, so it stays spawned but unhealthy.
+        let db = ServiceDef {
+            subdomain: Some("db".into()),
+            health: Some(HealthConfig {
+                kind: HealthKind::Tcp,
+                path: None,
+                interval: std::time::Duration::from_millis(100),
+                timeout: std::time::Duration::from_millis(50),
+                retries: 2,
+                start_period: None,
+                startup_retries: None,
+            }),
+            ..svc("sleep 60", vec![])
+        };
+        let web = ServiceDef {
+            subdomain: Some("web".into()),
+            ..svc("sleep 60", vec![])
+        };
+        let sup = make_supervisor(make_config(vec![("db", db), ("web", web)]));
+        sup.start_service("db", 0).await.unwrap();
+        sup.start_service("web", 1).await.unwrap();
+
+        assert!(sup.proxy.is_starting("db").await);
+        // Without a health check a service is ready once its process is up.
+        assert!(!sup.proxy.is_starting("web").await);
+
+        sup.stop_service("db").await;
+        assert!(!sup.proxy.is_starting("db").await);
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_status_rows_report_health_state_and_last_failure() {
+        use crate::config::{HealthConfig, HealthKind};
+        // db never listens, so its startup checks fail.
+        let db = ServiceDef {
+            health: Some(HealthConfig {
+                kind: HealthKind::Tcp,
+                path: None,
+                interval: std::time::Duration::from_millis(50),
+                timeout: std::time::Duration::from_millis(50),
+                retries: 2,
+                start_period: None,
+                startup_retries: None,
+            }),
+            ..svc("sleep 60", vec![])
+        };
+        let sup = make_supervisor(make_config(vec![
+            ("db", db),
+            ("web", svc("sleep 60", vec![])),
+        ]));
+        sup.start_service("db", 0).await.unwrap();
+        sup.start_service("web", 1).await.unwrap();
+
+        let rows = sup.status_rows().await;
+        let row = |name: &str| rows.iter().find(|r| r.name == name).unwrap();
+        assert_eq!(row("db").health, "unhealthy (2/2 failures)");
+        let reason = row("db").last_health_error.clone().unwrap();
+        assert!(reason.starts_with("connecting to :"), "{reason}");
+        assert_eq!(row("db").last_healthy_at, None);
+        assert_eq!(row("web").health, "none");
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_restarted_instance_gets_the_startup_grace() {
+        use crate::config::{HealthConfig, HealthKind};
+        let def = ServiceDef {
+            health: Some(HealthConfig {
+                kind: HealthKind::Tcp,
+                path: None,
+                interval: std::time::Duration::from_millis(20),
+                timeout: std::time::Duration::from_millis(50),
+                retries: 1,
+                start_period: Some(std::time::Duration::from_millis(400)),
+                startup_retries: Some(2),
+            }),
+            ..svc("sleep 60", vec![])
+        };
+        // Nothing listens on a port just released.
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let checker = Arc::new(HealthChecker::for_service(&def).unwrap());
+        let sup = make_supervisor(make_config(vec![("db", def.clone())]));
+
+        let state_after = |restarted: bool, wait_ms: u64| {
+            let (sup, checker, def) = (sup.clone(), checker.clone(), def.clone());
+            async move {
+                let child = tokio::process::Command::new("sleep")
+                    .arg("60")
+                    .process_group(0)
+                    .spawn()
+                    .unwrap();
+                let pid = child.id().unwrap();
+                sup.handles.write().await.insert(
+                    "db".into(),
+                    ServiceHandle {
+                        child,
+                        state: ServiceState::Running {
+                            pid,
+                            since: Instant::now(),
+                        },
+                        color_idx: 0,
+                        port,
+                        watcher_stop: None,
+                        restart_count: 0,
+                        cgroup: None,
+                        health: HealthProgress::default(),
+                    },
+                );
+                run_health_monitor(
+                    "db".into(),
+                    checker,
+                    def,
+                    sup.handles.clone(),
+                    sup.events.clone(),
+                    sup.health_stats.clone(),
+                    sup.proxy.clone(),
+                    restarted,
+                );
+                tokio::time::sleep(std::time::Duration::from_millis(wait_ms)).await;
+                let mut map = sup.handles.write().await;
+                let h = map.get_mut("db").unwrap();
+                let label = h.state.label().to_string();
+                let _ = h.child.kill().await;
+                label
+            }
+        };
+
+        // Steady state: one failure is enough.
+        assert_eq!(state_after(false, 200).await, "unhealthy");
+        // A fresh instance isn't judged within its start period...
+        assert_eq!(state_after(true, 200).await, "running");
+        // ...and only after startup_retries failures past it.
+        assert_eq!(state_after(true, 700).await, "unhealthy");
+    }
+
+    #[test]
+    fn test_health_progress_labels_and_restarts() {
+        let mut health = HealthProgress::default();
+        assert_eq!(health.label(3), "starting");
+        health.record(Ok(()));
+        assert_eq!(health.label(3), "healthy");
+        assert!(health.last_ok.is_some());
+        health.record(Err("GET /health returned 500".into()));
+        assert_eq!(health.label(3), "unhealthy (1/3 failures)");
+
+        // A restarted instance starts over, but keeps what was last seen.
+        let restarted = health.restarted();
+        assert_eq!(restarted.label(3), "starting");
+        assert_eq!(restarted.last_ok, health.last_ok);
+        assert_eq!(
+            restarted.last_error.as_deref(),
+            Some("GET /health returned 500")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unresolvable_secret_fails_only_that_service() {
+        let db = ServiceDef {
+            env_secret: [("TOKEN".to_string(), "exit 3".to_string())].into(),
+            ..svc("sleep 60", vec![])
+        };
+        let sup = make_supervisor(make_config(vec![
+            ("db", db),
+            ("api", svc("sleep 60", vec!["db"])),
+            ("web", svc("sleep 60", vec![])),
+        ]));
+        let err = sup.clone().start_all().await.unwrap_err();
+        assert!(err.to_string().contains("secret TOKEN"), "{err}");
+
+        let rows = sup.status_rows().await;
+        let row = |name: &str| rows.iter().find(|r| r.name == name).unwrap();
+        assert_eq!(row("db").state, "failed");
+        let why = row("db").start_error.clone().unwrap();
+        assert!(why.contains("secret TOKEN"), "{why}");
+        assert_eq!(row("api").state, "blocked");
+        assert_eq!(row("web").state, "running");
+        assert_eq!(row("web").start_error, None);
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_depends_on_timeout_blocks_only_the_dependent() {
+        use crate::config::{HealthConfig, HealthKind};
+        // db never listens, so its startup health wait runs for ~1s.
+        let db = ServiceDef {
+            health: Some(HealthConfig {
+                kind: HealthKind::Tcp,
+                path: None,
+                interval: std::time::Duration::from_millis(100),
+                timeout: std::time::Duration::from_millis(50),
+                retries: 10,
+                start_period: None,
+                startup_retries: None,
+            }),
+            ..svc("sleep 60", vec![])
+        };
+        let api = ServiceDef {
+            depends_on_timeout: Some(std::time::Duration::from_millis(200)),
+            ..svc("sleep 60", vec!["db"])
+        };
+        let sup = make_supervisor(make_config(vec![
+            ("db", db),
+            ("api", api),
+            ("worker", svc("sleep 60", vec!["api"])),
+            ("other", svc("sleep 60", vec![])),
+        ]));
+        sup.clone().start_all().await.unwrap();
+
+        let rows = sup.status_rows().await;
+        let row = |name: &str| rows.iter().find(|r| r.name == name).unwrap();
+        assert_eq!(row("db").state, "running");
+        assert_eq!(row("other").state, "running");
+        assert_eq!(row("api").state, "blocked");
+        assert_eq!(
+            row("api").blocked.as_deref(),
+            Some("db not ready after 200ms")
+        );
+        assert_eq!(row("worker").state, "blocked");
+        assert_eq!(row("worker").blocked.as_deref(), Some("api did not start"));
+
+        // Starting it by hand later clears the block.
+        sup.restart_service("api").await.unwrap();
+        let rows = sup.status_rows().await;
+        let api = rows.iter().find(|r| r.name == "api").unwrap();
+        assert_eq!(
+            (api.state.as_str(), api.blocked.as_ref()),
+            ("running", None)
+        );
+
+        sup.stop_all().await;
+    }
+
+    #[test]
+    fn test_crash_loop_backoff_doubles_up_to_cap() {
+        let cap = std::time::Duration::from_secs(30);
+        let ms = |n| crash_loop_backoff(n, cap).as_millis();
+        assert_eq!((ms(1), ms(2), ms(3), ms(4)), (250, 500, 1000, 2000));
+        assert_eq!(crash_loop_backoff(10, cap), cap);
+        assert_eq!(crash_loop_backoff(u32::MAX, cap), cap);
+        let small = std::time::Duration::from_millis(400);
+        assert_eq!(crash_loop_backoff(2, small), small);
+    }
+
+    #[tokio::test]
+    async fn test_fast_exits_crash_loop_until_restarted() {
+        let mut web = svc("sh -c 'echo boom; exit 3'", vec![]);
+        web.restart.crash_loop_after = 3;
+        let sup = make_supervisor(make_config(vec![("web", web)]));
+        sup.start_service("web", 0).await.unwrap();
+
+        let mut row = None;
+        for _ in 0..100 {
+            let r = sup.status_rows().await.remove(0);
+            if r.state == "crash-looped" {
+                row = Some(r);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        let row = row.expect("three fast exits should mark the service crash-looped");
+        assert_eq!((row.pid, row.exit_code), (None, Some(3)));
+        assert!(
+            row.last_logs.iter().any(|l| l == "boom"),
+            "{:?}",
+            row.last_logs
+        );
+
+        // Stays down: no more restarts once crash-looped.
+        tokio::time::sleep(std::time::Duration::from_millis(600)).await;
+        assert_eq!(sup.status_rows().await[0].state, "crash-looped");
+
+        // A manual restart tries again at once.
+        sup.restart_service("web").await.unwrap();
+        let row = sup.status_rows().await.remove(0);
+        assert_eq!(row.state, "running");
+        assert!(row.last_logs.is_empty());
+
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_restart_service() {
+        let sup = make_supervisor(make_config(vec![("web", svc("sleep 60", vec![]))]));
+        sup.start_service("web", 0).await.unwrap();
+
+        let pid_before = sup.status_rows().await[0].pid;
         sup.restart_service("web").await.unwrap();
         let pid_after = sup.status_rows().await[0].pid;
 
@@ -1083,4 +3019,653 @@ mod tests {
 
         sup.stop_all().await;
     }
+
+    #[tokio::test]
+    async fn test_ipc_answers_the_same_over_tcp_and_the_socket() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        let port = spawn::free_port().unwrap();
+        std::fs::write(
+            &hcl,
+            format!("dev {{\n  ipc_tcp_port = {port}\n}}\nservice \"web\" {{\n  cmd = \"sleep 60\"\n}}\n"),
+        )
+        .unwrap();
+        let cfg = Arc::new(DevConfig::from_file_with_env(&hcl, None).unwrap());
+        let proxy = Arc::new(crate::proxy::ProxyRouter::new(0));
+        let (sup, _) = Supervisor::new(cfg, proxy, hcl.clone(), None);
+        let sup = Arc::new(sup);
+        tokio::spawn(ipc::serve(sup.clone()));
+
+        let mut unix = None;
+        for _ in 0..50 {
+            if let Ok(s) = tokio::net::UnixStream::connect(crate::ipc::socket_path(&hcl)).await {
+                unix = Some(s);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let mut addr = crate::ipc::IpcAddr::for_project(&hcl);
+        for _ in 0..50 {
+            if addr.token.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            addr = crate::ipc::IpcAddr::for_project(&hcl);
+        }
+        assert_eq!(addr.tcp_port, Some(port));
+        let token = addr.token.clone().expect("IPC token");
+        assert_eq!(token.len(), 64);
+        let ask = |io: Box<dyn crate::ipc::IpcIo>, token: Option<&str>| {
+            let hello = serde_json::to_string(&crate::ipc::IpcRequest::Hello {
+                version: crate::ipc::PROTOCOL_VERSION,
+                token: token.map(str::to_string),
+            })
+            .unwrap();
+            async move {
+                let (reader, mut writer) = tokio::io::split(io);
+                let mut lines = BufReader::new(reader).lines();
+                let mut answers = Vec::new();
+                for req in [&hello, r#"{"cmd":"health_report"}"#, "nonsense"] {
+                    writer
+                        .write_all(format!("{req}\n").as_bytes())
+                        .await
+                        .unwrap();
+                    match lines.next_line().await {
+                        Ok(Some(line)) => answers.push(line),
+                        _ => break,
+                    }
+                }
+                answers
+            }
+        };
+        let via_unix = ask(Box::new(unix.expect("IPC socket")), None).await;
+        let tcp = || async {
+            Box::new(
+                tokio::net::TcpStream::connect(("127.0.0.1", port))
+                    .await
+                    .unwrap(),
+            )
+        };
+        let via_tcp = ask(tcp().await, Some(&token)).await;
+        assert_eq!(via_unix, via_tcp);
+        assert!(
+            via_tcp[1].contains(r#""type":"health_report""#),
+            "{via_tcp:?}"
+        );
+
+        // Any local user can reach the port: without the token nothing is served.
+        for wrong in [None, Some("guess")] {
+            let refused = ask(tcp().await, wrong).await;
+            assert_eq!(refused.len(), 1, "{refused:?}");
+            assert!(
+                refused[0].contains("missing or invalid IPC token"),
+                "{refused:?}"
+            );
+        }
+
+        // Without the socket, clients fall back to TCP.
+        let fallback = crate::ipc::IpcAddr {
+            socket: dir.path().join("gone.sock"),
+            ..addr.clone()
+        };
+        assert_eq!(
+            ask(fallback.connect().await.unwrap(), fallback.token.as_deref()).await,
+            via_tcp
+        );
+        let _ = std::fs::remove_file(&addr.socket);
+    }
+
+    #[tokio::test]
+    async fn test_ipc_logs_refuses_unknown_services() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl,
+            "service \"api\" {\n  cmd = \"sleep 60\"\n}\nservice \"worker\" {\n  cmd = \"sleep 60\"\n}\n",
+        )
+        .unwrap();
+        let cfg = Arc::new(DevConfig::from_file_with_env(&hcl, None).unwrap());
+        let proxy = Arc::new(crate::proxy::ProxyRouter::new(0));
+        let (sup, _) = Supervisor::new(cfg, proxy, hcl.clone(), None);
+        tokio::spawn(ipc::serve(Arc::new(sup)));
+
+        let socket = crate::ipc::socket_path(&hcl);
+        let ask = |req: &'static str| {
+            let socket = socket.clone();
+            async move {
+                let mut stream = None;
+                for _ in 0..50 {
+                    if let Ok(s) = tokio::net::UnixStream::connect(&socket).await {
+                        stream = Some(s);
+                        break;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                }
+                let (reader, mut writer) = tokio::io::split(stream.expect("IPC socket"));
+                let mut lines = BufReader::new(reader).lines();
+                let hello = r#"{"cmd":"hello","version":2}"#;
+                writer
+                    .write_all(format!("{hello}\n{req}\n").as_bytes())
+                    .await
+                    .unwrap();
+                let greeting = lines.next_line().await.unwrap().unwrap();
+                assert!(greeting.contains(r#""type":"hello""#), "{greeting}");
+                lines.next_line().await.unwrap()
+            }
+        };
+
+        for req in [
+            r#"{"cmd":"logs","services":["api","nope"],"follow":true}"#,
+            r#"{"cmd":"logs","service":"nope","follow":true}"#,
+            r#"{"cmd":"history","services":["nope"],"lines":10}"#,
+        ] {
+            let answer = ask(req).await.expect("an answer");
+            assert!(answer.contains(r#""type":"error""#), "{answer}");
+            assert!(answer.contains("'nope'"), "{answer}");
+            assert!(answer.contains("api, worker"), "{answer}");
+        }
+        // Known names pass: no history yet, so the connection just closes.
+        let answer = ask(r#"{"cmd":"history","services":["api","worker"],"lines":10}"#).await;
+        assert_eq!(answer, None);
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[tokio::test]
+    async fn test_ipc_requires_a_supported_hello_first() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        std::fs::write(&hcl, "service \"web\" {\n  cmd = \"sleep 60\"\n}\n").unwrap();
+        let cfg = Arc::new(DevConfig::from_file_with_env(&hcl, None).unwrap());
+        let proxy = Arc::new(crate::proxy::ProxyRouter::new(0));
+        let (sup, _) = Supervisor::new(cfg, proxy, hcl.clone(), None);
+        tokio::spawn(ipc::serve(Arc::new(sup)));
+
+        let socket = crate::ipc::socket_path(&hcl);
+        // Every answer on a connection that sends `reqs`, until the daemon closes it.
+        let exchange = |reqs: &'static [&'static str]| {
+            let socket = socket.clone();
+            async move {
+                let mut stream = None;
+                for _ in 0..50 {
+                    if let Ok(s) = tokio::net::UnixStream::connect(&socket).await {
+                        stream = Some(s);
+                        break;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                }
+                let (reader, mut writer) = tokio::io::split(stream.expect("IPC socket"));
+                let mut lines = BufReader::new(reader).lines();
+                let mut answers = Vec::new();
+                for req in reqs {
+                    if writer
+                        .write_all(format!("{req}\n").as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                    match lines.next_line().await {
+                        Ok(Some(answer)) => answers.push(answer),
+                        _ => break,
+                    }
+                }
+                answers
+            }
+        };
+
+        let ok = exchange(&[r#"{"cmd":"hello","version":2}"#, r#"{"cmd":"status"}"#]).await;
+        assert_eq!(ok[0], r#"{"type":"hello","version":2,"min_supported":2}"#);
+        assert!(ok[1].contains(r#""type":"status""#), "{ok:?}");
+
+        // An older client is told to upgrade, and nothing else is answered.
+        let old = exchange(&[r#"{"cmd":"hello","version":1}"#, r#"{"cmd":"status"}"#]).await;
+        assert_eq!(old.len(), 1, "{old:?}");
+        assert!(old[0].contains("needs at least v2"), "{old:?}");
+
+        let rude = exchange(&[r#"{"cmd":"status"}"#, r#"{"cmd":"status"}"#]).await;
+        assert_eq!(rude.len(), 1, "{rude:?}");
+        assert!(rude[0].contains("expected"), "{rude:?}");
+        assert!(rude[0].contains(r#""type":"error""#), "{rude:?}");
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[tokio::test]
+    async fn test_ipc_lets_restarts_finish_and_drops_idle_clients() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        // Nothing listens, so every (re)start waits out the start period and a check.
+        std::fs::write(
+            &hcl,
+            "dev {\n  ipc_request_timeout = \"200ms\"\n}\n\
+             service \"slow\" {\n  cmd = \"sleep 60\"\n  port = 1\n  health {\n    \
+             type = \"tcp\"\n    interval = \"100ms\"\n    retries = 1\n    \
+             start_period = \"500ms\"\n  }\n}\n",
+        )
+        .unwrap();
+        let cfg = Arc::new(DevConfig::from_file_with_env(&hcl, None).unwrap());
+        assert_eq!(
+            cfg.dev.ipc_request_timeout,
+            std::time::Duration::from_millis(200)
+        );
+        let proxy = Arc::new(crate::proxy::ProxyRouter::new(0));
+        let (sup, _) = Supervisor::new(cfg, proxy, hcl.clone(), None);
+        let sup = Arc::new(sup);
+        sup.start_service("slow", 0).await.unwrap();
+        let pid = sup.status_rows().await[0].pid;
+        tokio::spawn(ipc::serve(sup.clone()));
+
+        let socket = crate::ipc::socket_path(&hcl);
+        let mut stream = None;
+        for _ in 0..50 {
+            if let Ok(s) = tokio::net::UnixStream::connect(&socket).await {
+                stream = Some(s);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let (reader, mut writer) = tokio::io::split(stream.expect("IPC socket"));
+        let mut lines = BufReader::new(reader).lines();
+        for req in [
+            r#"{"cmd":"hello","version":2}"#,
+            r#"{"cmd":"status"}"#,
+            r#"{"cmd":"restart","service":"slow"}"#,
+        ] {
+            writer
+                .write_all(format!("{req}\n").as_bytes())
+                .await
+                .unwrap();
+        }
+        let hello = lines.next_line().await.unwrap().unwrap();
+        assert!(hello.contains(r#""type":"hello""#), "{hello}");
+        let status = lines.next_line().await.unwrap().unwrap();
+        assert!(status.contains(r#""type":"status""#), "{status}");
+        // The restart outlasts ipc_request_timeout and is answered all the same.
+        let started = std::time::Instant::now();
+        let restarted = lines.next_line().await.unwrap().unwrap();
+        assert_eq!(restarted, r#"{"type":"ok"}"#);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(500));
+        assert_ne!(sup.status_rows().await[0].pid, pid);
+
+        // A client that then says nothing is let go.
+        let started = std::time::Instant::now();
+        assert_eq!(lines.next_line().await.unwrap(), None, "connection closed");
+        assert!(started.elapsed() < std::time::Duration::from_millis(900));
+        sup.stop_all().await;
+        let _ = std::fs::remove_file(&socket);
+    }
+
+    #[test]
+    fn test_ipc_request_limits() {
+        let dev = GlobalSettings {
+            ipc_request_timeout: std::time::Duration::from_secs(30),
+            ipc_stream_timeout: std::time::Duration::from_secs(600),
+            ..Default::default()
+        };
+        let limit = |json: &str| ipc::request_limit(&dev, &serde_json::from_str(json).unwrap());
+        assert_eq!(limit(r#"{"cmd":"status"}"#).as_secs(), 30);
+        assert_eq!(
+            limit(r#"{"cmd":"kill","service":"a","signal":"SIGHUP"}"#).as_secs(),
+            30
+        );
+        assert_eq!(limit(r#"{"cmd":"events","services":[]}"#).as_secs(), 600);
+        assert_eq!(
+            limit(r#"{"cmd":"logs","services":[],"follow":true}"#).as_secs(),
+            600
+        );
+        for lifecycle in [
+            r#"{"cmd":"restart","service":"a"}"#,
+            r#"{"cmd":"stop","services":[]}"#,
+            r#"{"cmd":"start","services":["a"]}"#,
+            r#"{"cmd":"batch","requests":[{"cmd":"status"},{"cmd":"restart","service":"a"}]}"#,
+        ] {
+            assert!(limit(lifecycle).is_zero(), "{lifecycle}");
+        }
+        assert_eq!(
+            limit(r#"{"cmd":"batch","requests":[{"cmd":"status"}]}"#).as_secs(),
+            30
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ipc_within_gives_up_after_the_limit() {
+        let slow = tokio::time::sleep(std::time::Duration::from_secs(5));
+        let started = std::time::Instant::now();
+        assert_eq!(
+            ipc::within(std::time::Duration::from_millis(50), slow).await,
+            None
+        );
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert_eq!(
+            ipc::within(std::time::Duration::ZERO, async { 7 }).await,
+            Some(7)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_env_reload_restarts_on_env_file_change() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("web.env"), "GREETING=hello\nNAME=file\n").unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        std::fs::write(
+            &hcl,
+            "service \"web\" {\n  cmd = \"sleep 60\"\n  env_file = \"web.env\"\n  \
+             env_reload = true\n  env = {\n    NAME = \"inline\"\n  }\n}\n",
+        )
+        .unwrap();
+        let cfg = Arc::new(DevConfig::from_file_with_env(&hcl, None).unwrap());
+        let proxy = Arc::new(crate::proxy::ProxyRouter::new(0));
+        let (sup, _) = Supervisor::new(cfg, proxy, hcl.clone(), None);
+        let sup = Arc::new(sup);
+        sup.start_service("web", 0).await.unwrap();
+        let pid_before = sup.status_rows().await[0].pid;
+        let shutdown_timeout = sup.cfg().dev.shutdown_timeout;
+
+        // An A3sfile edit not yet reloaded stays out of the live config.
+        let text = std::fs::read_to_string(&hcl).unwrap();
+        std::fs::write(
+            &hcl,
+            format!("dev {{\n  shutdown_timeout = \"7s\"\n}}\n{text}"),
+        )
+        .unwrap();
+        // Saved by renaming a new file over the old one, as many editors do.
+        let tmp = dir.path().join("web.env.tmp");
+        std::fs::write(&tmp, "GREETING=bye\nNAME=file\n").unwrap();
+        std::fs::rename(&tmp, dir.path().join("web.env")).unwrap();
+        let mut restarted = false;
+        for _ in 0..100 {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let row = &sup.status_rows().await[0];
+            if row.state == "running" && row.pid != pid_before {
+                restarted = true;
+                break;
+            }
+        }
+        assert!(restarted, "editing the env_file should restart the service");
+        let env = &sup.cfg().service["web"].env;
+        assert_eq!(env["GREETING"], "bye");
+        assert_eq!(env["NAME"], "inline", "inline env still wins over env_file");
+        assert_eq!(sup.cfg().dev.shutdown_timeout, shutdown_timeout);
+
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_reload_from_disk_diffs_the_service_set() {
+        let dir = tempfile::tempdir().unwrap();
+        let hcl = dir.path().join("A3sfile.hcl");
+        let write = |services: &[(&str, &str)]| {
+            let text: String = services
+                .iter()
+                .map(|(name, cmd)| format!("service \"{name}\" {{\n  cmd = \"{cmd}\"\n}}\n"))
+                .collect();
+            std::fs::write(&hcl, text).unwrap();
+        };
+        write(&[
+            ("keep", "sleep 60"),
+            ("edit", "sleep 60"),
+            ("drop", "sleep 60"),
+        ]);
+        let cfg = Arc::new(DevConfig::from_file_with_env(&hcl, None).unwrap());
+        let proxy = Arc::new(crate::proxy::ProxyRouter::new(0));
+        let (sup, _) = Supervisor::new(cfg, proxy, hcl.clone(), None);
+        let sup = Arc::new(sup);
+        sup.start_all().await.unwrap();
+        let pid = |rows: &[StatusRow], name: &str| rows.iter().find(|r| r.name == name)?.pid;
+        let rows = sup.status_rows().await;
+        let (keep_pid, edit_pid) = (pid(&rows, "keep"), pid(&rows, "edit"));
+
+        write(&[
+            ("keep", "sleep 60"),
+            ("edit", "sleep 61"),
+            ("add", "sleep 60"),
+        ]);
+        let summary = sup.reload_from_disk(true).await.unwrap();
+        assert_eq!(summary.started, ["add"]);
+        assert_eq!(summary.stopped, ["drop"]);
+        assert_eq!(summary.restarted, ["edit"]);
+        assert!(summary.not_restarted.is_empty());
+
+        let rows = sup.status_rows().await;
+        assert_eq!(
+            pid(&rows, "keep"),
+            keep_pid,
+            "unchanged services keep running"
+        );
+        assert_ne!(pid(&rows, "edit"), edit_pid);
+        assert!(pid(&rows, "add").is_some());
+        assert!(pid(&rows, "drop").is_none());
+
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_reload_without_restart_reports_changed_services() {
+        let sup = make_supervisor(make_config(vec![
+            ("web", svc("sleep 60", vec![])),
+            ("old", svc("sleep 60", vec![])),
+        ]));
+        sup.start_all().await.unwrap();
+        let pid = |rows: &[StatusRow], name: &str| rows.iter().find(|r| r.name == name)?.pid;
+        let web_pid = pid(&sup.status_rows().await, "web");
+
+        let new = make_config(vec![
+            ("web", svc("sleep 61", vec![])),
+            ("new", svc("sleep 60", vec![])),
+        ]);
+        let summary = sup.reload(new, false).await.unwrap();
+        assert_eq!(summary.stopped, ["old"]);
+        assert_eq!(summary.started, ["new"]);
+        assert!(summary.restarted.is_empty());
+        assert_eq!(summary.not_restarted, ["web"]);
+        assert_eq!(pid(&sup.status_rows().await, "web"), web_pid);
+        // Still running the old definition, and the change isn't lost: reloading
+        // again finds it.
+        assert_eq!(sup.cfg().service["web"].cmd, "sleep 60");
+        let again = make_config(vec![
+            ("web", svc("sleep 61", vec![])),
+            ("new", svc("sleep 60", vec![])),
+        ]);
+        let summary = sup.reload(again.clone(), false).await.unwrap();
+        assert_eq!(summary.not_restarted, ["web"]);
+        let summary = sup.reload(again, true).await.unwrap();
+        assert_eq!(summary.restarted, ["web"]);
+        assert_ne!(pid(&sup.status_rows().await, "web"), web_pid);
+        assert_eq!(sup.cfg().service["web"].cmd, "sleep 61");
+
+        sup.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_reload_rejects_invalid_config_untouched() {
+        let sup = make_supervisor(make_config(vec![("web", svc("sleep 60", vec![]))]));
+        sup.start_all().await.unwrap();
+
+        // Removes "web" but also fails validation: nothing may be stopped.
+        let mut a = svc("sleep 60", vec![]);
+        a.port = 45_123;
+        let b = a.clone();
+        let err = sup
+            .reload(make_config(vec![("a", a), ("b", b)]), true)
+            .await
+            .err()
+            .expect("port conflict should be rejected");
+        assert!(matches!(err, DevError::PortConflict { .. }), "{err}");
+        assert_eq!(sup.status_rows().await[0].state, "running");
+        assert!(sup.cfg().service.contains_key("web"));
+
+        sup.stop_all().await;
+    }
+
+    /// Poll until the service's spare reports ready, returning (active pid, spare pid).
+    async fn wait_for_spare(sup: &Supervisor, name: &str) -> (u32, u32) {
+        for _ in 0..100 {
+            let rows = sup.status_rows().await;
+            let row = rows.iter().find(|r| r.name == name).unwrap();
+            if let Some(spare) = row.standby.as_ref().filter(|s| s.state == "running") {
+                return (row.pid.unwrap(), spare.pid.unwrap());
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        panic!("standby for '{name}' never became ready");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shutdown_kills_at_once_on_second_signal() {
+        let mut stubborn = svc("sh -c 'trap \"\" TERM; sleep 60'", vec![]);
+        stubborn.stop_timeout = std::time::Duration::from_secs(30);
+        let sup = make_supervisor(make_config(vec![("stubborn", stubborn)]));
+        sup.start_service("stubborn", 0).await.unwrap();
+        let pid = sup.status_rows().await[0].pid.unwrap();
+
+        let started = std::time::Instant::now();
+        sup.shutdown(tokio::time::sleep(std::time::Duration::from_millis(200)))
+            .await;
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        use nix::sys::signal::kill;
+        use nix::unistd::Pid;
+        let mut gone = false;
+        for _ in 0..100 {
+            // Reap it if it is our zombie, then check it's gone.
+            let _ = nix::sys::wait::waitpid(
+                Pid::from_raw(pid as i32),
+                Some(nix::sys::wait::WaitPidFlag::WNOHANG),
+            );
+            if kill(Pid::from_raw(pid as i32), None).is_err() {
+                gone = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(gone, "service {pid} survived the forced shutdown");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_all_stops_dependents_before_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let order = dir.path().join("order");
+        let mut db = svc("sleep 60", vec![]);
+        db.post_stop = Some(format!("sh -c 'echo db >> {}'", order.display()));
+        // api takes a while to exit; db must not be stopped before it has.
+        let mut api = svc(
+            "sh -c 'trap \"sleep 0.5; exit 0\" TERM; while :; do sleep 0.1; done'",
+            vec!["db"],
+        );
+        api.post_stop = Some(format!("sh -c 'echo api >> {}'", order.display()));
+        let sup = make_supervisor(make_config(vec![("db", db), ("api", api)]));
+        sup.clone().start_all().await.unwrap();
+
+        let mut stopped = sup.stop_all().await;
+        stopped.sort();
+        assert_eq!(stopped, ["api", "db"]);
+        assert_eq!(std::fs::read_to_string(&order).unwrap(), "api\ndb\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_all_kills_what_outlasts_shutdown_timeout() {
+        let mut stubborn = svc("sh -c 'trap \"\" TERM; sleep 60'", vec![]);
+        stubborn.stop_timeout = std::time::Duration::from_secs(30);
+        let mut cfg = Arc::into_inner(make_config(vec![("stubborn", stubborn)])).unwrap();
+        cfg.dev.shutdown_timeout = std::time::Duration::from_millis(300);
+        let sup = make_supervisor(Arc::new(cfg));
+        sup.start_service("stubborn", 0).await.unwrap();
+
+        let started = std::time::Instant::now();
+        assert_eq!(sup.stop_all().await, ["stubborn"]);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert_eq!(sup.status_rows().await[0].state, "stopped");
+    }
+
+    #[tokio::test]
+    async fn test_stop_one_reports_or_stops_dependents() {
+        let sup = make_supervisor(make_config(vec![
+            ("a", svc("sleep 60", vec![])),
+            ("b", svc("sleep 60", vec!["a"])),
+        ]));
+        sup.clone().start_all().await.unwrap();
+        assert_eq!(sup.stop_one("a", false).await, ["b"]);
+        assert!(sup.is_running("b").await);
+
+        sup.clone().start_named(&["a".to_string()]).await.unwrap();
+        assert!(sup.stop_one("a", true).await.is_empty());
+        assert!(!sup.is_running("a").await && !sup.is_running("b").await);
+    }
+
+    fn standby_svc() -> ServiceDef {
+        // No health check: the spare counts as ready as soon as it is spawned.
+        let mut def = svc("sleep 60", vec![]);
+        def.standby = true;
+        def
+    }
+
+    #[tokio::test]
+    async fn test_standby_restart_promotes_spare() {
+        let sup = make_supervisor(make_config(vec![("ml", standby_svc())]));
+        sup.start_service("ml", 0).await.unwrap();
+
+        let (active, spare) = wait_for_spare(&sup, "ml").await;
+        assert_ne!(active, spare);
+        let spare_port = sup.status_rows().await[0].standby.as_ref().unwrap().port;
+
+        sup.restart_service("ml").await.unwrap();
+        let row = &sup.status_rows().await[0];
+        assert_eq!(row.state, "running");
+        assert_eq!(
+            row.pid,
+            Some(spare),
+            "spare should now be the active instance"
+        );
+        assert_eq!(
+            row.port, spare_port,
+            "routing should follow the promoted spare"
+        );
+
+        // A replacement spare is warmed in the background.
+        let (_, next_spare) = wait_for_spare(&sup, "ml").await;
+        assert_ne!(next_spare, spare);
+
+        sup.stop_service("ml").await;
+        let row = &sup.status_rows().await[0];
+        assert_eq!(row.state, "stopped");
+        assert!(
+            row.standby.is_none(),
+            "spare must be stopped with the service"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_standby_promoted_when_active_crashes() {
+        let sup = make_supervisor(make_config(vec![("ml", standby_svc())]));
+        sup.start_service("ml", 0).await.unwrap();
+        let (active, spare) = wait_for_spare(&sup, "ml").await;
+
+        use nix::sys::signal::{kill, Signal};
+        use nix::unistd::Pid;
+        kill(Pid::from_raw(active as i32), Signal::SIGKILL).unwrap();
+
+        let mut promoted = false;
+        for _ in 0..100 {
+            if sup.status_rows().await[0].pid == Some(spare) {
+                promoted = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert!(promoted, "spare should take over after a crash");
+        assert_eq!(sup.status_rows().await[0].state, "running");
+
+        sup.stop_all().await;
+    }
 }
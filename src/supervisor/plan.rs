@@ -0,0 +1,191 @@
+//! `a3s up --dry-run`: what `up` would start, worked out from the config alone.
+//!
+//! The plan walks the same waves and start order as [`super::Supervisor::start_all`]
+//! and builds each command line the way the spawn path does, but nothing is spawned:
+//! no ports are bound, hooks don't run, and `env_secret` values are not resolved.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{DevConfig, HealthConfig};
+use crate::error::Result;
+use crate::graph::DependencyGraph;
+use crate::health::HealthChecker;
+
+use super::spawn::service_argv;
+
+/// Stands in for a port that is only picked when the service starts.
+pub const AUTO_PORT: &str = "<auto>";
+
+/// One service `a3s up` would start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    pub service: String,
+    /// Start wave (from 0); services in the same wave start concurrently.
+    pub wave: usize,
+    /// Working directory; `None` inherits a3s's own.
+    pub dir: Option<PathBuf>,
+    /// Program and arguments, after the shell and framework `--port` are applied.
+    pub argv: Vec<String>,
+    /// `None` when `port = 0` and a free port is assigned at start.
+    pub port: Option<u16>,
+    /// The environment the process gets, `PORT` and `HOST` included.
+    pub env: BTreeMap<String, String>,
+    /// `env_secret` names; their values are only read at start.
+    pub secrets: Vec<String>,
+    pub pre_start: Option<String>,
+    /// Dependencies to wait for, each bounded by `wait_timeout` (zero = forever).
+    pub waits: Vec<PlanWait>,
+    pub wait_timeout: Duration,
+    pub health: Option<HealthConfig>,
+}
+
+/// A `depends_on` edge the service waits on before it starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanWait {
+    pub service: String,
+    /// The dependency has a health check, so "up" means healthy rather than spawned.
+    pub healthy: bool,
+}
+
+/// What starting `names` (or every autostarted service when empty) and their
+/// dependencies would do, in start order. Unknown names fail like they do for `up`.
+pub fn plan(cfg: &DevConfig, names: &[String]) -> Result<Vec<PlanStep>> {
+    let graph = DependencyGraph::from_config(cfg)?;
+    let wanted = if names.is_empty() {
+        let autostarted: Vec<&str> = cfg
+            .service
+            .iter()
+            .filter(|(_, s)| s.autostart)
+            .map(|(n, _)| n.as_str())
+            .collect();
+        graph.transitive_start_order(&autostarted)
+    } else {
+        cfg.check_service_names(names)?;
+        let name_refs: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
+        graph.transitive_start_order(&name_refs)
+    };
+    let wave_of: HashMap<&str, usize> = graph
+        .start_waves()
+        .iter()
+        .enumerate()
+        .flat_map(|(i, wave)| wave.iter().map(move |n| (n.as_str(), i)))
+        .collect();
+    // Fixed ports are known up front; `${svc.port}` of an auto-assigned one stays as is.
+    let ports: HashMap<String, u16> = cfg
+        .service
+        .iter()
+        .filter(|(_, s)| s.port != 0 && !s.disabled)
+        .map(|(n, s)| (n.clone(), s.port))
+        .collect();
+
+    let mut steps = Vec::new();
+    for name in &wanted {
+        let svc = &cfg.service[name];
+        if svc.disabled {
+            continue;
+        }
+        let svc = crate::config::resolve_service_ports(svc.clone(), &ports);
+        let port = (svc.port != 0).then_some(svc.port);
+        let port_str = port.map_or(AUTO_PORT.to_string(), |p| p.to_string());
+        let mut env: BTreeMap<String, String> = svc.env.clone().into_iter().collect();
+        env.insert("PORT".into(), port_str.clone());
+        env.insert("HOST".into(), "127.0.0.1".into());
+        let mut secrets: Vec<String> = svc.env_secret.keys().cloned().collect();
+        secrets.sort();
+        let waits = svc
+            .depends_on
+            .iter()
+            .filter(|d| cfg.service.get(*d).is_some_and(|s| !s.disabled))
+            .map(|d| PlanWait {
+                service: d.clone(),
+                healthy: HealthChecker::for_service(&cfg.service[d]).is_some(),
+            })
+            .collect();
+        steps.push(PlanStep {
+            service: name.clone(),
+            wave: wave_of.get(name.as_str()).copied().unwrap_or(0),
+            dir: svc.dir.clone(),
+            argv: service_argv(&svc, &port_str),
+            port,
+            env,
+            secrets,
+            pre_start: svc.pre_start.clone(),
+            waits,
+            wait_timeout: svc.depends_on_timeout.unwrap_or(cfg.dev.depends_on_timeout),
+            health: svc.health.clone(),
+        });
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_follows_start_order_without_spawning() {
+        let mut cfg: DevConfig = hcl::from_str(
+            r#"
+            dev {
+              depends_on_timeout = "30s"
+            }
+            service "db" {
+              cmd  = "postgres -p 5432"
+              port = 5432
+              health {
+                type = "tcp"
+              }
+            }
+            service "web" {
+              cmd        = "vite"
+              shell      = "none"
+              dir        = "/srv/web"
+              env_secret = { TOKEN = "echo secret" }
+              depends_on = ["db"]
+            }
+            service "docs" {
+              cmd       = "mdbook serve"
+              port      = 3000
+              autostart = false
+            }
+            "#,
+        )
+        .unwrap();
+        cfg.service
+            .get_mut("web")
+            .unwrap()
+            .env
+            .insert("API".into(), "http://localhost:${db.port}".into());
+
+        let steps = plan(&cfg, &[]).unwrap();
+        let names: Vec<&str> = steps.iter().map(|s| s.service.as_str()).collect();
+        assert_eq!(names, ["db", "web"], "docs is not autostarted");
+
+        let (db, web) = (&steps[0], &steps[1]);
+        assert_eq!((db.wave, db.port), (0, Some(5432)));
+        assert_eq!(db.argv.last().unwrap(), "postgres -p 5432");
+        assert!(db.health.is_some());
+
+        assert_eq!((web.wave, web.port), (1, None));
+        assert_eq!(web.argv, ["vite", "--port", AUTO_PORT]);
+        assert_eq!(web.dir, Some(PathBuf::from("/srv/web")));
+        assert_eq!(web.env["API"], "http://localhost:5432");
+        assert_eq!(web.env["PORT"], AUTO_PORT);
+        assert_eq!(web.secrets, ["TOKEN"]);
+        assert!(!web.env.contains_key("TOKEN"), "secrets are not resolved");
+        assert_eq!(
+            web.waits,
+            [PlanWait {
+                service: "db".into(),
+                healthy: true
+            }]
+        );
+        assert_eq!(web.wait_timeout, Duration::from_secs(30));
+
+        let docs = plan(&cfg, &["docs".to_string()]).unwrap();
+        assert_eq!(docs.len(), 1);
+        assert!(plan(&cfg, &["nope".to_string()]).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use tokio::process::{Child, Command};
@@ -14,27 +15,75 @@ pub struct SpawnSpec<'a> {
     pub color_idx: usize,
     /// Directory containing A3sfile.hcl — used to resolve relative `log_file` paths.
     pub config_dir: &'a std::path::Path,
+    /// Warm standby spare — kept in its own cgroup, away from the active instance.
+    pub standby: bool,
 }
 
 pub struct SpawnResult {
     pub child: Child,
     pub pid: u32,
+    /// The service cgroup, when `limits` are enforced via cgroups v2.
+    pub cgroup: Option<super::limits::Cgroup>,
 }
 
-/// Run a hook command (in the service working directory).
-/// Returns an error if the hook exits non-zero.
+/// The shell `cmd`s run under when a service doesn't choose one, and its flag for
+/// "run this command line".
+const DEFAULT_SHELL: (&str, &str) = if cfg!(windows) {
+    ("cmd", "/C")
+} else {
+    ("sh", "-c")
+};
+
+/// Program and arguments that run `line` under `shell`: `<shell> -c <line>`, or the
+/// words of `line` itself for `"none"`.
+fn shell_argv(line: &str, shell: Option<&str>) -> Vec<String> {
+    let (shell, flag) = match shell {
+        Some("none") => return split_cmd(line),
+        Some(shell) => (shell, "-c"),
+        None => DEFAULT_SHELL,
+    };
+    vec![shell.to_string(), flag.to_string(), line.to_string()]
+}
+
+/// Program and arguments that run a service's `cmd` on `port`. Frameworks that
+/// ignore $PORT get `--port` appended to the command line.
+pub(super) fn service_argv(svc: &ServiceDef, port: &str) -> Vec<String> {
+    let extra_args = framework_port_args(&split_cmd(&svc.cmd), port);
+    let line = if extra_args.is_empty() {
+        svc.cmd.clone()
+    } else {
+        format!("{} {}", svc.cmd, extra_args.join(" "))
+    };
+    shell_argv(&line, svc.shell.as_deref())
+}
+
+/// Run a hook command (in the service working directory), killing it after
+/// `hook_timeout`. Returns an error if the hook exits non-zero or times out.
 pub(super) async fn run_hook(hook: &str, svc: &ServiceDef, label: &str) -> Result<()> {
-    let parts = split_cmd(hook);
+    let parts = shell_argv(hook, svc.shell.as_deref());
     let program = parts.first().map(|s| s.as_str()).unwrap_or("sh");
     let mut cmd = Command::new(program);
-    cmd.args(&parts[1..]).envs(&svc.env);
+    cmd.args(&parts[1..]).envs(&svc.env).kill_on_drop(true);
     if let Some(dir) = &svc.dir {
         cmd.current_dir(dir);
     }
-    let status = cmd.status().await.map_err(|e| DevError::Process {
+    let mut child = cmd.spawn().map_err(|e| DevError::Process {
         service: label.to_string(),
         msg: format!("{label} hook: {e}"),
     })?;
+    let status = match tokio::time::timeout(svc.hook_timeout, child.wait()).await {
+        Ok(status) => status?,
+        Err(_) => {
+            let _ = child.kill().await;
+            return Err(DevError::Process {
+                service: label.to_string(),
+                msg: format!(
+                    "{label} hook timed out after {}s",
+                    svc.hook_timeout.as_secs_f32()
+                ),
+            });
+        }
+    };
     if !status.success() {
         return Err(DevError::Process {
             service: label.to_string(),
@@ -46,24 +95,35 @@ pub(super) async fn run_hook(hook: &str, svc: &ServiceDef, label: &str) -> Resul
 
 /// Spawn a service process, attach stdout to the log aggregator, and return the child.
 /// Stderr is forwarded to the log aggregator as well.
+/// `env_secret` values are resolved first, for the hook and the service alike; a
+/// secret that can't be resolved fails the spawn.
 pub async fn spawn_process(spec: &SpawnSpec<'_>, log: &Arc<LogAggregator>) -> Result<SpawnResult> {
+    let secrets = crate::secrets::resolve(spec.name, spec.svc, spec.config_dir).await?;
+    log.set_masks(spec.name, crate::secrets::mask_values(&secrets));
+    let with_secrets;
+    let svc = if secrets.is_empty() {
+        spec.svc
+    } else {
+        let mut svc = spec.svc.clone();
+        svc.env.extend(secrets);
+        with_secrets = svc;
+        &with_secrets
+    };
+
     // Run pre_start hook before launching the service process.
-    if let Some(ref hook) = spec.svc.pre_start {
+    if let Some(ref hook) = svc.pre_start {
         tracing::info!("[{}] running pre_start: {hook}", spec.name);
-        run_hook(hook, spec.svc, spec.name).await?;
+        run_hook(hook, svc, spec.name).await?;
     }
 
-    let parts = split_cmd(&spec.svc.cmd);
+    let parts = service_argv(spec.svc, &spec.port.to_string());
     let program = parts.first().map(|s| s.as_str()).unwrap_or("sh");
-    let args = &parts[1..];
-    let extra_args = framework_port_args(&parts, spec.port);
 
     let mut cmd = Command::new(program);
-    cmd.args(args)
-        .args(&extra_args)
+    cmd.args(&parts[1..])
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
-        .envs(&spec.svc.env)
+        .envs(&svc.env)
         .env("PORT", spec.port.to_string())
         .env("HOST", "127.0.0.1");
 
@@ -76,6 +136,14 @@ pub async fn spawn_process(spec: &SpawnSpec<'_>, log: &Arc<LogAggregator>) -> Re
     #[cfg(unix)]
     cmd.process_group(0);
 
+    #[cfg(unix)]
+    let cgroup = spec.svc.limits.as_ref().and_then(|l| {
+        let group = super::limits::group_name(spec.name, spec.standby);
+        super::limits::apply(&mut cmd, spec.name, &group, l)
+    });
+    #[cfg(not(unix))]
+    let cgroup = None;
+
     let mut child = cmd.spawn().map_err(|e| DevError::Process {
         service: spec.name.to_string(),
         msg: e.to_string(),
@@ -100,12 +168,18 @@ pub async fn spawn_process(spec: &SpawnSpec<'_>, log: &Arc<LogAggregator>) -> Re
         };
         log.register_log_file(
             spec.name.to_string(),
-            resolved,
-            spec.svc.log_rotate_mb * 1024 * 1024,
+            crate::log::LogFileSpec {
+                path: resolved,
+                max_bytes: spec
+                    .svc
+                    .log_max_size
+                    .unwrap_or(spec.svc.log_rotate_mb * 1024 * 1024),
+                keep: spec.svc.log_keep,
+            },
         );
     }
 
-    Ok(SpawnResult { child, pid })
+    Ok(SpawnResult { child, pid, cgroup })
 }
 
 /// Bind to port 0 and return the OS-assigned free port.
@@ -121,6 +195,44 @@ pub fn free_port() -> Option<u16> {
     Some(port)
 }
 
+/// Ports `port = 0` services are assigned from, below the usual OS ephemeral ranges
+/// (32768+ on Linux, 49152+ on macOS) that [`free_port`] draws on.
+const AUTO_PORTS: std::ops::Range<u16> = 20_000..30_000;
+
+/// Ports tried from a service's preferred one before falling back to [`free_port`].
+const AUTO_PORT_PROBES: u16 = 64;
+
+/// The port a `port = 0` service prefers, derived from its name (FNV-1a) so it is
+/// the same on every run.
+pub fn preferred_port(name: &str) -> u16 {
+    let hash = name.bytes().fold(0x811c_9dc5u32, |h, b| {
+        (h ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    });
+    let span = u32::from(AUTO_PORTS.end - AUTO_PORTS.start);
+    AUTO_PORTS.start + (hash % span) as u16
+}
+
+/// A port for a `port = 0` service: its [`preferred_port`] or the first free one
+/// after it that isn't in `taken`, else a random free port that isn't either.
+pub fn assign_port(name: &str, taken: &HashSet<u16>) -> Option<u16> {
+    let span = AUTO_PORTS.end - AUTO_PORTS.start;
+    let first = preferred_port(name) - AUTO_PORTS.start;
+    (0..AUTO_PORT_PROBES)
+        .map(|i| AUTO_PORTS.start + (first + i) % span)
+        .find(|p| !taken.contains(p) && port_free(*p))
+        .or_else(|| {
+            std::iter::repeat_with(free_port)
+                .take(8)
+                .flatten()
+                .find(|p| !taken.contains(p))
+        })
+}
+
+/// Whether nothing is listening on `port` on the loopback interface.
+pub fn port_free(port: u16) -> bool {
+    std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
 /// Shell-style command splitting: handles single/double quotes and backslash escapes.
 /// e.g. `node server.js --title 'hello world'` → ["node", "server.js", "--title", "hello world"]
 pub fn split_cmd(cmd: &str) -> Vec<String> {
@@ -156,8 +268,7 @@ pub fn split_cmd(cmd: &str) -> Vec<String> {
 
 /// Detect framework from the command and inject `--port <port>` if needed.
 /// `parts` is the full split command (program + args).
-pub fn framework_port_args(parts: &[String], port: u16) -> Vec<String> {
-    let p = port.to_string();
+pub fn framework_port_args(parts: &[String], port: &str) -> Vec<String> {
     let direct = [
         "vite",
         "next",
@@ -185,7 +296,7 @@ pub fn framework_port_args(parts: &[String], port: u16) -> Vec<String> {
     };
 
     if direct.contains(&framework) {
-        vec!["--port".into(), p]
+        vec!["--port".into(), port.to_string()]
     } else {
         vec![]
     }
@@ -195,6 +306,26 @@ pub fn framework_port_args(parts: &[String], port: u16) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_preferred_port_is_stable_per_name() {
+        assert_eq!(preferred_port("web"), preferred_port("web"));
+        assert_ne!(preferred_port("web"), preferred_port("api"));
+        for name in ["", "web", "a-very-long-service-name"] {
+            assert!(AUTO_PORTS.contains(&preferred_port(name)), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_assign_port_skips_taken_and_busy_ports() {
+        let first = preferred_port("assign-test");
+        // Busy either way: ours, or whoever already listens there.
+        let _busy = std::net::TcpListener::bind(("127.0.0.1", first));
+        let taken = HashSet::from([first + 1]);
+        let port = assign_port("assign-test", &taken).unwrap();
+        assert!(port != first && port != first + 1, "{port}");
+        assert!(port_free(port));
+    }
+
     #[test]
     fn test_split_cmd_simple() {
         assert_eq!(split_cmd("node server.js"), vec!["node", "server.js"]);
@@ -221,27 +352,64 @@ mod tests {
         assert_eq!(split_cmd(r"echo hello\ world"), vec!["echo", "hello world"]);
     }
 
+    #[test]
+    fn test_shell_argv() {
+        let line = "make build | tee out.log";
+        assert_eq!(
+            shell_argv(line, None),
+            [DEFAULT_SHELL.0, DEFAULT_SHELL.1, line]
+        );
+        assert_eq!(shell_argv(line, Some("bash")), ["bash", "-c", line]);
+        assert_eq!(
+            shell_argv("node server.js --title 'hello world'", Some("none")),
+            ["node", "server.js", "--title", "hello world"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hooks_run_under_the_service_shell() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let mut svc: ServiceDef = hcl::from_str(r#"cmd = "true""#).unwrap();
+        svc.dir = Some(dir.path().to_path_buf());
+        // Redirection and `;` only work through a shell.
+        run_hook("echo a > out; echo b >> out", &svc, "web")
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "a\nb\n");
+
+        // Without one, `>` is just an argument to echo.
+        let direct = ServiceDef {
+            shell: Some("none".into()),
+            ..svc.clone()
+        };
+        run_hook("echo c > out", &direct, "web").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "a\nb\n");
+        assert!(run_hook("false", &direct, "web").await.is_err());
+    }
+
     #[test]
     fn test_framework_port_args_direct() {
         let parts = vec!["vite".to_string()];
-        assert_eq!(framework_port_args(&parts, 3000), vec!["--port", "3000"]);
+        assert_eq!(framework_port_args(&parts, "3000"), vec!["--port", "3000"]);
     }
 
     #[test]
     fn test_framework_port_args_npx() {
         let parts = vec!["npx".to_string(), "vite".to_string()];
-        assert_eq!(framework_port_args(&parts, 3000), vec!["--port", "3000"]);
+        assert_eq!(framework_port_args(&parts, "3000"), vec!["--port", "3000"]);
     }
 
     #[test]
     fn test_framework_port_args_pnpm_exec() {
         let parts = vec!["pnpm".to_string(), "exec".to_string(), "next".to_string()];
-        assert_eq!(framework_port_args(&parts, 3000), vec!["--port", "3000"]);
+        assert_eq!(framework_port_args(&parts, "3000"), vec!["--port", "3000"]);
     }
 
     #[test]
     fn test_framework_port_args_unknown() {
         let parts = vec!["node".to_string(), "server.js".to_string()];
-        assert_eq!(framework_port_args(&parts, 3000), Vec::<String>::new());
+        assert_eq!(framework_port_args(&parts, "3000"), Vec::<String>::new());
     }
 }
@@ -0,0 +1,310 @@
+//! Warm standby for expensive services (`standby = true`).
+//!
+//! Next to the active instance the supervisor keeps one pre-warmed spare on its own
+//! auto-assigned port. The spare is invisible to routing and to `depends_on`
+//! readiness; a restart promotes it by swapping the proxy route over to its port and
+//! only then stopping the old instance. A new spare is warmed in the background after
+//! every swap. File changes make the spare stale, so they start a fresh instance
+//! instead — still swapped in only once healthy.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::process::Child;
+use tokio::sync::{broadcast, RwLock};
+
+use super::limits::Cgroup;
+use super::spawn::{free_port, spawn_process, SpawnSpec};
+use super::{ConfigCell, ServiceHandle, SupervisorEvent};
+use crate::error::{DevError, Result};
+use crate::health::HealthChecker;
+use crate::ipc::StandbyRow;
+use crate::log::LogAggregator;
+use crate::proxy::ProxyRouter;
+use crate::state::ServiceState;
+
+/// A spare instance waiting to be promoted.
+pub(super) struct Spare {
+    child: Child,
+    pid: u32,
+    port: u16,
+    /// Set once the spare passed its health check; only ready spares are promoted.
+    ready: bool,
+    cgroup: Option<Cgroup>,
+}
+
+pub(super) type Spares = Arc<RwLock<HashMap<String, Spare>>>;
+
+/// Status of `name`'s spare for `a3s status`, reaping it if it has exited.
+pub(super) async fn spare_row(spares: &Spares, name: &str) -> Option<StandbyRow> {
+    let mut map = spares.write().await;
+    let spare = map.get_mut(name)?;
+    if !matches!(spare.child.try_wait(), Ok(None)) {
+        if let Some(cg) = map.remove(name).and_then(|s| s.cgroup) {
+            cg.remove();
+        }
+        return None;
+    }
+    Some(StandbyRow {
+        state: if spare.ready { "running" } else { "starting" }.into(),
+        pid: Some(spare.pid),
+        port: spare.port,
+    })
+}
+
+/// Everything needed to manage the instances of one standby service from any task.
+#[derive(Clone)]
+pub(super) struct StandbyCtx {
+    pub name: String,
+    pub color_idx: usize,
+    pub handles: Arc<RwLock<HashMap<String, ServiceHandle>>>,
+    pub spares: Spares,
+    pub config: ConfigCell,
+    pub events: broadcast::Sender<SupervisorEvent>,
+    pub log: Arc<LogAggregator>,
+    pub proxy: Arc<ProxyRouter>,
+    pub config_dir: PathBuf,
+}
+
+impl StandbyCtx {
+    /// (Re)start the spare in the background, replacing any existing one.
+    pub fn warm(&self) {
+        let ctx = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = ctx.start_spare().await {
+                tracing::warn!("[{}] standby: {e}", ctx.name);
+            }
+        });
+    }
+
+    /// Swap a ready spare in as the active instance.
+    /// Returns false (and changes nothing) when no ready spare is available.
+    pub async fn promote(&self) -> bool {
+        let spare = {
+            let mut map = self.spares.write().await;
+            let promotable = map
+                .get_mut(&self.name)
+                .is_some_and(|s| s.ready && matches!(s.child.try_wait(), Ok(None)));
+            if promotable {
+                map.remove(&self.name)
+            } else {
+                None
+            }
+        };
+        let Some(spare) = spare else {
+            return false;
+        };
+        tracing::info!("[{}] promoting standby on :{}", self.name, spare.port);
+        self.swap_in(spare).await;
+        self.warm();
+        true
+    }
+
+    /// Zero-downtime restart: start a fresh instance, wait until it is healthy, then
+    /// swap it in. The old instance keeps serving if the new one never becomes healthy.
+    pub async fn replace_fresh(&self) -> Result<()> {
+        let fresh = self.launch().await?;
+        if !self.healthy(fresh.port).await {
+            retire(fresh).await;
+            return Err(DevError::Process {
+                service: self.name.clone(),
+                msg: "new instance never became healthy — keeping the old one".into(),
+            });
+        }
+        self.swap_in(fresh).await;
+        Ok(())
+    }
+
+    /// Kill the spare, if any.
+    pub async fn discard(&self) {
+        let spare = self.spares.write().await.remove(&self.name);
+        if let Some(spare) = spare {
+            retire(spare).await;
+        }
+    }
+
+    async fn start_spare(&self) -> Result<()> {
+        self.discard().await;
+        let spare = self.launch().await?;
+        let (pid, port) = (spare.pid, spare.port);
+        self.spares.write().await.insert(self.name.clone(), spare);
+        tracing::info!("[{}] warming standby on :{port}", self.name);
+
+        // Health-wait without holding the map so status and discard stay responsive.
+        let healthy = self.healthy(port).await;
+        let active = self.is_active().await;
+
+        let mut map = self.spares.write().await;
+        let Some(spare) = map.get_mut(&self.name).filter(|s| s.pid == pid) else {
+            return Ok(()); // replaced or discarded while warming
+        };
+        if healthy && active {
+            spare.ready = true;
+            tracing::info!("[{}] standby ready", self.name);
+            return Ok(());
+        }
+        let Some(spare) = map.remove(&self.name) else {
+            return Ok(());
+        };
+        drop(map);
+        retire(spare).await;
+        if healthy {
+            Ok(()) // the service was stopped meanwhile
+        } else {
+            Err(DevError::Process {
+                service: self.name.clone(),
+                msg: format!("spare (pid {pid}) never became healthy"),
+            })
+        }
+    }
+
+    /// Spawn a new instance of the service on a fresh auto-assigned port.
+    async fn launch(&self) -> Result<Spare> {
+        let svc = self
+            .config
+            .read()
+            .unwrap()
+            .service
+            .get(&self.name)
+            .cloned()
+            .ok_or_else(|| DevError::UnknownService(self.name.clone()))?;
+        let port = free_port()
+            .ok_or_else(|| DevError::Config(format!("[{}] no free port available", self.name)))?;
+        let svc = {
+            let cfg = Arc::clone(&self.config.read().unwrap());
+            let mut ports = super::runtime_ports(&self.handles, &cfg).await;
+            ports.insert(self.name.clone(), port);
+            crate::config::resolve_service_ports(svc, &ports)
+        };
+        let spec = SpawnSpec {
+            name: &self.name,
+            svc: &svc,
+            port,
+            color_idx: self.color_idx,
+            config_dir: &self.config_dir,
+            standby: true,
+        };
+        let result = spawn_process(&spec, &self.log).await?;
+        Ok(Spare {
+            child: result.child,
+            pid: result.pid,
+            port,
+            ready: false,
+            cgroup: result.cgroup,
+        })
+    }
+
+    /// Wait for an instance on `port` to pass the service health check.
+    async fn healthy(&self, port: u16) -> bool {
+        let svc = self.config.read().unwrap().service.get(&self.name).cloned();
+        match svc {
+            Some(svc) => match HealthChecker::for_service(&svc) {
+                Some(checker) => checker.wait_healthy(&svc, port).await,
+                None => true,
+            },
+            None => false,
+        }
+    }
+
+    /// True while the active instance is up — spares outliving a stop are discarded.
+    async fn is_active(&self) -> bool {
+        self.handles.read().await.get(&self.name).is_some_and(|h| {
+            matches!(
+                h.state,
+                ServiceState::Running { .. } | ServiceState::Unhealthy { .. }
+            )
+        })
+    }
+
+    /// Make `next` the active instance: route to it, stop the previous one, then move
+    /// the new instance out of its spare cgroup into the service's own.
+    async fn swap_in(&self, next: Spare) {
+        let (pid, port) = (next.pid, next.port);
+        let old = {
+            let mut map = self.handles.write().await;
+            let Some(h) = map.get_mut(&self.name) else {
+                drop(map);
+                retire(next).await;
+                return;
+            };
+            let old_pid = h.state.pid();
+            // Crash recovery may be holding the real child of the old instance; it
+            // notices the pid change and switches to waiting on this one.
+            let old_child = std::mem::replace(&mut h.child, next.child);
+            h.port = port;
+            h.state = ServiceState::Running {
+                pid,
+                since: Instant::now(),
+            };
+            // Until the move below, OOM kills are detected in the spare's group.
+            let old_cgroup = std::mem::replace(&mut h.cgroup, next.cgroup);
+            (old_pid, old_child, old_cgroup)
+        };
+
+        let svc = self.config.read().unwrap().service.get(&self.name).cloned();
+        if let Some(sub) = svc.as_ref().and_then(|s| s.subdomain.clone()) {
+            // The spare passed its health check before it could be promoted.
+            self.proxy.set_starting(&sub, false).await;
+            self.proxy.update(sub, port).await;
+        }
+        let _ = self
+            .events
+            .send(SupervisorEvent::new(&self.name, "running"));
+        tracing::info!("[{}] now serving on :{port}", self.name);
+
+        let stop_timeout = svc
+            .as_ref()
+            .map(|s| s.stop_timeout)
+            .unwrap_or(std::time::Duration::from_secs(5));
+        let (old_pid, old_child, old_cgroup) = old;
+        if let Some(old_pid) = old_pid {
+            terminate(old_pid, old_child, stop_timeout).await;
+        }
+
+        if let Some(cg) = old_cgroup {
+            cg.remove();
+        }
+
+        // The old instance is gone, so the service's own group is free again.
+        let Some(limits) = svc.and_then(|s| s.limits) else {
+            return;
+        };
+        let mut map = self.handles.write().await;
+        let spare_cg = map
+            .get_mut(&self.name)
+            .filter(|h| h.state.pid() == Some(pid))
+            .and_then(|h| h.cgroup.as_mut());
+        if let Some(cg) = spare_cg {
+            match cg.clone().promote(&self.name, &limits) {
+                Ok(moved) => *cg = moved,
+                Err(e) => tracing::warn!("[{}] standby: cgroup not moved: {e}", self.name),
+            }
+        }
+    }
+}
+
+/// Stop a spare that will never serve and remove its cgroup.
+async fn retire(spare: Spare) {
+    terminate(spare.pid, spare.child, std::time::Duration::from_secs(1)).await;
+    if let Some(cg) = spare.cgroup {
+        cg.remove();
+    }
+}
+
+/// SIGTERM the process group, wait up to `grace`, then SIGKILL.
+async fn terminate(pid: u32, mut child: Child, grace: std::time::Duration) {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{kill, Signal};
+        use nix::unistd::Pid;
+        let pgid = Pid::from_raw(-(pid as i32));
+        let _ = kill(pgid, Signal::SIGTERM);
+        let _ = tokio::time::timeout(grace, child.wait()).await;
+        let _ = kill(pgid, Signal::SIGKILL);
+    }
+    #[cfg(not(unix))]
+    let _ = (pid, grace);
+    let _ = child.kill().await;
+}
@@ -0,0 +1,284 @@
+//! CPU and memory usage of each service for status rows.
+//!
+//! Many `cmd`s are shell wrappers around the process doing the work, so usage is
+//! summed over the whole process tree under each service's pid. On Linux the tree is
+//! read from `/proc` and CPU is measured between samples; elsewhere one `ps` call
+//! lists every process with its own (decaying) CPU average.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Samples taken this close together share one walk of the process table, so
+/// concurrent status requests stay cheap.
+const CACHE_TTL: Duration = Duration::from_millis(500);
+
+/// Resource usage of a process and all of its descendants.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Usage {
+    /// Percent of one core; above 100 when several cores are busy.
+    pub cpu_percent: f64,
+    /// Resident memory.
+    pub rss_bytes: u64,
+}
+
+/// One entry of the process table.
+#[derive(Debug, Clone, PartialEq)]
+struct Proc {
+    pid: u32,
+    ppid: u32,
+    /// Linux: CPU seconds used so far. Elsewhere: `ps`'s %CPU.
+    cpu: f64,
+    /// Seconds since the process started (Linux only, else 0).
+    age_secs: f64,
+    rss_bytes: u64,
+}
+
+/// Caches the last sample and remembers each tree's CPU time to measure the next.
+#[derive(Default)]
+pub struct UsageSampler {
+    /// When, for which roots, and what was found (exited roots are missing).
+    cached: Option<(Instant, Vec<u32>, HashMap<u32, Usage>)>,
+    /// Tree CPU seconds per root pid at the previous sample (Linux).
+    prev_cpu: HashMap<u32, (f64, Instant)>,
+}
+
+impl UsageSampler {
+    /// Usage of the process trees rooted at `roots`. Pids that no longer exist are
+    /// left out.
+    pub fn sample(&mut self, roots: &[u32]) -> HashMap<u32, Usage> {
+        let now = Instant::now();
+        if let Some((at, sampled, usage)) = &self.cached {
+            if now.duration_since(*at) < CACHE_TTL && roots.iter().all(|p| sampled.contains(p)) {
+                return usage.clone();
+            }
+        }
+        let usage = self.measure(&process_table(), roots, now);
+        self.cached = Some((now, roots.to_vec(), usage.clone()));
+        usage
+    }
+
+    fn measure(&mut self, procs: &[Proc], roots: &[u32], now: Instant) -> HashMap<u32, Usage> {
+        let by_pid: HashMap<u32, &Proc> = procs.iter().map(|p| (p.pid, p)).collect();
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for p in procs {
+            children.entry(p.ppid).or_default().push(p.pid);
+        }
+        let mut usage = HashMap::new();
+        for &root in roots {
+            let Some(root_proc) = by_pid.get(&root) else {
+                continue;
+            };
+            let (mut cpu, mut rss_bytes) = (0.0, 0);
+            let mut stack = vec![root];
+            while let Some(pid) = stack.pop() {
+                if let Some(p) = by_pid.get(&pid) {
+                    cpu += p.cpu;
+                    rss_bytes += p.rss_bytes;
+                }
+                stack.extend(children.get(&pid).into_iter().flatten());
+            }
+            let cpu_percent = if cfg!(target_os = "linux") {
+                self.cpu_since_last(root, cpu, root_proc.age_secs, now)
+            } else {
+                cpu
+            };
+            usage.insert(
+                root,
+                Usage {
+                    cpu_percent,
+                    rss_bytes,
+                },
+            );
+        }
+        self.prev_cpu.retain(|pid, _| usage.contains_key(pid));
+        usage
+    }
+
+    /// CPU percent of a tree that has used `cpu_secs` so far: over the time since the
+    /// previous sample, or over the root's lifetime on the first one.
+    fn cpu_since_last(&mut self, root: u32, cpu_secs: f64, age_secs: f64, now: Instant) -> f64 {
+        let (used, elapsed) = match self.prev_cpu.insert(root, (cpu_secs, now)) {
+            // Exited children take their CPU time with them; never go negative.
+            Some((prev, at)) => ((cpu_secs - prev).max(0.0), (now - at).as_secs_f64()),
+            None => (cpu_secs, age_secs),
+        };
+        if elapsed > 0.0 {
+            used / elapsed * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_table() -> Vec<Proc> {
+    // SAFETY: sysconf has no side effects.
+    let (ticks, page) = unsafe {
+        (
+            libc::sysconf(libc::_SC_CLK_TCK),
+            libc::sysconf(libc::_SC_PAGESIZE),
+        )
+    };
+    let uptime = std::fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|u| u.split_whitespace().next()?.parse().ok())
+        .unwrap_or(0.0);
+    let Ok(dir) = std::fs::read_dir("/proc") else {
+        return vec![];
+    };
+    dir.flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+        })
+        .filter_map(|e| std::fs::read_to_string(e.path().join("stat")).ok())
+        .filter_map(|stat| parse_stat(&stat, ticks as f64, page as u64, uptime))
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_table() -> Vec<Proc> {
+    std::process::Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid=,%cpu=,rss="])
+        .output()
+        .map(|out| parse_ps(&String::from_utf8_lossy(&out.stdout)))
+        .unwrap_or_default()
+}
+
+/// Parse `/proc/<pid>/stat`. The command name is in parentheses and may itself
+/// contain spaces and parentheses, so fields are counted from the last `)`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_stat(stat: &str, ticks_per_sec: f64, page_size: u64, uptime: f64) -> Option<Proc> {
+    let pid = stat.split_whitespace().next()?.parse().ok()?;
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    // After the name: state, ppid, ... utime (11), stime (12), starttime (19), rss (21).
+    let field = |i: usize| fields.get(i)?.parse::<u64>().ok();
+    if ticks_per_sec <= 0.0 {
+        return None;
+    }
+    let start = field(19)? as f64 / ticks_per_sec;
+    Some(Proc {
+        pid,
+        ppid: field(1)? as u32,
+        cpu: (field(11)? + field(12)?) as f64 / ticks_per_sec,
+        age_secs: (uptime - start).max(0.0),
+        rss_bytes: field(21)? * page_size,
+    })
+}
+
+/// Parse `ps -A -o pid=,ppid=,%cpu=,rss=` (rss in KiB).
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn parse_ps(out: &str) -> Vec<Proc> {
+    out.lines()
+        .filter_map(|line| {
+            let mut cols = line.split_whitespace();
+            Some(Proc {
+                pid: cols.next()?.parse().ok()?,
+                ppid: cols.next()?.parse().ok()?,
+                cpu: cols.next()?.parse().ok()?,
+                age_secs: 0.0,
+                rss_bytes: cols.next()?.parse::<u64>().ok()? * 1024,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proc(pid: u32, ppid: u32, cpu: f64, rss_bytes: u64) -> Proc {
+        Proc {
+            pid,
+            ppid,
+            cpu,
+            age_secs: 0.0,
+            rss_bytes,
+        }
+    }
+
+    #[test]
+    fn test_parse_stat_counts_fields_from_the_last_paren() {
+        let stat = "4242 (my (odd) app) S 4200 4242 4242 0 -1 4194560 500 0 0 0 \
+                    150 50 0 0 20 0 1 0 1000 123456789 300 18446744073709551615";
+        let p = parse_stat(stat, 100.0, 4096, 30.0).unwrap();
+        assert_eq!(p.pid, 4242);
+        assert_eq!(p.ppid, 4200);
+        assert_eq!(p.cpu, 2.0);
+        assert_eq!(p.age_secs, 20.0);
+        assert_eq!(p.rss_bytes, 300 * 4096);
+        assert_eq!(parse_stat("4242 (truncated) S 1", 100.0, 4096, 30.0), None);
+    }
+
+    #[test]
+    fn test_parse_ps_skips_malformed_lines() {
+        let out = "    1     0   0.0  10240\n  812     1  12.5   2048\ngarbage\n";
+        assert_eq!(
+            parse_ps(out),
+            [
+                proc(1, 0, 0.0, 10240 * 1024),
+                proc(812, 1, 12.5, 2048 * 1024)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_usage_is_summed_over_the_process_tree() {
+        // 10 is a shell wrapper around 11, which forked 12; 20 is another service.
+        let procs = [
+            proc(1, 0, 0.0, 1000),
+            proc(10, 1, 0.5, 100),
+            proc(11, 10, 2.0, 2000),
+            proc(12, 11, 1.5, 300),
+            proc(20, 1, 4.0, 5000),
+        ];
+        let mut sampler = UsageSampler::default();
+        let usage = sampler.measure(&procs, &[10, 20, 99], Instant::now());
+        assert_eq!(usage[&10].rss_bytes, 2400);
+        assert_eq!(usage[&20].rss_bytes, 5000);
+        assert!(!usage.contains_key(&99), "gone pids are left out");
+    }
+
+    #[test]
+    fn test_cpu_percent_is_measured_between_samples() {
+        let mut sampler = UsageSampler::default();
+        let t0 = Instant::now();
+        // First sample: 5 CPU seconds over a 10s lifetime.
+        assert_eq!(sampler.cpu_since_last(10, 5.0, 10.0, t0), 50.0);
+        // Then 1.5 more seconds over the next 2s.
+        let t1 = t0 + Duration::from_secs(2);
+        assert_eq!(sampler.cpu_since_last(10, 6.5, 12.0, t1), 75.0);
+        // A child exiting shrinks the total; that reads as idle, not negative.
+        let t2 = t1 + Duration::from_secs(1);
+        assert_eq!(sampler.cpu_since_last(10, 6.0, 13.0, t2), 0.0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sample_reads_this_process() {
+        let pid = std::process::id();
+        let usage = UsageSampler::default().sample(&[pid]);
+        assert!(usage[&pid].rss_bytes > 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sample_cache_holds_when_a_root_has_exited() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let dead = child.id();
+        child.wait().unwrap();
+        let pid = std::process::id();
+        let mut sampler = UsageSampler::default();
+        let usage = sampler.sample(&[pid, dead]);
+        assert!(!usage.contains_key(&dead));
+        let at = sampler.cached.as_ref().unwrap().0;
+        sampler.sample(&[dead, pid]);
+        assert_eq!(
+            sampler.cached.as_ref().unwrap().0,
+            at,
+            "served from the cache"
+        );
+    }
+}
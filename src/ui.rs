@@ -7,7 +7,7 @@ use http_body_util::{combinators::BoxBody, BodyExt, Full, StreamBody};
 use hyper::body::Frame;
 use hyper_util::rt::TokioIo;
 use tokio::net::TcpListener;
-use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::{BroadcastStream, ReceiverStream};
 use tokio_stream::StreamExt;
 
 use crate::box_mgr;
@@ -17,8 +17,7 @@ pub const DEFAULT_UI_PORT: u16 = 10350;
 
 type BoxResp = Response<BoxBody<Bytes, Infallible>>;
 
-pub async fn serve(sup: Arc<Supervisor>, port: u16) {
-    let addr = format!("127.0.0.1:{port}");
+pub async fn serve(sup: Arc<Supervisor>, addr: std::net::SocketAddr, auth: Arc<Auth>) {
     let listener = match TcpListener::bind(&addr).await {
         Ok(l) => l,
         Err(e) => {
@@ -37,9 +36,24 @@ pub async fn serve(sup: Arc<Supervisor>, port: u16) {
             }
         };
         let sup = sup.clone();
+        let auth = auth.clone();
         tokio::spawn(async move {
             let io = TokioIo::new(stream);
-            let svc = hyper::service::service_fn(move |req| handle(req, sup.clone()));
+            let svc = hyper::service::service_fn(move |req| {
+                let sup = sup.clone();
+                let auth = auth.clone();
+                async move {
+                    if !auth.allows(&req) {
+                        return Ok::<_, Infallible>(unauthorized_response());
+                    }
+                    let cookie = auth.login_cookie(&req);
+                    let mut resp = handle(req, sup).await?;
+                    if let Some(cookie) = cookie {
+                        resp.headers_mut().insert(http::header::SET_COOKIE, cookie);
+                    }
+                    Ok(resp)
+                }
+            });
             if let Err(e) = hyper::server::conn::http1::Builder::new()
                 .serve_connection(io, svc)
                 .await
@@ -67,6 +81,20 @@ async fn handle(
             let body = serde_json::to_vec(&rows).unwrap_or_default();
             full_response("application/json", body)
         }
+        (Method::GET, "/api/health-report") => {
+            let body = serde_json::to_vec(&sup.health_report()).unwrap_or_default();
+            full_response("application/json", body)
+        }
+        (Method::GET, "/api/doctor") => {
+            let sup = sup.clone();
+            match tokio::task::spawn_blocking(move || sup.doctor()).await {
+                Ok(report) => full_response(
+                    "application/json",
+                    serde_json::to_vec(&report).unwrap_or_default(),
+                ),
+                Err(e) => error_response(&e.to_string()),
+            }
+        }
         (Method::GET, "/api/history") => {
             let service_filter: Option<String> = query
                 .split('&')
@@ -76,7 +104,7 @@ async fn handle(
                 .as_ref()
                 .map(|s| vec![s.clone()])
                 .unwrap_or_default();
-            let recent = sup.log_history(&services, 200);
+            let recent = sup.log_history(&services, 200, None);
             let body = serde_json::to_vec(&recent).unwrap_or_default();
             full_response("application/json", body)
         }
@@ -111,6 +139,22 @@ async fn handle(
                 .body(StreamBody::new(stream).map_err(|e| e).boxed())
                 .unwrap_or_default()
         }
+        (Method::GET, "/api/events") => {
+            // SSE stream of service state changes
+            let rx = sup.subscribe_events();
+            let stream = BroadcastStream::new(rx).filter_map(|item| {
+                let event = item.ok()?;
+                let data = format!("data: {}\n\n", serde_json::to_string(&event).ok()?);
+                Some(Ok::<_, Infallible>(Frame::data(Bytes::from(data))))
+            });
+
+            Response::builder()
+                .header("content-type", "text/event-stream")
+                .header("cache-control", "no-cache")
+                .header("access-control-allow-origin", "*")
+                .body(StreamBody::new(stream).map_err(|e| e).boxed())
+                .unwrap_or_default()
+        }
         (Method::POST, p) if p.starts_with("/api/restart/") => {
             let name = urldecode(&p["/api/restart/".len()..]);
             match sup.restart_service(&name).await {
@@ -127,8 +171,16 @@ async fn handle(
         }
         (Method::POST, p) if p.starts_with("/api/stop/") => {
             let name = urldecode(&p["/api/stop/".len()..]);
-            sup.stop_service(&name).await;
-            full_response("application/json", b"{\"ok\":true}".to_vec())
+            let dependents = query.split('&').any(|p| p == "dependents=true");
+            let still_running = sup.stop_one(&name, dependents).await;
+            full_response(
+                "application/json",
+                serde_json::to_vec(&serde_json::json!({
+                    "ok": true,
+                    "dependents_running": still_running,
+                }))
+                .unwrap_or_default(),
+            )
         }
         // ── Box API ──────────────────────────────────────────────────────────
         (Method::GET, "/api/box/containers") => {
@@ -163,12 +215,134 @@ async fn handle(
             Err(e) => error_response(&e.to_string()),
         },
         (Method::GET, "/api/box/info") => match box_mgr::get_info().await {
-            Ok(v) => full_response(
-                "application/json",
-                serde_json::to_vec(&v).unwrap_or_default(),
-            ),
+            Ok(mut v) => {
+                // Best-effort: the dashboard still shows the rest without it.
+                v.disk_usage = box_mgr::disk_usage().await.ok();
+                full_response(
+                    "application/json",
+                    serde_json::to_vec(&v).unwrap_or_default(),
+                )
+            }
             Err(e) => error_response(&e.to_string()),
         },
+        (Method::GET, "/api/kube/resources") => {
+            let selector: Option<String> = query
+                .split('&')
+                .find(|p| p.starts_with("selector="))
+                .map(|p| urldecode(&p["selector=".len()..]))
+                .filter(|s| !s.is_empty());
+            match sup.kube_resources(selector.as_deref()).await {
+                Ok(v) => full_response(
+                    "application/json",
+                    serde_json::to_vec(&v).unwrap_or_default(),
+                ),
+                Err(e) => error_response(&e.to_string()),
+            }
+        }
+        (Method::GET, "/api/kube/top") => {
+            let namespace: Option<String> = query
+                .split('&')
+                .find(|p| p.starts_with("namespace="))
+                .map(|p| urldecode(&p["namespace=".len()..]))
+                .filter(|s| !s.is_empty());
+            match sup.kube_top(namespace.as_deref()).await {
+                Ok(v) => full_response(
+                    "application/json",
+                    serde_json::to_vec(&v).unwrap_or_default(),
+                ),
+                Err(e) => error_response(&e.to_string()),
+            }
+        }
+        (Method::GET, p) if path_param(p, "/api/kube/pods/", "/logs").is_some() => {
+            let pod = urldecode(path_param(p, "/api/kube/pods/", "/logs").unwrap_or_default());
+            let namespace: Option<String> = query
+                .split('&')
+                .find(|p| p.starts_with("namespace="))
+                .map(|p| urldecode(&p["namespace=".len()..]))
+                .filter(|s| !s.is_empty());
+            let follow = query.contains("follow=true");
+            // SSE stream, one event per line; a client going away drops the receiver,
+            // which kills kubectl
+            let (tx, rx) = tokio::sync::mpsc::channel::<String>(64);
+            tokio::spawn(async move {
+                let (line_tx, mut line_rx) = tokio::sync::mpsc::channel(64);
+                let logs = sup.kube_pod_logs(namespace.as_deref(), &pod, follow, line_tx);
+                let relay = async move {
+                    loop {
+                        let line = tokio::select! {
+                            line = line_rx.recv() => line,
+                            // Notice a client that left while the pod is quiet
+                            _ = tx.closed() => None,
+                        };
+                        let Some(line) = line else { break };
+                        let event = format!("data: {}\n\n", serde_json::json!({ "line": line }));
+                        if tx.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    tx
+                };
+                let (res, tx) = tokio::join!(logs, relay);
+                if let Err(e) = res {
+                    let event = format!(
+                        "event: error\ndata: {}\n\n",
+                        serde_json::json!({ "error": e.to_string() })
+                    );
+                    let _ = tx.send(event).await;
+                }
+            });
+            let stream = ReceiverStream::new(rx)
+                .map(|event| Ok::<_, Infallible>(Frame::data(Bytes::from(event))));
+            Response::builder()
+                .header("content-type", "text/event-stream")
+                .header("cache-control", "no-cache")
+                .header("access-control-allow-origin", "*")
+                .body(StreamBody::new(stream).map_err(|e| e).boxed())
+                .unwrap_or_default()
+        }
+        (Method::GET, p) if p.starts_with("/api/box/stats/") => {
+            let id = urldecode(&p["/api/box/stats/".len()..]);
+            if query.contains("stream=true") {
+                // SSE stream, one event per sample, until the client goes away
+                let (tx, rx) = tokio::sync::mpsc::channel(4);
+                tokio::spawn(async move {
+                    if let Err(e) = box_mgr::container_stats_stream(&id, tx).await {
+                        tracing::debug!("stats stream for {id} ended: {e}");
+                    }
+                });
+                let stream = ReceiverStream::new(rx).map(|stats| {
+                    let data = format!(
+                        "data: {}\n\n",
+                        serde_json::to_string(&stats).unwrap_or_default()
+                    );
+                    Ok::<_, Infallible>(Frame::data(Bytes::from(data)))
+                });
+                Response::builder()
+                    .header("content-type", "text/event-stream")
+                    .header("cache-control", "no-cache")
+                    .header("access-control-allow-origin", "*")
+                    .body(StreamBody::new(stream).map_err(|e| e).boxed())
+                    .unwrap_or_default()
+            } else {
+                match box_mgr::container_stats(&id).await {
+                    Ok(v) => full_response(
+                        "application/json",
+                        serde_json::to_vec(&v).unwrap_or_default(),
+                    ),
+                    Err(e) => error_response(&e.to_string()),
+                }
+            }
+        }
+        (Method::GET, p) if p.starts_with("/api/box/inspect/") => {
+            let id = urldecode(&p["/api/box/inspect/".len()..]);
+            match box_mgr::inspect(&id).await {
+                Ok(v) => full_response(
+                    "application/json",
+                    serde_json::to_vec(&v).unwrap_or_default(),
+                ),
+                Err(e) => error_response(&e.to_string()),
+            }
+        }
         (Method::GET, p) if p.starts_with("/api/box/logs/") => {
             let id = urldecode(&p["/api/box/logs/".len()..]);
             let tail: usize = query
@@ -188,6 +362,324 @@ async fn handle(
                 Err(e) => error_response(&e.to_string()),
             }
         }
+        (Method::POST, p) if path_param(p, "/api/box/containers/", "/restart").is_some() => {
+            let id =
+                urldecode(path_param(p, "/api/box/containers/", "/restart").unwrap_or_default());
+            let timeout: Option<u32> = query
+                .split('&')
+                .find(|p| p.starts_with("timeout="))
+                .and_then(|p| p["timeout=".len()..].parse().ok());
+            match box_mgr::restart_container(&id, timeout).await {
+                Ok(()) => full_response("application/json", b"{\"ok\":true}".to_vec()),
+                Err(e) => error_response(&e.to_string()),
+            }
+        }
+        (Method::POST, "/api/box/containers") => {
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            let mut spec = match serde_json::from_slice::<box_mgr::BoxRunSpec>(&body) {
+                Ok(spec) => spec,
+                Err(e) => return Ok(error_response(&format!("invalid request body: {e}"))),
+            };
+            // A foreground run would hold the request open until the container exits.
+            spec.detach = true;
+            // Relative bind mounts are relative to the A3sfile, like build contexts.
+            let dir = sup.config_dir();
+            for (host, _) in &mut spec.volumes {
+                if host.is_relative() {
+                    *host = dir.join(&*host);
+                }
+            }
+            match box_mgr::run_container(&spec).await {
+                Ok(c) => full_response(
+                    "application/json",
+                    serde_json::to_vec(&c).unwrap_or_default(),
+                ),
+                Err(e) => error_response(&e.to_string()),
+            }
+        }
+        (Method::POST, "/api/box/volumes") => {
+            #[derive(serde::Deserialize)]
+            struct VolumeBody {
+                name: String,
+                #[serde(default)]
+                driver: Option<String>,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            match serde_json::from_slice::<VolumeBody>(&body) {
+                Ok(b) => match box_mgr::create_volume_listed(&b.name, b.driver.as_deref()).await {
+                    Ok(v) => full_response(
+                        "application/json",
+                        serde_json::to_vec(&v).unwrap_or_default(),
+                    ),
+                    Err(e) => error_response(&e.to_string()),
+                },
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
+        (Method::POST, "/api/box/networks") => {
+            #[derive(serde::Deserialize)]
+            struct NetworkBody {
+                name: String,
+                #[serde(default)]
+                driver: Option<String>,
+                #[serde(default)]
+                subnet: Option<String>,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            match serde_json::from_slice::<NetworkBody>(&body) {
+                Ok(b) => {
+                    match box_mgr::create_network_listed(
+                        &b.name,
+                        b.driver.as_deref(),
+                        b.subnet.as_deref(),
+                    )
+                    .await
+                    {
+                        Ok(n) => full_response(
+                            "application/json",
+                            serde_json::to_vec(&n).unwrap_or_default(),
+                        ),
+                        Err(e) => error_response(&e.to_string()),
+                    }
+                }
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
+        (Method::POST, "/api/box/prune") => {
+            #[derive(serde::Deserialize, Default)]
+            struct PruneBody {
+                #[serde(default)]
+                volumes: bool,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            let parsed = if body.iter().all(u8::is_ascii_whitespace) {
+                Ok(PruneBody::default())
+            } else {
+                serde_json::from_slice::<PruneBody>(&body)
+            };
+            match parsed {
+                Ok(PruneBody { volumes }) => match box_mgr::prune(volumes).await {
+                    Ok(report) => full_response(
+                        "application/json",
+                        serde_json::to_vec(&report).unwrap_or_default(),
+                    ),
+                    Err(e) => error_response(&e.to_string()),
+                },
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
+        (Method::POST, p) if path_param(p, "/api/box/containers/", "/exec").is_some() => {
+            let id = urldecode(path_param(p, "/api/box/containers/", "/exec").unwrap_or_default());
+            #[derive(serde::Deserialize)]
+            struct ExecBody {
+                command: Vec<String>,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            match serde_json::from_slice::<ExecBody>(&body) {
+                Ok(ExecBody { command }) if !command.is_empty() => {
+                    let command: Vec<&str> = command.iter().map(String::as_str).collect();
+                    match box_mgr::exec_container(&id, &command).await {
+                        Ok(output) => full_response(
+                            "application/json",
+                            serde_json::to_vec(&serde_json::json!({ "output": output }))
+                                .unwrap_or_default(),
+                        ),
+                        Err(e) => error_response(&e.to_string()),
+                    }
+                }
+                Ok(_) => error_response("command must not be empty"),
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
+        (Method::POST, "/api/box/images/build") => {
+            #[derive(serde::Deserialize)]
+            struct BuildBody {
+                context: std::path::PathBuf,
+                tag: Option<String>,
+                file: Option<std::path::PathBuf>,
+                #[serde(default)]
+                build_args: std::collections::HashMap<String, String>,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            let build = match serde_json::from_slice::<BuildBody>(&body) {
+                Ok(b) => b,
+                Err(e) => return Ok(error_response(&format!("invalid request body: {e}"))),
+            };
+            let dir = sup.config_dir();
+            let context = dir.join(&build.context);
+            let file = build.file.map(|f| dir.join(f));
+            let tag = build
+                .tag
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| box_mgr::default_build_tag(&context));
+            let done = serde_json::json!({ "tag": tag });
+            line_events(done, move |lines| async move {
+                box_mgr::build_image(&context, &tag, file.as_deref(), &build.build_args, lines)
+                    .await
+            })
+        }
+        (Method::POST, "/api/box/images/push") => {
+            #[derive(serde::Deserialize)]
+            struct PushBody {
+                reference: String,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            let reference = match serde_json::from_slice::<PushBody>(&body) {
+                Ok(b) => b.reference,
+                Err(e) => return Ok(error_response(&format!("invalid request body: {e}"))),
+            };
+            let done = serde_json::json!({ "reference": reference });
+            line_events(done, move |lines| async move {
+                box_mgr::push_image(&reference, lines).await
+            })
+        }
+        (Method::POST, "/api/box/images/tag") => {
+            #[derive(serde::Deserialize)]
+            struct TagBody {
+                source: String,
+                target: String,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            match serde_json::from_slice::<TagBody>(&body) {
+                Ok(b) => match box_mgr::tag_image(&b.source, &b.target).await {
+                    Ok(()) => full_response("application/json", b"{\"ok\":true}".to_vec()),
+                    Err(e) => error_response(&e.to_string()),
+                },
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
+        (Method::POST, "/api/box/images/save") => {
+            #[derive(serde::Deserialize)]
+            struct SaveBody {
+                reference: String,
+                path: std::path::PathBuf,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            match serde_json::from_slice::<SaveBody>(&body) {
+                Ok(b) => {
+                    let path = sup.config_dir().join(&b.path);
+                    match box_mgr::save_image(&b.reference, &path).await {
+                        Ok(()) => full_response(
+                            "application/json",
+                            serde_json::to_vec(&serde_json::json!({ "path": path }))
+                                .unwrap_or_default(),
+                        ),
+                        Err(e) => error_response(&e.to_string()),
+                    }
+                }
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
+        (Method::POST, "/api/box/images/load") => {
+            #[derive(serde::Deserialize)]
+            struct LoadBody {
+                path: std::path::PathBuf,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            match serde_json::from_slice::<LoadBody>(&body) {
+                Ok(b) => match box_mgr::load_image(&sup.config_dir().join(&b.path)).await {
+                    Ok(images) => full_response(
+                        "application/json",
+                        serde_json::to_vec(&serde_json::json!({ "images": images }))
+                            .unwrap_or_default(),
+                    ),
+                    Err(e) => error_response(&e.to_string()),
+                },
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
+        (Method::POST, "/api/box/login") => {
+            #[derive(serde::Deserialize)]
+            struct LoginBody {
+                server: String,
+                username: String,
+                password: String,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            match serde_json::from_slice::<LoginBody>(&body) {
+                Ok(b) => match box_mgr::login_registry(&b.server, &b.username, &b.password).await {
+                    Ok(()) => full_response("application/json", b"{\"ok\":true}".to_vec()),
+                    Err(e) => error_response(&e.to_string()),
+                },
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
+        (Method::POST, "/api/box/logout") => {
+            #[derive(serde::Deserialize)]
+            struct LogoutBody {
+                server: String,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            match serde_json::from_slice::<LogoutBody>(&body) {
+                Ok(b) => match box_mgr::logout_registry(&b.server).await {
+                    Ok(()) => full_response("application/json", b"{\"ok\":true}".to_vec()),
+                    Err(e) => error_response(&e.to_string()),
+                },
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
+        (Method::POST, p) if path_param(p, "/api/kube/deployments/", "/scale").is_some() => {
+            let name =
+                urldecode(path_param(p, "/api/kube/deployments/", "/scale").unwrap_or_default());
+            #[derive(serde::Deserialize)]
+            struct ScaleBody {
+                replicas: u32,
+                namespace: Option<String>,
+            }
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error_response(&e.to_string())),
+            };
+            match serde_json::from_slice::<ScaleBody>(&body) {
+                Ok(ScaleBody {
+                    replicas,
+                    namespace,
+                }) => match sup
+                    .kube_scale_deployment(namespace.as_deref(), &name, replicas)
+                    .await
+                {
+                    Ok(()) => full_response("application/json", b"{\"ok\":true}".to_vec()),
+                    Err(e) => error_response(&e.to_string()),
+                },
+                Err(e) => error_response(&format!("invalid request body: {e}")),
+            }
+        }
         (Method::DELETE, p) if p.starts_with("/api/box/container/") => {
             let id = urldecode(&p["/api/box/container/".len()..]);
             match box_mgr::remove_container(&id).await {
@@ -219,7 +711,7 @@ async fn handle(
         (Method::POST, p) if p.starts_with("/api/box/pull/") => {
             let r = urldecode(&p["/api/box/pull/".len()..]);
             tokio::spawn(async move {
-                let _ = box_mgr::pull_image(&r).await;
+                let _ = box_mgr::pull_image(&r, None).await;
             });
             full_response("application/json", b"{\"ok\":true}".to_vec())
         }
@@ -236,6 +728,175 @@ async fn handle(
     Ok(resp)
 }
 
+// ── Auth ─────────────────────────────────────────────────────────────────────
+
+/// Name of the cookie the UI page keeps the token in.
+const TOKEN_COOKIE: &str = "a3s_token";
+
+/// A per-daemon token guarding the API. Mutating routes always need it; reads only
+/// when the UI listens beyond loopback. Clients send `Authorization: Bearer <token>`
+/// or the `a3s_token` cookie, which the page gets by being opened as `/?token=<token>`.
+pub struct Auth {
+    token: String,
+    loopback: bool,
+}
+
+impl Auth {
+    /// A fresh random token for a UI bound to `addr`.
+    pub fn generate(addr: &std::net::SocketAddr) -> std::io::Result<Self> {
+        Ok(Self::new(random_token()?, addr.ip().is_loopback()))
+    }
+
+    fn new(token: String, loopback: bool) -> Self {
+        Self { token, loopback }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Write the token to `path`, readable by the owner only.
+    pub fn save(&self, path: &std::path::Path) -> std::io::Result<()> {
+        save_token(&self.token, path)
+    }
+
+    fn allows<B>(&self, req: &Request<B>) -> bool {
+        let read_only = matches!(*req.method(), Method::GET | Method::HEAD);
+        if read_only && self.loopback {
+            return true;
+        }
+        self.login_cookie(req).is_some()
+            || presented_token(req).is_some_and(|t| constant_time_eq(t, &self.token))
+    }
+
+    /// `Set-Cookie` for a page opened with the right `?token=`.
+    fn login_cookie<B>(&self, req: &Request<B>) -> Option<http::HeaderValue> {
+        if !matches!(req.uri().path(), "/" | "/index.html") {
+            return None;
+        }
+        let token = req
+            .uri()
+            .query()?
+            .split('&')
+            .find_map(|kv| kv.strip_prefix("token="))?;
+        if !constant_time_eq(&urldecode(token), &self.token) {
+            return None;
+        }
+        http::HeaderValue::from_str(&format!(
+            "{TOKEN_COOKIE}={}; Path=/; HttpOnly; SameSite=Strict",
+            self.token
+        ))
+        .ok()
+    }
+}
+
+/// 32 random bytes, hex-encoded.
+pub(crate) fn random_token() -> std::io::Result<String> {
+    use std::io::Read;
+    let mut bytes = [0u8; 32];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Write `token` to `path`, readable by the owner only.
+pub(crate) fn save_token(token: &str, path: &std::path::Path) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // `mode` only applies on create; tighten a file left by an older version.
+    std::fs::set_permissions(path, std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(token.as_bytes())
+}
+
+/// `~/.a3s/token`.
+pub fn default_token_path() -> std::path::PathBuf {
+    let home = std::env::var_os("HOME").unwrap_or_else(|| ".".into());
+    std::path::PathBuf::from(home).join(".a3s").join("token")
+}
+
+/// The token from the `Authorization: Bearer` header or the token cookie.
+fn presented_token<B>(req: &Request<B>) -> Option<&str> {
+    let headers = req.headers();
+    if let Some(bearer) = headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        return Some(bearer.trim());
+    }
+    headers
+        .get_all(http::header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|c| c.trim().strip_prefix(TOKEN_COOKIE)?.strip_prefix('='))
+}
+
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn unauthorized_response() -> BoxResp {
+    let body = br#"{"error":"missing or invalid token: open the UI with the URL `a3s up` prints, or send `Authorization: Bearer $(cat ~/.a3s/token)`"}"#;
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header("content-type", "application/json")
+        .header("www-authenticate", "Bearer")
+        .body(Full::new(Bytes::from_static(body)).map_err(|e| e).boxed())
+        .unwrap()
+}
+
+/// SSE stream of the lines `job` sends, each as `{"line": ...}`, then a `done` event
+/// carrying `done` or an `error` event; the job runs to the end even if the client
+/// leaves.
+fn line_events<F, Fut>(done: serde_json::Value, job: F) -> BoxResp
+where
+    F: FnOnce(tokio::sync::mpsc::Sender<String>) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = crate::error::Result<()>> + Send + 'static,
+{
+    let (tx, rx) = tokio::sync::mpsc::channel::<String>(64);
+    tokio::spawn(async move {
+        let (line_tx, mut line_rx) = tokio::sync::mpsc::channel(64);
+        let events = tx.clone();
+        let relay = tokio::spawn(async move {
+            while let Some(line) = line_rx.recv().await {
+                let event = format!("data: {}\n\n", serde_json::json!({ "line": line }));
+                let _ = events.send(event).await;
+            }
+        });
+        let res = job(line_tx).await;
+        let _ = relay.await;
+        let event = match res {
+            Ok(()) => format!("event: done\ndata: {done}\n\n"),
+            Err(e) => format!(
+                "event: error\ndata: {}\n\n",
+                serde_json::json!({ "error": e.to_string() })
+            ),
+        };
+        let _ = tx.send(event).await;
+    });
+    let stream =
+        ReceiverStream::new(rx).map(|event| Ok::<_, Infallible>(Frame::data(Bytes::from(event))));
+    Response::builder()
+        .header("content-type", "text/event-stream")
+        .header("cache-control", "no-cache")
+        .header("access-control-allow-origin", "*")
+        .body(StreamBody::new(stream).map_err(|e| e).boxed())
+        .unwrap_or_default()
+}
+
 fn full_response(content_type: &str, body: Vec<u8>) -> BoxResp {
     Response::builder()
         .header("content-type", content_type)
@@ -254,6 +915,14 @@ fn error_response(msg: &str) -> BoxResp {
         .unwrap()
 }
 
+/// The `<id>` of a `<prefix><id><suffix>` path, e.g. of
+/// `/api/box/containers/<id>/exec`.
+fn path_param<'a>(path: &'a str, prefix: &str, suffix: &str) -> Option<&'a str> {
+    path.strip_prefix(prefix)?
+        .strip_suffix(suffix)
+        .filter(|id| !id.is_empty() && !id.contains('/'))
+}
+
 fn urldecode(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     let bytes = s.as_bytes();
@@ -324,4 +993,87 @@ mod tests {
         assert_eq!(urldecode("my-service"), "my-service");
         assert_eq!(urldecode("svc%5F1"), "svc_1");
     }
+
+    #[test]
+    fn test_path_param() {
+        let exec = |p| path_param(p, "/api/box/containers/", "/exec");
+        assert_eq!(exec("/api/box/containers/a3s-db/exec"), Some("a3s-db"));
+        assert_eq!(exec("/api/box/containers/exec"), None);
+        assert_eq!(exec("/api/box/containers//exec"), None);
+        assert_eq!(exec("/api/box/containers/a/b/exec"), None);
+        assert_eq!(exec("/api/box/containers/a3s-db"), None);
+        assert_eq!(
+            path_param("/api/kube/pods/api-0/logs", "/api/kube/pods/", "/logs"),
+            Some("api-0")
+        );
+    }
+
+    // ── auth ─────────────────────────────────────────────────────────────────
+
+    fn req(method: Method, uri: &str, headers: &[(&str, &str)]) -> Request<()> {
+        let mut b = Request::builder().method(method).uri(uri);
+        for (k, v) in headers {
+            b = b.header(*k, *v);
+        }
+        b.body(()).unwrap()
+    }
+
+    #[test]
+    fn test_auth_guards_mutating_routes_on_loopback() {
+        let auth = Auth::new("s3cret".into(), true);
+        assert!(auth.allows(&req(Method::GET, "/api/status", &[])));
+        assert!(!auth.allows(&req(Method::POST, "/api/stop/api", &[])));
+        assert!(!auth.allows(&req(
+            Method::DELETE,
+            "/api/box/container/x",
+            &[("authorization", "Bearer wrong")]
+        )));
+        assert!(auth.allows(&req(
+            Method::DELETE,
+            "/api/box/container/x",
+            &[("authorization", "Bearer s3cret")]
+        )));
+        assert!(auth.allows(&req(
+            Method::POST,
+            "/api/restart/api",
+            &[("cookie", "theme=dark; a3s_token=s3cret")]
+        )));
+    }
+
+    #[test]
+    fn test_auth_guards_everything_beyond_loopback() {
+        let auth = Auth::new("s3cret".into(), false);
+        assert!(!auth.allows(&req(Method::GET, "/api/status", &[])));
+        assert!(!auth.allows(&req(Method::GET, "/", &[])));
+        assert!(auth.allows(&req(
+            Method::GET,
+            "/api/status",
+            &[("authorization", "Bearer s3cret")]
+        )));
+
+        // Opening the page with the token logs the browser in.
+        let login = req(Method::GET, "/?token=s3cret", &[]);
+        assert!(auth.allows(&login));
+        let cookie = auth.login_cookie(&login).unwrap();
+        assert!(cookie.to_str().unwrap().starts_with("a3s_token=s3cret;"));
+        assert!(auth
+            .login_cookie(&req(Method::GET, "/api/status?token=s3cret", &[]))
+            .is_none());
+        assert!(!auth.allows(&req(Method::GET, "/?token=guess", &[])));
+    }
+
+    #[test]
+    fn test_auth_token_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".a3s").join("token");
+        let addr = "127.0.0.1:10350".parse().unwrap();
+        let auth = Auth::generate(&addr).unwrap();
+        assert_eq!(auth.token().len(), 64);
+        assert_ne!(auth.token(), Auth::generate(&addr).unwrap().token());
+        auth.save(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), auth.token());
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
 }
@@ -4,11 +4,11 @@ import './index.css';
 
 // ── Types ─────────────────────────────────────────────────────
 
-type View = 'services' | 'box';
+type View = 'services' | 'box' | 'kube';
 
 type SvcState =
   | 'running' | 'starting' | 'restarting'
-  | 'stopped' | 'failed' | 'unhealthy' | 'pending';
+  | 'stopped' | 'failed' | 'unhealthy' | 'oom-killed' | 'crash-looped' | 'pending' | 'blocked' | 'not requested';
 
 interface StatusRow {
   name: string;
@@ -16,8 +16,29 @@ interface StatusRow {
   pid?: number;
   port: number;
   subdomain?: string;
-  uptime_secs?: number;
+  uptime_secs: number;
+  cpu_percent?: number;
+  rss_bytes?: number;
   proxy_port: number;
+  share_url?: string;
+  standby?: { state: 'starting' | 'running'; pid?: number; port: number };
+  flaky?: boolean;
+  health?: string;
+  last_healthy_at?: number;
+  last_health_error?: string;
+  image?: string;
+  start_error?: string;
+  exit_code?: number;
+  last_logs?: string[];
+}
+
+interface HealthReportRow {
+  name: string;
+  checks: number;
+  uptime_pct?: number;
+  flaps: number;
+  longest_outage_secs: number;
+  flaky: boolean;
 }
 
 interface LogEntry {
@@ -137,6 +158,14 @@ function useLogs(selected: string | null) {
   return lines;
 }
 
+/** Tooltip of the health badge: when the last check passed and why the last one failed. */
+function healthTitle(row: StatusRow): string {
+  const ok = row.last_healthy_at != null
+    ? `last healthy ${new Date(row.last_healthy_at * 1000).toLocaleTimeString()}`
+    : 'never healthy';
+  return row.last_health_error ? `${ok}\nlast failure: ${row.last_health_error}` : ok;
+}
+
 // ── Components ────────────────────────────────────────────────
 
 function SvcRow({
@@ -156,16 +185,56 @@ function SvcRow({
   return (
     <div className={`svc-row${active ? ' active' : ''}`} onClick={onSelect}>
       <div className="svc-top">
-        <div className={`svc-dot ${row.state}`} />
+        <div className={`svc-dot ${row.state.replace(' ', '-')}`} />
         <span className="svc-name" style={{ color: c }}>{row.name}</span>
-        <span className={`svc-badge ${row.state}`}>{row.state}</span>
+        {row.image && <span className="svc-badge container" title={row.image}>container</span>}
+        {row.flaky && <span className="svc-badge flaky">flaky</span>}
+        {row.health && row.health !== 'none' && (
+          <span
+            className={`svc-badge health-${row.health.split(' ')[0]}`}
+            title={healthTitle(row)}
+          >
+            {row.health}
+          </span>
+        )}
+        <span className={`svc-badge ${row.state.replace(' ', '-')}`}>{row.state}</span>
       </div>
       <div className="svc-bottom">
         <span className="svc-url">
           <a href={url} target="_blank" rel="noreferrer" onClick={e => e.stopPropagation()}>{url}</a>
         </span>
-        <span className="svc-uptime">↑{row.uptime_secs != null ? fmtUptime(row.uptime_secs) : '—'}</span>
+        {row.cpu_percent != null && row.rss_bytes != null && (
+          <span className="svc-uptime" title="CPU and memory of the process tree">
+            {row.cpu_percent.toFixed(1)}% · {fmtMib(row.rss_bytes / 1048576)}
+          </span>
+        )}
+        <span className="svc-uptime">↑{row.pid != null ? fmtUptime(row.uptime_secs) : '—'}</span>
       </div>
+      {row.share_url && (
+        <div className="svc-bottom">
+          <span className="svc-url">
+            shared <a href={row.share_url} target="_blank" rel="noreferrer" onClick={e => e.stopPropagation()}>{row.share_url}</a>
+          </span>
+        </div>
+      )}
+      {row.start_error && (
+        <div className="svc-bottom">
+          <span className="svc-url" title={row.start_error}>{row.start_error}</span>
+        </div>
+      )}
+      {row.state === 'crash-looped' && (
+        <div className="svc-bottom">
+          <span className="svc-url" title={row.last_logs?.join('\n')}>
+            exited {row.exit_code ?? '?'} on start — restart to retry
+          </span>
+        </div>
+      )}
+      {row.standby && (
+        <div className="svc-bottom">
+          <span className="svc-url">standby :{row.standby.port}</span>
+          <span className={`svc-badge ${row.standby.state}`}>{row.standby.state}</span>
+        </div>
+      )}
       <div className="svc-actions">
         <button className="act-btn restart" onClick={e => { e.stopPropagation(); onRestart(); }}>restart</button>
         <button className="act-btn stop" onClick={e => { e.stopPropagation(); onStop(); }}>stop</button>
@@ -211,7 +280,14 @@ function Sidebar({ rows, selected, onSelect, width, onWidthChange }: {
   }, [onWidthChange]);
 
   async function restart(name: string) { await fetch(`/api/restart/${encodeURIComponent(name)}`, { method: 'POST' }); }
-  async function stop(name: string)    { await fetch(`/api/stop/${encodeURIComponent(name)}`, { method: 'POST' }); }
+  async function stop(name: string) {
+    const r = await fetch(`/api/stop/${encodeURIComponent(name)}`, { method: 'POST' }).catch(() => null);
+    const body = r ? await r.json().catch(() => null) : null;
+    const left: string[] = body?.dependents_running ?? [];
+    if (left.length && confirm(`${left.join(', ')} still depend on ${name}. Stop them too?`)) {
+      await fetch(`/api/stop/${encodeURIComponent(name)}?dependents=true`, { method: 'POST' });
+    }
+  }
 
   return (
     <aside className="sidebar">
@@ -231,11 +307,36 @@ function Sidebar({ rows, selected, onSelect, width, onWidthChange }: {
           />
         ))}
       </div>
+      <HealthPanel />
       <div className="sidebar-resize" onMouseDown={onMouseDown} />
     </aside>
   );
 }
 
+function HealthPanel() {
+  const rows = useBoxData<HealthReportRow[]>('/api/health-report', 15000) ?? [];
+  if (rows.length === 0) return null;
+  return (
+    <div className="health-panel">
+      <div className="sidebar-head">
+        <span className="sidebar-label">Health · 24h</span>
+      </div>
+      {rows.map(r => (
+        <div key={r.name} className="health-row" title={`${r.checks} checks`}>
+          <span className="health-name" style={{ color: getColor(r.name) }}>{r.name}</span>
+          <span className={`health-uptime${r.flaky ? ' flaky' : ''}`}>
+            {r.uptime_pct != null ? `${r.uptime_pct.toFixed(2)}%` : '—'}
+          </span>
+          <span className="health-stat">{r.flaps} flaps</span>
+          <span className="health-stat">
+            {r.longest_outage_secs > 0 ? `↓${fmtUptime(r.longest_outage_secs)}` : '—'}
+          </span>
+        </div>
+      ))}
+    </div>
+  );
+}
+
 function LogPanel({ lines, selected, onAll }: { lines: LogEntry[]; selected: string | null; onAll: () => void }) {
   const bodyRef = useRef<HTMLDivElement>(null);
   const autoRef = useRef(true);
@@ -332,10 +433,21 @@ function Statusbar({ rows }: { rows: StatusRow[] }) {
 type BoxView = 'containers' | 'images' | 'networks' | 'volumes' | 'info';
 
 interface BoxContainer { id: string; name: string; image: string; status: string; created: string; ports: string; command: string; }
-interface BoxImage     { repository: string; tag: string; digest: string; size: string; pulled: string; reference: string; }
+interface BoxImage     { repository: string; tag: string; digest: string; size: string; pulled: string; reference: string; platform: string; emulated: boolean; }
+interface BoxInspect {
+  Id: string; Name: string; Image: string; RestartCount: number;
+  State: { Status: string; Running: boolean; ExitCode: number; StartedAt: string; FinishedAt: string; Health: { Status: string; FailingStreak: number } | null };
+  Mounts: { Type: string; Source: string; Destination: string; RW: boolean }[];
+  Config: { Env: string[]; Cmd: string[] };
+  HostConfig: { Memory: number; CpuShares: number; NanoCpus: number };
+  NetworkSettings: { IPAddress: string; Ports: Record<string, { HostIp: string; HostPort: string }[] | null> };
+}
 interface BoxNetwork   { name: string; driver: string; subnet: string; gateway: string; isolation: string; endpoints: string; }
 interface BoxVolume    { driver: string; name: string; mount_point: string; in_use_by: string; }
-interface BoxInfo      { version: string; virtualization: string; home: string; boxes_total: number; boxes_running: number; images_cached: string; }
+interface BoxInfo      { version: string; virtualization: string; home: string; boxes_total: number; boxes_running: number; images_cached: string; disk_usage?: BoxDiskUsage; }
+interface BoxDiskUsage { images_size: number; containers_size: number; volumes_size: number; build_cache_size: number; reclaimable: number; }
+const gb = (bytes: number) => `${(bytes / 1e9).toFixed(2)} GB`;
+interface BoxPruneReport { containers_deleted: number; images_deleted: number; volumes_deleted: number; space_reclaimed_bytes: number; }
 
 function useBoxData<T>(url: string, ms = 4000) {
   const [data, setData] = useState<T | null>(null);
@@ -359,7 +471,37 @@ function BoxPanel() {
   const [showAll, setShowAll] = useState(false);
   const [selectedCtr, setSelectedCtr] = useState<string | null>(null);
   const [ctrLogs, setCtrLogs] = useState('');
+  const [ctrInspect, setCtrInspect] = useState<BoxInspect | null>(null);
   const logRef = useRef<HTMLDivElement>(null);
+  const [pruneVolumes, setPruneVolumes] = useState(false);
+  const [pruneResult, setPruneResult] = useState<string | null>(null);
+
+  async function prune() {
+    const what = pruneVolumes ? 'stopped containers, dangling images, unused networks and volumes' : 'stopped containers, dangling images and unused networks';
+    if (!confirm(`Remove all ${what}?`)) return;
+    setPruneResult('pruning…');
+    const r = await fetch('/api/box/prune', { method: 'POST', body: JSON.stringify({ volumes: pruneVolumes }) }).catch(() => null);
+    const body = r ? await r.json().catch(() => null) : null;
+    if (!r || !r.ok || !body) { setPruneResult(body?.error ?? 'prune failed'); return; }
+    const p = body as BoxPruneReport;
+    const mb = (p.space_reclaimed_bytes / 1e6).toFixed(1);
+    setPruneResult(`${p.containers_deleted} containers · ${p.images_deleted} images · ${p.volumes_deleted} volumes · ${mb} MB reclaimed`);
+  }
+
+  async function runImage(image: string) {
+    const ports = prompt(`Run ${image} — publish ports (host:container, comma-separated, optional)`, '');
+    if (ports === null) return;
+    const pairs = ports.split(',').map(p => p.trim()).filter(Boolean)
+      .map(p => p.split(':').map(Number)).filter(p => p.length === 2 && p.every(n => n > 0));
+    const r = await fetch('/api/box/containers', {
+      method: 'POST',
+      body: JSON.stringify({ image, ports: pairs, detach: true }),
+    }).catch(() => null);
+    const body = r ? await r.json().catch(() => null) : null;
+    if (!r || !r.ok) { alert(body?.error ?? `could not run ${image}`); return; }
+    setView('containers');
+    setSelectedCtr((body as BoxContainer).id);
+  }
 
   const containers = useBoxData<BoxContainer[]>(`/api/box/containers?all=${showAll}`);
   const images     = useBoxData<BoxImage[]>('/api/box/images');
@@ -379,6 +521,17 @@ function BoxPanel() {
     return () => { alive = false; clearInterval(id); };
   }, [selectedCtr]);
 
+  useEffect(() => {
+    setCtrInspect(null);
+    if (!selectedCtr) return;
+    let alive = true;
+    fetch(`/api/box/inspect/${encodeURIComponent(selectedCtr)}`)
+      .then(r => r.ok ? r.json() : null)
+      .then(d => { if (alive) setCtrInspect(d); })
+      .catch(() => {});
+    return () => { alive = false; };
+  }, [selectedCtr]);
+
   useEffect(() => {
     if (logRef.current) logRef.current.scrollTop = logRef.current.scrollHeight;
   }, [ctrLogs]);
@@ -466,6 +619,9 @@ function BoxPanel() {
                             <button className="kube-del-btn" style={{ opacity: 1, color: 'var(--yellow)' }}
                               onClick={e => { e.stopPropagation(); fetch(`/api/box/stop/${encodeURIComponent(c.id)}`, { method: 'POST' }); }}
                               title="stop">■</button>
+                            <button className="kube-del-btn" style={{ opacity: 1, color: 'var(--cyan)' }}
+                              onClick={e => { e.stopPropagation(); fetch(`/api/box/containers/${encodeURIComponent(c.id)}/restart`, { method: 'POST' }); }}
+                              title="restart">↻</button>
                             <button className="kube-del-btn"
                               onClick={e => { e.stopPropagation(); fetch(`/api/box/container/${encodeURIComponent(c.id)}`, { method: 'DELETE' }); }}
                               title="remove">✕</button>
@@ -480,6 +636,31 @@ function BoxPanel() {
                     <div className="kube-log-drawer-head">
                       <span className="kube-log-drawer-title">{selectedCtr}</span>
                     </div>
+                    {ctrInspect && (
+                      <div className="box-inspect">
+                        <div><span>state</span>{ctrInspect.State.Status || (ctrInspect.State.Running ? 'running' : '—')}
+                          {!ctrInspect.State.Running && ctrInspect.State.FinishedAt && ` (exit ${ctrInspect.State.ExitCode})`}</div>
+                        <div><span>started</span>{ctrInspect.State.StartedAt || '—'}</div>
+                        <div><span>restarts</span>{ctrInspect.RestartCount}</div>
+                        {ctrInspect.State.Health && (
+                          <div><span>health</span>{ctrInspect.State.Health.Status}
+                            {ctrInspect.State.Health.FailingStreak > 0 && ` (${ctrInspect.State.Health.FailingStreak} failing)`}</div>
+                        )}
+                        <div><span>memory</span>{ctrInspect.HostConfig.Memory ? `${Math.round(ctrInspect.HostConfig.Memory / 1048576)} MiB` : 'unlimited'}</div>
+                        <div><span>cpu</span>{ctrInspect.HostConfig.NanoCpus ? `${ctrInspect.HostConfig.NanoCpus / 1e9} cpus` : 'unlimited'}
+                          {ctrInspect.HostConfig.CpuShares > 0 && ` · ${ctrInspect.HostConfig.CpuShares} shares`}</div>
+                        <div><span>ip</span>{ctrInspect.NetworkSettings.IPAddress || '—'}</div>
+                        {Object.entries(ctrInspect.NetworkSettings.Ports ?? {}).map(([port, binds]) => (
+                          <div key={port}><span>port</span>{port} → {binds?.map(b => `${b.HostIp}:${b.HostPort}`).join(', ') || 'not published'}</div>
+                        ))}
+                        {ctrInspect.Mounts.map(m => (
+                          <div key={m.Destination}><span>mount</span>{m.Source} → {m.Destination}{m.RW ? '' : ' (ro)'}</div>
+                        ))}
+                        {ctrInspect.Config.Env.map(e => (
+                          <div key={e}><span>env</span>{e}</div>
+                        ))}
+                      </div>
+                    )}
                     <div className="kube-log-body" ref={logRef}>
                       {ctrLogs
                         ? ctrLogs.split('\n').map((l, i) => <div key={i} className="kube-log-line">{l}</div>)
@@ -498,17 +679,24 @@ function BoxPanel() {
                 <span className="kube-section-count">{images?.length ?? '…'}</span>
               </div>
               <table className="kube-table">
-                <thead><tr><th>repository</th><th>tag</th><th>size</th><th>pulled</th><th></th></tr></thead>
+                <thead><tr><th>repository</th><th>tag</th><th>platform</th><th>size</th><th>pulled</th><th></th></tr></thead>
                 <tbody>
-                  {!images ? <tr><td colSpan={5} className="kube-empty-row">loading…</td></tr>
-                  : images.length === 0 ? <tr><td colSpan={5} className="kube-empty-row">no images cached</td></tr>
+                  {!images ? <tr><td colSpan={6} className="kube-empty-row">loading…</td></tr>
+                  : images.length === 0 ? <tr><td colSpan={6} className="kube-empty-row">no images cached</td></tr>
                   : images.map(img => (
                     <tr key={img.reference}>
                       <td className="kube-cell-name">{img.repository}</td>
                       <td><span className="kube-ns-tag">{img.tag || 'latest'}</span></td>
+                      <td className="kube-cell-dim">
+                        {img.platform || '—'}
+                        {img.emulated && <span className="svc-badge emulated" title="not the host architecture — runs under emulation">emulated</span>}
+                      </td>
                       <td className="kube-cell-dim">{img.size}</td>
                       <td className="kube-cell-dim">{img.pulled}</td>
-                      <td>
+                      <td style={{ display: 'flex', gap: 4 }}>
+                        <button className="kube-del-btn" style={{ color: 'var(--green)' }}
+                          onClick={() => runImage(img.reference)}
+                          title="run detached">▶</button>
                         <button className="kube-del-btn"
                           onClick={() => fetch(`/api/box/image/${encodeURIComponent(img.reference)}`, { method: 'DELETE' })}
                           title="remove">✕</button>
@@ -584,7 +772,14 @@ function BoxPanel() {
             <div className="kube-section">
               <div className="kube-section-head">
                 <span className="kube-section-title">system info</span>
+                <div className="log-head-spacer" />
+                <label className="box-toggle">
+                  <input type="checkbox" checked={pruneVolumes} onChange={e => setPruneVolumes(e.target.checked)} />
+                  volumes
+                </label>
+                <button className="kube-del-btn" style={{ opacity: 1 }} title="remove unused resources" onClick={prune}>prune</button>
               </div>
+              {pruneResult && <div className="log-empty" style={{ textAlign: 'left' }}>{pruneResult}</div>}
               {!info ? <div className="log-empty">loading…</div> : (
                 <div className="box-info-grid">
                   {[
@@ -593,6 +788,10 @@ function BoxPanel() {
                     ['home',           info.home],
                     ['containers',     `${info.boxes_running} running / ${info.boxes_total} total`],
                     ['images',         info.images_cached],
+                    ...(info.disk_usage ? [
+                      ['disk', `${gb(info.disk_usage.images_size)} images / ${gb(info.disk_usage.containers_size)} containers / ${gb(info.disk_usage.volumes_size)} volumes / ${gb(info.disk_usage.build_cache_size)} cache`],
+                      ['reclaimable', gb(info.disk_usage.reclaimable)],
+                    ] : []),
                   ].map(([k, v]) => (
                     <div key={k} className="box-info-row">
                       <span className="box-info-key">{k}</span>
@@ -609,6 +808,120 @@ function BoxPanel() {
   );
 }
 
+// ── Kube Panel ────────────────────────────────────────────────
+
+interface KubeDeployment {
+  name: string; namespace: string; image: string; age: string;
+  desired_replicas: number; ready_replicas: number; available_replicas: number;
+}
+interface KubeService { name: string; namespace: string; type: string; cluster_ip: string; ports: string[]; }
+interface KubeResources { deployments: KubeDeployment[]; services: KubeService[]; selector?: string; }
+interface NodeUsage { name: string; cpu_millicores: number; cpu_percent?: number; memory_mib: number; memory_percent?: number; }
+interface PodUsage { name: string; namespace: string; cpu_millicores: number; memory_mib: number; }
+interface KubeTop { nodes: NodeUsage[]; pods: PodUsage[]; metrics_available: boolean; message?: string; }
+
+function fmtMib(mib: number) {
+  return mib >= 1024 ? `${(mib / 1024).toFixed(1)}Gi` : `${Math.round(mib)}Mi`;
+}
+
+const MAX_REPLICAS = 100;
+
+function KubePanel() {
+  const res = useBoxData<KubeResources & { error?: string }>('/api/kube/resources', 5000);
+  const top = useBoxData<KubeTop & { error?: string }>('/api/kube/top', 10000);
+  const [scaleError, setScaleError] = useState<string | null>(null);
+
+  async function scale(d: KubeDeployment, replicas: number) {
+    if (replicas < 0 || replicas > MAX_REPLICAS) return;
+    setScaleError(null);
+    const r = await fetch(`/api/kube/deployments/${encodeURIComponent(d.name)}/scale`, {
+      method: 'POST',
+      body: JSON.stringify({ replicas, namespace: d.namespace }),
+    }).catch(() => null);
+    if (!r || !r.ok) {
+      const body = r ? await r.json().catch(() => null) : null;
+      setScaleError(body?.error ?? `could not scale ${d.name}`);
+    }
+  }
+
+  const deployments = res?.deployments;
+  return (
+    <main className="kube-panel">
+      <div className="kube-head">
+        <span className="log-scope">kube / <span className="log-scope-name">deployments</span></span>
+        <div className="log-head-spacer" />
+        {scaleError && <span style={{ fontFamily: 'var(--mono)', fontSize: 10, color: 'var(--red)' }}>{scaleError}</span>}
+      </div>
+      <div className="kube-content">
+        <div className="kube-section">
+          <div className="kube-section-head">
+            <span className="kube-section-title">deployments</span>
+            <span className="kube-section-count">{deployments?.length ?? '…'}</span>
+          </div>
+          <table className="kube-table">
+            <thead><tr><th>name</th><th>image</th><th>ready</th><th>available</th><th>age</th><th>replicas</th></tr></thead>
+            <tbody>
+              {res?.error ? <tr><td colSpan={6} className="kube-empty-row">{res.error}</td></tr>
+              : !deployments ? <tr><td colSpan={6} className="kube-empty-row">loading…</td></tr>
+              : deployments.length === 0 ? <tr><td colSpan={6} className="kube-empty-row">no deployments</td></tr>
+              : deployments.map(d => (
+                <tr key={`${d.namespace}/${d.name}`}>
+                  <td className="kube-cell-name">{d.name}</td>
+                  <td className="kube-cell-dim" style={{ maxWidth: 240, overflow: 'hidden', textOverflow: 'ellipsis', whiteSpace: 'nowrap' }}>{d.image || '—'}</td>
+                  <td style={{ color: d.ready_replicas >= d.desired_replicas ? 'var(--green)' : 'var(--yellow)' }}>
+                    {d.ready_replicas}/{d.desired_replicas}
+                  </td>
+                  <td className="kube-cell-dim">{d.available_replicas}</td>
+                  <td className="kube-cell-dim">{d.age}</td>
+                  <td style={{ display: 'flex', gap: 4, alignItems: 'center' }}>
+                    <button className="kube-del-btn" style={{ opacity: 1 }} title="scale down"
+                      disabled={d.desired_replicas === 0}
+                      onClick={() => scale(d, d.desired_replicas - 1)}>−</button>
+                    <span style={{ fontFamily: 'var(--mono)', minWidth: 16, textAlign: 'center' }}>{d.desired_replicas}</span>
+                    <button className="kube-del-btn" style={{ opacity: 1, color: 'var(--green)' }} title="scale up"
+                      disabled={d.desired_replicas >= MAX_REPLICAS}
+                      onClick={() => scale(d, d.desired_replicas + 1)}>+</button>
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+        <div className="kube-section">
+          <div className="kube-section-head">
+            <span className="kube-section-title">usage</span>
+            <span className="kube-section-count">{top?.metrics_available ? `${top.nodes.length} nodes · ${top.pods.length} pods` : '…'}</span>
+          </div>
+          <table className="kube-table">
+            <thead><tr><th>node / pod</th><th>cpu</th><th>memory</th></tr></thead>
+            <tbody>
+              {top?.error ? <tr><td colSpan={3} className="kube-empty-row">{top.error}</td></tr>
+              : !top ? <tr><td colSpan={3} className="kube-empty-row">loading…</td></tr>
+              : !top.metrics_available ? <tr><td colSpan={3} className="kube-empty-row">{top.message}</td></tr>
+              : [
+                ...top.nodes.map(n => (
+                  <tr key={`node/${n.name}`}>
+                    <td className="kube-cell-name">{n.name}</td>
+                    <td>{n.cpu_millicores}m{n.cpu_percent != null && <span className="kube-cell-dim"> · {n.cpu_percent}%</span>}</td>
+                    <td>{fmtMib(n.memory_mib)}{n.memory_percent != null && <span className="kube-cell-dim"> · {n.memory_percent}%</span>}</td>
+                  </tr>
+                )),
+                ...top.pods.map(p => (
+                  <tr key={`pod/${p.namespace}/${p.name}`}>
+                    <td className="kube-cell-dim">{p.name}</td>
+                    <td>{p.cpu_millicores}m</td>
+                    <td>{fmtMib(p.memory_mib)}</td>
+                  </tr>
+                )),
+              ]}
+            </tbody>
+          </table>
+        </div>
+      </div>
+    </main>
+  );
+}
+
 // ── Topbar with nav ───────────────────────────────────────────
 
 function Topbar({ rows, connected, uptimeSecs, view, onView }: {
@@ -630,6 +943,9 @@ function Topbar({ rows, connected, uptimeSecs, view, onView }: {
         <button className={`nav-tab${view === 'box' ? ' active' : ''}`} onClick={() => onView('box')}>
           box
         </button>
+        <button className={`nav-tab${view === 'kube' ? ' active' : ''}`} onClick={() => onView('kube')}>
+          kube
+        </button>
       </nav>
       <div className="topbar-spacer" />
       <div className="conn-status">
@@ -657,8 +973,10 @@ export default function App() {
           <Sidebar rows={rows} selected={selected} onSelect={setSelected} width={sidebarWidth} onWidthChange={setSidebarWidth} />
           <LogPanel lines={lines} selected={selected} onAll={() => setSelected(null)} />
         </>
-      ) : (
+      ) : view === 'box' ? (
         <BoxPanel />
+      ) : (
+        <KubePanel />
       )}
       <Statusbar rows={rows} />
       {import.meta.env.DEV && <Agentation />}
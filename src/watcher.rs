@@ -1,18 +1,270 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc as std_mpsc;
-use std::time::Duration;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use tokio::sync::mpsc;
 
-const DEBOUNCE_MS: u64 = 500;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 
-/// Watches a set of paths and sends the service name on the channel when a change is detected.
+/// What a watcher skips. `watch.ignore` entries are compiled as `.gitignore` rules
+/// anchored at each watched path: `target/**` only ignores the `target` directly
+/// under a watched path, `*.tmp` matches at any depth, `!keep.tmp` re-includes and a
+/// trailing `/` matches directories only. With `use_gitignore` the repository's own
+/// rules apply underneath them: `.git/info/exclude` and every `.gitignore` from the
+/// repository root down to the changed file's directory, deeper files winning. Anything inside a
+/// `.git` directory is always ignored, and so is a3s's own state: `.a3s/` under a
+/// watched path and the directories passed to [`IgnoreRules::exclude`] (the log
+/// directory), whose writes would otherwise restart the service in a loop.
+pub(crate) struct IgnoreRules {
+    roots: Vec<WatchRoot>,
+    excluded: Vec<PathBuf>,
+}
+
+struct WatchRoot {
+    path: PathBuf,
+    /// The `watch.ignore` entries; they override every git ignore file.
+    own: Gitignore,
+    /// `.gitignore` files of directories below `path`, loaded on first use and keyed
+    /// by directory. `None` without `use_gitignore`.
+    nested: Option<Mutex<HashMap<PathBuf, Option<Gitignore>>>>,
+    /// The repository's files at and above `path`, highest precedence first.
+    repo: Vec<Gitignore>,
+}
+
+impl WatchRoot {
+    /// The verdict of the first layer with an opinion on `path`, deepest first.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let verdict = |gi: &Gitignore| {
+            let m = gi.matched_path_or_any_parents(path, is_dir);
+            (!m.is_none()).then(|| m.is_ignore())
+        };
+        if let Some(ignored) = verdict(&self.own) {
+            return ignored;
+        }
+        if let Some(nested) = &self.nested {
+            let mut nested = nested.lock().unwrap();
+            if path.file_name().is_some_and(|n| n == ".gitignore") {
+                // Edited: reload it next time.
+                nested.remove(path.parent().unwrap_or(path));
+            }
+            let dirs = path
+                .ancestors()
+                .skip(1)
+                .take_while(|d| *d != self.path && d.starts_with(&self.path));
+            for dir in dirs {
+                let gi = nested
+                    .entry(dir.to_path_buf())
+                    .or_insert_with(|| load_ignore_file(dir, &dir.join(".gitignore")));
+                if let Some(ignored) = gi.as_ref().and_then(verdict) {
+                    return ignored;
+                }
+            }
+        }
+        self.repo.iter().find_map(verdict).unwrap_or(false)
+    }
+}
+
+impl IgnoreRules {
+    pub(crate) fn new(paths: &[PathBuf], patterns: &[String], use_gitignore: bool) -> Self {
+        let roots = paths
+            .iter()
+            .map(|root| {
+                let mut builder = GitignoreBuilder::new(root);
+                for pattern in patterns {
+                    if let Err(e) = builder.add_line(None, pattern) {
+                        tracing::warn!("ignoring invalid watch.ignore entry '{pattern}': {e}");
+                    }
+                }
+                let own = builder.build().unwrap_or_else(|e| {
+                    tracing::warn!("watch.ignore for {}: {e}", root.display());
+                    Gitignore::empty()
+                });
+                WatchRoot {
+                    path: root.clone(),
+                    own,
+                    nested: use_gitignore.then(Default::default),
+                    repo: if use_gitignore {
+                        repo_ignores(root)
+                    } else {
+                        vec![]
+                    },
+                }
+            })
+            .collect();
+        Self {
+            roots,
+            excluded: vec![],
+        }
+    }
+
+    /// Also ignore everything under `dirs`, wherever they are.
+    pub(crate) fn exclude(mut self, dirs: &[PathBuf]) -> Self {
+        // Event paths are canonical; compare against the same form.
+        self.excluded.extend(dirs.iter().map(|d| {
+            std::fs::canonicalize(d)
+                .or_else(|_| std::path::absolute(d))
+                .unwrap_or_else(|_| d.clone())
+        }));
+        self
+    }
+
+    /// True if `path`, or a directory above it, is ignored relative to the watched
+    /// path that contains it. Paths outside every watched path are never ignored.
+    pub(crate) fn is_ignored(&self, path: &Path) -> bool {
+        if path.components().any(|c| c.as_os_str() == ".git")
+            || self.excluded.iter().any(|d| path.starts_with(d))
+        {
+            return true;
+        }
+        let is_dir = path.is_dir();
+        self.roots
+            .iter()
+            .filter_map(|r| Some((r, path.strip_prefix(&r.path).ok()?)))
+            .any(|(r, rel)| {
+                rel.components().any(|c| c.as_os_str() == ".a3s") || r.is_ignored(path, is_dir)
+            })
+    }
+}
+
+/// The git ignore files that apply to `root`, deepest `.gitignore` first and
+/// `.git/info/exclude` last. Empty when `root` is not inside a repository.
+fn repo_ignores(root: &Path) -> Vec<Gitignore> {
+    let Some(repo) = root.ancestors().find(|d| d.join(".git").exists()) else {
+        return vec![];
+    };
+    let mut layers: Vec<Gitignore> = root
+        .ancestors()
+        .take_while(|d| d.starts_with(repo))
+        .filter_map(|d| load_ignore_file(d, &d.join(".gitignore")))
+        .collect();
+    layers.extend(load_ignore_file(repo, &repo.join(".git/info/exclude")));
+    layers
+}
+
+/// Rules from the ignore file `file`, anchored at `base`; `None` if there is none.
+fn load_ignore_file(base: &Path, file: &Path) -> Option<Gitignore> {
+    if !file.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(base);
+    if let Some(e) = builder.add(file) {
+        tracing::warn!("{}: {e}", file.display());
+    }
+    builder.build().ok()
+}
+
+/// Trailing-edge debounce: fires once, `window` after the last event of a burst.
+/// Every event inside the window pushes the deadline back.
+#[derive(Debug)]
+struct Debouncer {
+    window: Duration,
+    deadline: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            deadline: None,
+        }
+    }
+
+    fn event(&mut self, now: Instant) {
+        self.deadline = Some(now + self.window);
+    }
+
+    /// True exactly once per burst, when the window has passed without new events.
+    fn fire(&mut self, now: Instant) -> bool {
+        match self.deadline {
+            Some(d) if now >= d => {
+                self.deadline = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// How long the event loop may block before the next `fire` check is due.
+    fn wait(&self, now: Instant, max: Duration) -> Duration {
+        self.deadline
+            .map_or(max, |d| d.saturating_duration_since(now).min(max))
+    }
+}
+
+/// Describe a notify error, replacing inotify watch exhaustion (a bare ENOSPC) with
+/// the sysctl that fixes it.
+fn describe_error(e: &notify::Error) -> String {
+    let exhausted = matches!(e.kind, notify::ErrorKind::MaxFilesWatch)
+        || matches!(&e.kind, notify::ErrorKind::Io(io) if io.raw_os_error() == Some(libc::ENOSPC));
+    if exhausted && cfg!(target_os = "linux") {
+        "inotify watch limit reached — raise it with \
+         `sudo sysctl fs.inotify.max_user_watches=524288` \
+         (add it to /etc/sysctl.conf to persist), or narrow the watch paths"
+            .into()
+    } else {
+        e.to_string()
+    }
+}
+
+/// Watches a set of paths and sends the service name on the channel once a burst of
+/// changes has been quiet for `debounce`. Changes under `exclude` never count.
 /// Returns a sender that stops the watcher thread when dropped or when any value is sent.
 pub fn spawn_watcher(
     service: String,
     paths: Vec<PathBuf>,
     ignore: Vec<String>,
+    use_gitignore: bool,
+    exclude: Vec<PathBuf>,
+    debounce: Duration,
+    tx: mpsc::Sender<String>,
+) -> std_mpsc::SyncSender<()> {
+    let watches = paths
+        .iter()
+        .map(|p| (p.clone(), RecursiveMode::Recursive))
+        .collect();
+    let counts = move || -> PathFilter {
+        let ignore = IgnoreRules::new(&paths, &ignore, use_gitignore).exclude(&exclude);
+        Box::new(move |p| !ignore.is_ignored(p))
+    };
+    spawn_event_loop(service, watches, counts, debounce, tx)
+}
+
+/// Like [`spawn_watcher`] for the single file `file`. Its directory is watched
+/// rather than the file itself: editors that save by writing a new file and renaming
+/// it over the old one would otherwise leave the watch on a deleted inode.
+pub fn watch_file(
+    service: String,
+    file: PathBuf,
+    debounce: Duration,
+    tx: mpsc::Sender<String>,
+) -> std_mpsc::SyncSender<()> {
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let name = file.file_name().map(|n| n.to_os_string());
+    let counts = move || -> PathFilter { Box::new(move |p| p.file_name() == name.as_deref()) };
+    spawn_event_loop(
+        service,
+        vec![(dir, RecursiveMode::NonRecursive)],
+        counts,
+        debounce,
+        tx,
+    )
+}
+
+/// Which changed paths count, built on the watcher thread.
+type PathFilter = Box<dyn Fn(&Path) -> bool>;
+
+/// The watcher thread behind [`spawn_watcher`] and [`watch_file`].
+fn spawn_event_loop(
+    service: String,
+    watches: Vec<(PathBuf, RecursiveMode)>,
+    counts: impl FnOnce() -> PathFilter + Send + 'static,
+    debounce: Duration,
     tx: mpsc::Sender<String>,
 ) -> std_mpsc::SyncSender<()> {
     let (stop_tx, stop_rx) = std_mpsc::sync_channel::<()>(1);
@@ -27,39 +279,36 @@ pub fn spawn_watcher(
             }
         };
 
-        for path in &paths {
-            if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
-                tracing::warn!("cannot watch {}: {e}", path.display());
+        for (path, mode) in &watches {
+            if let Err(e) = watcher.watch(path, *mode) {
+                tracing::error!("cannot watch {}: {}", path.display(), describe_error(&e));
             }
         }
 
-        let mut last_trigger = std::time::Instant::now()
-            .checked_sub(Duration::from_millis(DEBOUNCE_MS + 1))
-            .unwrap_or(std::time::Instant::now());
+        let counts = counts();
+        let mut debouncer = Debouncer::new(debounce);
 
         loop {
             // Check stop signal (non-blocking)
             if stop_rx.try_recv().is_ok() {
                 break;
             }
+            if debouncer.fire(Instant::now()) && tx.blocking_send(service.clone()).is_err() {
+                break;
+            }
 
             // Poll for file events with a short timeout so we can check stop regularly
-            match raw_rx.recv_timeout(Duration::from_millis(100)) {
+            let wait = debouncer.wait(Instant::now(), Duration::from_millis(100));
+            match raw_rx.recv_timeout(wait) {
                 Ok(Ok(event)) => {
-                    let relevant = event.paths.iter().any(|p| {
-                        let s = p.to_string_lossy();
-                        !ignore.iter().any(|ig| s.contains(ig.as_str()))
-                    });
-                    if !relevant {
-                        continue;
-                    }
-                    let now = std::time::Instant::now();
-                    if now.duration_since(last_trigger) >= Duration::from_millis(DEBOUNCE_MS) {
-                        last_trigger = now;
-                        let _ = tx.blocking_send(service.clone());
+                    // Reads don't change anything; ignored paths don't count either.
+                    let relevant = !matches!(event.kind, EventKind::Access(_))
+                        && event.paths.iter().any(|p| counts(p));
+                    if relevant {
+                        debouncer.event(Instant::now());
                     }
                 }
-                Ok(Err(e)) => tracing::warn!("watch error for {service}: {e}"),
+                Ok(Err(e)) => tracing::warn!("watch error for {service}: {}", describe_error(&e)),
                 Err(std_mpsc::RecvTimeoutError::Timeout) => {}
                 Err(std_mpsc::RecvTimeoutError::Disconnected) => break,
             }
@@ -74,11 +323,30 @@ mod tests {
     use super::*;
     use std::time::Duration;
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_describe_error_inotify_exhaustion() {
+        let e = notify::Error::new(notify::ErrorKind::MaxFilesWatch);
+        assert!(describe_error(&e).contains("fs.inotify.max_user_watches"));
+        let e = notify::Error::io(std::io::Error::from_raw_os_error(libc::ENOSPC));
+        assert!(describe_error(&e).contains("fs.inotify.max_user_watches"));
+        let e = notify::Error::generic("boom");
+        assert_eq!(describe_error(&e), "boom");
+    }
+
     #[tokio::test]
     async fn test_spawn_watcher_returns_stop_sender() {
         let dir = tempfile::tempdir().unwrap();
         let (tx, _rx) = tokio::sync::mpsc::channel::<String>(1);
-        let stop_tx = spawn_watcher("svc".into(), vec![dir.path().to_path_buf()], vec![], tx);
+        let stop_tx = spawn_watcher(
+            "svc".into(),
+            vec![dir.path().to_path_buf()],
+            vec![],
+            true,
+            vec![],
+            Duration::from_millis(300),
+            tx,
+        );
         // Sending stop should not panic
         let _ = stop_tx.send(());
     }
@@ -87,7 +355,15 @@ mod tests {
     async fn test_stop_sender_terminates_watcher_thread() {
         let dir = tempfile::tempdir().unwrap();
         let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(1);
-        let stop_tx = spawn_watcher("svc".into(), vec![dir.path().to_path_buf()], vec![], tx);
+        let stop_tx = spawn_watcher(
+            "svc".into(),
+            vec![dir.path().to_path_buf()],
+            vec![],
+            true,
+            vec![],
+            Duration::from_millis(300),
+            tx,
+        );
 
         // Stop the watcher thread
         let _ = stop_tx.send(());
@@ -102,7 +378,15 @@ mod tests {
         use std::io::Write;
         let dir = tempfile::tempdir().unwrap();
         let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(4);
-        let stop_tx = spawn_watcher("my-svc".into(), vec![dir.path().to_path_buf()], vec![], tx);
+        let stop_tx = spawn_watcher(
+            "my-svc".into(),
+            vec![dir.path().to_path_buf()],
+            vec![],
+            true,
+            vec![],
+            Duration::from_millis(300),
+            tx,
+        );
 
         // Brief pause so the watcher is set up before we write
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -130,6 +414,9 @@ mod tests {
             "svc".into(),
             vec![dir.path().to_path_buf()],
             vec!["target".into()], // ignore "target" subdirectory
+            true,
+            vec![],
+            Duration::from_millis(300),
             tx,
         );
 
@@ -150,4 +437,311 @@ mod tests {
             "should not have received event for ignored path"
         );
     }
+
+    #[test]
+    fn test_debouncer_coalesces_burst_into_one_fire() {
+        let window = Duration::from_millis(300);
+        let mut d = Debouncer::new(window);
+        let t0 = Instant::now();
+        let mut fires = 0;
+        // 5 events 50ms apart, each resetting the window.
+        for i in 0..5 {
+            let now = t0 + Duration::from_millis(50 * i);
+            d.event(now);
+            fires += usize::from(d.fire(now));
+        }
+        let last = t0 + Duration::from_millis(200);
+        assert!(
+            !d.fire(last + Duration::from_millis(299)),
+            "window reset by last event"
+        );
+        fires += usize::from(d.fire(last + window));
+        fires += usize::from(d.fire(last + window * 3));
+        assert_eq!(fires, 1);
+    }
+
+    #[test]
+    fn test_debouncer_wait_is_bounded_by_deadline() {
+        let mut d = Debouncer::new(Duration::from_millis(300));
+        let t0 = Instant::now();
+        let max = Duration::from_millis(100);
+        assert_eq!(d.wait(t0, max), max);
+        d.event(t0);
+        assert_eq!(
+            d.wait(t0 + Duration::from_millis(250), max),
+            Duration::from_millis(50)
+        );
+        assert_eq!(d.wait(t0 + Duration::from_secs(1), max), Duration::ZERO);
+    }
+
+    fn rules(patterns: &[&str]) -> IgnoreRules {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        IgnoreRules::new(&[PathBuf::from("/p")], &patterns, false)
+    }
+
+    #[test]
+    fn test_ignore_plain_names_and_globs() {
+        let ignore = rules(&["node_modules", "*.log", "**/dist/**"]);
+        assert!(ignore.is_ignored(Path::new("/p/node_modules/x.js")));
+        assert!(ignore.is_ignored(Path::new("/p/server.log")));
+        assert!(ignore.is_ignored(Path::new("/p/web/dist/app.js")));
+        assert!(!ignore.is_ignored(Path::new("/p/src/main.rs")));
+    }
+
+    #[test]
+    fn test_ignore_target_glob_is_anchored_at_watch_path() {
+        let ignore = rules(&["target/**"]);
+        assert!(ignore.is_ignored(Path::new("/p/target/debug/app")));
+        assert!(ignore.is_ignored(Path::new("/p/target/x.o")));
+        assert!(!ignore.is_ignored(Path::new("/p/src/target/x.rs")));
+        assert!(!ignore.is_ignored(Path::new("/p/src/main.rs")));
+    }
+
+    #[test]
+    fn test_ignore_negation_reincludes() {
+        let ignore = rules(&["*.tmp", "!keep.tmp"]);
+        assert!(ignore.is_ignored(Path::new("/p/scratch.tmp")));
+        assert!(ignore.is_ignored(Path::new("/p/sub/scratch.tmp")));
+        assert!(!ignore.is_ignored(Path::new("/p/keep.tmp")));
+        assert!(!ignore.is_ignored(Path::new("/p/sub/keep.tmp")));
+    }
+
+    #[test]
+    fn test_ignore_outside_watch_paths_is_not_ignored() {
+        assert!(!rules(&["*.log"]).is_ignored(Path::new("/elsewhere/server.log")));
+    }
+
+    #[test]
+    fn test_a3s_state_and_log_dir_are_always_ignored() {
+        let ignore = rules(&[]).exclude(&[PathBuf::from("/p/var/logs")]);
+        assert!(ignore.is_ignored(Path::new("/p/.a3s/daemon.log")));
+        assert!(ignore.is_ignored(Path::new("/p/.a3s/logs/web.log")));
+        assert!(ignore.is_ignored(Path::new("/p/sub/.a3s/health-stats.json")));
+        assert!(ignore.is_ignored(Path::new("/p/var/logs/web.log")));
+        assert!(!ignore.is_ignored(Path::new("/p/var/app.rs")));
+        // Only below the watched path: a project under some `.a3s/` still counts.
+        let nested = IgnoreRules::new(&[PathBuf::from("/home/u/.a3s/p")], &[], false);
+        assert!(!nested.is_ignored(Path::new("/home/u/.a3s/p/src/main.rs")));
+    }
+
+    /// A repository with a `.gitignore` at its root and one in `web/`, which is the
+    /// watched path.
+    fn repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join(".git/info")).unwrap();
+        std::fs::write(root.join(".git/info/exclude"), "secret.txt\n").unwrap();
+        std::fs::write(root.join(".gitignore"), "dist/\n*.log\n!keep.log\n").unwrap();
+        std::fs::create_dir_all(root.join("web/dist")).unwrap();
+        std::fs::create_dir_all(root.join("web/cache")).unwrap();
+        std::fs::write(root.join("web/.gitignore"), "cache\n!debug.log\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_gitignore_files_apply_to_watch_paths() {
+        let dir = repo();
+        let web = dir.path().join("web");
+        let ignore = IgnoreRules::new(std::slice::from_ref(&web), &[], true);
+        assert!(ignore.is_ignored(&web.join("dist/app.js")));
+        assert!(ignore.is_ignored(&web.join("server.log")));
+        assert!(!ignore.is_ignored(&web.join("keep.log")));
+        assert!(ignore.is_ignored(&web.join("cache/blob")));
+        assert!(ignore.is_ignored(&web.join("secret.txt")));
+        assert!(!ignore.is_ignored(&web.join("src/main.ts")));
+        // The deeper .gitignore wins over the root one.
+        assert!(!ignore.is_ignored(&web.join("debug.log")));
+    }
+
+    #[test]
+    fn test_gitignore_files_below_the_watch_path_apply() {
+        let dir = repo();
+        let web = dir.path().join("web");
+        std::fs::create_dir_all(web.join("src/generated")).unwrap();
+        std::fs::write(web.join("src/.gitignore"), "generated/\n!trace.log\n").unwrap();
+        let ignore = IgnoreRules::new(std::slice::from_ref(&web), &[], true);
+        assert!(ignore.is_ignored(&web.join("src/generated/api.ts")));
+        assert!(!ignore.is_ignored(&web.join("generated/api.ts")));
+        // Deeper still wins: re-included under src/, ignored by the root file elsewhere.
+        assert!(!ignore.is_ignored(&web.join("src/trace.log")));
+        assert!(ignore.is_ignored(&web.join("trace.log")));
+
+        // Edits to a nested .gitignore are picked up once the change is seen.
+        std::fs::write(web.join("src/.gitignore"), "*.ts\n").unwrap();
+        ignore.is_ignored(&web.join("src/.gitignore"));
+        assert!(ignore.is_ignored(&web.join("src/app.ts")));
+        assert!(ignore.is_ignored(&web.join("src/trace.log")));
+    }
+
+    #[test]
+    fn test_watch_ignore_overrides_gitignore() {
+        let dir = repo();
+        let web = dir.path().join("web");
+        let ignore = IgnoreRules::new(
+            std::slice::from_ref(&web),
+            &["!server.log".into(), "*.ts".into()],
+            true,
+        );
+        assert!(!ignore.is_ignored(&web.join("server.log")));
+        assert!(ignore.is_ignored(&web.join("src/main.ts")));
+    }
+
+    #[test]
+    fn test_gitignore_can_be_turned_off() {
+        let dir = repo();
+        let web = dir.path().join("web");
+        let ignore = IgnoreRules::new(std::slice::from_ref(&web), &[], false);
+        assert!(!ignore.is_ignored(&web.join("server.log")));
+        assert!(!ignore.is_ignored(&web.join("dist/app.js")));
+    }
+
+    #[test]
+    fn test_dot_git_is_always_ignored() {
+        assert!(rules(&[]).is_ignored(Path::new("/p/.git/index")));
+        assert!(rules(&["!.git"]).is_ignored(Path::new("/p/sub/.git/HEAD")));
+        assert!(!rules(&[]).is_ignored(Path::new("/p/.gitignore")));
+    }
+
+    #[tokio::test]
+    async fn test_git_and_gitignored_changes_do_not_restart() {
+        let dir = repo();
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(4);
+        let stop_tx = spawn_watcher(
+            "svc".into(),
+            vec![dir.path().to_path_buf()],
+            vec![],
+            true,
+            vec![],
+            Duration::from_millis(100),
+            tx,
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        std::fs::write(dir.path().join(".git/index"), "x").unwrap();
+        std::fs::write(dir.path().join("web/dist/app.js"), "x").unwrap();
+        std::fs::write(dir.path().join("build.log"), "x").unwrap();
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(rx.try_recv().is_err(), "ignored paths must not restart");
+
+        std::fs::write(dir.path().join("main.rs"), "x").unwrap();
+        let got = tokio::time::timeout(Duration::from_secs(2), rx.recv()).await;
+        assert_eq!(got.ok().flatten().as_deref(), Some("svc"));
+        let _ = stop_tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_ignored_change_does_not_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(4);
+        let stop_tx = spawn_watcher(
+            "svc".into(),
+            vec![dir.path().to_path_buf()],
+            vec!["target/**".into(), "*.tmp".into(), "!keep.tmp".into()],
+            true,
+            vec![],
+            Duration::from_millis(100),
+            tx,
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        std::fs::write(dir.path().join("target/debug/app"), "bin").unwrap();
+        std::fs::write(dir.path().join("scratch.tmp"), "x").unwrap();
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(rx.try_recv().is_err(), "ignored paths must not restart");
+
+        std::fs::write(dir.path().join("keep.tmp"), "x").unwrap();
+        let got = tokio::time::timeout(Duration::from_secs(2), rx.recv()).await;
+        assert_eq!(got.ok().flatten().as_deref(), Some("svc"));
+        let _ = stop_tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_burst_of_writes_triggers_single_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(8);
+        let stop_tx = spawn_watcher(
+            "svc".into(),
+            vec![dir.path().to_path_buf()],
+            vec![],
+            true,
+            vec![],
+            Duration::from_millis(300),
+            tx,
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        for i in 0..5 {
+            std::fs::write(dir.path().join(format!("f{i}.txt")), "x").unwrap();
+            tokio::time::sleep(Duration::from_millis(40)).await;
+        }
+
+        let first = tokio::time::timeout(Duration::from_secs(3), rx.recv()).await;
+        assert!(matches!(first, Ok(Some(ref s)) if s == "svc"), "{first:?}");
+        let second = tokio::time::timeout(Duration::from_millis(800), rx.recv()).await;
+        let _ = stop_tx.send(());
+        assert!(second.is_err(), "burst should restart once, got {second:?}");
+    }
+
+    #[tokio::test]
+    async fn test_log_writes_do_not_trigger_restarts() {
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("logs");
+        std::fs::create_dir_all(dir.path().join(".a3s/logs")).unwrap();
+        std::fs::create_dir_all(&logs).unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(4);
+        let stop_tx = spawn_watcher(
+            "svc".into(),
+            vec![dir.path().to_path_buf()],
+            vec![],
+            true,
+            vec![logs.clone()],
+            Duration::from_millis(100),
+            tx,
+        );
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        for file in [
+            dir.path().join(".a3s/daemon.log"),
+            dir.path().join(".a3s/logs/svc.log"),
+            logs.join("svc.log"),
+        ] {
+            let mut f = std::fs::File::create(file).unwrap();
+            writeln!(f, "a line").unwrap();
+        }
+
+        let result = tokio::time::timeout(Duration::from_millis(700), rx.recv()).await;
+        let _ = stop_tx.send(());
+        assert!(result.is_err(), "log writes restarted the service");
+    }
+
+    #[tokio::test]
+    async fn test_watched_file_survives_atomic_saves() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = dir.path().join("web.env");
+        std::fs::write(&env, "A=1\n").unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(4);
+        let stop_tx = watch_file("web".into(), env.clone(), Duration::from_millis(100), tx);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Other files in the directory don't count.
+        std::fs::write(dir.path().join("notes.txt"), "x").unwrap();
+        let quiet = tokio::time::timeout(Duration::from_millis(500), rx.recv()).await;
+        assert!(quiet.is_err(), "a sibling file triggered a restart");
+
+        // Saved the way many editors do: a new file renamed over the old one, twice.
+        for value in ["A=2\n", "A=3\n"] {
+            let tmp = dir.path().join("web.env.swp");
+            std::fs::write(&tmp, value).unwrap();
+            std::fs::rename(&tmp, &env).unwrap();
+            let result = tokio::time::timeout(Duration::from_secs(3), rx.recv()).await;
+            assert!(
+                matches!(result, Ok(Some(ref s)) if s == "web"),
+                "no event after saving {value:?}: {result:?}"
+            );
+        }
+        let _ = stop_tx.send(());
+    }
 }
@@ -0,0 +1,151 @@
+//! A temp project and a foreground `a3s up` running against it with
+//! `A3S_FAKE_EXEC` active, so tests never reach the real kubectl or a3s-box.
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+
+pub struct TestProject {
+    dir: tempfile::TempDir,
+    pub file: PathBuf,
+}
+
+impl TestProject {
+    /// A project whose A3sfile.hcl is `a3sfile`.
+    pub fn new(a3sfile: &str) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("A3sfile.hcl");
+        std::fs::write(&file, a3sfile).unwrap();
+        std::fs::create_dir(dir.path().join("fakes")).unwrap();
+        Self { dir, file }
+    }
+
+    /// The `A3S_FAKE_EXEC` directory.
+    pub fn fakes(&self) -> PathBuf {
+        self.dir.path().join("fakes")
+    }
+
+    /// Script a response; see `src/exec/fake.rs` for the format. Files match in name
+    /// order, and a running daemon sees new ones on its next call.
+    pub fn fake(&self, name: &str, response: serde_json::Value) {
+        std::fs::write(
+            self.fakes().join(format!("{name}.json")),
+            response.to_string(),
+        )
+        .unwrap();
+    }
+
+    /// Every faked invocation so far, as program followed by arguments.
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        std::fs::read_to_string(self.fakes().join("calls.jsonl"))
+            .unwrap_or_default()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    /// Run `a3s up` in the foreground with the web UI on a free port, and wait until
+    /// the UI answers.
+    pub fn up(&self) -> Daemon {
+        let port = free_port();
+        let child = Command::new(env!("CARGO_BIN_EXE_a3s"))
+            .arg("--file")
+            .arg(&self.file)
+            .args(["up", "--ui-port", &port.to_string()])
+            .env("A3S_FAKE_EXEC", self.fakes())
+            .env("HOME", self.dir.path())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let daemon = Daemon {
+            child,
+            port,
+            home: self.dir.path().to_path_buf(),
+        };
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while daemon.try_get("/api/status").is_none() {
+            assert!(Instant::now() < deadline, "web UI never came up");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        daemon
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// A foreground `a3s up`, stopped with SIGTERM when dropped.
+pub struct Daemon {
+    child: Child,
+    port: u16,
+    /// The daemon's `HOME`, where it saves the UI token.
+    home: PathBuf,
+}
+
+impl Daemon {
+    /// GET `path` from the web UI: status code and body.
+    pub fn get(&self, path: &str) -> (u16, String) {
+        self.try_get(path).expect("web UI request failed")
+    }
+
+    /// POST the JSON `body` to `path` with the daemon's UI token: status code and body.
+    pub fn post(&self, path: &str, body: &str) -> (u16, String) {
+        let token = std::fs::read_to_string(self.home.join(".a3s/token")).unwrap();
+        self.request(&format!(
+            "POST {path} HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\
+                 Authorization: Bearer {}\r\nContent-Type: application/json\r\n\
+                 Content-Length: {}\r\n\r\n{body}",
+            token.trim(),
+            body.len()
+        ))
+        .expect("web UI request failed")
+    }
+
+    fn try_get(&self, path: &str) -> Option<(u16, String)> {
+        self.request(&format!(
+            "GET {path} HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+        ))
+    }
+
+    fn request(&self, raw: &str) -> Option<(u16, String)> {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).ok()?;
+        stream.write_all(raw.as_bytes()).ok()?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).ok()?;
+        let (head, body) = response.split_once("\r\n\r\n")?;
+        let status = head.split_whitespace().nth(1)?.parse().ok()?;
+        Some((status, body.to_string()))
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = kill(Pid::from_raw(self.child.id() as i32), Signal::SIGTERM);
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
@@ -0,0 +1,257 @@
+//! Box and Kubernetes API routes against scripted kubectl/a3s-box responses.
+//! Run with `cargo test --features test-util`.
+#![cfg(unix)]
+
+mod common;
+
+use common::TestProject;
+use serde_json::json;
+
+const A3SFILE: &str = "dev {\n  proxy_port = 0\n  k8s_namespace = \"test\"\n}\n\nservice \"sleeper\" {\n  cmd = \"sleep 300\"\n}\n";
+
+#[test]
+fn box_ps_lists_scripted_containers() {
+    let project = TestProject::new(A3SFILE);
+    project.fake(
+        "ps",
+        json!({
+            "program": "a3s-box",
+            "args": ["ps", "**"],
+            "stdout": "{\"ID\":\"c1\",\"Names\":\"a3s-db\",\"Image\":\"postgres:16\",\"Status\":\"running\"}\n",
+        }),
+    );
+    let daemon = project.up();
+
+    let (status, body) = daemon.get("/api/box/containers?all=true");
+    assert_eq!(status, 200, "{body}");
+    let containers: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(containers[0]["Names"], "a3s-db");
+    assert_eq!(containers[0]["Image"], "postgres:16");
+    assert!(project.calls().contains(&vec![
+        "a3s-box".into(),
+        "ps".into(),
+        "--format".into(),
+        "json".into(),
+        "-a".into()
+    ]));
+}
+
+#[test]
+fn box_failure_surfaces_the_hint() {
+    let project = TestProject::new(A3SFILE);
+    project.fake(
+        "ps",
+        json!({
+            "program": "a3s-box",
+            "args": ["ps", "**"],
+            "stderr": "Error: failed to open /dev/kvm: Permission denied",
+            "exit": 1,
+            "delay_ms": 200,
+        }),
+    );
+    let daemon = project.up();
+
+    let (status, body) = daemon.get("/api/box/containers");
+    assert_eq!(status, 500);
+    assert!(body.contains("box-no-virtualization"), "{body}");
+}
+
+#[test]
+fn box_run_api_always_detaches() {
+    let project = TestProject::new(A3SFILE);
+    project.fake(
+        "ps",
+        json!({
+            "program": "a3s-box",
+            "args": ["ps", "**"],
+            "stdout": "{\"ID\":\"c9\",\"Names\":\"quirky-nginx\",\"Image\":\"nginx\",\"Status\":\"running\"}\n",
+        }),
+    );
+    project.fake(
+        "run",
+        json!({
+            "program": "a3s-box",
+            "args": ["run", "-d", "nginx"],
+            "stdout": "c9f00d\n",
+        }),
+    );
+    let daemon = project.up();
+
+    // Neither `detach` nor `name`: still a detached run, answered right away.
+    let (status, body) = daemon.post("/api/box/containers", r#"{"image": "nginx"}"#);
+    assert_eq!(status, 200, "{body}");
+    let container: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(container["Names"], "quirky-nginx");
+
+    let (status, body) = daemon.post(
+        "/api/box/containers",
+        r#"{"image": "nginx", "detach": false}"#,
+    );
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(
+        project
+            .calls()
+            .iter()
+            .filter(|c| c[1] == "run")
+            .collect::<Vec<_>>(),
+        vec![&["a3s-box", "run", "-d", "nginx"]; 2]
+    );
+}
+
+#[test]
+fn box_exec_is_scripted() {
+    let project = TestProject::new(A3SFILE);
+    project.fake(
+        "exec",
+        json!({
+            "program": "a3s-box",
+            "args": ["exec", "a3s-db", "--", "psql", "-c", "select 1"],
+            "stdout": "1\n",
+        }),
+    );
+    let daemon = project.up();
+
+    let (status, body) = daemon.post(
+        "/api/box/containers/a3s-db/exec",
+        r#"{"command": ["psql", "-c", "select 1"]}"#,
+    );
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&body).unwrap()["output"],
+        "1\n"
+    );
+}
+
+#[test]
+fn box_stats_stream_is_scripted() {
+    let project = TestProject::new(A3SFILE);
+    let sample = |cpu: &str| {
+        json!({"ID": "c1", "Name": "a3s-db", "CPUPerc": cpu, "MemUsage": "1MiB / 2MiB"}).to_string()
+    };
+    project.fake(
+        "stats",
+        json!({
+            "program": "a3s-box",
+            "args": ["stats", "--format", "json", "a3s-db"],
+            "stdout": format!("{}\n{}\n", sample("1.5%"), sample("2.5%")),
+        }),
+    );
+    let daemon = project.up();
+
+    // The scripted tool exits after two samples, which ends the event stream.
+    let (status, body) = daemon.get("/api/box/stats/a3s-db?stream=true");
+    assert_eq!(status, 200, "{body}");
+    let samples: Vec<serde_json::Value> = body
+        .lines()
+        .filter_map(|l| l.strip_prefix("data: "))
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(samples.len(), 2, "{body}");
+    assert_eq!(samples[1]["cpu_percent"], 2.5);
+}
+
+#[test]
+fn box_build_streams_scripted_output() {
+    let project = TestProject::new(A3SFILE);
+    std::fs::write(project.path().join("Dockerfile"), "FROM scratch\n").unwrap();
+    project.fake(
+        "build",
+        json!({
+            "program": "a3s-box",
+            "args": ["build", "**"],
+            "stdout": "step 1/1\n",
+            "stderr": "cache miss\n",
+        }),
+    );
+    let daemon = project.up();
+
+    let (status, body) = daemon.post(
+        "/api/box/images/build",
+        r#"{"context": ".", "tag": "app:dev"}"#,
+    );
+    assert_eq!(status, 200, "{body}");
+    assert!(body.contains("step 1/1"), "{body}");
+    assert!(body.contains("cache miss"), "{body}");
+    assert!(body.contains("event: done"), "{body}");
+}
+
+#[test]
+fn box_push_and_login_are_scripted() {
+    let project = TestProject::new(A3SFILE);
+    project.fake(
+        "push",
+        json!({
+            "program": "a3s-box",
+            "args": ["push", "ghcr.io/a3s/app:dev"],
+            "stderr": "denied: requested access\n",
+            "exit": 1,
+        }),
+    );
+    project.fake(
+        "login",
+        json!({
+            "program": "a3s-box",
+            "args": ["login", "--username", "dev", "--password-stdin", "ghcr.io"],
+            "stdout": "Login Succeeded\n",
+        }),
+    );
+    let daemon = project.up();
+
+    let (status, body) = daemon.post(
+        "/api/box/images/push",
+        r#"{"reference": "ghcr.io/a3s/app:dev"}"#,
+    );
+    assert_eq!(status, 200, "{body}");
+    assert!(body.contains("event: error"), "{body}");
+    assert!(body.contains("denied"), "{body}");
+
+    let (status, body) = daemon.post(
+        "/api/box/login",
+        r#"{"server": "ghcr.io", "username": "dev", "password": "s3cret"}"#,
+    );
+    assert_eq!(status, 200, "{body}");
+    // The password never shows up in the recorded command line.
+    assert!(!project
+        .calls()
+        .concat()
+        .iter()
+        .any(|a| a.contains("s3cret")));
+}
+
+#[test]
+fn kube_resources_from_scripted_kubectl() {
+    let project = TestProject::new(A3SFILE);
+    project.fake(
+        "deployments",
+        json!({
+            "program": "kubectl",
+            "args": ["--namespace", "test", "get", "deployments", "--output=json"],
+            "stdout": json!({"items": [{
+                "metadata": {"name": "api", "namespace": "test"},
+                "spec": {"replicas": 2},
+                "status": {"readyReplicas": 1},
+            }]}).to_string(),
+        }),
+    );
+    project.fake(
+        "services",
+        json!({
+            "program": "kubectl",
+            "args": ["--namespace", "test", "get", "services", "--output=json"],
+            "stdout": "{\"items\": []}",
+        }),
+    );
+    let daemon = project.up();
+
+    let (status, body) = daemon.get("/api/kube/resources");
+    assert_eq!(status, 200, "{body}");
+    let resources: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(resources["deployments"][0]["name"], "api");
+    assert_eq!(resources["deployments"][0]["desired_replicas"], 2);
+    assert_eq!(resources["services"], json!([]));
+
+    // Anything not scripted fails instead of reaching a real cluster.
+    let (status, body) = daemon.get("/api/kube/resources?selector=app%3Dweb");
+    assert_eq!(status, 500);
+    assert!(body.contains("A3S_FAKE_EXEC has no response"), "{body}");
+}
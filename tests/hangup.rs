@@ -0,0 +1,79 @@
+//! Closing the terminal of a foreground `a3s up` must not orphan its services.
+#![cfg(unix)]
+
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+
+/// PIDs of the direct children of `parent`.
+fn children(parent: u32) -> Vec<i32> {
+    let out = Command::new("pgrep")
+        .args(["-P", &parent.to_string()])
+        .output()
+        .expect("pgrep");
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(|l| l.trim().parse().ok())
+        .collect()
+}
+
+fn wait_until(timeout: Duration, mut done: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if done() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn sighup_stops_services_of_foreground_up() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("A3sfile.hcl");
+    std::fs::write(
+        &file,
+        "dev {\n  proxy_port = 0\n}\n\nservice \"sleeper\" {\n  cmd = \"sleep 300\"\n}\n",
+    )
+    .unwrap();
+
+    let mut up = Command::new(env!("CARGO_BIN_EXE_a3s"))
+        .arg("--file")
+        .arg(&file)
+        .args(["up", "--no-ui"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let mut services = vec![];
+    let started = wait_until(Duration::from_secs(20), || {
+        services = children(up.id());
+        !services.is_empty()
+    });
+    if !started {
+        let _ = up.kill();
+        panic!("service never started");
+    }
+
+    kill(Pid::from_raw(up.id() as i32), Signal::SIGHUP).unwrap();
+    let exited = wait_until(Duration::from_secs(20), || up.try_wait().unwrap().is_some());
+    if !exited {
+        let _ = up.kill();
+        panic!("a3s up did not exit on SIGHUP");
+    }
+
+    for pid in services {
+        let gone = wait_until(Duration::from_secs(5), || {
+            kill(Pid::from_raw(pid), None).is_err()
+        });
+        if !gone {
+            let _ = kill(Pid::from_raw(pid), Signal::SIGKILL);
+            panic!("service process {pid} outlived the supervisor");
+        }
+    }
+}